@@ -16,7 +16,10 @@ export type BiomeId =
   | 'tropical-forest'
   | 'savanna'
   | 'desert'
-  | 'alpine';
+  | 'alpine'
+  | 'beach'
+  | 'rocky-shore'
+  | 'steppe';
 
 export interface GeneratorResult {
   width: number;
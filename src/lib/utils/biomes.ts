@@ -10,7 +10,10 @@ export const BIOMES: BiomeId[] = [
   'tropical-forest',
   'savanna',
   'desert',
-  'alpine'
+  'alpine',
+  'beach',
+  'rocky-shore',
+  'steppe'
 ];
 
 export function decodeBiome(index: number): BiomeId {
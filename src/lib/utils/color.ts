@@ -28,7 +28,10 @@ const BIOME_COLORS: Record<BiomeId, [number, number, number]> = {
   'tropical-forest': [66, 140, 62],
   savanna: [198, 172, 94],
   desert: [218, 192, 130],
-  alpine: [210, 210, 210]
+  alpine: [210, 210, 210],
+  beach: [224, 208, 156],
+  'rocky-shore': [122, 118, 110],
+  steppe: [176, 176, 112]
 };
 
 export function hypsometricColor(height: number): [number, number, number] {
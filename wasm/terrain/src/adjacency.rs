@@ -0,0 +1,237 @@
+use std::collections::BTreeMap;
+
+#[cfg(feature = "wasm")]
+use js_sys::{Object, Uint32Array};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::biome::BIOMES;
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::json::Json;
+use crate::MapResult;
+
+const BIOME_COUNT: usize = BIOMES.len();
+
+/// Connected same-biome patches, 4-connected so diagonal touches between
+/// patches count as neither a join nor a border.
+pub(crate) struct Regions {
+    /// Region id per cell.
+    pub labels: Vec<u32>,
+    /// Biome code of each region.
+    pub biomes: Vec<u8>,
+    pub sizes: Vec<u32>,
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Shared-border counts between biomes: `{ size, matrix, pairs }` where
+    /// `matrix` is a symmetric `size × size` `Uint32Array` indexed by biome
+    /// code, counting orthogonally adjacent cell pairs, and `pairs` lists the
+    /// nonzero `{ a, b, count }` with `a < b`. With `options.regions`, also
+    /// returns `region_labels` per cell, `regions` as `{ id, biome, cells }`,
+    /// and `region_pairs` between touching regions.
+    pub fn biome_adjacency(&self, options: JsValue) -> Object {
+        let matrix = biome_adjacency(self);
+        let result = Object::new();
+        js::set(&result, "size", &JsValue::from(BIOME_COUNT as u32));
+        js::set(
+            &result,
+            "matrix",
+            &Uint32Array::from(matrix.as_slice()).into(),
+        );
+        let pairs = (0..BIOME_COUNT)
+            .flat_map(|a| ((a + 1)..BIOME_COUNT).map(move |b| (a, b)))
+            .filter(|&(a, b)| matrix[a * BIOME_COUNT + b] > 0)
+            .map(|(a, b)| (a as u32, b as u32, matrix[a * BIOME_COUNT + b]))
+            .collect::<Vec<_>>();
+        js::set(&result, "pairs", &pair_records(&pairs).to_js());
+
+        if js::get_bool(&options, "regions", false) {
+            let regions = label_regions(self);
+            let region_pairs: Vec<(u32, u32, u32)> = region_adjacency(self, &regions)
+                .into_iter()
+                .map(|((a, b), count)| (a, b, count))
+                .collect();
+            let records = (0..regions.biomes.len())
+                .map(|id| {
+                    Json::object()
+                        .with("id", id)
+                        .with("biome", regions.biomes[id] as u32)
+                        .with("cells", regions.sizes[id])
+                })
+                .collect::<Vec<_>>();
+            js::set(
+                &result,
+                "region_labels",
+                &Uint32Array::from(regions.labels.as_slice()).into(),
+            );
+            js::set(&result, "regions", &Json::from(records).to_js());
+            js::set(
+                &result,
+                "region_pairs",
+                &pair_records(&region_pairs).to_js(),
+            );
+        }
+        result
+    }
+}
+
+fn pair_records(pairs: &[(u32, u32, u32)]) -> Json {
+    Json::from(
+        pairs
+            .iter()
+            .map(|&(a, b, count)| {
+                Json::object()
+                    .with("a", a)
+                    .with("b", b)
+                    .with("count", count)
+            })
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// Symmetric biome border matrix in one pass over right and down neighbors.
+/// Codes outside the biome table are ignored.
+pub(crate) fn biome_adjacency(map: &MapResult) -> Vec<u32> {
+    let width = map.width as usize;
+    let mut matrix = vec![0u32; BIOME_COUNT * BIOME_COUNT];
+    let mut count = |a: u8, b: u8| {
+        let (a, b) = (a as usize, b as usize);
+        if a != b && a < BIOME_COUNT && b < BIOME_COUNT {
+            matrix[a * BIOME_COUNT + b] += 1;
+            matrix[b * BIOME_COUNT + a] += 1;
+        }
+    };
+    for (index, &biome) in map.biome.iter().enumerate() {
+        if index % width + 1 < width {
+            count(biome, map.biome[index + 1]);
+        }
+        if index + width < map.biome.len() {
+            count(biome, map.biome[index + width]);
+        }
+    }
+    matrix
+}
+
+pub(crate) fn label_regions(map: &MapResult) -> Regions {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let mut labels = vec![u32::MAX; map.biome.len()];
+    let mut biomes = Vec::new();
+    let mut sizes = Vec::new();
+    let mut stack = Vec::new();
+    for start in 0..map.biome.len() {
+        if labels[start] != u32::MAX {
+            continue;
+        }
+        let id = biomes.len() as u32;
+        let biome = map.biome[start];
+        let mut size = 0u32;
+        labels[start] = id;
+        stack.push(start);
+        while let Some(index) = stack.pop() {
+            size += 1;
+            let (x, y) = (index % width, index / width);
+            let candidates = [
+                (x > 0).then(|| index - 1),
+                (x + 1 < width).then(|| index + 1),
+                (y > 0).then(|| index - width),
+                (y + 1 < height).then(|| index + width),
+            ];
+            for next in candidates.into_iter().flatten() {
+                if labels[next] == u32::MAX && map.biome[next] == biome {
+                    labels[next] = id;
+                    stack.push(next);
+                }
+            }
+        }
+        biomes.push(biome);
+        sizes.push(size);
+    }
+    Regions {
+        labels,
+        biomes,
+        sizes,
+    }
+}
+
+/// Shared-border counts between touching regions, keyed `(a, b)` with `a < b`.
+pub(crate) fn region_adjacency(map: &MapResult, regions: &Regions) -> BTreeMap<(u32, u32), u32> {
+    let width = map.width as usize;
+    let labels = &regions.labels;
+    let mut pairs = BTreeMap::new();
+    let mut count = |a: u32, b: u32| {
+        if a != b {
+            *pairs.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+        }
+    };
+    for index in 0..labels.len() {
+        if index % width + 1 < width {
+            count(labels[index], labels[index + 1]);
+        }
+        if index + width < labels.len() {
+            count(labels[index], labels[index + width]);
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{biome_adjacency, label_regions, region_adjacency, BIOME_COUNT};
+    use crate::biome::Biome;
+    use crate::generate_map;
+
+    /// Paints a 6×4 map: ocean | desert | tundra columns, with a second
+    /// desert patch cut off by a tundra row.
+    fn crafted() -> crate::MapResult {
+        let mut map = generate_map(6, 4, 1, 0.42, 1.0, 40.0, 0, 1.0);
+        let (o, d, t) = (
+            Biome::Ocean.code(),
+            Biome::Desert.code(),
+            Biome::Tundra.code(),
+        );
+        map.biome = vec![
+            o, o, d, d, t, t, //
+            o, o, d, d, t, t, //
+            o, o, t, t, t, t, //
+            o, o, d, d, t, t, //
+        ];
+        map
+    }
+
+    #[test]
+    fn biome_matrix_counts_shared_edges() {
+        let map = crafted();
+        let matrix = biome_adjacency(&map);
+        let at = |a: Biome, b: Biome| matrix[a.code() as usize * BIOME_COUNT + b.code() as usize];
+        assert_eq!(at(Biome::Ocean, Biome::Desert), 3);
+        assert_eq!(at(Biome::Ocean, Biome::Tundra), 1);
+        assert_eq!(at(Biome::Desert, Biome::Tundra), 7);
+        assert_eq!(at(Biome::Tundra, Biome::Desert), 7);
+        assert_eq!(at(Biome::Desert, Biome::Desert), 0);
+        let total: u32 = matrix.iter().sum();
+        assert_eq!(total, 2 * (3 + 1 + 7));
+    }
+
+    #[test]
+    fn regions_split_disconnected_patches() {
+        let map = crafted();
+        let regions = label_regions(&map);
+        assert_eq!(regions.biomes.len(), 4);
+        assert_eq!(regions.sizes.iter().sum::<u32>(), 24);
+        let upper_desert = regions.labels[2];
+        let lower_desert = regions.labels[3 * 6 + 2];
+        assert_ne!(upper_desert, lower_desert);
+        let pairs = region_adjacency(&map, &regions);
+        let ocean = regions.labels[0];
+        let tundra = regions.labels[4];
+        let key = |a: u32, b: u32| (a.min(b), a.max(b));
+        assert_eq!(pairs[&key(ocean, upper_desert)], 2);
+        assert_eq!(pairs[&key(ocean, lower_desert)], 1);
+        assert_eq!(pairs[&key(ocean, tundra)], 1);
+        assert!(!pairs.contains_key(&key(upper_desert, lower_desert)));
+    }
+}
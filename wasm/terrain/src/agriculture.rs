@@ -0,0 +1,289 @@
+use std::f32::consts::TAU;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::economy::{territories, MAX_FARM_SLOPE, NO_TERRITORY};
+use crate::footprints::NO_FOOTPRINT;
+use crate::hand::{height_above_drainage, height_above_drainage_at};
+use crate::json::Json;
+use crate::weather::{DAYS_PER_YEAR, MIDSUMMER};
+use crate::{cell_slope, slope_map, MapResult};
+
+/// Periods the year is split into, one per month.
+pub(crate) const PERIODS: u32 = 12;
+/// Temperature a period must exceed for crops to grow.
+const GROWING_TEMPERATURE: f32 = 0.3;
+/// Temperature rise at midsummer and drop at midwinter around the annual
+/// mean in the temperature layer; matches seasonal ice.
+const SEASONAL_SWING: f32 = 0.15;
+/// Coldest-period temperature below which the ground freezes.
+const FROST_TEMPERATURE: f32 = 0.15;
+/// Moisture crops like best; fertility falls off linearly either side.
+const IDEAL_MOISTURE: f32 = 0.45;
+/// Farmland moisture below which harvests depend on the rains.
+const ARID_MOISTURE: f32 = 0.25;
+/// Farmland standing less than this above its drainage floods with it.
+const FLOOD_HAND: f32 = 0.005;
+/// Share of low-lying farmland that makes a settlement flood-prone.
+const FLOOD_SHARE: f32 = 0.25;
+
+/// Temperature of `period` (0 is the first month) for a cell whose annual
+/// mean is `mean`, following a sine over the year that peaks at midsummer.
+pub(crate) fn period_temperature(mean: f32, period: u32) -> f32 {
+    let day = (period as f32 + 0.5) * DAYS_PER_YEAR / PERIODS as f32;
+    mean + ((day - MIDSUMMER) / DAYS_PER_YEAR * TAU).cos() * SEASONAL_SWING
+}
+
+/// Longest run of consecutive periods warm enough to grow, wrapping over
+/// the new year, as `(sowing, harvest, length)` with the first and last
+/// period of the run; ties go to the run starting earlier.
+fn growing_season(mean: f32) -> Option<(u32, u32, u32)> {
+    let warm: Vec<bool> = (0..PERIODS)
+        .map(|period| period_temperature(mean, period) > GROWING_TEMPERATURE)
+        .collect();
+    if warm.iter().all(|&warm| warm) {
+        return Some((0, PERIODS - 1, PERIODS));
+    }
+    let mut best: Option<(u32, u32, u32)> = None;
+    for start in 0..PERIODS {
+        if !warm[start as usize] || warm[((start + PERIODS - 1) % PERIODS) as usize] {
+            continue;
+        }
+        let length = (0..PERIODS)
+            .take_while(|&step| warm[((start + step) % PERIODS) as usize])
+            .count() as u32;
+        if best.is_none_or(|(_, _, longest)| length > longest) {
+            best = Some((start, (start + length - 1) % PERIODS, length));
+        }
+    }
+    best
+}
+
+/// How well a cell's soil and lie suit crops, from 0 to 1: best at
+/// `IDEAL_MOISTURE` on flat ground, nothing on slopes too steep to plough.
+fn fertility(moisture: f32, slope: f32) -> f32 {
+    let wetness = 1.0 - ((moisture - IDEAL_MOISTURE).abs() / IDEAL_MOISTURE).min(1.0);
+    let lie = (1.0 - slope / MAX_FARM_SLOPE).max(0.0);
+    wetness * lie
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) struct Agriculture {
+    /// Growing periods in the year, 0 to `PERIODS`.
+    pub season: u32,
+    /// First growing period, or `None` when nothing grows.
+    pub sowing: Option<u32>,
+    /// Last growing period.
+    pub harvest: Option<u32>,
+    pub fertility: f32,
+    /// Fertility scaled by the share of the year crops can grow.
+    pub yield_modifier: f32,
+    pub arid: bool,
+    pub flood_prone: bool,
+    pub frost_prone: bool,
+}
+
+impl Agriculture {
+    /// The calendar for farmland averaging `temperature` and `moisture`,
+    /// with `flooded` the share of it lying low enough to flood.
+    fn new(temperature: f32, moisture: f32, fertility: f32, flooded: f32) -> Self {
+        let season = growing_season(temperature);
+        let length = season.map_or(0, |(_, _, length)| length);
+        let coldest = (0..PERIODS)
+            .map(|period| period_temperature(temperature, period))
+            .fold(f32::INFINITY, f32::min);
+        Self {
+            season: length,
+            sowing: season.map(|(sowing, _, _)| sowing),
+            harvest: season.map(|(_, harvest, _)| harvest),
+            fertility,
+            yield_modifier: fertility * length as f32 / PERIODS as f32,
+            arid: moisture < ARID_MOISTURE,
+            flood_prone: flooded > FLOOD_SHARE,
+            frost_prone: coldest < FROST_TEMPERATURE,
+        }
+    }
+
+    fn risks(&self) -> Vec<Json> {
+        [
+            ("arid", self.arid),
+            ("flood-prone", self.flood_prone),
+            ("frost-prone", self.frost_prone),
+        ]
+        .into_iter()
+        .filter(|&(_, flagged)| flagged)
+        .map(|(key, _)| Json::from(key))
+        .collect()
+    }
+
+    pub(crate) fn to_json(self) -> Json {
+        let period = |period: Option<u32>| period.map_or(Json::Null, Json::from);
+        Json::object()
+            .with("growing_season", self.season)
+            .with("sowing", period(self.sowing))
+            .with("harvest", period(self.harvest))
+            .with("fertility", self.fertility)
+            .with("yield", self.yield_modifier)
+            .with("risks", self.risks())
+    }
+}
+
+impl MapResult {
+    /// The calendar of a single cell. Water and river cells have no
+    /// fertility.
+    pub(crate) fn agriculture_at_cell(&self, cell: usize) -> Agriculture {
+        let width = self.width as usize;
+        let wet = self.is_water_body(cell) || self.is_river(cell);
+        let slope = cell_slope(
+            &self.heightmap,
+            width,
+            self.height as usize,
+            cell % width,
+            cell / width,
+        );
+        let moisture = self.moisture[cell];
+        let flooded = height_above_drainage_at(self, cell) < FLOOD_HAND;
+        Agriculture::new(
+            self.temperature[cell],
+            moisture,
+            if wet { 0.0 } else { fertility(moisture, slope) },
+            flooded as u32 as f32,
+        )
+    }
+}
+
+/// Running sums over one settlement's farmland.
+#[derive(Clone, Copy, Default)]
+struct Tally {
+    cells: u32,
+    temperature: f32,
+    moisture: f32,
+    fertility: f32,
+    flooded: u32,
+}
+
+/// Calendar per settlement, in `settlements` order, from the averages over
+/// its farmland: dry, ploughable cells of its territory outside every
+/// built-up footprint. A settlement without farmland gets the climate of
+/// its own cell and no fertility.
+pub(crate) fn settlement_agriculture(map: &MapResult) -> Vec<Agriculture> {
+    let width = map.width as usize;
+    let labels = territories(map);
+    let built = &map.settlement_footprints().owner;
+    let slopes = slope_map(&map.heightmap, width, map.height as usize);
+    let hand = height_above_drainage(map);
+    let mut tallies = vec![Tally::default(); map.settlements.len()];
+    for (cell, &label) in labels.iter().enumerate() {
+        if label == NO_TERRITORY
+            || map.is_water_body(cell)
+            || map.is_river(cell)
+            || slopes[cell] > MAX_FARM_SLOPE
+            || built[cell] != NO_FOOTPRINT
+        {
+            continue;
+        }
+        let tally = &mut tallies[label as usize];
+        tally.cells += 1;
+        tally.temperature += map.temperature[cell];
+        tally.moisture += map.moisture[cell];
+        tally.fertility += fertility(map.moisture[cell], slopes[cell]);
+        tally.flooded += (hand[cell] < FLOOD_HAND) as u32;
+    }
+    map.settlements
+        .iter()
+        .zip(tallies)
+        .map(|(settlement, tally)| {
+            if tally.cells == 0 {
+                let (x, y) = map.nearest_cell(settlement.x, settlement.y);
+                return Agriculture {
+                    fertility: 0.0,
+                    yield_modifier: 0.0,
+                    ..map.agriculture_at_cell(y * width + x)
+                };
+            }
+            let cells = tally.cells as f32;
+            Agriculture::new(
+                tally.temperature / cells,
+                tally.moisture / cells,
+                tally.fertility / cells,
+                tally.flooded as f32 / cells,
+            )
+        })
+        .collect()
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Agricultural calendar of the nearest cell as `{ growing_season,
+    /// sowing, harvest, fertility, yield, risks }`. The year is split into 12
+    /// periods (months, 0 first), each warmed or cooled from the cell's
+    /// annual mean temperature by a sine peaking at midsummer with a 0.15
+    /// swing. `growing_season` counts the longest run of periods above 0.3,
+    /// wrapping over the new year, from period `sowing` to `harvest` (both
+    /// `null` when nothing grows). `fertility` (0 to 1) peaks at moisture
+    /// 0.45 on flat ground and is 0 on water and on slopes too steep to
+    /// farm; `yield` is fertility times the share of the year in season.
+    /// `risks` lists `"arid"` below moisture 0.25, `"flood-prone"` within
+    /// 0.005 of the height of the water the cell drains to, and
+    /// `"frost-prone"` when the coldest period drops below 0.15.
+    pub fn agriculture_at(&self, world_x: f32, world_y: f32) -> JsValue {
+        let (x, y) = self.nearest_cell(world_x, world_y);
+        self.agriculture_at_cell(y * self.width as usize + x)
+            .to_json()
+            .to_js()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{growing_season, settlement_agriculture, Agriculture, PERIODS};
+    use crate::generate_map;
+
+    #[test]
+    fn seasons_lengthen_with_warmth_and_wrap_the_year() {
+        assert_eq!(growing_season(0.1), None);
+        assert_eq!(growing_season(0.5), Some((0, PERIODS - 1, PERIODS)));
+        let mut last = 0;
+        for step in 0..=40 {
+            let season = growing_season(0.1 + step as f32 * 0.01);
+            let length = season.map_or(0, |(_, _, length)| length);
+            assert!(length >= last);
+            last = length;
+            if let Some((sowing, harvest, length)) = season {
+                assert_eq!((sowing + length - 1) % PERIODS, harvest);
+            }
+        }
+        // Centered on midsummer, late June.
+        let (sowing, harvest, length) = growing_season(0.3).unwrap();
+        assert_eq!(length, 6);
+        assert_eq!((sowing, harvest), (3, 8));
+        // A cool mean leaves midwinter frozen.
+        assert!(Agriculture::new(0.25, 0.45, 1.0, 0.0).frost_prone);
+        assert!(!Agriculture::new(0.35, 0.45, 1.0, 0.0).frost_prone);
+    }
+
+    #[test]
+    fn settlement_calendars_scale_yield_by_season() {
+        let map = generate_map(96, 96, 3, 0.42, 1.0, 40.0, 2, 1.0);
+        let calendars = settlement_agriculture(&map);
+        assert_eq!(calendars.len(), map.settlements.len());
+        assert!(calendars
+            .iter()
+            .any(|calendar| calendar.yield_modifier > 0.0));
+        for calendar in &calendars {
+            assert!((0.0..=1.0).contains(&calendar.fertility));
+            let share = calendar.season as f32 / PERIODS as f32;
+            assert!((calendar.yield_modifier - calendar.fertility * share).abs() < 1e-6);
+            assert_eq!(calendar.sowing.is_some(), calendar.season > 0);
+        }
+
+        // Open water grows nothing, whatever its climate.
+        let water = (0..map.heightmap.len())
+            .find(|&cell| map.is_water_body(cell))
+            .unwrap();
+        let calendar = map.agriculture_at_cell(water);
+        assert_eq!((calendar.fertility, calendar.yield_modifier), (0.0, 0.0));
+    }
+}
@@ -0,0 +1,314 @@
+#[cfg(feature = "wasm")]
+use js_sys::{Array, Float32Array};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::biome::Biome;
+use crate::distance::chamfer_distance;
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::roughness::{local_mean, roughness};
+use crate::{MapResult, REGION_SIZE};
+
+const MAX_CHANNELS: usize = 16;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) enum Source {
+    /// Nearness to the ocean.
+    Surf,
+    /// Nearness to forest biomes.
+    Birds,
+    /// Height above the sea and local ruggedness.
+    Wind,
+    /// Nearness to rivers carrying at least `river_flow`.
+    River,
+}
+
+impl Source {
+    const ALL: [Source; 4] = [Source::Surf, Source::Birds, Source::Wind, Source::River];
+
+    fn key(self) -> &'static str {
+        match self {
+            Source::Surf => "surf",
+            Source::Birds => "birds",
+            Source::Wind => "wind",
+            Source::River => "river",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|source| source.key() == key)
+    }
+}
+
+/// One ambience loop. `radius` is in world units: how far surf, birds, and
+/// river sound carries from their source cells, and the window over which
+/// wind measures ruggedness.
+#[derive(Clone)]
+pub(crate) struct AmbienceChannel {
+    pub source: Source,
+    pub radius: f32,
+    pub gain: f32,
+}
+
+#[derive(Clone)]
+pub(crate) struct AmbienceOptions {
+    pub channels: Vec<AmbienceChannel>,
+    /// Half-width, in cells, of the box filter softening zone edges.
+    pub blur_radius: u32,
+    /// Rivers quieter than this fraction of the largest river flow are silent.
+    pub river_flow: f32,
+}
+
+impl Default for AmbienceOptions {
+    fn default() -> Self {
+        let channel = |source, radius| AmbienceChannel {
+            source,
+            radius,
+            gain: 1.0,
+        };
+        Self {
+            channels: vec![
+                channel(Source::Surf, 96.0),
+                channel(Source::Birds, 48.0),
+                channel(Source::Wind, 64.0),
+                channel(Source::River, 40.0),
+            ],
+            blur_radius: 3,
+            river_flow: 0.1,
+        }
+    }
+}
+
+impl AmbienceOptions {
+    #[cfg(feature = "wasm")]
+    fn from_js(options: &JsValue) -> Result<Self, JsValue> {
+        let defaults = Self::default();
+        let channels = match js::get_array(options, "channels") {
+            Some(channels) => {
+                if channels.length() == 0 || channels.length() as usize > MAX_CHANNELS {
+                    return Err(JsValue::from_str(&format!(
+                        "ambience needs between 1 and {MAX_CHANNELS} channels"
+                    )));
+                }
+                channels
+                    .iter()
+                    .map(|channel| {
+                        let key = js::get_string(&channel, "source").unwrap_or_default();
+                        let source = Source::from_key(&key).ok_or_else(|| {
+                            JsValue::from_str(&format!(
+                                "ambience source must be \"surf\", \"birds\", \"wind\", \
+                                 or \"river\", got {key:?}"
+                            ))
+                        })?;
+                        let default_radius = defaults
+                            .channels
+                            .iter()
+                            .find(|channel| channel.source == source)
+                            .map_or(0.0, |channel| channel.radius);
+                        Ok(AmbienceChannel {
+                            source,
+                            radius: js::get_f32(&channel, "radius", default_radius).max(0.0),
+                            gain: js::get_f32(&channel, "gain", 1.0).max(0.0),
+                        })
+                    })
+                    .collect::<Result<Vec<_>, JsValue>>()?
+            }
+            None => defaults.channels,
+        };
+        Ok(Self {
+            channels,
+            blur_radius: js::get_u32(options, "blur_radius", defaults.blur_radius),
+            river_flow: js::get_f32(options, "river_flow", defaults.river_flow).clamp(0.0, 1.0),
+        })
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Configures the ambience zones. Options: `channels`, an array of
+    /// `{ source, radius, gain }` with `source` one of `"surf"` (near ocean),
+    /// `"birds"` (near forest), `"wind"` (high, rugged ground), or `"river"`
+    /// (near rivers), `radius` in world units, and `gain` scaling the raw
+    /// weight; `blur_radius` (cells, default 3) softening zone edges; and
+    /// `river_flow` (fraction of the largest river's flow, default 0.1) below
+    /// which rivers are silent. Defaults to surf, birds, wind, and river.
+    pub fn set_ambience(&mut self, options: JsValue) -> Result<(), JsValue> {
+        self.ambience = AmbienceOptions::from_js(&options)?;
+        self.cache.ambience.take();
+        Ok(())
+    }
+
+    /// Source names of the ambience channels, in channel order.
+    pub fn ambience_channels(&self) -> Array {
+        self.ambience
+            .channels
+            .iter()
+            .map(|channel| JsValue::from(channel.source.key()))
+            .collect()
+    }
+
+    /// Per-cell ambience weights, interleaved by channel (`width * height *
+    /// channels` values). Each cell's weights sum to 1, or are all 0 where no
+    /// channel is audible.
+    pub fn ambience(&self) -> Float32Array {
+        let zones = self.ambience_zones();
+        let interleaved: Vec<f32> = (0..self.heightmap.len())
+            .flat_map(|index| zones.iter().map(move |channel| channel[index]))
+            .collect();
+        Float32Array::from(interleaved.as_slice())
+    }
+
+    /// Bilinearly interpolated ambience weights at a world position, one per
+    /// channel.
+    pub fn ambience_at(&self, world_x: f32, world_y: f32) -> Float32Array {
+        Float32Array::from(self.ambience_weights(world_x, world_y).as_slice())
+    }
+}
+
+impl MapResult {
+    /// Cached until the terrain or the ambience options change.
+    pub(crate) fn ambience_zones(&self) -> &[Vec<f32>] {
+        self.cache
+            .ambience
+            .get_or_init(|| ambience_zones(self, &self.ambience))
+    }
+
+    pub(crate) fn ambience_weights(&self, world_x: f32, world_y: f32) -> Vec<f32> {
+        self.ambience_zones()
+            .iter()
+            .map(|channel| self.bilinear(channel, world_x, world_y))
+            .collect()
+    }
+}
+
+pub(crate) fn ambience_zones(map: &MapResult, options: &AmbienceOptions) -> Vec<Vec<f32>> {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let mut zones: Vec<Vec<f32>> = options
+        .channels
+        .iter()
+        .map(|channel| {
+            let raw = raw_weights(map, channel, options);
+            let scaled: Vec<f32> = raw
+                .into_iter()
+                .map(|weight| weight * channel.gain)
+                .collect();
+            local_mean(&scaled, width, height, options.blur_radius as usize)
+        })
+        .collect();
+
+    for index in 0..map.heightmap.len() {
+        let total: f32 = zones.iter().map(|channel| channel[index]).sum();
+        for channel in &mut zones {
+            channel[index] = if total > f32::EPSILON {
+                channel[index] / total
+            } else {
+                0.0
+            };
+        }
+    }
+    zones
+}
+
+fn raw_weights(map: &MapResult, channel: &AmbienceChannel, options: &AmbienceOptions) -> Vec<f32> {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let fade = |distance: &[f32]| -> Vec<f32> {
+        distance
+            .iter()
+            .map(|&d| {
+                if channel.radius <= 0.0 {
+                    if d == 0.0 {
+                        1.0
+                    } else {
+                        0.0
+                    }
+                } else {
+                    (1.0 - d / channel.radius).max(0.0)
+                }
+            })
+            .collect()
+    };
+    match channel.source {
+        Source::Surf => fade(map.coast_distance()),
+        Source::Birds => {
+            let forest: Vec<bool> = map
+                .biome
+                .iter()
+                .map(|&code| {
+                    matches!(
+                        Biome::from_code(code),
+                        Some(Biome::TemperateForest | Biome::BorealForest | Biome::TropicalForest)
+                    )
+                })
+                .collect();
+            fade(&chamfer_distance(&forest, width, height))
+        }
+        Source::River => {
+            let loudest = (0..map.flow.len())
+                .filter(|&index| map.is_river(index))
+                .map(|index| map.flow[index])
+                .fold(0.0f32, f32::max);
+            let threshold = loudest * options.river_flow;
+            let rivers: Vec<bool> = (0..map.flow.len())
+                .map(|index| map.is_river(index) && map.flow[index] >= threshold)
+                .collect();
+            fade(&chamfer_distance(&rivers, width, height))
+        }
+        Source::Wind => {
+            let cell = REGION_SIZE / width.max(height) as f32;
+            let window = (channel.radius / cell).round() as usize;
+            let rugged = roughness(&map.heightmap, width, height, window.max(1));
+            let sea_level = map.sea_level;
+            map.heightmap
+                .iter()
+                .zip(rugged)
+                .map(|(&elevation, rugged)| {
+                    let exposure = ((elevation - sea_level) / (1.0 - sea_level).max(f32::EPSILON))
+                        .clamp(0.0, 1.0);
+                    (exposure + rugged) * 0.5
+                })
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ambience_zones, AmbienceOptions, Source};
+    use crate::generate_map;
+
+    #[test]
+    fn ambience_is_normalized_and_surf_follows_the_coast() {
+        let map = generate_map(96, 96, 8, 0.42, 1.0, 40.0, 2, 1.0);
+        let options = AmbienceOptions::default();
+        let zones = ambience_zones(&map, &options);
+        assert_eq!(zones.len(), 4);
+        for index in 0..map.heightmap.len() {
+            let total: f32 = zones.iter().map(|channel| channel[index]).sum();
+            assert!(total == 0.0 || (total - 1.0).abs() < 1e-4, "{total}");
+        }
+
+        let surf = options
+            .channels
+            .iter()
+            .position(|channel| channel.source == Source::Surf)
+            .unwrap();
+        let coast = map.coast_distance();
+        let mean_surf = |near: bool| {
+            let cells: Vec<usize> = (0..coast.len())
+                .filter(|&index| coast[index] > 0.0 && (coast[index] < 48.0) == near)
+                .collect();
+            cells.iter().map(|&index| zones[surf][index]).sum::<f32>() / cells.len() as f32
+        };
+        assert!(mean_surf(true) > mean_surf(false));
+
+        let (x, y) = map.cell_to_world(96 * 40 + 50);
+        let at = map.ambience_weights(x, y);
+        for (channel, weight) in zones.iter().zip(at) {
+            assert!((channel[96 * 40 + 50] - weight).abs() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,137 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::biome::BIOMES;
+use crate::MapResult;
+
+/// One character per biome code, then the river and settlement overlays.
+const DEFAULT_CHARSET: &str = "~o-##.#,:^_%;=*";
+const BIOME_SLOTS: usize = BIOMES.len();
+const RIVER_SLOT: usize = BIOME_SLOTS;
+const SETTLEMENT_SLOT: usize = BIOME_SLOTS + 1;
+const CHARSET_LENGTH: usize = BIOME_SLOTS + 2;
+// ASCII, so the byte length is the glyph count.
+const _: () = assert!(DEFAULT_CHARSET.is_ascii() && DEFAULT_CHARSET.len() == CHARSET_LENGTH);
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Renders a `cols` x `rows` text preview using the majority biome per
+    /// block, with rivers and settlements drawn on top. `charset` holds one
+    /// character per biome code, then river, then settlement.
+    pub fn render_ascii(
+        &self,
+        cols: u32,
+        rows: u32,
+        charset: Option<String>,
+    ) -> Result<String, JsValue> {
+        render_ascii(
+            self,
+            cols,
+            rows,
+            charset.as_deref().unwrap_or(DEFAULT_CHARSET),
+        )
+        .map_err(|message| JsValue::from_str(&message))
+    }
+}
+
+pub(crate) fn render_ascii(
+    map: &MapResult,
+    cols: u32,
+    rows: u32,
+    charset: &str,
+) -> Result<String, String> {
+    let glyphs: Vec<char> = charset.chars().collect();
+    if glyphs.len() != CHARSET_LENGTH {
+        return Err(format!(
+            "charset must contain {CHARSET_LENGTH} characters, got {}",
+            glyphs.len()
+        ));
+    }
+    if glyphs.iter().any(|glyph| glyph.is_control()) {
+        return Err("charset must not contain control characters".to_string());
+    }
+    if cols == 0 || rows == 0 {
+        return Err("cols and rows must be positive".to_string());
+    }
+
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let cols = cols as usize;
+    let rows = rows as usize;
+    let block_span = |index: usize, count: usize, extent: usize| {
+        let start = index * extent / count;
+        let end = ((index + 1) * extent / count).max(start + 1).min(extent);
+        (start.min(extent - 1), end)
+    };
+
+    let mut settlement_blocks = vec![false; cols * rows];
+    for settlement in &map.settlements {
+        let (x, y) = map.nearest_cell(settlement.x, settlement.y);
+        let col = (x * cols / width).min(cols - 1);
+        let row = (y * rows / height).min(rows - 1);
+        settlement_blocks[row * cols + col] = true;
+    }
+
+    let mut output = String::with_capacity((cols + 1) * rows);
+    let mut counts = [0u32; 256];
+    for row in 0..rows {
+        let (y0, y1) = block_span(row, rows, height);
+        for col in 0..cols {
+            let (x0, x1) = block_span(col, cols, width);
+            counts.fill(0);
+            let mut river_cells = 0usize;
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let index = y * width + x;
+                    counts[map.biome[index] as usize] += 1;
+                    if map.is_river(index) {
+                        river_cells += 1;
+                    }
+                }
+            }
+
+            // A river crossing the block covers roughly one cell per block side.
+            let river_threshold = (x1 - x0).min(y1 - y0).max(1);
+            let glyph = if settlement_blocks[row * cols + col] {
+                glyphs[SETTLEMENT_SLOT]
+            } else if river_cells >= river_threshold {
+                glyphs[RIVER_SLOT]
+            } else {
+                let majority = counts
+                    .iter()
+                    .enumerate()
+                    .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(&a.0)))
+                    .map(|(code, _)| code)
+                    .unwrap_or(0);
+                if majority < BIOME_SLOTS {
+                    glyphs[majority]
+                } else {
+                    '?'
+                }
+            };
+            output.push(glyph);
+        }
+        if row + 1 < rows {
+            output.push('\n');
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render_ascii, DEFAULT_CHARSET};
+    use crate::generate_map;
+
+    #[test]
+    fn default_charset_renders() {
+        let map = generate_map(64, 64, 3, 0.42, 1.0, 40.0, 0, 1.0);
+        let text = render_ascii(&map, 32, 16, DEFAULT_CHARSET).expect("default charset is valid");
+        assert_eq!(text.lines().count(), 16);
+        assert!(text.lines().all(|line| line.chars().count() == 32));
+        assert!(text
+            .chars()
+            .all(|glyph| glyph == '\n' || DEFAULT_CHARSET.contains(glyph)));
+    }
+}
@@ -0,0 +1,369 @@
+#[cfg(feature = "wasm")]
+use js_sys::{Array, Object, Uint8Array};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::biome::BIOMES;
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::json::Json;
+use crate::png::encode_rgba;
+use crate::render::hillshade;
+use crate::MapResult;
+
+const CHANNELS: [&str; 4] = ["r", "g", "b", "a"];
+const MAX_IMAGES: usize = 8;
+
+/// A layer that can be baked into one 8-bit channel.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum AtlasSource {
+    Height,
+    Moisture,
+    Temperature,
+    /// Biome code, stored as is.
+    Biome,
+    /// `render_rgba`'s relief shading.
+    Hillshade,
+    /// Flow accumulation, on a log scale.
+    Flow,
+    Water,
+    /// Unused channel, always 255 so alpha stays opaque.
+    Empty,
+}
+
+#[cfg(feature = "wasm")]
+const SOURCES: [AtlasSource; 8] = [
+    AtlasSource::Height,
+    AtlasSource::Moisture,
+    AtlasSource::Temperature,
+    AtlasSource::Biome,
+    AtlasSource::Hillshade,
+    AtlasSource::Flow,
+    AtlasSource::Water,
+    AtlasSource::Empty,
+];
+
+impl AtlasSource {
+    pub(crate) fn key(self) -> &'static str {
+        match self {
+            AtlasSource::Height => "height",
+            AtlasSource::Moisture => "moisture",
+            AtlasSource::Temperature => "temperature",
+            AtlasSource::Biome => "biome",
+            AtlasSource::Hillshade => "hillshade",
+            AtlasSource::Flow => "flow",
+            AtlasSource::Water => "water",
+            AtlasSource::Empty => "none",
+        }
+    }
+
+    #[cfg(feature = "wasm")]
+    fn from_key(key: &str) -> Option<Self> {
+        SOURCES.into_iter().find(|source| source.key() == key)
+    }
+}
+
+pub(crate) struct AtlasOptions {
+    /// Source of each RGBA channel, one entry per image.
+    pub images: Vec<[AtlasSource; 4]>,
+}
+
+impl Default for AtlasOptions {
+    fn default() -> Self {
+        Self {
+            images: vec![
+                [
+                    AtlasSource::Height,
+                    AtlasSource::Moisture,
+                    AtlasSource::Temperature,
+                    AtlasSource::Biome,
+                ],
+                [
+                    AtlasSource::Hillshade,
+                    AtlasSource::Flow,
+                    AtlasSource::Water,
+                    AtlasSource::Empty,
+                ],
+            ],
+        }
+    }
+}
+
+impl AtlasOptions {
+    #[cfg(feature = "wasm")]
+    fn from_js(options: &JsValue) -> Result<Self, JsValue> {
+        let Some(images) = js::get_array(options, "images") else {
+            return Ok(Self::default());
+        };
+        if images.length() == 0 || images.length() as usize > MAX_IMAGES {
+            return Err(JsValue::from_str(&format!(
+                "atlas images need between 1 and {MAX_IMAGES} entries"
+            )));
+        }
+        let mut parsed = Vec::with_capacity(images.length() as usize);
+        for image in images.iter() {
+            if !Array::is_array(&image) || Array::from(&image).length() > 4 {
+                return Err(JsValue::from_str(
+                    "each atlas image is an array of up to 4 layer names",
+                ));
+            }
+            let mut channels = [AtlasSource::Empty; 4];
+            for (slot, name) in channels.iter_mut().zip(Array::from(&image).iter()) {
+                if name.is_null() || name.is_undefined() {
+                    continue;
+                }
+                *slot = name
+                    .as_string()
+                    .as_deref()
+                    .and_then(AtlasSource::from_key)
+                    .ok_or_else(|| JsValue::from_str(&format!("unknown atlas layer {name:?}")))?;
+            }
+            parsed.push(channels);
+        }
+        Ok(Self { images: parsed })
+    }
+}
+
+/// How a channel's bytes map back to layer values.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) enum Encoding {
+    /// `value = byte * scale + offset`.
+    Linear { scale: f32, offset: f32 },
+    /// `value = exp(byte * scale + offset) - 1`.
+    Log { scale: f32, offset: f32 },
+    /// The byte is the value, e.g. a biome code.
+    Index,
+    /// Always 255.
+    Constant,
+}
+
+impl Encoding {
+    #[cfg(test)]
+    fn decode(self, byte: u8) -> f32 {
+        match self {
+            Encoding::Linear { scale, offset } => byte as f32 * scale + offset,
+            Encoding::Log { scale, offset } => (byte as f32 * scale + offset).exp_m1(),
+            Encoding::Index | Encoding::Constant => byte as f32,
+        }
+    }
+
+    fn record(self) -> Json {
+        match self {
+            Encoding::Linear { scale, offset } => Json::object()
+                .with("encoding", "linear")
+                .with("scale", scale)
+                .with("offset", offset),
+            Encoding::Log { scale, offset } => Json::object()
+                .with("encoding", "log")
+                .with("scale", scale)
+                .with("offset", offset),
+            Encoding::Index => Json::object().with("encoding", "index"),
+            Encoding::Constant => Json::object()
+                .with("encoding", "constant")
+                .with("value", 255u32),
+        }
+    }
+}
+
+/// Quantizes `values` over their own range into bytes.
+fn quantize(values: impl Iterator<Item = f32> + Clone) -> (Vec<u8>, f32, f32) {
+    let (low, high) = values
+        .clone()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(low, high), value| {
+            (low.min(value), high.max(value))
+        });
+    if !low.is_finite() || high <= low {
+        let offset = if low.is_finite() { low } else { 0.0 };
+        return (values.map(|_| 0).collect(), 0.0, offset);
+    }
+    let scale = (high - low) / 255.0;
+    let bytes = values
+        .map(|value| ((value - low) / scale).round().clamp(0.0, 255.0) as u8)
+        .collect();
+    (bytes, scale, low)
+}
+
+/// One layer as channel bytes plus how to decode them.
+fn bake(map: &MapResult, source: AtlasSource) -> (Vec<u8>, Encoding) {
+    let linear = |values: &[f32]| {
+        let (bytes, scale, offset) = quantize(values.iter().copied());
+        (bytes, Encoding::Linear { scale, offset })
+    };
+    match source {
+        AtlasSource::Height => linear(&map.heightmap),
+        AtlasSource::Moisture => linear(&map.moisture),
+        AtlasSource::Temperature => linear(&map.temperature),
+        AtlasSource::Water => linear(&map.water),
+        AtlasSource::Biome => (map.biome.clone(), Encoding::Index),
+        AtlasSource::Hillshade => {
+            let width = map.width as usize;
+            let height = map.height as usize;
+            let shade: Vec<f32> = (0..map.heightmap.len())
+                .map(|cell| hillshade(&map.heightmap, width, height, cell % width, cell / width))
+                .collect();
+            linear(&shade)
+        }
+        AtlasSource::Flow => {
+            let (bytes, scale, offset) =
+                quantize(map.flow.iter().map(|&flow| flow.max(0.0).ln_1p()));
+            (bytes, Encoding::Log { scale, offset })
+        }
+        AtlasSource::Empty => (vec![255; map.heightmap.len()], Encoding::Constant),
+    }
+}
+
+pub(crate) struct Atlas {
+    /// One PNG per requested image.
+    pub images: Vec<Vec<u8>>,
+    /// Source and decoding of each channel, per image.
+    pub channels: Vec<[(AtlasSource, Encoding); 4]>,
+}
+
+impl Atlas {
+    /// JSON description of the packing, enough to decode every channel.
+    pub(crate) fn manifest(&self, map: &MapResult) -> Json {
+        let images = self
+            .channels
+            .iter()
+            .map(|channels| {
+                let channels = channels
+                    .iter()
+                    .zip(CHANNELS)
+                    .map(|(&(source, encoding), channel)| {
+                        encoding
+                            .record()
+                            .with("channel", channel)
+                            .with("source", source.key())
+                    })
+                    .collect::<Vec<_>>();
+                Json::object().with("channels", channels)
+            })
+            .collect::<Vec<_>>();
+        Json::object()
+            .with("version", 1u32)
+            .with("width", map.width)
+            .with("height", map.height)
+            .with("sea_level", map.sea_level)
+            .with("images", images)
+            .with(
+                "biomes",
+                BIOMES
+                    .iter()
+                    .map(|biome| Json::from(biome.key()))
+                    .collect::<Vec<_>>(),
+            )
+    }
+}
+
+pub(crate) fn export_atlas(map: &MapResult, options: &AtlasOptions) -> Atlas {
+    let cells = map.heightmap.len();
+    let mut atlas = Atlas {
+        images: Vec::with_capacity(options.images.len()),
+        channels: Vec::with_capacity(options.images.len()),
+    };
+    for sources in &options.images {
+        let mut rgba = vec![0; cells * 4];
+        let mut channels = sources.map(|source| (source, Encoding::Constant));
+        for (index, (source, encoding)) in channels.iter_mut().enumerate() {
+            let (bytes, baked) = bake(map, *source);
+            *encoding = baked;
+            for (cell, byte) in bytes.into_iter().enumerate() {
+                rgba[cell * 4 + index] = byte;
+            }
+        }
+        atlas.images.push(encode_rgba(map.width, map.height, &rgba));
+        atlas.channels.push(channels);
+    }
+    atlas
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Bakes layers into RGBA PNGs for shaders, as `{ images, manifest }`:
+    /// `images` holds one PNG `Uint8Array` per entry of `options.images`,
+    /// and `manifest` is a JSON string describing how to decode them.
+    /// Each entry names the layers for its r, g, b, and a channels from
+    /// `"height"`, `"moisture"`, `"temperature"`, `"biome"`, `"hillshade"`,
+    /// `"flow"`, `"water"`, or `"none"` (or null); missing channels are
+    /// `"none"`, which stays 255. The default packs height, moisture,
+    /// temperature, and biome into the first image and hillshade, flow, and
+    /// water into the second.
+    ///
+    /// The manifest lists each image's channels with their `source` and
+    /// `encoding`: `"linear"` channels decode as `byte * scale + offset`
+    /// over the layer's own range, `"log"` ones (flow) as
+    /// `exp(byte * scale + offset) - 1`, `"index"` bytes are biome codes
+    /// naming the manifest's `biomes` keys, and `"constant"` ones are
+    /// always 255. Read pixels from the PNG bytes rather than through a
+    /// canvas when alpha carries data, since canvases premultiply it.
+    pub fn export_atlas(&self, options: JsValue) -> Result<JsValue, JsValue> {
+        let atlas = export_atlas(self, &AtlasOptions::from_js(&options)?);
+        let images = Array::new();
+        for png in &atlas.images {
+            images.push(&Uint8Array::from(png.as_slice()).into());
+        }
+        let result = Object::new();
+        js::set(&result, "images", &images.into());
+        js::set(
+            &result,
+            "manifest",
+            &JsValue::from_str(&atlas.manifest(self).to_string()),
+        );
+        Ok(result.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{export_atlas, AtlasOptions, AtlasSource, Encoding};
+    use crate::generate_map;
+    use crate::png::decode_stored;
+
+    #[test]
+    fn atlas_channels_decode_back_to_their_layers() {
+        let map = generate_map(64, 48, 3, 0.42, 1.0, 40.0, 2, 1.0);
+        let atlas = export_atlas(&map, &AtlasOptions::default());
+        assert_eq!(atlas.images.len(), 2);
+        let (width, height, first) = decode_stored(&atlas.images[0]);
+        assert_eq!((width, height), (64, 48));
+        let encodings = atlas.channels[0].map(|(_, encoding)| encoding);
+        let Encoding::Linear { scale, .. } = encodings[0] else {
+            panic!("height is linear");
+        };
+        for (cell, pixel) in first.chunks(4).enumerate() {
+            let decoded = encodings[0].decode(pixel[0]);
+            assert!((decoded - map.heightmap[cell]).abs() <= scale * 0.5 + 1e-5);
+            assert_eq!(pixel[3], map.biome[cell]);
+        }
+        let (_, _, second) = decode_stored(&atlas.images[1]);
+        assert!(second.chunks(4).all(|pixel| pixel[3] == 255));
+
+        // Custom packing; flow keeps its relative precision on a log scale.
+        let options = AtlasOptions {
+            images: vec![[
+                AtlasSource::Flow,
+                AtlasSource::Empty,
+                AtlasSource::Height,
+                AtlasSource::Empty,
+            ]],
+        };
+        let atlas = export_atlas(&map, &options);
+        let (_, _, pixels) = decode_stored(&atlas.images[0]);
+        let (source, flow) = atlas.channels[0][0];
+        assert_eq!(source, AtlasSource::Flow);
+        let Encoding::Log { scale, .. } = flow else {
+            panic!("flow is logarithmic");
+        };
+        for (cell, pixel) in pixels.chunks(4).enumerate() {
+            let expected = map.flow[cell].max(0.0);
+            let decoded = flow.decode(pixel[0]);
+            assert!((decoded + 1.0) / (expected + 1.0) - 1.0 <= scale.exp_m1() + 1e-4);
+            assert!((expected + 1.0) / (decoded + 1.0) - 1.0 <= scale.exp_m1() + 1e-4);
+            assert_eq!(pixel[1], 255);
+        }
+        let manifest = atlas.manifest(&map).to_string();
+        assert!(manifest.contains(r#""encoding":"log""#), "{manifest}");
+        assert!(manifest.contains(r#""channel":"b","source":"height""#));
+    }
+}
@@ -0,0 +1,347 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::json::Json;
+use crate::MapResult;
+
+/// Every biome the classifier can emit. The discriminant is the code stored
+/// in the biome layer, so this table is the one place codes are defined.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub enum Biome {
+    Ocean = 0,
+    Lake = 1,
+    Tundra = 2,
+    BorealForest = 3,
+    TemperateForest = 4,
+    TemperateGrassland = 5,
+    TropicalForest = 6,
+    Savanna = 7,
+    Desert = 8,
+    Alpine = 9,
+    /// Gentle land just above the sea next to the ocean.
+    Beach = 10,
+    /// Coastal band too steep for a beach.
+    RockyShore = 11,
+    /// Dry grass plains between temperate grassland and desert.
+    Steppe = 12,
+}
+
+/// All biomes in code order.
+pub(crate) const BIOMES: [Biome; 13] = [
+    Biome::Ocean,
+    Biome::Lake,
+    Biome::Tundra,
+    Biome::BorealForest,
+    Biome::TemperateForest,
+    Biome::TemperateGrassland,
+    Biome::TropicalForest,
+    Biome::Savanna,
+    Biome::Desert,
+    Biome::Alpine,
+    Biome::Beach,
+    Biome::RockyShore,
+    Biome::Steppe,
+];
+
+impl Biome {
+    pub(crate) const fn code(self) -> u8 {
+        self as u8
+    }
+
+    pub(crate) fn from_code(code: u8) -> Option<Self> {
+        BIOMES.get(code as usize).copied()
+    }
+
+    pub(crate) fn from_key(key: &str) -> Option<Self> {
+        BIOMES.into_iter().find(|biome| biome.key() == key)
+    }
+
+    /// Stable identifier, matching `BiomeId` on the TypeScript side.
+    pub(crate) fn key(self) -> &'static str {
+        match self {
+            Biome::Ocean => "ocean",
+            Biome::Lake => "lake",
+            Biome::Tundra => "tundra",
+            Biome::BorealForest => "boreal-forest",
+            Biome::TemperateForest => "temperate-forest",
+            Biome::TemperateGrassland => "temperate-grassland",
+            Biome::TropicalForest => "tropical-forest",
+            Biome::Savanna => "savanna",
+            Biome::Desert => "desert",
+            Biome::Alpine => "alpine",
+            Biome::Beach => "beach",
+            Biome::RockyShore => "rocky-shore",
+            Biome::Steppe => "steppe",
+        }
+    }
+
+    pub(crate) fn display_name(self) -> &'static str {
+        match self {
+            Biome::Ocean => "Ocean",
+            Biome::Lake => "Lake",
+            Biome::Tundra => "Tundra",
+            Biome::BorealForest => "Boreal Forest",
+            Biome::TemperateForest => "Temperate Forest",
+            Biome::TemperateGrassland => "Temperate Grassland",
+            Biome::TropicalForest => "Tropical Forest",
+            Biome::Savanna => "Savanna",
+            Biome::Desert => "Desert",
+            Biome::Alpine => "Alpine",
+            Biome::Beach => "Beach",
+            Biome::RockyShore => "Rocky Shore",
+            Biome::Steppe => "Steppe",
+        }
+    }
+
+    /// Mirrors `BIOME_COLORS` in the frontend's `color.ts`.
+    pub(crate) fn default_color(self) -> [u8; 3] {
+        match self {
+            Biome::Ocean => [32, 64, 128],
+            Biome::Lake => [42, 96, 160],
+            Biome::Tundra => [175, 196, 209],
+            Biome::BorealForest => [68, 102, 80],
+            Biome::TemperateForest => [74, 128, 88],
+            Biome::TemperateGrassland => [137, 169, 103],
+            Biome::TropicalForest => [66, 140, 62],
+            Biome::Savanna => [198, 172, 94],
+            Biome::Desert => [218, 192, 130],
+            Biome::Alpine => [210, 210, 210],
+            Biome::Beach => [224, 208, 156],
+            Biome::RockyShore => [122, 118, 110],
+            Biome::Steppe => [176, 176, 112],
+        }
+    }
+
+    fn definition(self) -> Json {
+        let [r, g, b] = self.default_color();
+        Json::object()
+            .with("code", self.code() as u32)
+            .with("key", self.key())
+            .with("display_name", self.display_name())
+            .with("default_color", vec![r as u32, g as u32, b as u32])
+    }
+}
+
+/// Climate lookup for land below the alpine line, in the style of a Whittaker
+/// diagram. A cell takes the first band whose `max_temperature` exceeds its
+/// temperature, then the first zone of that band whose `min_moisture` its
+/// moisture exceeds; the last band and the last zone of each band catch
+/// everything beyond them.
+#[derive(Clone)]
+pub struct BiomeTable {
+    bands: Vec<ClimateBand>,
+}
+
+#[derive(Clone)]
+struct ClimateBand {
+    max_temperature: f32,
+    /// `(min_moisture, biome)`, wettest first.
+    zones: Vec<(f32, Biome)>,
+}
+
+impl Default for BiomeTable {
+    /// Tundra at the poles, then boreal forest, temperate forest, grassland
+    /// and steppe, and tropical forest and savanna toward the equator, with
+    /// deserts wherever the mid and hot bands run dry.
+    fn default() -> Self {
+        let band = |max_temperature, zones: &[(f32, Biome)]| ClimateBand {
+            max_temperature,
+            zones: zones.to_vec(),
+        };
+        Self {
+            bands: vec![
+                band(0.2, &[(0.0, Biome::Tundra)]),
+                band(0.35, &[(0.4, Biome::BorealForest), (0.0, Biome::Tundra)]),
+                band(
+                    0.55,
+                    &[
+                        (0.55, Biome::TemperateForest),
+                        (0.35, Biome::TemperateGrassland),
+                        (0.2, Biome::Steppe),
+                        (0.0, Biome::Desert),
+                    ],
+                ),
+                band(
+                    0.75,
+                    &[
+                        (0.7, Biome::TropicalForest),
+                        (0.55, Biome::TemperateForest),
+                        (0.4, Biome::Savanna),
+                        (0.25, Biome::Steppe),
+                        (0.0, Biome::Desert),
+                    ],
+                ),
+                band(
+                    1.0,
+                    &[
+                        (0.7, Biome::TropicalForest),
+                        (0.45, Biome::Savanna),
+                        (0.0, Biome::Desert),
+                    ],
+                ),
+            ],
+        }
+    }
+}
+
+impl BiomeTable {
+    /// Builds a table from `(max_temperature, zones)` bands, coldest first,
+    /// with zones as `(min_moisture, biome)`, wettest first. Every band needs
+    /// at least one zone, and the table at least one band.
+    pub fn new(bands: Vec<(f32, Vec<(f32, Biome)>)>) -> Result<Self, String> {
+        if bands.is_empty() {
+            return Err("biome table needs at least one band".to_string());
+        }
+        if bands.iter().any(|(_, zones)| zones.is_empty()) {
+            return Err("biome table bands need at least one zone".to_string());
+        }
+        let bands = bands
+            .into_iter()
+            .map(|(max_temperature, zones)| ClimateBand {
+                max_temperature,
+                zones,
+            })
+            .collect();
+        Ok(Self { bands })
+    }
+
+    pub(crate) fn classify(&self, temperature: f32, moisture: f32) -> Biome {
+        let last = self.bands.len() - 1;
+        let band = self
+            .bands
+            .iter()
+            .enumerate()
+            .find(|&(i, band)| temperature < band.max_temperature || i == last)
+            .map(|(_, band)| band)
+            .expect("tables hold at least one band");
+        let last = band.zones.len() - 1;
+        band.zones
+            .iter()
+            .enumerate()
+            .find(|&(i, &(min_moisture, _))| moisture > min_moisture || i == last)
+            .map(|(_, &(_, biome))| biome)
+            .expect("bands hold at least one zone")
+    }
+
+    /// Reads `[{ max_temperature, zones: [{ min_moisture, biome }] }]`, bands
+    /// coldest first and zones wettest first, with biomes by key.
+    #[cfg(feature = "wasm")]
+    pub(crate) fn from_js(bands: &js_sys::Array) -> Result<Self, String> {
+        let bands = bands
+            .iter()
+            .map(|band| {
+                let zones = js::get_array(&band, "zones")
+                    .ok_or("biome table bands need a zones array")?
+                    .iter()
+                    .map(|zone| {
+                        let key = js::get_string(&zone, "biome").unwrap_or_default();
+                        let biome = Biome::from_key(&key)
+                            .ok_or_else(|| format!("unknown biome in table: {key}"))?;
+                        Ok((js::get_f32(&zone, "min_moisture", 0.0), biome))
+                    })
+                    .collect::<Result<Vec<_>, String>>()?;
+                Ok((js::get_f32(&band, "max_temperature", 1.0), zones))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Self::new(bands)
+    }
+
+    pub(crate) fn to_json(&self) -> Json {
+        let bands = self
+            .bands
+            .iter()
+            .map(|band| {
+                let zones = band
+                    .zones
+                    .iter()
+                    .map(|&(min_moisture, biome)| {
+                        Json::object()
+                            .with("min_moisture", min_moisture)
+                            .with("biome", biome.key())
+                    })
+                    .collect::<Vec<_>>();
+                Json::object()
+                    .with("max_temperature", band.max_temperature)
+                    .with("zones", zones)
+            })
+            .collect::<Vec<_>>();
+        Json::from(bands)
+    }
+}
+
+/// Every biome as `{ code, key, display_name, default_color: [r, g, b] }`,
+/// in code order.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn biome_definitions() -> JsValue {
+    Json::from(
+        BIOMES
+            .iter()
+            .map(|biome| biome.definition())
+            .collect::<Vec<_>>(),
+    )
+    .to_js()
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Display name for a biome code, or `undefined` for unknown codes.
+    pub fn biome_name(&self, code: u8) -> Option<String> {
+        Biome::from_code(code).map(|biome| biome.display_name().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Biome, BiomeTable};
+    use crate::generate_map;
+
+    #[test]
+    fn default_maps_band_by_latitude() {
+        let map = generate_map(128, 128, 11, 0.42, 1.0, 40.0, 2, 1.0);
+        let mean_latitude = |biome: Biome| {
+            let rows: Vec<f32> = (0..map.biome.len())
+                .filter(|&index| map.biome[index] == biome.code())
+                .map(|index| ((index / 128) as f32 / 127.0 - 0.5).abs())
+                .collect();
+            assert!(!rows.is_empty(), "no {}", biome.key());
+            rows.iter().sum::<f32>() / rows.len() as f32
+        };
+        let bands = [
+            Biome::TropicalForest,
+            Biome::TemperateForest,
+            Biome::BorealForest,
+            Biome::Tundra,
+        ]
+        .map(mean_latitude);
+        assert!(bands.windows(2).all(|pair| pair[0] < pair[1]), "{bands:?}");
+
+        let table = BiomeTable::default();
+        assert_eq!(table.classify(0.5, 0.25), Biome::Steppe);
+        assert_eq!(table.classify(0.3, 0.5), Biome::BorealForest);
+        assert_eq!(table.classify(0.9, 0.8), Biome::TropicalForest);
+        assert_eq!(table.classify(0.9, 0.5), Biome::Savanna);
+        assert_eq!(table.classify(1.5, -1.0), Biome::Desert);
+    }
+
+    #[test]
+    fn custom_tables_need_bands_and_zones() {
+        assert!(BiomeTable::new(Vec::new()).is_err());
+        assert!(BiomeTable::new(vec![(0.5, Vec::new())]).is_err());
+        let table = BiomeTable::new(vec![
+            (0.3, vec![(0.0, Biome::Tundra)]),
+            (
+                1.0,
+                vec![(0.5, Biome::TemperateForest), (0.0, Biome::Steppe)],
+            ),
+        ])
+        .unwrap();
+        assert_eq!(table.classify(0.1, 0.9), Biome::Tundra);
+        assert_eq!(table.classify(0.6, 0.9), Biome::TemperateForest);
+        assert_eq!(table.classify(2.0, 0.2), Biome::Steppe);
+    }
+}
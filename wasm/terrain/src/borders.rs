@@ -0,0 +1,211 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "wasm")]
+use crate::contour::flat_points;
+use crate::contour::{interfaces, simplify};
+use crate::economy::{territories, NO_TERRITORY};
+use crate::geometry::distance;
+#[cfg(feature = "wasm")]
+use crate::js;
+#[cfg(feature = "wasm")]
+use crate::json::Json;
+use crate::MapResult;
+
+pub(crate) struct PatrolOptions {
+    /// Largest world distance a simplified route may stray from the traced
+    /// border.
+    pub tolerance: f32,
+    /// World distance within which border points move onto a road.
+    pub road_snap: f32,
+}
+
+impl Default for PatrolOptions {
+    fn default() -> Self {
+        Self {
+            tolerance: 4.0,
+            road_snap: 24.0,
+        }
+    }
+}
+
+impl PatrolOptions {
+    #[cfg(feature = "wasm")]
+    fn from_js(options: &JsValue) -> Self {
+        let defaults = Self::default();
+        Self {
+            tolerance: js::get_f32(options, "tolerance", defaults.tolerance).max(0.0),
+            road_snap: js::get_f32(options, "road_snap", defaults.road_snap).max(0.0),
+        }
+    }
+}
+
+/// One border stretch between two territories, in world units.
+pub(crate) struct PatrolRoute {
+    /// Territories on either side, as indices into `settlements`, lower
+    /// first.
+    pub a: u32,
+    pub b: u32,
+    pub closed: bool,
+    pub points: Vec<(f32, f32)>,
+    /// Share of the traced border moved onto a road.
+    pub road_share: f32,
+}
+
+impl PatrolRoute {
+    pub(crate) fn length(&self) -> f32 {
+        let closing = self
+            .closed
+            .then(|| [self.points[self.points.len() - 1], self.points[0]]);
+        self.points
+            .windows(2)
+            .map(|pair| [pair[0], pair[1]])
+            .chain(closing)
+            .map(|[a, b]| distance(a, b))
+            .sum()
+    }
+}
+
+/// Borders between every pair of adjacent territories. Points within
+/// `road_snap` of a road move onto the nearest road cell before the line is
+/// simplified, so patrols keep to the road wherever it shadows the border.
+pub(crate) fn patrol_routes(map: &MapResult, options: &PatrolOptions) -> Vec<PatrolRoute> {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let labels = territories(map);
+    let roads = map.road_mask();
+    let cell = map.cell_to_world(width + 1);
+    let reach_x = (options.road_snap / cell.0.max(f32::EPSILON)).ceil() as i64;
+    let reach_y = (options.road_snap / cell.1.max(f32::EPSILON)).ceil() as i64;
+    let snap = |point: (f32, f32)| -> Option<(f32, f32)> {
+        if options.road_snap <= 0.0 {
+            return None;
+        }
+        let (cx, cy) = map.nearest_cell(point.0, point.1);
+        let mut best: Option<(f32, usize)> = None;
+        for dy in -reach_y..=reach_y {
+            for dx in -reach_x..=reach_x {
+                let (nx, ny) = (cx as i64 + dx, cy as i64 + dy);
+                if nx < 0 || ny < 0 || nx >= width as i64 || ny >= height as i64 {
+                    continue;
+                }
+                let index = ny as usize * width + nx as usize;
+                if !roads[index] {
+                    continue;
+                }
+                let (x, y) = map.cell_to_world(index);
+                let distance = distance((x, y), point);
+                if distance <= options.road_snap
+                    && best.is_none_or(|(d, i)| distance < d || (distance == d && index < i))
+                {
+                    best = Some((distance, index));
+                }
+            }
+        }
+        best.map(|(_, index)| map.cell_to_world(index))
+    };
+
+    let traced = interfaces(&labels, width, height, |a, b| {
+        a != NO_TERRITORY && b != NO_TERRITORY
+    });
+    let mut routes = Vec::new();
+    for ((a, b), contours) in traced {
+        for contour in contours {
+            let mut points: Vec<(f32, f32)> = Vec::with_capacity(contour.points.len());
+            let mut snapped = 0;
+            for &corner in &contour.points {
+                let world = map.corner_to_world(corner);
+                let point = match snap(world) {
+                    Some(road) => {
+                        snapped += 1;
+                        road
+                    }
+                    None => world,
+                };
+                if points.last() != Some(&point) {
+                    points.push(point);
+                }
+            }
+            if points.len() < 2 {
+                continue;
+            }
+            routes.push(PatrolRoute {
+                a,
+                b,
+                closed: contour.closed,
+                points: simplify(&points, options.tolerance, contour.closed),
+                road_share: snapped as f32 / contour.points.len() as f32,
+            });
+        }
+    }
+    routes
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Border routes between adjacent territories (see `territories()`) for
+    /// patrols and dashed border lines, as `{ a, b, closed, length,
+    /// road_share, points }`: `a` and `b` are the territories' indices into
+    /// `settlements()`, lower first, `points` interleaved world `x, y`, and
+    /// `road_share` the part of the border moved onto roads. Borders follow
+    /// cell sides without stair steps and are simplified to
+    /// `options.tolerance` (default 4) world units; wherever a road runs
+    /// within `options.road_snap` (default 24) world units the route takes
+    /// the road instead. One pair may share several stretches.
+    pub fn patrol_routes(&self, options: JsValue) -> JsValue {
+        let records = patrol_routes(self, &PatrolOptions::from_js(&options))
+            .iter()
+            .map(|route| {
+                Json::object()
+                    .with("a", route.a)
+                    .with("b", route.b)
+                    .with("closed", route.closed)
+                    .with("length", route.length())
+                    .with("road_share", route.road_share)
+                    .with("points", flat_points(&route.points))
+            })
+            .collect::<Vec<_>>();
+        Json::from(records).to_js()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{patrol_routes, PatrolOptions};
+    use crate::economy::territories;
+    use crate::generate_map;
+
+    #[test]
+    fn routes_separate_neighbours_and_take_nearby_roads() {
+        let map = generate_map(96, 96, 6, 0.42, 1.0, 40.0, 2, 1.0);
+        let labels = territories(&map);
+        let off_road = PatrolOptions {
+            road_snap: 0.0,
+            ..PatrolOptions::default()
+        };
+        let routes = patrol_routes(&map, &off_road);
+        assert!(!routes.is_empty());
+        for route in &routes {
+            assert!(route.a < route.b);
+            assert!(route.length() > 0.0);
+            assert_eq!(route.road_share, 0.0);
+            // Every vertex sits on the corner or side between the two.
+            for &(x, y) in &route.points {
+                let (cx, cy) = map.nearest_cell(x, y);
+                let near: Vec<u32> = (cy.saturating_sub(1)..=(cy + 1).min(95))
+                    .flat_map(|ny| {
+                        (cx.saturating_sub(1)..=(cx + 1).min(95)).map(move |nx| (nx, ny))
+                    })
+                    .map(|(nx, ny)| labels[ny * 96 + nx])
+                    .collect();
+                assert!(near.contains(&route.a) && near.contains(&route.b));
+            }
+        }
+
+        let snapped = patrol_routes(&map, &PatrolOptions::default());
+        if !map.road_graph.is_empty() {
+            assert!(snapped.iter().any(|route| route.road_share > 0.0));
+        }
+    }
+}
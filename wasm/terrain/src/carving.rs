@@ -0,0 +1,207 @@
+#[cfg(feature = "wasm")]
+use js_sys::Float32Array;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::changes::Layer;
+use crate::editing::{flag_settlements, rederive_climate, CellRect};
+use crate::geometry::{distance, smoothstep};
+#[cfg(feature = "wasm")]
+use crate::query::points_from_js;
+use crate::{render, MapResult, REGION_SIZE};
+
+/// Height drop enforced between consecutive channel cells so steepest-descent
+/// pointers follow the carved bed.
+const CHANNEL_GRADIENT: f32 = 1e-4;
+/// How far the drawn path may climb above the cut depth before carving is
+/// refused rather than deepened.
+const UPHILL_TOLERANCE: f32 = 0.05;
+/// Water assigned along the carved channel: a river, below the lake cutoff.
+const CHANNEL_WATER: f32 = 0.5;
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Carves a river along a polyline of interleaved world `x, y` points.
+    /// The bed sits `depth` below the terrain and falls monotonically
+    /// downstream; a path drawn from mouth to source is reversed, and one
+    /// that climbs more than the tolerance above the cut is rejected. Banks
+    /// follow a smooth cross-section out to `width / 2` world units. Water,
+    /// moisture, and biomes along the corridor are updated immediately; flow
+    /// picks up the new channel on the next `recompute`. Returns the number of
+    /// cells lowered.
+    pub fn carve_river(
+        &mut self,
+        points: &Float32Array,
+        width: f32,
+        depth: f32,
+    ) -> Result<u32, JsValue> {
+        let points = points_from_js(points)?;
+        carve_river(self, &points, width, depth).map_err(|message| JsValue::from_str(&message))
+    }
+}
+
+pub(crate) fn carve_river(
+    map: &mut MapResult,
+    points: &[f32],
+    width: f32,
+    depth: f32,
+) -> Result<u32, String> {
+    if points.len() < 4 {
+        return Err("a river needs at least two points".to_string());
+    }
+    if points.iter().any(|value| !value.is_finite()) {
+        return Err("river points must be finite".to_string());
+    }
+    if !(width.is_finite() && width > 0.0 && depth.is_finite() && depth >= 0.0) {
+        return Err("river width must be positive and depth non-negative".to_string());
+    }
+
+    let grid_width = map.width as usize;
+    let grid_height = map.height as usize;
+    let mut channel: Vec<usize> = Vec::new();
+    for segment in points.chunks_exact(2).collect::<Vec<_>>().windows(2) {
+        let from = map.nearest_cell(segment[0][0], segment[0][1]);
+        let to = map.nearest_cell(segment[1][0], segment[1][1]);
+        for (x, y) in render::raster_line(from, to) {
+            let index = y * grid_width + x;
+            if channel.last() != Some(&index) {
+                channel.push(index);
+            }
+        }
+    }
+    if map.heightmap[channel[channel.len() - 1]] > map.heightmap[channel[0]] {
+        channel.reverse();
+    }
+
+    let mut bed = Vec::with_capacity(channel.len());
+    let mut previous = f32::INFINITY;
+    for &index in &channel {
+        let cut = map.heightmap[index] - depth;
+        let level = cut.min(previous - CHANNEL_GRADIENT).max(0.0);
+        if cut - level > UPHILL_TOLERANCE {
+            let (x, y) = map.cell_to_world(index);
+            return Err(format!(
+                "river climbs too steeply near ({x:.0}, {y:.0}); draw it downhill"
+            ));
+        }
+        bed.push(level);
+        previous = level;
+    }
+
+    let half_width = width * 0.5;
+    let reach_x = (half_width / REGION_SIZE * map.width as f32).ceil() as usize;
+    let reach_y = (half_width / REGION_SIZE * map.height as f32).ceil() as usize;
+    let original = map.heightmap.clone();
+    let mut carved = original.clone();
+    let mut corridor: Option<CellRect> = None;
+    for (&center, &level) in channel.iter().zip(&bed) {
+        let (center_x, center_y) = map.cell_to_world(center);
+        let (cx, cy) = (center % grid_width, center / grid_width);
+        let rect = CellRect {
+            x0: cx.saturating_sub(reach_x),
+            y0: cy.saturating_sub(reach_y),
+            x1: (cx + reach_x).min(grid_width - 1),
+            y1: (cy + reach_y).min(grid_height - 1),
+        };
+        corridor = Some(corridor.map_or(rect, |corridor| corridor.union(rect)));
+        for index in rect.cells(grid_width) {
+            let (world_x, world_y) = map.cell_to_world(index);
+            let distance = distance((world_x, world_y), (center_x, center_y));
+            let profile = if index == center {
+                1.0
+            } else if distance < half_width {
+                smoothstep(1.0 - distance / half_width)
+            } else {
+                continue;
+            };
+            let height = original[index] + (level - original[index]) * profile;
+            carved[index] = carved[index].min(height);
+            if profile >= 0.5 {
+                map.water[index] = map.water[index].max(CHANNEL_WATER);
+            }
+        }
+    }
+
+    let lowered = carved
+        .iter()
+        .zip(&original)
+        .filter(|(carved, original)| carved < original)
+        .count();
+    map.heightmap = carved;
+
+    if let Some(corridor) = corridor {
+        let cells: Vec<usize> = corridor.cells(grid_width).collect();
+        rederive_climate(map, &cells);
+        flag_settlements(map, corridor);
+        map.changes.record(&[Layer::Water], corridor);
+        map.changes.record(&Layer::CLIMATE, corridor);
+        map.mark_dirty(corridor);
+    }
+    Ok(lowered as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::carve_river;
+    use crate::{generate_map, MapResult};
+
+    const SIZE: usize = 64;
+    const ROW: usize = 32;
+
+    /// Ground falling steadily from west to east, with `ridge` added across
+    /// the middle columns.
+    fn slope(ridge: f32) -> MapResult {
+        let mut map = generate_map(SIZE as u32, SIZE as u32, 3, 0.42, 1.0, 40.0, 0, 1.0);
+        for index in 0..SIZE * SIZE {
+            let x = index % SIZE;
+            let bump = if (28..36).contains(&x) { ridge } else { 0.0 };
+            map.heightmap[index] = 0.8 - 0.3 * x as f32 / SIZE as f32 + bump;
+        }
+        map.invalidate_terrain();
+        map
+    }
+
+    /// World points from column `from` to column `to` along `ROW`.
+    fn line(map: &MapResult, from: usize, to: usize) -> Vec<f32> {
+        let (x0, y0) = map.cell_to_world(ROW * SIZE + from);
+        let (x1, y1) = map.cell_to_world(ROW * SIZE + to);
+        vec![x0, y0, x1, y1]
+    }
+
+    #[test]
+    fn beds_fall_monotonically_whichever_way_they_are_drawn() {
+        let mut downhill = slope(0.0);
+        let points = line(&downhill, 4, 60);
+        assert!(carve_river(&mut downhill, &points, 64.0, 0.05).unwrap() > 0);
+        let bed: Vec<f32> = (4..=60)
+            .map(|x| downhill.heightmap[ROW * SIZE + x])
+            .collect();
+        for pair in bed.windows(2) {
+            assert!(pair[1] < pair[0], "{pair:?}");
+        }
+        assert!(downhill.dirty.is_some());
+
+        // Drawn from the mouth up to the source, the same bed is cut.
+        let mut uphill = slope(0.0);
+        let points = line(&uphill, 60, 4);
+        carve_river(&mut uphill, &points, 64.0, 0.05).unwrap();
+        assert_eq!(uphill.heightmap, downhill.heightmap);
+    }
+
+    #[test]
+    fn rivers_climbing_over_a_ridge_are_refused() {
+        let mut map = slope(0.2);
+        let before = map.heightmap.clone();
+        let points = line(&map, 4, 60);
+        let error = carve_river(&mut map, &points, 64.0, 0.05).unwrap_err();
+        assert!(error.contains("draw it downhill"), "{error}");
+        assert_eq!(map.heightmap, before);
+        assert!(map.dirty.is_none());
+
+        // A low bump within the tolerance is cut through instead.
+        let mut map = slope(0.03);
+        let points = line(&map, 4, 60);
+        assert!(carve_river(&mut map, &points, 64.0, 0.05).is_ok());
+    }
+}
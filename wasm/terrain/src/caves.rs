@@ -0,0 +1,321 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use noise::{NoiseFn, OpenSimplex};
+
+use crate::determinism::Fnv;
+use crate::geometry::distance;
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::json::Json;
+use crate::movement::Profile;
+use crate::{slope_map, MapResult, SimpleRng, DIRECTIONS, REGION_SIZE};
+
+/// Karst regions per map side, roughly.
+const KARST_FREQUENCY: f64 = 3.0;
+/// Expansions allowed when routing an entrance to a nearby road.
+const TRAIL_MAX_NODES: u32 = 20_000;
+
+pub(crate) struct CaveOptions {
+    pub max_count: u32,
+    /// Minimum world distance between entrances.
+    pub min_spacing: f32,
+    /// Normalized slope where cliff faces start to open into caves; cliffs
+    /// twice as steep score highest.
+    pub min_slope: f32,
+    /// Height above the sea that rock must keep over every cave cell.
+    pub min_overburden: f32,
+    /// Random-walk steps tracing each system's extent.
+    pub steps: u32,
+    /// Entrances within this many world units of a road get a trail to it.
+    pub connect_distance: f32,
+}
+
+impl Default for CaveOptions {
+    fn default() -> Self {
+        Self {
+            max_count: 12,
+            min_spacing: 160.0,
+            min_slope: 0.15,
+            min_overburden: 0.04,
+            steps: 48,
+            connect_distance: 96.0,
+        }
+    }
+}
+
+impl CaveOptions {
+    #[cfg(feature = "wasm")]
+    fn from_js(options: &JsValue) -> Self {
+        let defaults = Self::default();
+        Self {
+            max_count: js::get_u32(options, "max_count", defaults.max_count),
+            min_spacing: js::get_f32(options, "min_spacing", defaults.min_spacing),
+            min_slope: js::get_f32(options, "min_slope", defaults.min_slope).clamp(0.0, 0.99),
+            min_overburden: js::get_f32(options, "min_overburden", defaults.min_overburden),
+            steps: js::get_u32(options, "steps", defaults.steps),
+            connect_distance: js::get_f32(options, "connect_distance", defaults.connect_distance),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CaveKind {
+    /// Opens in a steep slope or cliff face.
+    Cliff,
+    /// Dissolved into wet, soluble ground.
+    Karst,
+}
+
+impl CaveKind {
+    fn key(self) -> &'static str {
+        match self {
+            CaveKind::Cliff => "cliff",
+            CaveKind::Karst => "karst",
+        }
+    }
+}
+
+pub(crate) struct Cave {
+    pub kind: CaveKind,
+    pub entrance: usize,
+    pub seed: u32,
+    /// Thickest rock over the system as a share of the map's relief.
+    pub depth_hint: f32,
+    /// Convex hull of the traced system, as world `(x, y)` corners.
+    pub extent: Vec<(f32, f32)>,
+    /// Foot path from the entrance to the nearest road, as world points.
+    pub trail: Option<Vec<(f32, f32)>>,
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Cave entrances as `{ kind, entrance_x, entrance_y, seed, depth_hint,
+    /// extent, trail }`. `kind` is `"cliff"` or `"karst"`, `seed` is stable
+    /// for the map seed and entrance, `depth_hint` is the thickest rock over
+    /// the system relative to the map's relief, `extent` is an interleaved
+    /// world polygon over the system, and `trail` is an interleaved foot path
+    /// to a nearby road or `null`. Options: `max_count`, `min_spacing`,
+    /// `min_slope`, `min_overburden`, `steps`, `connect_distance`.
+    /// Entrances are spaced from each other by `min_spacing` alone; they are
+    /// derived on request rather than registered, so `features()` leaves
+    /// them out.
+    pub fn caves(&self, options: JsValue) -> JsValue {
+        let records = caves(self, &CaveOptions::from_js(&options))
+            .iter()
+            .map(|cave| Json::from_record(cave.record(self)))
+            .collect::<Vec<_>>();
+        Json::from(records).to_js()
+    }
+}
+
+pub(crate) fn caves(map: &MapResult, options: &CaveOptions) -> Vec<Cave> {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let slopes = slope_map(&map.heightmap, width, height);
+    let noise = OpenSimplex::new(map.settings.seed.wrapping_add(617));
+    let peak = map.heightmap.iter().copied().fold(map.sea_level, f32::max);
+    let relief = (peak - map.sea_level).max(f32::EPSILON);
+    let roofed = |index: usize| {
+        !map.is_water_body(index) && map.heightmap[index] - map.sea_level >= options.min_overburden
+    };
+
+    let mut candidates: Vec<(f32, usize, CaveKind)> = (0..map.heightmap.len())
+        .filter(|&index| roofed(index) && !map.is_river(index))
+        .filter_map(|index| {
+            let cliff =
+                ((slopes[index] - options.min_slope) / options.min_slope.max(0.01)).min(1.0);
+            let (x, y) = map.cell_to_world(index);
+            let solubility = noise.get([
+                x as f64 / REGION_SIZE as f64 * KARST_FREQUENCY,
+                y as f64 / REGION_SIZE as f64 * KARST_FREQUENCY,
+            ]) as f32;
+            // Soluble rock only hollows out where water seeps through it.
+            let karst = ((solubility - 0.1) / 0.3).clamp(0.0, 1.0) * map.moisture[index];
+            if cliff <= 0.0 && karst < 0.5 {
+                return None;
+            }
+            Some(if cliff >= karst {
+                (cliff, index, CaveKind::Cliff)
+            } else {
+                (karst, index, CaveKind::Karst)
+            })
+        })
+        .collect();
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+
+    let roads = map.road_mask();
+    let foot = Profile::preset("foot").expect("foot is a preset");
+    let mut caves: Vec<Cave> = Vec::new();
+    for (_, entrance, kind) in candidates {
+        if caves.len() >= options.max_count as usize {
+            break;
+        }
+        let (x, y) = map.cell_to_world(entrance);
+        let crowded = caves
+            .iter()
+            .any(|cave| distance(map.cell_to_world(cave.entrance), (x, y)) < options.min_spacing);
+        if crowded {
+            continue;
+        }
+        let mut hash = Fnv::new();
+        hash.write_u32(map.settings.seed);
+        hash.write_u32(entrance as u32);
+        let seed = hash.finish() as u32;
+
+        let system = trace_system(map, entrance, seed, options.steps, &roofed);
+        let depth_hint = system
+            .iter()
+            .map(|&index| (map.heightmap[index] - map.sea_level) / relief)
+            .fold(0.0f32, f32::max)
+            .clamp(0.0, 1.0);
+        let extent = convex_hull(
+            system
+                .iter()
+                .map(|&index| map.cell_to_world(index))
+                .collect(),
+        );
+        let trail =
+            nearest_road(map, &roads, entrance, options.connect_distance).and_then(|road| {
+                let path = map.find_path_cells(
+                    (x, y),
+                    map.cell_to_world(road),
+                    &foot,
+                    TRAIL_MAX_NODES,
+                    0,
+                )?;
+                Some(
+                    path.into_iter()
+                        .map(|index| map.cell_to_world(index))
+                        .collect(),
+                )
+            });
+        caves.push(Cave {
+            kind,
+            entrance,
+            seed,
+            depth_hint,
+            extent,
+            trail,
+        });
+    }
+    caves
+}
+
+/// Cells visited by a random walk from the entrance that keeps its heading
+/// most of the time and never leaves cells with enough rock overhead.
+fn trace_system(
+    map: &MapResult,
+    entrance: usize,
+    seed: u32,
+    steps: u32,
+    roofed: &impl Fn(usize) -> bool,
+) -> Vec<usize> {
+    let width = map.width as i32;
+    let height = map.height as i32;
+    let mut rng = SimpleRng::new(seed);
+    let mut visited = vec![entrance];
+    let mut current = entrance;
+    let mut heading = (rng.next_u32() % DIRECTIONS.len() as u32) as usize;
+    for _ in 0..steps {
+        if rng.next_f32() < 0.35 {
+            heading =
+                (heading + DIRECTIONS.len() - 1 + (rng.next_u32() % 3) as usize) % DIRECTIONS.len();
+        }
+        let next = (0..DIRECTIONS.len()).find_map(|turn| {
+            let direction = (heading + turn) % DIRECTIONS.len();
+            let (dx, dy) = DIRECTIONS[direction];
+            let nx = (current % width as usize) as i32 + dx;
+            let ny = (current / width as usize) as i32 + dy;
+            if nx < 0 || ny < 0 || nx >= width || ny >= height {
+                return None;
+            }
+            let index = ny as usize * width as usize + nx as usize;
+            roofed(index).then_some((direction, index))
+        });
+        let Some((direction, index)) = next else {
+            break;
+        };
+        heading = direction;
+        current = index;
+        if !visited.contains(&index) {
+            visited.push(index);
+        }
+    }
+    visited
+}
+
+/// Closest road cell within `radius` world units, ties by index.
+fn nearest_road(map: &MapResult, roads: &[bool], from: usize, radius: f32) -> Option<usize> {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let reach_x = (radius / (REGION_SIZE / width as f32)).ceil() as usize;
+    let reach_y = (radius / (REGION_SIZE / height as f32)).ceil() as usize;
+    let (x, y) = (from % width, from / width);
+    let (fx, fy) = map.cell_to_world(from);
+    let mut best: Option<(f32, usize)> = None;
+    for ny in y.saturating_sub(reach_y)..=(y + reach_y).min(height - 1) {
+        for nx in x.saturating_sub(reach_x)..=(x + reach_x).min(width - 1) {
+            let index = ny * width + nx;
+            if !roads[index] {
+                continue;
+            }
+            let distance = distance(map.cell_to_world(index), (fx, fy));
+            if distance <= radius && best.is_none_or(|(d, _)| distance < d) {
+                best = Some((distance, index));
+            }
+        }
+    }
+    best.map(|(_, index)| index)
+}
+
+/// Andrew's monotone chain, counter-clockwise in screen space, without
+/// repeated or collinear corners.
+fn convex_hull(mut points: Vec<(f32, f32)>) -> Vec<(f32, f32)> {
+    points.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+    let cross = |o: (f32, f32), a: (f32, f32), b: (f32, f32)| {
+        (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+    };
+    let mut hull: Vec<(f32, f32)> = Vec::with_capacity(points.len() * 2);
+    for pass in [points.clone(), points.into_iter().rev().collect()] {
+        let floor = hull.len();
+        for point in pass {
+            while hull.len() >= floor + 2
+                && cross(hull[hull.len() - 2], hull[hull.len() - 1], point) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        hull.pop();
+    }
+    hull
+}
+
+impl Cave {
+    fn record(&self, map: &MapResult) -> Vec<(&'static str, Json)> {
+        let flatten = |points: &[(f32, f32)]| {
+            Json::Array(
+                points
+                    .iter()
+                    .flat_map(|&(x, y)| [Json::from(x), Json::from(y)])
+                    .collect(),
+            )
+        };
+        let (x, y) = map.cell_to_world(self.entrance);
+        vec![
+            ("kind", self.kind.key().into()),
+            ("entrance_x", x.into()),
+            ("entrance_y", y.into()),
+            ("seed", self.seed.into()),
+            ("depth_hint", self.depth_hint.into()),
+            ("extent", flatten(&self.extent)),
+            ("trail", self.trail.as_deref().map_or(Json::Null, flatten)),
+        ]
+    }
+}
@@ -0,0 +1,228 @@
+#[cfg(feature = "wasm")]
+use js_sys::{Float32Array, Uint8Array};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::editing::CellRect;
+#[cfg(feature = "wasm")]
+use crate::js;
+#[cfg(feature = "wasm")]
+use crate::json::Json;
+#[cfg(feature = "wasm")]
+use crate::MapResult;
+
+/// Rectangles kept per layer before they collapse into their bounding box.
+const MAX_REGIONS: usize = 16;
+
+/// Per-cell layers a renderer uploads, keyed like their getters.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Layer {
+    Heightmap,
+    Flow,
+    Moisture,
+    Temperature,
+    Water,
+    Biome,
+}
+
+impl Layer {
+    pub(crate) const ALL: [Layer; 6] = [
+        Layer::Heightmap,
+        Layer::Flow,
+        Layer::Moisture,
+        Layer::Temperature,
+        Layer::Water,
+        Layer::Biome,
+    ];
+    /// Layers `rederive_climate` rewrites.
+    pub(crate) const CLIMATE: [Layer; 3] = [Layer::Temperature, Layer::Moisture, Layer::Biome];
+
+    pub(crate) fn key(self) -> &'static str {
+        match self {
+            Layer::Heightmap => "heightmap",
+            Layer::Flow => "flow",
+            Layer::Moisture => "moisture",
+            Layer::Temperature => "temperature",
+            Layer::Water => "water",
+            Layer::Biome => "biome",
+        }
+    }
+}
+
+/// Rectangles of each layer changed since the last `take_dirty_regions`.
+/// Rectangles that overlap or share an edge are merged as they arrive.
+#[derive(Clone)]
+pub(crate) struct ChangeLog {
+    regions: [Vec<CellRect>; Layer::ALL.len()],
+}
+
+impl ChangeLog {
+    /// Every layer changed over the whole map, as after generation.
+    pub(crate) fn full(width: u32, height: u32) -> Self {
+        let mut log = Self {
+            regions: Default::default(),
+        };
+        log.record(&Layer::ALL, full_rect(width, height));
+        log
+    }
+
+    pub(crate) fn record(&mut self, layers: &[Layer], rect: CellRect) {
+        for &layer in layers {
+            let regions = &mut self.regions[layer as usize];
+            let mut merged = rect;
+            // A merge can grow the rectangle into ones it missed, so repeat.
+            while let Some(position) = regions.iter().position(|other| other.touches(&merged)) {
+                merged = merged.union(regions.swap_remove(position));
+            }
+            regions.push(merged);
+            if regions.len() > MAX_REGIONS {
+                let bounds = regions.iter().copied().reduce(CellRect::union);
+                *regions = bounds.into_iter().collect();
+            }
+        }
+    }
+
+    /// Empties the log, returning what it held.
+    pub(crate) fn take(&mut self) -> [Vec<CellRect>; Layer::ALL.len()] {
+        std::mem::take(&mut self.regions)
+    }
+}
+
+pub(crate) fn full_rect(width: u32, height: u32) -> CellRect {
+    CellRect {
+        x0: 0,
+        y0: 0,
+        x1: width as usize - 1,
+        y1: height as usize - 1,
+    }
+}
+
+/// Cells of `values` inside `rect`, row by row.
+pub(crate) fn region<T: Copy>(values: &[T], width: usize, rect: &CellRect) -> Vec<T> {
+    rect.cells(width).map(|index| values[index]).collect()
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Rectangles changed since the previous call, as `{ heightmap, flow,
+    /// moisture, temperature, water, biome }` each holding an array of `{ x,
+    /// y, w, h }` in cells; unchanged layers hold an empty array. Brushes,
+    /// `erode_steps`, `carve_river`, `recompute`, `set_sea_level`, and
+    /// `transform` record what they touch; a fresh map reports one
+    /// full-extent rectangle per layer. Overlapping and adjacent rectangles
+    /// are merged, and a layer past 16 collapses to their bounding box.
+    /// Clears the record.
+    pub fn take_dirty_regions(&mut self) -> JsValue {
+        let regions = self.changes.take();
+        let mut record = Json::object();
+        for layer in Layer::ALL {
+            let rects = regions[layer as usize]
+                .iter()
+                .map(|rect| {
+                    Json::object()
+                        .with("x", rect.x0)
+                        .with("y", rect.y0)
+                        .with("w", rect.x1 - rect.x0 + 1)
+                        .with("h", rect.y1 - rect.y0 + 1)
+                })
+                .collect::<Vec<_>>();
+            record = record.with(layer.key(), rects);
+        }
+        record.to_js()
+    }
+
+    /// Copies the cells of `layer` inside `rect` (`{ x, y, w, h }` in cells)
+    /// into the start of `dest`, row by row, ready for `texSubImage2D`.
+    /// `dest` is a `Float32Array` for float layers and a `Uint8Array` for
+    /// `"biome"`. Throws when the rectangle leaves the map or `dest` is too
+    /// short. Returns the number of cells written.
+    pub fn copy_region(&self, layer: &str, rect: JsValue, dest: JsValue) -> Result<u32, JsValue> {
+        let x = js::get_u32(&rect, "x", 0) as usize;
+        let y = js::get_u32(&rect, "y", 0) as usize;
+        let w = js::get_u32(&rect, "w", 0) as usize;
+        let h = js::get_u32(&rect, "h", 0) as usize;
+        if w == 0 || h == 0 || x + w > self.width as usize || y + h > self.height as usize {
+            return Err(JsValue::from_str("region must lie inside the map"));
+        }
+        let rect = CellRect {
+            x0: x,
+            y0: y,
+            x1: x + w - 1,
+            y1: y + h - 1,
+        };
+        let width = self.width as usize;
+        let count = (w * h) as u32;
+        let short = || JsValue::from_str(&format!("dest holds fewer than {count} cells"));
+        if layer == "biome" {
+            let dest = dest
+                .dyn_into::<Uint8Array>()
+                .map_err(|_| JsValue::from_str("biome regions need a Uint8Array"))?;
+            if dest.length() < count {
+                return Err(short());
+            }
+            dest.subarray(0, count)
+                .copy_from(&region(&self.biome, width, &rect));
+        } else {
+            let values = self
+                .float_layer(layer)
+                .ok_or_else(|| JsValue::from_str(&format!("unknown layer: {layer}")))?;
+            let dest = dest
+                .dyn_into::<Float32Array>()
+                .map_err(|_| JsValue::from_str("float regions need a Float32Array"))?;
+            if dest.length() < count {
+                return Err(short());
+            }
+            dest.subarray(0, count)
+                .copy_from(&region(values, width, &rect));
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{region, ChangeLog, Layer, MAX_REGIONS};
+    use crate::editing::{apply_brush, set_sea_level, Brush, CellRect};
+    use crate::generate_map;
+
+    fn rect(x0: usize, y0: usize, x1: usize, y1: usize) -> CellRect {
+        CellRect { x0, y0, x1, y1 }
+    }
+
+    #[test]
+    fn edits_record_merged_rects_per_layer() {
+        let mut map = generate_map(64, 64, 3, 0.42, 1.0, 40.0, 2, 1.0);
+        let fresh = map.changes.take();
+        for layer in Layer::ALL {
+            assert_eq!(fresh[layer as usize], vec![rect(0, 0, 63, 63)]);
+        }
+
+        apply_brush(&mut map, Brush::Raise, 300.0, 300.0, 90.0, 0.05).unwrap();
+        apply_brush(&mut map, Brush::Raise, 1700.0, 1700.0, 90.0, 0.05).unwrap();
+        let height = map.changes.regions[Layer::Heightmap as usize].to_vec();
+        assert_eq!(height.len(), 2);
+        assert!(map.changes.regions[Layer::Biome as usize].is_empty());
+        apply_brush(&mut map, Brush::Raise, 1000.0, 1000.0, 1200.0, 0.05).unwrap();
+        assert_eq!(map.changes.regions[Layer::Heightmap as usize].len(), 1);
+
+        map.changes.take();
+        set_sea_level(&mut map, 0.45, false).unwrap();
+        assert!(map.changes.regions[Layer::Flow as usize].is_empty());
+        assert_eq!(
+            map.changes.regions[Layer::Water as usize],
+            [rect(0, 0, 63, 63)]
+        );
+
+        let mut log = ChangeLog::full(64, 64);
+        log.take();
+        for i in 0..MAX_REGIONS + 1 {
+            log.record(&[Layer::Flow], rect(i * 3, i * 3, i * 3, i * 3));
+        }
+        assert_eq!(log.regions[Layer::Flow as usize], [rect(0, 0, 48, 48)]);
+        assert_eq!(
+            region(&[0, 1, 2, 3, 4, 5], 3, &rect(1, 0, 2, 1)),
+            [1, 2, 4, 5]
+        );
+    }
+}
@@ -0,0 +1,392 @@
+#[cfg(feature = "wasm")]
+use js_sys::{Uint16Array, Uint32Array, Uint8Array};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::economy::{territories, NO_TERRITORY};
+#[cfg(feature = "wasm")]
+use crate::editing::CellRect;
+#[cfg(feature = "wasm")]
+use crate::js;
+#[cfg(feature = "wasm")]
+use crate::json::Json;
+use crate::MapResult;
+
+/// Owner id of unclaimed cells. Settlement territories start as owners
+/// `1..=settlements`, in `settlements()` order.
+pub(crate) const UNOWNED: u16 = 0;
+
+/// Why a claim was refused; nothing changes when one is.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum ClaimError {
+    /// Owner 0 cannot claim.
+    NoOwner,
+    /// This many cells of the area are ocean, lake, or river.
+    Water(u32),
+    /// This many cells already belong to another owner.
+    Owned(u32),
+}
+
+impl ClaimError {
+    #[cfg(feature = "wasm")]
+    fn message(self) -> String {
+        match self {
+            ClaimError::NoOwner => "owner 0 means unowned and cannot claim".to_string(),
+            ClaimError::Water(count) => format!("{count} cells are water"),
+            ClaimError::Owned(count) => {
+                format!("{count} cells belong to another owner; pass force to take them")
+            }
+        }
+    }
+}
+
+/// Mutable per-cell owners, with running tallies and the cells changed since
+/// the last `take_changes`.
+#[derive(Clone)]
+pub(crate) struct Ownership {
+    pub owners: Vec<u16>,
+    /// Cells held, indexed by owner id.
+    tallies: Vec<u32>,
+    changed: Vec<usize>,
+    pending: Vec<bool>,
+}
+
+impl Ownership {
+    pub(crate) fn new(owners: Vec<u16>) -> Self {
+        let mut tallies = Vec::new();
+        for &owner in &owners {
+            if owner != UNOWNED {
+                if tallies.len() <= owner as usize {
+                    tallies.resize(owner as usize + 1, 0);
+                }
+                tallies[owner as usize] += 1;
+            }
+        }
+        let pending = vec![false; owners.len()];
+        Self {
+            owners,
+            tallies,
+            changed: Vec::new(),
+            pending,
+        }
+    }
+
+    /// Settlement territories, each settlement owning its own.
+    pub(crate) fn from_territories(map: &MapResult) -> Self {
+        let owners = territories(map)
+            .into_iter()
+            .map(|label| {
+                if label == NO_TERRITORY {
+                    UNOWNED
+                } else {
+                    (label + 1).min(u16::MAX as u32) as u16
+                }
+            })
+            .collect();
+        Self::new(owners)
+    }
+
+    pub(crate) fn area(&self, owner: u16) -> u32 {
+        match owner {
+            UNOWNED => self.owners.iter().filter(|&&o| o == UNOWNED).count() as u32,
+            _ => self.tallies.get(owner as usize).copied().unwrap_or(0),
+        }
+    }
+
+    /// Owners holding at least one cell, with their cell counts.
+    pub(crate) fn tallies(&self) -> impl Iterator<Item = (u16, u32)> + '_ {
+        self.tallies
+            .iter()
+            .enumerate()
+            .filter(|&(_, &count)| count > 0)
+            .map(|(owner, &count)| (owner as u16, count))
+    }
+
+    fn set(&mut self, cell: usize, owner: u16) {
+        let previous = self.owners[cell];
+        if previous == owner {
+            return;
+        }
+        if previous != UNOWNED {
+            self.tallies[previous as usize] -= 1;
+        }
+        if owner != UNOWNED {
+            if self.tallies.len() <= owner as usize {
+                self.tallies.resize(owner as usize + 1, 0);
+            }
+            self.tallies[owner as usize] += 1;
+        }
+        self.owners[cell] = owner;
+        if !self.pending[cell] {
+            self.pending[cell] = true;
+            self.changed.push(cell);
+        }
+    }
+
+    /// Moves owners to `owners[cell] = old[sources[cell]]`; every cell is
+    /// reported as changed since clients hold the old layout.
+    pub(crate) fn remap(&mut self, sources: &[usize]) {
+        self.owners = sources.iter().map(|&source| self.owners[source]).collect();
+        self.changed = (0..self.owners.len()).collect();
+        self.pending = vec![true; self.owners.len()];
+    }
+
+    /// Cells whose owner changed since the last call, ascending, with their
+    /// current owners.
+    pub(crate) fn take_changes(&mut self) -> Vec<(usize, u16)> {
+        let mut cells = std::mem::take(&mut self.changed);
+        cells.sort_unstable();
+        cells
+            .into_iter()
+            .map(|cell| {
+                self.pending[cell] = false;
+                (cell, self.owners[cell])
+            })
+            .collect()
+    }
+}
+
+impl MapResult {
+    /// The claims layer, primed from the territories on first use.
+    pub(crate) fn ownership_mut(&mut self) -> &mut Ownership {
+        if self.ownership.is_none() {
+            self.ownership = Some(Ownership::from_territories(self));
+        }
+        self.ownership.as_mut().expect("just primed")
+    }
+
+    /// Gives every cell of `cells` to `owner`, all or nothing. Water is never
+    /// claimable; cells of other owners only with `force`. Returns the cells
+    /// that changed hands.
+    pub(crate) fn claim(
+        &mut self,
+        owner: u16,
+        cells: &[usize],
+        force: bool,
+    ) -> Result<u32, ClaimError> {
+        if owner == UNOWNED {
+            return Err(ClaimError::NoOwner);
+        }
+        let water = cells
+            .iter()
+            .filter(|&&cell| self.is_water_body(cell) || self.is_river(cell))
+            .count() as u32;
+        if water > 0 {
+            return Err(ClaimError::Water(water));
+        }
+        let ownership = self.ownership_mut();
+        let owned = cells
+            .iter()
+            .filter(|&&cell| ![UNOWNED, owner].contains(&ownership.owners[cell]))
+            .count() as u32;
+        if owned > 0 && !force {
+            return Err(ClaimError::Owned(owned));
+        }
+        let mut taken = 0;
+        for &cell in cells {
+            if ownership.owners[cell] != owner {
+                ownership.set(cell, owner);
+                taken += 1;
+            }
+        }
+        Ok(taken)
+    }
+
+    /// Frees the cells of `cells` held by `owner`, or by anyone with
+    /// `force`. Returns the cells freed.
+    pub(crate) fn release(&mut self, owner: u16, cells: &[usize], force: bool) -> u32 {
+        let ownership = self.ownership_mut();
+        let mut freed = 0;
+        for &cell in cells {
+            let held = ownership.owners[cell];
+            if held != UNOWNED && (force || held == owner) {
+                ownership.set(cell, UNOWNED);
+                freed += 1;
+            }
+        }
+        freed
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Owner of each cell for claim gameplay: 0 unowned, otherwise an owner
+    /// id. Until the first claim, release, or `set_ownership`, this is the
+    /// settlement territories with settlement `i` of `settlements()` as
+    /// owner `i + 1`.
+    pub fn ownership(&self) -> Uint16Array {
+        let owners = match &self.ownership {
+            Some(ownership) => ownership.owners.clone(),
+            None => Ownership::from_territories(self).owners,
+        };
+        Uint16Array::from(owners.as_slice())
+    }
+
+    /// Gives `area` to `owner` (1..65535). `area` is a cell rectangle `{ x,
+    /// y, w, h }` or a `Uint8Array` mask with one nonzero byte per included
+    /// cell. The claim is all or nothing: it throws if any cell is ocean,
+    /// lake, or river, or, unless `force`, already belongs to another owner.
+    /// Returns the number of cells that changed hands.
+    pub fn claim_cells(&mut self, owner: u16, area: JsValue, force: bool) -> Result<u32, JsValue> {
+        let cells = self.area_cells(&area)?;
+        self.claim(owner, &cells, force)
+            .map_err(|error| JsValue::from_str(&error.message()))
+    }
+
+    /// Frees the cells of `area`, given as in `claim_cells`, that `owner`
+    /// holds; with `force`, frees them whoever holds them. Returns the
+    /// number of cells freed.
+    pub fn release_cells(
+        &mut self,
+        owner: u16,
+        area: JsValue,
+        force: bool,
+    ) -> Result<u32, JsValue> {
+        let cells = self.area_cells(&area)?;
+        Ok(self.release(owner, &cells, force))
+    }
+
+    /// Cells held by `owner`; owner 0 counts the unowned cells.
+    pub fn claimed_area(&self, owner: u16) -> u32 {
+        match &self.ownership {
+            Some(ownership) => ownership.area(owner),
+            None => Ownership::from_territories(self).area(owner),
+        }
+    }
+
+    /// Every owner holding land, as `{ owner, cells }` by ascending owner.
+    pub fn claim_tallies(&self) -> JsValue {
+        let primed;
+        let ownership = match &self.ownership {
+            Some(ownership) => ownership,
+            None => {
+                primed = Ownership::from_territories(self);
+                &primed
+            }
+        };
+        let records = ownership
+            .tallies()
+            .map(|(owner, cells)| {
+                Json::object()
+                    .with("owner", owner as u32)
+                    .with("cells", cells)
+            })
+            .collect::<Vec<_>>();
+        Json::from(records).to_js()
+    }
+
+    /// Cells whose owner changed since the previous call, as interleaved
+    /// `cell index, owner` pairs in ascending cell order, for syncing
+    /// clients. Clears the record.
+    pub fn take_claim_changes(&mut self) -> Uint32Array {
+        let changes: Vec<u32> = self
+            .ownership
+            .as_mut()
+            .map(Ownership::take_changes)
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|(cell, owner)| [cell as u32, owner as u32])
+            .collect();
+        Uint32Array::from(changes.as_slice())
+    }
+
+    /// Restores a layer read from `ownership()`, so a server can persist
+    /// claims alongside the map. Clears the change record.
+    pub fn set_ownership(&mut self, owners: &Uint16Array) -> Result<(), JsValue> {
+        if owners.length() as usize != self.heightmap.len() {
+            return Err(JsValue::from_str("ownership must hold one value per cell"));
+        }
+        self.ownership = Some(Ownership::new(owners.to_vec()));
+        Ok(())
+    }
+
+    /// Drops every claim, returning to the settlement territories.
+    pub fn reset_ownership(&mut self) {
+        self.ownership = None;
+    }
+}
+
+#[cfg(feature = "wasm")]
+impl MapResult {
+    /// Cells of a `{ x, y, w, h }` rectangle or a per-cell `Uint8Array` mask.
+    fn area_cells(&self, area: &JsValue) -> Result<Vec<usize>, JsValue> {
+        if let Some(mask) = area.dyn_ref::<Uint8Array>() {
+            if mask.length() as usize != self.heightmap.len() {
+                return Err(JsValue::from_str("area mask must hold one byte per cell"));
+            }
+            return Ok(mask
+                .to_vec()
+                .iter()
+                .enumerate()
+                .filter(|&(_, &value)| value != 0)
+                .map(|(cell, _)| cell)
+                .collect());
+        }
+        let x = js::get_u32(area, "x", 0) as usize;
+        let y = js::get_u32(area, "y", 0) as usize;
+        let w = js::get_u32(area, "w", 0) as usize;
+        let h = js::get_u32(area, "h", 0) as usize;
+        if w == 0 || h == 0 || x + w > self.width as usize || y + h > self.height as usize {
+            return Err(JsValue::from_str("area must lie inside the map"));
+        }
+        let rect = CellRect {
+            x0: x,
+            y0: y,
+            x1: x + w - 1,
+            y1: y + h - 1,
+        };
+        Ok(rect.cells(self.width as usize).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ClaimError, UNOWNED};
+    use crate::generate_map;
+
+    #[test]
+    fn claims_respect_water_and_owners_and_log_changes() {
+        let mut map = generate_map(64, 64, 4, 0.42, 1.0, 40.0, 2, 1.0);
+        assert!(map.ownership.is_none());
+        let land: Vec<usize> = (0..64 * 64)
+            .filter(|&cell| !map.is_water_body(cell) && !map.is_river(cell))
+            .collect();
+        let water = (0..64 * 64).find(|&cell| map.is_water_body(cell)).unwrap();
+        let settled = map.ownership_mut().owners.clone();
+        let home = (0..64 * 64).find(|&cell| settled[cell] == 1).unwrap();
+        map.ownership_mut().take_changes();
+
+        assert_eq!(
+            map.claim(500, &[land[0], water], false),
+            Err(ClaimError::Water(1))
+        );
+        assert_eq!(
+            map.claim(UNOWNED, &[land[0]], false),
+            Err(ClaimError::NoOwner)
+        );
+        let free: Vec<usize> = land
+            .iter()
+            .copied()
+            .filter(|&cell| settled[cell] == UNOWNED)
+            .take(5)
+            .collect();
+        assert_eq!(map.claim(500, &free, false), Ok(5));
+        assert_eq!(map.claim(501, &[free[0]], false), Err(ClaimError::Owned(1)));
+        let before = map.ownership_mut().area(1);
+        assert_eq!(map.claim(500, &[home], true), Ok(1));
+        assert_eq!(map.ownership_mut().area(1), before - 1);
+        assert_eq!(map.ownership_mut().area(500), 6);
+
+        // Releasing someone else's cell needs force.
+        assert_eq!(map.release(501, &[free[1]], false), 0);
+        assert_eq!(map.release(500, &[free[1]], false), 1);
+        let changes = map.ownership_mut().take_changes();
+        assert_eq!(changes.len(), 6);
+        assert!(changes.contains(&(free[1], UNOWNED)));
+        assert!(changes.contains(&(home, 500)));
+        assert!(map.ownership_mut().take_changes().is_empty());
+        let tallies: Vec<_> = map.ownership_mut().tallies().collect();
+        assert!(tallies.contains(&(500, 5)));
+    }
+}
@@ -0,0 +1,535 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::biome::Biome;
+use crate::distance::chamfer_distance;
+use crate::features::{components, neighbors};
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::json::Json;
+use crate::{MapResult, REGION_SIZE};
+
+/// Longest side of the grid the analysis runs on. Larger maps are reduced by
+/// majority vote first, which also irons out crinkly coastlines.
+const MAX_ANALYSIS_CELLS: usize = 256;
+
+pub(crate) struct CoastalOptions {
+    /// Bays have mouths narrower than twice this, in world units.
+    pub bay_radius: f32,
+    /// Minimum `area / mouth_width²` for a bay.
+    pub min_bay_enclosure: f32,
+    /// Peninsulas join the mainland through necks narrower than twice this.
+    pub peninsula_radius: f32,
+    /// Minimum share of a peninsula's edge that faces water.
+    pub min_peninsula_surround: f32,
+    /// Straits are water corridors narrower than twice this.
+    pub strait_radius: f32,
+    /// Smallest feature, and smallest water body a strait can join, as a
+    /// fraction of the map.
+    pub min_area_fraction: f32,
+}
+
+impl Default for CoastalOptions {
+    fn default() -> Self {
+        Self {
+            bay_radius: 96.0,
+            min_bay_enclosure: 0.6,
+            peninsula_radius: 64.0,
+            min_peninsula_surround: 0.7,
+            strait_radius: 48.0,
+            min_area_fraction: 0.0005,
+        }
+    }
+}
+
+impl CoastalOptions {
+    #[cfg(feature = "wasm")]
+    fn from_js(options: &JsValue) -> Self {
+        let defaults = Self::default();
+        Self {
+            bay_radius: js::get_f32(options, "bay_radius", defaults.bay_radius),
+            min_bay_enclosure: js::get_f32(
+                options,
+                "min_bay_enclosure",
+                defaults.min_bay_enclosure,
+            ),
+            peninsula_radius: js::get_f32(options, "peninsula_radius", defaults.peninsula_radius),
+            min_peninsula_surround: js::get_f32(
+                options,
+                "min_peninsula_surround",
+                defaults.min_peninsula_surround,
+            ),
+            strait_radius: js::get_f32(options, "strait_radius", defaults.strait_radius),
+            min_area_fraction: js::get_f32(
+                options,
+                "min_area_fraction",
+                defaults.min_area_fraction,
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum CoastalKind {
+    Bay,
+    Peninsula,
+    Strait,
+}
+
+impl CoastalKind {
+    fn key(self) -> &'static str {
+        match self {
+            CoastalKind::Bay => "bay",
+            CoastalKind::Peninsula => "peninsula",
+            CoastalKind::Strait => "strait",
+        }
+    }
+}
+
+/// A classified piece of coastline. `enclosure` is `area / mouth_width²` for
+/// bays and the water-facing share of the edge for peninsulas; `neck_width`
+/// is the bay mouth, peninsula neck, or strait width, in world units.
+pub(crate) struct CoastalFeature {
+    pub kind: CoastalKind,
+    pub anchor: (f32, f32),
+    /// World bounds `(min_x, min_y, max_x, max_y)`.
+    pub extent: (f32, f32, f32, f32),
+    pub area: f32,
+    pub enclosure: Option<f32>,
+    pub neck_width: f32,
+}
+
+/// A water region cut off from open water by a narrow mouth.
+pub(crate) struct BayRegion {
+    pub cells: Vec<usize>,
+    pub mouth_width: f32,
+    pub enclosure: f32,
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Bays, peninsulas, and straits as `{ kind, anchor_x, anchor_y, extent,
+    /// area, enclosure, neck_width }` in world units. Thresholds:
+    /// `bay_radius`, `min_bay_enclosure`, `peninsula_radius`,
+    /// `min_peninsula_surround`, `strait_radius`, `min_area_fraction`.
+    pub fn coastal_features(&self, options: JsValue) -> JsValue {
+        let records = coastal_features(self, &CoastalOptions::from_js(&options))
+            .iter()
+            .map(|feature| Json::from_record(feature.record()))
+            .collect::<Vec<_>>();
+        Json::from(records).to_js()
+    }
+}
+
+/// Morphological closing of the land: water within `radius` of land on both
+/// sides of a narrow mouth fills in, and what filled in is bay.
+pub(crate) fn bay_regions(
+    water: &[bool],
+    distance_to_land: &[f32],
+    width: usize,
+    height: usize,
+    radius: f32,
+    min_enclosure: f32,
+    min_cells: usize,
+) -> Vec<BayRegion> {
+    let open: Vec<bool> = distance_to_land
+        .iter()
+        .map(|&distance| distance > radius)
+        .collect();
+    let closed_water = chamfer_distance(&open, width, height);
+    let bay_mask: Vec<bool> = (0..water.len())
+        .map(|index| water[index] && closed_water[index] > radius)
+        .collect();
+    let open_water: Vec<bool> = (0..water.len())
+        .map(|index| water[index] && !bay_mask[index])
+        .collect();
+    let mut body = vec![usize::MAX; water.len()];
+    for (id, cells) in components(&open_water, width, height)
+        .into_iter()
+        .enumerate()
+    {
+        for index in cells {
+            body[index] = id;
+        }
+    }
+    let cell_size = REGION_SIZE / width.max(height) as f32;
+    let cell_area = (REGION_SIZE / width as f32) * (REGION_SIZE / height as f32);
+    components(&bay_mask, width, height)
+        .into_iter()
+        .filter(|bay| bay.len() >= min_cells)
+        .filter_map(|cells| {
+            let mut opens_onto = Vec::new();
+            let mut mouth = 0;
+            for &index in &cells {
+                let before = opens_onto.len();
+                opens_onto.extend(
+                    neighbors(index, width, height)
+                        .filter(|&next| open_water[next])
+                        .map(|next| body[next]),
+                );
+                mouth += (opens_onto.len() > before) as usize;
+            }
+            opens_onto.sort_unstable();
+            opens_onto.dedup();
+            // A narrow passage between two bodies of water is a strait.
+            if opens_onto.len() != 1 {
+                return None;
+            }
+            let mouth_width = mouth as f32 * cell_size;
+            let enclosure = cells.len() as f32 * cell_area / (mouth_width * mouth_width);
+            (mouth > 0 && enclosure >= min_enclosure).then_some(BayRegion {
+                cells,
+                mouth_width,
+                enclosure,
+            })
+        })
+        .collect()
+}
+
+/// Runs on a majority-vote reduction of the ocean mask so the morphology
+/// passes stay linear in a bounded grid however crinkly the coast is.
+pub(crate) fn coastal_features(map: &MapResult, options: &CoastalOptions) -> Vec<CoastalFeature> {
+    let grid = CoarseGrid::new(map);
+    let (width, height) = (grid.width, grid.height);
+    let water = &grid.water;
+    let land: Vec<bool> = water.iter().map(|&water| !water).collect();
+    let distance_to_land = chamfer_distance(&land, width, height);
+    let distance_to_water = chamfer_distance(water, width, height);
+    let min_cells = ((water.len() as f32 * options.min_area_fraction) as usize).max(4);
+    let mut features = Vec::new();
+
+    for bay in bay_regions(
+        water,
+        &distance_to_land,
+        width,
+        height,
+        options.bay_radius,
+        options.min_bay_enclosure,
+        min_cells,
+    ) {
+        let anchor = best(&bay.cells, |index| distance_to_land[index]);
+        features.push(grid.feature(
+            CoastalKind::Bay,
+            &bay.cells,
+            anchor,
+            Some(bay.enclosure),
+            bay.mouth_width,
+        ));
+    }
+
+    // Opening of the land: cores deeper than the radius, regrown by it. Per
+    // landmass, the largest regrown body is the mainland; what the opening
+    // shaved off and still touches it is a peninsula candidate.
+    let opened = opening(
+        &land,
+        &distance_to_water,
+        width,
+        height,
+        options.peninsula_radius,
+    );
+    let mut mainland = vec![false; land.len()];
+    let landmasses = components(&land, width, height);
+    let mut landmass = vec![usize::MAX; land.len()];
+    for (id, cells) in landmasses.iter().enumerate() {
+        for &index in cells {
+            landmass[index] = id;
+        }
+    }
+    let mut largest: Vec<Option<Vec<usize>>> = vec![None; landmasses.len()];
+    for body in components(&opened, width, height) {
+        let slot = &mut largest[landmass[body[0]]];
+        if slot.as_ref().is_none_or(|best| best.len() < body.len()) {
+            *slot = Some(body);
+        }
+    }
+    for body in largest.into_iter().flatten() {
+        for index in body {
+            mainland[index] = true;
+        }
+    }
+    let shaved: Vec<bool> = (0..land.len())
+        .map(|index| land[index] && !mainland[index])
+        .collect();
+    for cells in components(&shaved, width, height) {
+        if cells.len() < min_cells {
+            continue;
+        }
+        let neck = cells
+            .iter()
+            .filter(|&&index| neighbors(index, width, height).any(|next| mainland[next]))
+            .count();
+        let shore = cells
+            .iter()
+            .filter(|&&index| neighbors(index, width, height).any(|next| water[next]))
+            .count();
+        if neck == 0 {
+            continue;
+        }
+        let surround = shore as f32 / (shore + neck) as f32;
+        if surround < options.min_peninsula_surround {
+            continue;
+        }
+        let anchor = best(&cells, |index| distance_to_water[index]);
+        features.push(grid.feature(
+            CoastalKind::Peninsula,
+            &cells,
+            anchor,
+            Some(surround),
+            neck as f32 * grid.cell_size,
+        ));
+    }
+
+    // Opening of the water: bodies that survive are open water; a shaved
+    // corridor touching two of them is a strait.
+    let open_water = opening(
+        water,
+        &distance_to_land,
+        width,
+        height,
+        options.strait_radius,
+    );
+    let mut body = vec![usize::MAX; water.len()];
+    for (id, cells) in components(&open_water, width, height)
+        .into_iter()
+        .filter(|cells| cells.len() >= min_cells)
+        .enumerate()
+    {
+        for index in cells {
+            body[index] = id;
+        }
+    }
+    let corridors: Vec<bool> = (0..water.len())
+        .map(|index| water[index] && !open_water[index])
+        .collect();
+    for cells in components(&corridors, width, height) {
+        let mut joins: Vec<usize> = cells
+            .iter()
+            .flat_map(|&index| neighbors(index, width, height))
+            .map(|next| body[next])
+            .filter(|&id| id != usize::MAX)
+            .collect();
+        joins.sort_unstable();
+        joins.dedup();
+        if joins.len() < 2 {
+            continue;
+        }
+        let widest = cells
+            .iter()
+            .map(|&index| distance_to_land[index])
+            .fold(0.0f32, f32::max);
+        let (cx, cy) = cells.iter().fold((0.0, 0.0), |acc, &index| {
+            let count = cells.len() as f32;
+            (
+                (acc.0 + (index % width) as f32 / count),
+                (acc.1 + (index / width) as f32 / count),
+            )
+        });
+        let anchor = best(&cells, |index| {
+            -((index % width) as f32 - cx).hypot((index / width) as f32 - cy)
+        });
+        features.push(grid.feature(CoastalKind::Strait, &cells, anchor, None, 2.0 * widest));
+    }
+    features
+}
+
+/// Cells of `mask` within `radius` of a cell deeper than `radius` inside it.
+fn opening(mask: &[bool], depth: &[f32], width: usize, height: usize, radius: f32) -> Vec<bool> {
+    let core: Vec<bool> = depth.iter().map(|&distance| distance > radius).collect();
+    chamfer_distance(&core, width, height)
+        .iter()
+        .zip(mask)
+        .map(|(&distance, &inside)| inside && distance <= radius)
+        .collect()
+}
+
+fn best(cells: &[usize], score: impl Fn(usize) -> f32) -> usize {
+    cells
+        .iter()
+        .copied()
+        .max_by(|&a, &b| score(a).total_cmp(&score(b)).then(b.cmp(&a)))
+        .unwrap_or(0)
+}
+
+/// The ocean mask reduced by an integer factor, water where most of a block
+/// is ocean.
+struct CoarseGrid {
+    factor: usize,
+    width: usize,
+    height: usize,
+    water: Vec<bool>,
+    /// World width of one coarse cell.
+    cell_size: f32,
+    map_width: usize,
+    map_height: usize,
+}
+
+impl CoarseGrid {
+    fn new(map: &MapResult) -> Self {
+        let (map_width, map_height) = (map.width as usize, map.height as usize);
+        let factor = map_width
+            .max(map_height)
+            .div_ceil(MAX_ANALYSIS_CELLS)
+            .max(1);
+        let (width, height) = (map_width.div_ceil(factor), map_height.div_ceil(factor));
+        let mut votes = vec![(0u32, 0u32); width * height];
+        for (index, &biome) in map.biome.iter().enumerate() {
+            let cell = (index / map_width / factor) * width + index % map_width / factor;
+            votes[cell].0 += (biome == Biome::Ocean.code()) as u32;
+            votes[cell].1 += 1;
+        }
+        Self {
+            factor,
+            width,
+            height,
+            water: votes
+                .iter()
+                .map(|&(ocean, total)| ocean * 2 > total)
+                .collect(),
+            cell_size: REGION_SIZE / width.max(height) as f32,
+            map_width,
+            map_height,
+        }
+    }
+
+    /// World position of a coarse cell's center.
+    fn to_world(&self, index: usize) -> (f32, f32) {
+        let center = |coarse: usize, size: usize| {
+            let fine = (coarse * self.factor) as f32 + (self.factor - 1) as f32 * 0.5;
+            (fine / size as f32 * REGION_SIZE).min(REGION_SIZE)
+        };
+        (
+            center(index % self.width, self.map_width),
+            center(index / self.width, self.map_height),
+        )
+    }
+
+    fn feature(
+        &self,
+        kind: CoastalKind,
+        cells: &[usize],
+        anchor: usize,
+        enclosure: Option<f32>,
+        neck_width: f32,
+    ) -> CoastalFeature {
+        let mut extent = (f32::INFINITY, f32::INFINITY, 0.0f32, 0.0f32);
+        for &index in cells {
+            let (x, y) = self.to_world(index);
+            extent = (
+                extent.0.min(x),
+                extent.1.min(y),
+                extent.2.max(x),
+                extent.3.max(y),
+            );
+        }
+        let cell_area = (REGION_SIZE / self.width as f32) * (REGION_SIZE / self.height as f32);
+        CoastalFeature {
+            kind,
+            anchor: self.to_world(anchor),
+            extent,
+            area: cells.len() as f32 * cell_area,
+            enclosure,
+            neck_width,
+        }
+    }
+}
+
+impl CoastalFeature {
+    fn record(&self) -> Vec<(&'static str, Json)> {
+        let (min_x, min_y, max_x, max_y) = self.extent;
+        let extent = Json::object()
+            .with("min_x", min_x)
+            .with("min_y", min_y)
+            .with("max_x", max_x)
+            .with("max_y", max_y);
+        vec![
+            ("kind", self.kind.key().into()),
+            ("anchor_x", self.anchor.0.into()),
+            ("anchor_y", self.anchor.1.into()),
+            ("extent", extent),
+            ("area", self.area.into()),
+            ("enclosure", self.enclosure.map_or(Json::Null, Json::from)),
+            ("neck_width", self.neck_width.into()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{coastal_features, CoastalKind, CoastalOptions};
+    use crate::biome::Biome;
+    use crate::{generate_map, MapResult, REGION_SIZE};
+
+    const SIZE: usize = 64;
+
+    /// Land everywhere `ocean` is false.
+    fn coast(ocean: impl Fn(usize, usize) -> bool) -> MapResult {
+        let mut map = generate_map(SIZE as u32, SIZE as u32, 3, 0.42, 1.0, 40.0, 0, 1.0);
+        for index in 0..SIZE * SIZE {
+            map.biome[index] = if ocean(index % SIZE, index / SIZE) {
+                Biome::Ocean.code()
+            } else {
+                Biome::TemperateGrassland.code()
+            };
+        }
+        map
+    }
+
+    fn kinds(map: &MapResult) -> Vec<&'static str> {
+        coastal_features(map, &CoastalOptions::default())
+            .iter()
+            .map(|feature| feature.kind.key())
+            .collect()
+    }
+
+    #[test]
+    fn a_narrow_channel_between_two_seas_is_a_strait() {
+        // Seas north and south, joined through the land by a two-cell channel.
+        let map = coast(|x, y| !(20..44).contains(&y) || (30..32).contains(&x));
+        let features = coastal_features(&map, &CoastalOptions::default());
+        assert_eq!(kinds(&map), ["strait"]);
+        let strait = &features[0];
+        let cell = REGION_SIZE / SIZE as f32;
+        assert!(
+            strait.neck_width <= 2.0 * cell + 1.0,
+            "{}",
+            strait.neck_width
+        );
+        assert!((30.0 * cell..32.0 * cell).contains(&strait.anchor.0));
+        assert!((20.0 * cell..44.0 * cell).contains(&strait.anchor.1));
+        assert!(strait.enclosure.is_none());
+    }
+
+    #[test]
+    fn a_pocket_behind_a_narrow_mouth_is_a_bay_and_a_thin_spit_a_peninsula() {
+        // Sea along the north; a five-cell-wide pocket reached through a
+        // four-cell mouth, too wide to pinch off as a strait; a four-cell-wide
+        // spit reaching north into the sea.
+        let map = coast(|x, y| {
+            let sea = y < 16 && !((50..54).contains(&x) && y >= 4);
+            let mouth = (30..34).contains(&x) && (16..24).contains(&y);
+            let pocket = (29..34).contains(&x) && (24..36).contains(&y);
+            sea || mouth || pocket
+        });
+        let features = coastal_features(&map, &CoastalOptions::default());
+        let mut found = kinds(&map);
+        found.sort_unstable();
+        assert_eq!(found, ["bay", "peninsula"]);
+        let cell = REGION_SIZE / SIZE as f32;
+        for feature in &features {
+            match feature.kind {
+                CoastalKind::Bay => {
+                    assert!((29.0 * cell..34.0 * cell).contains(&feature.anchor.0));
+                    assert!(feature.anchor.1 > 16.0 * cell);
+                    assert!(feature.enclosure.unwrap() > 1.0);
+                }
+                CoastalKind::Peninsula => {
+                    assert!((50.0 * cell..54.0 * cell).contains(&feature.anchor.0));
+                    assert!(feature.anchor.1 < 16.0 * cell);
+                    assert!(feature.enclosure.unwrap() >= 0.7);
+                }
+                CoastalKind::Strait => unreachable!(),
+            }
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use crate::features::components;
+use crate::geometry::{distance, polyline_length};
+use crate::movement::Profile;
+use crate::pathfinding::{astar, snap_to_passable};
+use crate::render::raster_line;
+use crate::{slope_map, MapResult, REGION_SIZE};
+
+/// Cells this close to the ocean, in cell widths, count as shore.
+const COAST_BAND_CELLS: f32 = 3.0;
+/// Cells a settlement may move to reach passable ground for routing.
+const ROUTE_SNAP_RADIUS: u32 = 2;
+
+impl MapResult {
+    /// Shore route of each `road_graph` edge, by position, or `None` for
+    /// roads that run straight. Cached with the roads.
+    pub(crate) fn shore_routes(&self) -> &[Option<Vec<usize>>] {
+        self.cache.shore_routes.get_or_init(|| coastal_routes(self))
+    }
+
+    /// Cells the road at `position` in `road_graph` covers: its shore route
+    /// when it has one, else the straight line between its settlements.
+    /// `None` when either settlement is gone.
+    pub(crate) fn road_line(&self, position: usize) -> Option<Vec<(usize, usize)>> {
+        let width = self.width as usize;
+        if let Some(route) = &self.shore_routes()[position] {
+            return Some(
+                route
+                    .iter()
+                    .map(|&cell| (cell % width, cell / width))
+                    .collect(),
+            );
+        }
+        let (a, b) = self.road_graph[position];
+        let (start, end) = (self.settlement(a)?, self.settlement(b)?);
+        Some(raster_line(
+            self.nearest_cell(start.x, start.y),
+            self.nearest_cell(end.x, end.y),
+        ))
+    }
+}
+
+/// With `coastal_roads`, routes every road between two ports on the same
+/// landmass over cart costs with cells near the shore discounted by
+/// `coastal_road_discount`, keeping the route when its length is within
+/// `coastal_road_detour` of the straight distance.
+fn coastal_routes(map: &MapResult) -> Vec<Option<Vec<usize>>> {
+    let settings = &map.settings;
+    let mut routes = vec![None; map.road_graph.len()];
+    if !settings.coastal_roads {
+        return routes;
+    }
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let ports = map.docks();
+    let port = |id: u32| {
+        let position = map.settlements.iter().position(|s| s.id == id)?;
+        ports[position].map(|_| &map.settlements[position])
+    };
+    let pairs: Vec<_> = map
+        .road_graph
+        .iter()
+        .map(|&(a, b)| port(a).zip(port(b)))
+        .collect();
+    if pairs.iter().all(Option::is_none) {
+        return routes;
+    }
+
+    let land: Vec<bool> = (0..map.biome.len())
+        .map(|index| !map.is_water_body(index))
+        .collect();
+    let mut landmass = vec![usize::MAX; land.len()];
+    for (label, group) in components(&land, width, height).into_iter().enumerate() {
+        for index in group {
+            landmass[index] = label;
+        }
+    }
+    let costs = shore_costs(map, settings.coastal_road_discount);
+    let length = |path: &[usize]| -> f32 {
+        let points: Vec<_> = path.iter().map(|&index| map.cell_to_world(index)).collect();
+        polyline_length(&points, false)
+    };
+    let endpoint = |x: f32, y: f32| {
+        let (x, y) = map.nearest_cell(x, y);
+        snap_to_passable(&costs, width, height, x, y, ROUTE_SNAP_RADIUS)
+    };
+
+    for (route, pair) in routes.iter_mut().zip(pairs) {
+        let Some((a, b)) = pair else {
+            continue;
+        };
+        let (Some(start), Some(goal)) = (endpoint(a.x, a.y), endpoint(b.x, b.y)) else {
+            continue;
+        };
+        if landmass[start] != landmass[goal] {
+            continue;
+        }
+        let straight = distance((a.x, a.y), (b.x, b.y));
+        *route = astar(&costs, width, height, start, goal, (width * height) as u32)
+            .filter(|path| length(path) <= straight * settings.coastal_road_detour);
+    }
+    routes
+}
+
+/// Cart costs over dry land and rivers, which roads bridge, ignoring the
+/// existing roads; cells within `COAST_BAND_CELLS` of the ocean cost
+/// `discount` less.
+fn shore_costs(map: &MapResult, discount: f32) -> Vec<f32> {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let cart = Profile::preset("cart").expect("cart is a preset");
+    let slopes = slope_map(&map.heightmap, width, height);
+    let coast = map.coast_distance();
+    let band = COAST_BAND_CELLS * REGION_SIZE / width.max(height) as f32;
+    (0..map.heightmap.len())
+        .map(|index| {
+            if map.is_water_body(index) {
+                return f32::INFINITY;
+            }
+            let cost = 1.0 + slopes[index] * cart.slope;
+            if coast[index] <= band {
+                cost * (1.0 - discount)
+            } else {
+                cost
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::COAST_BAND_CELLS;
+    use crate::biome::Biome;
+    use crate::render::raster_line;
+    use crate::{generate_map, MapResult, Settlement, REGION_SIZE};
+
+    const SIZE: usize = 64;
+    const SHORE: f32 = 24.0;
+
+    /// A round island rising to a central peak, with four ports just inside
+    /// the shore joined by a ring of roads.
+    fn ring_island(detour: f32) -> MapResult {
+        let mut map = generate_map(SIZE as u32, SIZE as u32, 3, 0.42, 1.0, 40.0, 0, 1.0);
+        let center = SIZE as f32 / 2.0;
+        for index in 0..SIZE * SIZE {
+            let radius = ((index % SIZE) as f32 - center).hypot((index / SIZE) as f32 - center);
+            let land = radius < SHORE;
+            map.heightmap[index] = if land {
+                map.sea_level + 0.05 + (SHORE - radius) / SHORE * 0.3
+            } else {
+                map.sea_level - 0.1
+            };
+            map.flow[index] = 1.0;
+            map.water[index] = if land { 0.0 } else { 0.5 };
+            map.biome[index] = if land {
+                Biome::TemperateGrassland.code()
+            } else {
+                Biome::Ocean.code()
+            };
+        }
+        let cell = REGION_SIZE / SIZE as f32;
+        map.settlements = [(55, 32), (32, 55), (9, 32), (32, 9)]
+            .into_iter()
+            .enumerate()
+            .map(|(id, (x, y))| Settlement {
+                id: id as u32,
+                x: x as f32 * cell,
+                y: y as f32 * cell,
+                size: 2.0,
+                issue: None,
+                era: 0,
+                name: None,
+            })
+            .collect();
+        map.road_graph = vec![(0, 1), (1, 2), (2, 3), (3, 0)];
+        map.history.road_eras.clear();
+        map.settings.coastal_roads = true;
+        map.settings.coastal_road_detour = detour;
+        map.invalidate_settlements();
+        map.invalidate_terrain();
+        map
+    }
+
+    #[test]
+    fn ring_road_follows_the_shore_within_its_detour() {
+        let map = ring_island(1.5);
+        let cell = REGION_SIZE / SIZE as f32;
+        let coast = map.coast_distance();
+        let mask = map.road_mask();
+        for (position, &(a, b)) in map.road_graph.iter().enumerate() {
+            let route = map.shore_routes()[position]
+                .as_ref()
+                .expect("ports on one island take the shore");
+            assert!(route
+                .iter()
+                .all(|&index| coast[index] <= COAST_BAND_CELLS * cell));
+            assert!(route.iter().all(|&index| mask[index]));
+
+            // Nothing cuts across the interior along the chord.
+            let (start, end) = (map.settlement(a).unwrap(), map.settlement(b).unwrap());
+            let chord = raster_line(
+                map.nearest_cell(start.x, start.y),
+                map.nearest_cell(end.x, end.y),
+            );
+            let (x, y) = chord[chord.len() / 2];
+            assert!(!mask[y * SIZE + x]);
+            let length: f32 = route
+                .windows(2)
+                .map(|step| {
+                    let (dx, dy) = (
+                        (step[1] % SIZE) as f32 - (step[0] % SIZE) as f32,
+                        (step[1] / SIZE) as f32 - (step[0] / SIZE) as f32,
+                    );
+                    dx.hypot(dy) * cell
+                })
+                .sum();
+            assert!(length <= (start.x - end.x).hypot(start.y - end.y) * 1.5);
+        }
+
+        // Too tight a detour leaves every road straight across the island.
+        let tight = ring_island(1.05);
+        assert!(tight.shore_routes().iter().all(Option::is_none));
+        let mask = tight.road_mask();
+        for position in 0..tight.road_graph.len() {
+            let chord = tight.road_line(position).unwrap();
+            let (x, y) = chord[chord.len() / 2];
+            assert!(mask[y * SIZE + x]);
+            assert!(coast[y * SIZE + x] > COAST_BAND_CELLS * cell);
+        }
+
+        let mut plain = ring_island(1.5);
+        plain.settings.coastal_roads = false;
+        plain.invalidate_settlements();
+        assert!(plain.shore_routes().iter().all(Option::is_none));
+    }
+}
@@ -0,0 +1,231 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::geometry::distance;
+use crate::json::Json;
+use crate::style::FLOAT_LAYERS;
+use crate::MapResult;
+
+/// Settlements further apart than this many world units never pair up.
+const SETTLEMENT_MATCH_RADIUS: f32 = 64.0;
+
+pub(crate) struct LayerDifference {
+    pub name: &'static str,
+    pub rmse: f32,
+    pub max_abs: f32,
+}
+
+/// Differences between two maps of the same dimensions. Cell-wise metrics
+/// compare cells at the same index; settlements pair up by proximity.
+pub(crate) struct MapComparison {
+    pub layers: Vec<LayerDifference>,
+    /// `(biome in a, biome in b, cells)` for every nonzero pairing, sorted by
+    /// codes. Diagonal entries count unchanged cells.
+    pub biome_confusion: Vec<(u8, u8, u32)>,
+    pub biome_changed_fraction: f32,
+    /// Share of cells that are wet (water above zero) in exactly one map.
+    pub water_changed_fraction: f32,
+    /// `(id in a, id in b, world distance)`, closest pairs first.
+    pub matched_settlements: Vec<(u32, u32, f32)>,
+    pub unmatched_a: Vec<u32>,
+    pub unmatched_b: Vec<u32>,
+}
+
+/// Per-layer differences between two maps: `layers` maps each float layer
+/// name to `{ rmse, max_abs }`, `biome` holds `changed_fraction` and a
+/// sparse `confusion` list of `{ a, b, count }` code pairs, `water` holds
+/// the `changed_fraction` of cells wet in only one map, and `settlements`
+/// pairs ids within 64 world units, nearest first, as `matched: [{ a, b,
+/// distance }]` plus `unmatched_a` and `unmatched_b`. Throws when the
+/// dimensions differ.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn compare_maps(a: &MapResult, b: &MapResult) -> Result<JsValue, JsValue> {
+    Ok(compare(a, b)
+        .map_err(|message| JsValue::from_str(&message))?
+        .to_json()
+        .to_js())
+}
+
+pub(crate) fn compare(a: &MapResult, b: &MapResult) -> Result<MapComparison, String> {
+    if a.width != b.width || a.height != b.height {
+        return Err(format!(
+            "cannot compare a {}x{} map with a {}x{} map",
+            a.width, a.height, b.width, b.height
+        ));
+    }
+    let cells = a.biome.len();
+
+    let layers = FLOAT_LAYERS
+        .iter()
+        .filter_map(|layer| {
+            let name = layer.name;
+            let (left, right) = (a.float_layer(name)?, b.float_layer(name)?);
+            let (mut squares, mut max_abs) = (0.0f64, 0.0f32);
+            for (&x, &y) in left.iter().zip(right) {
+                let difference = (x - y).abs();
+                squares += (difference as f64).powi(2);
+                max_abs = max_abs.max(difference);
+            }
+            Some(LayerDifference {
+                name,
+                rmse: (squares / cells as f64).sqrt() as f32,
+                max_abs,
+            })
+        })
+        .collect();
+
+    let mut confusion = vec![0u32; 256 * 256];
+    for (&x, &y) in a.biome.iter().zip(&b.biome) {
+        confusion[x as usize * 256 + y as usize] += 1;
+    }
+    let biome_confusion: Vec<(u8, u8, u32)> = confusion
+        .iter()
+        .enumerate()
+        .filter(|(_, &count)| count > 0)
+        .map(|(index, &count)| ((index / 256) as u8, (index % 256) as u8, count))
+        .collect();
+    let biome_changed = biome_confusion
+        .iter()
+        .filter(|(x, y, _)| x != y)
+        .map(|&(_, _, count)| count)
+        .sum::<u32>();
+    let water_changed = a
+        .water
+        .iter()
+        .zip(&b.water)
+        .filter(|(&x, &y)| (x > 0.0) != (y > 0.0))
+        .count();
+
+    let mut candidates = Vec::new();
+    for left in &a.settlements {
+        for right in &b.settlements {
+            let distance = distance((left.x, left.y), (right.x, right.y));
+            if distance <= SETTLEMENT_MATCH_RADIUS {
+                candidates.push((left.id, right.id, distance));
+            }
+        }
+    }
+    candidates.sort_by(|x, y| x.2.total_cmp(&y.2).then(x.0.cmp(&y.0)).then(x.1.cmp(&y.1)));
+    let mut matched_settlements: Vec<(u32, u32, f32)> = Vec::new();
+    for (left, right, distance) in candidates {
+        if matched_settlements
+            .iter()
+            .all(|&(x, y, _)| x != left && y != right)
+        {
+            matched_settlements.push((left, right, distance));
+        }
+    }
+    let unmatched_a = a
+        .settlements
+        .iter()
+        .map(|settlement| settlement.id)
+        .filter(|&id| matched_settlements.iter().all(|&(x, _, _)| x != id))
+        .collect();
+    let unmatched_b = b
+        .settlements
+        .iter()
+        .map(|settlement| settlement.id)
+        .filter(|&id| matched_settlements.iter().all(|&(_, y, _)| y != id))
+        .collect();
+
+    Ok(MapComparison {
+        layers,
+        biome_confusion,
+        biome_changed_fraction: biome_changed as f32 / cells as f32,
+        water_changed_fraction: water_changed as f32 / cells as f32,
+        matched_settlements,
+        unmatched_a,
+        unmatched_b,
+    })
+}
+
+impl MapComparison {
+    pub(crate) fn to_json(&self) -> Json {
+        let layers = Json::from_record(
+            self.layers
+                .iter()
+                .map(|layer| {
+                    (
+                        layer.name,
+                        Json::object()
+                            .with("rmse", layer.rmse)
+                            .with("max_abs", layer.max_abs),
+                    )
+                })
+                .collect(),
+        );
+        let confusion = self
+            .biome_confusion
+            .iter()
+            .map(|&(a, b, count)| {
+                Json::object()
+                    .with("a", a as u32)
+                    .with("b", b as u32)
+                    .with("count", count)
+            })
+            .collect::<Vec<_>>();
+        let matched = self
+            .matched_settlements
+            .iter()
+            .map(|&(a, b, distance)| {
+                Json::object()
+                    .with("a", a)
+                    .with("b", b)
+                    .with("distance", distance)
+            })
+            .collect::<Vec<_>>();
+        Json::object()
+            .with("layers", layers)
+            .with(
+                "biome",
+                Json::object()
+                    .with("changed_fraction", self.biome_changed_fraction)
+                    .with("confusion", confusion),
+            )
+            .with(
+                "water",
+                Json::object().with("changed_fraction", self.water_changed_fraction),
+            )
+            .with(
+                "settlements",
+                Json::object()
+                    .with("matched", matched)
+                    .with("unmatched_a", self.unmatched_a.clone())
+                    .with("unmatched_b", self.unmatched_b.clone()),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compare;
+    use crate::generate_map;
+
+    #[test]
+    fn a_map_matches_itself_exactly() {
+        let map = generate_map(48, 48, 12, 0.42, 1.0, 40.0, 2, 1.0);
+        let same = compare(&map, &map).unwrap();
+        assert!(!same.layers.is_empty());
+        for layer in &same.layers {
+            assert_eq!((layer.rmse, layer.max_abs), (0.0, 0.0), "{}", layer.name);
+        }
+        assert!(same.biome_confusion.iter().all(|&(a, b, _)| a == b));
+        assert_eq!(same.biome_changed_fraction, 0.0);
+        assert_eq!(same.water_changed_fraction, 0.0);
+        assert_eq!(same.matched_settlements.len(), map.settlements.len());
+        for (&(a, b, distance), settlement) in same.matched_settlements.iter().zip(&map.settlements)
+        {
+            assert_eq!((a, b, distance), (settlement.id, settlement.id, 0.0));
+        }
+        assert!(same.unmatched_a.is_empty() && same.unmatched_b.is_empty());
+    }
+
+    #[test]
+    fn maps_of_different_sizes_do_not_compare() {
+        let a = generate_map(48, 48, 12, 0.42, 1.0, 40.0, 0, 1.0);
+        let b = generate_map(48, 32, 12, 0.42, 1.0, 40.0, 0, 1.0);
+        let error = compare(&a, &b).err().unwrap();
+        assert_eq!(error, "cannot compare a 48x48 map with a 48x32 map");
+    }
+}
@@ -0,0 +1,301 @@
+use std::collections::{BTreeMap, HashMap};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::biome::Biome;
+use crate::geometry::{distance, segment_distance};
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::json::Json;
+use crate::{MapResult, REGION_SIZE};
+
+/// A traced interface in cell-corner coordinates: `(x, y)` is the top-left
+/// corner of cell `(x, y)`.
+pub(crate) struct Contour {
+    pub points: Vec<(f32, f32)>,
+    /// Whether the last point joins back to the first.
+    pub closed: bool,
+}
+
+/// Traces the interfaces between differently labeled cells, grouped by the
+/// label pair `(low, high)` and kept only where `keep(low, high)` holds.
+/// Every cell side between the two labels is chained into polylines that
+/// end where three labels meet, at the map border, or where the interface
+/// pinches through a corner; loops come back closed. As in marching
+/// squares, vertices sit at the midpoints of the cell sides, so staircase
+/// edges come out as straight diagonals.
+pub(crate) fn interfaces(
+    labels: &[u32],
+    width: usize,
+    height: usize,
+    keep: impl Fn(u32, u32) -> bool,
+) -> BTreeMap<(u32, u32), Vec<Contour>> {
+    let corner = |x: usize, y: usize| y * (width + 1) + x;
+    let mut sides: BTreeMap<(u32, u32), Vec<(usize, usize)>> = BTreeMap::new();
+    for y in 0..height {
+        for x in 0..width {
+            let label = labels[y * width + x];
+            // The side to the east, then the side to the south.
+            let neighbours = [
+                (x + 1 < width).then(|| {
+                    (
+                        labels[y * width + x + 1],
+                        corner(x + 1, y),
+                        corner(x + 1, y + 1),
+                    )
+                }),
+                (y + 1 < height).then(|| {
+                    (
+                        labels[(y + 1) * width + x],
+                        corner(x, y + 1),
+                        corner(x + 1, y + 1),
+                    )
+                }),
+            ];
+            for (other, from, to) in neighbours.into_iter().flatten() {
+                let pair = (label.min(other), label.max(other));
+                if label != other && keep(pair.0, pair.1) {
+                    sides.entry(pair).or_default().push((from, to));
+                }
+            }
+        }
+    }
+    let to_point = |index: usize| ((index % (width + 1)) as f32, (index / (width + 1)) as f32);
+    sides
+        .into_iter()
+        .map(|(pair, sides)| {
+            let contours = chain(&sides)
+                .into_iter()
+                .map(|(corners, closed)| {
+                    let midpoints = corners.windows(2).map(|pair| {
+                        let (a, b) = (to_point(pair[0]), to_point(pair[1]));
+                        ((a.0 + b.0) * 0.5, (a.1 + b.1) * 0.5)
+                    });
+                    let points = if closed {
+                        midpoints.collect()
+                    } else {
+                        // Open ends keep their corners so neighbouring
+                        // interfaces meet at the junction.
+                        std::iter::once(to_point(corners[0]))
+                            .chain(midpoints)
+                            .chain(std::iter::once(to_point(corners[corners.len() - 1])))
+                            .collect()
+                    };
+                    Contour { points, closed }
+                })
+                .collect();
+            (pair, contours)
+        })
+        .collect()
+}
+
+/// Chains unit sides into corner runs. Runs start at corners not joining
+/// exactly two sides; the loops left over start at their lowest corner and
+/// repeat it at the end.
+fn chain(sides: &[(usize, usize)]) -> Vec<(Vec<usize>, bool)> {
+    let mut at: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (id, &(from, to)) in sides.iter().enumerate() {
+        at.entry(from).or_default().push(id);
+        at.entry(to).or_default().push(id);
+    }
+    let mut used = vec![false; sides.len()];
+    let walk = |start: usize, first: usize, used: &mut Vec<bool>| {
+        let mut corners = vec![start];
+        let (mut current, mut side) = (start, first);
+        loop {
+            used[side] = true;
+            let (from, to) = sides[side];
+            current = if from == current { to } else { from };
+            corners.push(current);
+            let next = match at[&current].as_slice() {
+                [a, b] => [*a, *b].into_iter().find(|&id| !used[id]),
+                _ => None,
+            };
+            match next {
+                Some(next) => side = next,
+                None => break,
+            }
+        }
+        corners
+    };
+
+    let mut runs = Vec::new();
+    let mut ends: Vec<usize> = at
+        .iter()
+        .filter(|(_, ids)| ids.len() != 2)
+        .map(|(&corner, _)| corner)
+        .collect();
+    ends.sort_unstable();
+    for start in ends {
+        for &side in &at[&start] {
+            if !used[side] {
+                runs.push((walk(start, side, &mut used), false));
+            }
+        }
+    }
+    let mut starts: Vec<usize> = at.keys().copied().collect();
+    starts.sort_unstable();
+    for start in starts {
+        if let Some(&side) = at[&start].iter().find(|&&id| !used[id]) {
+            runs.push((walk(start, side, &mut used), true));
+        }
+    }
+    runs
+}
+
+/// Douglas–Peucker simplification keeping every point within `tolerance` of
+/// the result. Closed rings are split at the point farthest from the first.
+pub(crate) fn simplify(points: &[(f32, f32)], tolerance: f32, closed: bool) -> Vec<(f32, f32)> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+    if closed {
+        let first = points[0];
+        let far = (1..points.len())
+            .max_by(|&a, &b| {
+                distance(points[a], first)
+                    .total_cmp(&distance(points[b], first))
+                    .then(b.cmp(&a))
+            })
+            .expect("at least three points");
+        let mut ring = simplify(&points[..=far], tolerance, false);
+        let mut back: Vec<_> = points[far..].iter().copied().chain([first]).collect();
+        back = simplify(&back, tolerance, false);
+        ring.extend(&back[1..back.len() - 1]);
+        return ring;
+    }
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+    let mut stack = vec![(0, points.len() - 1)];
+    while let Some((start, end)) = stack.pop() {
+        let (mut farthest, mut worst) = (0, 0.0f32);
+        for index in start + 1..end {
+            let offset = segment_distance(points[index], points[start], points[end]);
+            if offset > worst {
+                (farthest, worst) = (index, offset);
+            }
+        }
+        if worst > tolerance {
+            keep[farthest] = true;
+            stack.push((start, farthest));
+            stack.push((farthest, end));
+        }
+    }
+    points
+        .iter()
+        .zip(keep)
+        .filter_map(|(&point, keep)| keep.then_some(point))
+        .collect()
+}
+
+impl MapResult {
+    /// Corner coordinates to world units.
+    pub(crate) fn corner_to_world(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        (
+            x / self.width as f32 * REGION_SIZE,
+            y / self.height as f32 * REGION_SIZE,
+        )
+    }
+
+    /// Shorelines between ocean and everything else, in world units and
+    /// simplified to `tolerance` world units.
+    pub(crate) fn coastline_contours(&self, tolerance: f32) -> Vec<Contour> {
+        let ocean = Biome::Ocean.code();
+        let labels: Vec<u32> = self
+            .biome
+            .iter()
+            .map(|&biome| (biome == ocean) as u32)
+            .collect();
+        interfaces(
+            &labels,
+            self.width as usize,
+            self.height as usize,
+            |_, _| true,
+        )
+        .into_values()
+        .flatten()
+        .map(|contour| {
+            let world: Vec<_> = contour
+                .points
+                .iter()
+                .map(|&point| self.corner_to_world(point))
+                .collect();
+            Contour {
+                points: simplify(&world, tolerance, contour.closed),
+                closed: contour.closed,
+            }
+        })
+        .collect()
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Ocean shorelines as `{ closed, points }` with interleaved world `x, y`
+    /// points, simplified so no traced point lies more than
+    /// `options.tolerance` (default 4) world units off the line. Coasts
+    /// that go all the way round come back closed; coasts running off the
+    /// map are open.
+    pub fn coastlines(&self, options: JsValue) -> JsValue {
+        let tolerance = js::get_f32(&options, "tolerance", 4.0).max(0.0);
+        let records = self
+            .coastline_contours(tolerance)
+            .iter()
+            .map(|contour| {
+                Json::object()
+                    .with("closed", contour.closed)
+                    .with("points", flat_points(&contour.points))
+            })
+            .collect::<Vec<_>>();
+        Json::from(records).to_js()
+    }
+}
+
+pub(crate) fn flat_points(points: &[(f32, f32)]) -> Json {
+    points
+        .iter()
+        .flat_map(|&(x, y)| [Json::from(x), Json::from(y)])
+        .collect::<Vec<_>>()
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{interfaces, simplify};
+
+    #[test]
+    fn staircase_interfaces_trace_straight_and_loops_close() {
+        // Label 1 below the diagonal, 2 above, with a square of 3 inside 1.
+        let size = 16;
+        let labels: Vec<u32> = (0..size * size)
+            .map(|index| {
+                let (x, y) = (index % size, index / size);
+                if (10..13).contains(&x) && (2..5).contains(&y) {
+                    3
+                } else if x > y {
+                    1
+                } else {
+                    2
+                }
+            })
+            .collect();
+        let traced = interfaces(&labels, size, size, |_, _| true);
+        let diagonal = &traced[&(1, 2)];
+        assert_eq!(diagonal.len(), 1);
+        assert!(!diagonal[0].closed);
+        // Side midpoints lie half a cell's diagonal off the line joining the
+        // end corners, and nothing zigzags further.
+        let line = simplify(&diagonal[0].points, 0.5, false);
+        assert_eq!(line, vec![(1.0, 0.0), (size as f32, size as f32 - 1.0)]);
+
+        let square = &traced[&(1, 3)];
+        assert_eq!(square.len(), 1);
+        assert!(square[0].closed);
+        assert_eq!(square[0].points.len(), 12);
+        let ring = simplify(&square[0].points, 0.1, true);
+        assert!(ring.len() >= 4 && ring.len() <= 8, "{ring:?}");
+    }
+}
@@ -0,0 +1,202 @@
+#[cfg(feature = "wasm")]
+use js_sys::Float32Array;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::biome::Biome;
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::{slope_map, MapResult};
+
+/// How the three covers are brought under a sum of 1.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Normalization {
+    /// Snow first, then rock, then sand, each limited to what is left.
+    Priority,
+    /// All three scaled down together when they exceed 1.
+    Proportional,
+}
+
+pub(crate) struct CoverOptions {
+    /// Temperatures from full snow to none; colder cells are covered.
+    pub snow_temperature: (f32, f32),
+    /// Slopes from no bare rock to full.
+    pub rock_slope: (f32, f32),
+    /// Normalized roughness from no bare rock to full.
+    pub rock_roughness: (f32, f32),
+    /// Sand on desert cells; beaches are all sand.
+    pub desert_sand: f32,
+    /// Least snow plus rock on alpine cells, topped up with rock.
+    pub alpine_floor: f32,
+    pub normalization: Normalization,
+}
+
+impl Default for CoverOptions {
+    fn default() -> Self {
+        Self {
+            snow_temperature: (0.1, 0.25),
+            rock_slope: (0.1, 0.3),
+            rock_roughness: (0.4, 0.8),
+            desert_sand: 0.8,
+            alpine_floor: 0.6,
+            normalization: Normalization::Priority,
+        }
+    }
+}
+
+impl CoverOptions {
+    #[cfg(feature = "wasm")]
+    fn from_js(options: &JsValue) -> Result<Self, JsValue> {
+        let defaults = Self::default();
+        let range = |key_min: &str, key_max: &str, (min, max): (f32, f32)| {
+            (
+                js::get_f32(options, key_min, min),
+                js::get_f32(options, key_max, max),
+            )
+        };
+        let normalization = match js::get_string(options, "normalization").as_deref() {
+            None | Some("priority") => Normalization::Priority,
+            Some("proportional") => Normalization::Proportional,
+            Some(other) => {
+                return Err(JsValue::from_str(&format!(
+                    "unknown normalization: {other}"
+                )))
+            }
+        };
+        Ok(Self {
+            snow_temperature: range("snow_full", "snow_none", defaults.snow_temperature),
+            rock_slope: range("rock_slope_min", "rock_slope_max", defaults.rock_slope),
+            rock_roughness: range(
+                "rock_roughness_min",
+                "rock_roughness_max",
+                defaults.rock_roughness,
+            ),
+            desert_sand: js::get_f32(options, "desert_sand", defaults.desert_sand).clamp(0.0, 1.0),
+            alpine_floor: js::get_f32(options, "alpine_floor", defaults.alpine_floor)
+                .clamp(0.0, 1.0),
+            normalization,
+        })
+    }
+}
+
+/// Snow, bare rock, and sand fractions per cell, summing to at most 1.
+pub(crate) struct Cover {
+    pub snow: Vec<f32>,
+    pub rock: Vec<f32>,
+    pub sand: Vec<f32>,
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// One material cover layer, `"snow"`, `"rock"`, or `"sand"`, as 0..1 per
+    /// cell. The three sum to at most 1, the rest being vegetation or soil,
+    /// and are zero on oceans and lakes. Snow ramps from `snow_full` (default
+    /// temperature 0.1) to `snow_none` (0.25); rock is the larger of ramps
+    /// over slope (`rock_slope_min` 0.1 to `rock_slope_max` 0.3) and
+    /// roughness (`rock_roughness_min` 0.4 to `rock_roughness_max` 0.8);
+    /// sand covers beaches and `desert_sand` (default 0.8) of deserts.
+    /// Alpine cells keep at least `alpine_floor` (default 0.6) of snow plus
+    /// rock. `normalization` is `"priority"` (default; snow, then rock, then
+    /// sand take what is left) or `"proportional"`. Computed per call and not
+    /// kept, so unused layers cost no memory.
+    pub fn cover_layer(&self, layer: &str, options: JsValue) -> Result<Float32Array, JsValue> {
+        let options = CoverOptions::from_js(&options)?;
+        let cover = cover(self, &options);
+        let values = match layer {
+            "snow" => cover.snow,
+            "rock" => cover.rock,
+            "sand" => cover.sand,
+            other => return Err(JsValue::from_str(&format!("unknown cover layer: {other}"))),
+        };
+        Ok(Float32Array::from(values.as_slice()))
+    }
+}
+
+pub(crate) fn cover(map: &MapResult, options: &CoverOptions) -> Cover {
+    let size = map.heightmap.len();
+    let slopes = slope_map(&map.heightmap, map.width as usize, map.height as usize);
+    let roughness = map.detail_roughness();
+    let mut cover = Cover {
+        snow: vec![0.0; size],
+        rock: vec![0.0; size],
+        sand: vec![0.0; size],
+    };
+    for index in 0..size {
+        if map.is_water_body(index) {
+            continue;
+        }
+        let biome = Biome::from_code(map.biome[index]);
+        let (full, none) = options.snow_temperature;
+        let mut snow = 1.0 - ramp(map.temperature[index], full, none);
+        let mut rock = ramp(slopes[index], options.rock_slope.0, options.rock_slope.1).max(ramp(
+            roughness[index],
+            options.rock_roughness.0,
+            options.rock_roughness.1,
+        ));
+        let mut sand = match biome {
+            Some(Biome::Beach) => 1.0,
+            Some(Biome::Desert) => options.desert_sand,
+            _ => 0.0,
+        };
+        if biome == Some(Biome::Alpine) {
+            rock = rock.max(options.alpine_floor - snow);
+        }
+        match options.normalization {
+            Normalization::Priority => {
+                rock = rock.min(1.0 - snow);
+                sand = sand.min(1.0 - snow - rock);
+            }
+            Normalization::Proportional => {
+                let total = snow + rock + sand;
+                if total > 1.0 {
+                    snow /= total;
+                    rock /= total;
+                    sand /= total;
+                }
+            }
+        }
+        cover.snow[index] = snow;
+        cover.rock[index] = rock;
+        cover.sand[index] = sand.max(0.0);
+    }
+    cover
+}
+
+/// 0 at or below `from`, 1 at or above `to`, linear between.
+fn ramp(value: f32, from: f32, to: f32) -> f32 {
+    if to <= from {
+        return if value >= to { 1.0 } else { 0.0 };
+    }
+    ((value - from) / (to - from)).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cover, CoverOptions, Normalization};
+    use crate::biome::Biome;
+    use crate::generate_map;
+
+    #[test]
+    fn covers_stay_normalized_and_match_biomes() {
+        let map = generate_map(96, 96, 21, 0.42, 1.4, 40.0, 2, 1.0);
+        for normalization in [Normalization::Priority, Normalization::Proportional] {
+            let options = CoverOptions {
+                normalization,
+                ..CoverOptions::default()
+            };
+            let cover = cover(&map, &options);
+            for index in 0..map.biome.len() {
+                let (snow, rock, sand) = (cover.snow[index], cover.rock[index], cover.sand[index]);
+                assert!(snow >= 0.0 && rock >= 0.0 && sand >= 0.0);
+                assert!(snow + rock + sand <= 1.0 + 1e-5);
+                match Biome::from_code(map.biome[index]) {
+                    Some(Biome::Alpine) => assert!(snow + rock >= 0.5, "{snow} {rock}"),
+                    Some(Biome::Ocean | Biome::Lake) => assert_eq!(snow + rock + sand, 0.0),
+                    _ => {}
+                }
+            }
+            assert!(cover.snow.iter().any(|&snow| snow > 0.0));
+        }
+    }
+}
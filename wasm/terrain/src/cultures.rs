@@ -0,0 +1,513 @@
+use std::collections::BinaryHeap;
+
+#[cfg(feature = "wasm")]
+use js_sys::{Object, Uint8Array};
+use noise::{NoiseFn, OpenSimplex};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::biome::{Biome, BIOMES};
+use crate::geometry::distance;
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::json::Json;
+use crate::naming::{self, styled_name, Syllables};
+use crate::pathfinding::Frontier;
+use crate::{slope_map, MapResult, DIRECTIONS, REGION_SIZE};
+
+/// Layer value for water and unclaimed cells.
+pub(crate) const NO_CULTURE: u8 = u8::MAX;
+/// Upper bound on `count`, so every culture id stays below `NO_CULTURE`.
+pub(crate) const MAX_CULTURES: u32 = 254;
+/// Culture boundaries wander over features about this many per map side.
+const BORDER_NOISE_FREQUENCY: f64 = 4.0;
+/// How much the border noise stretches or shrinks travel cost.
+const BORDER_NOISE_AMPLITUDE: f32 = 0.6;
+/// Extra cost per unit of slope, so ranges tend to become borders.
+const SLOPE_RESISTANCE: f32 = 6.0;
+/// Cost multiplier for spreading across water.
+const WATER_RESISTANCE: f32 = 6.0;
+/// How strongly a preferred biome speeds a culture's spread.
+const AFFINITY_WEIGHT: f32 = 1.5;
+/// World distance from the ocean that still counts as coastal.
+const COASTAL_REACH: f32 = 64.0;
+/// Cells around a capital, per side, averaged when matching it to a culture.
+const CAPITAL_NEIGHBORHOOD: usize = 4;
+
+/// One entry in the culture list: a naming style and where it thrives.
+#[derive(Clone)]
+pub(crate) struct CultureDef {
+    pub style: &'static Syllables,
+    /// Affinity for land within `COASTAL_REACH` of the ocean.
+    pub coastal: f32,
+    /// Affinity per biome code.
+    pub biomes: [f32; BIOMES.len()],
+}
+
+impl CultureDef {
+    fn new(key: &str, coastal: f32, biomes: &[(Biome, f32)]) -> Self {
+        let mut weights = [0.0; BIOMES.len()];
+        for &(biome, weight) in biomes {
+            weights[biome.code() as usize] = weight;
+        }
+        Self {
+            style: naming::style(key).expect("built-in cultures use known styles"),
+            coastal,
+            biomes: weights,
+        }
+    }
+
+    /// Built-in list, one culture per naming style.
+    fn defaults() -> Vec<Self> {
+        vec![
+            CultureDef::new(
+                "northern",
+                0.0,
+                &[(Biome::Tundra, 1.0), (Biome::BorealForest, 1.0)],
+            ),
+            CultureDef::new("coastal", 1.5, &[(Biome::Beach, 0.5)]),
+            CultureDef::new(
+                "steppe",
+                0.0,
+                &[
+                    (Biome::TemperateGrassland, 1.0),
+                    (Biome::Savanna, 1.0),
+                    (Biome::Steppe, 1.0),
+                ],
+            ),
+            CultureDef::new(
+                "sylvan",
+                0.0,
+                &[
+                    (Biome::TemperateForest, 1.0),
+                    (Biome::TropicalForest, 0.5),
+                    (Biome::BorealForest, 0.5),
+                ],
+            ),
+            CultureDef::new(
+                "highland",
+                0.0,
+                &[(Biome::Alpine, 1.5), (Biome::Tundra, 0.3)],
+            ),
+            CultureDef::new(
+                "desert",
+                0.0,
+                &[(Biome::Desert, 1.5), (Biome::Savanna, 0.5)],
+            ),
+        ]
+    }
+
+    fn affinity(&self, map: &MapResult, coastal: &[bool], index: usize) -> f32 {
+        let biome = self
+            .biomes
+            .get(map.biome[index] as usize)
+            .copied()
+            .unwrap_or(0.0);
+        biome + if coastal[index] { self.coastal } else { 0.0 }
+    }
+}
+
+pub(crate) struct CultureOptions {
+    /// Cultures placed, at most one per entry in `cultures` and at most
+    /// `MAX_CULTURES`.
+    pub count: u32,
+    pub cultures: Vec<CultureDef>,
+    /// Whether biome and coast affinities steer capitals and spread.
+    pub affinities: bool,
+}
+
+impl Default for CultureOptions {
+    fn default() -> Self {
+        Self {
+            count: 4,
+            cultures: CultureDef::defaults(),
+            affinities: true,
+        }
+    }
+}
+
+impl CultureOptions {
+    #[cfg(feature = "wasm")]
+    fn from_js(options: &JsValue) -> Result<Self, JsValue> {
+        let defaults = Self::default();
+        let cultures = match js::get_array(options, "cultures") {
+            None => defaults.cultures,
+            Some(list) => list
+                .iter()
+                .map(|entry| {
+                    let key = js::get_string(&entry, "style").unwrap_or_default();
+                    let style = naming::style(&key).ok_or_else(|| {
+                        JsValue::from_str(&format!("unknown culture style: {key}"))
+                    })?;
+                    let mut biomes = [0.0; BIOMES.len()];
+                    if let Some(weights) = js::get(&entry, "biomes") {
+                        for biome in BIOMES {
+                            biomes[biome.code() as usize] = js::get_f32(&weights, biome.key(), 0.0);
+                        }
+                    }
+                    Ok(CultureDef {
+                        style,
+                        coastal: js::get_f32(&entry, "coastal", 0.0),
+                        biomes,
+                    })
+                })
+                .collect::<Result<Vec<_>, JsValue>>()?,
+        };
+        if cultures.is_empty() {
+            return Err(JsValue::from_str("cultures must not be empty"));
+        }
+        Ok(Self {
+            count: js::get_u32(options, "count", defaults.count).clamp(1, MAX_CULTURES),
+            cultures,
+            affinities: js::get_bool(options, "affinities", defaults.affinities),
+        })
+    }
+}
+
+pub(crate) struct Culture {
+    pub def: CultureDef,
+    pub name: String,
+    pub capital: usize,
+    pub cells: u32,
+}
+
+pub(crate) struct CultureMap {
+    pub cultures: Vec<Culture>,
+    /// Culture per cell, including the water it spread across.
+    pub owner: Vec<u8>,
+    /// Culture per entry in `MapResult::settlements`.
+    pub settlements: Vec<u8>,
+}
+
+impl CultureMap {
+    /// `owner` with water masked to `NO_CULTURE`, for border rendering.
+    pub(crate) fn layer(&self, map: &MapResult) -> Vec<u8> {
+        self.owner
+            .iter()
+            .enumerate()
+            .map(|(index, &owner)| {
+                if map.is_water_body(index) {
+                    NO_CULTURE
+                } else {
+                    owner
+                }
+            })
+            .collect()
+    }
+
+    pub(crate) fn settlement_name(&self, map: &MapResult, position: usize) -> Option<String> {
+        if let Some(name) = &map.settlements[position].name {
+            return Some(name.clone());
+        }
+        let culture = self.cultures.get(self.settlements[position] as usize)?;
+        let id = map.settlements[position].id;
+        Some(styled_name(culture.def.style, map.settings.seed, id))
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Spatially coherent cultures grown from capitals: `{ cultures, layer,
+    /// settlements }` with cultures `{ id, name, style, capital_x, capital_y,
+    /// cells }`, a per-cell `Uint8Array` of culture ids (255 on water), and
+    /// settlements `{ id, culture, name }` named in their culture's style.
+    /// Options: `count` (default 4, at most 254), `affinities` (default
+    /// true), and `cultures`, a list of `{ style, coastal, biomes }` where
+    /// `style` is one of `"northern"`, `"coastal"`, `"steppe"`, `"sylvan"`,
+    /// `"highland"`, or `"desert"` and `biomes` maps biome ids to affinity
+    /// weights.
+    pub fn cultures(&self, options: JsValue) -> Result<Object, JsValue> {
+        let culture_map = cultures(self, &CultureOptions::from_js(&options)?);
+        let records = culture_map
+            .cultures
+            .iter()
+            .enumerate()
+            .map(|(id, culture)| {
+                let (x, y) = self.cell_to_world(culture.capital);
+                Json::object()
+                    .with("id", id as u32)
+                    .with("name", culture.name.as_str())
+                    .with("style", culture.def.style.key)
+                    .with("capital_x", x)
+                    .with("capital_y", y)
+                    .with("cells", culture.cells)
+            })
+            .collect::<Vec<_>>();
+        let settlements = (0..self.settlements.len())
+            .map(|position| {
+                Json::object()
+                    .with("id", self.settlements[position].id)
+                    .with("culture", culture_map.settlements[position] as u32)
+                    .with(
+                        "name",
+                        culture_map
+                            .settlement_name(self, position)
+                            .map_or(Json::Null, Json::from),
+                    )
+            })
+            .collect::<Vec<_>>();
+        let result = Object::new();
+        js::set(&result, "cultures", &Json::from(records).to_js());
+        js::set(
+            &result,
+            "layer",
+            &Uint8Array::from(culture_map.layer(self).as_slice()).into(),
+        );
+        js::set(&result, "settlements", &Json::from(settlements).to_js());
+        Ok(result)
+    }
+}
+
+/// Picks capitals far apart, matches each to the best-suited unused culture,
+/// then grows all cultures at once with a multi-source Dijkstra whose step
+/// cost rises with slope, water, and low-frequency noise and falls where the
+/// spreading culture has an affinity. Every culture's cells stay connected.
+pub(crate) fn cultures(map: &MapResult, options: &CultureOptions) -> CultureMap {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let coastal: Vec<bool> = map
+        .coast_distance()
+        .iter()
+        .enumerate()
+        .map(|(index, &distance)| !map.is_water_body(index) && distance <= COASTAL_REACH)
+        .collect();
+    let count = options.count.min(MAX_CULTURES) as usize;
+    let capitals = pick_capitals(map, count.min(options.cultures.len()));
+
+    let mut unused: Vec<usize> = (0..options.cultures.len()).collect();
+    let mut cultures = Vec::with_capacity(capitals.len());
+    for &capital in &capitals {
+        let fit = |def: &CultureDef| {
+            if !options.affinities {
+                return 0.0;
+            }
+            let (cx, cy) = (capital % width, capital / width);
+            let mut total = 0.0;
+            let mut count = 0.0;
+            for y in cy.saturating_sub(CAPITAL_NEIGHBORHOOD)
+                ..=(cy + CAPITAL_NEIGHBORHOOD).min(height - 1)
+            {
+                for x in cx.saturating_sub(CAPITAL_NEIGHBORHOOD)
+                    ..=(cx + CAPITAL_NEIGHBORHOOD).min(width - 1)
+                {
+                    total += def.affinity(map, &coastal, y * width + x);
+                    count += 1.0;
+                }
+            }
+            total / count
+        };
+        // Ties keep list order, so an affinity-free list is used front to back.
+        let slot = (0..unused.len())
+            .max_by(|&a, &b| {
+                fit(&options.cultures[unused[a]])
+                    .total_cmp(&fit(&options.cultures[unused[b]]))
+                    .then(b.cmp(&a))
+            })
+            .expect("one culture per capital");
+        let def = options.cultures[unused.remove(slot)].clone();
+        let id = cultures.len() as u32;
+        cultures.push(Culture {
+            name: styled_name(def.style, map.settings.seed, 0x4000_0000 | id),
+            def,
+            capital,
+            cells: 0,
+        });
+    }
+
+    let slopes = slope_map(&map.heightmap, width, height);
+    let noise = OpenSimplex::new(map.settings.seed.wrapping_add(829));
+    let resistance: Vec<f32> = (0..map.heightmap.len())
+        .map(|index| {
+            let (x, y) = map.cell_to_world(index);
+            let wobble = noise.get([
+                x as f64 / REGION_SIZE as f64 * BORDER_NOISE_FREQUENCY,
+                y as f64 / REGION_SIZE as f64 * BORDER_NOISE_FREQUENCY,
+            ]) as f32;
+            let water = if map.is_water_body(index) {
+                WATER_RESISTANCE
+            } else {
+                1.0
+            };
+            (1.0 + slopes[index] * SLOPE_RESISTANCE)
+                * (1.0 + BORDER_NOISE_AMPLITUDE * (wobble + 0.5).clamp(0.0, 1.0))
+                * water
+        })
+        .collect();
+
+    let cell_w = REGION_SIZE / width as f32;
+    let cell_h = REGION_SIZE / height as f32;
+    let mut best = vec![f32::INFINITY; map.heightmap.len()];
+    let mut owner = vec![NO_CULTURE; map.heightmap.len()];
+    let mut heap = BinaryHeap::new();
+    for (id, culture) in cultures.iter().enumerate() {
+        best[culture.capital] = 0.0;
+        owner[culture.capital] = id as u8;
+        heap.push(Frontier {
+            priority: 0.0,
+            index: culture.capital,
+        });
+    }
+    while let Some(Frontier { priority, index }) = heap.pop() {
+        if priority > best[index] {
+            continue;
+        }
+        let def = &cultures[owner[index] as usize].def;
+        let (x, y) = ((index % width) as i32, (index / width) as i32);
+        for (dx, dy) in DIRECTIONS {
+            let (nx, ny) = (x + dx, y + dy);
+            if nx < 0 || ny < 0 || nx >= width as i32 || ny >= height as i32 {
+                continue;
+            }
+            let next = ny as usize * width + nx as usize;
+            let affinity = if options.affinities {
+                def.affinity(map, &coastal, next)
+            } else {
+                0.0
+            };
+            let step = (dx as f32 * cell_w).hypot(dy as f32 * cell_h);
+            let candidate = priority
+                + step * (resistance[index] + resistance[next]) * 0.5
+                    / (1.0 + AFFINITY_WEIGHT * affinity);
+            if candidate < best[next] {
+                best[next] = candidate;
+                owner[next] = owner[index];
+                heap.push(Frontier {
+                    priority: candidate,
+                    index: next,
+                });
+            }
+        }
+    }
+
+    for (index, &id) in owner.iter().enumerate() {
+        if id != NO_CULTURE && !map.is_water_body(index) {
+            cultures[id as usize].cells += 1;
+        }
+    }
+    let settlements = map
+        .settlements
+        .iter()
+        .map(|settlement| {
+            let (x, y) = map.nearest_cell(settlement.x, settlement.y);
+            owner[y * width + x]
+        })
+        .collect();
+    CultureMap {
+        cultures,
+        owner,
+        settlements,
+    }
+}
+
+/// Up to `count` capital cells, spread out by farthest-point sampling: the
+/// largest settlement first, then whichever candidate is farthest from every
+/// capital so far. Maps with too few settlements borrow land cells on a
+/// coarse lattice.
+fn pick_capitals(map: &MapResult, count: usize) -> Vec<usize> {
+    let width = map.width as usize;
+    let mut candidates: Vec<(usize, f32)> = map
+        .settlements
+        .iter()
+        .map(|settlement| {
+            let (x, y) = map.nearest_cell(settlement.x, settlement.y);
+            (y * width + x, settlement.size)
+        })
+        .collect();
+    if candidates.len() < count {
+        let stride = (map.width.max(map.height) as usize / 16).max(1);
+        candidates.extend(
+            (0..map.heightmap.len())
+                .filter(|&index| {
+                    (index % width).is_multiple_of(stride) && (index / width).is_multiple_of(stride)
+                })
+                .filter(|&index| !map.is_water_body(index))
+                .map(|index| (index, 0.0)),
+        );
+    }
+    let mut capitals: Vec<usize> = Vec::with_capacity(count);
+    let Some(&(first, _)) = candidates
+        .iter()
+        .max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.cmp(&a.0)))
+    else {
+        return capitals;
+    };
+    capitals.push(first);
+    while capitals.len() < count {
+        let spread = |index: usize| {
+            let (x, y) = map.cell_to_world(index);
+            capitals
+                .iter()
+                .map(|&capital| distance(map.cell_to_world(capital), (x, y)))
+                .fold(f32::INFINITY, f32::min)
+        };
+        let Some(&(next, _)) = candidates
+            .iter()
+            .filter(|(index, _)| !capitals.contains(index))
+            .max_by(|a, b| {
+                spread(a.0)
+                    .total_cmp(&spread(b.0))
+                    .then(a.1.total_cmp(&b.1))
+                    .then(b.0.cmp(&a.0))
+            })
+        else {
+            break;
+        };
+        capitals.push(next);
+    }
+    capitals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cultures, CultureOptions, MAX_CULTURES, NO_CULTURE};
+    use crate::biome::Biome;
+    use crate::features::components;
+    use crate::generate_map;
+
+    #[test]
+    fn same_seed_gives_same_cultures() {
+        let first = generate_map(96, 96, 5, 0.42, 1.0, 40.0, 2, 1.0);
+        let second = generate_map(96, 96, 5, 0.42, 1.0, 40.0, 2, 1.0);
+        let options = CultureOptions::default();
+        let (a, b) = (cultures(&first, &options), cultures(&second, &options));
+        assert_eq!(a.owner, b.owner);
+        assert_eq!(a.settlements, b.settlements);
+        let names = |map, culture_map: &super::CultureMap| {
+            (0..first.settlements.len())
+                .map(|position| culture_map.settlement_name(map, position))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&first, &a), names(&second, &b));
+    }
+
+    #[test]
+    fn each_culture_is_one_connected_region() {
+        let map = generate_map(96, 96, 9, 0.42, 1.0, 40.0, 2, 1.0);
+        let culture_map = cultures(&map, &CultureOptions::default());
+        assert!(culture_map.cultures.len() > 1);
+        assert!(culture_map.owner.iter().all(|&owner| owner != NO_CULTURE));
+        for id in 0..culture_map.cultures.len() as u8 {
+            let mask: Vec<bool> = culture_map.owner.iter().map(|&owner| owner == id).collect();
+            assert_eq!(components(&mask, 96, 96).len(), 1, "culture {id}");
+        }
+    }
+
+    #[test]
+    fn culture_ids_stay_below_the_water_sentinel() {
+        // All land, so there are more capital candidates than ids.
+        let mut map = generate_map(96, 96, 9, 0.42, 1.0, 40.0, 0, 1.0);
+        map.biome.fill(Biome::TemperateGrassland.code());
+        map.water.fill(0.0);
+        map.invalidate_terrain();
+        let defaults = CultureOptions::default();
+        let options = CultureOptions {
+            count: 1000,
+            cultures: vec![defaults.cultures[0].clone(); 300],
+            ..defaults
+        };
+        let culture_map = cultures(&map, &options);
+        assert_eq!(culture_map.cultures.len(), MAX_CULTURES as usize);
+        assert!(culture_map.owner.iter().all(|&owner| owner < NO_CULTURE));
+    }
+}
@@ -0,0 +1,271 @@
+#[cfg(feature = "wasm")]
+use js_sys::Float32Array;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::biome::Biome;
+use crate::distance::chamfer_distance;
+use crate::features::components;
+use crate::{MapResult, REGION_SIZE};
+
+/// Ocean pieces smaller than this many cells are too small for a gyre and
+/// stay still.
+const MIN_BASIN_CELLS: usize = 64;
+/// East-west stretches of open water narrower than this many world units
+/// carry proportionally weaker currents.
+const FULL_GYRE_WIDTH: f32 = 256.0;
+/// Exponent on the position across the basin; below 1 it squeezes the
+/// poleward western boundary current into a narrow band and spreads the
+/// equatorward return flow across the rest.
+const WESTERN_INTENSIFICATION: f32 = 0.5;
+/// Temperature change carried by the strongest poleward or equatorward
+/// current.
+const CURRENT_WARMTH: f32 = 0.08;
+/// World units inland that a current still warms or cools.
+const COASTAL_REACH: f32 = 96.0;
+
+/// Per-cell surface current, zero off the ocean. Speeds are at most 1.0,
+/// the strongest boundary current; `v` grows downward, matching cell rows.
+pub(crate) struct CurrentField {
+    pub u: Vec<f32>,
+    pub v: Vec<f32>,
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Ocean surface currents as interleaved `u, v` per cell, on the same
+    /// layout as `wind()`. Each ocean basin turns one gyre per hemisphere:
+    /// clockwise in the north, counterclockwise in the south, with a narrow
+    /// poleward current along its western edge. Speeds top out at 1.0;
+    /// land and small enclosed seas hold 0.
+    pub fn currents(&self) -> Float32Array {
+        let field = self.current_field();
+        let interleaved: Vec<f32> = field
+            .u
+            .iter()
+            .zip(&field.v)
+            .flat_map(|(&u, &v)| [u, v])
+            .collect();
+        Float32Array::from(interleaved.as_slice())
+    }
+}
+
+impl MapResult {
+    /// Lazily traced currents; dropped by `invalidate_terrain`.
+    pub(crate) fn current_field(&self) -> &CurrentField {
+        self.cache.currents.get_or_init(|| {
+            let ocean: Vec<bool> = self
+                .biome
+                .iter()
+                .map(|&biome| biome == Biome::Ocean.code())
+                .collect();
+            ocean_currents(&ocean, self.width as usize, self.height as usize)
+        })
+    }
+}
+
+/// Gyres for every ocean component, split at the equator (`y == height / 2`).
+/// Within a basin the zonal flow follows latitude: eastward on the poleward
+/// side, westward on the equatorward side. The meridional flow follows each
+/// row's run of open water: poleward near its western shore, equatorward
+/// across the rest.
+pub(crate) fn ocean_currents(ocean: &[bool], width: usize, height: usize) -> CurrentField {
+    let mut u = vec![0.0f32; ocean.len()];
+    let mut v = vec![0.0f32; ocean.len()];
+    let cell_w = REGION_SIZE / width as f32;
+    let equator = height / 2;
+
+    for component in components(ocean, width, height) {
+        for northern in [true, false] {
+            let basin: Vec<usize> = component
+                .iter()
+                .copied()
+                .filter(|&index| (index / width < equator) == northern)
+                .collect();
+            if basin.len() < MIN_BASIN_CELLS {
+                continue;
+            }
+            let mut in_basin = vec![false; ocean.len()];
+            for &index in &basin {
+                in_basin[index] = true;
+            }
+            let (top, bottom) = basin.iter().fold((usize::MAX, 0), |(top, bottom), &index| {
+                (top.min(index / width), bottom.max(index / width))
+            });
+            let center = (top + bottom) as f32 * 0.5;
+            let half_span = ((bottom - top) as f32 * 0.5).max(1.0);
+            // Rows grow toward the south pole.
+            let toward_pole = if northern { -1.0 } else { 1.0 };
+
+            for y in top..=bottom {
+                let zonal = ((y as f32 - center) * toward_pole / half_span).clamp(-1.0, 1.0);
+                let mut x = 0;
+                while x < width {
+                    if !in_basin[y * width + x] {
+                        x += 1;
+                        continue;
+                    }
+                    let start = x;
+                    while x < width && in_basin[y * width + x] {
+                        x += 1;
+                    }
+                    let run = x - start;
+                    let strength = (run as f32 * cell_w / FULL_GYRE_WIDTH).min(1.0);
+                    for cx in start..x {
+                        let across = (cx - start) as f32 / (run.max(2) - 1) as f32;
+                        let meridional =
+                            (std::f32::consts::PI * across.powf(WESTERN_INTENSIFICATION)).cos();
+                        let (mut cu, mut cv) = (zonal, meridional * toward_pole);
+                        let speed = cu.hypot(cv);
+                        if speed > 1.0 {
+                            cu /= speed;
+                            cv /= speed;
+                        }
+                        let index = y * width + cx;
+                        u[index] = cu * strength;
+                        v[index] = cv * strength;
+                    }
+                }
+            }
+        }
+    }
+    CurrentField { u, v }
+}
+
+/// Warms coasts washed by poleward currents and cools those washed by
+/// equatorward ones. Each cell takes the mean anomaly of the ocean within
+/// `COASTAL_REACH`, fading with its distance from the sea. Ocean cells
+/// follow `classify_cell`'s rule, since biomes are not known yet.
+pub(crate) fn apply_current_temperature(
+    heightmap: &[f32],
+    temperature: &mut [f32],
+    width: usize,
+    height: usize,
+    sea_level: f32,
+) {
+    let ocean: Vec<bool> = heightmap
+        .iter()
+        .map(|&elevation| elevation <= sea_level - 0.02)
+        .collect();
+    let currents = ocean_currents(&ocean, width, height);
+    let equator = height / 2;
+    let anomaly: Vec<f32> = (0..ocean.len())
+        .map(|index| {
+            let toward_pole = if index / width < equator { -1.0 } else { 1.0 };
+            currents.v[index] * toward_pole * CURRENT_WARMTH
+        })
+        .collect();
+    let distance = chamfer_distance(&ocean, width, height);
+    let cell_w = REGION_SIZE / width as f32;
+    let cell_h = REGION_SIZE / height as f32;
+    let reach_x = (COASTAL_REACH / cell_w).ceil() as usize;
+    let reach_y = (COASTAL_REACH / cell_h).ceil() as usize;
+    let sums = summed_area(&anomaly, width, height);
+    let counts = summed_area(
+        &ocean
+            .iter()
+            .map(|&ocean| ocean as u8 as f32)
+            .collect::<Vec<_>>(),
+        width,
+        height,
+    );
+    for y in 0..height {
+        for x in 0..width {
+            let index = y * width + x;
+            let fade = 1.0 - distance[index] / COASTAL_REACH;
+            if fade <= 0.0 {
+                continue;
+            }
+            let rect = (
+                x.saturating_sub(reach_x),
+                y.saturating_sub(reach_y),
+                (x + reach_x).min(width - 1),
+                (y + reach_y).min(height - 1),
+            );
+            let count = window(&counts, width, rect);
+            if count < 1.0 {
+                continue;
+            }
+            let mean = window(&sums, width, rect) / count;
+            temperature[index] = (temperature[index] + mean * fade).clamp(0.0, 1.0);
+        }
+    }
+}
+
+/// Inclusive prefix sums with a zero row and column in front.
+fn summed_area(values: &[f32], width: usize, height: usize) -> Vec<f32> {
+    let stride = width + 1;
+    let mut table = vec![0.0f32; stride * (height + 1)];
+    for y in 0..height {
+        for x in 0..width {
+            table[(y + 1) * stride + x + 1] =
+                values[y * width + x] + table[y * stride + x + 1] + table[(y + 1) * stride + x]
+                    - table[y * stride + x];
+        }
+    }
+    table
+}
+
+/// Sum over the inclusive cell rectangle `(x0, y0, x1, y1)`.
+fn window(table: &[f32], width: usize, (x0, y0, x1, y1): (usize, usize, usize, usize)) -> f32 {
+    let stride = width + 1;
+    table[(y1 + 1) * stride + x1 + 1] - table[y0 * stride + x1 + 1] - table[(y1 + 1) * stride + x0]
+        + table[y0 * stride + x0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_current_temperature, ocean_currents};
+
+    /// 128×128 ocean with a continent over the middle third of the columns,
+    /// stretching well into both hemispheres.
+    fn continent() -> (Vec<f32>, usize, usize) {
+        let (width, height) = (128, 128);
+        let heightmap = (0..width * height)
+            .map(|index| {
+                let (x, y) = (index % width, index / width);
+                if (43..85).contains(&x) && (12..116).contains(&y) {
+                    0.6
+                } else {
+                    0.2
+                }
+            })
+            .collect();
+        (heightmap, width, height)
+    }
+
+    #[test]
+    fn western_boundary_current_runs_poleward() {
+        let (heightmap, width, height) = continent();
+        let ocean: Vec<bool> = heightmap.iter().map(|&h| h < 0.4).collect();
+        let currents = ocean_currents(&ocean, width, height);
+        let row = 32 * width;
+        // Just east of the continent is the western edge of the eastern sea.
+        assert!(currents.v[row + 86] < 0.0);
+        // Just west of it is the eastern edge of the western sea.
+        assert!(currents.v[row + 41] > 0.0);
+        assert!(currents
+            .u
+            .iter()
+            .zip(&currents.v)
+            .all(|(u, v)| u.hypot(*v) <= 1.0 + 1e-6));
+    }
+
+    #[test]
+    fn east_and_west_coasts_differ_in_temperature() {
+        let (heightmap, width, height) = continent();
+        let mut temperature = vec![0.5f32; heightmap.len()];
+        apply_current_temperature(&heightmap, &mut temperature, width, height, 0.42);
+        for y in [32, 96] {
+            let west_coast = temperature[y * width + 43];
+            let east_coast = temperature[y * width + 84];
+            assert!(
+                east_coast - west_coast > 0.02,
+                "row {y}: west {west_coast}, east {east_coast}"
+            );
+        }
+        // Far inland nothing changes.
+        assert_eq!(temperature[64 * width + 64], 0.5);
+    }
+}
@@ -0,0 +1,191 @@
+#[cfg(feature = "wasm")]
+use js_sys::Float32Array;
+use noise::{NoiseFn, OpenSimplex};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::biome::Biome;
+use crate::distance::chamfer_distance;
+use crate::geometry::distance;
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::MapResult;
+
+/// A hand-placed danger source, in world units.
+pub(crate) struct Hotspot {
+    pub x: f32,
+    pub y: f32,
+    pub radius: f32,
+    pub strength: f32,
+}
+
+pub(crate) struct DangerOptions {
+    /// Weight of the distance-from-civilization term.
+    pub wilderness_weight: f32,
+    /// World distance at which the wilderness term reaches ~63% of its weight.
+    pub wilderness_falloff: f32,
+    /// Weight of the per-biome harshness term.
+    pub biome_weight: f32,
+    /// Weight of the seeded noise jitter, centered on zero.
+    pub noise_weight: f32,
+    /// Noise feature size in world units.
+    pub noise_scale: f32,
+    /// Weight of nearness to active faults, on maps generated with `faults`.
+    pub fault_weight: f32,
+    pub hotspots: Vec<Hotspot>,
+}
+
+impl Default for DangerOptions {
+    fn default() -> Self {
+        Self {
+            wilderness_weight: 0.6,
+            wilderness_falloff: 384.0,
+            biome_weight: 0.3,
+            noise_weight: 0.1,
+            noise_scale: 160.0,
+            fault_weight: 0.3,
+            hotspots: Vec::new(),
+        }
+    }
+}
+
+impl DangerOptions {
+    #[cfg(feature = "wasm")]
+    fn from_js(options: &JsValue) -> Result<Self, JsValue> {
+        let defaults = Self::default();
+        let mut hotspots = Vec::new();
+        for hotspot in js::get_array(options, "hotspots")
+            .iter()
+            .flat_map(|a| a.iter())
+        {
+            let (Some(x), Some(y)) = (
+                js::get(&hotspot, "x").and_then(|v| v.as_f64()),
+                js::get(&hotspot, "y").and_then(|v| v.as_f64()),
+            ) else {
+                return Err(JsValue::from_str("hotspots need numeric x and y"));
+            };
+            hotspots.push(Hotspot {
+                x: x as f32,
+                y: y as f32,
+                radius: js::get_f32(&hotspot, "radius", 128.0),
+                strength: js::get_f32(&hotspot, "strength", 0.5),
+            });
+        }
+        Ok(Self {
+            wilderness_weight: js::get_f32(
+                options,
+                "wilderness_weight",
+                defaults.wilderness_weight,
+            ),
+            wilderness_falloff: js::get_f32(
+                options,
+                "wilderness_falloff",
+                defaults.wilderness_falloff,
+            ),
+            biome_weight: js::get_f32(options, "biome_weight", defaults.biome_weight),
+            noise_weight: js::get_f32(options, "noise_weight", defaults.noise_weight),
+            noise_scale: js::get_f32(options, "noise_scale", defaults.noise_scale),
+            fault_weight: js::get_f32(options, "fault_weight", defaults.fault_weight),
+            hotspots,
+        })
+    }
+}
+
+/// How hostile a biome is to travellers, 0..1.
+fn harshness(biome: Biome) -> f32 {
+    match biome {
+        Biome::Ocean | Biome::Lake => 0.0,
+        Biome::Tundra => 0.7,
+        Biome::BorealForest => 0.6,
+        Biome::TemperateForest => 0.4,
+        Biome::TemperateGrassland => 0.1,
+        Biome::TropicalForest => 0.8,
+        Biome::Savanna => 0.3,
+        Biome::Steppe => 0.2,
+        Biome::Desert => 0.7,
+        Biome::Alpine => 1.0,
+        Biome::Beach => 0.1,
+        Biome::RockyShore => 0.3,
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Encounter danger per cell, 0 (safe) to 1. Rises with distance from
+    /// settlements and roads, in harsh biomes, and near active faults, plus
+    /// optional `hotspots` (`{ x, y, radius, strength }` in world units) and
+    /// seeded jitter. Weights: `wilderness_weight`, `wilderness_falloff`,
+    /// `biome_weight`, `fault_weight`, `noise_weight`, `noise_scale`.
+    pub fn danger(&self, options: JsValue) -> Result<Float32Array, JsValue> {
+        let options = DangerOptions::from_js(&options)?;
+        Ok(Float32Array::from(danger_field(self, &options).as_slice()))
+    }
+
+    /// Danger at the cell nearest a world position, with `danger`'s options.
+    pub fn danger_at(&self, world_x: f32, world_y: f32, options: JsValue) -> Result<f32, JsValue> {
+        let options = DangerOptions::from_js(&options)?;
+        let noise = OpenSimplex::new(self.settings.seed.wrapping_add(211));
+        let (x, y) = self.nearest_cell(world_x, world_y);
+        Ok(danger_cell(
+            self,
+            &options,
+            &noise,
+            y * self.width as usize + x,
+        ))
+    }
+}
+
+pub(crate) fn danger_field(map: &MapResult, options: &DangerOptions) -> Vec<f32> {
+    let noise = OpenSimplex::new(map.settings.seed.wrapping_add(211));
+    (0..map.heightmap.len())
+        .map(|index| danger_cell(map, options, &noise, index))
+        .collect()
+}
+
+fn danger_cell(map: &MapResult, options: &DangerOptions, noise: &OpenSimplex, index: usize) -> f32 {
+    let (world_x, world_y) = map.cell_to_world(index);
+    let civilization = map.civilization_distance()[index];
+    let wilderness = if civilization.is_finite() {
+        1.0 - (-civilization / options.wilderness_falloff.max(f32::EPSILON)).exp()
+    } else {
+        1.0
+    };
+    let biome = Biome::from_code(map.biome[index]).map_or(0.0, harshness);
+    let fault = if map.faults.is_empty() {
+        0.0
+    } else {
+        map.fault_heat()[index]
+    };
+    let scale = options.noise_scale.max(f32::EPSILON) as f64;
+    let jitter = noise.get([world_x as f64 / scale, world_y as f64 / scale]) as f32;
+    let hotspots: f32 = options
+        .hotspots
+        .iter()
+        .map(|hotspot| {
+            let d = distance((world_x, world_y), (hotspot.x, hotspot.y));
+            let t = (1.0 - d / hotspot.radius.max(f32::EPSILON)).max(0.0);
+            hotspot.strength * t * t
+        })
+        .sum();
+    (wilderness * options.wilderness_weight
+        + biome * options.biome_weight
+        + fault * options.fault_weight
+        + jitter * options.noise_weight
+        + hotspots)
+        .clamp(0.0, 1.0)
+}
+
+impl MapResult {
+    /// World distance from every cell to the nearest settlement or road cell.
+    pub(crate) fn civilization_distance(&self) -> &[f32] {
+        self.cache.civilization_distance.get_or_init(|| {
+            let mut sources = self.road_mask();
+            for settlement in &self.settlements {
+                let (x, y) = self.nearest_cell(settlement.x, settlement.y);
+                sources[y * self.width as usize + x] = true;
+            }
+            chamfer_distance(&sources, self.width as usize, self.height as usize)
+        })
+    }
+}
@@ -0,0 +1,144 @@
+#[cfg(feature = "wasm")]
+use js_sys::Float32Array;
+use noise::{NoiseFn, OpenSimplex};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "wasm")]
+use crate::query::points_from_js;
+use crate::roughness::roughness;
+use crate::{MapResult, REGION_SIZE};
+
+/// Window radius, in cells, of the roughness that scales the detail.
+const ROUGHNESS_RADIUS: usize = 2;
+/// Amplitude of the first detail octave on the most rugged cell.
+const DETAIL_AMPLITUDE: f32 = 0.012;
+/// Share of the amplitude left on perfectly smooth ground.
+const SMOOTH_FLOOR: f32 = 0.05;
+/// Octaves past this are below f32 precision at any map size.
+const MAX_OCTAVES: u32 = 12;
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Height at a world position with `detail_octaves` of sub-cell noise
+    /// over the bilinear heightmap, for close-up rendering without
+    /// refinement. The first octave has a wavelength of one cell and each
+    /// further octave halves both wavelength and amplitude; amplitude follows
+    /// the local `roughness`, so plains stay smooth and mountains turn
+    /// craggy. The noise is seeded from the map seed and sampled at world
+    /// coordinates, so nearby queries are continuous, and 0 octaves returns
+    /// `sample("heightmap", x, y)` exactly.
+    pub fn detail_sample(&self, world_x: f32, world_y: f32, detail_octaves: u32) -> f32 {
+        let noise = detail_noise(self);
+        detail_height(self, &noise, world_x, world_y, detail_octaves)
+    }
+
+    /// Batched `detail_sample` over interleaved `x, y` world coordinates.
+    pub fn detail_samples(
+        &self,
+        points: &Float32Array,
+        detail_octaves: u32,
+    ) -> Result<Float32Array, JsValue> {
+        let noise = detail_noise(self);
+        let heights: Vec<f32> = points_from_js(points)?
+            .chunks_exact(2)
+            .map(|point| detail_height(self, &noise, point[0], point[1], detail_octaves))
+            .collect();
+        Ok(Float32Array::from(heights.as_slice()))
+    }
+}
+
+impl MapResult {
+    /// Cached until the terrain changes.
+    pub(crate) fn detail_roughness(&self) -> &[f32] {
+        self.cache.detail_roughness.get_or_init(|| {
+            roughness(
+                &self.heightmap,
+                self.width as usize,
+                self.height as usize,
+                ROUGHNESS_RADIUS,
+            )
+        })
+    }
+}
+
+pub(crate) fn detail_noise(map: &MapResult) -> OpenSimplex {
+    OpenSimplex::new(map.settings.seed.wrapping_add(719))
+}
+
+pub(crate) fn detail_height(
+    map: &MapResult,
+    noise: &OpenSimplex,
+    world_x: f32,
+    world_y: f32,
+    detail_octaves: u32,
+) -> f32 {
+    let base = map.bilinear(&map.heightmap, world_x, world_y);
+    if detail_octaves == 0 {
+        return base;
+    }
+    let rugged = map.bilinear(map.detail_roughness(), world_x, world_y);
+    let mut amplitude = DETAIL_AMPLITUDE * (SMOOTH_FLOOR + (1.0 - SMOOTH_FLOOR) * rugged);
+    let mut wavelength = REGION_SIZE / map.width.max(map.height) as f32;
+    let mut detail = 0.0f32;
+    for _ in 0..detail_octaves.min(MAX_OCTAVES) {
+        let point = [(world_x / wavelength) as f64, (world_y / wavelength) as f64];
+        detail += noise.get(point) as f32 * amplitude;
+        amplitude *= 0.5;
+        wavelength *= 0.5;
+    }
+    base + detail
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{detail_height, detail_noise};
+    use crate::generate_map;
+
+    #[test]
+    fn detail_is_continuous_and_vanishes_at_zero_octaves() {
+        let map = generate_map(64, 64, 23, 0.42, 1.0, 40.0, 2, 1.0);
+        let noise = detail_noise(&map);
+        let rugged = map.detail_roughness();
+        let (smooth, craggy) = (0..rugged.len()).fold((0, 0), |(low, high), index| {
+            (
+                if rugged[index] < rugged[low] {
+                    index
+                } else {
+                    low
+                },
+                if rugged[index] > rugged[high] {
+                    index
+                } else {
+                    high
+                },
+            )
+        });
+        let spread = |index: usize| {
+            let (x, y) = map.cell_to_world(index);
+            let heights: Vec<f32> = (0..16)
+                .map(|step| {
+                    let offset = step as f32 * 2.0;
+                    detail_height(&map, &noise, x + offset, y + offset, 6)
+                        - map.bilinear(&map.heightmap, x + offset, y + offset)
+                })
+                .collect();
+            heights.iter().map(|h| h.abs()).fold(0.0f32, f32::max)
+        };
+        assert!(spread(smooth) < spread(craggy));
+
+        for index in (0..map.heightmap.len()).step_by(37) {
+            let (x, y) = map.cell_to_world(index);
+            let (x, y) = (x + 5.3, y + 11.7);
+            assert_eq!(
+                detail_height(&map, &noise, x, y, 0),
+                map.bilinear(&map.heightmap, x, y)
+            );
+            let here = detail_height(&map, &noise, x, y, 8);
+            let near = detail_height(&map, &noise, x + 0.01, y, 8);
+            assert!((here - near).abs() < 1e-3, "{here} vs {near}");
+            assert_eq!(here, detail_height(&map, &detail_noise(&map), x, y, 8));
+        }
+    }
+}
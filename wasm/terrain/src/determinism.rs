@@ -0,0 +1,254 @@
+//! Cross-platform reproducibility. Native and wasm builds can disagree in the
+//! last bit of `powf` and the noise library's float math, and a one-ulp
+//! difference is enough to reorder the flow sort or flip a settlement score.
+//! With `deterministic` set, generation snaps elevation, moisture,
+//! temperature, and water to multiples of [`QUANTUM`] before any decision
+//! reads them, and every ordering breaks ties by cell index.
+//!
+//! Bit-stable between builds in that mode: the biome layer, flow, the
+//! quantized layers, settlements, and roads, except where a raw value lands
+//! within an ulp of a quantum boundary. Only approximately stable: anything
+//! derived with further float math at query time, such as distance fields,
+//! renders, and analysis outputs.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::json::Json;
+use crate::{generate, GenerationSettings, MapResult};
+
+/// Grid that decision-making layers are snapped to in deterministic mode. A
+/// power of two, so snapping is exact.
+pub(crate) const QUANTUM: f32 = 1.0 / 65536.0;
+
+/// `(seed, width, height, fingerprint)` for deterministic generation with
+/// `generate_map`'s usual parameters; both builds must reproduce these.
+const TEST_VECTORS: [(u32, u32, u32, u64); 3] = [
+    (1, 128, 96, 0x7ad3_0c04_6d36_9957),
+    (42, 128, 96, 0x6f62_4f5b_d81a_f95f),
+    (2024, 96, 128, 0x1ac9_d721_2295_1e12),
+];
+
+pub(crate) fn quantize(values: &mut [f32]) {
+    for value in values {
+        *value = (*value / QUANTUM).round() * QUANTUM;
+    }
+}
+
+/// Layers `content_hash` accepts, in the order they are hashed.
+pub(crate) const HASHABLE_LAYERS: [&str; 6] = [
+    "heightmap",
+    "flow",
+    "moisture",
+    "temperature",
+    "water",
+    "biome",
+];
+
+/// 64-bit FNV-1a. Values are fed as little-endian bytes, so hashes match
+/// across platforms.
+pub(crate) struct Fnv(u64);
+
+impl Fnv {
+    pub(crate) fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    pub(crate) fn write_u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes());
+    }
+
+    pub(crate) fn write_f32(&mut self, value: f32) {
+        self.write_u32(value.to_bits());
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// FNV-1a over the dimensions, biome layer, settlements, and road graph.
+pub(crate) fn fingerprint(map: &MapResult) -> u64 {
+    let mut hash = Fnv::new();
+    hash.write_u32(map.width);
+    hash.write_u32(map.height);
+    hash.write(&map.biome);
+    for settlement in &map.settlements {
+        hash.write_u32(settlement.id);
+        hash.write_f32(settlement.x);
+        hash.write_f32(settlement.y);
+        hash.write_f32(settlement.size);
+    }
+    for &(a, b) in &map.road_graph {
+        hash.write_u32(a);
+        hash.write_u32(b);
+    }
+    hash.finish()
+}
+
+/// Hash of the named layers plus settlements and roads, serialized
+/// canonically: layers in `HASHABLE_LAYERS` order whatever order they are
+/// named in, each prefixed by its name and length; settlements by id; roads
+/// as sorted `(low, high)` id pairs. An empty selection hashes every layer.
+pub(crate) fn content_hash(map: &MapResult, layers: &[&str]) -> Result<u64, String> {
+    if let Some(unknown) = layers.iter().find(|layer| !HASHABLE_LAYERS.contains(layer)) {
+        return Err(format!("unknown layer: {unknown}"));
+    }
+    let mut hash = Fnv::new();
+    hash.write_u32(map.width);
+    hash.write_u32(map.height);
+    for name in HASHABLE_LAYERS {
+        if !layers.is_empty() && !layers.contains(&name) {
+            continue;
+        }
+        hash.write(name.as_bytes());
+        hash.write_u32(map.biome.len() as u32);
+        match map.float_layer(name) {
+            Some(values) => values.iter().for_each(|&value| hash.write_f32(value)),
+            None => hash.write(&map.biome),
+        }
+    }
+
+    let mut settlements: Vec<_> = map.settlements.iter().collect();
+    settlements.sort_by_key(|settlement| settlement.id);
+    hash.write_u32(settlements.len() as u32);
+    for settlement in settlements {
+        hash.write_u32(settlement.id);
+        hash.write_f32(settlement.x);
+        hash.write_f32(settlement.y);
+        hash.write_f32(settlement.size);
+        hash.write_u32(settlement.era);
+    }
+    let mut roads: Vec<(u32, u32)> = map
+        .road_graph
+        .iter()
+        .map(|&(a, b)| (a.min(b), a.max(b)))
+        .collect();
+    roads.sort_unstable();
+    hash.write_u32(roads.len() as u32);
+    for (a, b) in roads {
+        hash.write_u32(a);
+        hash.write_u32(b);
+    }
+    Ok(hash.finish())
+}
+
+fn vector_settings(seed: u32) -> GenerationSettings {
+    GenerationSettings {
+        deterministic: true,
+        ..GenerationSettings::new(seed, 0.42, 1.0, 40.0, 2, 1.0)
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Hex FNV-1a hash of the biome layer, settlements, and roads, for
+    /// checking that two builds generated the same map.
+    pub fn fingerprint(&self) -> String {
+        format!("{:016x}", fingerprint(self))
+    }
+
+    /// Hex hash of the named layers (any of `heightmap`, `flow`, `moisture`,
+    /// `temperature`, `water`, `biome`; all of them when empty) plus
+    /// settlements and roads. Layer order does not matter, and the hash is
+    /// the same on every platform for identical data, so it doubles as a
+    /// shareable world fingerprint. Throws for unknown layer names.
+    pub fn content_hash(&self, layers: Vec<String>) -> Result<String, JsValue> {
+        let layers: Vec<&str> = layers.iter().map(String::as_str).collect();
+        content_hash(self, &layers)
+            .map(|hash| format!("{hash:016x}"))
+            .map_err(|message| JsValue::from_str(&message))
+    }
+}
+
+/// Reference fingerprints as `{ seed, width, height, fingerprint }`, each for
+/// `generate_map_with_options(width, height, seed, 0.42, 1, 40, 2, 1,
+/// { deterministic: true })`. `verify_determinism` regenerates them.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn determinism_test_vectors() -> JsValue {
+    Json::from(
+        TEST_VECTORS
+            .iter()
+            .map(|&(seed, width, height, hash)| {
+                Json::object()
+                    .with("seed", seed)
+                    .with("width", width)
+                    .with("height", height)
+                    .with("fingerprint", format!("{hash:016x}").as_str())
+            })
+            .collect::<Vec<_>>(),
+    )
+    .to_js()
+}
+
+/// Regenerates every test vector and returns whether all fingerprints match
+/// this build's output.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn verify_determinism() -> bool {
+    TEST_VECTORS.iter().all(|&(seed, width, height, hash)| {
+        fingerprint(&generate(width, height, &vector_settings(seed), None)) == hash
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{content_hash, fingerprint, vector_settings, TEST_VECTORS};
+    use crate::{generate, generate_map};
+
+    /// Full-content hashes for `generate_map(128, 96, seed, 0.42, 1, 40, 2,
+    /// 1)`. Update deliberately, alongside `GENERATOR_VERSION`, when a change
+    /// is meant to alter existing seeds.
+    const GOLDEN_SEEDS: [(u32, u64); 5] = [
+        (3, 0x779e_6ebb_2181_e08a),
+        (17, 0x7a22_c5df_b9cc_ea41),
+        (99, 0xa142_8f2c_e8cf_4dfa),
+        (512, 0x81e9_f077_8ebc_3690),
+        (40_000, 0x686c_5b41_7329_1f0c),
+    ];
+
+    #[test]
+    fn test_vectors_match() {
+        for (seed, width, height, expected) in TEST_VECTORS {
+            let map = generate(width, height, &vector_settings(seed), None);
+            assert_eq!(
+                fingerprint(&map),
+                expected,
+                "seed {seed} at {width}x{height} got {:016x}",
+                fingerprint(&map)
+            );
+        }
+    }
+
+    #[test]
+    fn golden_seeds_unchanged() {
+        for (seed, expected) in GOLDEN_SEEDS {
+            let map = generate_map(128, 96, seed, 0.42, 1.0, 40.0, 2, 1.0);
+            let hash = content_hash(&map, &[]).unwrap();
+            assert_eq!(hash, expected, "seed {seed} now hashes to {hash:016x}");
+        }
+    }
+
+    #[test]
+    fn content_hash_ignores_layer_order() {
+        let map = generate_map(48, 48, 5, 0.42, 1.0, 40.0, 0, 1.0);
+        assert_eq!(
+            content_hash(&map, &["water", "biome"]),
+            content_hash(&map, &["biome", "water"])
+        );
+        assert_ne!(
+            content_hash(&map, &["biome"]),
+            content_hash(&map, &["water"])
+        );
+        assert!(content_hash(&map, &["rivers"]).is_err());
+    }
+}
@@ -0,0 +1,257 @@
+#[cfg(feature = "wasm")]
+use js_sys::Float32Array;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::biome::Biome;
+use crate::movement::Profile;
+use crate::pathfinding::relax_distances;
+use crate::{MapResult, REGION_SIZE};
+
+/// Forward half of the 5x5 chamfer mask; the backward pass uses the negation.
+const FORWARD_MASK: [(i32, i32); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (-2, -1),
+    (-1, -2),
+    (1, -2),
+    (2, -1),
+];
+
+/// Two-pass 5x5 chamfer distance transform in world units. Source cells hold
+/// 0; cells with no source anywhere on the map hold `f32::INFINITY`.
+pub(crate) fn chamfer_distance(sources: &[bool], width: usize, height: usize) -> Vec<f32> {
+    let cell_w = REGION_SIZE / width as f32;
+    let cell_h = REGION_SIZE / height as f32;
+    let weights: Vec<(i32, i32, f32)> = FORWARD_MASK
+        .iter()
+        .map(|&(dx, dy)| {
+            let length = ((dx as f32 * cell_w).powi(2) + (dy as f32 * cell_h).powi(2)).sqrt();
+            (dx, dy, length)
+        })
+        .collect();
+
+    let mut distance: Vec<f32> = sources
+        .iter()
+        .map(|&source| if source { 0.0 } else { f32::INFINITY })
+        .collect();
+
+    let relax = |distance: &mut Vec<f32>, x: usize, y: usize, sign: i32| {
+        let index = y * width + x;
+        let mut best = distance[index];
+        for &(dx, dy, length) in &weights {
+            let nx = x as i32 + dx * sign;
+            let ny = y as i32 + dy * sign;
+            if nx < 0 || ny < 0 || nx >= width as i32 || ny >= height as i32 {
+                continue;
+            }
+            let candidate = distance[ny as usize * width + nx as usize] + length;
+            if candidate < best {
+                best = candidate;
+            }
+        }
+        distance[index] = best;
+    };
+
+    for y in 0..height {
+        for x in 0..width {
+            relax(&mut distance, x, y, 1);
+        }
+    }
+    for y in (0..height).rev() {
+        for x in (0..width).rev() {
+            relax(&mut distance, x, y, -1);
+        }
+    }
+    distance
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Distance in world units from every cell to the nearest ocean cell.
+    /// Ocean cells hold 0; maps without ocean hold `Infinity` everywhere.
+    pub fn distance_to_coast(&self) -> Float32Array {
+        Float32Array::from(self.coast_distance())
+    }
+
+    /// Distance in world units from every cell to the nearest river cell.
+    pub fn distance_to_river(&self) -> Float32Array {
+        Float32Array::from(self.river_distance())
+    }
+
+    /// Travel cost from every cell to the nearest settlement, walking with
+    /// the `"foot"` movement profile, so ridges and marshes count extra.
+    /// Cells no settlement can walk to, including all water, hold
+    /// `Infinity`; with `ports` set, ocean cells instead hold the sailing
+    /// cost to the nearest port's harbor.
+    pub fn distance_to_settlement(&self, ports: bool) -> Float32Array {
+        if !ports {
+            return Float32Array::from(self.settlement_distance());
+        }
+        let mut distance = self.settlement_distance().to_vec();
+        for (index, &port) in self.port_distance().iter().enumerate() {
+            if self.biome[index] == Biome::Ocean.code() {
+                distance[index] = port;
+            }
+        }
+        Float32Array::from(distance.as_slice())
+    }
+
+    /// Distance in world units from every cell to the nearest road cell.
+    /// Maps without roads hold `Infinity` everywhere.
+    pub fn distance_to_road(&self) -> Float32Array {
+        Float32Array::from(self.road_distance())
+    }
+}
+
+impl MapResult {
+    pub(crate) fn coast_distance(&self) -> &[f32] {
+        self.cache.coast_distance.get_or_init(|| {
+            let sources: Vec<bool> = self
+                .biome
+                .iter()
+                .map(|&biome| biome == Biome::Ocean.code())
+                .collect();
+            chamfer_distance(&sources, self.width as usize, self.height as usize)
+        })
+    }
+
+    pub(crate) fn river_distance(&self) -> &[f32] {
+        self.cache.river_distance.get_or_init(|| {
+            let sources: Vec<bool> = (0..self.biome.len())
+                .map(|index| self.is_river(index))
+                .collect();
+            chamfer_distance(&sources, self.width as usize, self.height as usize)
+        })
+    }
+
+    /// Cached until the terrain, settlements, or roads change.
+    pub(crate) fn settlement_distance(&self) -> &[f32] {
+        self.cache.settlement_distance.get_or_init(|| {
+            let width = self.width as usize;
+            let sources: Vec<usize> = self
+                .settlements
+                .iter()
+                .map(|settlement| {
+                    let (x, y) = self.nearest_cell(settlement.x, settlement.y);
+                    y * width + x
+                })
+                .collect();
+            let foot = self.movement_costs(&Profile::preset("foot").expect("foot is a preset"));
+            travel_distance(self, &foot, &sources)
+        })
+    }
+
+    /// Sailing cost from every cell to the nearest port's harbor.
+    pub(crate) fn port_distance(&self) -> &[f32] {
+        self.cache.port_distance.get_or_init(|| {
+            let sources: Vec<usize> = self.docks().into_iter().flatten().collect();
+            let boat = self.movement_costs(&Profile::preset("boat").expect("boat is a preset"));
+            travel_distance(self, &boat, &sources)
+        })
+    }
+
+    pub(crate) fn road_distance(&self) -> &[f32] {
+        self.cache.road_distance.get_or_init(|| {
+            chamfer_distance(&self.road_mask(), self.width as usize, self.height as usize)
+        })
+    }
+}
+
+fn travel_distance(map: &MapResult, costs: &[f32], sources: &[usize]) -> Vec<f32> {
+    let mut distance = vec![f32::INFINITY; costs.len()];
+    let mut owner = vec![0u32; costs.len()];
+    relax_distances(
+        costs,
+        map.width as usize,
+        map.height as usize,
+        sources,
+        0,
+        &mut distance,
+        &mut owner,
+    );
+    distance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::chamfer_distance;
+    use crate::biome::Biome;
+    use crate::geometry::distance;
+    use crate::{generate_map, Settlement, REGION_SIZE};
+
+    #[test]
+    fn chamfer_distances_stay_close_to_euclidean() {
+        let (width, height) = (64, 64);
+        let source = 20 * width + 23;
+        let mut sources = vec![false; width * height];
+        sources[source] = true;
+        let field = chamfer_distance(&sources, width, height);
+        let (cell_w, cell_h) = (REGION_SIZE / width as f32, REGION_SIZE / height as f32);
+        let world = |index: usize| {
+            (
+                (index % width) as f32 * cell_w,
+                (index / width) as f32 * cell_h,
+            )
+        };
+        // The 5x5 mask strays a little over 2% on square cells.
+        for (index, &chamfer) in field.iter().enumerate() {
+            let exact = distance(world(index), world(source));
+            assert!(
+                (chamfer - exact).abs() <= exact * 0.03,
+                "{index}: {chamfer} vs {exact}"
+            );
+        }
+        assert_eq!(field[source], 0.0);
+        let none = chamfer_distance(&vec![false; width * height], width, height);
+        assert!(none.iter().all(|distance| distance.is_infinite()));
+
+        let map = generate_map(64, 64, 5, 0.42, 1.0, 40.0, 2, 1.0);
+        let coast = map.coast_distance();
+        let mut ocean = 0;
+        for (index, &biome) in map.biome.iter().enumerate() {
+            if biome == Biome::Ocean.code() {
+                assert_eq!(coast[index], 0.0);
+                ocean += 1;
+            } else {
+                assert!(coast[index] > 0.0);
+            }
+        }
+        assert!(ocean > 0);
+    }
+
+    #[test]
+    fn settlement_and_road_distances_follow_edits() {
+        let mut map = generate_map(96, 96, 5, 0.42, 1.0, 40.0, 2, 1.0);
+        let width = map.width as usize;
+        let roads = map.road_mask();
+        for (index, &road) in roads.iter().enumerate() {
+            assert_eq!(map.road_distance()[index] == 0.0, road);
+        }
+        for settlement in &map.settlements {
+            let (x, y) = map.nearest_cell(settlement.x, settlement.y);
+            assert_eq!(map.settlement_distance()[y * width + x], 0.0);
+        }
+
+        let farthest = (0..map.biome.len())
+            .filter(|&index| map.settlement_distance()[index].is_finite())
+            .max_by(|&a, &b| map.settlement_distance()[a].total_cmp(&map.settlement_distance()[b]))
+            .unwrap();
+        assert!(map.settlement_distance()[farthest] > 0.0);
+        let (x, y) = map.cell_to_world(farthest);
+        map.settlements.push(Settlement {
+            id: 99,
+            x,
+            y,
+            size: 1.0,
+            issue: None,
+            era: 0,
+            name: None,
+        });
+        map.invalidate_settlements();
+        assert_eq!(map.settlement_distance()[farthest], 0.0);
+    }
+}
@@ -0,0 +1,185 @@
+#[cfg(feature = "wasm")]
+use js_sys::{Float32Array, Uint8Array};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::biome::Biome;
+use crate::geometry::heading;
+use crate::{downslope_map, MapResult, DIRECTIONS};
+
+/// Direction code of cells that drain nowhere, and of the open ocean.
+pub(crate) const NO_DIRECTION: u8 = 255;
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Where each cell drains, as an index into the 8 directions `(-1, -1),
+    /// (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)`, or 255 for
+    /// pits and the ocean. Follows the same receivers as `flow()`, so lakes
+    /// with outflows point toward their outlet.
+    pub fn flow_directions(&self) -> Uint8Array {
+        Uint8Array::from(flow_directions(self).as_slice())
+    }
+
+    /// Flow direction as interleaved unit `x, y` per cell, averaged over the
+    /// `(2 * radius + 1)²` cells around each so animated water turns
+    /// smoothly; land and ocean are averaged separately. Ocean cells follow
+    /// the currents when the map was generated with `ocean_currents`, and
+    /// are zero otherwise. Pits stay zero.
+    pub fn flow_vectors(&self, radius: u32) -> Float32Array {
+        Float32Array::from(flow_vectors(self, radius as usize).as_slice())
+    }
+}
+
+impl MapResult {
+    /// Cell each cell's flow moves to: steepest descent, or the lake routing
+    /// when generated with `lake_outflows`. Built once and dropped by
+    /// `invalidate_terrain`.
+    pub(crate) fn receivers(&self) -> &[Option<usize>] {
+        self.cache.receivers.get_or_init(|| {
+            if self.settings.lake_outflows {
+                self.lake_routing().receiver
+            } else {
+                downslope_map(&self.heightmap, self.width, self.height)
+            }
+        })
+    }
+
+    fn is_ocean(&self, index: usize) -> bool {
+        self.biome[index] == Biome::Ocean.code()
+    }
+}
+
+pub(crate) fn flow_directions(map: &MapResult) -> Vec<u8> {
+    let width = map.width as usize;
+    map.receivers()
+        .iter()
+        .enumerate()
+        .map(|(index, receiver)| match receiver {
+            Some(target) if !map.is_ocean(index) => {
+                let dx = (target % width) as f32 - (index % width) as f32;
+                let dy = (target / width) as f32 - (index / width) as f32;
+                nearest_direction(dx, dy)
+            }
+            _ => NO_DIRECTION,
+        })
+        .collect()
+}
+
+/// Index into `DIRECTIONS` closest in angle to `(dx, dy)`, ties to the
+/// lower index. Lake receivers may lie several cells off, so the offset is
+/// not always a neighbour's.
+fn nearest_direction(dx: f32, dy: f32) -> u8 {
+    let toward = heading(dx, dy);
+    let turn = |&(ddx, ddy): &(i32, i32)| {
+        let turn = (heading(ddx as f32, ddy as f32) - toward).rem_euclid(360.0);
+        turn.min(360.0 - turn)
+    };
+    (0..DIRECTIONS.len())
+        .min_by(|&a, &b| {
+            turn(&DIRECTIONS[a])
+                .total_cmp(&turn(&DIRECTIONS[b]))
+                .then(a.cmp(&b))
+        })
+        .map_or(NO_DIRECTION, |slot| slot as u8)
+}
+
+pub(crate) fn flow_vectors(map: &MapResult, radius: usize) -> Vec<f32> {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let directions = flow_directions(map);
+    let currents = map.settings.ocean_currents.then(|| map.current_field());
+    let raw: Vec<(f32, f32)> = (0..directions.len())
+        .map(|index| {
+            if map.is_ocean(index) {
+                return currents.map_or((0.0, 0.0), |field| unit(field.u[index], field.v[index]));
+            }
+            match directions[index] {
+                NO_DIRECTION => (0.0, 0.0),
+                slot => {
+                    let (dx, dy) = DIRECTIONS[slot as usize];
+                    unit(dx as f32, dy as f32)
+                }
+            }
+        })
+        .collect();
+
+    let mut smoothed = Vec::with_capacity(raw.len() * 2);
+    for index in 0..raw.len() {
+        if raw[index] == (0.0, 0.0) {
+            smoothed.extend([0.0, 0.0]);
+            continue;
+        }
+        let (x, y) = (index % width, index / width);
+        let ocean = map.is_ocean(index);
+        let (mut sx, mut sy) = (0.0f32, 0.0f32);
+        for ny in y.saturating_sub(radius)..(y + radius + 1).min(height) {
+            for nx in x.saturating_sub(radius)..(x + radius + 1).min(width) {
+                let neighbor = ny * width + nx;
+                if map.is_ocean(neighbor) == ocean {
+                    sx += raw[neighbor].0;
+                    sy += raw[neighbor].1;
+                }
+            }
+        }
+        // Opposed neighbours can cancel out; keep the cell's own heading.
+        let (ux, uy) = unit(sx, sy);
+        smoothed.extend(if (ux, uy) == (0.0, 0.0) {
+            [raw[index].0, raw[index].1]
+        } else {
+            [ux, uy]
+        });
+    }
+    smoothed
+}
+
+fn unit(x: f32, y: f32) -> (f32, f32) {
+    let length = x.hypot(y);
+    if length > 1e-6 {
+        (x / length, y / length)
+    } else {
+        (0.0, 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{flow_directions, flow_vectors, nearest_direction, NO_DIRECTION};
+    use crate::biome::Biome;
+    use crate::{generate_map, DIRECTIONS};
+
+    #[test]
+    fn directions_follow_receivers_and_vectors_stay_unit() {
+        assert_eq!(nearest_direction(3.0, 0.2), 4);
+        assert_eq!(nearest_direction(-2.0, -2.0), 0);
+
+        let mut map = generate_map(64, 64, 9, 0.42, 1.0, 40.0, 2, 1.0);
+        let directions = flow_directions(&map);
+        for (index, &direction) in directions.iter().enumerate() {
+            if map.biome[index] == Biome::Ocean.code() {
+                assert_eq!(direction, NO_DIRECTION);
+            } else if direction != NO_DIRECTION {
+                let (dx, dy) = DIRECTIONS[direction as usize];
+                let target =
+                    ((index / 64) as i32 + dy) as usize * 64 + ((index % 64) as i32 + dx) as usize;
+                assert_eq!(map.receivers()[index], Some(target));
+                assert!(map.heightmap[target] < map.heightmap[index]);
+            }
+        }
+
+        let still = flow_vectors(&map, 1);
+        for pair in still.chunks(2) {
+            let length = pair[0].hypot(pair[1]);
+            assert!(length == 0.0 || (length - 1.0).abs() < 1e-4);
+        }
+        let ocean = (0..64 * 64)
+            .find(|&index| map.biome[index] == Biome::Ocean.code())
+            .unwrap();
+        assert_eq!(still[ocean * 2..ocean * 2 + 2], [0.0, 0.0]);
+
+        map.settings.ocean_currents = true;
+        let moving = flow_vectors(&map, 1);
+        assert!((0..64 * 64)
+            .any(|index| map.biome[index] == Biome::Ocean.code() && moving[index * 2] != 0.0));
+    }
+}
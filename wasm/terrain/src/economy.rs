@@ -0,0 +1,156 @@
+#[cfg(feature = "wasm")]
+use js_sys::Uint32Array;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::biome::Biome;
+use crate::footprints::NO_FOOTPRINT;
+use crate::json::Json;
+use crate::{slope_map, MapResult};
+
+/// Territory radius in world units per unit of settlement size.
+const TERRITORY_RADIUS_PER_SIZE: f32 = 40.0;
+/// Label for cells outside every territory.
+pub(crate) const NO_TERRITORY: u32 = u32::MAX;
+/// Steepest ground still worth ploughing.
+pub(crate) const MAX_FARM_SLOPE: f32 = 0.12;
+/// Slope above which exposed rock counts as a quarry or mine site.
+const MIN_MINING_SLOPE: f32 = 0.35;
+/// Rivers are one cell wide, so each river cell stands for a stretch of
+/// navigable bank.
+const RIVER_WEIGHT: f32 = 4.0;
+
+/// Share of a settlement's territory given to each industry; the shares sum
+/// to 1 unless the territory has no usable land or water at all.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub(crate) struct Economy {
+    pub farming: f32,
+    pub fishing: f32,
+    pub mining: f32,
+    pub timber: f32,
+    pub trade: f32,
+}
+
+impl Economy {
+    fn industries(&self) -> [(&'static str, f32); 5] {
+        [
+            ("farming", self.farming),
+            ("fishing", self.fishing),
+            ("mining", self.mining),
+            ("timber", self.timber),
+            ("trade", self.trade),
+        ]
+    }
+
+    /// Largest share, ties going to the earlier industry; `None` for an
+    /// empty profile.
+    pub(crate) fn dominant(&self) -> Option<&'static str> {
+        self.industries()
+            .into_iter()
+            .filter(|&(_, share)| share > 0.0)
+            .fold(None, |best: Option<(&str, f32)>, (key, share)| match best {
+                Some((_, best_share)) if best_share >= share => best,
+                _ => Some((key, share)),
+            })
+            .map(|(key, _)| key)
+    }
+
+    pub(crate) fn to_json(self) -> Json {
+        self.industries()
+            .into_iter()
+            .fold(Json::object(), |object, (key, share)| {
+                object.with(key, share)
+            })
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Index into `settlements()` of the settlement owning each cell, or
+    /// `0xFFFFFFFF` for unclaimed cells. Each cell belongs to its nearest
+    /// settlement when within `40 * size` world units of it.
+    pub fn territories(&self) -> Uint32Array {
+        Uint32Array::from(territories(self).as_slice())
+    }
+}
+
+impl MapResult {
+    /// Economy profile per settlement, in `settlements` order. Cached until
+    /// the terrain or the settlements change.
+    pub(crate) fn economies(&self) -> &[Economy] {
+        self.cache.economy.get_or_init(|| economies(self))
+    }
+}
+
+/// Nearest-settlement partition of the grid, clipped to a size-scaled radius
+/// around each settlement.
+pub(crate) fn territories(map: &MapResult) -> Vec<u32> {
+    if map.settlements.is_empty() {
+        return vec![NO_TERRITORY; map.heightmap.len()];
+    }
+    let index = map.settlement_index();
+    (0..map.heightmap.len())
+        .map(|cell| {
+            let (x, y) = map.cell_to_world(cell);
+            match index.nearest(&map.settlements, x, y) {
+                Some((position, distance))
+                    if distance <= map.settlements[position].size * TERRITORY_RADIUS_PER_SIZE =>
+                {
+                    position as u32
+                }
+                _ => NO_TERRITORY,
+            }
+        })
+        .collect()
+}
+
+/// Tallies every territory in one pass over the grid, then normalizes each
+/// tally into shares. Fields start outside the built-up footprints.
+pub(crate) fn economies(map: &MapResult) -> Vec<Economy> {
+    let labels = territories(map);
+    let built = &map.settlement_footprints().owner;
+    let slopes = slope_map(&map.heightmap, map.width as usize, map.height as usize);
+    let mut tallies = vec![Economy::default(); map.settlements.len()];
+    for (cell, &label) in labels.iter().enumerate() {
+        if label == NO_TERRITORY {
+            continue;
+        }
+        let tally = &mut tallies[label as usize];
+        if map.is_water_body(cell) {
+            tally.fishing += 1.0;
+            continue;
+        }
+        if map.is_river(cell) {
+            tally.trade += RIVER_WEIGHT;
+        }
+        match Biome::from_code(map.biome[cell]) {
+            Some(Biome::BorealForest | Biome::TemperateForest | Biome::TropicalForest) => {
+                tally.timber += 1.0;
+            }
+            Some(Biome::TemperateGrassland | Biome::Savanna | Biome::Steppe)
+                if slopes[cell] <= MAX_FARM_SLOPE && built[cell] == NO_FOOTPRINT =>
+            {
+                tally.farming += 1.0;
+            }
+            Some(Biome::Alpine) => tally.mining += 1.0,
+            _ if slopes[cell] >= MIN_MINING_SLOPE => tally.mining += 1.0,
+            _ => {}
+        }
+    }
+    tallies.into_iter().map(normalize).collect()
+}
+
+fn normalize(tally: Economy) -> Economy {
+    let total: f32 = tally.industries().iter().map(|&(_, share)| share).sum();
+    if total <= 0.0 {
+        return tally;
+    }
+    Economy {
+        farming: tally.farming / total,
+        fishing: tally.fishing / total,
+        mining: tally.mining / total,
+        timber: tally.timber / total,
+        trade: tally.trade / total,
+    }
+}
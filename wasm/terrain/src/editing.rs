@@ -0,0 +1,697 @@
+#[cfg(feature = "wasm")]
+use js_sys::Object;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::changes::{full_rect, Layer};
+use crate::geometry::{distance, smoothstep};
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::thermal::apply_thermal_erosion;
+use crate::{
+    apply_shores, build_roads, cell_temperature, cell_water, classify_cell, downslope,
+    enhanced_moisture, local_flatness, MapResult, REGION_SIZE,
+};
+
+/// Cells recomputed around the dirty rectangle so drainage entering or
+/// leaving the edit settles before the boundary.
+const RECOMPUTE_MARGIN: usize = 8;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Brush {
+    Raise,
+    Lower,
+    Flatten,
+    Smooth,
+}
+
+/// Why a settlement no longer fits the ground it stands on.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SettlementIssue {
+    Underwater,
+    Cliff,
+}
+
+impl SettlementIssue {
+    pub(crate) fn key(self) -> &'static str {
+        match self {
+            SettlementIssue::Underwater => "underwater",
+            SettlementIssue::Cliff => "cliff",
+        }
+    }
+}
+
+/// What `set_sea_level` changed.
+pub(crate) struct SeaLevelChange {
+    pub flooded: usize,
+    pub exposed: usize,
+    pub flagged: Vec<u32>,
+    pub removed: Vec<u32>,
+}
+
+/// Inclusive cell bounds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct CellRect {
+    pub x0: usize,
+    pub y0: usize,
+    pub x1: usize,
+    pub y1: usize,
+}
+
+impl CellRect {
+    pub(crate) fn union(self, other: CellRect) -> CellRect {
+        CellRect {
+            x0: self.x0.min(other.x0),
+            y0: self.y0.min(other.y0),
+            x1: self.x1.max(other.x1),
+            y1: self.y1.max(other.y1),
+        }
+    }
+
+    fn padded(self, margin: usize, width: usize, height: usize) -> CellRect {
+        CellRect {
+            x0: self.x0.saturating_sub(margin),
+            y0: self.y0.saturating_sub(margin),
+            x1: (self.x1 + margin).min(width - 1),
+            y1: (self.y1 + margin).min(height - 1),
+        }
+    }
+
+    /// Whether the two overlap or share an edge.
+    pub(crate) fn touches(&self, other: &CellRect) -> bool {
+        self.x0 <= other.x1 + 1
+            && other.x0 <= self.x1 + 1
+            && self.y0 <= other.y1 + 1
+            && other.y0 <= self.y1 + 1
+    }
+
+    pub(crate) fn contains(&self, x: usize, y: usize) -> bool {
+        x >= self.x0 && x <= self.x1 && y >= self.y0 && y <= self.y1
+    }
+
+    pub(crate) fn cells(&self, width: usize) -> impl Iterator<Item = usize> + '_ {
+        (self.y0..=self.y1).flat_map(move |y| (self.x0..=self.x1).map(move |x| y * width + x))
+    }
+}
+
+impl MapResult {
+    /// Grows the dirty rectangle after a heightmap edit, records it for
+    /// `take_dirty_regions`, and drops caches derived from the old terrain.
+    pub(crate) fn mark_dirty(&mut self, rect: CellRect) {
+        self.dirty = Some(self.dirty.map_or(rect, |dirty| dirty.union(rect)));
+        self.changes.record(&[Layer::Heightmap], rect);
+        self.invalidate_terrain();
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Edits the heightmap with a `"raise"`, `"lower"`, `"flatten"`, or
+    /// `"smooth"` brush centred on a world position. Raise and lower move the
+    /// centre by `strength`; flatten and smooth blend toward their target by
+    /// `strength` in 0..1. Derived layers are left stale until `recompute`.
+    /// Returns the number of cells changed.
+    pub fn apply_brush(
+        &mut self,
+        kind: &str,
+        world_x: f32,
+        world_y: f32,
+        radius: f32,
+        strength: f32,
+    ) -> Result<u32, JsValue> {
+        let brush = match kind {
+            "raise" => Brush::Raise,
+            "lower" => Brush::Lower,
+            "flatten" => Brush::Flatten,
+            "smooth" => Brush::Smooth,
+            other => return Err(JsValue::from_str(&format!("unknown brush: {other}"))),
+        };
+        apply_brush(self, brush, world_x, world_y, radius, strength)
+            .map_err(|message| JsValue::from_str(&message))
+    }
+
+    /// Runs `steps` more thermal erosion iterations on the stored heightmap
+    /// and returns the total applied since sampling, generation's
+    /// `erosion_iterations` included. Iterations carry no state between
+    /// calls, so two calls of `k` match one of `2k`, and brush edits may be
+    /// interleaved. Derived layers are left stale until `recompute`.
+    pub fn erode_steps(&mut self, steps: u32) -> u32 {
+        erode_steps(self, steps)
+    }
+
+    /// Rederives flow, water, temperature, moisture, and biomes from the
+    /// current heightmap, then flags settlements left underwater or on
+    /// cliffs. With `region_only` only the brushed area plus a margin is
+    /// updated; flow leaving that area is not carried further downstream.
+    pub fn recompute(&mut self, region_only: bool) {
+        let rect = if region_only {
+            match self.dirty {
+                Some(dirty) => {
+                    dirty.padded(RECOMPUTE_MARGIN, self.width as usize, self.height as usize)
+                }
+                None => return,
+            }
+        } else {
+            full_rect(self.width, self.height)
+        };
+        recompute(self, rect);
+    }
+
+    /// Moves the sea level without regenerating: water, temperature,
+    /// moisture, and biomes are rederived from the stored layers. Settlements
+    /// left underwater are flagged, or removed with roads rebuilt when
+    /// `options.remove_underwater` is true. Returns `{ flooded, exposed,
+    /// flagged, removed }`, counting cells and listing settlement ids.
+    pub fn set_sea_level(&mut self, new_level: f32, options: JsValue) -> Result<Object, JsValue> {
+        let remove = js::get_bool(&options, "remove_underwater", false);
+        let change = set_sea_level(self, new_level, remove)
+            .map_err(|message| JsValue::from_str(&message))?;
+        let ids = |ids: &[u32]| js_sys::Array::from_iter(ids.iter().map(|&id| JsValue::from(id)));
+        let result = Object::new();
+        js::set(&result, "flooded", &JsValue::from(change.flooded as u32));
+        js::set(&result, "exposed", &JsValue::from(change.exposed as u32));
+        js::set(&result, "flagged", &ids(&change.flagged).into());
+        js::set(&result, "removed", &ids(&change.removed).into());
+        Ok(result)
+    }
+}
+
+pub(crate) fn set_sea_level(
+    map: &mut MapResult,
+    new_level: f32,
+    remove_underwater: bool,
+) -> Result<SeaLevelChange, String> {
+    if !new_level.is_finite() {
+        return Err("sea level must be finite".to_string());
+    }
+    let was_water: Vec<bool> = (0..map.biome.len())
+        .map(|index| map.is_water_body(index))
+        .collect();
+
+    map.sea_level = new_level.clamp(0.0, 1.0);
+    if map.settings.lake_outflows {
+        route_lake_water(map);
+    } else {
+        let max_flow = map.flow.iter().fold(0.0f32, |acc, &v| acc.max(v));
+        for index in 0..map.water.len() {
+            map.water[index] = cell_water(
+                map.heightmap[index],
+                map.flow[index],
+                max_flow,
+                map.sea_level,
+            );
+        }
+    }
+    let cells: Vec<usize> = (0..map.heightmap.len()).collect();
+    rederive_climate(map, &cells);
+
+    let mut change = SeaLevelChange {
+        flooded: 0,
+        exposed: 0,
+        flagged: Vec::new(),
+        removed: Vec::new(),
+    };
+    for (index, was_water) in was_water.into_iter().enumerate() {
+        match (was_water, map.is_water_body(index)) {
+            (false, true) => change.flooded += 1,
+            (true, false) => change.exposed += 1,
+            _ => {}
+        }
+    }
+
+    let full = full_rect(map.width, map.height);
+    map.changes.record(&[Layer::Water], full);
+    map.changes.record(&Layer::CLIMATE, full);
+    let before: Vec<Option<SettlementIssue>> = map
+        .settlements
+        .iter()
+        .map(|settlement| settlement.issue)
+        .collect();
+    flag_settlements(map, full);
+    for (settlement, before) in map.settlements.iter().zip(before) {
+        if settlement.issue == Some(SettlementIssue::Underwater)
+            && before != Some(SettlementIssue::Underwater)
+        {
+            change.flagged.push(settlement.id);
+        }
+    }
+
+    if remove_underwater {
+        map.settlements.retain(|settlement| {
+            if settlement.issue == Some(SettlementIssue::Underwater) {
+                change.removed.push(settlement.id);
+                false
+            } else {
+                true
+            }
+        });
+        if !change.removed.is_empty() {
+            map.road_graph = build_roads(&map.settlements);
+            let roads = &map.road_graph;
+            map.history.road_eras.retain(|road, _| roads.contains(road));
+            map.invalidate_settlements();
+        }
+    }
+
+    map.invalidate_terrain();
+    Ok(change)
+}
+
+pub(crate) fn apply_brush(
+    map: &mut MapResult,
+    brush: Brush,
+    world_x: f32,
+    world_y: f32,
+    radius: f32,
+    strength: f32,
+) -> Result<u32, String> {
+    if !(world_x.is_finite() && world_y.is_finite() && strength.is_finite()) {
+        return Err("brush position and strength must be finite".to_string());
+    }
+    if !(radius.is_finite() && radius > 0.0) {
+        return Err("brush radius must be positive".to_string());
+    }
+
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let (cx, cy) = map.world_to_cell(world_x, world_y);
+    let rx = radius / REGION_SIZE * map.width as f32;
+    let ry = radius / REGION_SIZE * map.height as f32;
+    let x0 = (cx - rx).ceil().max(0.0);
+    let y0 = (cy - ry).ceil().max(0.0);
+    let x1 = (cx + rx).floor().min((width - 1) as f32);
+    let y1 = (cy + ry).floor().min((height - 1) as f32);
+    if x0 > x1 || y0 > y1 {
+        return Ok(0);
+    }
+    let bounds = CellRect {
+        x0: x0 as usize,
+        y0: y0 as usize,
+        x1: x1 as usize,
+        y1: y1 as usize,
+    };
+
+    let (center_x, center_y) = map.nearest_cell(world_x, world_y);
+    let flatten_target = map.heightmap[center_y * width + center_x];
+    let blend = strength.clamp(0.0, 1.0);
+
+    // Collected first so smoothing reads unmodified neighbours.
+    let mut updates = Vec::new();
+    let mut touched: Option<CellRect> = None;
+    for index in bounds.cells(width) {
+        let (x, y) = (index % width, index / width);
+        let distance = distance(map.cell_to_world(index), (world_x, world_y));
+        if distance >= radius {
+            continue;
+        }
+        let weight = smoothstep(1.0 - distance / radius);
+        let current = map.heightmap[index];
+        let target = match brush {
+            Brush::Raise => current + strength * weight,
+            Brush::Lower => current - strength * weight,
+            Brush::Flatten => current + (flatten_target - current) * blend * weight,
+            Brush::Smooth => {
+                let mean = neighborhood_mean(&map.heightmap, width, height, x, y);
+                current + (mean - current) * blend * weight
+            }
+        }
+        .clamp(0.0, 1.0);
+        if target != current {
+            updates.push((index, target));
+            let cell = CellRect {
+                x0: x,
+                y0: y,
+                x1: x,
+                y1: y,
+            };
+            touched = Some(touched.map_or(cell, |rect| rect.union(cell)));
+        }
+    }
+
+    for &(index, value) in &updates {
+        map.heightmap[index] = value;
+    }
+    if let Some(touched) = touched {
+        map.mark_dirty(touched);
+    }
+    Ok(updates.len() as u32)
+}
+
+pub(crate) fn erode_steps(map: &mut MapResult, steps: u32) -> u32 {
+    if steps > 0 {
+        apply_thermal_erosion(
+            &mut map.heightmap,
+            map.width,
+            map.height,
+            steps,
+            &map.settings,
+        );
+        map.settings.erosion_iterations = map.settings.erosion_iterations.saturating_add(steps);
+        map.mark_dirty(full_rect(map.width, map.height));
+    }
+    map.settings.erosion_iterations
+}
+
+/// Rederives every layer inside `rect` from the heightmap. Flow from cells
+/// just outside the rectangle that drain into it is carried in from their
+/// stored values, so a full-map rectangle reproduces generation exactly.
+/// Partial rectangles on `lake_outflows` maps fall back to plain steepest
+/// descent.
+pub(crate) fn recompute(map: &mut MapResult, rect: CellRect) {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let cells: Vec<usize> = rect.cells(width).collect();
+
+    // Lake basins route over the whole map, so only a full pass can redo them.
+    if map.settings.lake_outflows && cells.len() == map.heightmap.len() {
+        route_lake_water(map);
+    } else {
+        for &index in &cells {
+            map.flow[index] = 1.0;
+        }
+        let ring = rect.padded(1, width, height);
+        for index in ring.cells(width) {
+            if rect.contains(index % width, index / width) {
+                continue;
+            }
+            if let Some(target) = downslope(&map.heightmap, map.width, map.height, index) {
+                if rect.contains(target % width, target / width) {
+                    map.flow[target] += map.flow[index];
+                }
+            }
+        }
+
+        let mut order = cells.clone();
+        order.sort_by(|a, b| {
+            map.heightmap[*b]
+                .total_cmp(&map.heightmap[*a])
+                .then(a.cmp(b))
+        });
+        for &index in &order {
+            if let Some(target) = downslope(&map.heightmap, map.width, map.height, index) {
+                if rect.contains(target % width, target / width) {
+                    map.flow[target] += map.flow[index];
+                }
+            }
+        }
+
+        let max_flow = map.flow.iter().fold(0.0f32, |acc, &v| acc.max(v));
+        for &index in &cells {
+            map.water[index] = cell_water(
+                map.heightmap[index],
+                map.flow[index],
+                max_flow,
+                map.sea_level,
+            );
+        }
+    }
+    rederive_climate(map, &cells);
+    flag_settlements(map, rect);
+
+    map.changes.record(&[Layer::Flow, Layer::Water], rect);
+    map.changes.record(&Layer::CLIMATE, rect);
+    map.dirty = None;
+    map.invalidate_terrain();
+}
+
+/// Rederives flow and water over the whole map through `lake_routing`, as
+/// `lake_outflows` generation does.
+fn route_lake_water(map: &mut MapResult) {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    // Evaporation reads temperature, which does not depend on water;
+    // refresh it first so later `lake_routing` calls agree.
+    for index in 0..map.heightmap.len() {
+        map.temperature[index] = cell_temperature(
+            (index / width) as f32 / height as f32,
+            map.heightmap[index],
+            map.sea_level,
+        );
+    }
+    let routing = map.lake_routing();
+    map.water = routing.water(&map.heightmap, map.sea_level);
+    map.flow = routing.flow;
+}
+
+/// Refreshes temperature, moisture, and biome for `cells` from the current
+/// height, flow, and water layers.
+pub(crate) fn rederive_climate(map: &mut MapResult, cells: &[usize]) {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let max_flow = map.flow.iter().fold(0.0f32, |acc, &v| acc.max(v));
+    for &index in cells {
+        let elevation = map.heightmap[index];
+        let water = map.water[index];
+        let temperature = cell_temperature(
+            (index / width) as f32 / height as f32,
+            elevation,
+            map.sea_level,
+        );
+        let moisture = enhanced_moisture(
+            map.base_moisture[index],
+            water,
+            map.flow[index],
+            max_flow,
+            &map.settings,
+        );
+        map.temperature[index] = temperature;
+        map.moisture[index] = moisture;
+        map.biome[index] = classify_cell(
+            elevation,
+            water,
+            temperature,
+            moisture,
+            map.sea_level,
+            &map.settings.biome_table,
+        )
+        .code();
+    }
+    apply_shores(
+        &map.heightmap,
+        &mut map.biome,
+        width,
+        height,
+        map.sea_level,
+        &map.settings,
+        cells.iter().copied(),
+    );
+}
+
+/// Re-evaluates the ground under settlements inside `rect`, setting or
+/// clearing their `issue`.
+pub(crate) fn flag_settlements(map: &mut MapResult, rect: CellRect) {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    for i in 0..map.settlements.len() {
+        let (x, y) = map.nearest_cell(map.settlements[i].x, map.settlements[i].y);
+        if !rect.contains(x, y) {
+            continue;
+        }
+        let index = y * width + x;
+        // Same flatness cutoff that settlement placement uses.
+        map.settlements[i].issue =
+            if map.is_water_body(index) || map.heightmap[index] <= map.sea_level {
+                Some(SettlementIssue::Underwater)
+            } else if local_flatness(&map.heightmap, width, height, x, y) > 0.08 {
+                Some(SettlementIssue::Cliff)
+            } else {
+                None
+            };
+    }
+}
+
+fn neighborhood_mean(heightmap: &[f32], width: usize, height: usize, x: usize, y: usize) -> f32 {
+    let mut total = 0.0f32;
+    let mut count = 0.0f32;
+    for ny in y.saturating_sub(1)..=(y + 1).min(height - 1) {
+        for nx in x.saturating_sub(1)..=(x + 1).min(width - 1) {
+            total += heightmap[ny * width + nx];
+            count += 1.0;
+        }
+    }
+    total / count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        apply_brush, erode_steps, recompute, set_sea_level, Brush, SettlementIssue,
+        RECOMPUTE_MARGIN,
+    };
+    use crate::biome::Biome;
+    use crate::changes::full_rect;
+    use crate::{build_roads, generate, GenerationSettings, MapResult};
+
+    #[test]
+    fn full_recompute_after_a_no_op_brush_reproduces_generation() {
+        let settings = GenerationSettings::new(9, 0.42, 1.0, 40.0, 2, 1.0);
+        let generated = generate(64, 64, &settings, None);
+        let mut map = generate(64, 64, &settings, None);
+        map.flow.fill(1.0);
+        map.water.fill(0.0);
+        map.biome.fill(Biome::Ocean.code());
+        assert_eq!(
+            apply_brush(&mut map, Brush::Raise, 1000.0, 1000.0, 200.0, 0.0),
+            Ok(0)
+        );
+        assert!(map.dirty.is_none());
+
+        let full = full_rect(map.width, map.height);
+        recompute(&mut map, full);
+        assert_eq!(map.flow, generated.flow);
+        assert_eq!(map.water, generated.water);
+        assert_eq!(map.temperature, generated.temperature);
+        assert_eq!(map.moisture, generated.moisture);
+        assert_eq!(map.biome, generated.biome);
+    }
+
+    #[test]
+    fn region_recompute_stays_local_and_flags_drowned_settlements() {
+        let settings = GenerationSettings::new(9, 0.42, 1.0, 40.0, 2, 1.0);
+        let mut map = generate(64, 64, &settings, None);
+        let (width, height) = (map.width as usize, map.height as usize);
+        let town = map.settlements[0].clone();
+        let brushed = apply_brush(&mut map, Brush::Lower, town.x, town.y, 96.0, 1.0).unwrap();
+        assert!(brushed > 0);
+        let rect =
+            map.dirty
+                .expect("the brush marks its cells")
+                .padded(RECOMPUTE_MARGIN, width, height);
+        let (biome, water) = (map.biome.clone(), map.water.clone());
+        let issues: Vec<_> = map.settlements.iter().map(|s| s.issue).collect();
+
+        recompute(&mut map, rect);
+        assert!(map.dirty.is_none());
+        assert!(map.settlements[0].issue == Some(SettlementIssue::Underwater));
+        let (x, y) = map.nearest_cell(town.x, town.y);
+        assert_eq!(map.biome[y * width + x], Biome::Ocean.code());
+        for index in 0..width * height {
+            if !rect.contains(index % width, index / width) {
+                assert_eq!(map.biome[index], biome[index]);
+                assert_eq!(map.water[index], water[index]);
+            }
+        }
+        // Settlements outside the edit are left as they were.
+        for (settlement, issue) in map.settlements.iter().zip(issues).skip(1) {
+            let (x, y) = map.nearest_cell(settlement.x, settlement.y);
+            if !rect.contains(x, y) {
+                assert!(settlement.issue == issue);
+            }
+        }
+    }
+
+    #[test]
+    fn erosion_steps_compose() {
+        let settings = |iterations| GenerationSettings::new(4, 0.42, 1.0, 40.0, iterations, 1.0);
+        let uneroded = generate(64, 64, &settings(2), None);
+        let mut split = generate(64, 64, &settings(2), None);
+        assert_eq!(erode_steps(&mut split, 3), 5);
+        assert_ne!(split.heightmap, uneroded.heightmap);
+        assert_eq!(erode_steps(&mut split, 3), 8);
+        assert!(split.dirty.is_some());
+
+        let mut once = generate(64, 64, &settings(2), None);
+        assert_eq!(erode_steps(&mut once, 6), 8);
+        assert_eq!(split.heightmap, once.heightmap);
+
+        // Nothing distinguishes the result from eroding during generation.
+        let generated = generate(64, 64, &settings(8), None);
+        assert_eq!(split.heightmap, generated.heightmap);
+        assert_eq!(erode_steps(&mut split, 0), 8);
+    }
+
+    #[test]
+    fn sea_level_counts_flooded_and_exposed_cells() {
+        let mut map = generate(
+            48,
+            48,
+            &GenerationSettings::new(6, 0.42, 1.0, 40.0, 2, 1.0),
+            None,
+        );
+        let water = |map: &MapResult| {
+            (0..map.biome.len())
+                .map(|index| map.is_water_body(index))
+                .collect::<Vec<_>>()
+        };
+        let count = |from: &[bool], to: &[bool], was: bool| {
+            from.iter()
+                .zip(to)
+                .filter(|&(&before, &after)| before == was && after != was)
+                .count()
+        };
+
+        let before = water(&map);
+        let level = map.sea_level + 0.05;
+        let risen = set_sea_level(&mut map, level, false).unwrap();
+        let after = water(&map);
+        assert!(risen.flooded > 0);
+        assert_eq!(risen.flooded, count(&before, &after, false));
+        assert_eq!(risen.exposed, count(&before, &after, true));
+
+        let level = map.sea_level - 0.1;
+        let fallen = set_sea_level(&mut map, level, false).unwrap();
+        let lowered = water(&map);
+        assert!(fallen.exposed > 0);
+        assert_eq!(fallen.flooded, count(&after, &lowered, false));
+        assert_eq!(fallen.exposed, count(&after, &lowered, true));
+        assert!(set_sea_level(&mut map, f32::NAN, false).is_err());
+    }
+
+    #[test]
+    fn drowned_settlements_are_removed_and_roads_rebuilt() {
+        let mut map = generate(
+            64,
+            64,
+            &GenerationSettings::new(6, 0.42, 1.0, 40.0, 2, 1.0),
+            None,
+        );
+        let ground = |map: &MapResult, id: usize| {
+            let (x, y) = map.nearest_cell(map.settlements[id].x, map.settlements[id].y);
+            map.heightmap[y * map.width as usize + x]
+        };
+        let lowest = (0..map.settlements.len())
+            .min_by(|&a, &b| ground(&map, a).total_cmp(&ground(&map, b)))
+            .unwrap();
+        let count = map.settlements.len();
+        let level = ground(&map, lowest) + 0.001;
+        let change = set_sea_level(&mut map, level, true).unwrap();
+
+        assert!(change.removed.contains(&(lowest as u32)));
+        assert_eq!(change.removed, change.flagged);
+        assert_eq!(map.settlements.len(), count - change.removed.len());
+        assert!(map.settlements.len() >= 2, "the test needs roads left");
+        assert_eq!(map.road_graph, build_roads(&map.settlements));
+        for &(a, b) in &map.road_graph {
+            assert!(map.settlement(a).is_some() && map.settlement(b).is_some());
+        }
+        for road in map.history.road_eras.keys() {
+            assert!(map.road_graph.contains(road));
+        }
+    }
+
+    #[test]
+    fn sea_level_keeps_routed_lakes() {
+        let mut lakes_seen = 0;
+        for seed in 0..10 {
+            let settings = GenerationSettings {
+                lake_outflows: true,
+                ..GenerationSettings::new(seed, 0.42, 1.0, 40.0, 2, 1.0)
+            };
+            let mut map = generate(48, 48, &settings, None);
+            let lake_cells = |map: &MapResult| {
+                map.biome
+                    .iter()
+                    .filter(|&&biome| biome == Biome::Lake.code())
+                    .count()
+            };
+            let (water, lakes) = (map.water.clone(), lake_cells(&map));
+            let sea_level = map.sea_level;
+            set_sea_level(&mut map, sea_level, false).unwrap();
+            assert_eq!(map.water, water, "seed {seed}");
+            assert_eq!(lake_cells(&map), lakes, "seed {seed}");
+            lakes_seen += lakes;
+        }
+        assert!(lakes_seen > 0);
+    }
+}
@@ -0,0 +1,130 @@
+#[cfg(feature = "wasm")]
+use js_sys::{Float32Array, Uint8Array};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::geometry::{segment_distance, smoothstep};
+#[cfg(feature = "wasm")]
+use crate::query::points_from_js;
+use crate::{MapResult, REGION_SIZE};
+
+/// Default width, in world units, of the fade beyond a reveal radius.
+pub(crate) const DEFAULT_REVEAL_EDGE: f32 = 32.0;
+
+/// Per-cell fog of war: 0 hidden, 255 revealed. Reveals blend with `max`, so
+/// revealed ground never fogs over again.
+pub(crate) struct Exploration {
+    pub mask: Vec<u8>,
+    pub edge: f32,
+}
+
+impl Exploration {
+    pub(crate) fn new(cells: usize) -> Self {
+        Self {
+            mask: vec![0; cells],
+            edge: DEFAULT_REVEAL_EDGE,
+        }
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Exploration mask, one byte per cell: 0 hidden, 255 revealed, with
+    /// intermediate values along soft edges.
+    pub fn exploration(&self) -> Uint8Array {
+        Uint8Array::from(self.exploration.mask.as_slice())
+    }
+
+    /// Restores a mask previously read from `exploration()`, so a campaign
+    /// can persist its progress alongside the map.
+    pub fn set_exploration(&mut self, mask: &Uint8Array) -> Result<(), JsValue> {
+        if mask.length() as usize != self.exploration.mask.len() {
+            return Err(JsValue::from_str(
+                "exploration mask must hold one byte per cell",
+            ));
+        }
+        mask.copy_to(&mut self.exploration.mask);
+        Ok(())
+    }
+
+    /// Sets the fade width, in world units, applied beyond later reveals.
+    pub fn set_reveal_edge(&mut self, width: f32) {
+        self.exploration.edge = if width.is_finite() {
+            width.max(0.0)
+        } else {
+            0.0
+        };
+    }
+
+    /// Reveals everything within `radius` world units of a point.
+    pub fn reveal_circle(&mut self, world_x: f32, world_y: f32, radius: f32) {
+        reveal_segment(self, (world_x, world_y), (world_x, world_y), radius);
+    }
+
+    /// Reveals a corridor of `radius` world units along a polyline of
+    /// interleaved `x, y` world coordinates.
+    pub fn reveal_path(&mut self, points: &Float32Array, radius: f32) -> Result<(), JsValue> {
+        let points = points_from_js(points)?;
+        let points: Vec<(f32, f32)> = points.chunks_exact(2).map(|p| (p[0], p[1])).collect();
+        match points.as_slice() {
+            [] => {}
+            [only] => reveal_segment(self, *only, *only, radius),
+            _ => {
+                for pair in points.windows(2) {
+                    reveal_segment(self, pair[0], pair[1], radius);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Share of the map revealed, weighting soft edges by their opacity.
+    pub fn explored_fraction(&self) -> f32 {
+        let mask = &self.exploration.mask;
+        let total: u64 = mask.iter().map(|&value| value as u64).sum();
+        (total as f64 / (mask.len() as f64 * 255.0)) as f32
+    }
+}
+
+/// Max-blends a capsule around the segment `from`-`to` into the mask.
+pub(crate) fn reveal_segment(map: &mut MapResult, from: (f32, f32), to: (f32, f32), radius: f32) {
+    let finite = [radius, from.0, from.1, to.0, to.1]
+        .iter()
+        .all(|value| value.is_finite());
+    if !finite || radius < 0.0 {
+        return;
+    }
+    let edge = map.exploration.edge;
+    let reach = radius + edge;
+    let width = map.width as usize;
+    let cell_w = REGION_SIZE / map.width as f32;
+    let cell_h = REGION_SIZE / map.height as f32;
+    let x0 = ((from.0.min(to.0) - reach) / cell_w).floor().max(0.0) as usize;
+    let y0 = ((from.1.min(to.1) - reach) / cell_h).floor().max(0.0) as usize;
+    let x1 = ((from.0.max(to.0) + reach) / cell_w)
+        .ceil()
+        .min((map.width - 1) as f32);
+    let y1 = ((from.1.max(to.1) + reach) / cell_h)
+        .ceil()
+        .min((map.height - 1) as f32);
+    if x1 < 0.0 || y1 < 0.0 {
+        return;
+    }
+    for y in y0..=y1 as usize {
+        for x in x0..=x1 as usize {
+            let point = (x as f32 * cell_w, y as f32 * cell_h);
+            let distance = segment_distance(point, from, to);
+            let opacity = if distance <= radius {
+                1.0
+            } else if distance < reach {
+                1.0 - smoothstep((distance - radius) / edge)
+            } else {
+                continue;
+            };
+            let value = (opacity * 255.0).round() as u8;
+            let cell = &mut map.exploration.mask[y * width + x];
+            *cell = (*cell).max(value);
+        }
+    }
+}
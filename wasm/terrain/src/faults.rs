@@ -0,0 +1,354 @@
+use std::f32::consts::TAU;
+
+use noise::{NoiseFn, OpenSimplex};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::geometry::{distance, polyline_length};
+use crate::json::Json;
+use crate::poi::{Poi, PoiKind};
+use crate::{MapResult, SimpleRng, REGION_SIZE};
+
+/// Faults traced per map.
+const FAULT_COUNT: u32 = 5;
+/// Land cells tried per fault before giving up on it.
+const START_ATTEMPTS: u32 = 64;
+/// World length of one polyline segment.
+const STEP: f32 = 16.0;
+/// Segments traced each way from the starting point, at most.
+const MAX_STEPS: u32 = 40;
+/// Feature size, in world units, of the noise bending the curves.
+const BEND_SCALE: f64 = 420.0;
+/// Heading change per segment at full bend noise, in radians.
+const MAX_TURN: f32 = 0.3;
+/// Depth below sea level at which ordinary faults stop.
+const DEEP_OCEAN: f32 = 0.08;
+/// Share of faults that run on into deep ocean as trenches.
+const TRENCH_CHANCE: f32 = 0.2;
+/// Elevation offset on either side of a fully active fault.
+const SCARP_HEIGHT: f32 = 0.01;
+/// World distance from the line at which the scarp offset peaks.
+const SCARP_WIDTH: f32 = 20.0;
+/// World length over which scarps taper in at the fault ends.
+const SCARP_TAPER: f32 = 64.0;
+/// World distance within which faults heat the ground and raise danger.
+const FAULT_REACH: f32 = 96.0;
+/// Geothermal sites placed per fault, at most.
+const SITES_PER_FAULT: usize = 3;
+/// World distance kept between geothermal sites.
+const SITE_SPACING: f32 = 64.0;
+/// Normalized land height above which vents are fumaroles.
+const FUMAROLE_ELEVATION: f32 = 0.5;
+/// Fault heat above which vents are geysers.
+const GEYSER_HEAT: f32 = 0.6;
+
+/// A fault line in world coordinates.
+#[derive(Clone)]
+pub struct Fault {
+    pub points: Vec<(f32, f32)>,
+    /// 0..1; drives scarp height, geothermal sites, and danger.
+    pub activity: f32,
+    /// Whether the fault was allowed to run into deep ocean.
+    pub trench: bool,
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Fault lines as `{ id, points, activity, trench }`, with `points` as
+    /// interleaved world `x, y`. Empty unless generated with `faults`.
+    pub fn faults(&self) -> JsValue {
+        let records = self
+            .faults
+            .iter()
+            .enumerate()
+            .map(|(id, fault)| {
+                let points = fault
+                    .points
+                    .iter()
+                    .flat_map(|&(x, y)| [Json::from(x), Json::from(y)])
+                    .collect::<Vec<_>>();
+                Json::object()
+                    .with("id", id as u32)
+                    .with("points", points)
+                    .with("activity", fault.activity)
+                    .with("trench", fault.trench)
+            })
+            .collect::<Vec<_>>();
+        Json::from(records).to_js()
+    }
+}
+
+impl MapResult {
+    /// Per-cell fault heat: the strongest `activity * (1 - distance /
+    /// FAULT_REACH)` over all faults. Cached until the terrain changes.
+    pub(crate) fn fault_heat(&self) -> &[f32] {
+        self.cache.fault_heat.get_or_init(|| {
+            let mut heat = vec![0.0f32; self.heightmap.len()];
+            for (index, value) in heat.iter_mut().enumerate() {
+                let (x, y) = self.cell_to_world(index);
+                for fault in &self.faults {
+                    let distance = nearest_on(fault, x, y).distance;
+                    *value = value.max(fault.activity * (1.0 - distance / FAULT_REACH).max(0.0));
+                }
+            }
+            heat
+        })
+    }
+
+    /// Adds hot spring, geyser, and fumarole POIs along the faults, hottest
+    /// ground first. Returns the number placed.
+    pub(crate) fn add_geothermal(&mut self) -> u32 {
+        let heat = self.fault_heat();
+        let mut rng = SimpleRng::new(self.settings.seed.wrapping_add(887));
+        let mut candidates: Vec<(f32, usize)> = (0..heat.len())
+            .filter(|&index| {
+                heat[index] > 0.0 && !self.is_water_body(index) && !self.is_river(index)
+            })
+            .map(|index| (heat[index] * (0.5 + 0.5 * rng.next_f32()), index))
+            .collect();
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+
+        let limit = self.faults.len() * SITES_PER_FAULT;
+        let mut sites: Vec<(PoiKind, f32, f32)> = Vec::new();
+        for (_, index) in candidates {
+            if sites.len() >= limit {
+                break;
+            }
+            let (x, y) = self.cell_to_world(index);
+            if sites
+                .iter()
+                .any(|&(_, sx, sy)| distance((sx, sy), (x, y)) < SITE_SPACING)
+            {
+                continue;
+            }
+            let elevation =
+                (self.heightmap[index] - self.sea_level) / (1.0 - self.sea_level).max(f32::EPSILON);
+            let kind = if elevation > FUMAROLE_ELEVATION {
+                PoiKind::Fumarole
+            } else if heat[index] > GEYSER_HEAT {
+                PoiKind::Geyser
+            } else {
+                PoiKind::HotSpring
+            };
+            sites.push((kind, x, y));
+        }
+
+        let era = self.history.current_era();
+        sites
+            .into_iter()
+            .filter_map(|(kind, x, y)| {
+                self.register_poi(Poi {
+                    id: 0,
+                    kind,
+                    x,
+                    y,
+                    era,
+                    reason: None,
+                    coverage: None,
+                })
+            })
+            .count() as u32
+    }
+}
+
+/// Traces `FAULT_COUNT` noise-bent curves from random land cells. Ordinary
+/// faults stop where the sea gets deeper than `DEEP_OCEAN`; trenches carry
+/// on until they leave the map.
+pub(crate) fn trace_faults(
+    heightmap: &[f32],
+    width: usize,
+    height: usize,
+    sea_level: f32,
+    seed: u32,
+) -> Vec<Fault> {
+    let mut rng = SimpleRng::new(seed.wrapping_add(881));
+    let bend = OpenSimplex::new(seed.wrapping_add(883));
+    let height_at = |x: f32, y: f32| {
+        let cx = ((x / REGION_SIZE * width as f32) as usize).min(width - 1);
+        let cy = ((y / REGION_SIZE * height as f32) as usize).min(height - 1);
+        heightmap[cy * width + cx]
+    };
+
+    let mut faults = Vec::new();
+    for _ in 0..FAULT_COUNT {
+        let start = (0..START_ATTEMPTS)
+            .map(|_| (rng.next_f32() * REGION_SIZE, rng.next_f32() * REGION_SIZE))
+            .find(|&(x, y)| height_at(x, y) > sea_level);
+        let heading = rng.next_f32() * TAU;
+        let activity = 0.2 + 0.8 * rng.next_f32();
+        let trench = rng.next_f32() < TRENCH_CHANCE;
+        let Some(start) = start else {
+            continue;
+        };
+
+        let walk = |heading: f32, salt: f64| {
+            let mut points = Vec::new();
+            let (mut x, mut y, mut heading) = (start.0, start.1, heading);
+            for _ in 0..MAX_STEPS {
+                heading += bend.get([x as f64 / BEND_SCALE, y as f64 / BEND_SCALE, salt]) as f32
+                    * MAX_TURN;
+                let (nx, ny) = (x + heading.cos() * STEP, y + heading.sin() * STEP);
+                if !(0.0..REGION_SIZE).contains(&nx) || !(0.0..REGION_SIZE).contains(&ny) {
+                    break;
+                }
+                if !trench && height_at(nx, ny) < sea_level - DEEP_OCEAN {
+                    break;
+                }
+                (x, y) = (nx, ny);
+                points.push((x, y));
+            }
+            points
+        };
+        let mut points = walk(heading + TAU / 2.0, 0.5);
+        points.reverse();
+        points.push(start);
+        points.extend(walk(heading, 0.0));
+        if points.len() >= 2 {
+            faults.push(Fault {
+                points,
+                activity,
+                trench,
+            });
+        }
+    }
+    faults
+}
+
+/// Offsets the heightmap into a small step across each fault: one side
+/// rises and the other sinks by up to `SCARP_HEIGHT * activity`, fading with
+/// distance from the line and tapering in at the fault ends.
+pub(crate) fn apply_scarps(heightmap: &mut [f32], width: usize, height: usize, faults: &[Fault]) {
+    let cell_w = REGION_SIZE / width as f32;
+    let cell_h = REGION_SIZE / height as f32;
+    // x * exp(-x²) peaks at 1 / sqrt(2 e) when x = 1 / sqrt(2).
+    let peak = (0.5f32).sqrt() * (-0.5f32).exp();
+    for fault in faults {
+        let total = length(&fault.points);
+        for (index, value) in heightmap.iter_mut().enumerate() {
+            let x = (index % width) as f32 * cell_w;
+            let y = (index / width) as f32 * cell_h;
+            let nearest = nearest_on(fault, x, y);
+            if nearest.distance > SCARP_WIDTH * 4.0 {
+                continue;
+            }
+            let taper = (nearest.along.min(total - nearest.along) / SCARP_TAPER).clamp(0.0, 1.0);
+            let t = nearest.distance / SCARP_WIDTH * (0.5f32).sqrt();
+            let profile = t * (-t * t).exp() / peak;
+            *value += nearest.side * profile * taper * SCARP_HEIGHT * fault.activity;
+        }
+    }
+}
+
+struct Nearest {
+    distance: f32,
+    /// +1 left of the line, -1 right of it.
+    side: f32,
+    /// World distance along the fault to the nearest point.
+    along: f32,
+}
+
+fn nearest_on(fault: &Fault, x: f32, y: f32) -> Nearest {
+    let mut best = Nearest {
+        distance: f32::INFINITY,
+        side: 1.0,
+        along: 0.0,
+    };
+    let mut walked = 0.0;
+    for pair in fault.points.windows(2) {
+        let ((ax, ay), (bx, by)) = (pair[0], pair[1]);
+        let (dx, dy) = (bx - ax, by - ay);
+        let span = dx.hypot(dy);
+        let t = if span > 0.0 {
+            (((x - ax) * dx + (y - ay) * dy) / (span * span)).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let (px, py) = (ax + dx * t, ay + dy * t);
+        let distance = distance((x, y), (px, py));
+        if distance < best.distance {
+            let cross = dx * (y - ay) - dy * (x - ax);
+            best = Nearest {
+                distance,
+                side: if cross >= 0.0 { 1.0 } else { -1.0 },
+                along: walked + span * t,
+            };
+        }
+        walked += span;
+    }
+    best
+}
+
+fn length(points: &[(f32, f32)]) -> f32 {
+    polyline_length(points, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{trace_faults, DEEP_OCEAN, FAULT_REACH};
+    use crate::poi::PoiKind;
+    use crate::{generate, GenerationSettings, REGION_SIZE};
+
+    #[test]
+    fn faults_stay_off_deep_ocean_and_heat_geothermal_sites() {
+        let plain = GenerationSettings {
+            seed: 12,
+            ..GenerationSettings::default()
+        };
+        let faulted = GenerationSettings {
+            faults: true,
+            fault_scarps: true,
+            ..plain.clone()
+        };
+        let off = generate(96, 96, &plain, None);
+        assert!(off.faults.is_empty());
+        assert!(off.pois.iter().all(|poi| poi.kind == PoiKind::Ruin));
+
+        let map = generate(96, 96, &faulted, None);
+        assert!(!map.faults.is_empty());
+        assert_ne!(map.heightmap, off.heightmap);
+        let unscarped = generate(
+            96,
+            96,
+            &GenerationSettings {
+                fault_scarps: false,
+                ..faulted.clone()
+            },
+            None,
+        );
+        assert_eq!(unscarped.heightmap, off.heightmap);
+
+        let traced = trace_faults(&off.heightmap, 96, 96, off.sea_level, 12);
+        for fault in traced.iter().filter(|fault| !fault.trench) {
+            for &(x, y) in &fault.points {
+                assert!((0.0..REGION_SIZE).contains(&x) && (0.0..REGION_SIZE).contains(&y));
+                let (cx, cy) = (
+                    (x / REGION_SIZE * 96.0) as usize,
+                    (y / REGION_SIZE * 96.0) as usize,
+                );
+                assert!(off.heightmap[cy * 96 + cx] >= off.sea_level - DEEP_OCEAN);
+            }
+        }
+
+        let vents: Vec<_> = map
+            .pois
+            .iter()
+            .filter(|poi| {
+                matches!(
+                    poi.kind,
+                    PoiKind::HotSpring | PoiKind::Geyser | PoiKind::Fumarole
+                )
+            })
+            .collect();
+        assert!(!vents.is_empty());
+        for vent in vents {
+            let near = map.faults.iter().any(|fault| {
+                fault
+                    .points
+                    .iter()
+                    .any(|&(x, y)| (x - vent.x).hypot(y - vent.y) <= FAULT_REACH + 16.0)
+            });
+            assert!(near);
+        }
+    }
+}
@@ -0,0 +1,285 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::biome::Biome;
+use crate::coastal::bay_regions;
+use crate::distance::chamfer_distance;
+use crate::geometry::distance;
+use crate::json::Json;
+use crate::naming::place_name;
+use crate::{MapResult, DIRECTIONS, REGION_SIZE};
+
+/// Smallest sea worth naming, as a fraction of the map.
+const MIN_SEA_FRACTION: f32 = 0.002;
+/// Coastline concavities narrower than twice this many world units can close
+/// off a bay.
+const BAY_CLOSING_RADIUS: f32 = 96.0;
+/// Minimum `area / mouth_width²` for a concavity to count as a bay rather
+/// than a gentle curve of the coast.
+const MIN_BAY_ENCLOSURE: f32 = 0.6;
+/// Smallest bay worth naming, as a fraction of the map.
+const MIN_BAY_FRACTION: f32 = 0.0005;
+/// Share of the range between sea level and the highest peak above which a
+/// cell counts as mountainous.
+const RANGE_ELEVATION: f32 = 0.75;
+/// High ground separated by less than this many world units joins one range.
+const RANGE_GAP: f32 = 32.0;
+/// Smallest range worth naming, as a fraction of the map.
+const MIN_RANGE_FRACTION: f32 = 0.001;
+
+#[derive(Clone, Copy)]
+pub(crate) enum FeatureKind {
+    Sea,
+    Bay,
+    Range,
+}
+
+impl FeatureKind {
+    fn key(self) -> &'static str {
+        match self {
+            FeatureKind::Sea => "sea",
+            FeatureKind::Bay => "bay",
+            FeatureKind::Range => "range",
+        }
+    }
+
+    fn label(self, name: String) -> String {
+        match self {
+            FeatureKind::Sea => format!("Sea of {name}"),
+            FeatureKind::Bay => format!("{name} Bay"),
+            FeatureKind::Range => format!("{name} Mountains"),
+        }
+    }
+}
+
+/// A labeled region. Seas and bays measure area; ranges measure length along
+/// their principal axis.
+pub(crate) struct NamedFeature {
+    pub kind: FeatureKind,
+    pub name: String,
+    pub anchor: usize,
+    pub size: f32,
+    /// Inclusive cell bounds `(min_x, min_y, max_x, max_y)`.
+    pub bounds: (usize, usize, usize, usize),
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Named seas, bays, and mountain ranges as `{ kind, name, anchor_x,
+    /// anchor_y, area_or_length, extent: { min_x, min_y, max_x, max_y } }` in
+    /// world units. Names are deterministic for the map seed.
+    pub fn named_features(&self) -> JsValue {
+        let records = named_features(self)
+            .iter()
+            .map(|feature| Json::from_record(feature.record(self)))
+            .collect::<Vec<_>>();
+        Json::from(records).to_js()
+    }
+}
+
+pub(crate) fn named_features(map: &MapResult) -> Vec<NamedFeature> {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let cells = map.biome.len() as f32;
+    let ocean: Vec<bool> = map
+        .biome
+        .iter()
+        .map(|&biome| biome == Biome::Ocean.code())
+        .collect();
+    let land: Vec<bool> = ocean.iter().map(|&ocean| !ocean).collect();
+    let distance_to_land = chamfer_distance(&land, width, height);
+    let mut features = Vec::new();
+
+    for sea in components(&ocean, width, height) {
+        if (sea.len() as f32) < (cells * MIN_SEA_FRACTION).max(16.0) {
+            continue;
+        }
+        let anchor = farthest(map, &sea, &distance_to_land);
+        features.push(feature(
+            map,
+            FeatureKind::Sea,
+            &sea,
+            anchor,
+            sea.len() as f32 * map.cell_area(),
+        ));
+    }
+
+    let min_bay_cells = (cells * MIN_BAY_FRACTION).max(8.0) as usize;
+    for bay in bay_regions(
+        &ocean,
+        &distance_to_land,
+        width,
+        height,
+        BAY_CLOSING_RADIUS,
+        MIN_BAY_ENCLOSURE,
+        min_bay_cells,
+    ) {
+        let anchor = farthest(map, &bay.cells, &distance_to_land);
+        let area = bay.cells.len() as f32 * map.cell_area();
+        features.push(feature(map, FeatureKind::Bay, &bay.cells, anchor, area));
+    }
+
+    let peak = map.heightmap.iter().copied().fold(map.sea_level, f32::max);
+    let threshold = map.sea_level + (peak - map.sea_level) * RANGE_ELEVATION;
+    let high: Vec<bool> = map
+        .heightmap
+        .iter()
+        .enumerate()
+        .map(|(index, &elevation)| !ocean[index] && elevation >= threshold && peak > map.sea_level)
+        .collect();
+    let near_high: Vec<bool> = chamfer_distance(&high, width, height)
+        .iter()
+        .map(|&distance| distance <= RANGE_GAP * 0.5)
+        .collect();
+    for group in components(&near_high, width, height) {
+        let range: Vec<usize> = group.into_iter().filter(|&index| high[index]).collect();
+        if (range.len() as f32) < (cells * MIN_RANGE_FRACTION).max(6.0) {
+            continue;
+        }
+        let (anchor, length) = principal_axis(map, &range);
+        features.push(feature(map, FeatureKind::Range, &range, anchor, length));
+    }
+
+    for (index, feature) in features.iter_mut().enumerate() {
+        let salt = ((feature.kind as u32) << 16) | index as u32;
+        feature.name = feature.kind.label(place_name(map.settings.seed, salt));
+    }
+    features
+}
+
+fn feature(
+    map: &MapResult,
+    kind: FeatureKind,
+    cells: &[usize],
+    anchor: usize,
+    size: f32,
+) -> NamedFeature {
+    let width = map.width as usize;
+    let mut bounds = (usize::MAX, usize::MAX, 0, 0);
+    for &index in cells {
+        let (x, y) = (index % width, index / width);
+        bounds = (
+            bounds.0.min(x),
+            bounds.1.min(y),
+            bounds.2.max(x),
+            bounds.3.max(y),
+        );
+    }
+    NamedFeature {
+        kind,
+        name: String::new(),
+        anchor,
+        size,
+        bounds,
+    }
+}
+
+/// The member cell farthest from land and the map border, where a water
+/// label has the most room.
+fn farthest(map: &MapResult, cells: &[usize], distance_to_land: &[f32]) -> usize {
+    let room = |index: usize| {
+        let (x, y) = map.cell_to_world(index);
+        let border = x.min(y).min(REGION_SIZE - x).min(REGION_SIZE - y);
+        distance_to_land[index].min(border)
+    };
+    cells
+        .iter()
+        .copied()
+        .max_by(|&a, &b| room(a).total_cmp(&room(b)).then(b.cmp(&a)))
+        .unwrap_or(0)
+}
+
+/// Member cell nearest the centroid, and the world extent along the
+/// direction of greatest spread.
+fn principal_axis(map: &MapResult, cells: &[usize]) -> (usize, f32) {
+    let points: Vec<(f32, f32)> = cells
+        .iter()
+        .map(|&index| map.cell_to_world(index))
+        .collect();
+    let count = points.len() as f32;
+    let (mean_x, mean_y) = points.iter().fold((0.0, 0.0), |acc, p| {
+        (acc.0 + p.0 / count, acc.1 + p.1 / count)
+    });
+    let (mut xx, mut xy, mut yy) = (0.0f32, 0.0f32, 0.0f32);
+    for &(x, y) in &points {
+        let (dx, dy) = (x - mean_x, y - mean_y);
+        xx += dx * dx;
+        xy += dx * dy;
+        yy += dy * dy;
+    }
+    let angle = 0.5 * (2.0 * xy).atan2(xx - yy);
+    let (ux, uy) = (angle.cos(), angle.sin());
+    let (lo, hi) = points
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), p| {
+            let t = (p.0 - mean_x) * ux + (p.1 - mean_y) * uy;
+            (lo.min(t), hi.max(t))
+        });
+    let anchor = cells
+        .iter()
+        .zip(&points)
+        .min_by(|a, b| {
+            let da = distance(*a.1, (mean_x, mean_y));
+            let db = distance(*b.1, (mean_x, mean_y));
+            da.total_cmp(&db)
+        })
+        .map_or(0, |(&index, _)| index);
+    (anchor, hi - lo)
+}
+
+pub(crate) fn neighbors(index: usize, width: usize, height: usize) -> impl Iterator<Item = usize> {
+    let (x, y) = ((index % width) as i32, (index / width) as i32);
+    DIRECTIONS.iter().filter_map(move |&(dx, dy)| {
+        let (nx, ny) = (x + dx, y + dy);
+        (nx >= 0 && ny >= 0 && nx < width as i32 && ny < height as i32)
+            .then(|| ny as usize * width + nx as usize)
+    })
+}
+
+/// 8-connected components of `mask`, in row-major order of their first cell.
+pub(crate) fn components(mask: &[bool], width: usize, height: usize) -> Vec<Vec<usize>> {
+    let mut visited = vec![false; mask.len()];
+    let mut groups = Vec::new();
+    for start in 0..mask.len() {
+        if visited[start] || !mask[start] {
+            continue;
+        }
+        visited[start] = true;
+        let mut group = Vec::new();
+        let mut stack = vec![start];
+        while let Some(index) = stack.pop() {
+            group.push(index);
+            for next in neighbors(index, width, height) {
+                if !visited[next] && mask[next] {
+                    visited[next] = true;
+                    stack.push(next);
+                }
+            }
+        }
+        groups.push(group);
+    }
+    groups
+}
+
+impl NamedFeature {
+    pub(crate) fn record(&self, map: &MapResult) -> Vec<(&'static str, Json)> {
+        let (anchor_x, anchor_y) = map.cell_to_world(self.anchor);
+        let cell_w = REGION_SIZE / map.width as f32;
+        let cell_h = REGION_SIZE / map.height as f32;
+        let (min_x, min_y, max_x, max_y) = self.bounds;
+        let extent = Json::object()
+            .with("min_x", min_x as f32 * cell_w)
+            .with("min_y", min_y as f32 * cell_h)
+            .with("max_x", max_x as f32 * cell_w)
+            .with("max_y", max_y as f32 * cell_h);
+        vec![
+            ("kind", self.kind.key().into()),
+            ("name", self.name.as_str().into()),
+            ("anchor_x", anchor_x.into()),
+            ("anchor_y", anchor_y.into()),
+            ("area_or_length", self.size.into()),
+            ("extent", extent),
+        ]
+    }
+}
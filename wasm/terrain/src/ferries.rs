@@ -0,0 +1,129 @@
+use std::collections::VecDeque;
+
+use crate::features::neighbors;
+use crate::geometry::distance;
+use crate::render::raster_line;
+use crate::MapResult;
+
+/// A straight crossing over open water between two shore cells.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Crossing {
+    pub from: usize,
+    pub to: usize,
+    /// World distance between the two shore cells.
+    pub length: f32,
+}
+
+/// Cells reachable from `start` over finite `costs`, 8-connected.
+pub(crate) fn reachable(costs: &[f32], width: usize, height: usize, start: usize) -> Vec<bool> {
+    let mut seen = vec![false; costs.len()];
+    let mut queue = VecDeque::from([start]);
+    seen[start] = true;
+    while let Some(index) = queue.pop_front() {
+        for next in neighbors(index, width, height) {
+            if !seen[next] && costs[next].is_finite() {
+                seen[next] = true;
+                queue.push_back(next);
+            }
+        }
+    }
+    seen
+}
+
+/// Scans the shores of `near` and `far` inside `corridor` for the closest
+/// opposing pair whose straight line runs over lakes or ocean only, up to
+/// `max_length` world units. Ties go to the lowest cell indices, so the
+/// choice is stable.
+pub(crate) fn narrowest_crossing(
+    map: &MapResult,
+    near: &[bool],
+    far: &[bool],
+    corridor: impl Fn(usize) -> bool,
+    max_length: f32,
+) -> Option<Crossing> {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let shore = |side: &[bool]| -> Vec<usize> {
+        (0..side.len())
+            .filter(|&index| {
+                side[index]
+                    && corridor(index)
+                    && neighbors(index, width, height).any(|next| map.is_water_body(next))
+            })
+            .collect()
+    };
+    let (near, far) = (shore(near), shore(far));
+
+    let mut pairs: Vec<(f32, usize, usize)> = Vec::new();
+    for &from in &near {
+        let (ax, ay) = map.cell_to_world(from);
+        for &to in &far {
+            let (bx, by) = map.cell_to_world(to);
+            let length = distance((ax, ay), (bx, by));
+            if length <= max_length {
+                pairs.push((length, from, to));
+            }
+        }
+    }
+    pairs.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+    pairs
+        .into_iter()
+        .find(|&(_, from, to)| {
+            let line = raster_line((from % width, from / width), (to % width, to / width));
+            line.len() > 2
+                && line[1..line.len() - 1]
+                    .iter()
+                    .all(|&(x, y)| map.is_water_body(y * width + x))
+        })
+        .map(|(length, from, to)| Crossing { from, to, length })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{narrowest_crossing, reachable};
+    use crate::biome::Biome;
+    use crate::generate_map;
+
+    #[test]
+    fn picks_the_narrowest_opposing_shores_of_a_strait() {
+        // Land above and below a strait that pinches from 27 water cells
+        // wide at the edges to 7 around column 40.
+        let mut map = generate_map(64, 64, 1, 0.42, 1.0, 40.0, 0, 1.0);
+        let half_gap = |x: usize| 4 + (x as i32 - 40).unsigned_abs() as usize / 4;
+        for index in 0..map.heightmap.len() {
+            let (x, y) = (index % 64, index / 64);
+            let water = y.abs_diff(32) < half_gap(x);
+            map.heightmap[index] = if water { 0.2 } else { 0.5 };
+            map.water[index] = if water { 1.0 } else { 0.0 };
+            map.biome[index] = if water {
+                Biome::Ocean.code()
+            } else {
+                Biome::TemperateGrassland.code()
+            };
+        }
+        let costs: Vec<f32> = (0..map.heightmap.len())
+            .map(|index| {
+                if map.is_water_body(index) {
+                    f32::INFINITY
+                } else {
+                    1.0
+                }
+            })
+            .collect();
+        let north = reachable(&costs, 64, 64, 5 * 64 + 5);
+        let south = reachable(&costs, 64, 64, 60 * 64 + 5);
+        assert!(!north[60 * 64 + 5]);
+
+        let crossing = narrowest_crossing(&map, &north, &south, |_| true, 512.0).unwrap();
+        assert!((37..=43).contains(&(crossing.from % 64)));
+        assert_eq!(crossing.to % 64, crossing.from % 64);
+        assert_eq!(crossing.to / 64 - crossing.from / 64, 8);
+        assert!((crossing.length - 8.0 * 32.0).abs() < 1e-3);
+
+        // Only the wide western end lies inside this corridor.
+        let west =
+            narrowest_crossing(&map, &north, &south, |index| index % 64 < 8, 1024.0).unwrap();
+        assert!(west.length > crossing.length);
+        assert!(narrowest_crossing(&map, &north, &south, |_| true, 200.0).is_none());
+    }
+}
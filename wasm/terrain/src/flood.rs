@@ -0,0 +1,335 @@
+#[cfg(feature = "wasm")]
+use js_sys::{Array, Float32Array, Object, Uint8Array};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::hydrology::ocean_spill_levels;
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::{MapResult, DIRECTIONS, REGION_SIZE};
+
+/// Which neighbors join the filled region.
+#[derive(Clone, Copy)]
+pub(crate) enum FloodPredicate {
+    /// Same biome code as the start cell.
+    SameBiome,
+    /// Elevation within `delta` of the start cell.
+    Elevation(f32),
+    /// Any cell carrying water: ocean, lakes, and rivers.
+    Water,
+}
+
+pub(crate) struct FloodArea {
+    pub visited: Vec<bool>,
+    pub cells: usize,
+    /// Inclusive cell bounds `(min_x, min_y, max_x, max_y)`.
+    pub bounds: (usize, usize, usize, usize),
+    pub touches_border: bool,
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Flood-fills from the cell nearest a world position. `options.mode` is
+    /// `"biome"` (default), `"elevation"` (with `options.delta`), or `"water"`;
+    /// `options.diagonal` (default true) selects 8- over 4-connectivity.
+    /// Returns `{ cells, area, min_x, min_y, max_x, max_y, touches_border }`
+    /// in world units, plus a 0/1 `mask` when `options.mask` is true.
+    pub fn flood_area(
+        &self,
+        world_x: f32,
+        world_y: f32,
+        options: JsValue,
+    ) -> Result<Object, JsValue> {
+        let predicate = match js::get_string(&options, "mode").as_deref() {
+            None | Some("biome") => FloodPredicate::SameBiome,
+            Some("elevation") => FloodPredicate::Elevation(js::get_f32(&options, "delta", 0.02)),
+            Some("water") => FloodPredicate::Water,
+            Some(other) => return Err(JsValue::from_str(&format!("unknown flood mode: {other}"))),
+        };
+        let diagonal = js::get_bool(&options, "diagonal", true);
+        let (x, y) = self.nearest_cell(world_x, world_y);
+        let fill = flood_area(self, y * self.width as usize + x, predicate, diagonal);
+
+        let cell_w = REGION_SIZE / self.width as f32;
+        let cell_h = REGION_SIZE / self.height as f32;
+        let (min_x, min_y, max_x, max_y) = fill.bounds;
+        let result = Object::new();
+        js::set(&result, "cells", &JsValue::from(fill.cells as u32));
+        js::set(
+            &result,
+            "area",
+            &JsValue::from(fill.cells as f32 * self.cell_area()),
+        );
+        js::set(&result, "min_x", &JsValue::from(min_x as f32 * cell_w));
+        js::set(&result, "min_y", &JsValue::from(min_y as f32 * cell_h));
+        js::set(&result, "max_x", &JsValue::from(max_x as f32 * cell_w));
+        js::set(&result, "max_y", &JsValue::from(max_y as f32 * cell_h));
+        js::set(
+            &result,
+            "touches_border",
+            &JsValue::from(fill.touches_border),
+        );
+        if js::get_bool(&options, "mask", false) {
+            let mask: Vec<u8> = fill.visited.iter().map(|&v| v as u8).collect();
+            js::set(&result, "mask", &Uint8Array::from(mask.as_slice()).into());
+        }
+        Ok(result)
+    }
+}
+
+/// Iterative fill with an explicit stack, so map size never affects call depth.
+/// A start cell that fails its own predicate (dry land in `Water` mode) yields
+/// an empty area.
+pub(crate) fn flood_area(
+    map: &MapResult,
+    start: usize,
+    predicate: FloodPredicate,
+    diagonal: bool,
+) -> FloodArea {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let start_biome = map.biome[start];
+    let start_elevation = map.heightmap[start];
+    let matches = |index: usize| match predicate {
+        FloodPredicate::SameBiome => map.biome[index] == start_biome,
+        FloodPredicate::Elevation(delta) => (map.heightmap[index] - start_elevation).abs() <= delta,
+        FloodPredicate::Water => map.water[index] > 0.0,
+    };
+
+    let mut visited = vec![false; map.heightmap.len()];
+    let mut fill = FloodArea {
+        visited: Vec::new(),
+        cells: 0,
+        bounds: (start % width, start / width, start % width, start / width),
+        touches_border: false,
+    };
+    if !matches(start) {
+        fill.visited = visited;
+        return fill;
+    }
+
+    visited[start] = true;
+    let mut stack = vec![start];
+    while let Some(index) = stack.pop() {
+        let x = index % width;
+        let y = index / width;
+        fill.cells += 1;
+        fill.bounds.0 = fill.bounds.0.min(x);
+        fill.bounds.1 = fill.bounds.1.min(y);
+        fill.bounds.2 = fill.bounds.2.max(x);
+        fill.bounds.3 = fill.bounds.3.max(y);
+        if x == 0 || y == 0 || x == width - 1 || y == height - 1 {
+            fill.touches_border = true;
+        }
+        for (dx, dy) in DIRECTIONS {
+            if !diagonal && dx != 0 && dy != 0 {
+                continue;
+            }
+            let nx = x as i32 + dx;
+            let ny = y as i32 + dy;
+            if nx < 0 || ny < 0 || nx >= width as i32 || ny >= height as i32 {
+                continue;
+            }
+            let next = ny as usize * width + nx as usize;
+            if !visited[next] && matches(next) {
+                visited[next] = true;
+                stack.push(next);
+            }
+        }
+    }
+    fill.visited = visited;
+    fill
+}
+
+/// Land drowned by one sea level in `flood_analysis`.
+pub(crate) struct FloodLevel {
+    pub level: f32,
+    /// Newly submerged cells, ascending.
+    pub cells: Vec<usize>,
+    pub settlements: Vec<u32>,
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// What drowns at each absolute sea level in `levels`, flooding only land
+    /// connected to the ocean below that level. Returns one `{ level,
+    /// flooded_cells, flooded_area, settlements }` per level, in input order,
+    /// with a 0/1 `mask` of newly flooded cells when `options.mask` is true.
+    pub fn flood_analysis(&self, levels: &Float32Array, options: JsValue) -> Array {
+        let with_mask = js::get_bool(&options, "mask", false);
+        let size = self.heightmap.len();
+        flood_analysis(self, &levels.to_vec())
+            .into_iter()
+            .map(|flood| {
+                let result = Object::new();
+                js::set(&result, "level", &JsValue::from(flood.level));
+                js::set(
+                    &result,
+                    "flooded_cells",
+                    &JsValue::from(flood.cells.len() as u32),
+                );
+                js::set(
+                    &result,
+                    "flooded_area",
+                    &JsValue::from(flood.cells.len() as f32 * self.cell_area()),
+                );
+                let ids = Array::from_iter(flood.settlements.iter().map(|&id| JsValue::from(id)));
+                js::set(&result, "settlements", &ids.into());
+                if with_mask {
+                    let mut mask = vec![0u8; size];
+                    for &index in &flood.cells {
+                        mask[index] = 1;
+                    }
+                    js::set(&result, "mask", &Uint8Array::from(mask.as_slice()).into());
+                }
+                JsValue::from(result)
+            })
+            .collect()
+    }
+}
+
+/// Spill levels are computed once; each level then takes the prefix of dry
+/// cells sorted by spill level, so a descending sequence only ever trims the
+/// previous answer.
+pub(crate) fn flood_analysis(map: &MapResult, levels: &[f32]) -> Vec<FloodLevel> {
+    let spill = ocean_spill_levels(map);
+    let mut dry: Vec<usize> = (0..spill.len())
+        .filter(|&index| !map.is_water_body(index))
+        .collect();
+    dry.sort_by(|&a, &b| spill[a].total_cmp(&spill[b]).then(a.cmp(&b)));
+    let settlement_cells: Vec<(u32, usize)> = map
+        .settlements
+        .iter()
+        .map(|settlement| {
+            let (x, y) = map.nearest_cell(settlement.x, settlement.y);
+            (settlement.id, y * map.width as usize + x)
+        })
+        .collect();
+
+    levels
+        .iter()
+        .map(|&level| {
+            let count = dry.partition_point(|&index| spill[index] <= level);
+            let mut cells = dry[..count].to_vec();
+            cells.sort_unstable();
+            let settlements = settlement_cells
+                .iter()
+                .filter(|(_, index)| cells.binary_search(index).is_ok())
+                .map(|&(id, _)| id)
+                .collect();
+            FloodLevel {
+                level,
+                cells,
+                settlements,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{flood_analysis, flood_area, FloodPredicate};
+    use crate::biome::Biome;
+    use crate::{generate_map, MapResult, Settlement, REGION_SIZE};
+
+    const SIZE: usize = 32;
+
+    /// Ocean down the west edge, low land east of it, and a pit walled off
+    /// by a high ring around `(20, 16)`.
+    fn walled_pit() -> MapResult {
+        let mut map = generate_map(SIZE as u32, SIZE as u32, 3, 0.42, 1.0, 40.0, 0, 1.0);
+        map.sea_level = 0.4;
+        for index in 0..SIZE * SIZE {
+            let (x, y) = (index % SIZE, index / SIZE);
+            let ring = x.abs_diff(20).max(y.abs_diff(16));
+            let (height, biome) = match (x, ring) {
+                (0..4, _) => (0.3, Biome::Ocean),
+                (_, 0..=2) => (0.45, Biome::TemperateGrassland),
+                (_, 3) => (0.7, Biome::TemperateGrassland),
+                _ => (0.5, Biome::TemperateGrassland),
+            };
+            map.heightmap[index] = height;
+            map.water[index] = if biome == Biome::Ocean { 1.0 } else { 0.0 };
+            map.biome[index] = biome.code();
+        }
+        let cell = REGION_SIZE / SIZE as f32;
+        map.settlements = [(10, 10), (20, 16)]
+            .into_iter()
+            .enumerate()
+            .map(|(id, (x, y))| Settlement {
+                id: id as u32,
+                x: x as f32 * cell,
+                y: y as f32 * cell,
+                size: 1.0,
+                issue: None,
+                era: 0,
+                name: None,
+            })
+            .collect();
+        map.invalidate_settlements();
+        map.invalidate_terrain();
+        map
+    }
+
+    #[test]
+    fn enclosed_depressions_stay_dry_until_the_sea_tops_their_rim() {
+        let map = walled_pit();
+        let pit = 16 * SIZE + 20;
+        let floods = flood_analysis(&map, &[0.55, 0.75, 0.4]);
+
+        let below_rim = &floods[0];
+        assert!(below_rim.cells.contains(&(10 * SIZE + 10)));
+        assert!(!below_rim.cells.contains(&pit));
+        assert!(below_rim
+            .cells
+            .iter()
+            .all(|&index| map.heightmap[index] < 0.7));
+        assert_eq!(below_rim.settlements, vec![0]);
+
+        let over_rim = &floods[1];
+        assert!(over_rim.cells.contains(&pit));
+        assert_eq!(over_rim.cells.len(), SIZE * SIZE - 4 * SIZE);
+        assert_eq!(over_rim.settlements, vec![0, 1]);
+
+        assert!(floods[2].cells.is_empty());
+    }
+
+    #[test]
+    fn fills_follow_their_mode_and_connectivity() {
+        let mut map = walled_pit();
+        let at = |x: usize, y: usize| y * SIZE + x;
+        // Two forest cells touching only at a corner, and a wet strip.
+        map.biome[at(10, 10)] = Biome::TemperateForest.code();
+        map.biome[at(11, 11)] = Biome::TemperateForest.code();
+        for x in 4..8 {
+            map.water[at(x, 5)] = 0.4;
+        }
+
+        let diagonal = flood_area(&map, at(10, 10), FloodPredicate::SameBiome, true);
+        assert_eq!(diagonal.cells, 2);
+        assert_eq!(diagonal.bounds, (10, 10, 11, 11));
+        assert!(!diagonal.touches_border);
+        let straight = flood_area(&map, at(10, 10), FloodPredicate::SameBiome, false);
+        assert_eq!(straight.cells, 1);
+        assert!(straight.visited[at(10, 10)] && !straight.visited[at(11, 11)]);
+
+        // The pit floor alone sits within 0.1 of its centre.
+        let pit = flood_area(&map, at(20, 16), FloodPredicate::Elevation(0.1), true);
+        assert_eq!(pit.cells, 25);
+        assert_eq!(pit.bounds, (18, 14, 22, 18));
+
+        // Ocean and the strip wading into it carry water; land does not.
+        let sea = flood_area(&map, at(0, 0), FloodPredicate::Water, true);
+        for (index, &water) in map.water.iter().enumerate() {
+            assert_eq!(sea.visited[index], water > 0.0, "cell {index}");
+        }
+        assert!(sea.touches_border);
+        assert_eq!(sea.bounds, (0, 0, 7, SIZE - 1));
+
+        let dry = flood_area(&map, at(20, 16), FloodPredicate::Water, true);
+        assert_eq!(dry.cells, 0);
+        assert!(dry.visited.iter().all(|&visited| !visited));
+    }
+}
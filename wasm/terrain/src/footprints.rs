@@ -0,0 +1,360 @@
+use std::collections::{BinaryHeap, HashMap};
+
+#[cfg(feature = "wasm")]
+use js_sys::Uint32Array;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::distance::chamfer_distance;
+use crate::json::Json;
+use crate::pathfinding::Frontier;
+use crate::{slope_map, MapResult, REGION_SIZE};
+
+/// Label for cells outside every footprint.
+pub(crate) const NO_FOOTPRINT: u32 = u32::MAX;
+/// Built-up world area per unit of settlement size.
+const AREA_PER_SIZE: f32 = 2400.0;
+/// Steepest ground that still gets built on.
+const MAX_BUILD_SLOPE: f32 = 0.15;
+/// World distance over which roads and rivers pull growth along them.
+const HUG_WIDTH: f32 = 32.0;
+/// How much cheaper growth is right beside a road or a river.
+const ROAD_PULL: f32 = 2.0;
+const RIVER_PULL: f32 = 1.0;
+
+const ORTHOGONAL: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+/// Built-up area of one settlement.
+pub(crate) struct Footprint {
+    /// Cells covered, in the order they were claimed; empty when the
+    /// settlement sits in water.
+    pub cells: Vec<usize>,
+    /// Cell count the settlement's size asked for.
+    pub target: usize,
+    /// Outer boundary along cell edges, as world `(x, y)` corners running
+    /// clockwise on screen, without collinear corners.
+    pub outline: Vec<(f32, f32)>,
+    /// Cell deepest inside the footprint, for labels.
+    pub label: Option<usize>,
+}
+
+/// Footprints in `settlements` order plus the per-cell owner layer.
+pub(crate) struct Footprints {
+    pub footprints: Vec<Footprint>,
+    pub owner: Vec<u32>,
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Built-up area of each settlement, in `settlements()` order, as
+    /// `{ id, polygon, area, target_area, complete, label_x, label_y }`.
+    /// `polygon` holds interleaved world corners of the outer boundary, which
+    /// follows cell edges; `complete` is false when water, steep ground, or a
+    /// neighbor stopped growth short of `target_area`; the label point is
+    /// the spot deepest inside the footprint. Footprints grow from the center
+    /// cell over flat, dry land, hugging roads and rivers, and never overlap.
+    pub fn footprints(&self) -> JsValue {
+        let records = self
+            .settlement_footprints()
+            .footprints
+            .iter()
+            .zip(&self.settlements)
+            .map(|(footprint, settlement)| {
+                let mut record = footprint.record(self);
+                record.insert(0, ("id", settlement.id.into()));
+                Json::from_record(record)
+            })
+            .collect::<Vec<_>>();
+        Json::from(records).to_js()
+    }
+
+    /// Index into `settlements()` of the footprint covering each cell, or
+    /// `0xFFFFFFFF` outside every footprint.
+    pub fn footprint_layer(&self) -> Uint32Array {
+        Uint32Array::from(self.settlement_footprints().owner.as_slice())
+    }
+}
+
+impl MapResult {
+    /// Cached until the terrain, settlements, or roads change.
+    pub(crate) fn settlement_footprints(&self) -> &Footprints {
+        self.cache.footprints.get_or_init(|| footprints(self))
+    }
+}
+
+/// Grows every footprint at once from a shared queue, so neighbors split
+/// contested ground instead of the first settlement taking it all. Each
+/// settlement's step costs are divided by the square root of its target, so
+/// larger towns spread proportionally faster.
+pub(crate) fn footprints(map: &MapResult) -> Footprints {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let slopes = slope_map(&map.heightmap, width, height);
+    let road_distance = map.road_distance();
+    let river_distance = map.river_distance();
+    let buildable = |index: usize| {
+        !map.is_water_body(index) && !map.is_river(index) && slopes[index] <= MAX_BUILD_SLOPE
+    };
+    let pull = |index: usize| {
+        let near = |distance: f32| (1.0 - distance / HUG_WIDTH).max(0.0);
+        1.0 + ROAD_PULL * near(road_distance[index]) + RIVER_PULL * near(river_distance[index])
+    };
+    let cell_w = REGION_SIZE / width as f32;
+    let cell_h = REGION_SIZE / height as f32;
+
+    let mut owner = vec![NO_FOOTPRINT; map.heightmap.len()];
+    let mut footprints: Vec<Footprint> = map
+        .settlements
+        .iter()
+        .map(|settlement| Footprint {
+            cells: Vec::new(),
+            target: ((settlement.size * AREA_PER_SIZE / map.cell_area()).round() as usize).max(1),
+            outline: Vec::new(),
+            label: None,
+        })
+        .collect();
+    let scales: Vec<f32> = footprints
+        .iter()
+        .map(|footprint| (footprint.target as f32).sqrt())
+        .collect();
+
+    // Heap entries index `claims`, so equal priorities pop in push order.
+    let mut claims: Vec<(usize, u32)> = Vec::new();
+    let mut heap = BinaryHeap::new();
+    for (position, settlement) in map.settlements.iter().enumerate() {
+        let (x, y) = map.nearest_cell(settlement.x, settlement.y);
+        let center = y * width + x;
+        if map.is_water_body(center) {
+            continue;
+        }
+        heap.push(Frontier {
+            priority: 0.0,
+            index: claims.len(),
+        });
+        claims.push((center, position as u32));
+    }
+    while let Some(Frontier { priority, index }) = heap.pop() {
+        let (cell, position) = claims[index];
+        let footprint = &mut footprints[position as usize];
+        if owner[cell] != NO_FOOTPRINT || footprint.cells.len() >= footprint.target {
+            continue;
+        }
+        owner[cell] = position;
+        footprint.cells.push(cell);
+        let (x, y) = ((cell % width) as i32, (cell / width) as i32);
+        for (dx, dy) in ORTHOGONAL {
+            let (nx, ny) = (x + dx, y + dy);
+            if nx < 0 || ny < 0 || nx >= width as i32 || ny >= height as i32 {
+                continue;
+            }
+            let next = ny as usize * width + nx as usize;
+            if owner[next] != NO_FOOTPRINT || !buildable(next) {
+                continue;
+            }
+            let step = if dx != 0 { cell_w } else { cell_h };
+            heap.push(Frontier {
+                priority: priority + step / pull(next) / scales[position as usize],
+                index: claims.len(),
+            });
+            claims.push((next, position));
+        }
+    }
+
+    // Labels sit on the cell farthest from any edge of its own footprint.
+    let edges: Vec<bool> = (0..owner.len())
+        .map(|cell| owner[cell] != NO_FOOTPRINT && on_edge(&owner, width, height, cell))
+        .collect();
+    let depth = chamfer_distance(&edges, width, height);
+    for footprint in &mut footprints {
+        footprint.label = footprint
+            .cells
+            .iter()
+            .copied()
+            .max_by(|&a, &b| depth[a].total_cmp(&depth[b]).then(b.cmp(&a)));
+        footprint.outline = outline(&owner, width, height, &footprint.cells)
+            .into_iter()
+            .map(|(x, y)| {
+                (
+                    x as f32 / width as f32 * REGION_SIZE,
+                    y as f32 / height as f32 * REGION_SIZE,
+                )
+            })
+            .collect();
+    }
+    Footprints { footprints, owner }
+}
+
+/// Whether any orthogonal neighbor, or the map border, lies outside `cell`'s
+/// footprint.
+fn on_edge(owner: &[u32], width: usize, height: usize, cell: usize) -> bool {
+    let (x, y) = ((cell % width) as i32, (cell / width) as i32);
+    ORTHOGONAL.iter().any(|&(dx, dy)| {
+        let (nx, ny) = (x + dx, y + dy);
+        nx < 0
+            || ny < 0
+            || nx >= width as i32
+            || ny >= height as i32
+            || owner[ny as usize * width + nx as usize] != owner[cell]
+    })
+}
+
+/// Outer boundary of `cells` in cell-corner coordinates. Every exposed cell
+/// side becomes an edge running clockwise around its cell; chaining them
+/// prefers turning clockwise, which splits loops where two cells touch only
+/// at a corner. Holes are dropped by keeping the loop of largest area.
+fn outline(owner: &[u32], width: usize, height: usize, cells: &[usize]) -> Vec<(i32, i32)> {
+    let Some(&first) = cells.first() else {
+        return Vec::new();
+    };
+    let label = owner[first];
+    let inside = |x: i32, y: i32| {
+        x >= 0
+            && y >= 0
+            && x < width as i32
+            && y < height as i32
+            && owner[y as usize * width + x as usize] == label
+    };
+    let mut outgoing: HashMap<(i32, i32), Vec<(i32, i32)>> = HashMap::new();
+    let mut sorted = cells.to_vec();
+    sorted.sort_unstable();
+    for &cell in &sorted {
+        let (x, y) = ((cell % width) as i32, (cell / width) as i32);
+        let corners = [(x, y), (x + 1, y), (x + 1, y + 1), (x, y + 1)];
+        for (side, &(dx, dy)) in ORTHOGONAL.iter().enumerate() {
+            if !inside(x + dx, y + dy) {
+                let (from, to) = (corners[side], corners[(side + 1) % 4]);
+                outgoing.entry(from).or_default().push(to);
+            }
+        }
+    }
+
+    let mut best: (i64, Vec<(i32, i32)>) = (0, Vec::new());
+    let mut starts: Vec<(i32, i32)> = outgoing.keys().copied().collect();
+    starts.sort_unstable_by_key(|&(x, y)| (y, x));
+    for start in starts {
+        while outgoing.get(&start).is_some_and(|ends| !ends.is_empty()) {
+            let mut ring = vec![start];
+            let mut current = start;
+            let mut heading: Option<(i32, i32)> = None;
+            loop {
+                let ends = outgoing
+                    .get_mut(&current)
+                    .expect("boundary edges form loops");
+                let turn = |end: &(i32, i32)| {
+                    let direction = (end.0 - current.0, end.1 - current.1);
+                    match heading {
+                        Some((hx, hy)) if direction == (-hy, hx) => 0,
+                        Some(heading) if direction == heading => 1,
+                        _ => 2,
+                    }
+                };
+                let (slot, _) = ends
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, end)| turn(end))
+                    .expect("boundary edges form loops");
+                let next = ends.swap_remove(slot);
+                heading = Some((next.0 - current.0, next.1 - current.1));
+                current = next;
+                if current == start {
+                    break;
+                }
+                ring.push(current);
+            }
+            let doubled_area: i64 = (0..ring.len())
+                .map(|i| {
+                    let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+                    a.0 as i64 * b.1 as i64 - b.0 as i64 * a.1 as i64
+                })
+                .sum();
+            if doubled_area.abs() > best.0 {
+                best = (doubled_area.abs(), ring);
+            }
+        }
+    }
+    drop_collinear(best.1)
+}
+
+fn drop_collinear(ring: Vec<(i32, i32)>) -> Vec<(i32, i32)> {
+    let count = ring.len();
+    (0..count)
+        .filter(|&i| {
+            let (prev, point, next) = (
+                ring[(i + count - 1) % count],
+                ring[i],
+                ring[(i + 1) % count],
+            );
+            (point.0 - prev.0) * (next.1 - point.1) != (point.1 - prev.1) * (next.0 - point.0)
+        })
+        .map(|i| ring[i])
+        .collect()
+}
+
+impl Footprint {
+    fn record(&self, map: &MapResult) -> Vec<(&'static str, Json)> {
+        let polygon = self
+            .outline
+            .iter()
+            .flat_map(|&(x, y)| [Json::from(x), Json::from(y)])
+            .collect::<Vec<_>>();
+        // Cell corners sit at `cell_to_world`; labels go at cell centers.
+        let label = self.label.map(|cell| {
+            let (x, y) = map.cell_to_world(cell);
+            (
+                x + REGION_SIZE / map.width as f32 * 0.5,
+                y + REGION_SIZE / map.height as f32 * 0.5,
+            )
+        });
+        vec![
+            ("polygon", polygon.into()),
+            ("area", (self.cells.len() as f32 * map.cell_area()).into()),
+            ("target_area", (self.target as f32 * map.cell_area()).into()),
+            ("complete", (self.cells.len() >= self.target).into()),
+            ("label_x", label.map_or(Json::Null, |(x, _)| x.into())),
+            ("label_y", label.map_or(Json::Null, |(_, y)| y.into())),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{footprints, NO_FOOTPRINT};
+    use crate::generate_map;
+
+    #[test]
+    fn footprints_stay_dry_disjoint_and_closed() {
+        let map = generate_map(128, 128, 5, 0.42, 1.0, 40.0, 2, 1.0);
+        let result = footprints(&map);
+        assert!(!result.footprints.is_empty());
+        let width = map.width as usize;
+        for (position, footprint) in result.footprints.iter().enumerate() {
+            assert!(footprint.cells.len() <= footprint.target);
+            for &cell in &footprint.cells {
+                assert_eq!(result.owner[cell], position as u32);
+                assert!(!map.is_water_body(cell));
+            }
+            if footprint.cells.is_empty() {
+                continue;
+            }
+            // Every corner of the outline lies on the edge of a covered cell.
+            assert!(footprint.outline.len() >= 4);
+            let cell_w = 2048.0 / width as f32;
+            for &(x, y) in &footprint.outline {
+                let (cx, cy) = ((x / cell_w).round() as i64, (y / cell_w).round() as i64);
+                let touches = [(0, 0), (-1, 0), (0, -1), (-1, -1)].iter().any(|(dx, dy)| {
+                    let (nx, ny) = (cx + dx, cy + dy);
+                    nx >= 0
+                        && ny >= 0
+                        && (nx as usize) < width
+                        && (ny as usize) < width
+                        && result.owner[ny as usize * width + nx as usize] == position as u32
+                });
+                assert!(touches);
+            }
+        }
+        let covered = result.owner.iter().filter(|&&o| o != NO_FOOTPRINT).count();
+        let claimed: usize = result.footprints.iter().map(|f| f.cells.len()).sum();
+        assert_eq!(covered, claimed);
+    }
+}
@@ -0,0 +1,179 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::REGION_SIZE;
+
+/// Offset from `from` to `to`. With `wrap`, each axis takes the shorter way
+/// around the region, as on a map whose edges meet.
+pub(crate) fn delta(from: (f32, f32), to: (f32, f32), wrap: bool) -> (f32, f32) {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    if !wrap {
+        return (dx, dy);
+    }
+    let half = REGION_SIZE * 0.5;
+    let shortest = |d: f32| (d + half).rem_euclid(REGION_SIZE) - half;
+    (shortest(dx), shortest(dy))
+}
+
+/// Straight-line distance between two world points.
+pub(crate) fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    wrapped_distance(a, b, false)
+}
+
+pub(crate) fn wrapped_distance(a: (f32, f32), b: (f32, f32), wrap: bool) -> f32 {
+    let (dx, dy) = delta(a, b, wrap);
+    dx.hypot(dy)
+}
+
+/// Compass heading of an offset, in degrees clockwise from north (up the
+/// map, toward smaller `y`).
+pub(crate) fn heading(dx: f32, dy: f32) -> f32 {
+    dx.atan2(-dy).to_degrees().rem_euclid(360.0)
+}
+
+/// Heading from `from` toward `to`; see `heading`.
+pub(crate) fn wrapped_bearing(from: (f32, f32), to: (f32, f32), wrap: bool) -> f32 {
+    let (dx, dy) = delta(from, to, wrap);
+    heading(dx, dy)
+}
+
+/// Distance from `point` to the nearest point of the segment `a`–`b`.
+pub(crate) fn segment_distance(point: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length_sq = dx * dx + dy * dy;
+    let t = if length_sq > 0.0 {
+        (((point.0 - a.0) * dx + (point.1 - a.1) * dy) / length_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    distance(point, (a.0 + dx * t, a.1 + dy * t))
+}
+
+/// Hermite ease of `t` clamped to 0..1, flat at both ends.
+pub(crate) fn smoothstep(t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+pub(crate) fn polyline_length(points: &[(f32, f32)], wrap: bool) -> f32 {
+    points
+        .windows(2)
+        .map(|pair| wrapped_distance(pair[0], pair[1], wrap))
+        .sum()
+}
+
+/// Position and unit forward direction at arc length `station` along a
+/// polyline. Stations past the end give the last point and a zero direction.
+/// With `wrap`, segments take the shorter way around and the position is
+/// folded back into the region.
+pub(crate) fn point_at(
+    points: &[(f32, f32)],
+    station: f32,
+    wrap: bool,
+) -> ((f32, f32), (f32, f32)) {
+    let mut remaining = station;
+    for pair in points.windows(2) {
+        let (dx, dy) = delta(pair[0], pair[1], wrap);
+        let length = dx.hypot(dy);
+        if length <= 0.0 {
+            continue;
+        }
+        let direction = (dx / length, dy / length);
+        if remaining <= length {
+            let t = remaining / length;
+            let (x, y) = (pair[0].0 + dx * t, pair[0].1 + dy * t);
+            let position = if wrap {
+                (x.rem_euclid(REGION_SIZE), y.rem_euclid(REGION_SIZE))
+            } else {
+                (x, y)
+            };
+            return (position, direction);
+        }
+        remaining -= length;
+    }
+    (points[points.len() - 1], (0.0, 0.0))
+}
+
+/// Point `fraction` of the way along a polyline, clamped to its ends.
+pub(crate) fn point_at_fraction(points: &[(f32, f32)], fraction: f32, wrap: bool) -> (f32, f32) {
+    let length = polyline_length(points, wrap);
+    point_at(points, length * fraction.clamp(0.0, 1.0), wrap).0
+}
+
+#[cfg(feature = "wasm")]
+fn pairs(points: &[f32]) -> Result<Vec<(f32, f32)>, JsValue> {
+    if points.len() < 2 || !points.len().is_multiple_of(2) {
+        return Err(JsValue::from_str(
+            "points must hold at least one interleaved x, y pair",
+        ));
+    }
+    Ok(points.chunks(2).map(|pair| (pair[0], pair[1])).collect())
+}
+
+/// Straight-line world distance between two points. With `wrap`, each axis
+/// takes the shorter way around the region.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = distance)]
+pub fn js_distance(x0: f32, y0: f32, x1: f32, y1: f32, wrap: Option<bool>) -> f32 {
+    wrapped_distance((x0, y0), (x1, y1), wrap.unwrap_or(false))
+}
+
+/// Heading from the first point toward the second, in degrees clockwise from
+/// north (up the map). With `wrap`, as for `distance`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = bearing)]
+pub fn js_bearing(x0: f32, y0: f32, x1: f32, y1: f32, wrap: Option<bool>) -> f32 {
+    wrapped_bearing((x0, y0), (x1, y1), wrap.unwrap_or(false))
+}
+
+/// Length of a polyline given as interleaved world `x, y` points, measured
+/// the way roads and sea routes are. With `wrap`, as for `distance`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = polyline_length)]
+pub fn js_polyline_length(points: &[f32], wrap: Option<bool>) -> Result<f32, JsValue> {
+    Ok(polyline_length(&pairs(points)?, wrap.unwrap_or(false)))
+}
+
+/// `[x, y]` of the point `t` (0 to 1, clamped) of the way along a polyline
+/// of interleaved world `x, y` points. With `wrap`, segments take the
+/// shorter way around and the point is folded back into the region.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = point_at_fraction)]
+pub fn js_point_at_fraction(
+    points: &[f32],
+    t: f32,
+    wrap: Option<bool>,
+) -> Result<Vec<f32>, JsValue> {
+    let (x, y) = point_at_fraction(&pairs(points)?, t, wrap.unwrap_or(false));
+    Ok(vec![x, y])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        distance, point_at_fraction, polyline_length, segment_distance, wrapped_bearing,
+        wrapped_distance,
+    };
+    use crate::REGION_SIZE;
+
+    #[test]
+    fn wrapping_takes_the_shorter_way_around() {
+        let (a, b) = ((10.0, 1000.0), (REGION_SIZE - 30.0, 1000.0));
+        assert_eq!(distance(a, b), REGION_SIZE - 40.0);
+        assert_eq!(wrapped_distance(a, b, true), 40.0);
+        assert_eq!(wrapped_bearing(a, b, false), 90.0);
+        assert_eq!(wrapped_bearing(a, b, true), 270.0);
+        assert_eq!(wrapped_bearing((0.0, 10.0), (0.0, 0.0), false), 0.0);
+
+        let line = [a, b];
+        assert_eq!(polyline_length(&line, true), 40.0);
+        let (x, y) = point_at_fraction(&line, 0.5, true);
+        assert!((x - (REGION_SIZE - 10.0)).abs() < 1e-3 && y == 1000.0);
+        assert_eq!(point_at_fraction(&line, 2.0, false), b);
+
+        let (from, to) = ((0.0, 0.0), (10.0, 0.0));
+        assert_eq!(segment_distance((5.0, 3.0), from, to), 3.0);
+        assert_eq!(segment_distance((13.0, 4.0), from, to), 5.0);
+        assert_eq!(segment_distance((3.0, 4.0), from, from), 5.0);
+    }
+}
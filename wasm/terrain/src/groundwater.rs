@@ -0,0 +1,199 @@
+#[cfg(feature = "wasm")]
+use js_sys::Float32Array;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::biome::Biome;
+use crate::geometry::distance;
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::poi::{Poi, PoiKind};
+use crate::{downslope_map, MapResult};
+
+/// Share of stored groundwater lost per cell it seeps through, so aquifers
+/// fed from distant uplands fade out instead of accumulating like rivers.
+const SEEPAGE_DECAY: f32 = 0.08;
+/// 3×3 smoothing passes applied after downslope seepage; groundwater spreads
+/// sideways through rock where surface flow cannot.
+const DIFFUSION_PASSES: u32 = 4;
+
+pub(crate) struct OasisOptions {
+    /// Lowest normalized groundwater an oasis may sit on.
+    pub min_groundwater: f32,
+    /// Highest moisture an oasis cell may have; oases belong in dry land.
+    pub max_moisture: f32,
+    /// Minimum world distance between oases.
+    pub spacing: f32,
+    pub max_count: u32,
+}
+
+impl Default for OasisOptions {
+    fn default() -> Self {
+        Self {
+            min_groundwater: 0.5,
+            max_moisture: 0.35,
+            spacing: 256.0,
+            max_count: 6,
+        }
+    }
+}
+
+impl OasisOptions {
+    #[cfg(feature = "wasm")]
+    fn from_js(options: &JsValue) -> Self {
+        let defaults = Self::default();
+        Self {
+            min_groundwater: js::get_f32(options, "min_groundwater", defaults.min_groundwater),
+            max_moisture: js::get_f32(options, "max_moisture", defaults.max_moisture),
+            spacing: js::get_f32(options, "spacing", defaults.spacing),
+            max_count: js::get_u32(options, "max_count", defaults.max_count),
+        }
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Groundwater proxy per cell, 0..1: rainfall recharge seeping downslope
+    /// with a slow decay and diffusing sideways, independent of surface flow.
+    /// Zero in the ocean.
+    pub fn groundwater(&self) -> Float32Array {
+        Float32Array::from(self.groundwater_map())
+    }
+
+    /// Adds oasis POIs on desert cells with high groundwater but little
+    /// moisture and no surface water, strongest aquifers first. Options:
+    /// `min_groundwater`, `max_moisture`, `spacing` (world units, also kept
+    /// from existing oases), `max_count`. Returns the number placed.
+    pub fn place_oases(&mut self, options: JsValue) -> u32 {
+        self.add_oases(&OasisOptions::from_js(&options))
+    }
+}
+
+impl MapResult {
+    pub(crate) fn add_oases(&mut self, options: &OasisOptions) -> u32 {
+        let sites = oasis_sites(self, options);
+        let era = self.history.current_era();
+        sites
+            .into_iter()
+            .filter_map(|index| {
+                let (x, y) = self.cell_to_world(index);
+                self.register_poi(Poi {
+                    id: 0,
+                    kind: PoiKind::Oasis,
+                    x,
+                    y,
+                    era,
+                    reason: None,
+                    coverage: None,
+                })
+            })
+            .count() as u32
+    }
+
+    pub(crate) fn groundwater_map(&self) -> &[f32] {
+        self.cache
+            .groundwater
+            .get_or_init(|| groundwater_field(self))
+    }
+}
+
+fn groundwater_field(map: &MapResult) -> Vec<f32> {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let ocean: Vec<bool> = map
+        .biome
+        .iter()
+        .map(|&biome| biome == Biome::Ocean.code())
+        .collect();
+    let downslope = downslope_map(&map.heightmap, map.width, map.height);
+    let mut order: Vec<usize> = (0..map.heightmap.len()).collect();
+    order.sort_by(|&a, &b| {
+        map.heightmap[b]
+            .total_cmp(&map.heightmap[a])
+            .then(a.cmp(&b))
+    });
+
+    let mut stored = vec![0.0f32; map.heightmap.len()];
+    for &cell in &order {
+        if ocean[cell] {
+            continue;
+        }
+        stored[cell] += map.base_moisture[cell];
+        if let Some(target) = downslope[cell] {
+            stored[target] += stored[cell] * (1.0 - SEEPAGE_DECAY);
+        }
+    }
+
+    let mut scratch = stored.clone();
+    for _ in 0..DIFFUSION_PASSES {
+        for y in 0..height {
+            for x in 0..width {
+                let index = y * width + x;
+                if ocean[index] {
+                    scratch[index] = 0.0;
+                    continue;
+                }
+                let (mut total, mut count) = (0.0f32, 0.0f32);
+                for ny in y.saturating_sub(1)..(y + 2).min(height) {
+                    for nx in x.saturating_sub(1)..(x + 2).min(width) {
+                        let neighbor = ny * width + nx;
+                        if !ocean[neighbor] {
+                            total += stored[neighbor];
+                            count += 1.0;
+                        }
+                    }
+                }
+                scratch[index] = total / count;
+            }
+        }
+        std::mem::swap(&mut stored, &mut scratch);
+    }
+
+    let peak = stored.iter().copied().fold(0.0f32, f32::max);
+    if peak > 0.0 {
+        // Seepage saturates near recharge / decay; the log keeps broad
+        // aquifers visible next to the few cells where everything converges.
+        let scale = (1.0 + peak).ln();
+        for value in &mut stored {
+            *value = (1.0 + *value).ln() / scale;
+        }
+    }
+    stored
+}
+
+pub(crate) fn oasis_sites(map: &MapResult, options: &OasisOptions) -> Vec<usize> {
+    let groundwater = map.groundwater_map();
+    let mut candidates: Vec<usize> = (0..map.biome.len())
+        .filter(|&index| {
+            map.biome[index] == Biome::Desert.code()
+                && map.water[index] <= 0.0
+                && map.moisture[index] <= options.max_moisture
+                && groundwater[index] >= options.min_groundwater
+        })
+        .collect();
+    candidates.sort_by(|&a, &b| groundwater[b].total_cmp(&groundwater[a]).then(a.cmp(&b)));
+
+    let mut taken: Vec<(f32, f32)> = map
+        .pois
+        .iter()
+        .filter(|poi| poi.kind == PoiKind::Oasis)
+        .map(|poi| (poi.x, poi.y))
+        .collect();
+    let mut sites = Vec::new();
+    for index in candidates {
+        if sites.len() as u32 >= options.max_count {
+            break;
+        }
+        let (x, y) = map.cell_to_world(index);
+        if taken
+            .iter()
+            .any(|&taken| distance(taken, (x, y)) < options.spacing)
+        {
+            continue;
+        }
+        taken.push((x, y));
+        sites.push(index);
+    }
+    sites
+}
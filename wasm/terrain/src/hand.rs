@@ -0,0 +1,116 @@
+#[cfg(feature = "wasm")]
+use js_sys::Float32Array;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::MapResult;
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Height above nearest drainage: how far each cell stands above the
+    /// river, lake, or ocean cell its flow first reaches, in normalized
+    /// height. Drainage cells are 0. Cells whose flow ends in a pit or at
+    /// the map edge without reaching water get their height above sea level.
+    /// Low values flood first.
+    pub fn height_above_drainage(&self) -> Float32Array {
+        Float32Array::from(height_above_drainage(self).as_slice())
+    }
+}
+
+/// HAND over the cached receivers; see `height_above_drainage()`.
+pub(crate) fn height_above_drainage(map: &MapResult) -> Vec<f32> {
+    const UNKNOWN: usize = usize::MAX;
+    const NONE: usize = usize::MAX - 1;
+    let receivers = map.receivers();
+    // Drainage cell each cell reaches, filled in along each walked path.
+    let mut outlet = vec![UNKNOWN; map.heightmap.len()];
+    let mut path = Vec::new();
+    for start in 0..map.heightmap.len() {
+        let mut current = start;
+        let found = loop {
+            if outlet[current] != UNKNOWN {
+                break outlet[current];
+            }
+            if map.is_water_body(current) || map.is_river(current) {
+                break current;
+            }
+            path.push(current);
+            match receivers[current] {
+                // Lake routing never loops, but a cap keeps a bad receiver
+                // table from hanging.
+                Some(next) if path.len() <= map.heightmap.len() => current = next,
+                _ => break NONE,
+            }
+        };
+        for cell in path.drain(..) {
+            outlet[cell] = found;
+        }
+        if outlet[start] == UNKNOWN {
+            outlet[start] = found;
+        }
+    }
+    outlet
+        .iter()
+        .enumerate()
+        .map(|(index, &drain)| {
+            let height = map.heightmap[index];
+            match drain {
+                NONE => (height - map.sea_level).max(0.0),
+                drain => (height - map.heightmap[drain]).max(0.0),
+            }
+        })
+        .collect()
+}
+
+/// HAND of one cell, following only its own flow path.
+pub(crate) fn height_above_drainage_at(map: &MapResult, cell: usize) -> f32 {
+    let receivers = map.receivers();
+    let mut current = cell;
+    let mut steps = 0;
+    let drain = loop {
+        if map.is_water_body(current) || map.is_river(current) {
+            break Some(current);
+        }
+        steps += 1;
+        match receivers[current] {
+            Some(next) if steps <= map.heightmap.len() => current = next,
+            _ => break None,
+        }
+    };
+    let height = map.heightmap[cell];
+    match drain {
+        Some(drain) => (height - map.heightmap[drain]).max(0.0),
+        None => (height - map.sea_level).max(0.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{height_above_drainage, height_above_drainage_at};
+    use crate::generate_map;
+
+    #[test]
+    fn hand_is_zero_on_water_and_rises_away_from_it() {
+        let map = generate_map(96, 96, 4, 0.42, 1.0, 40.0, 2, 1.0);
+        let hand = height_above_drainage(&map);
+        for (index, &value) in hand.iter().enumerate() {
+            assert!(value.is_finite() && value >= 0.0);
+            if map.is_water_body(index) || map.is_river(index) {
+                assert_eq!(value, 0.0);
+            }
+            // Never above the cell's own height over the sea or its drain.
+            assert!(value <= map.heightmap[index]);
+            assert_eq!(value, height_above_drainage_at(&map, index));
+        }
+        // Following flow downhill never climbs in HAND.
+        let receivers = map.receivers();
+        for (index, receiver) in receivers.iter().enumerate() {
+            if let Some(next) = *receiver {
+                if !map.is_water_body(index) && !map.is_river(index) {
+                    assert!(hand[next] <= hand[index] + 1e-6);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,298 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::biome::Biome;
+use crate::features::components;
+use crate::geometry::{distance, heading};
+use crate::json::Json;
+use crate::{MapResult, DIRECTIONS, REGION_SIZE};
+
+/// Settlements this close to the ocean, in world units, run a port.
+pub(crate) const PORT_REACH: f32 = 48.0;
+/// Smallest body of ocean, in square world units, a port can dock on.
+/// Settlements whose only water within reach is smaller are not ports.
+const MIN_HARBOR_WATER: f32 = 4096.0;
+/// Depth below sea level from which a ship has open water.
+const NAVIGABLE_DEPTH: f32 = 0.04;
+/// Longest approach channel traced, in world units.
+const CHANNEL_MAX_LENGTH: f32 = 160.0;
+
+/// Where a port meets the water.
+#[derive(Clone, Debug)]
+pub(crate) struct Harbor {
+    /// Ocean cell the port loads at: the nearest to the settlement.
+    pub dock: usize,
+    /// Cells from `dock` out toward open water, `dock` first.
+    pub channel: Vec<usize>,
+    /// Whether `channel` ends at `NAVIGABLE_DEPTH`.
+    pub open: bool,
+}
+
+impl Harbor {
+    /// Heading ships leave the dock by, in degrees clockwise from north:
+    /// toward the channel's end, or straight off the settlement when the
+    /// channel is the dock alone.
+    pub(crate) fn approach(&self, map: &MapResult, from: (f32, f32)) -> f32 {
+        let dock = map.cell_to_world(self.dock);
+        let toward = match self.channel.last() {
+            Some(&end) if end != self.dock => map.cell_to_world(end),
+            _ => (2.0 * dock.0 - from.0, 2.0 * dock.1 - from.1),
+        };
+        heading(toward.0 - dock.0, toward.1 - dock.1)
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Waterfront of every port as `{ settlement, dock_x, dock_y, approach,
+    /// open, points }`. The dock is the ocean cell nearest the settlement on
+    /// a body of water of at least 4096 square world units; settlements
+    /// with only smaller ponds in reach are not ports. `points` is the
+    /// approach channel from the dock, following the steepest descent of
+    /// the sea floor for at most 160 world units; `open` tells whether it
+    /// reached navigable depth. `approach` is the heading out along it, in
+    /// degrees clockwise from north. Sea routes in `trade_routes()` end at
+    /// the docks.
+    pub fn harbors(&self) -> JsValue {
+        let records = self
+            .settlements
+            .iter()
+            .zip(self.ports())
+            .filter_map(|(settlement, harbor)| {
+                let harbor = harbor.as_ref()?;
+                let (dock_x, dock_y) = self.cell_to_world(harbor.dock);
+                let points = harbor
+                    .channel
+                    .iter()
+                    .flat_map(|&index| {
+                        let (x, y) = self.cell_to_world(index);
+                        [Json::from(x), Json::from(y)]
+                    })
+                    .collect::<Vec<_>>();
+                Some(
+                    Json::object()
+                        .with("settlement", settlement.id)
+                        .with("dock_x", dock_x)
+                        .with("dock_y", dock_y)
+                        .with(
+                            "approach",
+                            harbor.approach(self, (settlement.x, settlement.y)),
+                        )
+                        .with("open", harbor.open)
+                        .with("points", points),
+                )
+            })
+            .collect::<Vec<_>>();
+        Json::from(records).to_js()
+    }
+}
+
+impl MapResult {
+    /// Harbor of each settlement, by position, or `None` for settlements
+    /// that are not ports. Cached with the roads.
+    pub(crate) fn ports(&self) -> &[Option<Harbor>] {
+        self.cache.harbors.get_or_init(|| harbors(self))
+    }
+
+    /// Dock cell of each settlement, by position; see `ports`.
+    pub(crate) fn docks(&self) -> Vec<Option<usize>> {
+        self.ports()
+            .iter()
+            .map(|harbor| harbor.as_ref().map(|harbor| harbor.dock))
+            .collect()
+    }
+}
+
+fn harbors(map: &MapResult) -> Vec<Option<Harbor>> {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let coast = map.coast_distance();
+    let ocean: Vec<bool> = map
+        .biome
+        .iter()
+        .map(|&biome| biome == Biome::Ocean.code())
+        .collect();
+    // Ocean cells of bodies too small to dock on are left out.
+    let cell_area = (REGION_SIZE / width as f32) * (REGION_SIZE / height as f32);
+    let mut harborable = vec![false; ocean.len()];
+    for group in components(&ocean, width, height) {
+        if group.len() as f32 * cell_area >= MIN_HARBOR_WATER {
+            for index in group {
+                harborable[index] = true;
+            }
+        }
+    }
+    map.settlements
+        .iter()
+        .map(|settlement| {
+            let (x, y) = map.nearest_cell(settlement.x, settlement.y);
+            if coast[y * width + x] > PORT_REACH {
+                return None;
+            }
+            let dock = dock(map, (x, y), &harborable)?;
+            let (channel, open) = channel(map, dock, &ocean);
+            Some(Harbor {
+                dock,
+                channel,
+                open,
+            })
+        })
+        .collect()
+}
+
+/// Nearest `harborable` cell within `PORT_REACH`, ties by index.
+fn dock(map: &MapResult, (x, y): (usize, usize), harborable: &[bool]) -> Option<usize> {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let reach_x = (PORT_REACH / (REGION_SIZE / width as f32)).ceil() as usize;
+    let reach_y = (PORT_REACH / (REGION_SIZE / height as f32)).ceil() as usize;
+    let from = map.cell_to_world(y * width + x);
+    let mut best: Option<(f32, usize)> = None;
+    for ny in y.saturating_sub(reach_y)..=(y + reach_y).min(height - 1) {
+        for nx in x.saturating_sub(reach_x)..=(x + reach_x).min(width - 1) {
+            let index = ny * width + nx;
+            if !harborable[index] {
+                continue;
+            }
+            let distance = distance(map.cell_to_world(index), from);
+            if distance <= PORT_REACH && best.is_none_or(|(d, _)| distance < d) {
+                best = Some((distance, index));
+            }
+        }
+    }
+    best.map(|(_, index)| index)
+}
+
+/// Steepest descent over the sea floor from `dock`, one ocean cell at a
+/// time, until the water is `NAVIGABLE_DEPTH` deep. Every step deepens, so
+/// the channel never runs back over a shoal or reef, and diagonal steps
+/// need ocean on both sides so it never slips between two land cells.
+/// Returns the cells and whether open water was reached before the descent
+/// stalled or ran past `CHANNEL_MAX_LENGTH`.
+fn channel(map: &MapResult, dock: usize, ocean: &[bool]) -> (Vec<usize>, bool) {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let sea_level = map.sea_level;
+    let mut cells = vec![dock];
+    let mut length = 0.0;
+    loop {
+        let cell = cells[cells.len() - 1];
+        if sea_level - map.heightmap[cell] >= NAVIGABLE_DEPTH {
+            return (cells, true);
+        }
+        let (x, y) = ((cell % width) as i32, (cell / width) as i32);
+        let here = map.cell_to_world(cell);
+        let mut steepest: Option<(f32, usize, f32)> = None;
+        for (dx, dy) in DIRECTIONS {
+            let (nx, ny) = (x + dx, y + dy);
+            if nx < 0 || ny < 0 || nx >= width as i32 || ny >= height as i32 {
+                continue;
+            }
+            let next = ny as usize * width + nx as usize;
+            let beside = [
+                y as usize * width + nx as usize,
+                ny as usize * width + x as usize,
+            ];
+            if !ocean[next] || !beside.iter().all(|&side| ocean[side]) {
+                continue;
+            }
+            let drop = map.heightmap[cell] - map.heightmap[next];
+            let step = distance(here, map.cell_to_world(next));
+            if drop > 0.0 && steepest.is_none_or(|(best, _, _)| drop / step > best) {
+                steepest = Some((drop / step, next, step));
+            }
+        }
+        match steepest {
+            Some((_, next, step)) if length + step <= CHANNEL_MAX_LENGTH => {
+                length += step;
+                cells.push(next);
+            }
+            _ => return (cells, false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NAVIGABLE_DEPTH;
+    use crate::biome::Biome;
+    use crate::editing::set_sea_level;
+    use crate::{generate_map, MapResult, Settlement, REGION_SIZE};
+
+    const SIZE: usize = 64;
+    const SHORE: f32 = 20.0;
+
+    /// A round island on a shelf that deepens away from the shore, with a
+    /// town on the east coast and one inland beside a one-cell pond.
+    fn island() -> MapResult {
+        let mut map = generate_map(SIZE as u32, SIZE as u32, 3, 0.42, 1.0, 40.0, 0, 1.0);
+        let center = SIZE as f32 / 2.0;
+        let pond = 32 * SIZE + 31;
+        for index in 0..SIZE * SIZE {
+            let radius = ((index % SIZE) as f32 - center).hypot((index / SIZE) as f32 - center);
+            let land = radius < SHORE && index != pond;
+            map.heightmap[index] = if land {
+                map.sea_level + 0.05
+            } else {
+                map.sea_level - 0.01 - (radius - SHORE).max(0.0) * 0.01
+            };
+            map.biome[index] = if land {
+                Biome::TemperateGrassland.code()
+            } else {
+                Biome::Ocean.code()
+            };
+        }
+        let cell = REGION_SIZE / SIZE as f32;
+        map.settlements = [(51, 32), (32, 32)]
+            .into_iter()
+            .enumerate()
+            .map(|(id, (x, y))| Settlement {
+                id: id as u32,
+                x: x as f32 * cell,
+                y: y as f32 * cell,
+                size: 2.0,
+                issue: None,
+                era: 0,
+                name: None,
+            })
+            .collect();
+        map.invalidate_settlements();
+        map.invalidate_terrain();
+        map
+    }
+
+    #[test]
+    fn channels_deepen_out_to_sea_and_ponds_are_not_ports() {
+        let map = island();
+        let port = map.ports()[0].clone().expect("the coastal town is a port");
+        assert_eq!(port.channel[0], port.dock);
+        assert!(port.open);
+        let end = port.channel[port.channel.len() - 1];
+        assert!(map.sea_level - map.heightmap[end] >= NAVIGABLE_DEPTH);
+        for step in port.channel.windows(2) {
+            assert_eq!(map.biome[step[1]], Biome::Ocean.code());
+            assert!(map.heightmap[step[1]] < map.heightmap[step[0]]);
+        }
+        let approach = port.approach(&map, (map.settlements[0].x, map.settlements[0].y));
+        assert!((approach - 90.0).abs() < 30.0, "{approach}");
+        assert!(map.ports()[1].is_none());
+        assert_eq!(map.docks(), vec![Some(port.dock), None]);
+    }
+
+    #[test]
+    fn channels_stop_at_depth_below_the_edited_sea_level() {
+        let mut map = island();
+        let level = map.sea_level + 0.01;
+        set_sea_level(&mut map, level, false).unwrap();
+        let port = map.ports()[0]
+            .clone()
+            .expect("the coastal town is still a port");
+        assert!(port.open);
+        let (end, shallows) = port.channel.split_last().unwrap();
+        assert!(level - map.heightmap[*end] >= NAVIGABLE_DEPTH);
+        for &cell in shallows {
+            assert!(level - map.heightmap[cell] < NAVIGABLE_DEPTH);
+        }
+    }
+}
@@ -0,0 +1,343 @@
+#[cfg(feature = "wasm")]
+use js_sys::{Array, BigUint64Array, Float32Array, Int32Array, Object, Uint8Array};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::biome::BIOMES;
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::region_seed::region_seed;
+use crate::{MapResult, REGION_SIZE};
+
+const SQRT_3: f32 = 1.732_050_8;
+/// Guards against hex sizes so small the grid outgrows the map.
+const MAX_HEXES: usize = 1 << 20;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HexOrientation {
+    Pointy,
+    Flat,
+}
+
+impl HexOrientation {
+    fn key(self) -> &'static str {
+        match self {
+            HexOrientation::Pointy => "pointy",
+            HexOrientation::Flat => "flat",
+        }
+    }
+
+    /// World offsets of one step in `q` and one step in `r` for hexes of
+    /// circumradius `size`; hex `(0, 0)` is centred on the world origin.
+    fn basis(self, size: f32) -> ((f32, f32), (f32, f32)) {
+        match self {
+            HexOrientation::Pointy => ((SQRT_3 * size, 0.0), (SQRT_3 * 0.5 * size, 1.5 * size)),
+            HexOrientation::Flat => ((1.5 * size, SQRT_3 * 0.5 * size), (0.0, SQRT_3 * size)),
+        }
+    }
+}
+
+/// Per-hex aggregates, one entry per hex whose centre lies in the world.
+pub(crate) struct HexGrid {
+    pub size: f32,
+    pub q_min: i32,
+    pub r_min: i32,
+    pub q_count: usize,
+    pub r_count: usize,
+    /// Slot of axial `(q, r)` at `(r - r_min) * q_count + (q - q_min)`, or -1.
+    pub slots: Vec<i32>,
+    pub q: Vec<i32>,
+    pub r: Vec<i32>,
+    pub center_x: Vec<f32>,
+    pub center_y: Vec<f32>,
+    pub biome: Vec<u8>,
+    pub elevation: Vec<f32>,
+    pub max_flow: Vec<f32>,
+    pub river: Vec<u8>,
+    pub water_fraction: Vec<f32>,
+    pub settlements: Vec<Vec<u32>>,
+    /// `region_seed` of each hex's `(q, r)`.
+    pub seeds: Vec<u64>,
+}
+
+impl HexGrid {
+    fn slot(&self, q: i32, r: i32) -> Option<usize> {
+        let dq = q - self.q_min;
+        let dr = r - self.r_min;
+        if dq < 0 || dr < 0 || dq as usize >= self.q_count || dr as usize >= self.r_count {
+            return None;
+        }
+        let slot = self.slots[dr as usize * self.q_count + dq as usize];
+        (slot >= 0).then_some(slot as usize)
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Aggregates the map onto a `"pointy"` or `"flat"` hex grid whose hexes
+    /// have circumradius `hex_size` world units. Every hex with its centre
+    /// inside the world is listed, with parallel arrays `q`, `r`, `center_x`,
+    /// `center_y`, `biome` (majority), `elevation` (mean), `max_flow`,
+    /// `river` (0/1), `water_fraction`, `settlements` (id arrays), and
+    /// `seeds` (a `BigUint64Array` of each hex's `region_seed(q, r)`).
+    /// `index` maps axial `(q, r)` to a list position via
+    /// `(r - r_min) * q_count + (q - q_min)`, holding -1 for absent hexes, and
+    /// `transform` gives the world centre as
+    /// `origin + q * (qx, qy) + r * (rx, ry)`.
+    pub fn to_hex_grid(&self, hex_size: f32, orientation: &str) -> Result<Object, JsValue> {
+        let orientation = match orientation {
+            "pointy" => HexOrientation::Pointy,
+            "flat" => HexOrientation::Flat,
+            other => {
+                return Err(JsValue::from_str(&format!(
+                    "orientation must be \"pointy\" or \"flat\", got {other}"
+                )))
+            }
+        };
+        let grid = to_hex_grid(self, hex_size, orientation)
+            .map_err(|message| JsValue::from_str(&message))?;
+
+        let result = Object::new();
+        js::set(&result, "orientation", &orientation.key().into());
+        js::set(&result, "size", &grid.size.into());
+        js::set(&result, "q_min", &grid.q_min.into());
+        js::set(&result, "r_min", &grid.r_min.into());
+        js::set(&result, "q_count", &(grid.q_count as u32).into());
+        js::set(&result, "r_count", &(grid.r_count as u32).into());
+        js::set(
+            &result,
+            "index",
+            &Int32Array::from(grid.slots.as_slice()).into(),
+        );
+        js::set(&result, "q", &Int32Array::from(grid.q.as_slice()).into());
+        js::set(&result, "r", &Int32Array::from(grid.r.as_slice()).into());
+        js::set(
+            &result,
+            "center_x",
+            &Float32Array::from(grid.center_x.as_slice()).into(),
+        );
+        js::set(
+            &result,
+            "center_y",
+            &Float32Array::from(grid.center_y.as_slice()).into(),
+        );
+        js::set(
+            &result,
+            "biome",
+            &Uint8Array::from(grid.biome.as_slice()).into(),
+        );
+        js::set(
+            &result,
+            "elevation",
+            &Float32Array::from(grid.elevation.as_slice()).into(),
+        );
+        js::set(
+            &result,
+            "max_flow",
+            &Float32Array::from(grid.max_flow.as_slice()).into(),
+        );
+        js::set(
+            &result,
+            "river",
+            &Uint8Array::from(grid.river.as_slice()).into(),
+        );
+        js::set(
+            &result,
+            "water_fraction",
+            &Float32Array::from(grid.water_fraction.as_slice()).into(),
+        );
+        let settlements = grid
+            .settlements
+            .iter()
+            .map(|ids| Array::from_iter(ids.iter().map(|&id| JsValue::from(id))))
+            .collect::<Array>();
+        js::set(&result, "settlements", &settlements.into());
+        js::set(
+            &result,
+            "seeds",
+            &BigUint64Array::from(grid.seeds.as_slice()).into(),
+        );
+
+        let ((qx, qy), (rx, ry)) = orientation.basis(grid.size);
+        let transform = Object::new();
+        js::set(&transform, "origin_x", &0.0f32.into());
+        js::set(&transform, "origin_y", &0.0f32.into());
+        js::set(&transform, "qx", &qx.into());
+        js::set(&transform, "qy", &qy.into());
+        js::set(&transform, "rx", &rx.into());
+        js::set(&transform, "ry", &ry.into());
+        js::set(&result, "transform", &transform.into());
+        Ok(result)
+    }
+}
+
+pub(crate) fn to_hex_grid(
+    map: &MapResult,
+    hex_size: f32,
+    orientation: HexOrientation,
+) -> Result<HexGrid, String> {
+    if !(hex_size.is_finite() && hex_size > 0.0) {
+        return Err("hex size must be positive".to_string());
+    }
+    let ((qx, qy), (rx, ry)) = orientation.basis(hex_size);
+    let determinant = qx * ry - qy * rx;
+    let to_axial = |x: f32, y: f32| {
+        (
+            (x * ry - y * rx) / determinant,
+            (y * qx - x * qy) / determinant,
+        )
+    };
+
+    let corners = [
+        (0.0, 0.0),
+        (REGION_SIZE, 0.0),
+        (0.0, REGION_SIZE),
+        (REGION_SIZE, REGION_SIZE),
+    ]
+    .map(|(x, y)| to_axial(x, y));
+    let q_min = corners
+        .iter()
+        .map(|c| c.0)
+        .fold(f32::INFINITY, f32::min)
+        .floor() as i32
+        - 1;
+    let q_max = corners
+        .iter()
+        .map(|c| c.0)
+        .fold(f32::NEG_INFINITY, f32::max)
+        .ceil() as i32
+        + 1;
+    let r_min = corners
+        .iter()
+        .map(|c| c.1)
+        .fold(f32::INFINITY, f32::min)
+        .floor() as i32
+        - 1;
+    let r_max = corners
+        .iter()
+        .map(|c| c.1)
+        .fold(f32::NEG_INFINITY, f32::max)
+        .ceil() as i32
+        + 1;
+    let q_count = (q_max - q_min + 1) as usize;
+    let r_count = (r_max - r_min + 1) as usize;
+    if q_count.saturating_mul(r_count) > MAX_HEXES {
+        return Err(format!("hex size {hex_size} yields too many hexes"));
+    }
+
+    let mut grid = HexGrid {
+        size: hex_size,
+        q_min,
+        r_min,
+        q_count,
+        r_count,
+        slots: vec![-1; q_count * r_count],
+        q: Vec::new(),
+        r: Vec::new(),
+        center_x: Vec::new(),
+        center_y: Vec::new(),
+        biome: Vec::new(),
+        elevation: Vec::new(),
+        max_flow: Vec::new(),
+        river: Vec::new(),
+        water_fraction: Vec::new(),
+        settlements: Vec::new(),
+        seeds: Vec::new(),
+    };
+    for r in r_min..=r_max {
+        for q in q_min..=q_max {
+            let x = q as f32 * qx + r as f32 * rx;
+            let y = q as f32 * qy + r as f32 * ry;
+            if !((0.0..REGION_SIZE).contains(&x) && (0.0..REGION_SIZE).contains(&y)) {
+                continue;
+            }
+            grid.slots[(r - r_min) as usize * q_count + (q - q_min) as usize] = grid.q.len() as i32;
+            grid.q.push(q);
+            grid.r.push(r);
+            grid.center_x.push(x);
+            grid.center_y.push(y);
+            grid.seeds.push(region_seed(map.settings.seed, q, r));
+        }
+    }
+
+    let hexes = grid.q.len();
+    let mut biome_counts = vec![[0u32; BIOMES.len()]; hexes];
+    let mut cells = vec![0u32; hexes];
+    let mut elevation = vec![0.0f64; hexes];
+    let mut water_cells = vec![0u32; hexes];
+    let mut max_flow = vec![0.0f32; hexes];
+    let mut river = vec![0u8; hexes];
+    let hex_of = |x: f32, y: f32| {
+        let (q, r) = to_axial(x, y);
+        let (q, r) = axial_round(q, r);
+        grid.slot(q, r)
+    };
+    for index in 0..map.heightmap.len() {
+        let (x, y) = map.cell_to_world(index);
+        let Some(slot) = hex_of(x, y) else {
+            continue;
+        };
+        cells[slot] += 1;
+        elevation[slot] += map.heightmap[index] as f64;
+        if let Some(count) = biome_counts[slot].get_mut(map.biome[index] as usize) {
+            *count += 1;
+        }
+        if map.is_water_body(index) {
+            water_cells[slot] += 1;
+        }
+        if map.is_river(index) {
+            river[slot] = 1;
+        }
+        max_flow[slot] = max_flow[slot].max(map.flow[index]);
+    }
+
+    let mut settlements = vec![Vec::new(); hexes];
+    for settlement in &map.settlements {
+        if let Some(slot) = hex_of(settlement.x, settlement.y) {
+            settlements[slot].push(settlement.id);
+        }
+    }
+    grid.settlements = settlements;
+
+    for slot in 0..hexes {
+        if cells[slot] == 0 {
+            // Hexes smaller than a cell take the cell under their centre.
+            let (x, y) = map.nearest_cell(grid.center_x[slot], grid.center_y[slot]);
+            let index = y * map.width as usize + x;
+            grid.biome.push(map.biome[index]);
+            grid.elevation.push(map.heightmap[index]);
+            max_flow[slot] = map.flow[index];
+            river[slot] = map.is_river(index) as u8;
+            grid.water_fraction
+                .push(map.is_water_body(index) as u8 as f32);
+            continue;
+        }
+        let majority = biome_counts[slot]
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(&a.0)))
+            .map_or(0, |(code, _)| code as u8);
+        grid.biome.push(majority);
+        grid.elevation
+            .push((elevation[slot] / cells[slot] as f64) as f32);
+        grid.water_fraction
+            .push(water_cells[slot] as f32 / cells[slot] as f32);
+    }
+    grid.max_flow = max_flow;
+    grid.river = river;
+    Ok(grid)
+}
+
+/// Rounds fractional axial coordinates to the containing hex via cube rounding.
+fn axial_round(q: f32, r: f32) -> (i32, i32) {
+    let s = -q - r;
+    let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+    let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+    if dq > dr && dq > ds {
+        rq = -rr - rs;
+    } else if dr > ds {
+        rr = -rq - rs;
+    }
+    (rq as i32, rr as i32)
+}
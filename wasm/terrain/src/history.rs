@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::json::Json;
+use crate::poi::{Poi, PoiKind};
+use crate::ruins::RuinReason;
+use crate::scenario::{snap_site, ExclusionZone, Snap};
+use crate::{
+    build_roads, place_settlements, GenerationSettings, MapResult, Settlement, SimpleRng,
+    SiteLayers,
+};
+
+/// Upper bound on `options.eras`.
+pub(crate) const MAX_ERAS: u32 = 8;
+/// Site-score noise for earlier eras, so they settle different ground than
+/// the present instead of the same best sites.
+const EARLY_ERA_JITTER: f32 = 0.3;
+
+/// What one era's settlement placement starts from.
+pub(crate) struct EraPlacement {
+    pub era: u32,
+    pub score_jitter: f32,
+    /// Survivors of earlier eras, with ids matching their positions.
+    pub existing: Vec<Settlement>,
+    /// Ruin positions later settlements keep `ruin_exclusion` away from.
+    pub ruins: Vec<(f32, f32)>,
+    pub ruin_exclusion: f32,
+    /// Caller-placed settlements added ahead of the procedural ones; ids are
+    /// assigned on placement.
+    pub forced: Vec<Settlement>,
+    /// Forced sites procedural settlements keep their spacing from, in
+    /// every era.
+    pub reserved: Vec<(f32, f32)>,
+    pub exclusions: Vec<ExclusionZone>,
+}
+
+/// An overgrown road whose settlement was abandoned, in world coordinates.
+#[derive(Clone)]
+pub(crate) struct Trail {
+    pub from: (f32, f32),
+    pub to: (f32, f32),
+    /// Era the road was built in.
+    pub era: u32,
+}
+
+/// Era bookkeeping for the features that outlive generation.
+#[derive(Clone, Default)]
+pub(crate) struct History {
+    pub eras: u32,
+    pub trails: Vec<Trail>,
+    /// Era each `road_graph` edge was built in, keyed as stored there. Roads
+    /// added after generation are missing and belong to the current era.
+    pub road_eras: HashMap<(u32, u32), u32>,
+}
+
+impl History {
+    pub(crate) fn current_era(&self) -> u32 {
+        self.eras.saturating_sub(1)
+    }
+
+    pub(crate) fn road_era(&self, road: (u32, u32)) -> u32 {
+        self.road_eras
+            .get(&road)
+            .copied()
+            .unwrap_or(self.current_era())
+    }
+}
+
+pub(crate) struct Eras {
+    pub settlements: Vec<Settlement>,
+    pub road_graph: Vec<(u32, u32)>,
+    pub pois: Vec<Poi>,
+    pub history: History,
+    /// Where each forced settlement was placed.
+    pub placements: Vec<Snap>,
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Eras behind the map: `{ eras, roads, trails }` with roads as `{ a, b,
+    /// era }` over settlement ids and trails as `{ x0, y0, x1, y1, era }` in
+    /// world units. Settlements and POIs carry their own `era`.
+    pub fn history(&self) -> JsValue {
+        let roads = self
+            .road_graph
+            .iter()
+            .map(|&(a, b)| {
+                Json::object()
+                    .with("a", a)
+                    .with("b", b)
+                    .with("era", self.history.road_era((a, b)))
+            })
+            .collect::<Vec<_>>();
+        let trails = self
+            .history
+            .trails
+            .iter()
+            .map(|trail| {
+                Json::object()
+                    .with("x0", trail.from.0)
+                    .with("y0", trail.from.1)
+                    .with("x1", trail.to.0)
+                    .with("y1", trail.to.1)
+                    .with("era", trail.era)
+            })
+            .collect::<Vec<_>>();
+        Json::object()
+            .with("eras", self.history.eras)
+            .with("roads", roads)
+            .with("trails", trails)
+            .to_js()
+    }
+}
+
+/// Places settlements and roads once per era, oldest first. Between eras a
+/// seeded share of settlements is abandoned: each becomes a ruin, its roads
+/// become trails, and the roads between survivors carry over into the next
+/// era's network, which only adds the edges it is missing. A single era is
+/// exactly the classic placement. Forced settlements snap to the nearest
+/// site within `snap_radius` and join the current era ahead of its
+/// procedural settlements; those that find no site are left out.
+pub(crate) fn simulate_eras(
+    heightmap: &[f32],
+    water: &[f32],
+    moisture: &[f32],
+    width: u32,
+    height: u32,
+    settings: &GenerationSettings,
+) -> Eras {
+    let eras = settings.eras.max(1);
+    let mut rng = SimpleRng::new(settings.seed.wrapping_mul(389));
+    let mut survivors: Vec<Settlement> = Vec::new();
+    let mut kept_roads: Vec<(u32, u32)> = Vec::new();
+    let mut road_eras: HashMap<(u32, u32), u32> = HashMap::new();
+    let mut pois: Vec<Poi> = Vec::new();
+    let mut trails = Vec::new();
+    let layers = SiteLayers {
+        heightmap,
+        water,
+        moisture,
+        width: width as usize,
+        height: height as usize,
+        sea_level: settings.sea_level,
+    };
+    let placements: Vec<Snap> = settings
+        .forced_settlements
+        .iter()
+        .enumerate()
+        .map(|(index, forced)| Snap {
+            index,
+            from: (forced.x, forced.y),
+            to: snap_site(&layers, forced.x, forced.y, settings.snap_radius),
+        })
+        .collect();
+    let reserved: Vec<(f32, f32)> = placements.iter().filter_map(|snap| snap.to).collect();
+
+    for era in 0..eras {
+        let current = era + 1 == eras;
+        let placement = EraPlacement {
+            era,
+            score_jitter: if current { 0.0 } else { EARLY_ERA_JITTER },
+            existing: std::mem::take(&mut survivors),
+            ruins: pois.iter().map(|poi| (poi.x, poi.y)).collect(),
+            ruin_exclusion: settings.ruin_exclusion,
+            forced: if current {
+                placements
+                    .iter()
+                    .filter_map(|snap| {
+                        let (x, y) = snap.to?;
+                        let forced = &settings.forced_settlements[snap.index];
+                        Some(Settlement {
+                            id: 0,
+                            x,
+                            y,
+                            size: forced.size,
+                            issue: None,
+                            era,
+                            name: forced.name.clone(),
+                        })
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            },
+            reserved: reserved.clone(),
+            exclusions: settings.exclusion_zones.clone(),
+        };
+        let seed = settings
+            .seed
+            .wrapping_add((eras - 1 - era).wrapping_mul(0x9e37_79b9));
+        let settlements = place_settlements(&layers, seed, &placement);
+        let mut roads = std::mem::take(&mut kept_roads);
+        for (a, b) in build_roads(&settlements) {
+            if !roads.contains(&(a, b)) && !roads.contains(&(b, a)) {
+                roads.push((a, b));
+                road_eras.insert((a, b), era);
+            }
+        }
+        if current {
+            return Eras {
+                settlements,
+                road_graph: roads,
+                pois,
+                history: History {
+                    eras,
+                    trails,
+                    road_eras,
+                },
+                placements,
+            };
+        }
+
+        let abandoned: Vec<bool> = settlements
+            .iter()
+            .map(|_| rng.next_f32() < settings.abandon_fraction)
+            .collect();
+        let positions: Vec<(f32, f32)> = settlements
+            .iter()
+            .map(|settlement| (settlement.x, settlement.y))
+            .collect();
+        let mut renumbered = vec![None; settlements.len()];
+        for (index, settlement) in settlements.into_iter().enumerate() {
+            if abandoned[index] {
+                pois.push(Poi {
+                    id: pois.len() as u32,
+                    kind: PoiKind::Ruin,
+                    x: settlement.x,
+                    y: settlement.y,
+                    era: settlement.era,
+                    reason: Some(RuinReason::Abandoned),
+                    coverage: None,
+                });
+                continue;
+            }
+            renumbered[index] = Some(survivors.len() as u32);
+            survivors.push(Settlement {
+                id: survivors.len() as u32,
+                ..settlement
+            });
+        }
+        let mut carried = HashMap::new();
+        for (a, b) in roads {
+            let built = road_eras.get(&(a, b)).copied().unwrap_or(era);
+            match (renumbered[a as usize], renumbered[b as usize]) {
+                (Some(a), Some(b)) => {
+                    kept_roads.push((a, b));
+                    carried.insert((a, b), built);
+                }
+                _ => trails.push(Trail {
+                    from: positions[a as usize],
+                    to: positions[b as usize],
+                    era: built,
+                }),
+            }
+        }
+        road_eras = carried;
+    }
+    unreachable!("the last era always returns")
+}
+
+/// Deterministic per-cell value in 0..1 for perturbing site scores.
+pub(crate) fn site_noise(seed: u32, index: usize) -> f32 {
+    let mut hash = seed ^ (index as u32).wrapping_mul(0x85eb_ca6b);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0xc2b2_ae35);
+    hash ^= hash >> 16;
+    (hash as f64 / u32::MAX as f64) as f32
+}
@@ -0,0 +1,177 @@
+#[cfg(feature = "wasm")]
+use js_sys::{Float32Array, Function, Object};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "wasm")]
+use crate::js;
+
+/// Where in the pipeline a generation hook runs.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Stage {
+    /// After noise, erosion, and faults, before water is traced. Height
+    /// changes steer the rivers.
+    Terrain,
+    /// After water and its moisture bonuses, before biomes. The heightmap is
+    /// only a copy here, as flow has already been routed over it.
+    Climate,
+}
+
+impl Stage {
+    pub(crate) fn key(self) -> &'static str {
+        match self {
+            Stage::Terrain => "terrain",
+            Stage::Climate => "climate",
+        }
+    }
+}
+
+/// Layers a hook may rewrite. All are `width * height` and row-major.
+pub(crate) struct StageLayers<'a> {
+    pub stage: Stage,
+    pub width: u32,
+    pub height: u32,
+    pub heightmap: &'a mut [f32],
+    pub moisture: &'a mut [f32],
+    pub temperature: &'a mut [f32],
+}
+
+pub(crate) type Hook<'a> = (Stage, &'a mut dyn FnMut(&mut StageLayers));
+
+/// Runs `hook` over the layers, then puts every value it changed back in
+/// 0..1, undoing NaNs, so later stages never see values they cannot sort.
+pub(crate) fn run(hook: &mut dyn FnMut(&mut StageLayers), layers: &mut StageLayers) {
+    let before = [
+        layers.heightmap.to_vec(),
+        layers.moisture.to_vec(),
+        layers.temperature.to_vec(),
+    ];
+    hook(layers);
+    let after = [
+        &mut *layers.heightmap,
+        &mut *layers.moisture,
+        &mut *layers.temperature,
+    ];
+    for (values, before) in after.into_iter().zip(&before) {
+        for (value, &old) in values.iter_mut().zip(before) {
+            // Untouched values pass through even when out of range, so a
+            // hook that changes nothing changes nothing.
+            if value.to_bits() != old.to_bits() {
+                *value = if value.is_nan() {
+                    old
+                } else {
+                    value.clamp(0.0, 1.0)
+                };
+            }
+        }
+    }
+}
+
+/// `options.hook` and `options.hook_stage` of `generate_map_with_options`.
+#[cfg(feature = "wasm")]
+pub(crate) fn from_options(options: &JsValue) -> Result<Option<(Stage, Function)>, JsValue> {
+    let Some(hook) = js::get(options, "hook") else {
+        return Ok(None);
+    };
+    let hook = hook
+        .dyn_into::<Function>()
+        .map_err(|_| JsValue::from_str("hook must be a function"))?;
+    let stage = match js::get_string(options, "hook_stage").as_deref() {
+        None | Some("climate") => Stage::Climate,
+        Some("terrain") => Stage::Terrain,
+        Some(other) => return Err(JsValue::from_str(&format!("unknown hook stage: {other}"))),
+    };
+    Ok(Some((stage, hook)))
+}
+
+/// Calls a JS hook with `{ stage, width, height, heightmap, moisture,
+/// temperature }`. The arrays are copies made for the call and read back
+/// once it returns, so the hook may write into them or replace them with
+/// new `Float32Array`s of the same length.
+#[cfg(feature = "wasm")]
+pub(crate) fn call_js(hook: &Function, layers: &mut StageLayers) -> Result<(), JsValue> {
+    let arg = Object::new();
+    js::set(&arg, "stage", &JsValue::from_str(layers.stage.key()));
+    js::set(&arg, "width", &JsValue::from(layers.width));
+    js::set(&arg, "height", &JsValue::from(layers.height));
+    let keys = ["heightmap", "moisture", "temperature"];
+    let layers_mut = [
+        &mut *layers.heightmap,
+        &mut *layers.moisture,
+        &mut *layers.temperature,
+    ];
+    for (key, values) in keys.iter().zip(&layers_mut) {
+        js::set(&arg, key, &Float32Array::from(&values[..]).into());
+    }
+    hook.call1(&JsValue::NULL, &arg)?;
+    for (key, values) in keys.iter().zip(layers_mut) {
+        let array = js::get(&arg, key)
+            .and_then(|value| value.dyn_into::<Float32Array>().ok())
+            .filter(|array| array.length() as usize == values.len())
+            .ok_or_else(|| {
+                JsValue::from_str(&format!(
+                    "hook left {key} without a Float32Array of {} values",
+                    values.len()
+                ))
+            })?;
+        array.copy_to(values);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Stage;
+    use crate::determinism::fingerprint;
+    use crate::{generate, generate_with_hook, GenerationSettings};
+
+    #[test]
+    fn hooks_feed_later_stages_and_cannot_poison_them() {
+        let settings = GenerationSettings {
+            seed: 3,
+            ..GenerationSettings::default()
+        };
+        let plain = generate(64, 64, &settings, None);
+        let idle = generate_with_hook(64, 64, &settings, None, Some((Stage::Climate, &mut |_| {})));
+        assert_eq!(fingerprint(&plain), fingerprint(&idle));
+        assert_eq!(plain.moisture, idle.moisture);
+
+        let mut seen = None;
+        let dry = generate_with_hook(
+            64,
+            64,
+            &settings,
+            None,
+            Some((Stage::Climate, &mut |layers| {
+                seen = Some((layers.stage, layers.width, layers.moisture.len()));
+                layers.moisture.fill(0.0);
+                layers.temperature[0] = f32::NAN;
+                layers.temperature[1] = 7.0;
+                // A copy at this stage; rivers are already routed.
+                layers.heightmap.fill(1.0);
+            })),
+        );
+        assert_eq!(seen, Some((Stage::Climate, 64, 64 * 64)));
+        assert!(dry.moisture.iter().all(|&m| m == 0.0));
+        assert_eq!(dry.temperature[0], plain.temperature[0]);
+        assert_eq!(dry.temperature[1], 1.0);
+        assert_eq!(dry.heightmap, plain.heightmap);
+        assert_ne!(dry.biome, plain.biome);
+
+        let flooded = generate_with_hook(
+            64,
+            64,
+            &settings,
+            None,
+            Some((Stage::Terrain, &mut |layers| {
+                for height in layers.heightmap.iter_mut() {
+                    *height = f32::NAN;
+                }
+                layers.heightmap[0] = -3.0;
+            })),
+        );
+        assert_eq!(flooded.heightmap[0], 0.0);
+        assert_eq!(flooded.heightmap[1..], plain.heightmap[1..]);
+        assert!(flooded.flow.iter().all(|flow| flow.is_finite()));
+    }
+}
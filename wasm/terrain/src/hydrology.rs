@@ -0,0 +1,730 @@
+use std::collections::{BinaryHeap, VecDeque};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::biome::Biome;
+use crate::features::{components, neighbors};
+use crate::geometry::distance;
+use crate::json::Json;
+use crate::pathfinding::Frontier;
+#[cfg(feature = "wasm")]
+use crate::stream_order::river_records;
+use crate::{cell_water, MapResult, DIRECTIONS};
+
+/// Where a traced river stops.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Outlet {
+    Ocean,
+    Lake,
+    /// Joins the river with the given id.
+    Confluence(u32),
+    /// Ends in a pit or at the map edge without reaching water.
+    Sink,
+}
+
+impl Outlet {
+    fn key(self) -> &'static str {
+        match self {
+            Outlet::Ocean => "ocean",
+            Outlet::Lake => "lake",
+            Outlet::Confluence(_) => "confluence",
+            Outlet::Sink => "sink",
+        }
+    }
+}
+
+/// A river traced from its head down the steepest-descent pointers. `cells`
+/// runs from source to outlet and includes the receiving water or confluence
+/// cell so polylines connect visually.
+pub(crate) struct River {
+    pub id: u32,
+    pub cells: Vec<usize>,
+    pub outlet: Outlet,
+    pub max_flow: f32,
+    /// Whether the river starts as a lake's overflow, in which case `cells`
+    /// begins with the lake cell it spills from.
+    pub from_lake: bool,
+}
+
+/// A connected group of lake cells.
+pub(crate) struct Lake {
+    pub id: u32,
+    pub cells: Vec<usize>,
+    /// Drainage from `lake_outflows` routing: the spill cell its outflow
+    /// river starts from, or `None` for closed salt lakes.
+    pub drainage: Option<LakeDrainage>,
+}
+
+pub(crate) struct LakeDrainage {
+    pub outlet: Option<usize>,
+    pub inflow: f32,
+}
+
+/// Traces every river head to its outlet. Heads whose paths are longest are
+/// traced first, so main stems get low ids and tributaries end in confluences.
+pub(crate) fn extract_rivers(map: &MapResult) -> Vec<River> {
+    trace_channels(map, |index| map.is_river(index))
+}
+
+/// `extract_rivers` over any set of channel cells, following steepest descent
+/// from every member with no member upstream.
+pub(crate) fn trace_channels(map: &MapResult, is_member: impl Fn(usize) -> bool) -> Vec<River> {
+    let size = map.heightmap.len();
+    let routing = map.settings.lake_outflows.then(|| map.lake_routing());
+    let downslope = map.receivers();
+    // Lake cell each overflow river spills from, keyed by its outlet.
+    let mut spills: Vec<Option<usize>> = vec![None; size];
+    for spill in routing.iter().flat_map(|routing| &routing.basins) {
+        if let Some(outlet) = spill.outlet.filter(|_| spill.lake) {
+            spills[outlet] = spill
+                .cells
+                .iter()
+                .copied()
+                .filter(|&index| map.is_water_body(index))
+                .find(|&index| {
+                    neighbors(outlet, map.width as usize, map.height as usize).any(|n| n == index)
+                });
+        }
+    }
+
+    let mut has_upstream = vec![false; size];
+    for (index, target) in downslope.iter().enumerate() {
+        if let Some(target) = *target {
+            if is_member(index) && is_member(target) {
+                has_upstream[target] = true;
+            }
+        }
+    }
+
+    let path_length = |head: usize| {
+        let mut length = 0usize;
+        let mut current = head;
+        while let Some(next) = downslope[current] {
+            length += 1;
+            if !is_member(next) || length > size {
+                break;
+            }
+            current = next;
+        }
+        length
+    };
+
+    let mut heads: Vec<(usize, usize)> = (0..size)
+        .filter(|&index| is_member(index) && !has_upstream[index])
+        .map(|index| (index, path_length(index)))
+        .collect();
+    heads.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    let mut owner: Vec<Option<u32>> = vec![None; size];
+    let mut rivers = Vec::with_capacity(heads.len());
+    for (head, _) in heads {
+        let id = rivers.len() as u32;
+        let from_lake = spills[head].is_some();
+        let mut cells: Vec<usize> = spills[head].into_iter().chain([head]).collect();
+        let mut max_flow = map.flow[head];
+        owner[head] = Some(id);
+        let mut current = head;
+        let outlet = loop {
+            let Some(next) = downslope[current] else {
+                break Outlet::Sink;
+            };
+            if let Some(other) = owner[next] {
+                cells.push(next);
+                break Outlet::Confluence(other);
+            }
+            if !is_member(next) {
+                if map.is_water_body(next) {
+                    cells.push(next);
+                    break if map.biome[next] == Biome::Ocean.code() {
+                        Outlet::Ocean
+                    } else {
+                        Outlet::Lake
+                    };
+                }
+                break Outlet::Sink;
+            }
+            owner[next] = Some(id);
+            max_flow = max_flow.max(map.flow[next]);
+            cells.push(next);
+            current = next;
+        };
+        rivers.push(River {
+            id,
+            cells,
+            outlet,
+            max_flow,
+            from_lake,
+        });
+    }
+    rivers
+}
+
+/// Labels 8-connected lake components in row-major order of their first cell.
+pub(crate) fn extract_lakes(map: &MapResult) -> Vec<Lake> {
+    let width = map.width as i32;
+    let height = map.height as i32;
+    let mut visited = vec![false; map.biome.len()];
+    let mut lakes = Vec::new();
+
+    for start in 0..map.biome.len() {
+        if visited[start] || map.biome[start] != Biome::Lake.code() {
+            continue;
+        }
+        visited[start] = true;
+        let mut cells = Vec::new();
+        let mut stack = vec![start];
+        while let Some(index) = stack.pop() {
+            cells.push(index);
+            let x = index as i32 % width;
+            let y = index as i32 / width;
+            for (dx, dy) in DIRECTIONS {
+                let nx = x + dx;
+                let ny = y + dy;
+                if nx < 0 || ny < 0 || nx >= width || ny >= height {
+                    continue;
+                }
+                let n_index = (ny * width + nx) as usize;
+                if !visited[n_index] && map.biome[n_index] == Biome::Lake.code() {
+                    visited[n_index] = true;
+                    stack.push(n_index);
+                }
+            }
+        }
+        cells.sort_unstable();
+        lakes.push(Lake {
+            id: lakes.len() as u32,
+            cells,
+            drainage: None,
+        });
+    }
+    if map.settings.lake_outflows {
+        let routing = map.lake_routing();
+        for lake in &mut lakes {
+            // Shallow shore water and wide rivers sit in no basin.
+            let spill = lake
+                .cells
+                .iter()
+                .find_map(|&index| routing.basins.get(routing.basin[index] as usize))
+                .filter(|spill| spill.lake);
+            lake.drainage = spill.map(|spill| LakeDrainage {
+                outlet: spill.outlet,
+                inflow: spill.inflow,
+            });
+        }
+    }
+    lakes
+}
+
+/// Priority-Flood depression filling seeded from the ocean: each cell gets
+/// the lowest water level at which it joins the sea, i.e. the highest point
+/// on its lowest route to an ocean cell (or its own height if higher). A
+/// basin enclosed by higher land only floods once the water tops its rim.
+/// Maps without ocean drain over the border instead.
+pub(crate) fn ocean_spill_levels(map: &MapResult) -> Vec<f32> {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let has_ocean = map.biome.contains(&Biome::Ocean.code());
+    let seeds: Vec<bool> = (0..map.heightmap.len())
+        .map(|index| {
+            if has_ocean {
+                map.biome[index] == Biome::Ocean.code()
+            } else {
+                on_border(index, width, height)
+            }
+        })
+        .collect();
+    priority_flood(&map.heightmap, width, height, &seeds)
+}
+
+fn on_border(index: usize, width: usize, height: usize) -> bool {
+    let (x, y) = (index % width, index / width);
+    x == 0 || y == 0 || x == width - 1 || y == height - 1
+}
+
+/// Lowest level at which each cell drains to a `seeds` cell; see
+/// `ocean_spill_levels`. Unreachable cells stay infinite.
+pub(crate) fn priority_flood(
+    heightmap: &[f32],
+    width: usize,
+    height: usize,
+    seeds: &[bool],
+) -> Vec<f32> {
+    let mut spill = vec![f32::INFINITY; heightmap.len()];
+    let mut heap = BinaryHeap::new();
+    for index in (0..heightmap.len()).filter(|&index| seeds[index]) {
+        spill[index] = heightmap[index];
+        heap.push(Frontier {
+            priority: spill[index],
+            index,
+        });
+    }
+
+    let mut done = vec![false; heightmap.len()];
+    while let Some(Frontier { priority, index }) = heap.pop() {
+        if done[index] {
+            continue;
+        }
+        done[index] = true;
+        for next in neighbors(index, width, height) {
+            let level = priority.max(heightmap[next]);
+            if !done[next] && level < spill[next] {
+                spill[next] = level;
+                heap.push(Frontier {
+                    priority: level,
+                    index: next,
+                });
+            }
+        }
+    }
+    spill
+}
+
+/// Filled depressions smaller or shallower than this drain through without
+/// holding standing water.
+const MIN_LAKE_CELLS: usize = 4;
+const MIN_LAKE_DEPTH: f32 = 0.004;
+/// Flow units a lake cell loses to evaporation at full heat and no moisture;
+/// a lake whose evaporation outweighs its inflow has no outlet.
+const EVAPORATION: f32 = 12.0;
+
+/// A filled depression drained as one node: everything flowing into any of
+/// its cells leaves together through `outlet`.
+pub(crate) struct Basin {
+    pub cells: Vec<usize>,
+    /// Rim cell the overflow spills over; `None` for closed basins.
+    pub outlet: Option<usize>,
+    /// Flow reaching a lake, counting its own cells; 0 for filled pits.
+    pub inflow: f32,
+    /// Whether the basin holds a lake rather than filling in as land.
+    pub lake: bool,
+}
+
+impl Basin {
+    /// Flow the basin's cells could evaporate given per-cell `aridity`; a
+    /// lake loses this much of its inflow, or all of it when less.
+    pub(crate) fn evaporation(&self, aridity: &[f32]) -> f32 {
+        self.cells
+            .iter()
+            .map(|&index| aridity[index] * EVAPORATION)
+            .sum()
+    }
+}
+
+/// Flow routing with depressions filled to their spill level.
+pub(crate) struct LakeRouting {
+    /// Next cell downstream. Lake cells point at their outlet.
+    pub receiver: Vec<Option<usize>>,
+    /// Basin index of each cell, or `u32::MAX` outside every basin.
+    pub basin: Vec<u32>,
+    pub basins: Vec<Basin>,
+    pub flow: Vec<f32>,
+}
+
+impl LakeRouting {
+    /// Standing water 1.0 across lakes, otherwise `cell_water` runoff.
+    pub(crate) fn water(&self, heightmap: &[f32], sea_level: f32) -> Vec<f32> {
+        let max_flow = self.flow.iter().fold(0.0f32, |acc, &v| acc.max(v));
+        (0..heightmap.len())
+            .map(|index| match self.basins.get(self.basin[index] as usize) {
+                Some(basin) if basin.lake => 1.0,
+                _ => cell_water(heightmap[index], self.flow[index], max_flow, sea_level),
+            })
+            .collect()
+    }
+}
+
+/// Fills every depression from the ocean (or the map border) and routes
+/// flow across the filled surface, treating each basin as a single node
+/// that passes its inflow, less lake evaporation, to its spill point.
+/// `aridity` is per-cell heat times dryness, 0..1.
+pub(crate) fn route_lakes(
+    heightmap: &[f32],
+    aridity: &[f32],
+    width: usize,
+    height: usize,
+    sea_level: f32,
+) -> LakeRouting {
+    let size = heightmap.len();
+    let seeds: Vec<bool> = (0..size)
+        .map(|index| heightmap[index] <= sea_level - 0.02 || on_border(index, width, height))
+        .collect();
+    let levels = priority_flood(heightmap, width, height, &seeds);
+    let flooded: Vec<bool> = (0..size)
+        .map(|index| levels[index] > heightmap[index])
+        .collect();
+
+    let mut basin = vec![u32::MAX; size];
+    let mut basins: Vec<Basin> = components(&flooded, width, height)
+        .into_iter()
+        .enumerate()
+        .map(|(id, cells)| {
+            for &index in &cells {
+                basin[index] = id as u32;
+            }
+            // The water surface is the height of the lowest point on the rim.
+            let level = cells.iter().map(|&index| levels[index]).fold(0.0, f32::max);
+            let floor = cells
+                .iter()
+                .map(|&index| heightmap[index])
+                .fold(f32::INFINITY, f32::min);
+            let lake = cells.len() >= MIN_LAKE_CELLS && level - floor >= MIN_LAKE_DEPTH;
+            Basin {
+                cells,
+                outlet: None,
+                inflow: 0.0,
+                lake,
+            }
+        })
+        .collect();
+    for (id, spill) in basins.iter_mut().enumerate() {
+        // The lowest dry neighbor is where the filled surface overflows.
+        spill.outlet = spill
+            .cells
+            .iter()
+            .flat_map(|&index| neighbors(index, width, height))
+            .filter(|&next| basin[next] != id as u32)
+            .min_by(|&a, &b| levels[a].total_cmp(&levels[b]).then(a.cmp(&b)));
+    }
+
+    // Steepest descent over the filled surface; basin rims never drain back
+    // in, since the water stands exactly at their height.
+    let mut receiver: Vec<Option<usize>> = (0..size)
+        .map(|index| {
+            neighbors(index, width, height)
+                .filter(|&next| levels[next] < levels[index])
+                .min_by(|&a, &b| levels[a].total_cmp(&levels[b]).then(a.cmp(&b)))
+        })
+        .collect();
+    // Filled pits too small for a lake drain across their flat surface
+    // along the shortest hop path to the outlet, so flow still gathers into
+    // a single channel; lake cells point straight at their outlet.
+    for (id, spill) in basins.iter().enumerate() {
+        for &index in &spill.cells {
+            receiver[index] = None;
+        }
+        let Some(outlet) = spill.outlet else {
+            continue;
+        };
+        if spill.lake {
+            for &index in &spill.cells {
+                receiver[index] = Some(outlet);
+            }
+            continue;
+        }
+        let mut frontier = VecDeque::from([outlet]);
+        while let Some(current) = frontier.pop_front() {
+            for next in neighbors(current, width, height) {
+                if basin[next] == id as u32 && receiver[next].is_none() {
+                    receiver[next] = Some(current);
+                    frontier.push_back(next);
+                }
+            }
+        }
+    }
+
+    // Nodes are cells followed by basins; lakes gather everything flowing
+    // into any of their cells into their own node. Accumulate in
+    // topological order.
+    let is_lake: Vec<bool> = basins.iter().map(|spill| spill.lake).collect();
+    let lake_of = |index: usize| {
+        Some(basin[index])
+            .filter(|&id| id != u32::MAX && is_lake[id as usize])
+            .map(|id| size + id as usize)
+    };
+    let node_of = |index: usize| lake_of(index).unwrap_or(index);
+    let mut downstream: Vec<Option<usize>> = (0..size)
+        .map(|index| lake_of(index).or(receiver[index].map(node_of)))
+        .collect();
+    downstream.extend(
+        basins
+            .iter()
+            .map(|spill| spill.outlet.filter(|_| spill.lake)),
+    );
+    let mut pending = vec![0u32; downstream.len()];
+    for &target in downstream.iter().flatten() {
+        pending[target] += 1;
+    }
+    let mut node_flow: Vec<f32> = (0..downstream.len())
+        .map(|node| if node < size { 1.0 } else { 0.0 })
+        .collect();
+    let mut ready: Vec<usize> = (0..downstream.len())
+        .filter(|&node| pending[node] == 0)
+        .collect();
+    while let Some(node) = ready.pop() {
+        let mut outflow = node_flow[node];
+        if node >= size {
+            let spill = &mut basins[node - size];
+            spill.inflow = outflow;
+            outflow -= spill.evaporation(aridity);
+            if outflow <= 0.0 {
+                // Closed: the spill point stays dry, but still unblocks
+                // whatever drains past it.
+                spill.outlet = None;
+                outflow = 0.0;
+            }
+        }
+        if let Some(target) = downstream[node] {
+            node_flow[target] += outflow;
+            pending[target] -= 1;
+            if pending[target] == 0 {
+                ready.push(target);
+            }
+        }
+    }
+
+    for spill in basins.iter().filter(|spill| spill.lake) {
+        for &index in &spill.cells {
+            receiver[index] = spill.outlet;
+        }
+    }
+    let flow = (0..size)
+        .map(|index| node_flow[node_of(index)].max(1.0))
+        .collect();
+    LakeRouting {
+        receiver,
+        basin,
+        basins,
+        flow,
+    }
+}
+
+impl MapResult {
+    /// `route_lakes` over the stored layers, as `lake_outflows` generation
+    /// ran it.
+    pub(crate) fn lake_routing(&self) -> LakeRouting {
+        let aridity = aridity(
+            &self.temperature,
+            &self.base_moisture,
+            self.settings.moisture_scale,
+        );
+        route_lakes(
+            &self.heightmap,
+            &aridity,
+            self.width as usize,
+            self.height as usize,
+            self.sea_level,
+        )
+    }
+}
+
+/// Heat times dryness from temperature and unscaled noise moisture.
+pub(crate) fn aridity(temperature: &[f32], moisture: &[f32], moisture_scale: f32) -> Vec<f32> {
+    temperature
+        .iter()
+        .zip(moisture)
+        .map(|(&heat, &moisture)| heat * (1.0 - (moisture * moisture_scale).clamp(0.0, 1.0)))
+        .collect()
+}
+
+impl River {
+    pub(crate) fn length(&self, map: &MapResult) -> f32 {
+        self.cells
+            .windows(2)
+            .map(|pair| distance(map.cell_to_world(pair[0]), map.cell_to_world(pair[1])))
+            .sum()
+    }
+
+    /// Field list shared by the `rivers()` getter and the table exporter.
+    pub(crate) fn record(&self, map: &MapResult) -> Vec<(&'static str, Json)> {
+        let (source_x, source_y) = map.cell_to_world(self.cells[0]);
+        let (mouth_x, mouth_y) = map.cell_to_world(self.cells[self.cells.len() - 1]);
+        let joins = match self.outlet {
+            Outlet::Confluence(id) => Json::from(id),
+            _ => Json::Null,
+        };
+        let points = self
+            .cells
+            .iter()
+            .flat_map(|&index| {
+                let (x, y) = map.cell_to_world(index);
+                [Json::from(x), Json::from(y)]
+            })
+            .collect::<Vec<_>>();
+        vec![
+            ("id", self.id.into()),
+            ("source_x", source_x.into()),
+            ("source_y", source_y.into()),
+            ("mouth_x", mouth_x.into()),
+            ("mouth_y", mouth_y.into()),
+            ("length", self.length(map).into()),
+            ("max_flow", self.max_flow.into()),
+            ("outlet", self.outlet.key().into()),
+            ("joins", joins),
+            ("from_lake", self.from_lake.into()),
+            ("points", points.into()),
+        ]
+    }
+}
+
+impl Lake {
+    /// Closed basins where evaporation outweighs inflow.
+    pub(crate) fn is_salt(&self) -> bool {
+        self.drainage
+            .as_ref()
+            .is_some_and(|drainage| drainage.outlet.is_none())
+    }
+
+    pub(crate) fn record(&self, map: &MapResult) -> Vec<(&'static str, Json)> {
+        let (sum_x, sum_y) = self.cells.iter().fold((0.0f64, 0.0f64), |acc, &index| {
+            let (x, y) = map.cell_to_world(index);
+            (acc.0 + x as f64, acc.1 + y as f64)
+        });
+        let count = self.cells.len() as f64;
+        let outlet = self
+            .drainage
+            .as_ref()
+            .and_then(|drainage| drainage.outlet)
+            .map(|outlet| map.cell_to_world(outlet));
+        vec![
+            ("id", self.id.into()),
+            ("x", ((sum_x / count) as f32).into()),
+            ("y", ((sum_y / count) as f32).into()),
+            ("area_cells", self.cells.len().into()),
+            ("area", (self.cells.len() as f32 * map.cell_area()).into()),
+            ("outlet_x", outlet.map_or(Json::Null, |(x, _)| x.into())),
+            ("outlet_y", outlet.map_or(Json::Null, |(_, y)| y.into())),
+            (
+                "inflow",
+                self.drainage
+                    .as_ref()
+                    .map_or(Json::Null, |drainage| drainage.inflow.into()),
+            ),
+            ("salt", self.is_salt().into()),
+        ]
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// River polylines traced over the water layer, with `points` holding
+    /// interleaved world coordinates from source to outlet. `from_lake`
+    /// marks overflow rivers, whose points start inside the lake. `orders`
+    /// holds the Strahler order of each segment between consecutive points,
+    /// and `order` the highest along the river; see `stream_orders()`.
+    /// `snowmelt` is the snow-covered share of the basin above the river's
+    /// mouth; it sets `regime` (`"pluvial"`, `"nival"` from 0.15, or
+    /// `"glacial"` from 0.5) and the `spring_flow` and `autumn_flow`
+    /// multiples of the mean flow, which change smoothly with it.
+    pub fn rivers(&self) -> JsValue {
+        let records = river_records(self)
+            .into_iter()
+            .map(Json::from_record)
+            .collect::<Vec<_>>();
+        Json::from(records).to_js()
+    }
+
+    /// River sources as `{ river_id, x, y, flow }`: the upstream-most cell of
+    /// each river, where flow first crosses the river threshold with no river
+    /// cell draining into it. Tributaries have their own springs. Derived
+    /// from the flow on request rather than registered, so springs are not
+    /// in `features()` and keep no spacing from other point features.
+    pub fn springs(&self) -> JsValue {
+        let records = extract_rivers(self)
+            .iter()
+            .map(|river| {
+                let head = river.cells[0];
+                let (x, y) = self.cell_to_world(head);
+                Json::object()
+                    .with("river_id", river.id)
+                    .with("x", x)
+                    .with("y", y)
+                    .with("flow", self.flow[head])
+            })
+            .collect::<Vec<_>>();
+        Json::from(records).to_js()
+    }
+
+    /// Connected lake bodies with their centroid and area. Maps generated
+    /// with `lake_outflows` add the overflow's spill point as `outlet_x`,
+    /// `outlet_y` (`null` for closed basins), the total `inflow`, and `salt`
+    /// for closed lakes; otherwise these are `null` and `false`.
+    pub fn lakes(&self) -> JsValue {
+        let records = extract_lakes(self)
+            .iter()
+            .map(|lake| Json::from_record(lake.record(self)))
+            .collect::<Vec<_>>();
+        Json::from(records).to_js()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{extract_lakes, extract_rivers};
+    use crate::biome::Biome;
+    use crate::{generate_map, MapResult};
+
+    const SIZE: usize = 64;
+
+    /// A crater ringed by a high rim with a notch cut through its eastern
+    /// side, standing in a ring of ocean.
+    fn crater(heat: f32, moisture: f32) -> MapResult {
+        let mut map = generate_map(SIZE as u32, SIZE as u32, 1, 0.42, 1.0, 40.0, 2, 1.0);
+        map.settings.lake_outflows = true;
+        for index in 0..SIZE * SIZE {
+            let (x, y) = ((index % SIZE) as f32 - 32.0, (index / SIZE) as f32 - 32.0);
+            let r = x.hypot(y);
+            let slope = 0.8 - 0.025 * (r - 14.0);
+            map.heightmap[index] = if r > 28.0 {
+                0.2
+            } else if r < 12.0 && !(x > 0.0 && y.abs() <= 1.0 && r >= 11.0) {
+                0.5 + 0.01 * r
+            } else if x > 0.0 && y.abs() <= 1.0 {
+                slope.min(0.65 - 0.012 * (r - 11.0).max(0.0))
+            } else if r <= 14.0 {
+                0.8
+            } else {
+                slope
+            };
+            map.temperature[index] = heat;
+            map.base_moisture[index] = moisture;
+        }
+        let routing = map.lake_routing();
+        map.water = routing.water(&map.heightmap, map.sea_level);
+        map.flow = routing.flow;
+        for index in 0..SIZE * SIZE {
+            map.biome[index] = if map.heightmap[index] <= map.sea_level - 0.02 {
+                Biome::Ocean
+            } else if map.water[index] > 0.6 {
+                Biome::Lake
+            } else {
+                Biome::TemperateGrassland
+            }
+            .code();
+        }
+        map
+    }
+
+    #[test]
+    fn crater_lake_overflows_through_its_notch() {
+        let map = crater(0.36, 0.76);
+        let lakes = extract_lakes(&map);
+        assert_eq!(lakes.len(), 1);
+        let outlet = lakes[0].drainage.as_ref().unwrap().outlet.unwrap();
+        let (x, y) = (outlet % SIZE, outlet / SIZE);
+        assert!(x > 32 && y.abs_diff(32) <= 1, "outlet at ({x}, {y})");
+
+        let rivers = extract_rivers(&map);
+        let outflows: Vec<_> = rivers.iter().filter(|river| river.from_lake).collect();
+        assert_eq!(outflows.len(), 1);
+        let river = outflows[0];
+        assert!(map.is_water_body(river.cells[0]));
+        assert_eq!(river.cells[1], outlet);
+        // Downstream of the notch the river stays in the cut until the sea.
+        for &index in &river.cells[1..river.cells.len() - 1] {
+            assert!(index % SIZE > 32 && (index / SIZE).abs_diff(32) <= 1);
+        }
+        assert!(map.biome[*river.cells.last().unwrap()] == Biome::Ocean.code());
+        assert!(map.flow[outlet] > 1.0);
+    }
+
+    #[test]
+    fn arid_crater_lake_stays_closed_and_salty() {
+        let map = crater(1.0, 0.0);
+        let lakes = extract_lakes(&map);
+        assert_eq!(lakes.len(), 1);
+        assert!(lakes[0].is_salt());
+        assert!(extract_rivers(&map).iter().all(|river| !river.from_lake));
+    }
+}
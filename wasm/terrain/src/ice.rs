@@ -0,0 +1,178 @@
+use std::f32::consts::TAU;
+
+#[cfg(feature = "wasm")]
+use js_sys::Uint8Array;
+use noise::{NoiseFn, OpenSimplex};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::weather::{DAYS_PER_YEAR, MIDSUMMER};
+use crate::MapResult;
+
+/// Open water.
+pub(crate) const ICE_NONE: u8 = 0;
+/// Broken ice in the fringe above the freezing point; boats slow down.
+pub(crate) const ICE_PARTIAL: u8 = 1;
+/// Solid ice; impassable by boat.
+pub(crate) const ICE_SOLID: u8 = 2;
+
+#[derive(Clone)]
+pub(crate) struct IceOptions {
+    /// Temperature below which open water, lakes, and rivers freeze solid.
+    pub freezing_point: f32,
+    /// Temperature band above the freezing point holding scattered floes.
+    pub fringe: f32,
+    /// Feature size of the floe pattern in world units.
+    pub noise_scale: f32,
+    /// When set, shifts temperatures by the season, so ice can form only in
+    /// winter.
+    pub day_of_year: Option<f32>,
+    /// Temperature drop at midwinter, and rise at midsummer.
+    pub seasonal_swing: f32,
+}
+
+impl Default for IceOptions {
+    fn default() -> Self {
+        Self {
+            freezing_point: 0.15,
+            fringe: 0.08,
+            noise_scale: 48.0,
+            day_of_year: None,
+            seasonal_swing: 0.15,
+        }
+    }
+}
+
+impl IceOptions {
+    #[cfg(feature = "wasm")]
+    fn from_js(options: &JsValue) -> Self {
+        let defaults = Self::default();
+        Self {
+            freezing_point: js::get_f32(options, "freezing_point", defaults.freezing_point),
+            fringe: js::get_f32(options, "fringe", defaults.fringe).max(0.0),
+            noise_scale: js::get_f32(options, "noise_scale", defaults.noise_scale),
+            day_of_year: js::get(options, "day_of_year")
+                .and_then(|value| value.as_f64())
+                .map(|day| day as f32),
+            seasonal_swing: js::get_f32(options, "seasonal_swing", defaults.seasonal_swing),
+        }
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Freezes cold oceans, lakes, and rivers. Options: `freezing_point`
+    /// (temperature, default 0.15), `fringe` (temperature band of scattered
+    /// floes above it, default 0.08), `noise_scale` (world units), and
+    /// `day_of_year` with `seasonal_swing` (default 0.15) for seasonal ice.
+    /// Boat routes then avoid solid ice and slow down in floes.
+    pub fn set_ice(&mut self, options: JsValue) {
+        self.ice = Some(IceOptions::from_js(&options));
+        self.invalidate_roads();
+    }
+
+    /// Removes ice set by `set_ice`.
+    pub fn clear_ice(&mut self) {
+        self.ice = None;
+        self.invalidate_roads();
+    }
+
+    /// Per-cell ice: 0 open water or land, 1 floes, 2 solid. `undefined`
+    /// until `set_ice` is called.
+    pub fn ice(&self) -> Option<Uint8Array> {
+        self.ice_mask()
+            .map(|mask| Uint8Array::from(mask.as_slice()))
+    }
+}
+
+impl MapResult {
+    /// Ice for the current `set_ice` options, if any.
+    pub(crate) fn ice_mask(&self) -> Option<Vec<u8>> {
+        self.ice.as_ref().map(|options| ice_mask(self, options))
+    }
+}
+
+pub(crate) fn ice_mask(map: &MapResult, options: &IceOptions) -> Vec<u8> {
+    let noise = OpenSimplex::new(map.settings.seed.wrapping_add(431));
+    let seasonal = options.day_of_year.map_or(0.0, |day| {
+        ((day - MIDSUMMER) / DAYS_PER_YEAR * TAU).cos() * options.seasonal_swing
+    });
+    let scale = options.noise_scale.max(f32::EPSILON) as f64;
+    (0..map.biome.len())
+        .map(|index| {
+            if !map.is_water_body(index) && !map.is_river(index) {
+                return ICE_NONE;
+            }
+            let temperature = map.temperature[index] + seasonal;
+            if temperature < options.freezing_point {
+                return ICE_SOLID;
+            }
+            if options.fringe <= 0.0 {
+                return ICE_NONE;
+            }
+            let cover = 1.0 - (temperature - options.freezing_point) / options.fringe;
+            if cover <= 0.0 {
+                return ICE_NONE;
+            }
+            let (x, y) = map.cell_to_world(index);
+            let floe = noise.get([x as f64 / scale, y as f64 / scale]) as f32 * 0.5 + 0.5;
+            if floe < cover {
+                ICE_PARTIAL
+            } else {
+                ICE_NONE
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IceOptions, ICE_NONE, ICE_PARTIAL, ICE_SOLID};
+    use crate::generate_map;
+    use crate::movement::{build_cost_field, Profile};
+
+    #[test]
+    fn solid_ice_blocks_boats_and_floes_double_their_cost() {
+        let mut map = generate_map(96, 96, 11, 0.42, 1.0, 40.0, 2, 1.0);
+        let boat = Profile::preset("boat").unwrap();
+        let open = build_cost_field(&map, &boat);
+
+        // Freeze the colder half of the water, with floes just above it.
+        let mut temperatures: Vec<f32> = (0..open.len())
+            .filter(|&index| map.is_water_body(index))
+            .map(|index| map.temperature[index])
+            .collect();
+        temperatures.sort_unstable_by(f32::total_cmp);
+        map.ice = Some(IceOptions {
+            freezing_point: temperatures[temperatures.len() / 2],
+            fringe: 0.1,
+            ..IceOptions::default()
+        });
+        map.invalidate_roads();
+        let mask = map.ice_mask().unwrap();
+        let frozen = build_cost_field(&map, &boat);
+
+        let mut seen = [0; 3];
+        for (index, &ice) in mask.iter().enumerate() {
+            seen[ice as usize] += 1;
+            match ice {
+                ICE_SOLID => assert!(frozen[index].is_infinite()),
+                ICE_PARTIAL if open[index].is_finite() => {
+                    assert_eq!(frozen[index], open[index] * 2.0);
+                }
+                ICE_PARTIAL => assert!(frozen[index].is_infinite()),
+                ICE_NONE => assert_eq!(frozen[index].to_bits(), open[index].to_bits()),
+                _ => unreachable!(),
+            }
+        }
+        assert!(seen.iter().all(|&count| count > 0), "{seen:?}");
+
+        // Clearing the ice restores open-water costs.
+        map.ice = None;
+        map.invalidate_roads();
+        assert_eq!(build_cost_field(&map, &boat), open);
+    }
+}
@@ -0,0 +1,97 @@
+use crate::thermal::apply_thermal_erosion;
+use crate::{accumulate_flow, downslope_map, GenerationSettings, REGION_SIZE};
+
+/// Share of the drop to the downstream cell one pass may cut, so a channel
+/// deepens without digging pits below its own outlet.
+const MAX_CUT: f32 = 0.5;
+
+/// Stream-power incision: `incision_iterations` rounds of routing flow over
+/// the current heightmap, then lowering each land cell by
+/// `incision_k * area^incision_m * gradient^incision_n`, where `area` is the
+/// share of the map draining through it and `gradient` the world rise over
+/// run to its receiver. A thermal pass after each round slumps the fresh
+/// walls back to the talus angle. Zero iterations leave the heightmap
+/// untouched.
+pub(crate) fn apply_incision(
+    heightmap: &mut [f32],
+    width: u32,
+    height: u32,
+    settings: &GenerationSettings,
+) {
+    let width_i = width as usize;
+    let cell_w = REGION_SIZE / width as f32;
+    let cell_h = REGION_SIZE / height as f32;
+    let cells = heightmap.len() as f32;
+    for _ in 0..settings.incision_iterations {
+        let receivers = downslope_map(heightmap, width, height);
+        let flow = accumulate_flow(heightmap, &receivers);
+        let mut cuts = vec![0.0f32; heightmap.len()];
+        for (index, receiver) in receivers.iter().enumerate() {
+            let Some(target) = *receiver else {
+                continue;
+            };
+            if heightmap[index] <= settings.sea_level {
+                continue;
+            }
+            let drop = heightmap[index] - heightmap[target];
+            let dx = (index % width_i).abs_diff(target % width_i) as f32 * cell_w;
+            let dy = (index / width_i).abs_diff(target / width_i) as f32 * cell_h;
+            let gradient = drop * settings.vertical_scale / dx.hypot(dy);
+            let power = settings.incision_k
+                * (flow[index] / cells).powf(settings.incision_m)
+                * gradient.powf(settings.incision_n);
+            cuts[index] = power.min(drop * MAX_CUT);
+        }
+        for (height, cut) in heightmap.iter_mut().zip(&cuts) {
+            *height -= cut;
+        }
+        apply_thermal_erosion(heightmap, width, height, 1, settings);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::apply_incision;
+    use crate::GenerationSettings;
+
+    const SIZE: usize = 64;
+
+    /// A plateau tilted east with a faint trough along the middle row.
+    fn plateau() -> Vec<f32> {
+        (0..SIZE * SIZE)
+            .map(|index| {
+                let (x, y) = (index % SIZE, index / SIZE);
+                0.8 - 0.004 * x as f32 + 0.0005 * y.abs_diff(SIZE / 2) as f32
+            })
+            .collect()
+    }
+
+    #[test]
+    fn incision_cuts_one_gorge_down_the_trough() {
+        let idle = GenerationSettings::default();
+        let mut untouched = plateau();
+        apply_incision(&mut untouched, SIZE as u32, SIZE as u32, &idle);
+        assert_eq!(untouched, plateau());
+
+        let settings = GenerationSettings {
+            incision_iterations: 12,
+            ..GenerationSettings::default()
+        };
+        let mut carved = plateau();
+        apply_incision(&mut carved, SIZE as u32, SIZE as u32, &settings);
+        let before = plateau();
+        let lowered = |x: usize, y: usize| before[y * SIZE + x] - carved[y * SIZE + x];
+        let gorge = lowered(48, SIZE / 2);
+        assert!(gorge > 0.02, "gorge cut {gorge}");
+        // Sheet flow lowers the flanks a little; the gorge is a few cells
+        // wide and deepens downstream.
+        for y in [SIZE / 2 - 12, SIZE / 2 - 3, SIZE / 2 + 3, SIZE / 2 + 12] {
+            assert!(
+                lowered(48, y) < gorge * 0.2,
+                "{} vs {gorge}",
+                lowered(48, y)
+            );
+        }
+        assert!(lowered(16, SIZE / 2) < gorge);
+    }
+}
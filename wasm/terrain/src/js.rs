@@ -0,0 +1,48 @@
+#[cfg(feature = "wasm")]
+use js_sys::{Array, Object, Reflect};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+pub(crate) fn set(obj: &Object, key: &str, value: &JsValue) {
+    Reflect::set(obj, &JsValue::from(key), value).ok();
+}
+
+pub(crate) fn get(options: &JsValue, key: &str) -> Option<JsValue> {
+    if !options.is_object() {
+        return None;
+    }
+    Reflect::get(options, &JsValue::from(key))
+        .ok()
+        .filter(|value| !value.is_undefined() && !value.is_null())
+}
+
+pub(crate) fn get_f32(options: &JsValue, key: &str, default: f32) -> f32 {
+    get(options, key)
+        .and_then(|value| value.as_f64())
+        .map(|value| value as f32)
+        .unwrap_or(default)
+}
+
+pub(crate) fn get_bool(options: &JsValue, key: &str, default: bool) -> bool {
+    get(options, key)
+        .and_then(|value| value.as_bool())
+        .unwrap_or(default)
+}
+
+pub(crate) fn get_array(options: &JsValue, key: &str) -> Option<Array> {
+    get(options, key)
+        .filter(Array::is_array)
+        .map(|value| Array::from(&value))
+}
+
+pub(crate) fn get_u32(options: &JsValue, key: &str, default: u32) -> u32 {
+    get(options, key)
+        .and_then(|value| value.as_f64())
+        .filter(|value| *value >= 0.0)
+        .map(|value| value as u32)
+        .unwrap_or(default)
+}
+
+pub(crate) fn get_string(options: &JsValue, key: &str) -> Option<String> {
+    get(options, key).and_then(|value| value.as_string())
+}
@@ -0,0 +1,161 @@
+use std::fmt::{self, Write};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::JsValue;
+
+/// Minimal JSON document model used by the string exporters.
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    /// Single-precision layer values, printed at f32 precision.
+    Float(f32),
+    String(String),
+    Array(Vec<Json>),
+    /// Dense integer arrays (tile data, masks) serialized without per-item boxing.
+    Integers(Vec<u32>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub(crate) fn object() -> Self {
+        Json::Object(Vec::new())
+    }
+
+    pub(crate) fn from_record(fields: Vec<(&'static str, Json)>) -> Self {
+        Json::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    /// Converts to a plain JS value by round-tripping through `JSON.parse`.
+    #[cfg(feature = "wasm")]
+    pub(crate) fn to_js(&self) -> JsValue {
+        js_sys::JSON::parse(&self.to_string()).unwrap_or(JsValue::NULL)
+    }
+
+    /// Appends a field to an object value; ignored for other variants.
+    pub(crate) fn with(mut self, key: &str, value: impl Into<Json>) -> Self {
+        if let Json::Object(fields) = &mut self {
+            fields.push((key.to_string(), value.into()));
+        }
+        self
+    }
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Self {
+        Json::Bool(value)
+    }
+}
+
+impl From<f32> for Json {
+    fn from(value: f32) -> Self {
+        Json::Float(value)
+    }
+}
+
+impl From<f64> for Json {
+    fn from(value: f64) -> Self {
+        Json::Number(value)
+    }
+}
+
+impl From<u32> for Json {
+    fn from(value: u32) -> Self {
+        Json::Number(value as f64)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(value: usize) -> Self {
+        Json::Number(value as f64)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(value: &str) -> Self {
+        Json::String(value.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(value: String) -> Self {
+        Json::String(value)
+    }
+}
+
+impl From<Vec<Json>> for Json {
+    fn from(value: Vec<Json>) -> Self {
+        Json::Array(value)
+    }
+}
+
+impl From<Vec<u32>> for Json {
+    fn from(value: Vec<u32>) -> Self {
+        Json::Integers(value)
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(value) => write!(f, "{value}"),
+            Json::Number(value) if value.is_finite() => write!(f, "{value}"),
+            Json::Float(value) if value.is_finite() => write!(f, "{value}"),
+            Json::Number(_) | Json::Float(_) => f.write_str("null"),
+            Json::String(value) => write_escaped(f, value),
+            Json::Array(items) => {
+                f.write_char('[')?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                f.write_char(']')
+            }
+            Json::Integers(items) => {
+                f.write_char('[')?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                f.write_char(']')
+            }
+            Json::Object(fields) => {
+                f.write_char('{')?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_escaped(f, key)?;
+                    write!(f, ":{value}")?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+fn write_escaped(f: &mut fmt::Formatter<'_>, value: &str) -> fmt::Result {
+    f.write_char('"')?;
+    for c in value.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
@@ -0,0 +1,422 @@
+#[cfg(feature = "wasm")]
+use js_sys::Float32Array;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::geometry::distance;
+#[cfg(feature = "wasm")]
+use crate::js;
+#[cfg(feature = "wasm")]
+use crate::json::Json;
+#[cfg(feature = "wasm")]
+use crate::MapResult;
+use crate::REGION_SIZE;
+
+/// Fractions along a path tried for linear labels, best first.
+const PATH_STATIONS: [f32; 5] = [0.5, 0.35, 0.65, 0.2, 0.8];
+
+/// Something to label: a point such as a settlement, an area such as a sea
+/// centred on its anchor, or a line such as a river or ridge.
+pub(crate) struct Label {
+    /// Anchor in world units.
+    pub x: f32,
+    pub y: f32,
+    /// Label size in screen pixels.
+    pub width: f32,
+    pub height: f32,
+    /// Higher priorities are placed first.
+    pub priority: f32,
+    pub shape: Shape,
+}
+
+pub(crate) enum Shape {
+    Point,
+    Area,
+    /// Interleaved world `x, y` points.
+    Path(Vec<f32>),
+}
+
+pub(crate) struct LabelOptions {
+    /// Screen pixels per world unit.
+    pub scale: f32,
+    /// Clearance kept between labels, in pixels.
+    pub padding: f32,
+    /// Radius of the dot drawn at each point anchor, in pixels. Labels never
+    /// cover a dot.
+    pub dot_radius: f32,
+}
+
+impl Default for LabelOptions {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            padding: 2.0,
+            dot_radius: 3.0,
+        }
+    }
+}
+
+impl LabelOptions {
+    #[cfg(feature = "wasm")]
+    fn from_js(options: &JsValue) -> Self {
+        let defaults = Self::default();
+        Self {
+            scale: js::get_f32(options, "scale", defaults.scale),
+            padding: js::get_f32(options, "padding", defaults.padding).max(0.0),
+            dot_radius: js::get_f32(options, "dot_radius", defaults.dot_radius).max(0.0),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Position {
+    Right,
+    Left,
+    Above,
+    Below,
+    Center,
+    Path,
+}
+
+impl Position {
+    #[cfg(feature = "wasm")]
+    fn key(self) -> &'static str {
+        match self {
+            Position::Right => "right",
+            Position::Left => "left",
+            Position::Above => "above",
+            Position::Below => "below",
+            Position::Center => "center",
+            Position::Path => "path",
+        }
+    }
+}
+
+/// Where a label went: its centre offset from the anchor in pixels, and for
+/// path labels the rotation in radians.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Placement {
+    pub position: Position,
+    pub dx: f32,
+    pub dy: f32,
+    pub angle: f32,
+}
+
+/// Screen-space box, `min` inclusive and `max` exclusive.
+#[derive(Clone, Copy)]
+struct Rect {
+    x0: f32,
+    y0: f32,
+    x1: f32,
+    y1: f32,
+}
+
+impl Rect {
+    fn centered(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Rect {
+            x0: x - width * 0.5,
+            y0: y - height * 0.5,
+            x1: x + width * 0.5,
+            y1: y + height * 0.5,
+        }
+    }
+
+    fn overlaps(&self, other: &Rect, padding: f32) -> bool {
+        self.x0 < other.x1 + padding
+            && other.x0 < self.x1 + padding
+            && self.y0 < other.y1 + padding
+            && other.y0 < self.y1 + padding
+    }
+}
+
+/// Placed boxes bucketed on a coarse grid so each test only visits nearby
+/// labels.
+struct Occupancy {
+    cell: f32,
+    buckets: std::collections::HashMap<(i32, i32), Vec<Rect>>,
+}
+
+impl Occupancy {
+    fn new(cell: f32) -> Self {
+        Self {
+            cell: cell.max(1.0),
+            buckets: Default::default(),
+        }
+    }
+
+    fn span(&self, rect: &Rect, padding: f32) -> impl Iterator<Item = (i32, i32)> {
+        let cell = self.cell;
+        let (x0, x1) = (
+            ((rect.x0 - padding) / cell).floor() as i32,
+            ((rect.x1 + padding) / cell).floor() as i32,
+        );
+        let (y0, y1) = (
+            ((rect.y0 - padding) / cell).floor() as i32,
+            ((rect.y1 + padding) / cell).floor() as i32,
+        );
+        (y0..=y1).flat_map(move |y| (x0..=x1).map(move |x| (x, y)))
+    }
+
+    fn collides(&self, rect: &Rect, padding: f32) -> bool {
+        self.span(rect, padding).any(|key| {
+            self.buckets
+                .get(&key)
+                .is_some_and(|rects| rects.iter().any(|other| rect.overlaps(other, padding)))
+        })
+    }
+
+    fn insert(&mut self, rect: Rect) {
+        let keys: Vec<_> = self.span(&rect, 0.0).collect();
+        for key in keys {
+            self.buckets.entry(key).or_default().push(rect);
+        }
+    }
+}
+
+/// Greedy placement: labels go in descending priority, each taking its first
+/// candidate that stays on the map and clear of every dot and every label
+/// already placed. `None` marks a label dropped at this scale.
+pub(crate) fn place_labels(labels: &[Label], options: &LabelOptions) -> Vec<Option<Placement>> {
+    let scale = options.scale;
+    let extent = REGION_SIZE * scale;
+    let largest = labels
+        .iter()
+        .map(|label| label.width.max(label.height))
+        .fold(options.dot_radius * 2.0, f32::max);
+
+    let mut occupied = Occupancy::new(largest);
+    let dots: Vec<usize> = (0..labels.len())
+        .filter(|&i| matches!(labels[i].shape, Shape::Point))
+        .collect();
+    for &i in &dots {
+        let diameter = options.dot_radius * 2.0;
+        occupied.insert(Rect::centered(
+            labels[i].x * scale,
+            labels[i].y * scale,
+            diameter,
+            diameter,
+        ));
+    }
+
+    let mut order: Vec<usize> = (0..labels.len()).collect();
+    order.sort_by(|&a, &b| {
+        labels[b]
+            .priority
+            .total_cmp(&labels[a].priority)
+            .then(a.cmp(&b))
+    });
+    let mut placements = vec![None; labels.len()];
+    for i in order {
+        let label = &labels[i];
+        let (ax, ay) = (label.x * scale, label.y * scale);
+        let placement = candidates(label, options).into_iter().find(|candidate| {
+            let (width, height) = rotated_extent(label, candidate.angle);
+            let rect = Rect::centered(ax + candidate.dx, ay + candidate.dy, width, height);
+            rect.x0 >= 0.0
+                && rect.y0 >= 0.0
+                && rect.x1 <= extent
+                && rect.y1 <= extent
+                && !occupied.collides(&rect, options.padding)
+        });
+        if let Some(placement) = placement {
+            let (width, height) = rotated_extent(label, placement.angle);
+            occupied.insert(Rect::centered(
+                ax + placement.dx,
+                ay + placement.dy,
+                width,
+                height,
+            ));
+        }
+        placements[i] = placement;
+    }
+    placements
+}
+
+fn candidates(label: &Label, options: &LabelOptions) -> Vec<Placement> {
+    let at = |position, dx, dy| Placement {
+        position,
+        dx,
+        dy,
+        angle: 0.0,
+    };
+    match &label.shape {
+        Shape::Point => {
+            // Clear of the dot, with the padding the occupancy test expects.
+            let gap = options.dot_radius + options.padding;
+            let half_w = label.width * 0.5 + gap;
+            let half_h = label.height * 0.5 + gap;
+            vec![
+                at(Position::Right, half_w, 0.0),
+                at(Position::Left, -half_w, 0.0),
+                at(Position::Above, 0.0, -half_h),
+                at(Position::Below, 0.0, half_h),
+            ]
+        }
+        Shape::Area => vec![at(Position::Center, 0.0, 0.0)],
+        Shape::Path(points) => {
+            let points: Vec<(f32, f32)> = points
+                .chunks_exact(2)
+                .map(|point| (point[0], point[1]))
+                .collect();
+            PATH_STATIONS
+                .iter()
+                .filter_map(|&fraction| station(&points, fraction))
+                .map(|((x, y), angle)| Placement {
+                    position: Position::Path,
+                    dx: (x - label.x) * options.scale,
+                    dy: (y - label.y) * options.scale,
+                    angle,
+                })
+                .collect()
+        }
+    }
+}
+
+/// Point `fraction` of the way along a polyline and the direction of travel
+/// there, flipped so text never reads upside down.
+fn station(points: &[(f32, f32)], fraction: f32) -> Option<((f32, f32), f32)> {
+    let lengths: Vec<f32> = points
+        .windows(2)
+        .map(|pair| distance(pair[0], pair[1]))
+        .collect();
+    let total: f32 = lengths.iter().sum();
+    if total <= 0.0 {
+        return None;
+    }
+    let mut remaining = total * fraction;
+    for (pair, &length) in points.windows(2).zip(&lengths) {
+        if remaining <= length && length > 0.0 {
+            let t = remaining / length;
+            let (dx, dy) = (pair[1].0 - pair[0].0, pair[1].1 - pair[0].1);
+            let mut angle = dy.atan2(dx);
+            if angle > std::f32::consts::FRAC_PI_2 {
+                angle -= std::f32::consts::PI;
+            } else if angle < -std::f32::consts::FRAC_PI_2 {
+                angle += std::f32::consts::PI;
+            }
+            return Some(((pair[0].0 + dx * t, pair[0].1 + dy * t), angle));
+        }
+        remaining -= length;
+    }
+    None
+}
+
+/// Axis-aligned size of the label box turned by `angle`.
+fn rotated_extent(label: &Label, angle: f32) -> (f32, f32) {
+    let (sin, cos) = (angle.sin().abs(), angle.cos().abs());
+    (
+        label.width * cos + label.height * sin,
+        label.width * sin + label.height * cos,
+    )
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Places non-overlapping labels for one zoom level. `labels` lists `{ x,
+    /// y, width, height, priority, kind, path }`: anchors in world units,
+    /// sizes in screen pixels, and `kind` `"point"` (default; tried right,
+    /// left, above, then below a dot), `"area"` (centred on the anchor), or
+    /// `"path"` (centred at points along `path`, interleaved world `x, y`,
+    /// and turned to follow it). Settlement size and feature area make good
+    /// priorities. Options: `scale` (pixels per world unit, default 1),
+    /// `padding` (pixels between labels, default 2), and `dot_radius`
+    /// (pixels kept clear around point anchors, default 3). Returns one `{
+    /// placed, position, dx, dy, angle }` per label in input order, offsets
+    /// in pixels from the anchor to the label centre; `placed` is false for
+    /// labels dropped at this scale.
+    pub fn place_labels(&self, labels: JsValue, options: JsValue) -> Result<JsValue, JsValue> {
+        let labels = js_sys::Array::from(&labels)
+            .iter()
+            .map(|entry| {
+                let shape = match js::get_string(&entry, "kind").as_deref() {
+                    None | Some("point") => Shape::Point,
+                    Some("area") => Shape::Area,
+                    Some("path") => {
+                        let path = js::get(&entry, "path")
+                            .ok_or_else(|| JsValue::from_str("path labels need a path"))?;
+                        Shape::Path(Float32Array::new(&path).to_vec())
+                    }
+                    Some(other) => {
+                        return Err(JsValue::from_str(&format!("unknown label kind: {other}")))
+                    }
+                };
+                Ok(Label {
+                    x: js::get_f32(&entry, "x", 0.0),
+                    y: js::get_f32(&entry, "y", 0.0),
+                    width: js::get_f32(&entry, "width", 0.0).max(0.0),
+                    height: js::get_f32(&entry, "height", 0.0).max(0.0),
+                    priority: js::get_f32(&entry, "priority", 0.0),
+                    shape,
+                })
+            })
+            .collect::<Result<Vec<_>, JsValue>>()?;
+        let records = place_labels(&labels, &LabelOptions::from_js(&options))
+            .into_iter()
+            .map(|placement| match placement {
+                Some(placement) => Json::object()
+                    .with("placed", true)
+                    .with("position", placement.position.key())
+                    .with("dx", placement.dx)
+                    .with("dy", placement.dy)
+                    .with("angle", placement.angle),
+                None => Json::object().with("placed", false),
+            })
+            .collect::<Vec<_>>();
+        Ok(Json::from(records).to_js())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{place_labels, Label, LabelOptions, Position, Shape};
+
+    fn label(x: f32, y: f32, priority: f32, shape: Shape) -> Label {
+        Label {
+            x,
+            y,
+            width: 40.0,
+            height: 10.0,
+            priority,
+            shape,
+        }
+    }
+
+    #[test]
+    fn labels_step_aside_then_drop_when_crowded() {
+        let options = LabelOptions::default();
+        let labels = [
+            label(1000.0, 1000.0, 1.0, Shape::Point),
+            label(1010.0, 1000.0, 5.0, Shape::Point),
+            label(
+                900.0,
+                900.0,
+                2.0,
+                Shape::Path(vec![800.0, 900.0, 1000.0, 900.0]),
+            ),
+        ];
+        let placed = place_labels(&labels, &options);
+        // The bigger settlement takes the right, so the smaller one's label
+        // has to go elsewhere.
+        assert_eq!(placed[1].unwrap().position, Position::Right);
+        assert_ne!(placed[0].unwrap().position, Position::Right);
+        let river = placed[2].unwrap();
+        assert_eq!(river.position, Position::Path);
+        assert!(river.angle.abs() < 1e-6);
+
+        // Zoomed out, a cluster of settlements has room for only a few.
+        let far = LabelOptions {
+            scale: 0.05,
+            ..LabelOptions::default()
+        };
+        let cluster: Vec<Label> = (0..6)
+            .map(|i| label(1000.0 + i as f32, 1000.0, i as f32, Shape::Point))
+            .collect();
+        let placed = place_labels(&cluster, &far);
+        assert!(placed[5].is_some());
+        assert!(placed.iter().any(Option::is_none));
+
+        let edge = [label(2047.0, 1000.0, 1.0, Shape::Area)];
+        assert!(place_labels(&edge, &options)[0].is_none());
+    }
+}
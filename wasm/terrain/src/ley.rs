@@ -0,0 +1,387 @@
+#[cfg(feature = "wasm")]
+use js_sys::{Float32Array, Object};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::geometry::{distance, segment_distance};
+use crate::hydrology::extract_lakes;
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::json::Json;
+use crate::{MapResult, SimpleRng, REGION_SIZE};
+
+/// Points sampled along each ley curve.
+const CURVE_SAMPLES: usize = 24;
+/// Smaller lakes are too minor to anchor a line.
+const MIN_LAKE_CELLS: usize = 6;
+
+pub(crate) struct LeyOptions {
+    /// Highest peaks used as anchors.
+    pub peaks: u32,
+    /// Minimum world distance between peak anchors.
+    pub min_spacing: f32,
+    /// Longest allowed link, in world units.
+    pub max_link: f32,
+    /// Nearest neighbors each anchor links to.
+    pub links_per_anchor: u32,
+    /// Sideways bow of each curve as a fraction of its length.
+    pub bend: f32,
+    /// Falloff width of the intensity field around a line, in world units.
+    pub width: f32,
+}
+
+impl Default for LeyOptions {
+    fn default() -> Self {
+        Self {
+            peaks: 6,
+            min_spacing: 256.0,
+            max_link: 900.0,
+            links_per_anchor: 2,
+            bend: 0.25,
+            width: 24.0,
+        }
+    }
+}
+
+impl LeyOptions {
+    #[cfg(feature = "wasm")]
+    fn from_js(options: &JsValue) -> Self {
+        let defaults = Self::default();
+        Self {
+            peaks: js::get_u32(options, "peaks", defaults.peaks),
+            min_spacing: js::get_f32(options, "min_spacing", defaults.min_spacing),
+            max_link: js::get_f32(options, "max_link", defaults.max_link),
+            links_per_anchor: js::get_u32(options, "links_per_anchor", defaults.links_per_anchor),
+            bend: js::get_f32(options, "bend", defaults.bend),
+            width: js::get_f32(options, "width", defaults.width).max(f32::EPSILON),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AnchorKind {
+    Peak,
+    Lake,
+}
+
+impl AnchorKind {
+    fn key(self) -> &'static str {
+        match self {
+            AnchorKind::Peak => "peak",
+            AnchorKind::Lake => "lake",
+        }
+    }
+}
+
+pub(crate) struct LeyAnchor {
+    pub kind: AnchorKind,
+    pub x: f32,
+    pub y: f32,
+}
+
+pub(crate) struct LeyLine {
+    pub from: usize,
+    pub to: usize,
+    /// World `(x, y)` samples from `from` to `to`.
+    pub points: Vec<(f32, f32)>,
+}
+
+/// Where two lines cross, or where three or more meet at an anchor.
+pub(crate) struct Nexus {
+    pub x: f32,
+    pub y: f32,
+    pub lines: Vec<usize>,
+}
+
+pub(crate) struct LeyNetwork {
+    pub anchors: Vec<LeyAnchor>,
+    pub lines: Vec<LeyLine>,
+    pub nexuses: Vec<Nexus>,
+    pub intensity: Vec<f32>,
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Fantasy ley-line network, built only when called. Returns `{ anchors,
+    /// lines, nexuses, intensity }`: anchors `{ kind, x, y }`, lines `{ from,
+    /// to, points }` with interleaved world coordinates, nexuses `{ x, y,
+    /// lines }`, and a per-cell 0..1 magical intensity. Options: `peaks`,
+    /// `min_spacing`, `max_link`, `links_per_anchor`, `bend`, `width`.
+    pub fn ley_lines(&self, options: JsValue) -> Object {
+        let network = ley_network(self, &LeyOptions::from_js(&options));
+        let anchors = network
+            .anchors
+            .iter()
+            .map(|anchor| {
+                Json::object()
+                    .with("kind", anchor.kind.key())
+                    .with("x", anchor.x)
+                    .with("y", anchor.y)
+            })
+            .collect::<Vec<_>>();
+        let lines = network
+            .lines
+            .iter()
+            .map(|line| {
+                let points = line
+                    .points
+                    .iter()
+                    .flat_map(|&(x, y)| [Json::from(x), Json::from(y)])
+                    .collect::<Vec<_>>();
+                Json::object()
+                    .with("from", line.from)
+                    .with("to", line.to)
+                    .with("points", points)
+            })
+            .collect::<Vec<_>>();
+        let nexuses = network
+            .nexuses
+            .iter()
+            .map(|nexus| {
+                let lines = nexus
+                    .lines
+                    .iter()
+                    .map(|&line| Json::from(line))
+                    .collect::<Vec<_>>();
+                Json::object()
+                    .with("x", nexus.x)
+                    .with("y", nexus.y)
+                    .with("lines", lines)
+            })
+            .collect::<Vec<_>>();
+        let result = Object::new();
+        js::set(&result, "anchors", &Json::from(anchors).to_js());
+        js::set(&result, "lines", &Json::from(lines).to_js());
+        js::set(&result, "nexuses", &Json::from(nexuses).to_js());
+        js::set(
+            &result,
+            "intensity",
+            &Float32Array::from(network.intensity.as_slice()).into(),
+        );
+        result
+    }
+}
+
+/// Builds the network in a fixed stage order, each stage reading only the
+/// ones before it: anchors, links, curves, nexuses, then the intensity field.
+/// Anything placed from nexuses (standing stones and the like) must run after
+/// this. Ruins will join the anchors once the map has them.
+pub(crate) fn ley_network(map: &MapResult, options: &LeyOptions) -> LeyNetwork {
+    let anchors = ley_anchors(map, options);
+    let links = ley_links(&anchors, options);
+    let mut rng = SimpleRng::new(map.settings.seed.wrapping_add(503));
+    let lines: Vec<LeyLine> = links
+        .into_iter()
+        .map(|(from, to)| LeyLine {
+            from,
+            to,
+            points: bowed_curve(&anchors[from], &anchors[to], options.bend, &mut rng),
+        })
+        .collect();
+    let nexuses = find_nexuses(&anchors, &lines);
+    let intensity = splat_intensity(map, &lines, &nexuses, options.width);
+    LeyNetwork {
+        anchors,
+        lines,
+        nexuses,
+        intensity,
+    }
+}
+
+/// The highest land cells at least `min_spacing` apart, then the centroids
+/// of lakes with at least `MIN_LAKE_CELLS` cells.
+fn ley_anchors(map: &MapResult, options: &LeyOptions) -> Vec<LeyAnchor> {
+    let mut cells: Vec<usize> = (0..map.heightmap.len())
+        .filter(|&index| !map.is_water_body(index))
+        .collect();
+    cells.sort_by(|&a, &b| {
+        map.heightmap[b]
+            .total_cmp(&map.heightmap[a])
+            .then(a.cmp(&b))
+    });
+    let mut anchors: Vec<LeyAnchor> = Vec::new();
+    for index in cells {
+        if anchors.len() >= options.peaks as usize {
+            break;
+        }
+        let (x, y) = map.cell_to_world(index);
+        if anchors
+            .iter()
+            .all(|anchor| distance((anchor.x, anchor.y), (x, y)) >= options.min_spacing)
+        {
+            anchors.push(LeyAnchor {
+                kind: AnchorKind::Peak,
+                x,
+                y,
+            });
+        }
+    }
+    for lake in extract_lakes(map) {
+        if lake.cells.len() < MIN_LAKE_CELLS {
+            continue;
+        }
+        let count = lake.cells.len() as f32;
+        let (x, y) = lake.cells.iter().fold((0.0, 0.0), |acc, &index| {
+            let (x, y) = map.cell_to_world(index);
+            (acc.0 + x / count, acc.1 + y / count)
+        });
+        anchors.push(LeyAnchor {
+            kind: AnchorKind::Lake,
+            x,
+            y,
+        });
+    }
+    anchors
+}
+
+/// Each anchor's nearest neighbors within `max_link`, without duplicates.
+fn ley_links(anchors: &[LeyAnchor], options: &LeyOptions) -> Vec<(usize, usize)> {
+    let mut links = Vec::new();
+    for (a, anchor) in anchors.iter().enumerate() {
+        let mut near: Vec<(f32, usize)> = anchors
+            .iter()
+            .enumerate()
+            .filter(|&(b, _)| b != a)
+            .map(|(b, other)| (distance((other.x, other.y), (anchor.x, anchor.y)), b))
+            .filter(|&(distance, _)| distance <= options.max_link)
+            .collect();
+        near.sort_by(|x, y| x.0.total_cmp(&y.0).then(x.1.cmp(&y.1)));
+        for &(_, b) in near.iter().take(options.links_per_anchor as usize) {
+            let link = (a.min(b), a.max(b));
+            if !links.contains(&link) {
+                links.push(link);
+            }
+        }
+    }
+    links.sort_unstable();
+    links
+}
+
+/// Quadratic Bézier whose control point sits off the midpoint by a seeded
+/// fraction of `bend`, to either side.
+fn bowed_curve(
+    from: &LeyAnchor,
+    to: &LeyAnchor,
+    bend: f32,
+    rng: &mut SimpleRng,
+) -> Vec<(f32, f32)> {
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    let offset = (rng.next_f32() * 2.0 - 1.0) * bend;
+    let control = (
+        (from.x + to.x) * 0.5 - dy * offset,
+        (from.y + to.y) * 0.5 + dx * offset,
+    );
+    (0..=CURVE_SAMPLES)
+        .map(|step| {
+            let t = step as f32 / CURVE_SAMPLES as f32;
+            let u = 1.0 - t;
+            let x = u * u * from.x + 2.0 * u * t * control.0 + t * t * to.x;
+            let y = u * u * from.y + 2.0 * u * t * control.1 + t * t * to.y;
+            (x.clamp(0.0, REGION_SIZE), y.clamp(0.0, REGION_SIZE))
+        })
+        .collect()
+}
+
+fn find_nexuses(anchors: &[LeyAnchor], lines: &[LeyLine]) -> Vec<Nexus> {
+    let mut nexuses = Vec::new();
+    for (index, anchor) in anchors.iter().enumerate() {
+        let meeting: Vec<usize> = lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| line.from == index || line.to == index)
+            .map(|(line, _)| line)
+            .collect();
+        if meeting.len() >= 3 {
+            nexuses.push(Nexus {
+                x: anchor.x,
+                y: anchor.y,
+                lines: meeting,
+            });
+        }
+    }
+    for (a, first) in lines.iter().enumerate() {
+        for (b, second) in lines.iter().enumerate().skip(a + 1) {
+            let shares_anchor = [first.from, first.to]
+                .iter()
+                .any(|end| *end == second.from || *end == second.to);
+            if shares_anchor {
+                continue;
+            }
+            for p in first.points.windows(2) {
+                for q in second.points.windows(2) {
+                    if let Some((x, y)) = segment_intersection(p[0], p[1], q[0], q[1]) {
+                        nexuses.push(Nexus {
+                            x,
+                            y,
+                            lines: vec![a, b],
+                        });
+                    }
+                }
+            }
+        }
+    }
+    nexuses
+}
+
+fn segment_intersection(
+    a: (f32, f32),
+    b: (f32, f32),
+    c: (f32, f32),
+    d: (f32, f32),
+) -> Option<(f32, f32)> {
+    let r = (b.0 - a.0, b.1 - a.1);
+    let s = (d.0 - c.0, d.1 - c.1);
+    let denominator = r.0 * s.1 - r.1 * s.0;
+    if denominator.abs() < f32::EPSILON {
+        return None;
+    }
+    let t = ((c.0 - a.0) * s.1 - (c.1 - a.1) * s.0) / denominator;
+    let u = ((c.0 - a.0) * r.1 - (c.1 - a.1) * r.0) / denominator;
+    ((0.0..1.0).contains(&t) && (0.0..1.0).contains(&u)).then_some((a.0 + r.0 * t, a.1 + r.1 * t))
+}
+
+/// Gaussian falloff around every line segment, max-blended, with each nexus
+/// splatting a wider bloom on top.
+fn splat_intensity(map: &MapResult, lines: &[LeyLine], nexuses: &[Nexus], width: f32) -> Vec<f32> {
+    let mut intensity = vec![0.0f32; map.heightmap.len()];
+    let segments = lines.iter().flat_map(|line| {
+        line.points
+            .windows(2)
+            .map(|pair| (pair[0], pair[1], width, 1.0))
+    });
+    let blooms = nexuses
+        .iter()
+        .map(|nexus| ((nexus.x, nexus.y), (nexus.x, nexus.y), width * 3.0, 1.0));
+    for (from, to, width, peak) in segments.chain(blooms) {
+        splat_segment(map, &mut intensity, from, to, width, peak);
+    }
+    intensity
+}
+
+fn splat_segment(
+    map: &MapResult,
+    field: &mut [f32],
+    from: (f32, f32),
+    to: (f32, f32),
+    width: f32,
+    peak: f32,
+) {
+    let reach = width * 3.0;
+    let columns = map.width as usize;
+    let cell_w = REGION_SIZE / map.width as f32;
+    let cell_h = REGION_SIZE / map.height as f32;
+    let x0 = ((from.0.min(to.0) - reach) / cell_w).floor().max(0.0) as usize;
+    let y0 = ((from.1.min(to.1) - reach) / cell_h).floor().max(0.0) as usize;
+    let x1 = (((from.0.max(to.0) + reach) / cell_w).ceil() as usize).min(columns - 1);
+    let y1 = (((from.1.max(to.1) + reach) / cell_h).ceil() as usize).min(map.height as usize - 1);
+    for y in y0..=y1 {
+        for x in x0..=x1 {
+            let point = (x as f32 * cell_w, y as f32 * cell_h);
+            let distance = segment_distance(point, from, to) / width;
+            let value = peak * (-distance * distance).exp();
+            let cell = &mut field[y * columns + x];
+            *cell = cell.max(value);
+        }
+    }
+}
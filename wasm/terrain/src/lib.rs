@@ -1,7 +1,114 @@
+// Most of the crate is reached through the JS bindings; without them only
+// the `native` API is public, so much of it goes unused.
+#![cfg_attr(not(feature = "wasm"), allow(dead_code, unused_imports))]
+
+use std::cell::OnceCell;
+
+#[cfg(feature = "wasm")]
 use js_sys::{Array, Float32Array, Object, Uint8Array};
 use noise::{NoiseFn, OpenSimplex};
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
+use biome::Biome;
+use geometry::distance;
+
+mod adjacency;
+mod agriculture;
+mod ambience;
+mod ascii;
+mod atlas;
+mod biome;
+mod borders;
+mod carving;
+mod caves;
+mod changes;
+mod claims;
+mod coastal;
+mod coastal_roads;
+mod compare;
+mod contour;
+mod cover;
+mod cultures;
+mod currents;
+mod danger;
+mod detail;
+mod determinism;
+mod distance;
+mod drainage;
+mod economy;
+mod editing;
+mod exploration;
+mod faults;
+mod features;
+mod ferries;
+mod flood;
+mod footprints;
+mod geometry;
+mod groundwater;
+mod hand;
+mod harbor;
+mod hexgrid;
+mod history;
+mod hooks;
+mod hydrology;
+mod ice;
+mod incision;
+#[cfg(feature = "wasm")]
+mod js;
+mod json;
+mod labels;
+mod ley;
+mod lookouts;
+mod mask;
+mod metadata;
+mod movement;
+mod naming;
+pub mod native;
+mod navigation;
+mod noise_cache;
+mod overview;
+mod pathfinding;
+mod png;
+mod poi;
+mod population;
+mod presets;
+mod pyramid;
+mod query;
+mod regime;
+mod region_seed;
+mod registry;
+mod render;
+mod requirements;
+mod road_condition;
+mod roughness;
+mod ruins;
+mod sampling;
+mod scenario;
+mod self_test;
+mod settlement_index;
+mod skeleton;
+mod spawns;
+mod splatmap;
+mod stats;
+mod stitch;
+mod stream_order;
+mod style;
+mod suggest;
+mod table;
+mod thermal;
+mod thumbnails;
+mod tiled;
+mod trade;
+mod transform;
+mod tunnels;
+mod variation;
+mod visibility;
+mod waypoints;
+mod weather;
+mod wetness;
+mod wind;
+
 const REGION_SIZE: f32 = 2048.0;
 const DIRECTIONS: [(i32, i32); 8] = [
     (-1, -1),
@@ -20,9 +127,15 @@ struct Settlement {
     x: f32,
     y: f32,
     size: f32,
+    /// Set when terrain edits leave the settlement on unsuitable ground.
+    issue: Option<editing::SettlementIssue>,
+    /// Era the settlement was founded in; the current era is `eras - 1`.
+    era: u32,
+    /// Name given by the caller, overriding the culture's.
+    name: Option<String>,
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub struct MapResult {
     width: u32,
     height: u32,
@@ -34,8 +147,61 @@ pub struct MapResult {
     water: Vec<f32>,
     road_graph: Vec<(u32, u32)>,
     settlements: Vec<Settlement>,
+    sea_level: f32,
+    /// Unscaled noise moisture, kept so edits can re-derive `moisture`
+    /// without regenerating noise.
+    base_moisture: Vec<f32>,
+    settings: GenerationSettings,
+    /// Cells touched by brushes since the last `recompute`.
+    dirty: Option<editing::CellRect>,
+    exploration: exploration::Exploration,
+    pois: Vec<poi::Poi>,
+    history: history::History,
+    /// Freezing rules set by `set_ice`; the mask is derived on demand.
+    ice: Option<ice::IceOptions>,
+    /// Fault lines traced when generated with `faults`.
+    faults: Vec<faults::Fault>,
+    /// Channels set by `set_ambience`.
+    ambience: ambience::AmbienceOptions,
+    /// Layer rectangles changed since the last `take_dirty_regions`.
+    changes: changes::ChangeLog,
+    /// Layers reduced by `enable_pyramid`; the levels are built on demand.
+    pyramid: Option<pyramid::PyramidOptions>,
+    /// Claims layer, materialized from the territories on the first claim.
+    ownership: Option<claims::Ownership>,
+    /// Where each of `settings.forced_settlements` was placed.
+    placements: Vec<scenario::Snap>,
+    cache: MapCache,
+}
+
+/// Derived data built lazily on first query. Mutations that touch the inputs
+/// of an entry must reset it.
+#[derive(Default)]
+struct MapCache {
+    settlement_index: OnceCell<settlement_index::SettlementIndex>,
+    path_costs: [OnceCell<movement::CostField>; movement::PRESETS.len()],
+    coast_distance: OnceCell<Vec<f32>>,
+    river_distance: OnceCell<Vec<f32>>,
+    civilization_distance: OnceCell<Vec<f32>>,
+    settlement_distance: OnceCell<Vec<f32>>,
+    port_distance: OnceCell<Vec<f32>>,
+    road_distance: OnceCell<Vec<f32>>,
+    groundwater: OnceCell<Vec<f32>>,
+    detail_roughness: OnceCell<Vec<f32>>,
+    ambience: OnceCell<Vec<Vec<f32>>>,
+    fault_heat: OnceCell<Vec<f32>>,
+    economy: OnceCell<Vec<economy::Economy>>,
+    footprints: OnceCell<footprints::Footprints>,
+    harbors: OnceCell<Vec<Option<harbor::Harbor>>>,
+    trade: OnceCell<trade::TradeNetwork>,
+    shore_routes: OnceCell<Vec<Option<Vec<usize>>>>,
+    wind: OnceCell<wind::WindField>,
+    currents: OnceCell<currents::CurrentField>,
+    pyramids: [OnceCell<pyramid::Pyramid>; 2],
+    receivers: OnceCell<Vec<Option<usize>>>,
 }
 
+#[cfg(feature = "wasm")]
 #[wasm_bindgen]
 impl MapResult {
     #[wasm_bindgen(getter)]
@@ -84,9 +250,25 @@ impl MapResult {
         array
     }
 
+    /// Settlements as `{ id, x, y, size, issue, era, name, economy, industry,
+    /// port, trade_balance, agriculture }`, where `name` is the caller's for
+    /// forced settlements and `null` otherwise, `economy` holds the `farming`,
+    /// `fishing`, `mining`, `timber`, and `trade` shares of the settlement's
+    /// territory, `industry` names the largest share, or is `null` when the
+    /// territory yields nothing, and `trade_balance` is exports minus imports
+    /// in the `trade_routes()` simulation. `agriculture` is the
+    /// `agriculture_at` calendar for the averaged temperature, moisture, and
+    /// fertility of the settlement's farmland: flat, dry territory outside
+    /// built-up footprints. It is `"flood-prone"` when over a quarter of that
+    /// farmland is. Settlements without farmland get their own cell's
+    /// climate and no fertility.
     pub fn settlements(&self) -> Array {
         let array = Array::new();
-        for settlement in &self.settlements {
+        let trade = self.trade_network();
+        let agriculture = agriculture::settlement_agriculture(self);
+        for (position, (settlement, economy)) in
+            self.settlements.iter().zip(self.economies()).enumerate()
+        {
             let obj = Object::new();
             js_sys::Reflect::set(&obj, &JsValue::from("id"), &JsValue::from(settlement.id)).ok();
             js_sys::Reflect::set(&obj, &JsValue::from("x"), &JsValue::from(settlement.x)).ok();
@@ -97,13 +279,188 @@ impl MapResult {
                 &JsValue::from(settlement.size),
             )
             .ok();
+            let issue = settlement
+                .issue
+                .map_or(JsValue::NULL, |issue| JsValue::from(issue.key()));
+            js_sys::Reflect::set(&obj, &JsValue::from("issue"), &issue).ok();
+            js_sys::Reflect::set(&obj, &JsValue::from("era"), &JsValue::from(settlement.era)).ok();
+            let name = settlement
+                .name
+                .as_deref()
+                .map_or(JsValue::NULL, JsValue::from);
+            js_sys::Reflect::set(&obj, &JsValue::from("name"), &name).ok();
+            js_sys::Reflect::set(&obj, &JsValue::from("economy"), &economy.to_json().to_js()).ok();
+            let industry = economy.dominant().map_or(JsValue::NULL, JsValue::from);
+            js_sys::Reflect::set(&obj, &JsValue::from("industry"), &industry).ok();
+            let port = JsValue::from(trade.ports[position]);
+            js_sys::Reflect::set(&obj, &JsValue::from("port"), &port).ok();
+            let balance = JsValue::from(trade.balance(position));
+            js_sys::Reflect::set(&obj, &JsValue::from("trade_balance"), &balance).ok();
+            let calendar = agriculture[position].to_json().to_js();
+            js_sys::Reflect::set(&obj, &JsValue::from("agriculture"), &calendar).ok();
             array.push(&obj.into());
         }
         array
     }
+
+    /// Adds a settlement at world coordinates and returns its id. Roads are
+    /// left unchanged.
+    pub fn add_settlement(&mut self, world_x: f32, world_y: f32, size: f32) -> u32 {
+        let id = self
+            .settlements
+            .iter()
+            .map(|settlement| settlement.id + 1)
+            .max()
+            .unwrap_or(0);
+        self.settlements.push(Settlement {
+            id,
+            x: world_x.clamp(0.0, REGION_SIZE),
+            y: world_y.clamp(0.0, REGION_SIZE),
+            size,
+            issue: None,
+            era: self.history.current_era(),
+            name: None,
+        });
+        self.invalidate_settlements();
+        id
+    }
+
+    /// Removes the settlement with `id` along with the roads touching it.
+    /// Returns whether a settlement was removed.
+    pub fn remove_settlement(&mut self, id: u32) -> bool {
+        let before = self.settlements.len();
+        self.settlements.retain(|settlement| settlement.id != id);
+        if self.settlements.len() == before {
+            return false;
+        }
+        self.road_graph.retain(|(a, b)| *a != id && *b != id);
+        self.history
+            .road_eras
+            .retain(|(a, b), _| *a != id && *b != id);
+        self.invalidate_settlements();
+        true
+    }
 }
 
-#[wasm_bindgen]
+impl MapResult {
+    /// Looks a settlement up by id. Ids match positions until settlements are
+    /// removed, so the direct index is tried first.
+    fn settlement(&self, id: u32) -> Option<&Settlement> {
+        match self.settlements.get(id as usize) {
+            Some(settlement) if settlement.id == id => Some(settlement),
+            _ => self
+                .settlements
+                .iter()
+                .find(|settlement| settlement.id == id),
+        }
+    }
+
+    fn invalidate_settlements(&mut self) {
+        self.cache.settlement_index.take();
+        self.invalidate_roads();
+    }
+
+    /// Footprints hug roads, economies farm around footprints, and trade
+    /// runs on both, so all three go with the roads.
+    fn invalidate_roads(&mut self) {
+        for costs in &mut self.cache.path_costs {
+            costs.take();
+        }
+        self.cache.civilization_distance.take();
+        self.cache.settlement_distance.take();
+        self.cache.harbors.take();
+        self.cache.port_distance.take();
+        self.cache.road_distance.take();
+        self.cache.footprints.take();
+        self.cache.economy.take();
+        self.cache.trade.take();
+        self.cache.shore_routes.take();
+    }
+
+    /// Drops everything derived from the height, water, or biome layers.
+    fn invalidate_terrain(&mut self) {
+        self.invalidate_roads();
+        self.cache.coast_distance.take();
+        self.cache.river_distance.take();
+        self.cache.groundwater.take();
+        self.cache.detail_roughness.take();
+        self.cache.ambience.take();
+        self.cache.fault_heat.take();
+        self.cache.wind.take();
+        self.cache.currents.take();
+        self.cache.pyramids = Default::default();
+        self.cache.receivers.take();
+    }
+
+    /// Converts world coordinates to fractional cell coordinates. Cell `(x, y)`
+    /// sits at world `(x / width, y / height) * REGION_SIZE`, matching settlements.
+    fn world_to_cell(&self, world_x: f32, world_y: f32) -> (f32, f32) {
+        (
+            world_x / REGION_SIZE * self.width as f32,
+            world_y / REGION_SIZE * self.height as f32,
+        )
+    }
+
+    /// Float layers addressable by name from JS, keyed like their getters.
+    fn float_layer(&self, name: &str) -> Option<&[f32]> {
+        match name {
+            "heightmap" => Some(&self.heightmap),
+            "flow" => Some(&self.flow),
+            "moisture" => Some(&self.moisture),
+            "temperature" => Some(&self.temperature),
+            "water" => Some(&self.water),
+            _ => None,
+        }
+    }
+
+    /// Ocean and lake cells; rivers are tracked separately by `is_river`.
+    fn is_water_body(&self, index: usize) -> bool {
+        matches!(
+            Biome::from_code(self.biome[index]),
+            Some(Biome::Ocean | Biome::Lake)
+        )
+    }
+
+    fn is_river(&self, index: usize) -> bool {
+        !self.is_water_body(index) && self.water[index] > 0.0
+    }
+
+    /// Rasterizes every road edge along its cells; see `road_line`.
+    fn road_mask(&self) -> Vec<bool> {
+        let width = self.width as usize;
+        let mut mask = vec![false; self.heightmap.len()];
+        for position in 0..self.road_graph.len() {
+            for (x, y) in self.road_line(position).unwrap_or_default() {
+                mask[y * width + x] = true;
+            }
+        }
+        mask
+    }
+
+    fn cell_to_world(&self, index: usize) -> (f32, f32) {
+        let width = self.width as usize;
+        (
+            (index % width) as f32 / self.width as f32 * REGION_SIZE,
+            (index / width) as f32 / self.height as f32 * REGION_SIZE,
+        )
+    }
+
+    /// World-space area covered by a single cell.
+    fn cell_area(&self) -> f32 {
+        (REGION_SIZE / self.width as f32) * (REGION_SIZE / self.height as f32)
+    }
+
+    fn nearest_cell(&self, world_x: f32, world_y: f32) -> (usize, usize) {
+        let (cx, cy) = self.world_to_cell(world_x, world_y);
+        (
+            cx.round().clamp(0.0, (self.width - 1) as f32) as usize,
+            cy.round().clamp(0.0, (self.height - 1) as f32) as usize,
+        )
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[allow(clippy::too_many_arguments)]
 pub fn generate_map(
     width: u32,
     height: u32,
@@ -114,81 +471,558 @@ pub fn generate_map(
     erosion_iterations: u32,
     moisture_scale: f32,
 ) -> MapResult {
-    let size = (width * height) as usize;
-    let mut heightmap = vec![0.0f32; size];
-    let mut moisture = vec![0.0f32; size];
-    let mut temperature = vec![0.0f32; size];
+    let settings = GenerationSettings::new(
+        seed,
+        sea_level,
+        elevation_amplitude,
+        warp_strength,
+        erosion_iterations,
+        moisture_scale,
+    );
+    generate(width, height, &settings, None)
+}
 
-    let base_noise = OpenSimplex::new(seed);
-    let warp_noise = OpenSimplex::new(seed.wrapping_add(13));
-    let moisture_noise = OpenSimplex::new(seed.wrapping_add(97));
+/// `generate_map` with extra options. `options.mask` is a `width * height`
+/// `Uint8Array` sketch of the continent: 0 forces ocean, 255 forces land, and
+/// values between bias the shape. Set `options.resample_mask` with
+/// `mask_width` and `mask_height` to supply a mask of another size.
+/// `options.warp_mode` is `"independent"` (default), `"recursive"` for a
+/// second warp layer, or `"legacy"` to reproduce maps from before the axes
+/// were warped separately. `options.water_moisture_bonus` (default 0.45) and
+/// `options.flow_moisture_bonus` (default 0.55) set how much moisture standing
+/// water and river flow add on top of the scaled noise.
+/// `options.elevation_mode` is `"compress"` (default) or `"legacy"` for the
+/// old hard clamp that flattens peaks at high amplitudes.
+/// `options.talus_angle` (default 4 degrees) is the steepest slope thermal
+/// erosion leaves, measured with `vertical_scale` (default 160) world units
+/// per unit of height, so maps erode alike at any resolution;
+/// `thermal_transfer` (default 0.5) is the share of a slope's excess moved
+/// per iteration. `hardness` (default 0) up to 1 makes noise-driven patches,
+/// `hardness_scale` (default 320) world units across, resist erosion and
+/// stand as crags; see `hardness()`. `erosion_mode: "legacy"` restores the
+/// old fixed per-cell threshold.
+/// `options.incision_iterations` (default 0) alternates flow routing with
+/// stream-power incision, cutting `incision_k * area^incision_m *
+/// gradient^incision_n` (defaults 0.2, 0.5, 1) from each channel per round,
+/// so high-flow rivers crossing steep ground sink into gorges.
+/// `options.beach_band` (default 0.02), `shore_radius` (default 2 cells), and
+/// `beach_max_slope` (default 0.12) shape the beach and rocky-shore biomes.
+/// `options.eras` (default 1) runs settlement placement and road building
+/// once per era; `abandon_fraction` (default 0.5) of each earlier era's
+/// settlements become ruins, and `ruin_exclusion` (default 48 world units)
+/// keeps later settlements off them.
+/// `options.forced_settlements` lists `{ x, y, size, name }` settlements
+/// (size default 3) placed in the current era before the procedural ones,
+/// each moved to the nearest valid site within `snap_radius` (default 128
+/// world units); see `forced_placements()` for how far each moved.
+/// Procedural settlements keep their usual spacing from them and stay out
+/// of `options.exclusion_zones`, given as `{ x, y, radius }` circles or `{
+/// points }` polygons of interleaved world coordinates. Roads join forced
+/// and procedural settlements alike. When a forced settlement finds no site
+/// in reach, generation fails with `{ message, failed }`, listing each
+/// failed entry as `{ index, x, y }`.
+/// `options.road_paved_size` (default 3), `road_era_decay` (default 1),
+/// `road_remote_distance` (default 192 world units), and `road_decay`
+/// (default false) grade roads paved, dirt, or overgrown; see `roads()`.
+/// `options.coastal_roads` (default false) routes roads between two ports on
+/// the same landmass along the shore: cells within 3 cells of the ocean cost
+/// `coastal_road_discount` (default 0.6) less, and a route is kept only when
+/// it is at most `coastal_road_detour` (default 1.5) times the straight
+/// distance, else the road runs straight as usual.
+/// `options.ocean_currents` (default false) lets gyres warm coasts washed by
+/// poleward currents and cool those washed by equatorward ones.
+/// `options.lake_outflows` (default false) fills inland depressions into
+/// lakes whose gathered inflow spills over the lowest point of their rim as
+/// a river; hot, dry lakes that lose more to evaporation stay closed as salt
+/// lakes.
+/// `options.faults` (default false) traces fault lines, see `faults()`, and
+/// places hot springs, geysers, and fumaroles along them as POIs; with
+/// `fault_scarps` (default false) the faults also step the terrain.
+/// `options.biome_table` replaces the temperature and moisture thresholds of
+/// the land biomes: bands coldest first as `{ max_temperature, zones }`, each
+/// zone `{ min_moisture, biome }` wettest first with `biome` a key from
+/// `biome_definitions()`. `metadata().biome_table` reports the default.
+/// `options.deterministic` quantizes elevation, moisture, temperature, and
+/// water before they drive flow, biomes, and settlements, so native and wasm
+/// builds produce identical maps; check with `fingerprint()`.
+/// `options.hook` is a function called once during generation with `{ stage,
+/// width, height, heightmap, moisture, temperature }` at `hook_stage`:
+/// `"climate"` (default), after water and before biomes, or `"terrain"`,
+/// after erosion and before water is traced. The arrays are copies read back
+/// when the hook returns; write into them or replace them with arrays of the
+/// same length. At `"climate"` the heightmap is read-only and `moisture`
+/// includes the water bonuses; at `"terrain"` it is the raw noise. Changed
+/// values are clamped to 0..1 and NaNs restored, and a throwing hook fails
+/// the generation.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn generate_map_with_options(
+    width: u32,
+    height: u32,
+    seed: u32,
+    sea_level: f32,
+    elevation_amplitude: f32,
+    warp_strength: f32,
+    erosion_iterations: u32,
+    moisture_scale: f32,
+    options: JsValue,
+) -> Result<MapResult, JsValue> {
+    let defaults = GenerationSettings::new(
+        seed,
+        sea_level,
+        elevation_amplitude,
+        warp_strength,
+        erosion_iterations,
+        moisture_scale,
+    );
+    let settings = GenerationSettings::with_options(defaults, &options)?;
+    let mask = mask::LandMask::from_options(&options, width, height)?;
+    let Some((stage, hook)) = hooks::from_options(&options)? else {
+        return scenario::check_placements(generate(width, height, &settings, mask.as_ref()));
+    };
+    let mut failure = None;
+    let map = generate_with_hook(
+        width,
+        height,
+        &settings,
+        mask.as_ref(),
+        Some((stage, &mut |layers| {
+            if let Err(error) = hooks::call_js(&hook, layers) {
+                failure = Some(error);
+            }
+        })),
+    );
+    match failure {
+        Some(error) => Err(error),
+        None => scenario::check_placements(map),
+    }
+}
 
-    let width_f = width as f32;
-    let height_f = height as f32;
+impl GenerationSettings {
+    /// Applies the optional fields of `generate_map_with_options` on top of
+    /// `defaults`.
+    #[cfg(feature = "wasm")]
+    fn with_options(defaults: Self, options: &JsValue) -> Result<Self, JsValue> {
+        let warp_mode = match js::get_string(options, "warp_mode").as_deref() {
+            None | Some("independent") => WarpMode::Independent,
+            Some("recursive") => WarpMode::Recursive,
+            Some("legacy") => WarpMode::Legacy,
+            Some(other) => return Err(JsValue::from_str(&format!("unknown warp mode: {other}"))),
+        };
+        let elevation_mode = match js::get_string(options, "elevation_mode").as_deref() {
+            None | Some("compress") => ElevationMode::Compress,
+            Some("legacy") => ElevationMode::Legacy,
+            Some(other) => {
+                return Err(JsValue::from_str(&format!(
+                    "unknown elevation mode: {other}"
+                )))
+            }
+        };
+        let erosion_mode = match js::get_string(options, "erosion_mode").as_deref() {
+            None | Some("talus") => thermal::ErosionMode::Talus,
+            Some("legacy") => thermal::ErosionMode::Legacy,
+            Some(other) => {
+                return Err(JsValue::from_str(&format!("unknown erosion mode: {other}")))
+            }
+        };
+        let biome_table = match js::get_array(options, "biome_table") {
+            Some(bands) => {
+                biome::BiomeTable::from_js(&bands).map_err(|message| JsValue::from_str(&message))?
+            }
+            None => defaults.biome_table.clone(),
+        };
+        let (forced_settlements, exclusion_zones) = scenario::from_options(options)?;
+        Ok(GenerationSettings {
+            warp_mode,
+            elevation_mode,
+            erosion_mode,
+            talus_angle: js::get_f32(options, "talus_angle", defaults.talus_angle).clamp(0.0, 89.0),
+            vertical_scale: js::get_f32(options, "vertical_scale", defaults.vertical_scale)
+                .max(1.0),
+            thermal_transfer: js::get_f32(options, "thermal_transfer", defaults.thermal_transfer)
+                .clamp(0.0, 1.0),
+            hardness: js::get_f32(options, "hardness", defaults.hardness).clamp(0.0, 1.0),
+            hardness_scale: js::get_f32(options, "hardness_scale", defaults.hardness_scale),
+            incision_iterations: js::get_u32(
+                options,
+                "incision_iterations",
+                defaults.incision_iterations,
+            ),
+            incision_k: js::get_f32(options, "incision_k", defaults.incision_k).max(0.0),
+            incision_m: js::get_f32(options, "incision_m", defaults.incision_m).max(0.0),
+            incision_n: js::get_f32(options, "incision_n", defaults.incision_n).max(0.0),
+            water_moisture_bonus: js::get_f32(
+                options,
+                "water_moisture_bonus",
+                defaults.water_moisture_bonus,
+            ),
+            flow_moisture_bonus: js::get_f32(
+                options,
+                "flow_moisture_bonus",
+                defaults.flow_moisture_bonus,
+            ),
+            beach_band: js::get_f32(options, "beach_band", defaults.beach_band),
+            shore_radius: js::get_u32(options, "shore_radius", defaults.shore_radius),
+            beach_max_slope: js::get_f32(options, "beach_max_slope", defaults.beach_max_slope),
+            eras: js::get_u32(options, "eras", defaults.eras).clamp(1, history::MAX_ERAS),
+            abandon_fraction: js::get_f32(options, "abandon_fraction", defaults.abandon_fraction)
+                .clamp(0.0, 1.0),
+            ruin_exclusion: js::get_f32(options, "ruin_exclusion", defaults.ruin_exclusion),
+            road_paved_size: js::get_f32(options, "road_paved_size", defaults.road_paved_size),
+            road_era_decay: js::get_u32(options, "road_era_decay", defaults.road_era_decay),
+            road_remote_distance: js::get_f32(
+                options,
+                "road_remote_distance",
+                defaults.road_remote_distance,
+            )
+            .max(0.0),
+            road_decay: js::get_bool(options, "road_decay", defaults.road_decay),
+            coastal_roads: js::get_bool(options, "coastal_roads", defaults.coastal_roads),
+            coastal_road_discount: js::get_f32(
+                options,
+                "coastal_road_discount",
+                defaults.coastal_road_discount,
+            )
+            .clamp(0.0, 0.95),
+            coastal_road_detour: js::get_f32(
+                options,
+                "coastal_road_detour",
+                defaults.coastal_road_detour,
+            )
+            .max(1.0),
+            ocean_currents: js::get_bool(options, "ocean_currents", defaults.ocean_currents),
+            lake_outflows: js::get_bool(options, "lake_outflows", defaults.lake_outflows),
+            faults: js::get_bool(options, "faults", defaults.faults),
+            fault_scarps: js::get_bool(options, "fault_scarps", defaults.fault_scarps),
+            biome_table,
+            deterministic: js::get_bool(options, "deterministic", defaults.deterministic),
+            forced_settlements: forced_settlements
+                .unwrap_or_else(|| defaults.forced_settlements.clone()),
+            exclusion_zones: exclusion_zones.unwrap_or_else(|| defaults.exclusion_zones.clone()),
+            snap_radius: js::get_f32(options, "snap_radius", defaults.snap_radius).max(0.0),
+            ..defaults
+        })
+    }
+}
 
-    for y in 0..height {
-        for x in 0..width {
-            let nx = (x as f32 / width_f) * 2.0 - 1.0;
-            let ny = (y as f32 / height_f) * 2.0 - 1.0;
-            let index = (y * width + x) as usize;
+/// How noise coordinates are displaced before sampling elevation and moisture.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum WarpMode {
+    /// One warp sample added to both axes, shifting terrain along the
+    /// diagonal. Kept so older seeds still reproduce.
+    Legacy,
+    /// Separate warp samples per axis.
+    Independent,
+    /// Independent warp applied a second time to its own output.
+    Recursive,
+}
 
-            let warp = warp_noise.get([nx as f64 * 1.5, ny as f64 * 1.5]) as f32;
-            let warped_x = nx + warp * (warp_strength / 400.0);
-            let warped_y = ny + warp * (warp_strength / 400.0);
-
-            let mut elevation = 0.0f32;
-            let mut frequency = 1.2f32;
-            let mut amplitude = 1.0f32;
-            for _octave in 0..5 {
-                let sample = base_noise.get([
-                    warped_x as f64 * frequency as f64,
-                    warped_y as f64 * frequency as f64,
-                ]) as f32;
-                elevation += sample * amplitude;
-                frequency *= 2.0;
-                amplitude *= 0.5;
+impl WarpMode {
+    fn key(self) -> &'static str {
+        match self {
+            WarpMode::Legacy => "legacy",
+            WarpMode::Independent => "independent",
+            WarpMode::Recursive => "recursive",
+        }
+    }
+
+    /// Offset of noise-space point `(x, y)` for a warp of `strength` units.
+    fn offset(self, noise: &OpenSimplex, x: f32, y: f32, strength: f32) -> (f32, f32) {
+        let sample = |x: f32, y: f32, shift: (f64, f64)| {
+            noise.get([x as f64 * 1.5 + shift.0, y as f64 * 1.5 + shift.1]) as f32
+        };
+        let pair = |x: f32, y: f32| (sample(x, y, (0.0, 0.0)), sample(x, y, (5.2, 1.3)));
+        let scale = strength / 400.0;
+        match self {
+            WarpMode::Legacy => {
+                let warp = sample(x, y, (0.0, 0.0));
+                (warp * scale, warp * scale)
+            }
+            WarpMode::Independent => {
+                let (dx, dy) = pair(x, y);
+                (dx * scale, dy * scale)
             }
+            WarpMode::Recursive => {
+                let (qx, qy) = pair(x, y);
+                let inner_x = x + qx * scale * 2.0;
+                let inner_y = y + qy * scale * 2.0;
+                let dx = sample(inner_x, inner_y, (1.7, 9.2));
+                let dy = sample(inner_x, inner_y, (8.3, 2.8));
+                (dx * scale, dy * scale)
+            }
+        }
+    }
+}
 
-            elevation = elevation / 2.5;
-            let distance = (nx * nx + ny * ny).sqrt();
-            let continentality = (1.0 - distance.powf(1.6)).clamp(0.0, 1.0);
-            let mut value =
-                (elevation * elevation_amplitude + continentality * 0.65) / (1.0 + 0.65);
-            value = value.clamp(-1.0, 1.0);
-            let normalized = ((value + 1.0) * 0.5).powf(1.18);
-            heightmap[index] = normalized;
+/// How combined noise and continentality are brought into -1..1.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ElevationMode {
+    /// Hard clamp; high amplitudes saturate into flat plateaus.
+    Legacy,
+    /// Identity up to a knee, then a tanh shoulder that approaches but
+    /// never reaches the limits.
+    Compress,
+}
 
-            let moist_sample =
-                moisture_noise.get([warped_x as f64 * 1.8, warped_y as f64 * 1.8]) as f32;
-            moisture[index] = ((moist_sample * 0.5 + 0.5) * moisture_scale).clamp(0.0, 1.0);
+impl ElevationMode {
+    fn key(self) -> &'static str {
+        match self {
+            ElevationMode::Legacy => "legacy",
+            ElevationMode::Compress => "compress",
+        }
+    }
 
-            let latitude = (y as f32 / height_f) - 0.5;
-            let altitude_penalty = ((normalized - sea_level).max(0.0) * 1.5).min(1.0);
-            let base_temperature = (1.0 - latitude.abs() * 1.8).clamp(0.0, 1.0);
-            temperature[index] = (base_temperature - altitude_penalty).clamp(0.0, 1.0);
+    fn limit(self, value: f32) -> f32 {
+        const KNEE: f32 = 0.7;
+        match self {
+            ElevationMode::Legacy => value.clamp(-1.0, 1.0),
+            ElevationMode::Compress => {
+                let magnitude = value.abs();
+                if magnitude <= KNEE {
+                    return value;
+                }
+                let headroom = 1.0 - KNEE;
+                let shoulder = KNEE + headroom * ((magnitude - KNEE) / headroom).tanh();
+                shoulder.copysign(value)
+            }
         }
     }
+}
 
-    apply_thermal_erosion(&mut heightmap, width, height, erosion_iterations);
+/// Inputs a map was generated from, reported by `metadata()`. `sea_level`
+/// here is the generation value; edits move `MapResult::sea_level`. Native
+/// callers start from `Default`.
+#[derive(Clone)]
+pub struct GenerationSettings {
+    pub seed: u32,
+    pub sea_level: f32,
+    pub elevation_amplitude: f32,
+    pub warp_strength: f32,
+    pub warp_mode: WarpMode,
+    pub elevation_mode: ElevationMode,
+    pub erosion_iterations: u32,
+    pub erosion_mode: thermal::ErosionMode,
+    /// Steepest slope, in degrees over world distance, that thermal erosion
+    /// leaves standing.
+    pub talus_angle: f32,
+    /// World units spanned by one normalized height unit.
+    pub vertical_scale: f32,
+    /// Share of a slope's excess over the talus angle moved per iteration.
+    pub thermal_transfer: f32,
+    /// Peak resistance to thermal erosion; 0 erodes everything alike.
+    pub hardness: f32,
+    /// Feature size of the hardness noise in world units.
+    pub hardness_scale: f32,
+    /// Rounds of flow routing and stream-power incision; see `incision`.
+    pub incision_iterations: u32,
+    /// Incision rate: height cut per round at full drainage and unit
+    /// gradient.
+    pub incision_k: f32,
+    /// Exponent on the share of the map draining through a cell.
+    pub incision_m: f32,
+    /// Exponent on the world gradient to the downstream cell.
+    pub incision_n: f32,
+    /// Multiplies noise moisture before bonuses are added; moisture never
+    /// decreases as it grows.
+    pub moisture_scale: f32,
+    /// Moisture added at full standing water.
+    pub water_moisture_bonus: f32,
+    /// Moisture added along the strongest river flow.
+    pub flow_moisture_bonus: f32,
+    /// Height above the sea that coastal land may reach and stay shore.
+    pub beach_band: f32,
+    /// Cells from the ocean within which land counts as coastal.
+    pub shore_radius: u32,
+    /// Steepest normalized slope that still makes a beach.
+    pub beach_max_slope: f32,
+    /// Settlement eras simulated; earlier eras leave ruins and trails.
+    pub eras: u32,
+    /// Share of each earlier era's settlements abandoned before the next.
+    pub abandon_fraction: f32,
+    /// World distance later settlements keep from ruins.
+    pub ruin_exclusion: f32,
+    /// Roads between settlements at least this size start paved; see
+    /// `road_condition`.
+    pub road_paved_size: f32,
+    /// Eras of age that cost a road one condition; 0 ignores age.
+    pub road_era_decay: u32,
+    /// On single-era maps, roads passing farther than this world distance
+    /// from every settlement lose one condition.
+    pub road_remote_distance: f32,
+    /// Roads running mostly outside every territory lose one condition.
+    pub road_decay: bool,
+    /// Route roads between ports on one landmass along the shore; see
+    /// `coastal_roads`.
+    pub coastal_roads: bool,
+    /// Share of the cost taken off cells near the shore for coastal routes.
+    pub coastal_road_discount: f32,
+    /// Longest coastal route accepted, as a multiple of the straight
+    /// distance between its settlements.
+    pub coastal_road_detour: f32,
+    /// Shift coastal temperatures by the ocean currents; see `currents`.
+    pub ocean_currents: bool,
+    /// Fill depressions into lakes that overflow through their lowest rim
+    /// cell; see `hydrology::route_lakes`.
+    pub lake_outflows: bool,
+    /// Trace fault lines and place geothermal POIs along them; see `faults`.
+    pub faults: bool,
+    /// With `faults`, offset the heightmap into small scarps along each line.
+    pub fault_scarps: bool,
+    /// Temperature and moisture thresholds of the land biomes.
+    pub biome_table: biome::BiomeTable,
+    /// Quantize decision-making layers so native and wasm builds agree; see
+    /// `determinism`.
+    pub deterministic: bool,
+    /// Settlements placed before the procedural ones; see `scenario`.
+    pub forced_settlements: Vec<scenario::ForcedSettlement>,
+    /// Areas procedural settlements stay out of.
+    pub exclusion_zones: Vec<scenario::ExclusionZone>,
+    /// World distance a forced settlement may move to reach a valid site.
+    pub snap_radius: f32,
+}
 
-    let (flow, water) = build_flow_map(&heightmap, width, height, sea_level);
-    enhance_moisture(&mut moisture, &water, &flow, moisture_scale);
-    let biome = classify_biomes(
-        &heightmap,
-        &water,
-        &temperature,
-        &moisture,
+impl Default for GenerationSettings {
+    fn default() -> Self {
+        Self::new(0, 0.42, 1.0, 40.0, 2, 1.0)
+    }
+}
+
+impl GenerationSettings {
+    fn new(
+        seed: u32,
+        sea_level: f32,
+        elevation_amplitude: f32,
+        warp_strength: f32,
+        erosion_iterations: u32,
+        moisture_scale: f32,
+    ) -> Self {
+        Self {
+            seed,
+            sea_level,
+            elevation_amplitude,
+            warp_strength,
+            warp_mode: WarpMode::Independent,
+            elevation_mode: ElevationMode::Compress,
+            erosion_iterations,
+            erosion_mode: thermal::ErosionMode::Talus,
+            talus_angle: 4.0,
+            vertical_scale: 160.0,
+            thermal_transfer: 0.5,
+            hardness: 0.0,
+            hardness_scale: 320.0,
+            incision_iterations: 0,
+            incision_k: 0.2,
+            incision_m: 0.5,
+            incision_n: 1.0,
+            moisture_scale,
+            water_moisture_bonus: 0.45,
+            flow_moisture_bonus: 0.55,
+            beach_band: 0.02,
+            shore_radius: 2,
+            beach_max_slope: 0.12,
+            eras: 1,
+            abandon_fraction: 0.5,
+            ruin_exclusion: 48.0,
+            road_paved_size: 3.0,
+            road_era_decay: 1,
+            road_remote_distance: 192.0,
+            road_decay: false,
+            coastal_roads: false,
+            coastal_road_discount: 0.6,
+            coastal_road_detour: 1.5,
+            ocean_currents: false,
+            lake_outflows: false,
+            faults: false,
+            fault_scarps: false,
+            biome_table: biome::BiomeTable::default(),
+            deterministic: false,
+            forced_settlements: Vec::new(),
+            exclusion_zones: Vec::new(),
+            snap_radius: 128.0,
+        }
+    }
+}
+
+fn generate(
+    width: u32,
+    height: u32,
+    settings: &GenerationSettings,
+    mask: Option<&mask::LandMask>,
+) -> MapResult {
+    generate_with_hook(width, height, settings, mask, None)
+}
+
+/// `generate` with a hook run over the layers at one stage; see `hooks`.
+fn generate_with_hook(
+    width: u32,
+    height: u32,
+    settings: &GenerationSettings,
+    mask: Option<&mask::LandMask>,
+    mut hook: Option<hooks::Hook>,
+) -> MapResult {
+    let mut terrain = native::terrain(width, height, settings, mask);
+    if let Some((hooks::Stage::Terrain, hook)) = &mut hook {
+        hooks::run(
+            *hook,
+            &mut hooks::StageLayers {
+                stage: hooks::Stage::Terrain,
+                width,
+                height,
+                heightmap: &mut terrain.heightmap,
+                moisture: &mut terrain.moisture,
+                temperature: &mut terrain.temperature,
+            },
+        );
+        if settings.deterministic {
+            determinism::quantize(&mut terrain.heightmap);
+            determinism::quantize(&mut terrain.moisture);
+            determinism::quantize(&mut terrain.temperature);
+        }
+    }
+    let mut hydrology = native::trace_water(&terrain, settings);
+    if let Some((hooks::Stage::Climate, hook)) = &mut hook {
+        hooks::run(
+            *hook,
+            &mut hooks::StageLayers {
+                stage: hooks::Stage::Climate,
+                width,
+                height,
+                heightmap: &mut terrain.heightmap.clone(),
+                moisture: &mut hydrology.moisture,
+                temperature: &mut terrain.temperature,
+            },
+        );
+    }
+    let biome = native::classify_biomes(&terrain, &hydrology, settings);
+    let history::Eras {
+        settlements,
+        road_graph,
+        pois,
+        history,
+        placements,
+    } = history::simulate_eras(
+        &terrain.heightmap,
+        &hydrology.water,
+        &hydrology.moisture,
         width,
         height,
-        sea_level,
+        settings,
     );
+    let native::Terrain {
+        heightmap,
+        faults,
+        moisture: base_moisture,
+        temperature,
+        ..
+    } = terrain;
+    let native::Hydrology {
+        flow,
+        water,
+        moisture,
+    } = hydrology;
 
-    let settlements = place_settlements(
-        &heightmap, &water, &moisture, width, height, sea_level, seed,
-    );
-    let road_graph = build_roads(&settlements);
-
-    MapResult {
+    let mut map = MapResult {
         width,
         height,
         heightmap,
@@ -199,218 +1033,438 @@ pub fn generate_map(
         water,
         road_graph,
         settlements,
+        sea_level: settings.sea_level,
+        base_moisture,
+        settings: settings.clone(),
+        dirty: None,
+        exploration: exploration::Exploration::new((width * height) as usize),
+        pois,
+        history,
+        ice: None,
+        faults,
+        ambience: ambience::AmbienceOptions::default(),
+        changes: changes::ChangeLog::full(width, height),
+        pyramid: None,
+        ownership: None,
+        placements,
+        cache: MapCache::default(),
+    };
+    map.resolve_features();
+    if !map.faults.is_empty() {
+        map.add_geothermal();
     }
+    map
 }
 
-fn apply_thermal_erosion(heightmap: &mut [f32], width: u32, height: u32, iterations: u32) {
-    let width_i = width as usize;
-    let height_i = height as usize;
-    for _ in 0..iterations {
-        let mut scratch = heightmap.to_vec();
-        for y in 1..(height_i - 1) {
-            for x in 1..(width_i - 1) {
-                let index = y * width_i + x;
-                let center = heightmap[index];
-                let mut total = center;
-                let mut count = 1.0f32;
-                for (dx, dy) in DIRECTIONS {
-                    let nx = (x as i32 + dx) as usize;
-                    let ny = (y as i32 + dy) as usize;
-                    let n_index = ny * width_i + nx;
-                    let neighbor = heightmap[n_index];
-                    if (center - neighbor) > 0.03 {
-                        total += neighbor + (center - neighbor) * 0.5;
-                        count += 1.0;
-                    }
-                }
-                scratch[index] = total / count;
-            }
+/// Fills the noise-driven elevation, raw moisture, and temperature fields,
+/// before erosion or any water. Buffers are overwritten, so callers may
+/// reuse them across seeds.
+fn sample_fields(
+    width: u32,
+    height: u32,
+    settings: &GenerationSettings,
+    mask: Option<&mask::LandMask>,
+    heightmap: &mut [f32],
+    moisture: &mut [f32],
+    temperature: &mut [f32],
+) {
+    let sampler = FieldSampler::new(settings);
+    let raw = noise_cache::raw_noise(width, height, settings, &sampler);
+    let width_f = width as f32;
+    let height_f = height as f32;
+
+    for y in 0..height {
+        for x in 0..width {
+            let nx = (x as f32 / width_f) * 2.0 - 1.0;
+            let ny = (y as f32 / height_f) * 2.0 - 1.0;
+            let index = (y * width + x) as usize;
+
+            let continentality = match mask {
+                Some(mask) => mask.continentality(index),
+                None => FieldSampler::continentality(nx, ny),
+            };
+            let normalized = sampler.shape(raw.elevation[index], continentality);
+            heightmap[index] = normalized;
+            moisture[index] = raw.moisture[index];
+            temperature[index] =
+                cell_temperature(y as f32 / height_f, normalized, settings.sea_level);
         }
-        heightmap.copy_from_slice(&scratch);
     }
 }
 
+/// Per-point noise sampling behind `sample_fields`, in noise space where the
+/// map spans `-1..1` on both axes.
+struct FieldSampler {
+    base: OpenSimplex,
+    warp: OpenSimplex,
+    moisture: OpenSimplex,
+    elevation_amplitude: f32,
+    warp_strength: f32,
+    warp_mode: WarpMode,
+    elevation_mode: ElevationMode,
+}
+
+impl FieldSampler {
+    fn new(settings: &GenerationSettings) -> Self {
+        Self {
+            base: OpenSimplex::new(settings.seed),
+            warp: OpenSimplex::new(settings.seed.wrapping_add(13)),
+            moisture: OpenSimplex::new(settings.seed.wrapping_add(97)),
+            elevation_amplitude: settings.elevation_amplitude,
+            warp_strength: settings.warp_strength,
+            warp_mode: settings.warp_mode,
+            elevation_mode: settings.elevation_mode,
+        }
+    }
+
+    fn warped(&self, nx: f32, ny: f32) -> (f32, f32) {
+        let (warp_x, warp_y) = self
+            .warp_mode
+            .offset(&self.warp, nx, ny, self.warp_strength);
+        (nx + warp_x, ny + warp_y)
+    }
+
+    /// Radial falloff used when no land mask shapes the continent.
+    fn continentality(nx: f32, ny: f32) -> f32 {
+        let distance = (nx * nx + ny * ny).sqrt();
+        (1.0 - distance.powf(1.6)).clamp(0.0, 1.0)
+    }
+
+    fn elevation(&self, warped_x: f32, warped_y: f32, continentality: f32) -> f32 {
+        self.shape(self.octaves(warped_x, warped_y), continentality)
+    }
+
+    /// Summed elevation octaves before amplitude and continentality; depends
+    /// only on the seed and the warped position, so `noise_cache` keeps it.
+    fn octaves(&self, warped_x: f32, warped_y: f32) -> f32 {
+        let mut elevation = 0.0f32;
+        let mut frequency = 1.2f32;
+        let mut amplitude = 1.0f32;
+        for _octave in 0..5 {
+            let sample = self.base.get([
+                warped_x as f64 * frequency as f64,
+                warped_y as f64 * frequency as f64,
+            ]) as f32;
+            elevation += sample * amplitude;
+            frequency *= 2.0;
+            amplitude *= 0.5;
+        }
+
+        elevation / 2.5
+    }
+
+    fn shape(&self, octaves: f32, continentality: f32) -> f32 {
+        let value = self
+            .elevation_mode
+            .limit((octaves * self.elevation_amplitude + continentality * 0.65) / (1.0 + 0.65));
+        ((value + 1.0) * 0.5).powf(1.18)
+    }
+
+    fn moisture(&self, warped_x: f32, warped_y: f32) -> f32 {
+        let sample = self
+            .moisture
+            .get([warped_x as f64 * 1.8, warped_y as f64 * 1.8]) as f32;
+        (sample * 0.5 + 0.5).clamp(0.0, 1.0)
+    }
+}
+
+/// Latitude falls off from the equator at `y_fraction == 0.5`; land loses
+/// warmth with altitude above the sea.
+fn cell_temperature(y_fraction: f32, elevation: f32, sea_level: f32) -> f32 {
+    let latitude = y_fraction - 0.5;
+    let altitude_penalty = ((elevation - sea_level).max(0.0) * 1.5).min(1.0);
+    let base_temperature = (1.0 - latitude.abs() * 1.8).clamp(0.0, 1.0);
+    (base_temperature - altitude_penalty).clamp(0.0, 1.0)
+}
+
 fn build_flow_map(
     heightmap: &[f32],
     width: u32,
     height: u32,
     sea_level: f32,
 ) -> (Vec<f32>, Vec<f32>) {
-    let width_i = width as usize;
-    let height_i = height as usize;
-    let size = width_i * height_i;
-    let mut downslope = vec![None; size];
-
-    for y in 0..height_i {
-        for x in 0..width_i {
-            let index = y * width_i + x;
-            let current = heightmap[index];
-            let mut lowest = current;
-            let mut lowest_index: Option<usize> = None;
-
-            for (dx, dy) in DIRECTIONS {
-                let nx = x as i32 + dx;
-                let ny = y as i32 + dy;
-                if nx < 0 || ny < 0 || nx >= width as i32 || ny >= height as i32 {
-                    continue;
-                }
-                let n_index = ny as usize * width_i + nx as usize;
-                let neighbor = heightmap[n_index];
-                if neighbor < lowest {
-                    lowest = neighbor;
-                    lowest_index = Some(n_index);
-                }
-            }
+    let downslope = downslope_map(heightmap, width, height);
+    let flow = accumulate_flow(heightmap, &downslope);
 
-            downslope[index] = lowest_index;
-        }
+    let max_flow = flow.iter().fold(0.0f32, |acc, &v| acc.max(v));
+    let water = heightmap
+        .iter()
+        .zip(&flow)
+        .map(|(&elevation, &flow)| cell_water(elevation, flow, max_flow, sea_level))
+        .collect();
+
+    (flow, water)
+}
+
+/// Standing water below sea level, otherwise runoff once accumulated flow is
+/// large enough to form a river.
+fn cell_water(elevation: f32, flow: f32, max_flow: f32, sea_level: f32) -> f32 {
+    if elevation <= sea_level {
+        return 1.0;
     }
+    let runoff = (flow / (max_flow + 1.0)).powf(0.4);
+    if runoff > 0.3 {
+        runoff
+    } else {
+        0.0
+    }
+}
 
-    let mut order: Vec<usize> = (0..size).collect();
-    order.sort_by(|a, b| heightmap[*b].partial_cmp(&heightmap[*a]).unwrap());
+/// Cells draining through each cell, itself included, passing flow from the
+/// highest cell down along `downslope`.
+fn accumulate_flow(heightmap: &[f32], downslope: &[Option<usize>]) -> Vec<f32> {
+    let mut order: Vec<usize> = (0..heightmap.len()).collect();
+    order.sort_by(|a, b| heightmap[*b].total_cmp(&heightmap[*a]).then(a.cmp(b)));
 
-    let mut flow = vec![1.0f32; size];
+    let mut flow = vec![1.0f32; heightmap.len()];
     for &cell in &order {
         if let Some(target) = downslope[cell] {
             flow[target] += flow[cell];
         }
     }
+    flow
+}
 
-    let max_flow = flow.iter().fold(0.0f32, |acc, &v| acc.max(v));
-    let mut water = vec![0.0f32; size];
+/// Steepest-descent neighbor of every cell, or `None` for pits and minima.
+fn downslope_map(heightmap: &[f32], width: u32, height: u32) -> Vec<Option<usize>> {
+    (0..heightmap.len())
+        .map(|index| downslope(heightmap, width, height, index))
+        .collect()
+}
 
-    for (index, value) in heightmap.iter().enumerate() {
-        if *value <= sea_level {
-            water[index] = 1.0;
-        } else {
-            let runoff = (flow[index] / (max_flow + 1.0)).powf(0.4);
-            if runoff > 0.3 {
-                water[index] = runoff;
-            }
+fn downslope(heightmap: &[f32], width: u32, height: u32, index: usize) -> Option<usize> {
+    let width_i = width as usize;
+    let x = (index % width_i) as i32;
+    let y = (index / width_i) as i32;
+    let mut lowest = heightmap[index];
+    let mut lowest_index: Option<usize> = None;
+
+    for (dx, dy) in DIRECTIONS {
+        let nx = x + dx;
+        let ny = y + dy;
+        if nx < 0 || ny < 0 || nx >= width as i32 || ny >= height as i32 {
+            continue;
+        }
+        let n_index = ny as usize * width_i + nx as usize;
+        let neighbor = heightmap[n_index];
+        if neighbor < lowest {
+            lowest = neighbor;
+            lowest_index = Some(n_index);
         }
     }
 
-    (flow, water)
+    lowest_index
 }
 
-fn enhance_moisture(moisture: &mut [f32], water: &[f32], flow: &[f32], moisture_scale: f32) {
+fn enhance_moisture(
+    moisture: &mut [f32],
+    water: &[f32],
+    flow: &[f32],
+    settings: &GenerationSettings,
+) {
     let max_flow = flow.iter().fold(0.0f32, |acc, &v| acc.max(v));
     for (index, value) in moisture.iter_mut().enumerate() {
-        let water_bonus = (water[index] * 0.7).min(0.7);
-        let flow_bonus = ((flow[index] / (max_flow + 1.0)) * 1.8).min(0.8);
-        *value = (*value + water_bonus + flow_bonus) / (1.0 + moisture_scale * 0.5);
-        *value = value.clamp(0.0, 1.0);
+        *value = enhanced_moisture(*value, water[index], flow[index], max_flow, settings);
     }
 }
 
-fn classify_biomes(
+/// Scales noise moisture once, then adds bonuses near standing water and
+/// along high-flow channels. Flow saturates at 44% of the strongest river.
+fn enhanced_moisture(
+    base: f32,
+    water: f32,
+    flow: f32,
+    max_flow: f32,
+    settings: &GenerationSettings,
+) -> f32 {
+    let water_bonus = water.clamp(0.0, 1.0) * settings.water_moisture_bonus;
+    let flow_bonus = ((flow / (max_flow + 1.0)) * 2.25).min(1.0) * settings.flow_moisture_bonus;
+    (base * BASE_MOISTURE_WEIGHT * settings.moisture_scale + water_bonus + flow_bonus)
+        .clamp(0.0, 1.0)
+}
+
+/// Share of the moisture range noise fills at `moisture_scale == 1`, leaving
+/// headroom for the water and flow bonuses.
+const BASE_MOISTURE_WEIGHT: f32 = 0.65;
+
+fn classify_cell(
+    elevation: f32,
+    water: f32,
+    temp: f32,
+    moist: f32,
+    sea_level: f32,
+    table: &biome::BiomeTable,
+) -> Biome {
+    if elevation <= sea_level - 0.02 {
+        return Biome::Ocean;
+    }
+
+    if water > 0.6 {
+        return Biome::Lake;
+    }
+
+    if elevation > 0.82 {
+        return Biome::Alpine;
+    }
+
+    table.classify(temp, moist)
+}
+
+/// Second classification pass for coasts. Land within `beach_band` above the
+/// sea and `shore_radius` cells of the sea becomes beach, or rocky shore
+/// where steeper than `beach_max_slope`. Precedence is therefore ocean, lake,
+/// shore, then the alpine and climate biomes from `classify_cell`. Coastal
+/// features added later (mangroves, deltas) belong ahead of beaches here.
+fn apply_shores(
     heightmap: &[f32],
-    water: &[f32],
-    temperature: &[f32],
-    moisture: &[f32],
-    width: u32,
-    height: u32,
+    biome: &mut [u8],
+    width: usize,
+    height: usize,
     sea_level: f32,
-) -> Vec<u8> {
-    let mut biomes = vec![0u8; (width * height) as usize];
-    for i in 0..biomes.len() {
-        let elevation = heightmap[i];
-        if elevation <= sea_level - 0.02 {
-            biomes[i] = 0; // ocean
-            continue;
-        }
+    settings: &GenerationSettings,
+    cells: impl IntoIterator<Item = usize>,
+) {
+    apply_shores_by(
+        |index| heightmap[index],
+        biome,
+        width,
+        height,
+        sea_level,
+        settings,
+        cells,
+    );
+}
 
-        if water[i] > 0.6 {
-            biomes[i] = 1; // lake
+/// `apply_shores` reading each cell's elevation through `heightmap`.
+fn apply_shores_by(
+    heightmap: impl Fn(usize) -> f32,
+    biome: &mut [u8],
+    width: usize,
+    height: usize,
+    sea_level: f32,
+    settings: &GenerationSettings,
+    cells: impl IntoIterator<Item = usize>,
+) {
+    let radius = settings.shore_radius as i64;
+    for index in cells {
+        if matches!(
+            Biome::from_code(biome[index]),
+            Some(Biome::Ocean | Biome::Lake)
+        ) || heightmap(index) > sea_level + settings.beach_band
+        {
             continue;
         }
-
-        if elevation > 0.82 {
-            biomes[i] = 9; // alpine
+        let x = (index % width) as i64;
+        let y = (index / width) as i64;
+        // Shallows just below the sea classify as lake, so submerged cells
+        // count as sea alongside open ocean.
+        let near_sea = (-radius..=radius).any(|dy| {
+            (-radius..=radius).any(|dx| {
+                let (nx, ny) = (x + dx, y + dy);
+                if dx * dx + dy * dy > radius * radius
+                    || nx < 0
+                    || ny < 0
+                    || nx >= width as i64
+                    || ny >= height as i64
+                {
+                    return false;
+                }
+                let neighbor = ny as usize * width + nx as usize;
+                biome[neighbor] == Biome::Ocean.code() || heightmap(neighbor) <= sea_level
+            })
+        });
+        if !near_sea {
             continue;
         }
-
-        let temp = temperature[i];
-        let moist = moisture[i];
-
-        let biome = if temp < 0.2 {
-            2 // tundra
-        } else if temp < 0.35 {
-            if moist > 0.4 {
-                3
-            } else {
-                2
-            }
-        } else if temp < 0.55 {
-            if moist > 0.55 {
-                4
-            } else if moist > 0.35 {
-                5
-            } else {
-                8
-            }
-        } else if temp < 0.75 {
-            if moist > 0.65 {
-                4
-            } else if moist > 0.4 {
-                5
-            } else {
-                7
-            }
+        let slope = cell_slope_by(&heightmap, width, height, x as usize, y as usize);
+        biome[index] = if slope <= settings.beach_max_slope {
+            Biome::Beach.code()
         } else {
-            if moist > 0.7 {
-                6
-            } else if moist > 0.45 {
-                7
-            } else {
-                8
-            }
+            Biome::RockyShore.code()
         };
-
-        biomes[i] = biome;
     }
-    biomes
 }
 
-fn place_settlements(
-    heightmap: &[f32],
-    water: &[f32],
-    moisture: &[f32],
-    width: u32,
-    height: u32,
-    sea_level: f32,
-    seed: u32,
-) -> Vec<Settlement> {
-    let mut candidates: Vec<(usize, f32)> = Vec::new();
-    let width_i = width as usize;
-    let height_i = height as usize;
+/// Most settlements a map holds after placement.
+const MAX_SETTLEMENTS: usize = 16;
+/// World distance procedural settlements keep from every other settlement.
+const SETTLEMENT_SPACING: f32 = 120.0;
 
-    for y in 2..(height_i - 2) {
-        for x in 2..(width_i - 2) {
-            let index = y * width_i + x;
-            let elevation = heightmap[index];
-            if elevation <= sea_level + 0.02 {
-                continue;
-            }
-            if water[index] > 0.2 {
-                continue;
-            }
-            let flatness = local_flatness(heightmap, width_i, height_i, x, y);
-            if flatness > 0.08 {
-                continue;
-            }
+/// Score a candidate cell must beat to be considered for a settlement.
+const SITE_THRESHOLD: f32 = 0.35;
+
+/// Layers settlement sites are scored on. Placement passes the map's own;
+/// historical ruins pass a perturbed past climate.
+pub(crate) struct SiteLayers<'a> {
+    pub heightmap: &'a [f32],
+    pub water: &'a [f32],
+    pub moisture: &'a [f32],
+    pub width: usize,
+    pub height: usize,
+    pub sea_level: f32,
+}
+
+impl SiteLayers<'_> {
+    /// Unjittered suitability of cell `(x, y)`, or `None` when it is unfit.
+    pub(crate) fn score(&self, x: usize, y: usize) -> Option<f32> {
+        let index = y * self.width + x;
+        let flatness = settlement_site(
+            self.heightmap,
+            self.water,
+            self.width,
+            self.height,
+            x,
+            y,
+            self.sea_level,
+        )?;
+        Some(self.moisture[index] * 0.6 + (1.0 - flatness) * 0.3 + self.heightmap[index] * 0.1)
+    }
 
-            let score = moisture[index] * 0.6 + (1.0 - flatness) * 0.3 + elevation * 0.1;
-            if score > 0.35 {
-                candidates.push((index, score));
+    /// Cells scoring above `SITE_THRESHOLD`, best first, with scores
+    /// jittered by up to `score_jitter` per `seed`. The two-cell border is
+    /// never a candidate.
+    pub(crate) fn candidates(&self, seed: u32, score_jitter: f32) -> Vec<(usize, f32)> {
+        let mut candidates: Vec<(usize, f32)> = Vec::new();
+        for y in 2..(self.height - 2) {
+            for x in 2..(self.width - 2) {
+                let index = y * self.width + x;
+                let Some(mut score) = self.score(x, y) else {
+                    continue;
+                };
+                if score_jitter > 0.0 {
+                    score += (history::site_noise(seed, index) - 0.5) * score_jitter;
+                }
+                if score > SITE_THRESHOLD {
+                    candidates.push((index, score));
+                }
             }
         }
+        candidates.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        candidates
     }
+}
 
-    candidates.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+/// Adds settlements for one era to the survivors in `era.existing`.
+fn place_settlements(
+    layers: &SiteLayers,
+    seed: u32,
+    era: &history::EraPlacement,
+) -> Vec<Settlement> {
+    let candidates = layers.candidates(seed, era.score_jitter);
+    let (width_i, height_i) = (layers.width, layers.height);
+    let (width, height) = (width_i as u32, height_i as u32);
 
     let mut rng = SimpleRng::new(seed.wrapping_mul(747));
-    let mut settlements: Vec<Settlement> = Vec::new();
+    let mut settlements: Vec<Settlement> = era.existing.clone();
+    for forced in &era.forced {
+        settlements.push(Settlement {
+            id: settlements.len() as u32,
+            ..forced.clone()
+        });
+    }
+    if settlements.len() >= MAX_SETTLEMENTS {
+        return settlements;
+    }
 
     for (index, score) in candidates.into_iter().take(200) {
         let x = (index % width_i) as f32;
@@ -418,25 +1472,63 @@ fn place_settlements(
         let world_x = (x / width as f32) * REGION_SIZE;
         let world_y = (y / height as f32) * REGION_SIZE;
 
-        if settlements.iter().any(|s| {
-            let dx = s.x - world_x;
-            let dy = s.y - world_y;
-            (dx * dx + dy * dy).sqrt() < 120.0
-        }) {
+        let excluded = |x: f32, y: f32| era.exclusions.iter().any(|zone| zone.contains(x, y));
+        let here = (world_x, world_y);
+        if settlements
+            .iter()
+            .any(|s| distance((s.x, s.y), here) < SETTLEMENT_SPACING)
+            || era
+                .reserved
+                .iter()
+                .any(|&point| distance(point, here) < SETTLEMENT_SPACING)
+            || excluded(world_x, world_y)
+            || era
+                .ruins
+                .iter()
+                .any(|&point| distance(point, here) < era.ruin_exclusion)
+        {
             continue;
         }
 
-        let jitter_x = (rng.next_f32() - 0.5) * 25.0;
-        let jitter_y = (rng.next_f32() - 0.5) * 25.0;
+        // Jitter can carry a settlement onto a neighboring water or cliff
+        // cell, so each attempt is checked against the cell it lands in.
+        let mut position = (world_x, world_y);
+        for _attempt in 0..4 {
+            let jittered_x = (world_x + (rng.next_f32() - 0.5) * 25.0).clamp(0.0, REGION_SIZE);
+            let jittered_y = (world_y + (rng.next_f32() - 0.5) * 25.0).clamp(0.0, REGION_SIZE);
+            let cell_x = (jittered_x / REGION_SIZE * width as f32).round() as usize;
+            let cell_y = (jittered_y / REGION_SIZE * height as f32).round() as usize;
+            if cell_x < width_i
+                && cell_y < height_i
+                && !excluded(jittered_x, jittered_y)
+                && settlement_site(
+                    layers.heightmap,
+                    layers.water,
+                    width_i,
+                    height_i,
+                    cell_x,
+                    cell_y,
+                    layers.sea_level,
+                )
+                .is_some()
+            {
+                position = (jittered_x, jittered_y);
+                break;
+            }
+        }
+
         let size = (score * 6.0).clamp(1.2, 6.5);
         settlements.push(Settlement {
             id: settlements.len() as u32,
-            x: world_x + jitter_x,
-            y: world_y + jitter_y,
+            x: position.0,
+            y: position.1,
             size,
+            issue: None,
+            era: era.era,
+            name: None,
         });
 
-        if settlements.len() >= 16 {
+        if settlements.len() >= MAX_SETTLEMENTS {
             break;
         }
     }
@@ -444,6 +1536,25 @@ fn place_settlements(
     settlements
 }
 
+/// Flatness of a dry cell comfortably above the sea, or `None` when the cell
+/// is unfit for a settlement.
+fn settlement_site(
+    heightmap: &[f32],
+    water: &[f32],
+    width: usize,
+    height: usize,
+    x: usize,
+    y: usize,
+    sea_level: f32,
+) -> Option<f32> {
+    let index = y * width + x;
+    if heightmap[index] <= sea_level + 0.02 || water[index] > 0.2 {
+        return None;
+    }
+    let flatness = local_flatness(heightmap, width, height, x, y);
+    (flatness <= 0.08).then_some(flatness)
+}
+
 fn build_roads(settlements: &[Settlement]) -> Vec<(u32, u32)> {
     let count = settlements.len();
     if count < 2 {
@@ -464,9 +1575,7 @@ fn build_roads(settlements: &[Settlement]) -> Vec<(u32, u32)> {
                 if connected[j] {
                     continue;
                 }
-                let dx = a.x - b.x;
-                let dy = a.y - b.y;
-                let distance = (dx * dx + dy * dy).sqrt();
+                let distance = distance((a.x, a.y), (b.x, b.y));
                 if let Some((_bi, _bj, best)) = best_edge {
                     if distance < best {
                         best_edge = Some((i, j, distance));
@@ -479,7 +1588,7 @@ fn build_roads(settlements: &[Settlement]) -> Vec<(u32, u32)> {
 
         if let Some((a, b, _)) = best_edge {
             connected[b] = true;
-            edges.push((a as u32, b as u32));
+            edges.push((settlements[a].id, settlements[b].id));
         } else {
             break;
         }
@@ -488,6 +1597,58 @@ fn build_roads(settlements: &[Settlement]) -> Vec<(u32, u32)> {
     edges
 }
 
+/// Vertical exaggeration applied to gradients measured per map width.
+const RELIEF_SCALE: f32 = 0.2;
+
+/// Central-difference gradient in relief units. Gradients are measured per map
+/// width so slopes read the same at any resolution.
+fn gradient(heightmap: &[f32], width: usize, height: usize, x: usize, y: usize) -> (f32, f32) {
+    gradient_by(|index| heightmap[index], width, height, x, y)
+}
+
+fn gradient_by(
+    heightmap: impl Fn(usize) -> f32,
+    width: usize,
+    height: usize,
+    x: usize,
+    y: usize,
+) -> (f32, f32) {
+    let at = |x: usize, y: usize| heightmap(y * width + x);
+    let left = at(x.saturating_sub(1), y);
+    let right = at((x + 1).min(width - 1), y);
+    let up = at(x, y.saturating_sub(1));
+    let down = at(x, (y + 1).min(height - 1));
+    let dzdx = (right - left) * width as f32 * 0.5 * RELIEF_SCALE;
+    let dzdy = (down - up) * height as f32 * 0.5 * RELIEF_SCALE;
+    (dzdx, dzdy)
+}
+
+/// Surface angle normalized to 0..1, where 1.0 is a vertical wall.
+fn slope_map(heightmap: &[f32], width: usize, height: usize) -> Vec<f32> {
+    let mut slopes = vec![0.0f32; heightmap.len()];
+    for y in 0..height {
+        for x in 0..width {
+            slopes[y * width + x] = cell_slope(heightmap, width, height, x, y);
+        }
+    }
+    slopes
+}
+
+fn cell_slope(heightmap: &[f32], width: usize, height: usize, x: usize, y: usize) -> f32 {
+    cell_slope_by(|index| heightmap[index], width, height, x, y)
+}
+
+fn cell_slope_by(
+    heightmap: impl Fn(usize) -> f32,
+    width: usize,
+    height: usize,
+    x: usize,
+    y: usize,
+) -> f32 {
+    let (dzdx, dzdy) = gradient_by(heightmap, width, height, x, y);
+    (dzdx * dzdx + dzdy * dzdy).sqrt().atan() / std::f32::consts::FRAC_PI_2
+}
+
 fn local_flatness(heightmap: &[f32], width: usize, height: usize, x: usize, y: usize) -> f32 {
     let center = heightmap[y * width + x];
     let mut variance = 0.0f32;
@@ -525,3 +1686,98 @@ impl SimpleRng {
         (value as f64 / u32::MAX as f64) as f32
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn land_moisture_grows_with_moisture_scale() {
+        for seed in 0..4 {
+            let mut previous = f32::NEG_INFINITY;
+            for scale in [0.0, 0.25, 0.5, 1.0, 1.5, 2.0, 3.0] {
+                let map = generate_map(48, 48, seed, 0.42, 1.0, 40.0, 2, scale);
+                let land: Vec<f32> = (0..map.moisture.len())
+                    .filter(|&index| !map.is_water_body(index))
+                    .map(|index| map.moisture[index])
+                    .collect();
+                let mean = land.iter().sum::<f32>() / land.len() as f32;
+                assert!(mean >= previous, "seed {seed} scale {scale}");
+                previous = mean;
+            }
+        }
+    }
+
+    #[test]
+    fn high_amplitudes_do_not_flat_top() {
+        for amplitude in [1.0, 1.5, 2.0, 2.5, 3.0, 5.0] {
+            let map = generate_map(96, 96, 21, 0.42, amplitude, 40.0, 0, 1.0);
+            let saturated = map.heightmap.iter().filter(|&&value| value >= 1.0).count();
+            assert!(
+                saturated * 1000 < map.heightmap.len(),
+                "amplitude {amplitude}: {saturated} cells at 1.0"
+            );
+        }
+    }
+
+    #[test]
+    fn shores_follow_coastal_slope_with_explicit_precedence() {
+        let (width, height) = (12, 8);
+        let sea_level = 0.42;
+        let settings = GenerationSettings::new(1, sea_level, 1.0, 40.0, 0, 1.0);
+        let mut heightmap = vec![0.0f32; width * height];
+        let mut biome = vec![Biome::TemperateGrassland.code(); width * height];
+        for y in 0..height {
+            for x in 0..width {
+                let index = y * width + x;
+                heightmap[index] = match x {
+                    0..=2 => 0.41,
+                    _ if y < height / 2 => 0.425 + 0.004 * (x - 3) as f32,
+                    3 => 0.43,
+                    _ => 0.9,
+                };
+                if x <= 2 {
+                    biome[index] = Biome::Ocean.code();
+                }
+            }
+        }
+        // A river mouth that classified as lake keeps its code.
+        biome[width + 3] = Biome::Lake.code();
+
+        apply_shores(
+            &heightmap,
+            &mut biome,
+            width,
+            height,
+            sea_level,
+            &settings,
+            0..width * height,
+        );
+
+        let at = |x: usize, y: usize| Biome::from_code(biome[y * width + x]);
+        assert_eq!(at(3, 2), Some(Biome::Beach));
+        assert_eq!(at(4, 2), Some(Biome::Beach));
+        assert_eq!(at(3, 6), Some(Biome::RockyShore));
+        assert_eq!(at(3, 1), Some(Biome::Lake));
+        assert_eq!(at(1, 2), Some(Biome::Ocean));
+        // Beyond the shore radius, or above the beach band, land is untouched.
+        assert_eq!(at(6, 2), Some(Biome::TemperateGrassland));
+        assert_eq!(at(4, 6), Some(Biome::TemperateGrassland));
+    }
+
+    #[test]
+    fn settlements_stay_on_land_inside_the_world() {
+        for seed in 0..40 {
+            let map = generate_map(64, 64, seed, 0.42, 1.0, 40.0, 2, 1.0);
+            for settlement in &map.settlements {
+                assert!((0.0..=REGION_SIZE).contains(&settlement.x));
+                assert!((0.0..=REGION_SIZE).contains(&settlement.y));
+                let (x, y) = map.nearest_cell(settlement.x, settlement.y);
+                let index = y * 64 + x;
+                assert!(!map.is_water_body(index), "seed {seed}");
+                assert!(map.heightmap[index] > map.sea_level, "seed {seed}");
+                assert!(map.water[index] <= 0.2, "seed {seed}");
+            }
+        }
+    }
+}
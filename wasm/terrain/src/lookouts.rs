@@ -0,0 +1,429 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::biome::Biome;
+use crate::distance::chamfer_distance;
+use crate::economy::{territories, NO_TERRITORY};
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::poi::{Coverage, Poi, PoiKind};
+use crate::visibility::local_viewshed;
+use crate::MapResult;
+
+pub(crate) struct LookoutOptions {
+    /// Lighthouses to add at most.
+    pub lighthouses: u32,
+    /// Watchtowers to add at most.
+    pub watchtowers: u32,
+    /// How far a lookout sees, in world units.
+    pub radius: f32,
+    /// Eye height above the ground, in normalized elevation units.
+    pub observer_height: f32,
+    /// Side of the coarse blocks, in cells: each block offers one candidate
+    /// site and counts as one target.
+    pub stride: u32,
+    /// Ocean within this many world units of land counts as sea lane.
+    pub lane_reach: f32,
+    /// Watchtower sites must lie within this many world units of a road or
+    /// border.
+    pub road_reach: f32,
+}
+
+impl Default for LookoutOptions {
+    fn default() -> Self {
+        Self {
+            lighthouses: 3,
+            watchtowers: 4,
+            radius: 256.0,
+            observer_height: 0.03,
+            stride: 4,
+            lane_reach: 64.0,
+            road_reach: 64.0,
+        }
+    }
+}
+
+impl LookoutOptions {
+    #[cfg(feature = "wasm")]
+    fn from_js(options: &JsValue) -> Self {
+        let defaults = Self::default();
+        Self {
+            lighthouses: js::get_u32(options, "lighthouses", defaults.lighthouses),
+            watchtowers: js::get_u32(options, "watchtowers", defaults.watchtowers),
+            radius: js::get_f32(options, "radius", defaults.radius).max(0.0),
+            observer_height: js::get_f32(options, "observer_height", defaults.observer_height)
+                .max(0.0),
+            stride: js::get_u32(options, "stride", defaults.stride).max(1),
+            lane_reach: js::get_f32(options, "lane_reach", defaults.lane_reach).max(0.0),
+            road_reach: js::get_f32(options, "road_reach", defaults.road_reach).max(0.0),
+        }
+    }
+}
+
+/// Target cells grouped into coarse blocks.
+struct Targets {
+    /// `(cell, block)` for every target cell, in cell order.
+    cells: Vec<(usize, usize)>,
+    /// First target cell of each block, by block id.
+    anchors: Vec<usize>,
+    /// Target cells in each block.
+    sizes: Vec<u32>,
+}
+
+impl Targets {
+    fn new(is_target: &[bool], width: usize, stride: usize) -> Self {
+        let blocks_x = width.div_ceil(stride);
+        let mut ids = std::collections::HashMap::new();
+        let mut targets = Targets {
+            cells: Vec::new(),
+            anchors: Vec::new(),
+            sizes: Vec::new(),
+        };
+        for (cell, _) in is_target.iter().enumerate().filter(|(_, &on)| on) {
+            let block = (cell / width / stride) * blocks_x + (cell % width) / stride;
+            let id = *ids.entry(block).or_insert_with(|| {
+                targets.anchors.push(cell);
+                targets.sizes.push(0);
+                targets.anchors.len() - 1
+            });
+            targets.sizes[id] += 1;
+            targets.cells.push((cell, id));
+        }
+        targets
+    }
+
+    /// Ids of the blocks with at least one target visible from `cell`.
+    /// Only targets inside the viewshed window are scanned, a row at a time.
+    fn seen_from(&self, map: &MapResult, cell: usize, options: &LookoutOptions) -> Vec<usize> {
+        let width = map.width as usize;
+        let view = local_viewshed(
+            map,
+            cell % width,
+            cell / width,
+            options.observer_height,
+            options.radius,
+        );
+        let mut seen = Vec::new();
+        for y in view.y0..view.y0 + view.height {
+            let row = y * width + view.x0;
+            let first = self.cells.partition_point(|&(target, _)| target < row);
+            let last = self
+                .cells
+                .partition_point(|&(target, _)| target < row + view.width);
+            seen.extend(
+                self.cells[first..last]
+                    .iter()
+                    .filter(|&&(target, _)| view.sees(target % width, y))
+                    .map(|&(_, block)| block),
+            );
+        }
+        seen.sort_unstable();
+        seen.dedup();
+        seen
+    }
+}
+
+/// Greedy maximum coverage: repeatedly takes the candidate whose unwatched
+/// blocks hold the most target cells (`weights`), ties to the earlier
+/// candidate, until `count` sites are chosen or none adds anything. Blocks
+/// in `watched` start out covered. Returns each chosen candidate with the
+/// blocks it added.
+fn greedy_cover(
+    seen: &[Vec<usize>],
+    weights: &[u32],
+    watched: &mut [bool],
+    count: u32,
+) -> Vec<(usize, Vec<usize>)> {
+    let mut chosen = Vec::new();
+    for _ in 0..count {
+        let mut best: Option<(usize, u32)> = None;
+        for (candidate, blocks) in seen.iter().enumerate() {
+            let gain: u32 = blocks
+                .iter()
+                .filter(|&&block| !watched[block])
+                .map(|&block| weights[block])
+                .sum();
+            if gain > 0 && best.is_none_or(|(_, most)| gain > most) {
+                best = Some((candidate, gain));
+            }
+        }
+        let Some((candidate, _)) = best else {
+            break;
+        };
+        let added: Vec<usize> = seen[candidate]
+            .iter()
+            .copied()
+            .filter(|&block| !watched[block])
+            .collect();
+        for &block in &added {
+            watched[block] = true;
+        }
+        chosen.push((candidate, added));
+    }
+    chosen
+}
+
+/// Highest cell among `sites` in each coarse block, ties to the lower
+/// index, in cell order.
+fn thin(map: &MapResult, sites: impl Iterator<Item = usize>, stride: usize) -> Vec<usize> {
+    let width = map.width as usize;
+    let mut best: std::collections::BTreeMap<(usize, usize), usize> = Default::default();
+    for cell in sites {
+        let block = ((cell / width) / stride, (cell % width) / stride);
+        let entry = best.entry(block).or_insert(cell);
+        if map.heightmap[cell] > map.heightmap[*entry] {
+            *entry = cell;
+        }
+    }
+    let mut cells: Vec<usize> = best.into_values().collect();
+    cells.sort_unstable();
+    cells
+}
+
+/// Whether `cell` is at least as high as each of its eight neighbours.
+fn is_summit(map: &MapResult, cell: usize) -> bool {
+    let width = map.width as i64;
+    let height = map.height as i64;
+    let (x, y) = ((cell as i64) % width, (cell as i64) / width);
+    (-1..=1).all(|dy| {
+        (-1..=1).all(|dx| {
+            let (nx, ny) = (x + dx, y + dy);
+            nx < 0
+                || ny < 0
+                || nx >= width
+                || ny >= height
+                || map.heightmap[(ny * width + nx) as usize] <= map.heightmap[cell]
+        })
+    })
+}
+
+/// Sea cells in the `radius`-cell window around `cell`.
+fn sea_around(map: &MapResult, sea: &[bool], cell: usize, radius: i64) -> usize {
+    let width = map.width as i64;
+    let height = map.height as i64;
+    let (x, y) = ((cell as i64) % width, (cell as i64) / width);
+    let mut count = 0;
+    for ny in (y - radius).max(0)..=(y + radius).min(height - 1) {
+        for nx in (x - radius).max(0)..=(x + radius).min(width - 1) {
+            count += sea[(ny * width + nx) as usize] as usize;
+        }
+    }
+    count
+}
+
+fn sea_mask(map: &MapResult) -> Vec<bool> {
+    (0..map.biome.len())
+        .map(|cell| {
+            map.biome[cell] == Biome::Ocean.code()
+                || (map.is_water_body(cell) && map.heightmap[cell] < map.sea_level)
+        })
+        .collect()
+}
+
+/// Lighthouses and watchtowers placed to watch the most sea lane, road, and
+/// border from the fewest sites.
+pub(crate) fn lookout_sites(map: &MapResult, options: &LookoutOptions) -> Vec<Poi> {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let stride = options.stride.max(1) as usize;
+    // Ocean plus the shallows below sea level that classify as lake.
+    let sea = sea_mask(map);
+    let land = |cell: usize| !map.is_water_body(cell) && !map.is_river(cell);
+
+    // Sea lanes: the shipping band along the shore.
+    let shore_distance = chamfer_distance(
+        &sea.iter().map(|&sea| !sea).collect::<Vec<_>>(),
+        width,
+        height,
+    );
+    let lanes: Vec<bool> = (0..sea.len())
+        .map(|cell| sea[cell] && shore_distance[cell] <= options.lane_reach)
+        .collect();
+    // Headlands: shore cells with more sea around them than a straight
+    // coast would leave in the surrounding 5x5 window. Land rises inland, so
+    // unlike hills they need not be summits; thinning keeps the highest.
+    let headlands = (0..sea.len())
+        .filter(|&cell| land(cell) && sea_around(map, &sea, cell, 1) > 0)
+        .filter(|&cell| sea_around(map, &sea, cell, 2) > 10);
+    let lighthouses = (
+        PoiKind::Lighthouse,
+        Targets::new(&lanes, width, stride),
+        thin(map, headlands, stride),
+    );
+
+    // Roads and the land cells on a border between two territories.
+    let labels = territories(map);
+    let roads = map.road_mask();
+    let border = |cell: usize| {
+        let (x, y) = (cell % width, cell / width);
+        labels[cell] != NO_TERRITORY
+            && [
+                (x + 1 < width).then(|| cell + 1),
+                (y + 1 < height).then(|| cell + width),
+            ]
+            .into_iter()
+            .flatten()
+            .any(|next| labels[next] != NO_TERRITORY && labels[next] != labels[cell])
+    };
+    let watched: Vec<bool> = (0..sea.len())
+        .map(|cell| land(cell) && (roads[cell] || border(cell)))
+        .collect();
+    let watched_distance = chamfer_distance(&watched, width, height);
+    let hills = (0..sea.len()).filter(|&cell| {
+        land(cell) && watched_distance[cell] <= options.road_reach && is_summit(map, cell)
+    });
+    let towers = (
+        PoiKind::Watchtower,
+        Targets::new(&watched, width, stride),
+        thin(map, hills, stride),
+    );
+
+    let era = map.history.current_era();
+    let cell_area = map.cell_area();
+    let mut pois = Vec::new();
+    for ((kind, targets, candidates), count) in [
+        (lighthouses, options.lighthouses),
+        (towers, options.watchtowers),
+    ] {
+        // Blocks existing lookouts of this kind already watch.
+        let mut covered = vec![false; targets.anchors.len()];
+        for poi in map.pois.iter().filter(|poi| poi.kind == kind) {
+            let (x, y) = map.nearest_cell(poi.x, poi.y);
+            for block in targets.seen_from(map, y * width + x, options) {
+                covered[block] = true;
+            }
+        }
+        let seen: Vec<Vec<usize>> = candidates
+            .iter()
+            .map(|&cell| targets.seen_from(map, cell, options))
+            .collect();
+        for (candidate, blocks) in greedy_cover(&seen, &targets.sizes, &mut covered, count) {
+            let (x, y) = map.cell_to_world(candidates[candidate]);
+            pois.push(Poi {
+                id: 0,
+                kind,
+                x,
+                y,
+                era,
+                reason: None,
+                coverage: Some(Coverage {
+                    area: blocks
+                        .iter()
+                        .map(|&block| targets.sizes[block] as f32 * cell_area)
+                        .sum(),
+                    targets: blocks
+                        .iter()
+                        .map(|&block| map.cell_to_world(targets.anchors[block]))
+                        .collect(),
+                }),
+            });
+        }
+    }
+    pois
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Adds lighthouse and watchtower POIs where they see the most.
+    /// Lighthouse sites are shore cells jutting into the sea; watchtower
+    /// sites are hilltops within `options.road_reach` (default 64) world
+    /// units of a road or territory border. Lighthouses watch sea lanes, sea
+    /// within `options.lane_reach` (default 64) world units of land;
+    /// watchtowers watch road and border cells. Targets are grouped into
+    /// blocks of `options.stride` (default 4) cells square, and each block
+    /// offers its highest site as the one candidate. A block counts as
+    /// watched when any of its targets is in a site's viewshed
+    /// (`options.observer_height`, default 0.03, and `options.radius`,
+    /// default 256). Sites are picked greedily by the target area they add,
+    /// up to `options.lighthouses` (default 3) and `options.watchtowers`
+    /// (default 4); lookouts already on the map count as placed. Each POI's
+    /// `coverage` is the area it adds, in square world units, and `targets`
+    /// one interleaved world `x, y` point per block it adds. Returns the
+    /// number placed.
+    pub fn place_lookouts(&mut self, options: JsValue) -> u32 {
+        self.add_lookouts(&LookoutOptions::from_js(&options))
+    }
+}
+
+impl MapResult {
+    pub(crate) fn add_lookouts(&mut self, options: &LookoutOptions) -> u32 {
+        lookout_sites(self, options)
+            .into_iter()
+            .filter_map(|poi| self.register_poi(poi))
+            .count() as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{greedy_cover, sea_around, sea_mask, LookoutOptions};
+    use crate::generate_map;
+    use crate::poi::PoiKind;
+
+    #[test]
+    fn greedy_cover_takes_the_largest_gain_first() {
+        let seen = vec![vec![0, 1], vec![1, 2, 3], vec![3, 4], vec![0, 4]];
+        let mut watched = vec![false; 5];
+        let chosen = greedy_cover(&seen, &[1; 5], &mut watched, 3);
+        assert_eq!(chosen, vec![(1, vec![1, 2, 3]), (3, vec![0, 4])]);
+        assert!(watched.iter().all(|&covered| covered));
+
+        // Denser blocks outweigh more of them.
+        let mut watched = vec![false; 5];
+        let chosen = greedy_cover(&seen, &[1, 1, 1, 1, 9], &mut watched, 1);
+        assert_eq!(chosen, vec![(2, vec![3, 4])]);
+    }
+
+    #[test]
+    fn lookouts_watch_disjoint_targets_and_repeat_exactly() {
+        let mut map = generate_map(96, 96, 6, 0.42, 1.0, 40.0, 2, 1.0);
+        let before = map.pois.len();
+        let mut twin = generate_map(96, 96, 6, 0.42, 1.0, 40.0, 2, 1.0);
+        let options = LookoutOptions::default();
+        let first = map.add_lookouts(&options);
+        assert!(first > 0);
+        assert_eq!(twin.add_lookouts(&options), first);
+        for (poi, copy) in map.pois[before..].iter().zip(&twin.pois[before..]) {
+            assert_eq!(
+                (poi.kind.key(), poi.x, poi.y),
+                (copy.kind.key(), copy.x, copy.y)
+            );
+        }
+        // A second round keeps clear of what the first already watches.
+        let second = map.add_lookouts(&options) as usize;
+        let rounds = [
+            &map.pois[before..before + first as usize],
+            &map.pois[before + first as usize..],
+        ];
+        assert_eq!(rounds[1].len(), second);
+
+        for kind in [PoiKind::Lighthouse, PoiKind::Watchtower] {
+            let mut targets = Vec::new();
+            for round in rounds {
+                let mut last = f32::INFINITY;
+                for poi in round.iter().filter(|poi| poi.kind == kind) {
+                    let (x, y) = map.nearest_cell(poi.x, poi.y);
+                    let cell = y * 96 + x;
+                    assert!(!map.is_water_body(cell));
+                    if kind == PoiKind::Lighthouse {
+                        assert!(sea_around(&map, &sea_mask(&map), cell, 1) > 0);
+                    }
+                    let coverage = poi.coverage.as_ref().unwrap();
+                    assert!(coverage.area > 0.0 && coverage.area <= last);
+                    last = coverage.area;
+                    targets.extend(
+                        coverage
+                            .targets
+                            .iter()
+                            .map(|&(x, y)| (x.to_bits(), y.to_bits())),
+                    );
+                }
+            }
+            let count = targets.len();
+            targets.sort_unstable();
+            targets.dedup();
+            assert_eq!(targets.len(), count, "{} targets overlap", kind.key());
+        }
+    }
+}
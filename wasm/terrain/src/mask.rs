@@ -0,0 +1,164 @@
+#[cfg(feature = "wasm")]
+use js_sys::Uint8Array;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "wasm")]
+use crate::js;
+
+/// Margin a forced-land cell is kept above the sea.
+const LAND_MARGIN: f32 = 0.01;
+/// Depth a forced-ocean cell is kept below the sea, deep enough to classify
+/// as ocean rather than shoreline.
+const OCEAN_MARGIN: f32 = 0.03;
+
+/// Per-cell land bias in 0..1 taken from a user sketch: 0 forces ocean, 1
+/// forces land, and values between only bias the continent shape.
+pub(crate) struct LandMask {
+    values: Vec<f32>,
+}
+
+impl LandMask {
+    /// Reads `options.mask`. A mask whose size differs from the map is an
+    /// error unless `options.resample_mask` is set, in which case
+    /// `options.mask_width` and `options.mask_height` give its dimensions.
+    #[cfg(feature = "wasm")]
+    pub(crate) fn from_options(
+        options: &JsValue,
+        width: u32,
+        height: u32,
+    ) -> Result<Option<Self>, JsValue> {
+        let Some(mask) = js::get(options, "mask") else {
+            return Ok(None);
+        };
+        let bytes = mask
+            .dyn_into::<Uint8Array>()
+            .map_err(|_| JsValue::from_str("mask must be a Uint8Array"))?
+            .to_vec();
+        let source = if js::get_bool(options, "resample_mask", false) {
+            (
+                js::get_u32(options, "mask_width", width),
+                js::get_u32(options, "mask_height", height),
+            )
+        } else {
+            (width, height)
+        };
+        Self::from_bytes(&bytes, source, width, height)
+            .map(Some)
+            .map_err(|message| JsValue::from_str(&message))
+    }
+
+    pub(crate) fn from_bytes(
+        bytes: &[u8],
+        (source_width, source_height): (u32, u32),
+        width: u32,
+        height: u32,
+    ) -> Result<Self, String> {
+        if source_width == 0
+            || source_height == 0
+            || bytes.len() != (source_width * source_height) as usize
+        {
+            return Err(format!(
+                "mask holds {} bytes but {source_width}x{source_height} were expected",
+                bytes.len()
+            ));
+        }
+        let at = |x: usize, y: usize| bytes[y * source_width as usize + x] as f32 / 255.0;
+        let values = if (source_width, source_height) == (width, height) {
+            bytes.iter().map(|&byte| byte as f32 / 255.0).collect()
+        } else {
+            let mut values = Vec::with_capacity((width * height) as usize);
+            for y in 0..height {
+                for x in 0..width {
+                    let sx = resample_coord(x, width, source_width);
+                    let sy = resample_coord(y, height, source_height);
+                    let (x0, y0) = (sx.floor() as usize, sy.floor() as usize);
+                    let x1 = (x0 + 1).min(source_width as usize - 1);
+                    let y1 = (y0 + 1).min(source_height as usize - 1);
+                    let (tx, ty) = (sx.fract(), sy.fract());
+                    let top = at(x0, y0) + (at(x1, y0) - at(x0, y0)) * tx;
+                    let bottom = at(x0, y1) + (at(x1, y1) - at(x0, y1)) * tx;
+                    values.push(top + (bottom - top) * ty);
+                }
+            }
+            values
+        };
+        Ok(Self { values })
+    }
+
+    /// Replaces the radial continent falloff at `index`.
+    pub(crate) fn continentality(&self, index: usize) -> f32 {
+        self.values[index]
+    }
+
+    /// Pins cells the mask marks as certain land or ocean to the right side of
+    /// the sea level, leaving the noise relief on top of that.
+    pub(crate) fn enforce(&self, heightmap: &mut [f32], sea_level: f32) {
+        for (value, &bias) in heightmap.iter_mut().zip(&self.values) {
+            if bias >= 1.0 {
+                *value = value.max(sea_level + LAND_MARGIN);
+            } else if bias <= 0.0 {
+                *value = value.min(sea_level - OCEAN_MARGIN);
+            }
+        }
+    }
+}
+
+/// Maps a target cell onto the source grid so both grids span the same world.
+fn resample_coord(target: u32, target_size: u32, source_size: u32) -> f32 {
+    (target as f32 / target_size as f32 * source_size as f32).min((source_size - 1) as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LandMask;
+    use crate::{generate, GenerationSettings};
+
+    /// A lopsided blob, as if sketched by hand, on a 64x64 grid.
+    fn island(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = vec![0u8; (width * height) as usize];
+        for y in 0..height {
+            for x in 0..width {
+                let nx = x as f32 / width as f32 - 0.4;
+                let ny = y as f32 / height as f32 - 0.55;
+                if (nx * nx) / 0.09 + (ny * ny) / 0.04 < 1.0 {
+                    bytes[(y * width + x) as usize] = 255;
+                }
+            }
+        }
+        bytes
+    }
+
+    #[test]
+    fn coastline_follows_hand_drawn_island() {
+        let bytes = island(64, 64);
+        let mask = LandMask::from_bytes(&bytes, (64, 64), 64, 64).unwrap();
+        let settings = GenerationSettings::new(5, 0.42, 1.0, 40.0, 2, 1.0);
+        let map = generate(64, 64, &settings, Some(&mask));
+        for (index, &byte) in bytes.iter().enumerate() {
+            let water_body = map.is_water_body(index);
+            if byte == 255 {
+                assert!(map.heightmap[index] > map.sea_level);
+                assert_ne!(map.biome[index], 0);
+            } else {
+                assert!(water_body);
+            }
+        }
+        assert!(!map.settlements.is_empty());
+        for settlement in &map.settlements {
+            let (x, y) = map.nearest_cell(settlement.x, settlement.y);
+            assert_eq!(bytes[y * 64 + x], 255);
+        }
+        assert!(map.biome.iter().any(|&biome| biome > 1));
+        assert!((0..bytes.len()).any(|index| map.is_river(index)));
+    }
+
+    #[test]
+    fn mismatched_mask_needs_resampling() {
+        let bytes = island(32, 32);
+        assert!(LandMask::from_bytes(&bytes, (64, 64), 64, 64).is_err());
+        let mask = LandMask::from_bytes(&bytes, (32, 32), 64, 64).unwrap();
+        assert_eq!(mask.values.len(), 64 * 64);
+        assert_eq!(mask.continentality(0), 0.0);
+    }
+}
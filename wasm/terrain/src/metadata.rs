@@ -0,0 +1,102 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::json::Json;
+use crate::{GenerationSettings, MapResult};
+
+/// Bumped whenever the same inputs start producing different maps.
+///
+/// 1. Original generator.
+/// 2. Domain warp samples each axis separately (`warp_mode: "independent"`);
+///    `"legacy"` keeps version 1 terrain for a given seed.
+/// 3. `moisture_scale` multiplies noise moisture once instead of also
+///    dividing the water and flow bonuses, which now have their own weights.
+/// 4. Elevation is compressed toward its limits instead of clamped
+///    (`elevation_mode: "compress"`); `"legacy"` keeps the clamp.
+/// 5. Beach and rocky-shore biomes along the coast.
+/// 6. Land biomes come from `BiomeTable`, which adds steppe and moves wet
+///    subtropics to tropical forest and dry ones to savanna and steppe.
+/// 7. Thermal erosion sheds slopes steeper than a talus angle in world units
+///    (`erosion_mode: "talus"`, 4 degrees by default) and temperature is taken
+///    from the eroded ground; `"legacy"` keeps the fixed per-cell step.
+pub(crate) const GENERATOR_VERSION: u32 = 7;
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Generator version and the settings this map was built from, so saved
+    /// seeds can be regenerated identically or flagged as stale.
+    pub fn metadata(&self) -> JsValue {
+        let mut record = vec![("generator_version", GENERATOR_VERSION.into())];
+        record.extend(settings_record(&self.settings));
+        Json::from_record(record).to_js()
+    }
+}
+
+/// Every generation setting, named as in `generate_map_with_options`.
+#[cfg(feature = "wasm")]
+pub(crate) fn settings_record(settings: &GenerationSettings) -> Vec<(&'static str, Json)> {
+    vec![
+        ("seed", settings.seed.into()),
+        ("sea_level", settings.sea_level.into()),
+        ("elevation_amplitude", settings.elevation_amplitude.into()),
+        ("warp_strength", settings.warp_strength.into()),
+        ("warp_mode", settings.warp_mode.key().into()),
+        ("elevation_mode", settings.elevation_mode.key().into()),
+        ("erosion_iterations", settings.erosion_iterations.into()),
+        ("erosion_mode", settings.erosion_mode.key().into()),
+        ("talus_angle", settings.talus_angle.into()),
+        ("vertical_scale", settings.vertical_scale.into()),
+        ("thermal_transfer", settings.thermal_transfer.into()),
+        ("hardness", settings.hardness.into()),
+        ("hardness_scale", settings.hardness_scale.into()),
+        ("incision_iterations", settings.incision_iterations.into()),
+        ("incision_k", settings.incision_k.into()),
+        ("incision_m", settings.incision_m.into()),
+        ("incision_n", settings.incision_n.into()),
+        ("moisture_scale", settings.moisture_scale.into()),
+        ("water_moisture_bonus", settings.water_moisture_bonus.into()),
+        ("flow_moisture_bonus", settings.flow_moisture_bonus.into()),
+        ("beach_band", settings.beach_band.into()),
+        ("shore_radius", settings.shore_radius.into()),
+        ("beach_max_slope", settings.beach_max_slope.into()),
+        ("eras", settings.eras.into()),
+        ("abandon_fraction", settings.abandon_fraction.into()),
+        ("ruin_exclusion", settings.ruin_exclusion.into()),
+        ("road_paved_size", settings.road_paved_size.into()),
+        ("road_era_decay", settings.road_era_decay.into()),
+        ("road_remote_distance", settings.road_remote_distance.into()),
+        ("road_decay", settings.road_decay.into()),
+        ("coastal_roads", settings.coastal_roads.into()),
+        (
+            "coastal_road_discount",
+            settings.coastal_road_discount.into(),
+        ),
+        ("coastal_road_detour", settings.coastal_road_detour.into()),
+        ("ocean_currents", settings.ocean_currents.into()),
+        ("lake_outflows", settings.lake_outflows.into()),
+        ("faults", settings.faults.into()),
+        ("fault_scarps", settings.fault_scarps.into()),
+        ("biome_table", settings.biome_table.to_json()),
+        ("deterministic", settings.deterministic.into()),
+        (
+            "forced_settlements",
+            settings
+                .forced_settlements
+                .iter()
+                .map(|forced| forced.to_json())
+                .collect::<Vec<_>>()
+                .into(),
+        ),
+        (
+            "exclusion_zones",
+            settings
+                .exclusion_zones
+                .iter()
+                .map(|zone| zone.to_json())
+                .collect::<Vec<_>>()
+                .into(),
+        ),
+        ("snap_radius", settings.snap_radius.into()),
+    ]
+}
@@ -0,0 +1,483 @@
+use std::borrow::Cow;
+
+#[cfg(feature = "wasm")]
+use js_sys::{Float32Array, Reflect};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::biome::{Biome, BIOMES};
+use crate::ice;
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::json::Json;
+use crate::navigation;
+use crate::roughness::roughness;
+use crate::{slope_map, MapResult};
+
+/// Stand-in for impassable cells in exported cost layers, which cannot hold
+/// `Infinity` through JSON. Custom tables treat `null` and anything at or
+/// above it as impassable too.
+pub(crate) const IMPASSABLE: f32 = 1.0e9;
+/// Upper bound on `max_swim`; each allowed swum cell adds a search layer.
+pub(crate) const MAX_SWIM: u32 = 64;
+/// Window radius, in cells, of the roughness term.
+const ROUGHNESS_RADIUS: usize = 2;
+
+/// Per-cell movement cost weights. A land cell costs
+/// `1 + slope * slope + roughness * roughness + biome[b]`, times `roads`
+/// where a paved road crosses it; dirt and overgrown roads keep only part of
+/// that discount (see `road_condition`). Water is crossed on foot by depth,
+/// read from the `water` layer: river, lake, and sea cells shallower than
+/// `wade_depth` cost the land cost plus `river`, those shallower than
+/// `swim_depth` cost `swim`, at most `max_swim` cells in a row, and deeper
+/// ones cannot be walked. Roads bridge rivers, which then cost as land.
+/// Oceans and lakes can be sailed for `water`, and rivers for
+/// `water * river_sailing`, whichever is cheaper. With a nonzero `portage`,
+/// only navigable reaches sail so, portages cost `portage` times as much,
+/// and other river cells cannot be sailed. Infinite weights make cells
+/// impassable.
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) struct Profile {
+    pub name: &'static str,
+    pub slope: f32,
+    pub roughness: f32,
+    /// Additive penalty per biome code; ocean and lake use `water` instead.
+    pub biomes: [f32; BIOMES.len()],
+    pub water: f32,
+    pub river: f32,
+    pub river_sailing: f32,
+    /// Multiplier on sailing portages; 0 sails every river cell alike.
+    pub portage: f32,
+    /// Multiplier on land cells covered by a paved road.
+    pub roads: f32,
+    /// Water shallower than this is waded for the land cost plus `river`.
+    pub wade_depth: f32,
+    /// Water from `wade_depth` up to this depth is swum.
+    pub swim_depth: f32,
+    /// Cost of a swum cell.
+    pub swim: f32,
+    /// Most swum cells in a row on a path, at most `MAX_SWIM`; see
+    /// `astar_swimming`. Distance fields price swimming but ignore the limit.
+    pub max_swim: u32,
+}
+
+/// Built-in profiles, in the order `movement_profiles()` lists them.
+pub(crate) const PRESETS: [&str; 4] = ["foot", "cart", "boat", "flying"];
+
+impl Profile {
+    pub(crate) fn preset(name: &str) -> Option<Self> {
+        let biome_penalties = |forest: f32, tundra: f32, desert: f32, alpine: f32| {
+            BIOMES.map(|biome| match biome {
+                Biome::BorealForest | Biome::TemperateForest | Biome::TropicalForest => forest,
+                Biome::Tundra => tundra,
+                Biome::Desert => desert,
+                Biome::Alpine => alpine,
+                _ => 0.0,
+            })
+        };
+        let profile = match name {
+            "foot" => Profile {
+                name: "foot",
+                slope: 8.0,
+                roughness: 0.0,
+                biomes: biome_penalties(0.5, 0.3, 0.4, 2.0),
+                water: f32::INFINITY,
+                river: 2.0,
+                river_sailing: f32::INFINITY,
+                portage: 0.0,
+                roads: 0.4,
+                wade_depth: 0.45,
+                swim_depth: 0.7,
+                swim: 8.0,
+                max_swim: 3,
+            },
+            "cart" => Profile {
+                name: "cart",
+                slope: 20.0,
+                roughness: 4.0,
+                biomes: biome_penalties(1.5, 0.6, 0.8, f32::INFINITY),
+                water: f32::INFINITY,
+                river: 6.0,
+                river_sailing: f32::INFINITY,
+                portage: 0.0,
+                roads: 0.25,
+                wade_depth: 0.4,
+                swim_depth: 0.0,
+                swim: f32::INFINITY,
+                max_swim: 0,
+            },
+            "boat" => Profile {
+                name: "boat",
+                slope: 0.0,
+                roughness: 0.0,
+                biomes: [f32::INFINITY; BIOMES.len()],
+                water: 1.0,
+                river: 0.0,
+                river_sailing: 1.5,
+                portage: 10.0,
+                roads: 1.0,
+                wade_depth: 0.0,
+                swim_depth: 0.0,
+                swim: f32::INFINITY,
+                max_swim: 0,
+            },
+            "flying" => Profile {
+                name: "flying",
+                slope: 0.0,
+                roughness: 0.0,
+                biomes: [0.0; BIOMES.len()],
+                water: 1.0,
+                river: 0.0,
+                river_sailing: 1.0,
+                portage: 0.0,
+                roads: 1.0,
+                wade_depth: f32::INFINITY,
+                swim_depth: 0.0,
+                swim: f32::INFINITY,
+                max_swim: 0,
+            },
+            _ => return None,
+        };
+        Some(profile)
+    }
+
+    /// Slot in `MapCache::path_costs` for unmodified presets.
+    pub(crate) fn cache_slot(&self) -> Option<usize> {
+        let slot = PRESETS.iter().position(|&name| name == self.name)?;
+        (Profile::preset(self.name).as_ref() == Some(self)).then_some(slot)
+    }
+
+    pub(crate) fn to_json(&self) -> Json {
+        let biomes = BIOMES.iter().fold(Json::object(), |object, biome| {
+            object.with(biome.key(), self.biomes[biome.code() as usize])
+        });
+        Json::object()
+            .with("name", self.name)
+            .with("slope", self.slope)
+            .with("roughness", self.roughness)
+            .with("water", self.water)
+            .with("river", self.river)
+            .with("river_sailing", self.river_sailing)
+            .with("portage", self.portage)
+            .with("roads", self.roads)
+            .with("wade_depth", self.wade_depth)
+            .with("swim_depth", self.swim_depth)
+            .with("swim", self.swim)
+            .with("max_swim", self.max_swim)
+            .with("biomes", biomes)
+    }
+
+    /// A preset name, or an object with any of the `to_json` fields layered
+    /// over `base` (default `"foot"`); `biomes` is keyed by biome id.
+    #[cfg(feature = "wasm")]
+    pub(crate) fn from_js(value: &JsValue) -> Result<Self, JsValue> {
+        if let Some(name) = value.as_string() {
+            return Profile::preset(&name)
+                .ok_or_else(|| JsValue::from_str(&format!("unknown movement profile: {name}")));
+        }
+        let base = js::get_string(value, "base").unwrap_or_else(|| "foot".to_string());
+        let mut profile = Profile::preset(&base)
+            .ok_or_else(|| JsValue::from_str(&format!("unknown movement profile: {base}")))?;
+        profile.name = "custom";
+        let fields: [(&str, &mut f32); 10] = [
+            ("slope", &mut profile.slope),
+            ("roughness", &mut profile.roughness),
+            ("water", &mut profile.water),
+            ("river", &mut profile.river),
+            ("river_sailing", &mut profile.river_sailing),
+            ("portage", &mut profile.portage),
+            ("roads", &mut profile.roads),
+            ("wade_depth", &mut profile.wade_depth),
+            ("swim_depth", &mut profile.swim_depth),
+            ("swim", &mut profile.swim),
+        ];
+        for (key, field) in fields {
+            if let Some(cost) = cost_entry(value, key)? {
+                *field = cost;
+            }
+        }
+        profile.max_swim = js::get_u32(value, "max_swim", profile.max_swim);
+        if profile.max_swim > MAX_SWIM {
+            return Err(JsValue::from_str(&format!(
+                "max_swim must be at most {MAX_SWIM}"
+            )));
+        }
+        if let Some(biomes) = js::get(value, "biomes") {
+            for biome in BIOMES {
+                if let Some(cost) = cost_entry(&biomes, biome.key())? {
+                    profile.biomes[biome.code() as usize] = cost;
+                }
+            }
+        }
+        Ok(profile)
+    }
+}
+
+/// Reads one weight: missing keys are `None`, `null` and values at or above
+/// `IMPASSABLE` are infinite.
+#[cfg(feature = "wasm")]
+fn cost_entry(object: &JsValue, key: &str) -> Result<Option<f32>, JsValue> {
+    let value = Reflect::get(object, &JsValue::from(key)).unwrap_or(JsValue::UNDEFINED);
+    if value.is_undefined() {
+        return Ok(None);
+    }
+    if value.is_null() {
+        return Ok(Some(f32::INFINITY));
+    }
+    match value.as_f64() {
+        Some(cost) if cost >= IMPASSABLE as f64 => Ok(Some(f32::INFINITY)),
+        Some(cost) if cost >= 0.0 => Ok(Some(cost as f32)),
+        _ => Err(JsValue::from_str(&format!(
+            "movement weight {key} must be a non-negative number or null"
+        ))),
+    }
+}
+
+/// Built-in movement profiles as `{ name, slope, roughness, water, river,
+/// river_sailing, portage, roads, wade_depth, swim_depth, swim, max_swim,
+/// biomes }`, with `null` for impassable weights.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn movement_profiles() -> JsValue {
+    let profiles = PRESETS
+        .iter()
+        .filter_map(|name| Profile::preset(name))
+        .map(|profile| profile.to_json())
+        .collect::<Vec<_>>();
+    Json::from(profiles).to_js()
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Per-cell cost multipliers for a movement profile: a preset name from
+    /// `movement_profiles()` (`"foot"`, `"cart"`, `"boat"`, `"flying"`) or a
+    /// custom weight object layered over `base`. Impassable cells hold
+    /// `1e9`.
+    pub fn movement_cost(&self, profile: JsValue) -> Result<Float32Array, JsValue> {
+        let profile = Profile::from_js(&profile)?;
+        let costs: Vec<f32> = self
+            .movement_costs(&profile)
+            .iter()
+            .map(|&cost| cost.min(IMPASSABLE))
+            .collect();
+        Ok(Float32Array::from(costs.as_slice()))
+    }
+}
+
+/// A profile's cost field with the cells it crosses by swimming.
+#[derive(Clone)]
+pub(crate) struct CostField {
+    pub costs: Vec<f32>,
+    pub swimming: Vec<bool>,
+}
+
+impl MapResult {
+    /// Cost field for `profile`; presets are cached until the terrain,
+    /// settlements, roads, or ice change.
+    pub(crate) fn movement_costs(&self, profile: &Profile) -> Cow<'_, [f32]> {
+        match self.movement_field(profile) {
+            Cow::Borrowed(field) => field.costs.as_slice().into(),
+            Cow::Owned(field) => field.costs.into(),
+        }
+    }
+
+    /// `movement_costs` with the swum cells, cached alike.
+    pub(crate) fn movement_field(&self, profile: &Profile) -> Cow<'_, CostField> {
+        match profile.cache_slot() {
+            Some(slot) => {
+                Cow::Borrowed(self.cache.path_costs[slot].get_or_init(|| cost_field(self, profile)))
+            }
+            None => Cow::Owned(cost_field(self, profile)),
+        }
+    }
+}
+
+/// Per-cell cost multipliers; `f32::INFINITY` marks impassable cells. Ice
+/// only affects sailing: solid ice blocks boats, and floes double their cost.
+pub(crate) fn build_cost_field(map: &MapResult, profile: &Profile) -> Vec<f32> {
+    cost_field(map, profile).costs
+}
+
+fn cost_field(map: &MapResult, profile: &Profile) -> CostField {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let slopes = slope_map(&map.heightmap, width, height);
+    let rugged = (profile.roughness != 0.0)
+        .then(|| roughness(&map.heightmap, width, height, ROUGHNESS_RADIUS));
+    let roads = map.road_condition_mask();
+    let ice = map.ice_mask();
+    let navigation = (profile.portage > 0.0).then(|| navigation::classify(map).0);
+    let (costs, swimming) = (0..map.heightmap.len())
+        .map(|index| {
+            let sailing = |cost: f32| match ice.as_ref().map(|ice| ice[index]) {
+                Some(ice::ICE_SOLID) => f32::INFINITY,
+                Some(ice::ICE_PARTIAL) => cost * 2.0,
+                _ => cost,
+            };
+            let body = map.is_water_body(index);
+            let river = map.is_river(index);
+            let biome = map.biome[index] as usize;
+            let land = 1.0
+                + slopes[index] * profile.slope
+                + rugged.as_ref().map_or(0.0, |rugged| rugged[index]) * profile.roughness
+                + profile.biomes.get(biome).copied().unwrap_or(0.0);
+            // Open water has no bridges, so roads only help over rivers.
+            let road = roads[index].filter(|_| !body);
+            let depth = map.water[index];
+            let (walking, swum) = if (body || river) && road.is_none() {
+                if depth < profile.wade_depth {
+                    (land + profile.river, false)
+                } else if depth < profile.swim_depth {
+                    (profile.swim, true)
+                } else {
+                    (f32::INFINITY, false)
+                }
+            } else {
+                let discount = road.map_or(1.0, |condition| {
+                    1.0 + (profile.roads - 1.0) * condition.speed_share()
+                });
+                (land * discount, false)
+            };
+            let afloat = if body {
+                sailing(profile.water)
+            } else if river {
+                let boating = profile.water * profile.river_sailing;
+                let boating = match navigation.as_ref().map(|classes| classes[index]) {
+                    None | Some(navigation::NAV_NAVIGABLE) => boating,
+                    Some(navigation::NAV_PORTAGE) => boating * profile.portage,
+                    _ => f32::INFINITY,
+                };
+                sailing(boating)
+            } else {
+                f32::INFINITY
+            };
+            if afloat < walking {
+                (afloat, false)
+            } else {
+                (walking, swum)
+            }
+        })
+        .unzip();
+    CostField { costs, swimming }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_cost_field, Profile, PRESETS};
+    use crate::biome::Biome;
+    use crate::navigation::{classify, NAV_BLOCKED};
+    use crate::{generate_map, MapResult, Settlement, REGION_SIZE};
+
+    const SIZE: usize = 48;
+    const BRIDGE_ROW: usize = 6;
+
+    /// Flat grassland split by a three-cell river down columns 22 to 24,
+    /// too deep to wade, with a road bridging it on `BRIDGE_ROW`.
+    fn river_crossing() -> MapResult {
+        let mut map = generate_map(SIZE as u32, SIZE as u32, 3, 0.42, 1.0, 40.0, 0, 1.0);
+        for index in 0..SIZE * SIZE {
+            let river = (22..=24).contains(&(index % SIZE));
+            map.heightmap[index] = 0.5;
+            map.flow[index] = 1.0;
+            map.water[index] = if river { 0.55 } else { 0.0 };
+            map.biome[index] = Biome::TemperateGrassland.code();
+        }
+        let cell = REGION_SIZE / SIZE as f32;
+        map.settlements = [16, 30]
+            .into_iter()
+            .enumerate()
+            .map(|(id, x)| Settlement {
+                id: id as u32,
+                x: x as f32 * cell,
+                y: BRIDGE_ROW as f32 * cell,
+                size: 1.0,
+                issue: None,
+                era: 0,
+                name: None,
+            })
+            .collect();
+        map.road_graph = vec![(0, 1)];
+        map.history.road_eras.clear();
+        map.invalidate_settlements();
+        map.invalidate_terrain();
+        map
+    }
+
+    #[test]
+    fn walkers_swim_narrow_rivers_and_carts_take_the_bridge() {
+        let map = river_crossing();
+        let cell = REGION_SIZE / SIZE as f32;
+        let from = (10.0 * cell, 40.0 * cell);
+        let to = (36.0 * cell, 40.0 * cell);
+        let crossing = |path: &[usize]| {
+            path.iter()
+                .filter(|&&index| (22..=24).contains(&(index % SIZE)))
+                .map(|&index| index / SIZE)
+                .collect::<Vec<_>>()
+        };
+
+        // On foot: straight across, swimming all three cells.
+        let foot = Profile::preset("foot").unwrap();
+        let path = map.find_path_cells(from, to, &foot, 100_000, 0).unwrap();
+        let swum = crossing(&path);
+        assert_eq!(swum.len(), 3);
+        assert!(swum.iter().all(|&row| row > 30), "{swum:?}");
+
+        // Carts cannot swim, and walkers allowed only two strokes in a row
+        // cannot make it either: both go round by the bridge.
+        let cart = Profile::preset("cart").unwrap();
+        let timid = Profile {
+            max_swim: 2,
+            ..foot.clone()
+        };
+        for profile in [&cart, &timid] {
+            let path = map.find_path_cells(from, to, profile, 100_000, 0).unwrap();
+            assert!(crossing(&path).iter().all(|&row| row == BRIDGE_ROW));
+        }
+        // The limit lives in the search; the cost field still prices the swim.
+        let costs = build_cost_field(&map, &timid);
+        assert!(costs[40 * SIZE + 23].is_finite());
+        assert!(build_cost_field(&map, &cart)[40 * SIZE + 23].is_infinite());
+    }
+
+    #[test]
+    fn boats_invert_foot_passability() {
+        let map = generate_map(96, 96, 11, 0.42, 1.0, 40.0, 2, 1.0);
+        let walker = Profile::preset("foot").unwrap();
+        let foot = build_cost_field(&map, &walker);
+        let boat = build_cost_field(&map, &Profile::preset("boat").unwrap());
+        let classes = classify(&map).0;
+        let (mut water, mut land) = (0, 0);
+        for index in 0..foot.len() {
+            if map.is_river(index) {
+                // Boats only take the rivers they can float on.
+                assert!(foot[index].is_finite());
+                assert_eq!(boat[index].is_finite(), classes[index] != NAV_BLOCKED);
+            } else if map.is_water_body(index) {
+                water += 1;
+                // Walkers only swim the shallows.
+                let shallow = map.water[index] < walker.swim_depth;
+                assert_eq!(foot[index].is_finite(), shallow);
+                assert!(boat[index].is_finite());
+            } else {
+                land += 1;
+                assert!(foot[index].is_finite() && boat[index].is_infinite());
+            }
+        }
+        assert!(water > 0 && land > 0);
+    }
+
+    #[test]
+    fn presets_round_trip_their_cache_slots() {
+        for (slot, name) in PRESETS.iter().enumerate() {
+            let profile = Profile::preset(name).unwrap();
+            assert_eq!(profile.cache_slot(), Some(slot));
+            let custom = Profile {
+                slope: profile.slope + 1.0,
+                ..profile
+            };
+            assert_eq!(custom.cache_slot(), None);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use crate::SimpleRng;
+
+/// Sounds a naming style draws from. Empty codas end a syllable open, so
+/// repeating `""` makes open endings more common.
+pub(crate) struct Syllables {
+    pub key: &'static str,
+    pub onsets: &'static [&'static str],
+    pub vowels: &'static [&'static str],
+    pub codas: &'static [&'static str],
+}
+
+/// The style used for geographic features.
+pub(crate) const COMMON: Syllables = Syllables {
+    key: "common",
+    onsets: &[
+        "b", "d", "f", "g", "h", "k", "l", "m", "n", "r", "s", "t", "v", "th", "br", "dr", "kr",
+        "st", "gl", "vel",
+    ],
+    vowels: &["a", "e", "i", "o", "u", "ae", "ei", "ou", "y", "ia"],
+    codas: &["", "", "", "n", "r", "l", "s", "th", "m", "nd", "rn", "sh"],
+};
+
+/// Culture styles, selectable by `key`.
+pub(crate) const STYLES: [Syllables; 6] = [
+    Syllables {
+        key: "northern",
+        onsets: &[
+            "k", "sk", "th", "br", "dr", "gr", "h", "r", "st", "v", "sv", "tr",
+        ],
+        vowels: &["a", "e", "i", "o", "u", "ei", "au", "y"],
+        codas: &["r", "rd", "nd", "k", "ld", "rn", "st", "gg", "m"],
+    },
+    Syllables {
+        key: "coastal",
+        onsets: &["l", "m", "n", "s", "v", "c", "p", "t", "r", "f", "b"],
+        vowels: &["a", "e", "i", "o", "ia", "io", "ea", "a", "o"],
+        codas: &["", "", "", "", "s", "n", "l", "r"],
+    },
+    Syllables {
+        key: "steppe",
+        onsets: &["k", "t", "b", "q", "z", "ch", "s", "y", "d", "kh", "j"],
+        vowels: &["a", "u", "o", "e", "i", "a", "u"],
+        codas: &["n", "r", "k", "t", "z", "", "", "l"],
+    },
+    Syllables {
+        key: "sylvan",
+        onsets: &["l", "f", "el", "th", "s", "n", "m", "gw", "lh", "ar"],
+        vowels: &["a", "e", "i", "ae", "ia", "ie", "y", "ea"],
+        codas: &["n", "l", "th", "ss", "", "", "wen", "dil"],
+    },
+    Syllables {
+        key: "highland",
+        onsets: &["dr", "gl", "cr", "b", "m", "t", "gr", "k", "ll", "c"],
+        vowels: &["a", "o", "u", "ai", "ea", "ao"],
+        codas: &["ch", "n", "ll", "r", "m", "", "rr", "gh"],
+    },
+    Syllables {
+        key: "desert",
+        onsets: &["z", "q", "h", "s", "k", "m", "r", "j", "kh", "sh", "b"],
+        vowels: &["a", "i", "u", "aa", "ei", "a", "i"],
+        codas: &["r", "m", "n", "d", "", "", "h", "b"],
+    },
+];
+
+pub(crate) fn style(key: &str) -> Option<&'static Syllables> {
+    STYLES.iter().find(|style| style.key == key)
+}
+
+/// Deterministic two-to-three syllable place name. The same `seed` and
+/// `salt` always give the same name; different salts give unrelated names.
+pub(crate) fn place_name(seed: u32, salt: u32) -> String {
+    styled_name(&COMMON, seed, salt)
+}
+
+/// `place_name` drawing on the sounds of `style`.
+pub(crate) fn styled_name(style: &Syllables, seed: u32, salt: u32) -> String {
+    let mixed = seed.wrapping_mul(0x9e37_79b1) ^ salt.wrapping_mul(0x85eb_ca77).rotate_left(15);
+    let mut rng = SimpleRng::new(mixed);
+    for _ in 0..4 {
+        rng.next_u32();
+    }
+    let mut pick = |options: &[&'static str]| options[rng.next_u32() as usize % options.len()];
+
+    let syllables = 2 + (pick(&["", "", "x"]) == "x") as usize;
+    let mut name = String::new();
+    for syllable in 0..syllables {
+        name.push_str(pick(style.onsets));
+        name.push_str(pick(style.vowels));
+        if syllable + 1 == syllables {
+            name.push_str(pick(style.codas));
+        }
+    }
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => name,
+    }
+}
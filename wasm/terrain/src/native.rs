@@ -0,0 +1,311 @@
+//! Plain Rust API for native builds: the generation pipeline as separate
+//! stages over `Vec<f32>` and `Vec<u8>` layers, with no JS types. Build with
+//! `--no-default-features` to drop the wasm-bindgen layer entirely.
+//!
+//! ```no_run
+//! use terrain::native::{self, GenerationSettings};
+//!
+//! let settings = GenerationSettings {
+//!     seed: 7,
+//!     ..GenerationSettings::default()
+//! };
+//! let terrain = native::sample_terrain(256, 256, &settings);
+//! let hydrology = native::trace_water(&terrain, &settings);
+//! let biome = native::classify_biomes(&terrain, &hydrology, &settings);
+//! let (settlements, roads) = native::place_settlements(&terrain, &hydrology, &settings);
+//! ```
+
+use crate::incision::apply_incision;
+use crate::mask::LandMask;
+use crate::thermal::apply_thermal_erosion;
+use crate::{
+    apply_shores, build_flow_map, cell_temperature, classify_cell, currents, determinism,
+    enhance_moisture, faults, history, hydrology, sample_fields, MapResult,
+};
+
+pub use crate::biome::{Biome, BiomeTable};
+pub use crate::faults::Fault;
+pub use crate::scenario::{ExclusionZone, ForcedSettlement};
+pub use crate::thermal::ErosionMode;
+pub use crate::{ElevationMode, GenerationSettings, WarpMode};
+
+/// Noise-driven layers after erosion, before any water. With
+/// `ocean_currents` set, `temperature` already includes the coastal shift;
+/// with `fault_scarps`, `heightmap` already includes the scarps.
+pub struct Terrain {
+    pub width: u32,
+    pub height: u32,
+    pub heightmap: Vec<f32>,
+    /// Fault lines, traced before any scarps; empty without `faults`.
+    pub faults: Vec<Fault>,
+    /// Unscaled noise moisture; `Hydrology::moisture` adds water bonuses.
+    pub moisture: Vec<f32>,
+    pub temperature: Vec<f32>,
+}
+
+/// Flow accumulation and the water and moisture derived from it.
+pub struct Hydrology {
+    pub flow: Vec<f32>,
+    pub water: Vec<f32>,
+    pub moisture: Vec<f32>,
+}
+
+pub struct SettlementData {
+    pub id: u32,
+    /// World coordinates, `0..REGION_SIZE` on both axes.
+    pub x: f32,
+    pub y: f32,
+    pub size: f32,
+    pub era: u32,
+    /// Caller's name for forced settlements.
+    pub name: Option<String>,
+}
+
+/// Every layer of a generated map, mirroring `MapResult`'s getters.
+pub struct MapData {
+    pub width: u32,
+    pub height: u32,
+    pub sea_level: f32,
+    pub heightmap: Vec<f32>,
+    pub flow: Vec<f32>,
+    pub moisture: Vec<f32>,
+    pub temperature: Vec<f32>,
+    pub biome: Vec<u8>,
+    pub water: Vec<f32>,
+    pub settlements: Vec<SettlementData>,
+    /// Settlement id pairs joined by a road.
+    pub roads: Vec<(u32, u32)>,
+}
+
+/// Runs every stage, exactly as `generate_map_with_options` does.
+pub fn generate(width: u32, height: u32, settings: &GenerationSettings) -> MapData {
+    MapData::from(&crate::generate(width, height, settings, None))
+}
+
+/// Samples elevation, moisture, and temperature noise and erodes the result.
+pub fn sample_terrain(width: u32, height: u32, settings: &GenerationSettings) -> Terrain {
+    terrain(width, height, settings, None)
+}
+
+pub(crate) fn terrain(
+    width: u32,
+    height: u32,
+    settings: &GenerationSettings,
+    mask: Option<&LandMask>,
+) -> Terrain {
+    let size = (width * height) as usize;
+    let mut heightmap = vec![0.0f32; size];
+    let mut moisture = vec![0.0f32; size];
+    let mut temperature = vec![0.0f32; size];
+    sample_fields(
+        width,
+        height,
+        settings,
+        mask,
+        &mut heightmap,
+        &mut moisture,
+        &mut temperature,
+    );
+
+    apply_thermal_erosion(
+        &mut heightmap,
+        width,
+        height,
+        settings.erosion_iterations,
+        settings,
+    );
+    apply_incision(&mut heightmap, width, height, settings);
+    let faults = if settings.faults {
+        faults::trace_faults(
+            &heightmap,
+            width as usize,
+            height as usize,
+            settings.sea_level,
+            settings.seed,
+        )
+    } else {
+        Vec::new()
+    };
+    if settings.fault_scarps {
+        faults::apply_scarps(&mut heightmap, width as usize, height as usize, &faults);
+    }
+    if let Some(mask) = mask {
+        mask.enforce(&mut heightmap, settings.sea_level);
+    }
+    // Erosion, incision, and scarps moved the ground the lapse rate was
+    // sampled on.
+    for (index, temperature) in temperature.iter_mut().enumerate() {
+        let latitude = (index / width as usize) as f32 / height as f32;
+        *temperature = cell_temperature(latitude, heightmap[index], settings.sea_level);
+    }
+    if settings.ocean_currents {
+        currents::apply_current_temperature(
+            &heightmap,
+            &mut temperature,
+            width as usize,
+            height as usize,
+            settings.sea_level,
+        );
+    }
+    if settings.deterministic {
+        determinism::quantize(&mut heightmap);
+        determinism::quantize(&mut moisture);
+        determinism::quantize(&mut temperature);
+    }
+    Terrain {
+        width,
+        height,
+        heightmap,
+        faults,
+        moisture,
+        temperature,
+    }
+}
+
+/// Accumulates downslope flow, marks oceans, lakes, and rivers, and adds
+/// their moisture bonuses. With `lake_outflows` set, depressions fill into
+/// lakes that drain onward through their spill points.
+pub fn trace_water(terrain: &Terrain, settings: &GenerationSettings) -> Hydrology {
+    let (flow, mut water) = if settings.lake_outflows {
+        let aridity = hydrology::aridity(
+            &terrain.temperature,
+            &terrain.moisture,
+            settings.moisture_scale,
+        );
+        let routing = hydrology::route_lakes(
+            &terrain.heightmap,
+            &aridity,
+            terrain.width as usize,
+            terrain.height as usize,
+            settings.sea_level,
+        );
+        let water = routing.water(&terrain.heightmap, settings.sea_level);
+        (routing.flow, water)
+    } else {
+        build_flow_map(
+            &terrain.heightmap,
+            terrain.width,
+            terrain.height,
+            settings.sea_level,
+        )
+    };
+    if settings.deterministic {
+        determinism::quantize(&mut water);
+    }
+    let mut moisture = terrain.moisture.clone();
+    enhance_moisture(&mut moisture, &water, &flow, settings);
+    Hydrology {
+        flow,
+        water,
+        moisture,
+    }
+}
+
+/// Biome codes per cell, including beaches and rocky shores.
+pub fn classify_biomes(
+    terrain: &Terrain,
+    hydrology: &Hydrology,
+    settings: &GenerationSettings,
+) -> Vec<u8> {
+    let mut biome: Vec<u8> = (0..terrain.heightmap.len())
+        .map(|i| {
+            classify_cell(
+                terrain.heightmap[i],
+                hydrology.water[i],
+                terrain.temperature[i],
+                hydrology.moisture[i],
+                settings.sea_level,
+                &settings.biome_table,
+            )
+            .code()
+        })
+        .collect();
+    apply_shores(
+        &terrain.heightmap,
+        &mut biome,
+        terrain.width as usize,
+        terrain.height as usize,
+        settings.sea_level,
+        settings,
+        0..terrain.heightmap.len(),
+    );
+    biome
+}
+
+/// Settlements surviving every era and the roads between them. Forced
+/// settlements with no valid site within `snap_radius` are left out.
+pub fn place_settlements(
+    terrain: &Terrain,
+    hydrology: &Hydrology,
+    settings: &GenerationSettings,
+) -> (Vec<SettlementData>, Vec<(u32, u32)>) {
+    let eras = history::simulate_eras(
+        &terrain.heightmap,
+        &hydrology.water,
+        &hydrology.moisture,
+        terrain.width,
+        terrain.height,
+        settings,
+    );
+    let settlements = eras.settlements.iter().map(SettlementData::from).collect();
+    (settlements, eras.road_graph)
+}
+
+impl From<&crate::Settlement> for SettlementData {
+    fn from(settlement: &crate::Settlement) -> Self {
+        Self {
+            id: settlement.id,
+            x: settlement.x,
+            y: settlement.y,
+            size: settlement.size,
+            era: settlement.era,
+            name: settlement.name.clone(),
+        }
+    }
+}
+
+impl From<&MapResult> for MapData {
+    fn from(map: &MapResult) -> Self {
+        Self {
+            width: map.width,
+            height: map.height,
+            sea_level: map.sea_level,
+            heightmap: map.heightmap.clone(),
+            flow: map.flow.clone(),
+            moisture: map.moisture.clone(),
+            temperature: map.temperature.clone(),
+            biome: map.biome.clone(),
+            water: map.water.clone(),
+            settlements: map.settlements.iter().map(SettlementData::from).collect(),
+            roads: map.road_graph.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        classify_biomes, generate, place_settlements, sample_terrain, trace_water,
+        GenerationSettings,
+    };
+
+    #[test]
+    fn staged_pipeline_matches_generate() {
+        let settings = GenerationSettings {
+            seed: 19,
+            ..GenerationSettings::default()
+        };
+        let map = generate(96, 64, &settings);
+        let terrain = sample_terrain(96, 64, &settings);
+        let hydrology = trace_water(&terrain, &settings);
+        let biome = classify_biomes(&terrain, &hydrology, &settings);
+        let (settlements, roads) = place_settlements(&terrain, &hydrology, &settings);
+        assert_eq!(terrain.heightmap, map.heightmap);
+        assert_eq!(hydrology.water, map.water);
+        assert_eq!(hydrology.moisture, map.moisture);
+        assert_eq!(biome, map.biome);
+        assert_eq!(roads, map.roads);
+        assert_eq!(settlements.len(), map.settlements.len());
+        assert!(!settlements.is_empty());
+    }
+}
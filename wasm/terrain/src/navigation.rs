@@ -0,0 +1,267 @@
+#[cfg(feature = "wasm")]
+use js_sys::Uint8Array;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::features::neighbors;
+use crate::hydrology::{extract_rivers, Outlet, River};
+#[cfg(feature = "wasm")]
+use crate::json::Json;
+use crate::{MapResult, REGION_SIZE};
+
+/// Per-cell classes of `river_navigability()`.
+pub(crate) const NAV_NONE: u8 = 0;
+pub(crate) const NAV_NAVIGABLE: u8 = 1;
+pub(crate) const NAV_PORTAGE: u8 = 2;
+pub(crate) const NAV_BLOCKED: u8 = 3;
+
+/// Least `water` (the runoff depth proxy) a boat can float on.
+const MIN_DEPTH: f32 = 0.45;
+/// Least channel cells, river or open water, around a cell; fewer means a
+/// trickle too narrow to turn a barge.
+const MIN_WIDTH: usize = 2;
+/// Steepest world rise over run a boat can work against; waterfalls and
+/// rapids exceed it.
+const MAX_GRADIENT: f32 = 0.08;
+/// Longest unnavigable run, in cells, carried around as a portage.
+const MAX_PORTAGE: usize = 6;
+
+/// A run of one river's cells sharing a class, from upstream to downstream.
+pub(crate) struct Reach {
+    pub river: u32,
+    pub class: u8,
+    pub cells: Vec<usize>,
+}
+
+impl Reach {
+    #[cfg(feature = "wasm")]
+    fn record(&self, map: &MapResult) -> Json {
+        let kind = if self.class == NAV_PORTAGE {
+            "portage"
+        } else {
+            "navigable"
+        };
+        let points = self
+            .cells
+            .iter()
+            .flat_map(|&index| {
+                let (x, y) = map.cell_to_world(index);
+                [Json::from(x), Json::from(y)]
+            })
+            .collect::<Vec<_>>();
+        let (from_x, from_y) = map.cell_to_world(self.cells[0]);
+        let (to_x, to_y) = map.cell_to_world(self.cells[self.cells.len() - 1]);
+        Json::object()
+            .with("river", self.river)
+            .with("kind", kind)
+            .with("portage", self.class == NAV_PORTAGE)
+            .with("from_x", from_x)
+            .with("from_y", from_y)
+            .with("to_x", to_x)
+            .with("to_y", to_y)
+            .with("cells", self.cells.len())
+            .with("points", points)
+    }
+}
+
+/// Whether a boat can work a river cell whose flow continues to `next`.
+fn sailable(map: &MapResult, index: usize, next: usize) -> bool {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let channel = neighbors(index, width, height)
+        .filter(|&n| map.is_river(n) || map.is_water_body(n))
+        .count();
+    let dx = (next % width).abs_diff(index % width) as f32 * REGION_SIZE / width as f32;
+    let dy = (next / width).abs_diff(index / width) as f32 * REGION_SIZE / height as f32;
+    let rise = (map.heightmap[index] - map.heightmap[next]).max(0.0) * map.settings.vertical_scale;
+    map.water[index] >= MIN_DEPTH
+        && channel >= MIN_WIDTH
+        && rise / dx.hypot(dy).max(f32::EPSILON) <= MAX_GRADIENT
+}
+
+/// Classes every river cell by depth, width, and gradient, then bridges
+/// short unnavigable runs with navigable water at both ends as portages.
+/// Returns the per-cell classes and the reaches of every river.
+pub(crate) fn classify(map: &MapResult) -> (Vec<u8>, Vec<Reach>) {
+    let rivers = extract_rivers(map);
+    let mut classes = vec![NAV_NONE; map.heightmap.len()];
+    for river in &rivers {
+        for (position, &index) in river.cells.iter().enumerate() {
+            if !map.is_river(index) || owned_elsewhere(river, position) {
+                continue;
+            }
+            // Rivers sinking into a pit or off the edge end in still water.
+            let next = river.cells.get(position + 1).copied().unwrap_or(index);
+            classes[index] = if sailable(map, index, next) {
+                NAV_NAVIGABLE
+            } else {
+                NAV_BLOCKED
+            };
+        }
+    }
+    // Open water and navigable river both float a boat past a run's ends.
+    let floats =
+        |classes: &[u8], index: usize| map.is_water_body(index) || classes[index] == NAV_NAVIGABLE;
+
+    let mut reaches = Vec::new();
+    for river in &rivers {
+        let mut start = 0;
+        while start < river.cells.len() {
+            let class = classes[river.cells[start]];
+            let mut end = start + 1;
+            while end < river.cells.len()
+                && map.is_river(river.cells[end])
+                && classes[river.cells[end]] == class
+                && !owned_elsewhere(river, end)
+            {
+                end += 1;
+            }
+            if class == NAV_NONE || owned_elsewhere(river, start) {
+                start = end;
+                continue;
+            }
+            let mut class = class;
+            if class == NAV_BLOCKED
+                && end - start <= MAX_PORTAGE
+                && start > 0
+                && end < river.cells.len()
+                && floats(&classes, river.cells[start - 1])
+                && floats(&classes, river.cells[end])
+            {
+                class = NAV_PORTAGE;
+                for &index in &river.cells[start..end] {
+                    classes[index] = NAV_PORTAGE;
+                }
+            }
+            reaches.push(Reach {
+                river: river.id,
+                class,
+                cells: river.cells[start..end].to_vec(),
+            });
+            start = end;
+        }
+    }
+    (classes, reaches)
+}
+
+/// The last cell of a river ending in a confluence belongs to the river it
+/// joins.
+fn owned_elsewhere(river: &River, position: usize) -> bool {
+    position == river.cells.len() - 1 && matches!(river.outlet, Outlet::Confluence(_))
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Boat access of each cell: 0 not a river, 1 navigable, 2 portage around
+    /// a short waterfall or rapid between navigable water, 3 blocked. Cells
+    /// need enough flow (depth), neighbouring channel (width), and a gentle
+    /// enough drop downstream to be navigable. The `"boat"` movement profile
+    /// sails classes 1 and 2, the latter at `portage` times the cost.
+    pub fn river_navigability(&self) -> Uint8Array {
+        Uint8Array::from(classify(self).0.as_slice())
+    }
+
+    /// The boat network of the rivers: every navigable reach and the
+    /// portages linking them, as `{ river, kind, portage, from_x, from_y,
+    /// to_x, to_y, cells, points }` running downstream, `kind` being
+    /// `"navigable"` or `"portage"`. Reaches of one river meet end to end,
+    /// and the last reach of a tributary meets the reach it flows into.
+    pub fn navigable_reaches(&self) -> JsValue {
+        let records = classify(self)
+            .1
+            .iter()
+            .filter(|reach| reach.class != NAV_BLOCKED)
+            .map(|reach| reach.record(self))
+            .collect::<Vec<_>>();
+        Json::from(records).to_js()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{classify, NAV_BLOCKED, NAV_NAVIGABLE, NAV_PORTAGE};
+    use crate::biome::Biome;
+    use crate::movement::{build_cost_field, Profile};
+    use crate::pathfinding::astar;
+    use crate::{generate_map, MapResult, REGION_SIZE};
+
+    const WIDTH: usize = 96;
+    const HEIGHT: usize = 32;
+    const FALLS: usize = 40;
+
+    /// A river down the middle row from a spring at x = 2 to the sea past
+    /// x = 84, shallow for its first cells and dropping over a waterfall at
+    /// `FALLS`.
+    fn valley() -> MapResult {
+        let mut map = generate_map(WIDTH as u32, HEIGHT as u32, 1, 0.42, 1.0, 40.0, 2, 1.0);
+        for index in 0..WIDTH * HEIGHT {
+            let (x, y) = (index % WIDTH, index / WIDTH);
+            // Just above the sea at the mouth, so the river runs out level.
+            let floor =
+                0.425 + 0.0004 * (84 - x.min(84)) as f32 + if x <= FALLS { 0.05 } else { 0.0 };
+            map.heightmap[index] = if x > 84 {
+                0.415
+            } else {
+                floor + 0.01 * y.abs_diff(HEIGHT / 2) as f32
+            };
+            let river = y == HEIGHT / 2 && (2..=84).contains(&x);
+            map.flow[index] = if river { x as f32 * 10.0 } else { 1.0 };
+            map.water[index] = match (x > 84, river) {
+                (true, _) => 1.0,
+                (false, true) if x < 12 => 0.35,
+                (false, true) => 0.8,
+                _ => 0.0,
+            };
+            map.biome[index] = if x > 84 {
+                Biome::Ocean
+            } else {
+                Biome::TemperateGrassland
+            }
+            .code();
+        }
+        map.invalidate_terrain();
+        map
+    }
+
+    #[test]
+    fn barges_portage_round_the_falls_to_the_sea() {
+        let map = valley();
+        let (classes, reaches) = classify(&map);
+        let row = HEIGHT / 2 * WIDTH;
+        assert_eq!(classes[row + 5], NAV_BLOCKED);
+        assert_eq!(classes[row + FALLS], NAV_PORTAGE);
+        assert_eq!(classes[row + 60], NAV_NAVIGABLE);
+        assert!(reaches
+            .iter()
+            .any(|reach| reach.class == NAV_PORTAGE && reach.cells == [row + FALLS]));
+
+        let boat = Profile::preset("boat").unwrap();
+        let costs = build_cost_field(&map, &boat);
+        assert!(costs[row + 5].is_infinite());
+        let town = row + 14;
+        let port = row + 90;
+        let path = astar(&costs, WIDTH, HEIGHT, town, port, 100_000).unwrap();
+        assert!(path.contains(&(row + FALLS)));
+        assert!(path[..path.len() - 5]
+            .iter()
+            .all(|&index| index / WIDTH == HEIGHT / 2));
+
+        // Sailing time is the plain river and sea passage plus the carry
+        // round the falls.
+        let step = REGION_SIZE / WIDTH as f32;
+        let sailing = boat.water * boat.river_sailing;
+        let time = |cost: &dyn Fn(usize) -> f32| -> f32 {
+            path.windows(2)
+                .map(|pair| (cost(pair[0]) + cost(pair[1])) * 0.5 * step)
+                .sum()
+        };
+        let total = time(&|index| costs[index]);
+        let plain = time(&|index| costs[index].min(sailing));
+        let carry = (boat.portage - 1.0) * sailing * step;
+        assert!(
+            (total - plain - carry).abs() < 1e-2 * total,
+            "{total} vs {plain}"
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::{FieldSampler, GenerationSettings, WarpMode};
+
+/// Everything the raw noise fields depend on. Amplitude, elevation mode,
+/// sea level, masks, and all later stages apply on top, so changing them
+/// reuses the fields.
+#[derive(Clone, Copy, PartialEq)]
+struct NoiseKey {
+    seed: u32,
+    width: u32,
+    height: u32,
+    warp_strength: f32,
+    warp_mode: WarpMode,
+}
+
+/// Per-cell noise before amplitude and continentality shape it.
+pub(crate) struct RawNoise {
+    /// `FieldSampler::octaves` at each cell's warped position.
+    pub elevation: Vec<f32>,
+    /// `FieldSampler::moisture` at each cell's warped position.
+    pub moisture: Vec<f32>,
+}
+
+thread_local! {
+    /// The fields of the latest generation only, so the cache never holds
+    /// more than one map's worth.
+    static CACHE: RefCell<Option<(NoiseKey, Rc<RawNoise>)>> = const { RefCell::new(None) };
+}
+
+/// The raw fields for `settings` at `width * height`, sampled on a miss.
+pub(crate) fn raw_noise(
+    width: u32,
+    height: u32,
+    settings: &GenerationSettings,
+    sampler: &FieldSampler,
+) -> Rc<RawNoise> {
+    let key = NoiseKey {
+        seed: settings.seed,
+        width,
+        height,
+        warp_strength: settings.warp_strength,
+        warp_mode: settings.warp_mode,
+    };
+    CACHE.with(|cache| {
+        if let Some((cached, noise)) = cache.borrow().as_ref() {
+            if *cached == key {
+                return noise.clone();
+            }
+        }
+        let noise = Rc::new(sample(width, height, sampler));
+        *cache.borrow_mut() = Some((key, noise.clone()));
+        noise
+    })
+}
+
+fn sample(width: u32, height: u32, sampler: &FieldSampler) -> RawNoise {
+    let size = (width * height) as usize;
+    let mut elevation = Vec::with_capacity(size);
+    let mut moisture = Vec::with_capacity(size);
+    for y in 0..height {
+        for x in 0..width {
+            let nx = (x as f32 / width as f32) * 2.0 - 1.0;
+            let ny = (y as f32 / height as f32) * 2.0 - 1.0;
+            let (warped_x, warped_y) = sampler.warped(nx, ny);
+            elevation.push(sampler.octaves(warped_x, warped_y));
+            moisture.push(sampler.moisture(warped_x, warped_y));
+        }
+    }
+    RawNoise {
+        elevation,
+        moisture,
+    }
+}
+
+pub(crate) fn clear() {
+    CACHE.with(|cache| cache.borrow_mut().take());
+}
+
+/// Frees the noise kept from the last generation. Generating again with the
+/// same seed, size, `warp_strength`, and `warp_mode` reuses that noise and
+/// skips straight to shaping it, so editors that only move sea level,
+/// amplitude, moisture scale, or erosion regenerate quickly; call this when
+/// done to release the memory.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn clear_cache() {
+    clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::{clear, raw_noise};
+    use crate::{generate, FieldSampler, GenerationSettings};
+
+    #[test]
+    fn noise_is_reused_until_an_input_changes() {
+        let settings = GenerationSettings::default();
+        let sampler = FieldSampler::new(&settings);
+        let first = raw_noise(32, 32, &settings, &sampler);
+        let downstream = GenerationSettings {
+            sea_level: 0.5,
+            elevation_amplitude: 1.4,
+            moisture_scale: 0.6,
+            ..settings.clone()
+        };
+        assert!(Rc::ptr_eq(
+            &first,
+            &raw_noise(32, 32, &downstream, &sampler)
+        ));
+        let reseeded = GenerationSettings {
+            seed: 1,
+            ..settings.clone()
+        };
+        let other = raw_noise(32, 32, &reseeded, &FieldSampler::new(&reseeded));
+        assert!(!Rc::ptr_eq(&first, &other));
+        assert!(!Rc::ptr_eq(
+            &other,
+            &raw_noise(32, 16, &reseeded, &FieldSampler::new(&reseeded))
+        ));
+
+        // Cached and fresh noise give the same map.
+        clear();
+        let fresh = generate(48, 48, &downstream, None);
+        let cached = generate(48, 48, &downstream, None);
+        assert_eq!(cached.heightmap, fresh.heightmap);
+        assert_eq!(cached.moisture, fresh.moisture);
+    }
+}
@@ -0,0 +1,308 @@
+use crate::thermal::{hardness_sampler, talus_limits, ErosionMode, LEGACY_STEP};
+use crate::thumbnails::sample_preview_fields;
+use crate::{
+    apply_shores_by, cell_water, classify_cell, enhanced_moisture, GenerationSettings, DIRECTIONS,
+};
+
+/// Largest side an overview runs at; past it the 256 height levels show as
+/// terraces.
+pub(crate) const OVERVIEW_MAX_SIZE: u32 = 256;
+
+/// Steps a normalized 0..1 value is quantized to.
+const LEVELS: f32 = 255.0;
+/// Fractional bits of the fixed-point levels talus erosion works in.
+const FRACTION_BITS: u32 = 8;
+
+pub(crate) fn quantize(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * LEVELS).round() as u8
+}
+
+pub(crate) fn level(value: u8) -> f32 {
+    value as f32 / LEVELS
+}
+
+/// Buffers for the overview pipeline: every layer a byte per cell, plus
+/// flow counts and their processing order when rivers are traced.
+pub(crate) struct OverviewScratch {
+    pub heightmap: Vec<u8>,
+    pub moisture: Vec<u8>,
+    pub temperature: Vec<u8>,
+    pub water: Vec<u8>,
+    pub biome: Vec<u8>,
+    /// Erosion's second buffer.
+    spare: Vec<u8>,
+    /// Quantized `hardness_field` for talus erosion.
+    hardness: Vec<u8>,
+    flow: Vec<u16>,
+    order: Vec<u32>,
+}
+
+impl OverviewScratch {
+    pub(crate) fn new(size: usize) -> Self {
+        Self {
+            heightmap: vec![0; size],
+            moisture: vec![0; size],
+            temperature: vec![0; size],
+            water: vec![0; size],
+            biome: vec![0; size],
+            spare: Vec::new(),
+            hardness: Vec::new(),
+            flow: Vec::new(),
+            order: Vec::new(),
+        }
+    }
+}
+
+/// `preview_layers` over byte layers: noise is quantized as it is sampled,
+/// and erosion, flow routing, and the water cut run on the quantized
+/// heights. Not bit-identical to the float pipeline, but deterministic and
+/// close; biomes agree on most cells.
+pub(crate) fn overview_layers(
+    size: u32,
+    settings: &GenerationSettings,
+    flow: bool,
+    scratch: &mut OverviewScratch,
+) {
+    let side = size as usize;
+    let sea_level = settings.sea_level;
+    sample_preview_fields(side, settings, |index, elevation, moisture, heat| {
+        scratch.heightmap[index] = quantize(elevation);
+        scratch.moisture[index] = quantize(moisture);
+        scratch.temperature[index] = quantize(heat);
+    });
+    erode(scratch, side, settings);
+
+    let max_flow = if flow {
+        trace_flow(scratch, side);
+        let max_flow = scratch.flow.iter().copied().max().unwrap_or(0) as f32;
+        for (index, water) in scratch.water.iter_mut().enumerate() {
+            let elevation = level(scratch.heightmap[index]);
+            let cell_flow = scratch.flow[index] as f32;
+            *water = quantize(cell_water(elevation, cell_flow, max_flow, sea_level));
+        }
+        max_flow
+    } else {
+        for (water, &elevation) in scratch.water.iter_mut().zip(&scratch.heightmap) {
+            *water = if level(elevation) <= sea_level {
+                255
+            } else {
+                0
+            };
+        }
+        0.0
+    };
+
+    let OverviewScratch {
+        heightmap,
+        moisture,
+        temperature,
+        water,
+        biome,
+        flow: flows,
+        ..
+    } = scratch;
+    for index in 0..side * side {
+        let cell_flow = flows.get(index).map_or(0.0, |&flow| flow as f32);
+        let water = level(water[index]);
+        let moist = enhanced_moisture(level(moisture[index]), water, cell_flow, max_flow, settings);
+        biome[index] = classify_cell(
+            level(heightmap[index]),
+            water,
+            level(temperature[index]),
+            moist,
+            sea_level,
+            &settings.biome_table,
+        )
+        .code();
+    }
+    apply_shores_by(
+        |index| level(heightmap[index]),
+        biome,
+        side,
+        side,
+        sea_level,
+        settings,
+        0..side * side,
+    );
+}
+
+/// Thermal erosion on quantized heights, following `settings.erosion_mode`
+/// with the float thresholds carried over into levels.
+fn erode(scratch: &mut OverviewScratch, side: usize, settings: &GenerationSettings) {
+    if settings.erosion_iterations == 0 || side < 3 {
+        return;
+    }
+    let OverviewScratch {
+        heightmap,
+        spare,
+        hardness,
+        ..
+    } = scratch;
+    spare.resize(heightmap.len(), 0);
+    hardness.clear();
+    if settings.erosion_mode == ErosionMode::Talus {
+        if let Some(sample) = hardness_sampler(side as u32, side as u32, settings) {
+            hardness.extend((0..side * side).map(|index| quantize(sample(index))));
+        }
+    }
+    let limits = talus_limits(side as u32, side as u32, settings)
+        .map(|limit| (limit * LEVELS * (1 << FRACTION_BITS) as f32).round() as i32);
+    let transfer = (settings.thermal_transfer.clamp(0.0, 1.0) * 256.0) as i32;
+    let step = (LEGACY_STEP * LEVELS).round() as i32;
+    let neighbor =
+        |index: usize, (dx, dy): (i32, i32)| (index as i32 + dy * side as i32 + dx) as usize;
+
+    for _ in 0..settings.erosion_iterations {
+        spare.copy_from_slice(heightmap);
+        for y in 1..side - 1 {
+            for x in 1..side - 1 {
+                let index = y * side + x;
+                let center = heightmap[index] as i32;
+                match settings.erosion_mode {
+                    ErosionMode::Legacy => {
+                        let (mut total, mut count) = (center, 1);
+                        for direction in DIRECTIONS {
+                            let below = heightmap[neighbor(index, direction)] as i32;
+                            if center - below > step {
+                                total += below + (center - below) / 2;
+                                count += 1;
+                            }
+                        }
+                        spare[index] = ((total + count / 2) / count) as u8;
+                    }
+                    ErosionMode::Talus => {
+                        let mut excess = [0i32; 8];
+                        for (slot, direction) in DIRECTIONS.into_iter().enumerate() {
+                            let below = heightmap[neighbor(index, direction)] as i32;
+                            excess[slot] =
+                                ((center - below) << FRACTION_BITS).saturating_sub(limits[slot]);
+                        }
+                        let excess = excess.map(|excess| excess.max(0));
+                        let total: i32 = excess.iter().sum();
+                        let (steepest_slot, &steepest) = excess
+                            .iter()
+                            .enumerate()
+                            .max_by_key(|&(slot, excess)| (*excess, -(slot as i32)))
+                            .expect("eight neighbours");
+                        if total == 0 {
+                            continue;
+                        }
+                        let softness = 255 - hardness.get(index).map_or(0, |&h| h as i32);
+                        let moved = ((steepest as i64 * transfer as i64 * softness as i64
+                            / (2 * 256 * 255))
+                            >> FRACTION_BITS) as i32;
+                        if moved == 0 {
+                            continue;
+                        }
+                        spare[index] = (spare[index] as i32 - moved) as u8;
+                        // Shares round down; the steepest neighbour takes the
+                        // rest so no height is lost.
+                        let mut given = 0;
+                        for (slot, direction) in DIRECTIONS.into_iter().enumerate() {
+                            let share = (moved as i64 * excess[slot] as i64 / total as i64) as i32;
+                            let target = neighbor(index, direction);
+                            spare[target] = spare[target].saturating_add(share as u8);
+                            given += share;
+                        }
+                        let target = neighbor(index, DIRECTIONS[steepest_slot]);
+                        spare[target] = spare[target].saturating_add((moved - given) as u8);
+                    }
+                }
+            }
+        }
+        heightmap.copy_from_slice(spare);
+    }
+}
+
+/// Flow accumulation over quantized heights. Cells are ordered highest
+/// first by a counting sort over the 256 levels, ties by index. Flow moves
+/// to the lowest lower neighbour or, on a flat, to an equal neighbour further
+/// along, so that order visits every cell before its receiver; quantization
+/// makes flats common, and stopping at each one would starve the rivers.
+/// Counts saturate at `u16::MAX`.
+fn trace_flow(scratch: &mut OverviewScratch, side: usize) {
+    let OverviewScratch {
+        heightmap,
+        flow,
+        order,
+        ..
+    } = scratch;
+    let mut starts = [0u32; 257];
+    for &height in heightmap.iter() {
+        starts[255 - height as usize + 1] += 1;
+    }
+    for level in 1..starts.len() {
+        starts[level] += starts[level - 1];
+    }
+    order.resize(heightmap.len(), 0);
+    for (index, &height) in heightmap.iter().enumerate() {
+        let slot = &mut starts[255 - height as usize];
+        order[*slot as usize] = index as u32;
+        *slot += 1;
+    }
+
+    flow.clear();
+    flow.resize(heightmap.len(), 1);
+    for &cell in order.iter() {
+        let cell = cell as usize;
+        let (x, y) = ((cell % side) as i32, (cell / side) as i32);
+        let mut lowest = (heightmap[cell], None);
+        let mut level_with = None;
+        for (dx, dy) in DIRECTIONS {
+            let (nx, ny) = (x + dx, y + dy);
+            if nx < 0 || ny < 0 || nx >= side as i32 || ny >= side as i32 {
+                continue;
+            }
+            let next = ny as usize * side + nx as usize;
+            if heightmap[next] < lowest.0 {
+                lowest = (heightmap[next], Some(next));
+            } else if heightmap[next] == heightmap[cell] && next > cell && level_with.is_none() {
+                level_with = Some(next);
+            }
+        }
+        if let Some(next) = lowest.1.or(level_with) {
+            flow[next] = flow[next].saturating_add(flow[cell]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{overview_layers, OverviewScratch};
+    use crate::thermal::ErosionMode;
+    use crate::thumbnails::{preview_layers, ThumbnailScratch};
+    use crate::GenerationSettings;
+
+    #[test]
+    fn overviews_are_deterministic_and_close_to_float_previews() {
+        const SIZE: u32 = 128;
+        let cells = (SIZE * SIZE) as usize;
+        for (seed, erosion_mode, flow) in [
+            (3, ErosionMode::Talus, false),
+            (11, ErosionMode::Talus, true),
+            (29, ErosionMode::Legacy, true),
+        ] {
+            let settings = GenerationSettings {
+                erosion_mode,
+                ..GenerationSettings::new(seed, 0.42, 1.0, 40.0, 3, 1.0)
+            };
+            let mut float = ThumbnailScratch::new(cells);
+            preview_layers(SIZE, &settings, flow, &mut float);
+            let mut first = OverviewScratch::new(cells);
+            overview_layers(SIZE, &settings, flow, &mut first);
+            let mut second = OverviewScratch::new(cells);
+            overview_layers(SIZE, &settings, flow, &mut second);
+            assert_eq!(first.heightmap, second.heightmap);
+            assert_eq!(first.biome, second.biome);
+
+            let agreeing = first
+                .biome
+                .iter()
+                .zip(&float.biome)
+                .filter(|(a, b)| a == b)
+                .count();
+            let agreement = agreeing as f32 / cells as f32;
+            assert!(agreement >= 0.88, "seed {seed}: {agreement}");
+        }
+    }
+}
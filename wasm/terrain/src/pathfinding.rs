@@ -0,0 +1,436 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+#[cfg(feature = "wasm")]
+use js_sys::Float32Array;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::movement::Profile;
+use crate::{MapResult, DIRECTIONS, REGION_SIZE};
+
+const DEFAULT_MAX_NODES: u32 = 250_000;
+const DEFAULT_SNAP_RADIUS: u32 = 4;
+
+#[derive(PartialEq)]
+pub(crate) struct Frontier {
+    pub priority: f32,
+    pub index: usize,
+}
+
+impl Eq for Frontier {}
+
+impl Ord for Frontier {
+    // Reversed so the max-heap pops the lowest priority, ties by lowest index.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .priority
+            .total_cmp(&self.priority)
+            .then(other.index.cmp(&self.index))
+    }
+}
+
+impl PartialOrd for Frontier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A* over an 8-connected cost field. Steps cost the mean of both cells'
+/// multipliers times the world-space step length. Returns the cell path, or
+/// `None` when the goal is unreachable or `max_nodes` expansions run out.
+pub(crate) fn astar(
+    costs: &[f32],
+    width: usize,
+    height: usize,
+    start: usize,
+    goal: usize,
+    max_nodes: u32,
+) -> Option<Vec<usize>> {
+    astar_swimming(costs, None, width, height, start, goal, max_nodes)
+}
+
+/// Cells that must not run longer than `max` in a row on a path.
+pub(crate) struct SwimLimit<'a> {
+    pub cells: &'a [bool],
+    pub max: u32,
+}
+
+/// Best cost and predecessor per search state. Run-0 states are dense like
+/// the cell grid; swimming states only exist on swum cells, so they live in
+/// a map and a search without swimming allocates nothing extra.
+struct States {
+    cells: usize,
+    land: Vec<(f32, usize)>,
+    swimming: HashMap<usize, (f32, usize)>,
+}
+
+impl States {
+    fn new(cells: usize) -> Self {
+        States {
+            cells,
+            land: vec![(f32::INFINITY, usize::MAX); cells],
+            swimming: HashMap::new(),
+        }
+    }
+
+    fn get(&self, state: usize) -> (f32, usize) {
+        if state < self.cells {
+            self.land[state]
+        } else {
+            self.swimming
+                .get(&state)
+                .copied()
+                .unwrap_or((f32::INFINITY, usize::MAX))
+        }
+    }
+
+    fn set(&mut self, state: usize, entry: (f32, usize)) {
+        if state < self.cells {
+            self.land[state] = entry;
+        } else {
+            self.swimming.insert(state, entry);
+        }
+    }
+}
+
+/// `astar` where no path may cross more than `swim.max` consecutive
+/// `swim.cells`. The search runs over `(cell, cells swum so far)` states, so
+/// a cheap crossing too wide to swim gives way to a longer route, and a
+/// path may rest on land between swims.
+pub(crate) fn astar_swimming(
+    costs: &[f32],
+    swim: Option<SwimLimit>,
+    width: usize,
+    height: usize,
+    start: usize,
+    goal: usize,
+    max_nodes: u32,
+) -> Option<Vec<usize>> {
+    let cells = costs.len();
+    let cell_w = REGION_SIZE / width as f32;
+    let cell_h = REGION_SIZE / height as f32;
+    let min_cost = costs
+        .iter()
+        .copied()
+        .filter(|cost| cost.is_finite())
+        .fold(f32::INFINITY, f32::min);
+    if !min_cost.is_finite() || !costs[start].is_finite() || !costs[goal].is_finite() {
+        return None;
+    }
+    let swum = |index: usize| swim.as_ref().is_some_and(|swim| swim.cells[index]);
+    let max_swim = swim.as_ref().map_or(0, |swim| swim.max as usize);
+    if swum(start) && max_swim == 0 {
+        return None;
+    }
+    let (goal_x, goal_y) = ((goal % width) as f32, (goal / width) as f32);
+    let heuristic = |index: usize| {
+        let dx = ((index % width) as f32 - goal_x) * cell_w;
+        let dy = ((index / width) as f32 - goal_y) * cell_h;
+        (dx * dx + dy * dy).sqrt() * min_cost
+    };
+
+    // State `run * cells + index`: standing on `index` after `run` swum
+    // cells in a row.
+    let mut states = States::new(cells);
+    let mut heap = BinaryHeap::new();
+    let first = swum(start) as usize * cells + start;
+    states.set(first, (0.0, usize::MAX));
+    heap.push(Frontier {
+        priority: heuristic(start),
+        index: first,
+    });
+
+    let mut expanded = 0u32;
+    while let Some(Frontier {
+        priority,
+        index: state,
+    }) = heap.pop()
+    {
+        let (run, index) = (state / cells, state % cells);
+        if index == goal {
+            let mut path = vec![goal];
+            let mut current = state;
+            while current != first {
+                current = states.get(current).1;
+                path.push(current % cells);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        let cost = states.get(state).0;
+        if priority > cost + heuristic(index) {
+            continue;
+        }
+        expanded += 1;
+        if expanded > max_nodes {
+            return None;
+        }
+        let x = (index % width) as i32;
+        let y = (index / width) as i32;
+        for (dx, dy) in DIRECTIONS {
+            let nx = x + dx;
+            let ny = y + dy;
+            if nx < 0 || ny < 0 || nx >= width as i32 || ny >= height as i32 {
+                continue;
+            }
+            let next = ny as usize * width + nx as usize;
+            if !costs[next].is_finite() {
+                continue;
+            }
+            let next_run = if swum(next) { run + 1 } else { 0 };
+            if next_run > max_swim {
+                continue;
+            }
+            let next_state = next_run * cells + next;
+            let step = ((dx as f32 * cell_w).powi(2) + (dy as f32 * cell_h).powi(2)).sqrt();
+            let candidate = cost + (costs[index] + costs[next]) * 0.5 * step;
+            if candidate < states.get(next_state).0 {
+                states.set(next_state, (candidate, state));
+                heap.push(Frontier {
+                    priority: candidate + heuristic(next),
+                    index: next_state,
+                });
+            }
+        }
+    }
+    None
+}
+
+/// Multi-source Dijkstra over the same step costs as `astar`, lowering
+/// `distance` wherever a path from `sources` beats it and marking those cells
+/// with `label` in `owner`. Passing existing fields adds sources
+/// incrementally; unreachable cells keep their values.
+pub(crate) fn relax_distances(
+    costs: &[f32],
+    width: usize,
+    height: usize,
+    sources: &[usize],
+    label: u32,
+    distance: &mut [f32],
+    owner: &mut [u32],
+) {
+    let cell_w = REGION_SIZE / width as f32;
+    let cell_h = REGION_SIZE / height as f32;
+    let mut heap = BinaryHeap::new();
+    for &source in sources {
+        if costs[source].is_finite() {
+            distance[source] = 0.0;
+            owner[source] = label;
+            heap.push(Frontier {
+                priority: 0.0,
+                index: source,
+            });
+        }
+    }
+    while let Some(Frontier { priority, index }) = heap.pop() {
+        if priority > distance[index] {
+            continue;
+        }
+        let x = (index % width) as i32;
+        let y = (index / width) as i32;
+        for (dx, dy) in DIRECTIONS {
+            let nx = x + dx;
+            let ny = y + dy;
+            if nx < 0 || ny < 0 || nx >= width as i32 || ny >= height as i32 {
+                continue;
+            }
+            let next = ny as usize * width + nx as usize;
+            if !costs[next].is_finite() {
+                continue;
+            }
+            let step = ((dx as f32 * cell_w).powi(2) + (dy as f32 * cell_h).powi(2)).sqrt();
+            let candidate = priority + (costs[index] + costs[next]) * 0.5 * step;
+            if candidate < distance[next] {
+                distance[next] = candidate;
+                owner[next] = label;
+                heap.push(Frontier {
+                    priority: candidate,
+                    index: next,
+                });
+            }
+        }
+    }
+}
+
+/// Nearest passable cell to `(x, y)` within `radius` cells, by Euclidean cell
+/// distance with ties broken by index.
+pub(crate) fn snap_to_passable(
+    costs: &[f32],
+    width: usize,
+    height: usize,
+    x: usize,
+    y: usize,
+    radius: u32,
+) -> Option<usize> {
+    let radius = radius as i64;
+    let mut best: Option<(i64, usize)> = None;
+    for dy in -radius..=radius {
+        for dx in -radius..=radius {
+            let nx = x as i64 + dx;
+            let ny = y as i64 + dy;
+            if nx < 0 || ny < 0 || nx >= width as i64 || ny >= height as i64 {
+                continue;
+            }
+            let index = ny as usize * width + nx as usize;
+            let distance = dx * dx + dy * dy;
+            if distance > radius * radius || !costs[index].is_finite() {
+                continue;
+            }
+            if best.is_none_or(|(d, i)| distance < d || (distance == d && index < i)) {
+                best = Some((distance, index));
+            }
+        }
+    }
+    best.map(|(_, index)| index)
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Cheapest path between two world positions as interleaved world `x, y`
+    /// waypoints, one per cell. `options.profile` is a movement profile as
+    /// accepted by `movement_cost` (default `"foot"`); the older
+    /// `options.water` of `"impassable"` or `"cheap"` still selects `"foot"`
+    /// or `"boat"`. `options.max_nodes` caps A* expansions and
+    /// `options.snap_radius` (cells) lets endpoints in impassable cells move
+    /// to passable ground. Paths never swim more than the profile's
+    /// `max_swim` cells in a row. Returns `null` when no path is found.
+    pub fn find_path(
+        &self,
+        x0: f32,
+        y0: f32,
+        x1: f32,
+        y1: f32,
+        options: JsValue,
+    ) -> Result<Option<Float32Array>, JsValue> {
+        let profile = match (
+            js::get(&options, "profile"),
+            js::get_string(&options, "water"),
+        ) {
+            (Some(profile), _) => Profile::from_js(&profile)?,
+            (None, None) => Profile::preset("foot").expect("foot is a preset"),
+            (None, Some(water)) => match water.as_str() {
+                "impassable" => Profile::preset("foot").expect("foot is a preset"),
+                "cheap" => Profile::preset("boat").expect("boat is a preset"),
+                other => {
+                    return Err(JsValue::from_str(&format!(
+                        "water must be \"impassable\" or \"cheap\", got {other}"
+                    )))
+                }
+            },
+        };
+        let max_nodes = js::get_u32(&options, "max_nodes", DEFAULT_MAX_NODES);
+        let snap_radius = js::get_u32(&options, "snap_radius", DEFAULT_SNAP_RADIUS);
+        Ok(self
+            .find_path_cells((x0, y0), (x1, y1), &profile, max_nodes, snap_radius)
+            .map(|path| {
+                let points: Vec<f32> = path
+                    .into_iter()
+                    .flat_map(|index| {
+                        let (x, y) = self.cell_to_world(index);
+                        [x, y]
+                    })
+                    .collect();
+                Float32Array::from(points.as_slice())
+            }))
+    }
+}
+
+impl MapResult {
+    pub(crate) fn find_path_cells(
+        &self,
+        from: (f32, f32),
+        to: (f32, f32),
+        profile: &Profile,
+        max_nodes: u32,
+        snap_radius: u32,
+    ) -> Option<Vec<usize>> {
+        let width = self.width as usize;
+        let height = self.height as usize;
+        let field = self.movement_field(profile);
+        let costs = &field.costs;
+        let (sx, sy) = self.nearest_cell(from.0, from.1);
+        let (gx, gy) = self.nearest_cell(to.0, to.1);
+        let start = snap_to_passable(costs, width, height, sx, sy, snap_radius)?;
+        let goal = snap_to_passable(costs, width, height, gx, gy, snap_radius)?;
+        let swim = SwimLimit {
+            cells: &field.swimming,
+            max: profile.max_swim,
+        };
+        astar_swimming(costs, Some(swim), width, height, start, goal, max_nodes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::{astar, snap_to_passable};
+    use crate::generate_map;
+    use crate::movement::{build_cost_field, Profile};
+
+    #[test]
+    fn searches_give_up_when_the_node_budget_runs_out() {
+        let (width, height) = (32, 32);
+        let costs = vec![1.0; width * height];
+        let goal = width * height - 1;
+        let path = astar(&costs, width, height, 0, goal, 10_000).unwrap();
+        assert_eq!(path.len(), width);
+        assert_eq!((path[0], path[path.len() - 1]), (0, goal));
+        assert!(astar(&costs, width, height, 0, goal, 8).is_none());
+    }
+
+    #[test]
+    fn snapping_takes_the_nearest_passable_cell_within_the_radius() {
+        let (width, height) = (9, 9);
+        let mut costs = vec![f32::INFINITY; width * height];
+        // Two cells two steps from the center, tied and so broken by index,
+        // and a diagonal one farther out.
+        costs[4 * width + 6] = 1.0;
+        costs[6 * width + 4] = 1.0;
+        costs[6 * width + 6] = 1.0;
+        assert_eq!(snap_to_passable(&costs, width, height, 4, 4, 1), None);
+        assert_eq!(
+            snap_to_passable(&costs, width, height, 4, 4, 2),
+            Some(4 * width + 6)
+        );
+        assert_eq!(
+            snap_to_passable(&costs, width, height, 6, 6, 0),
+            Some(6 * width + 6)
+        );
+        costs[4 * width + 4] = 1.0;
+        assert_eq!(
+            snap_to_passable(&costs, width, height, 4, 4, 2),
+            Some(4 * width + 4)
+        );
+    }
+
+    #[test]
+    fn preset_cost_fields_are_cached_until_the_roads_change() {
+        let mut map = generate_map(48, 48, 5, 0.42, 1.0, 40.0, 2, 1.0);
+        let foot = Profile::preset("foot").unwrap();
+        let Cow::Borrowed(first) = map.movement_field(&foot) else {
+            panic!("presets are cached");
+        };
+        let Cow::Borrowed(again) = map.movement_field(&foot) else {
+            panic!("presets are cached");
+        };
+        assert!(std::ptr::eq(first, again));
+        let custom = Profile {
+            slope: foot.slope + 1.0,
+            ..foot.clone()
+        };
+        assert!(matches!(map.movement_field(&custom), Cow::Owned(_)));
+
+        assert!(!map.road_graph.is_empty());
+        let roads = map.movement_field(&foot).costs.clone();
+        map.road_graph.clear();
+        map.invalidate_settlements();
+        let field = map.movement_field(&foot);
+        assert_ne!(field.costs, roads);
+        assert_eq!(field.costs, build_cost_field(&map, &foot));
+    }
+}
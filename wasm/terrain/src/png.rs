@@ -0,0 +1,157 @@
+/// Largest payload of one stored deflate block.
+const STORED_BLOCK: usize = 65_535;
+
+/// Encodes an 8-bit RGBA buffer of `width * height * 4` bytes as a PNG.
+/// The image data is wrapped in stored (uncompressed) deflate blocks, which
+/// every decoder accepts; the file is about the size of the raw pixels, so
+/// recompress it if size matters.
+pub(crate) fn encode_rgba(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    let stride = width as usize * 4;
+    debug_assert_eq!(rgba.len(), stride * height as usize);
+    // Each scanline starts with filter type 0 (none).
+    let mut scanlines = Vec::with_capacity(rgba.len() + height as usize);
+    for row in rgba.chunks(stride.max(1)).take(height as usize) {
+        scanlines.push(0);
+        scanlines.extend_from_slice(row);
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // Bit depth 8, color type 6 (RGBA), deflate, adaptive filtering, no
+    // interlace.
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    let mut png = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"IDAT", &zlib_stored(&scanlines));
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// A zlib stream holding `data` in stored deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let blocks = data.len().div_ceil(STORED_BLOCK).max(1);
+    let mut stream = Vec::with_capacity(data.len() + blocks * 5 + 6);
+    // Deflate with a 32 KiB window, no preset dictionary, fastest level.
+    stream.extend_from_slice(&[0x78, 0x01]);
+    let mut chunks = data.chunks(STORED_BLOCK).peekable();
+    if chunks.peek().is_none() {
+        stream.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(chunk) = chunks.next() {
+        let last = chunks.peek().is_none();
+        let length = chunk.len() as u16;
+        stream.push(last as u8);
+        stream.extend_from_slice(&length.to_le_bytes());
+        stream.extend_from_slice(&(!length).to_le_bytes());
+        stream.extend_from_slice(chunk);
+    }
+    stream.extend_from_slice(&adler32(data).to_be_bytes());
+    stream
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    const MODULUS: u32 = 65_521;
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 bytes is the most that can be summed before `b` could overflow.
+    for chunk in bytes.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MODULUS;
+        b %= MODULUS;
+    }
+    (b << 16) | a
+}
+
+/// Reads back a PNG written by `encode_rgba`, checking every checksum
+/// along the way.
+#[cfg(test)]
+pub(crate) fn decode_stored(png: &[u8]) -> (u32, u32, Vec<u8>) {
+    let be = |at: usize| u32::from_be_bytes(png[at..at + 4].try_into().unwrap());
+    let (width, height) = (be(16), be(20));
+    let mut stream = Vec::new();
+    let mut at = 8;
+    while at < png.len() {
+        let length = be(at) as usize;
+        assert_eq!(be(at + 8 + length), crc32(&png[at + 4..at + 8 + length]));
+        if &png[at + 4..at + 8] == b"IDAT" {
+            stream.extend_from_slice(&png[at + 8..at + 8 + length]);
+        }
+        at += 12 + length;
+    }
+
+    let mut scanlines = Vec::new();
+    let mut at = 2;
+    loop {
+        let last = stream[at] & 1 == 1;
+        let length = u16::from_le_bytes([stream[at + 1], stream[at + 2]]) as usize;
+        assert!(length <= STORED_BLOCK);
+        scanlines.extend_from_slice(&stream[at + 5..at + 5 + length]);
+        at += 5 + length;
+        if last {
+            break;
+        }
+    }
+    assert_eq!(
+        u32::from_be_bytes(stream[at..at + 4].try_into().unwrap()),
+        adler32(&scanlines)
+    );
+    let mut rgba = Vec::new();
+    for row in scanlines.chunks(width as usize * 4 + 1) {
+        assert_eq!(row[0], 0);
+        rgba.extend_from_slice(&row[1..]);
+    }
+    (width, height, rgba)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{adler32, crc32, decode_stored, encode_rgba};
+
+    #[test]
+    fn checksums_and_layout_match_the_spec() {
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+
+        // Large enough to need two stored blocks.
+        let (width, height) = (200u32, 90u32);
+        let pixels: Vec<u8> = (0..width * height * 4).map(|i| (i % 251) as u8).collect();
+        let png = encode_rgba(width, height, &pixels);
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[16..20], &width.to_be_bytes());
+        assert_eq!(&png[20..24], &height.to_be_bytes());
+        assert_eq!(&png[png.len() - 12..], b"\0\0\0\0IEND\xae\x42\x60\x82");
+
+        let (decoded_width, decoded_height, decoded) = decode_stored(&png);
+        assert_eq!((decoded_width, decoded_height), (width, height));
+        assert_eq!(decoded, pixels);
+    }
+}
@@ -0,0 +1,131 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::contour::flat_points;
+use crate::json::Json;
+use crate::registry::FeatureClass;
+use crate::ruins::RuinReason;
+use crate::MapResult;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PoiKind {
+    /// A settlement abandoned in an earlier era.
+    Ruin,
+    /// Desert spring fed by groundwater.
+    Oasis,
+    /// Warm spring along a fault.
+    HotSpring,
+    /// Erupting spring on strongly active faults.
+    Geyser,
+    /// Steam vent on high ground along a fault.
+    Fumarole,
+    /// Hilltop tower watching roads and borders.
+    Watchtower,
+    /// Light on a coastal headland watching the sea lanes.
+    Lighthouse,
+}
+
+/// Every POI kind, in declaration order.
+pub(crate) const POI_KINDS: [PoiKind; 7] = [
+    PoiKind::Ruin,
+    PoiKind::Oasis,
+    PoiKind::HotSpring,
+    PoiKind::Geyser,
+    PoiKind::Fumarole,
+    PoiKind::Watchtower,
+    PoiKind::Lighthouse,
+];
+
+impl PoiKind {
+    pub(crate) fn key(self) -> &'static str {
+        match self {
+            PoiKind::Ruin => "ruin",
+            PoiKind::Oasis => "oasis",
+            PoiKind::HotSpring => "hot_spring",
+            PoiKind::Geyser => "geyser",
+            PoiKind::Fumarole => "fumarole",
+            PoiKind::Watchtower => "watchtower",
+            PoiKind::Lighthouse => "lighthouse",
+        }
+    }
+
+    /// Priority in the point-feature registry; see `register_poi`.
+    pub(crate) fn class(self) -> FeatureClass {
+        match self {
+            PoiKind::Ruin | PoiKind::Watchtower | PoiKind::Lighthouse => FeatureClass::Poi,
+            PoiKind::Oasis | PoiKind::HotSpring | PoiKind::Geyser | PoiKind::Fumarole => {
+                FeatureClass::Micro
+            }
+        }
+    }
+}
+
+/// A point of interest in world coordinates.
+#[derive(Clone)]
+pub(crate) struct Poi {
+    pub id: u32,
+    pub kind: PoiKind,
+    pub x: f32,
+    pub y: f32,
+    /// Era the underlying feature was founded in.
+    pub era: u32,
+    /// Why a ruin was abandoned; `None` for other kinds.
+    pub reason: Option<RuinReason>,
+    /// What a lookout watches; `None` for other kinds.
+    pub coverage: Option<Coverage>,
+}
+
+/// Targets a lookout adds to the watched set.
+#[derive(Clone)]
+pub(crate) struct Coverage {
+    /// World area of the targets below.
+    pub area: f32,
+    /// One world point per watched target block.
+    pub targets: Vec<(f32, f32)>,
+}
+
+impl Poi {
+    /// Field list shared by the `pois()` getter and the table exporter.
+    pub(crate) fn record(&self) -> Vec<(&'static str, Json)> {
+        vec![
+            ("id", self.id.into()),
+            ("kind", self.kind.key().into()),
+            ("x", self.x.into()),
+            ("y", self.y.into()),
+            ("era", self.era.into()),
+            (
+                "reason",
+                self.reason.map_or(Json::Null, |reason| reason.key().into()),
+            ),
+            (
+                "coverage",
+                self.coverage
+                    .as_ref()
+                    .map_or(Json::Null, |coverage| coverage.area.into()),
+            ),
+            (
+                "targets",
+                self.coverage
+                    .as_ref()
+                    .map_or(Json::Null, |coverage| flat_points(&coverage.targets)),
+            ),
+        ]
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Points of interest as `{ id, kind, x, y, era, reason, coverage,
+    /// targets }`; `reason` is set on ruins only, and `coverage` and
+    /// `targets` on watchtowers and lighthouses only (see `place_lookouts`).
+    /// The POI entries of `features()`.
+    pub fn pois(&self) -> JsValue {
+        let records = self
+            .pois
+            .iter()
+            .map(|poi| Json::from_record(poi.record()))
+            .collect::<Vec<_>>();
+        Json::from(records).to_js()
+    }
+}
@@ -0,0 +1,138 @@
+#[cfg(feature = "wasm")]
+use js_sys::Float32Array;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::{slope_map, MapResult, Settlement, REGION_SIZE};
+
+pub(crate) struct PopulationOptions {
+    /// Inhabitants per unit of settlement size.
+    pub people_per_size: f32,
+    /// Kernel standard deviation per unit of settlement size, in world units.
+    pub radius_per_size: f32,
+    /// Extra kernel weight next to roads, fading over `road_width`.
+    pub road_boost: f32,
+    pub road_width: f32,
+    /// Extra kernel weight near rivers, scaled by moisture and fading over
+    /// `valley_width`.
+    pub valley_boost: f32,
+    pub valley_width: f32,
+    /// Cells steeper than this are uninhabitable, like water bodies.
+    pub max_slope: f32,
+}
+
+impl Default for PopulationOptions {
+    fn default() -> Self {
+        Self {
+            people_per_size: 500.0,
+            radius_per_size: 48.0,
+            road_boost: 1.0,
+            road_width: 48.0,
+            valley_boost: 1.0,
+            valley_width: 96.0,
+            max_slope: 0.2,
+        }
+    }
+}
+
+impl PopulationOptions {
+    /// Inhabitants a settlement spreads over the density layer.
+    pub(crate) fn people(&self, settlement: &Settlement) -> f32 {
+        settlement.size * self.people_per_size
+    }
+
+    #[cfg(feature = "wasm")]
+    fn from_js(options: &JsValue) -> Self {
+        let defaults = Self::default();
+        Self {
+            people_per_size: js::get_f32(options, "people_per_size", defaults.people_per_size),
+            radius_per_size: js::get_f32(options, "radius_per_size", defaults.radius_per_size),
+            road_boost: js::get_f32(options, "road_boost", defaults.road_boost),
+            road_width: js::get_f32(options, "road_width", defaults.road_width),
+            valley_boost: js::get_f32(options, "valley_boost", defaults.valley_boost),
+            valley_width: js::get_f32(options, "valley_width", defaults.valley_width),
+            max_slope: js::get_f32(options, "max_slope", defaults.max_slope),
+        }
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Inhabitants per square world unit. Each settlement spreads
+    /// `size * people_per_size` people over a Gaussian of standard deviation
+    /// `size * radius_per_size`, weighted toward roads (`road_boost`,
+    /// `road_width`) and moist river valleys (`valley_boost`, `valley_width`).
+    /// Water bodies and cells steeper than `max_slope` hold no one.
+    pub fn population_density(&self, options: JsValue) -> Float32Array {
+        let density = population_density(self, &PopulationOptions::from_js(&options));
+        Float32Array::from(density.as_slice())
+    }
+}
+
+/// Density layer from the current settlements, so edits show up on the next
+/// call. Each settlement's kernel is normalized over its habitable cells, so
+/// the layer integrates to the total population unless a settlement has no
+/// habitable ground within reach.
+pub(crate) fn population_density(map: &MapResult, options: &PopulationOptions) -> Vec<f32> {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let slopes = slope_map(&map.heightmap, width, height);
+    let road_distance = map.road_distance();
+    let river_distance = map.river_distance();
+    let affinity: Vec<f32> = (0..map.heightmap.len())
+        .map(|index| {
+            if map.is_water_body(index) || slopes[index] > options.max_slope {
+                return 0.0;
+            }
+            let road = (-road_distance[index] / options.road_width.max(f32::EPSILON)).exp();
+            let valley = (-river_distance[index] / options.valley_width.max(f32::EPSILON)).exp()
+                * map.moisture[index];
+            1.0 + options.road_boost * road + options.valley_boost * valley
+        })
+        .collect();
+
+    let cell_w = REGION_SIZE / map.width as f32;
+    let cell_h = REGION_SIZE / map.height as f32;
+    let mut density = vec![0.0f32; map.heightmap.len()];
+    let mut weights = Vec::new();
+    for settlement in &map.settlements {
+        let population = options.people(settlement);
+        let sigma = (settlement.size * options.radius_per_size).max(f32::EPSILON);
+        let reach = sigma * 3.0;
+        let x0 = ((settlement.x - reach) / cell_w).floor().max(0.0) as usize;
+        let y0 = ((settlement.y - reach) / cell_h).floor().max(0.0) as usize;
+        let x1 = (((settlement.x + reach) / cell_w).ceil() as usize).min(width - 1);
+        let y1 = (((settlement.y + reach) / cell_h).ceil() as usize).min(height - 1);
+
+        weights.clear();
+        let mut total = 0.0f32;
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                let index = y * width + x;
+                let dx = x as f32 * cell_w - settlement.x;
+                let dy = y as f32 * cell_h - settlement.y;
+                let weight = (-(dx * dx + dy * dy) / (2.0 * sigma * sigma)).exp() * affinity[index];
+                if weight > 0.0 {
+                    weights.push((index, weight));
+                    total += weight;
+                }
+            }
+        }
+        if total <= 0.0 {
+            continue;
+        }
+        let scale = population / (total * map.cell_area());
+        for &(index, weight) in &weights {
+            density[index] += weight * scale;
+        }
+    }
+    density
+}
+
+/// Integral of the density layer over the map, in inhabitants.
+pub(crate) fn total_population(map: &MapResult, density: &[f32]) -> f32 {
+    density.iter().map(|&value| value as f64).sum::<f64>() as f32 * map.cell_area()
+}
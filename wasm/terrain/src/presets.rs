@@ -0,0 +1,178 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "wasm")]
+use crate::json::Json;
+#[cfg(feature = "wasm")]
+use crate::metadata::{settings_record, GENERATOR_VERSION};
+use crate::GenerationSettings;
+#[cfg(feature = "wasm")]
+use crate::{generate, MapResult};
+
+/// Bumped whenever a preset's values change, so a preset name, this
+/// version, `GENERATOR_VERSION`, and a seed always reproduce the same map.
+///
+/// 1. `default`, `archipelago`, `highlands`, `pangaea-arid`, `many-islands`.
+pub(crate) const PRESET_VERSION: u32 = 1;
+
+pub(crate) struct Preset {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Share of cells above sea level most seeds land within.
+    pub land_fraction: (f32, f32),
+    sea_level: f32,
+    elevation_amplitude: f32,
+    warp_strength: f32,
+    erosion_iterations: u32,
+    moisture_scale: f32,
+}
+
+pub(crate) const PRESETS: [Preset; 5] = [
+    Preset {
+        name: "default",
+        description: "Broad continents with temperate climate, as generate_map defaults.",
+        land_fraction: (0.75, 1.0),
+        sea_level: 0.42,
+        elevation_amplitude: 1.0,
+        warp_strength: 40.0,
+        erosion_iterations: 2,
+        moisture_scale: 1.0,
+    },
+    Preset {
+        name: "archipelago",
+        description: "Chains of mid-sized islands in open sea.",
+        land_fraction: (0.15, 0.45),
+        sea_level: 0.55,
+        elevation_amplitude: 1.1,
+        warp_strength: 70.0,
+        erosion_iterations: 2,
+        moisture_scale: 1.1,
+    },
+    Preset {
+        name: "highlands",
+        description: "Rugged, heavily weathered uplands with tall ranges.",
+        land_fraction: (0.75, 1.0),
+        sea_level: 0.4,
+        elevation_amplitude: 1.5,
+        warp_strength: 40.0,
+        erosion_iterations: 4,
+        moisture_scale: 1.0,
+    },
+    Preset {
+        name: "pangaea-arid",
+        description: "One dry supercontinent with a smooth coastline.",
+        land_fraction: (0.65, 0.95),
+        sea_level: 0.44,
+        elevation_amplitude: 0.9,
+        warp_strength: 30.0,
+        erosion_iterations: 3,
+        moisture_scale: 0.55,
+    },
+    Preset {
+        name: "many-islands",
+        description: "Scattered small, wet islands across a wide ocean.",
+        land_fraction: (0.03, 0.2),
+        sea_level: 0.62,
+        elevation_amplitude: 1.2,
+        warp_strength: 90.0,
+        erosion_iterations: 1,
+        moisture_scale: 1.2,
+    },
+];
+
+impl Preset {
+    pub(crate) fn find(name: &str) -> Option<&'static Preset> {
+        PRESETS.iter().find(|preset| preset.name == name)
+    }
+
+    pub(crate) fn settings(&self, seed: u32) -> GenerationSettings {
+        GenerationSettings::new(
+            seed,
+            self.sea_level,
+            self.elevation_amplitude,
+            self.warp_strength,
+            self.erosion_iterations,
+            self.moisture_scale,
+        )
+    }
+}
+
+/// Generates a map from a named preset; see `list_presets`. Throws for
+/// unknown names.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn generate_preset(
+    name: &str,
+    width: u32,
+    height: u32,
+    seed: u32,
+) -> Result<MapResult, JsValue> {
+    let preset =
+        Preset::find(name).ok_or_else(|| JsValue::from_str(&format!("unknown preset: {name}")))?;
+    Ok(generate(width, height, &preset.settings(seed), None))
+}
+
+/// Every preset as `{ name, description, version, generator_version,
+/// land_fraction: [min, max], settings }`, where `settings` holds every
+/// option `generate_map_with_options` takes, as `metadata()` reports them,
+/// minus the seed.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn list_presets() -> JsValue {
+    let presets = PRESETS
+        .iter()
+        .map(|preset| {
+            let settings = settings_record(&preset.settings(0))
+                .into_iter()
+                .filter(|&(key, _)| key != "seed")
+                .collect();
+            Json::object()
+                .with("name", preset.name)
+                .with("description", preset.description)
+                .with("version", PRESET_VERSION)
+                .with("generator_version", GENERATOR_VERSION)
+                .with(
+                    "land_fraction",
+                    vec![
+                        Json::from(preset.land_fraction.0),
+                        Json::from(preset.land_fraction.1),
+                    ],
+                )
+                .with("settings", Json::from_record(settings))
+        })
+        .collect::<Vec<_>>();
+    Json::from(presets).to_js()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Preset, PRESETS};
+    use crate::generate;
+
+    #[test]
+    fn presets_generate_at_several_sizes_and_hit_land_targets() {
+        let default = Preset::find("default").unwrap().settings(9);
+        let reference = crate::GenerationSettings::default();
+        assert_eq!(
+            (default.seed, default.sea_level, default.elevation_amplitude),
+            (9, reference.sea_level, reference.elevation_amplitude)
+        );
+        for preset in &PRESETS {
+            for (seed, (width, height)) in [(1, (48, 36)), (2, (96, 72)), (3, (128, 128))] {
+                let map = generate(width, height, &preset.settings(seed), None);
+                let land = map
+                    .heightmap
+                    .iter()
+                    .filter(|&&height| height > map.sea_level)
+                    .count() as f32
+                    / map.heightmap.len() as f32;
+                let (min, max) = preset.land_fraction;
+                assert!(
+                    (min..=max).contains(&land),
+                    "{} at {width}x{height}: {land}",
+                    preset.name
+                );
+            }
+        }
+    }
+}
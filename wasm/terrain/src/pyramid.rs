@@ -0,0 +1,339 @@
+#[cfg(feature = "wasm")]
+use js_sys::Float32Array;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::{MapResult, REGION_SIZE};
+
+/// How a 2×2 block of cells folds into one cell of the next level.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Reduction {
+    Average,
+    /// Keeps the highest value, so a coarse cell bounds every cell under it.
+    Max,
+}
+
+impl Reduction {
+    #[cfg(feature = "wasm")]
+    fn from_js(mode: Option<String>) -> Result<Self, JsValue> {
+        match mode.as_deref() {
+            None | Some("average") => Ok(Reduction::Average),
+            Some("max") => Ok(Reduction::Max),
+            Some(other) => Err(JsValue::from_str(&format!("unknown reduction: {other}"))),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct PyramidOptions {
+    /// Levels halve until neither side exceeds this many cells.
+    pub min_size: u32,
+    /// Float layers reduced besides the heightmap, keyed like their getters.
+    pub layers: Vec<&'static str>,
+}
+
+impl Default for PyramidOptions {
+    fn default() -> Self {
+        Self {
+            min_size: 8,
+            layers: Vec::new(),
+        }
+    }
+}
+
+impl PyramidOptions {
+    #[cfg(feature = "wasm")]
+    fn from_js(options: &JsValue) -> Self {
+        let defaults = Self::default();
+        let layers = ["moisture", "temperature"]
+            .into_iter()
+            .filter(|&layer| js::get_bool(options, layer, false))
+            .collect();
+        Self {
+            min_size: js::get_u32(options, "min_size", defaults.min_size).max(1),
+            layers,
+        }
+    }
+}
+
+/// One reduced level; cell `(x, y)` covers cells `2x..=2x + 1` and
+/// `2y..=2y + 1` of the level below, clipped at odd edges.
+pub(crate) struct Level {
+    pub width: usize,
+    pub height: usize,
+    pub values: Vec<f32>,
+}
+
+/// Reduced levels of each pyramided layer, coarsest last. Level 0, the layer
+/// itself, is not copied.
+pub(crate) struct Pyramid {
+    pub layers: Vec<(&'static str, Vec<Level>)>,
+}
+
+impl Pyramid {
+    pub(crate) fn levels(&self, layer: &str) -> Option<&[Level]> {
+        self.layers
+            .iter()
+            .find(|(key, _)| *key == layer)
+            .map(|(_, levels)| levels.as_slice())
+    }
+}
+
+/// Halves `values` until neither side exceeds `min_size`.
+pub(crate) fn build(
+    values: &[f32],
+    width: usize,
+    height: usize,
+    reduction: Reduction,
+    min_size: usize,
+) -> Vec<Level> {
+    let mut levels: Vec<Level> = Vec::new();
+    let (mut w, mut h) = (width, height);
+    while w.max(h) > min_size.max(1) {
+        let below = levels.last().map_or(values, |level| &level.values);
+        let next = reduce(below, w, h, reduction);
+        (w, h) = (next.width, next.height);
+        levels.push(next);
+    }
+    levels
+}
+
+fn reduce(values: &[f32], width: usize, height: usize, reduction: Reduction) -> Level {
+    let (w, h) = (width.div_ceil(2), height.div_ceil(2));
+    let mut reduced = Vec::with_capacity(w * h);
+    for y in 0..h {
+        for x in 0..w {
+            let block = (2 * y..(2 * y + 2).min(height)).flat_map(|fy| {
+                (2 * x..(2 * x + 2).min(width)).map(move |fx| values[fy * width + fx])
+            });
+            reduced.push(match reduction {
+                Reduction::Average => {
+                    let (sum, count) = block.fold((0.0, 0), |(sum, count), v| (sum + v, count + 1));
+                    sum / count as f32
+                }
+                Reduction::Max => block.fold(f32::NEG_INFINITY, f32::max),
+            });
+        }
+    }
+    Level {
+        width: w,
+        height: h,
+        values: reduced,
+    }
+}
+
+impl Level {
+    /// Bilinear sample at fractional cell coordinates of this level.
+    fn sample(&self, cx: f32, cy: f32) -> f32 {
+        let cx = cx.clamp(0.0, (self.width - 1) as f32);
+        let cy = cy.clamp(0.0, (self.height - 1) as f32);
+        let (x0, y0) = (cx.floor() as usize, cy.floor() as usize);
+        let x1 = (x0 + 1).min(self.width - 1);
+        let y1 = (y0 + 1).min(self.height - 1);
+        let (tx, ty) = (cx - x0 as f32, cy - y0 as f32);
+        let at = |x: usize, y: usize| self.values[y * self.width + x];
+        let top = at(x0, y0) * (1.0 - tx) + at(x1, y0) * tx;
+        let bottom = at(x0, y1) * (1.0 - tx) + at(x1, y1) * tx;
+        top * (1.0 - ty) + bottom * ty
+    }
+}
+
+impl MapResult {
+    /// Reduced levels of `layer`, built on first use; `None` unless
+    /// `enable_pyramid` covers the layer.
+    pub(crate) fn pyramid_levels(&self, layer: &str, reduction: Reduction) -> Option<&[Level]> {
+        let options = self.pyramid.as_ref()?;
+        let pyramid = self.cache.pyramids[reduction as usize].get_or_init(|| {
+            let (width, height) = (self.width as usize, self.height as usize);
+            let min_size = options.min_size as usize;
+            let layers = std::iter::once("heightmap")
+                .chain(options.layers.iter().copied())
+                .map(|key| {
+                    let values = self.float_layer(key).expect("pyramided layers exist");
+                    (key, build(values, width, height, reduction, min_size))
+                })
+                .collect();
+            Pyramid { layers }
+        });
+        pyramid.levels(layer)
+    }
+
+    /// Bilinear sample of `layer` at `level` (0 the full grid) and a world
+    /// position. Coarse cells sit at the centre of the cells they cover.
+    pub(crate) fn sample_pyramid(
+        &self,
+        layer: &str,
+        level: usize,
+        reduction: Reduction,
+        world_x: f32,
+        world_y: f32,
+    ) -> Option<f32> {
+        if level == 0 {
+            let values = self.float_layer(layer)?;
+            return Some(self.bilinear(values, world_x, world_y));
+        }
+        let reduced = self.pyramid_levels(layer, reduction)?.get(level - 1)?;
+        let scale = (1u32 << level) as f32;
+        let offset = (scale - 1.0) * 0.5;
+        let cx = world_x / REGION_SIZE * self.width as f32;
+        let cy = world_y / REGION_SIZE * self.height as f32;
+        Some(reduced.sample((cx - offset) / scale, (cy - offset) / scale))
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Keeps successive 2× reductions of the heightmap, and of `moisture`
+    /// and `temperature` when those options are true, for multi-scale
+    /// queries. Levels halve until neither side exceeds `min_size` (default
+    /// 8) cells. Average and max levels are each built on first use and
+    /// cost about a third of a layer apiece; edits drop them.
+    pub fn enable_pyramid(&mut self, options: JsValue) {
+        self.pyramid = Some(PyramidOptions::from_js(&options));
+        self.cache.pyramids = Default::default();
+    }
+
+    /// Drops the levels kept by `enable_pyramid`.
+    pub fn disable_pyramid(&mut self) {
+        self.pyramid = None;
+        self.cache.pyramids = Default::default();
+    }
+
+    /// Levels including the full grid at 0, or 1 when no pyramid is enabled.
+    pub fn level_count(&self) -> u32 {
+        self.pyramid_levels("heightmap", Reduction::Average)
+            .map_or(0, <[Level]>::len) as u32
+            + 1
+    }
+
+    /// `[width, height]` of `level` in cells.
+    pub fn level_size(&self, level: u32) -> Result<Vec<u32>, JsValue> {
+        if level == 0 {
+            return Ok(vec![self.width, self.height]);
+        }
+        let reduced = self.reduced_level("heightmap", level, Reduction::Average)?;
+        Ok(vec![reduced.width as u32, reduced.height as u32])
+    }
+
+    /// The heightmap at `level`, row by row. `mode` is `"average"` (default)
+    /// or `"max"`.
+    pub fn heightmap_level(
+        &self,
+        level: u32,
+        mode: Option<String>,
+    ) -> Result<Float32Array, JsValue> {
+        self.layer_level("heightmap", level, mode)
+    }
+
+    /// `"heightmap"`, `"moisture"`, or `"temperature"` at `level`, like
+    /// `heightmap_level`; other layers must be enabled first.
+    pub fn layer_level(
+        &self,
+        layer: &str,
+        level: u32,
+        mode: Option<String>,
+    ) -> Result<Float32Array, JsValue> {
+        let reduction = Reduction::from_js(mode)?;
+        if level == 0 {
+            let values = self
+                .float_layer(layer)
+                .ok_or_else(|| JsValue::from_str(&format!("unknown layer: {layer}")))?;
+            return Ok(Float32Array::from(values));
+        }
+        let reduced = self.reduced_level(layer, level, reduction)?;
+        Ok(Float32Array::from(reduced.values.as_slice()))
+    }
+
+    /// Heightmap at `level` sampled bilinearly at a world position.
+    pub fn sample_level(
+        &self,
+        level: u32,
+        x: f32,
+        y: f32,
+        mode: Option<String>,
+    ) -> Result<f32, JsValue> {
+        let reduction = Reduction::from_js(mode)?;
+        if level > 0 {
+            self.reduced_level("heightmap", level, reduction)?;
+        }
+        Ok(self
+            .sample_pyramid("heightmap", level as usize, reduction, x, y)
+            .expect("level checked above"))
+    }
+}
+
+#[cfg(feature = "wasm")]
+impl MapResult {
+    fn reduced_level(
+        &self,
+        layer: &str,
+        level: u32,
+        reduction: Reduction,
+    ) -> Result<&Level, JsValue> {
+        let levels = self
+            .pyramid_levels(layer, reduction)
+            .ok_or_else(|| JsValue::from_str(&format!("no pyramid for layer: {layer}")))?;
+        levels
+            .get(level as usize - 1)
+            .ok_or_else(|| JsValue::from_str(&format!("level {level} out of range")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build, PyramidOptions, Reduction};
+    use crate::generate_map;
+    use crate::visibility::line_of_sight;
+
+    #[test]
+    fn levels_halve_to_the_minimum_and_bound_the_fine_grid() {
+        let values: Vec<f32> = (0..15).map(|v| v as f32).collect();
+        let levels = build(&values, 5, 3, Reduction::Average, 1);
+        let sizes: Vec<_> = levels.iter().map(|l| (l.width, l.height)).collect();
+        assert_eq!(sizes, [(3, 2), (2, 1), (1, 1)]);
+        // Edge blocks are clipped: column 4 pairs with nothing.
+        assert_eq!(levels[0].values, [3.0, 5.0, 6.5, 10.5, 12.5, 14.0]);
+
+        let mut map = generate_map(64, 48, 5, 0.42, 1.0, 40.0, 2, 1.0);
+        assert!(map.pyramid_levels("heightmap", Reduction::Max).is_none());
+        map.pyramid = Some(PyramidOptions::default());
+        let max = map.pyramid_levels("heightmap", Reduction::Max).unwrap();
+        assert_eq!(max.len(), 3);
+        assert_eq!((max[2].width, max[2].height), (8, 6));
+        for (index, &height) in map.heightmap.iter().enumerate() {
+            let (x, y) = (index % 64, index / 64);
+            assert!(height <= max[2].values[(y / 8) * 8 + x / 8]);
+        }
+        let mean = map.heightmap.iter().sum::<f32>() / map.heightmap.len() as f32;
+        let coarse = map.pyramid_levels("heightmap", Reduction::Average).unwrap();
+        let coarse_mean = coarse[2].values.iter().sum::<f32>() / coarse[2].values.len() as f32;
+        assert!((mean - coarse_mean).abs() < 1e-4);
+        assert!(map.pyramid_levels("moisture", Reduction::Average).is_none());
+
+        let centre = map.sample_pyramid(
+            "heightmap",
+            3,
+            Reduction::Average,
+            112.0,
+            3.5 * 2048.0 / 48.0,
+        );
+        assert!((centre.unwrap() - coarse[2].values[0]).abs() < 1e-5);
+
+        // The coarse early-out never changes an answer.
+        let rays = (0..40).map(|i| {
+            let t = i as f32 * 0.61;
+            let from = (100.0 + 40.0 * i as f32, 200.0 + 30.0 * t.sin());
+            let to = (1900.0 - 35.0 * i as f32, 1800.0 * t.cos().abs());
+            (from, to, 0.02 * (i % 5) as f32)
+        });
+        for (from, to, eye) in rays {
+            let coarse = line_of_sight(&map, from, to, eye, 0.01);
+            map.pyramid = None;
+            assert_eq!(coarse, line_of_sight(&map, from, to, eye, 0.01));
+            map.pyramid = Some(PyramidOptions::default());
+        }
+    }
+}
@@ -0,0 +1,182 @@
+#[cfg(feature = "wasm")]
+use js_sys::{Float32Array, Uint8Array};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::biome::Biome;
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::{MapResult, REGION_SIZE};
+
+/// Biome returned for coordinates outside the world when not in strict mode.
+const OUT_OF_BOUNDS_BIOME: u8 = Biome::Ocean.code();
+/// Water value returned for coordinates outside the world when not in strict mode.
+const OUT_OF_BOUNDS_WATER: f32 = 1.0;
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Bilinearly samples a float layer at world coordinates, clamping at the
+    /// map borders. Throws for unknown layer names.
+    pub fn sample(&self, layer: &str, world_x: f32, world_y: f32) -> Result<f32, JsValue> {
+        let values = self.layer_or_error(layer)?;
+        Ok(self.bilinear(values, world_x, world_y))
+    }
+
+    /// Batched `sample` over interleaved `x, y` world coordinates.
+    pub fn sample_many(&self, layer: &str, points: &Float32Array) -> Result<Float32Array, JsValue> {
+        let values = self.layer_or_error(layer)?;
+        let points = points_from_js(points)?;
+        let samples: Vec<f32> = points
+            .chunks_exact(2)
+            .map(|point| self.bilinear(values, point[0], point[1]))
+            .collect();
+        Ok(Float32Array::from(samples.as_slice()))
+    }
+
+    /// Biome code of the nearest cell. Coordinates outside `0..REGION_SIZE`
+    /// return ocean (0), or throw when `options.strict` is set.
+    pub fn biome_at(&self, world_x: f32, world_y: f32, options: JsValue) -> Result<u8, JsValue> {
+        self.biome_at_world(world_x, world_y, js::get_bool(&options, "strict", false))
+            .map_err(|message| JsValue::from_str(&message))
+    }
+
+    /// Bilinearly interpolated water value. Coordinates outside `0..REGION_SIZE`
+    /// return 1.0, or throw when `options.strict` is set.
+    pub fn water_at(&self, world_x: f32, world_y: f32, options: JsValue) -> Result<f32, JsValue> {
+        self.water_at_world(world_x, world_y, js::get_bool(&options, "strict", false))
+            .map_err(|message| JsValue::from_str(&message))
+    }
+
+    /// Batched `biome_at` over interleaved `x, y` world coordinates.
+    pub fn biomes_at(
+        &self,
+        points: &Float32Array,
+        options: JsValue,
+    ) -> Result<Uint8Array, JsValue> {
+        let strict = js::get_bool(&options, "strict", false);
+        let biomes = points_from_js(points)?
+            .chunks_exact(2)
+            .map(|point| self.biome_at_world(point[0], point[1], strict))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|message| JsValue::from_str(&message))?;
+        Ok(Uint8Array::from(biomes.as_slice()))
+    }
+
+    /// Batched `water_at` over interleaved `x, y` world coordinates.
+    pub fn waters_at(
+        &self,
+        points: &Float32Array,
+        options: JsValue,
+    ) -> Result<Float32Array, JsValue> {
+        let strict = js::get_bool(&options, "strict", false);
+        let water = points_from_js(points)?
+            .chunks_exact(2)
+            .map(|point| self.water_at_world(point[0], point[1], strict))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|message| JsValue::from_str(&message))?;
+        Ok(Float32Array::from(water.as_slice()))
+    }
+}
+
+impl MapResult {
+    pub(crate) fn biome_at_world(
+        &self,
+        world_x: f32,
+        world_y: f32,
+        strict: bool,
+    ) -> Result<u8, String> {
+        if !in_world(world_x, world_y) {
+            return out_of_bounds(world_x, world_y, strict, OUT_OF_BOUNDS_BIOME);
+        }
+        let (x, y) = self.nearest_cell(world_x, world_y);
+        Ok(self.biome[y * self.width as usize + x])
+    }
+
+    pub(crate) fn water_at_world(
+        &self,
+        world_x: f32,
+        world_y: f32,
+        strict: bool,
+    ) -> Result<f32, String> {
+        if !in_world(world_x, world_y) {
+            return out_of_bounds(world_x, world_y, strict, OUT_OF_BOUNDS_WATER);
+        }
+        Ok(self.bilinear(&self.water, world_x, world_y))
+    }
+
+    #[cfg(feature = "wasm")]
+    fn layer_or_error(&self, layer: &str) -> Result<&[f32], JsValue> {
+        self.float_layer(layer)
+            .ok_or_else(|| JsValue::from_str(&format!("unknown layer: {layer}")))
+    }
+
+    /// Bilinear interpolation of `values` at world coordinates, clamped to the grid.
+    pub(crate) fn bilinear(&self, values: &[f32], world_x: f32, world_y: f32) -> f32 {
+        let width = self.width as usize;
+        let (cx, cy) = self.world_to_cell(world_x, world_y);
+        let cx = cx.clamp(0.0, (self.width - 1) as f32);
+        let cy = cy.clamp(0.0, (self.height - 1) as f32);
+        let x0 = cx.floor() as usize;
+        let y0 = cy.floor() as usize;
+        let x1 = (x0 + 1).min(width - 1);
+        let y1 = (y0 + 1).min(self.height as usize - 1);
+        let tx = cx - x0 as f32;
+        let ty = cy - y0 as f32;
+        let top = values[y0 * width + x0] * (1.0 - tx) + values[y0 * width + x1] * tx;
+        let bottom = values[y1 * width + x0] * (1.0 - tx) + values[y1 * width + x1] * tx;
+        top * (1.0 - ty) + bottom * ty
+    }
+}
+
+/// Copies interleaved `x, y` pairs out of JS, rejecting odd lengths.
+#[cfg(feature = "wasm")]
+pub(crate) fn points_from_js(points: &Float32Array) -> Result<Vec<f32>, JsValue> {
+    let points = points.to_vec();
+    if !points.len().is_multiple_of(2) {
+        return Err(JsValue::from_str("points must hold interleaved x, y pairs"));
+    }
+    Ok(points)
+}
+
+fn in_world(world_x: f32, world_y: f32) -> bool {
+    (0.0..REGION_SIZE).contains(&world_x) && (0.0..REGION_SIZE).contains(&world_y)
+}
+
+fn out_of_bounds<T>(world_x: f32, world_y: f32, strict: bool, sentinel: T) -> Result<T, String> {
+    if strict {
+        Err(format!(
+            "({world_x}, {world_y}) is outside the world bounds"
+        ))
+    } else {
+        Ok(sentinel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generate_map;
+
+    #[test]
+    fn point_queries_match_cells() {
+        let map = generate_map(96, 64, 11, 0.42, 1.0, 40.0, 2, 1.0);
+        for index in 0..map.biome.len() {
+            let (world_x, world_y) = map.cell_to_world(index);
+            assert_eq!(
+                map.biome_at_world(world_x, world_y, true),
+                Ok(map.biome[index])
+            );
+            let water = map.water_at_world(world_x, world_y, true).unwrap();
+            assert!((water - map.water[index]).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn out_of_bounds_uses_sentinels_or_errors() {
+        let map = generate_map(32, 32, 3, 0.42, 1.0, 40.0, 0, 1.0);
+        assert_eq!(map.biome_at_world(-1.0, 10.0, false), Ok(0));
+        assert_eq!(map.water_at_world(10.0, 2048.0, false), Ok(1.0));
+        assert!(map.biome_at_world(-1.0, 10.0, true).is_err());
+        assert!(map.water_at_world(10.0, 2048.0, true).is_err());
+    }
+}
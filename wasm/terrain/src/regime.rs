@@ -0,0 +1,146 @@
+use std::collections::VecDeque;
+
+use crate::cover::{cover, CoverOptions};
+use crate::hydrology::{Outlet, River};
+use crate::json::Json;
+use crate::MapResult;
+
+/// Basin snow share from which a river counts as snow-fed.
+const NIVAL_SHARE: f32 = 0.15;
+/// Basin snow share from which a river counts as glacier-fed.
+const GLACIAL_SHARE: f32 = 0.5;
+/// Rise of spring flow over the annual mean for an entirely snowy basin.
+const SPRING_PEAK: f32 = 1.5;
+/// Fall of autumn flow below the annual mean for an entirely snowy basin.
+const AUTUMN_LOW: f32 = 0.6;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum RegimeKind {
+    Glacial,
+    Nival,
+    Pluvial,
+}
+
+impl RegimeKind {
+    pub(crate) fn key(self) -> &'static str {
+        match self {
+            RegimeKind::Glacial => "glacial",
+            RegimeKind::Nival => "nival",
+            RegimeKind::Pluvial => "pluvial",
+        }
+    }
+}
+
+/// How a river's flow follows the seasons.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) struct Regime {
+    pub kind: RegimeKind,
+    /// Snow-covered share of the basin upstream of the river's mouth.
+    pub snowmelt: f32,
+    /// Spring and autumn flow as multiples of the annual mean. Both follow
+    /// `snowmelt` continuously, so only `kind` steps at the thresholds.
+    pub spring: f32,
+    pub autumn: f32,
+}
+
+impl Regime {
+    fn new(snowmelt: f32) -> Self {
+        let kind = if snowmelt >= GLACIAL_SHARE {
+            RegimeKind::Glacial
+        } else if snowmelt >= NIVAL_SHARE {
+            RegimeKind::Nival
+        } else {
+            RegimeKind::Pluvial
+        };
+        Self {
+            kind,
+            snowmelt,
+            spring: 1.0 + SPRING_PEAK * snowmelt,
+            autumn: 1.0 - AUTUMN_LOW * snowmelt,
+        }
+    }
+
+    pub(crate) fn record(&self) -> Vec<(&'static str, Json)> {
+        vec![
+            ("regime", self.kind.key().into()),
+            ("snowmelt", self.snowmelt.into()),
+            ("spring_flow", self.spring.into()),
+            ("autumn_flow", self.autumn.into()),
+        ]
+    }
+}
+
+/// Snow-covered share of the cells draining through each cell, itself
+/// included, summed down the receivers in one topological pass. Cells in or
+/// below a receiver cycle keep only their own share.
+pub(crate) fn basin_snow(map: &MapResult, snow: &[f32]) -> Vec<f32> {
+    let size = snow.len();
+    let receivers = map.receivers();
+    let mut pending = vec![0u32; size];
+    for &next in receivers.iter().flatten() {
+        pending[next] += 1;
+    }
+    let mut totals: Vec<(f32, f32)> = snow.iter().map(|&snow| (snow, 1.0)).collect();
+    let mut queue: VecDeque<usize> = (0..size).filter(|&cell| pending[cell] == 0).collect();
+    while let Some(cell) = queue.pop_front() {
+        let Some(next) = receivers[cell] else {
+            continue;
+        };
+        let (snow, area) = totals[cell];
+        totals[next].0 += snow;
+        totals[next].1 += area;
+        pending[next] -= 1;
+        if pending[next] == 0 {
+            queue.push_back(next);
+        }
+    }
+    totals.iter().map(|&(snow, area)| snow / area).collect()
+}
+
+/// Regime of each river, from the basin above the last cell it owns: the
+/// cell before its confluence or outlet water, or its end at a sink.
+pub(crate) fn river_regimes(map: &MapResult, rivers: &[River]) -> Vec<Regime> {
+    let snow = cover(map, &CoverOptions::default()).snow;
+    let basins = basin_snow(map, &snow);
+    rivers
+        .iter()
+        .map(|river| {
+            let owned = match river.outlet {
+                Outlet::Sink => river.cells.len(),
+                _ => river.cells.len() - 1,
+            };
+            let mouth = river.cells[owned.max(1) - 1];
+            Regime::new(basins[mouth])
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{basin_snow, river_regimes, Regime, RegimeKind};
+    use crate::generate_map;
+    use crate::hydrology::extract_rivers;
+
+    #[test]
+    fn regimes_follow_basin_snow_smoothly() {
+        assert_eq!(Regime::new(0.0).kind, RegimeKind::Pluvial);
+        assert_eq!(Regime::new(0.3).kind, RegimeKind::Nival);
+        assert_eq!(Regime::new(0.8).kind, RegimeKind::Glacial);
+        let (below, above) = (Regime::new(0.4999), Regime::new(0.5));
+        assert!((below.spring - above.spring).abs() < 1e-3);
+        assert!((below.autumn - above.autumn).abs() < 1e-3);
+
+        let map = generate_map(96, 96, 4, 0.42, 1.0, 40.0, 2, 1.0);
+        let snow: Vec<f32> = (0..map.heightmap.len())
+            .map(|cell| (cell % 96 < 48) as u8 as f32)
+            .collect();
+        let basins = basin_snow(&map, &snow);
+        assert!(basins.iter().all(|share| (0.0..=1.0).contains(share)));
+        let rivers = extract_rivers(&map);
+        let regimes = river_regimes(&map, &rivers);
+        assert_eq!(regimes.len(), rivers.len());
+        for regime in regimes {
+            assert!(regime.spring >= 1.0 && regime.autumn <= 1.0 && regime.autumn > 0.0);
+        }
+    }
+}
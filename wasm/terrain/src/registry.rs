@@ -0,0 +1,269 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::geometry::distance;
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::json::Json;
+use crate::poi::Poi;
+use crate::{MapResult, REGION_SIZE};
+
+/// Priority of a point feature, highest first. A feature never displaces
+/// one of its own class or above.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub(crate) enum FeatureClass {
+    Settlement,
+    /// Ruins and lookouts.
+    Poi,
+    /// Hot springs, oases, and vents.
+    Micro,
+}
+
+impl FeatureClass {
+    pub(crate) fn key(self) -> &'static str {
+        match self {
+            FeatureClass::Settlement => "settlement",
+            FeatureClass::Poi => "poi",
+            FeatureClass::Micro => "micro",
+        }
+    }
+
+    /// World distance other point features keep from this one; two features
+    /// clash when closer than the larger of their radii.
+    pub(crate) fn radius(self) -> f32 {
+        match self {
+            FeatureClass::Settlement => 40.0,
+            FeatureClass::Poi => 24.0,
+            FeatureClass::Micro => 16.0,
+        }
+    }
+}
+
+/// Farthest a clashing feature moves, as a multiple of its own radius,
+/// before it is dropped instead.
+const NUDGE_REACH: f32 = 2.0;
+
+/// One entry of the combined point-feature view.
+pub(crate) struct PointFeature {
+    pub kind: &'static str,
+    pub class: FeatureClass,
+    /// Settlement or POI id, depending on `class`.
+    pub id: u32,
+    pub x: f32,
+    pub y: f32,
+}
+
+impl PointFeature {
+    fn record(&self) -> Vec<(&'static str, Json)> {
+        vec![
+            ("type", self.kind.into()),
+            ("class", self.class.key().into()),
+            ("id", self.id.into()),
+            ("x", self.x.into()),
+            ("y", self.y.into()),
+            ("radius", self.class.radius().into()),
+        ]
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Every stored point feature as `{ type, class, id, x, y, radius }`:
+    /// settlements first (`type` `"settlement"`), then POIs with their
+    /// `kind` as `type`. `class` is `"settlement"`, `"poi"` (ruins and
+    /// lookouts), or `"micro"` (hot springs, geysers, fumaroles, and
+    /// oases), in falling priority; no two features stand closer than the
+    /// larger of their `radius`. `options.types` keeps only the listed
+    /// types. `settlements()` and `pois()` return the same features. River
+    /// heads from `springs()` and entrances from `caves()` are derived on
+    /// request, are not listed here, and keep no spacing from these.
+    pub fn features(&self, options: JsValue) -> JsValue {
+        let types: Option<Vec<String>> = js::get_array(&options, "types")
+            .map(|types| types.iter().filter_map(|kind| kind.as_string()).collect());
+        let records = self
+            .point_features()
+            .iter()
+            .filter(|feature| {
+                types
+                    .as_ref()
+                    .is_none_or(|types| types.iter().any(|kind| kind == feature.kind))
+            })
+            .map(|feature| Json::from_record(feature.record()))
+            .collect::<Vec<_>>();
+        Json::from(records).to_js()
+    }
+}
+
+impl MapResult {
+    pub(crate) fn point_features(&self) -> Vec<PointFeature> {
+        let settlements = self.settlements.iter().map(|settlement| PointFeature {
+            kind: "settlement",
+            class: FeatureClass::Settlement,
+            id: settlement.id,
+            x: settlement.x,
+            y: settlement.y,
+        });
+        let pois = self.pois.iter().map(|poi| PointFeature {
+            kind: poi.kind.key(),
+            class: poi.kind.class(),
+            id: poi.id,
+            x: poi.x,
+            y: poi.y,
+        });
+        settlements.chain(pois).collect()
+    }
+
+    /// Adds a POI, nudged clear of every settlement and POI of its class or
+    /// above, or not at all when no free spot lies within reach. POIs of
+    /// lower classes in its way are nudged in turn or dropped, and POI ids
+    /// are renumbered to stay positions. Returns the new POI's id.
+    pub(crate) fn register_poi(&mut self, poi: Poi) -> Option<u32> {
+        let class = poi.kind.class();
+        let (x, y) = self.free_spot((poi.x, poi.y), class, &[])?;
+        self.pois.push(Poi { x, y, ..poi });
+        let added = self.pois.len() - 1;
+
+        let mut dropped = Vec::new();
+        for index in 0..added {
+            let other = &self.pois[index];
+            let other_class = other.kind.class();
+            if other_class <= class || !clashes((x, y), class, (other.x, other.y), other_class) {
+                continue;
+            }
+            dropped.push(index);
+            if let Some((nx, ny)) = self.free_spot((other.x, other.y), other_class, &dropped) {
+                dropped.pop();
+                self.pois[index].x = nx;
+                self.pois[index].y = ny;
+            }
+        }
+        let mut position = 0;
+        self.pois.retain(|_| {
+            position += 1;
+            !dropped.contains(&(position - 1))
+        });
+        for (id, poi) in self.pois.iter_mut().enumerate() {
+            poi.id = id as u32;
+        }
+        Some((added - dropped.len()) as u32)
+    }
+
+    /// Re-registers every POI in order, so generation-time POIs obey the
+    /// same spacing as those added later.
+    pub(crate) fn resolve_features(&mut self) {
+        for poi in std::mem::take(&mut self.pois) {
+            self.register_poi(poi);
+        }
+    }
+
+    /// Nearest point to `at`, on a cell of the same biome and river state,
+    /// clear of every settlement and of every POI of `class` or above other
+    /// than those at `ignored` positions. `at` itself wins when clear.
+    fn free_spot(
+        &self,
+        at: (f32, f32),
+        class: FeatureClass,
+        ignored: &[usize],
+    ) -> Option<(f32, f32)> {
+        let clear = |point: (f32, f32)| {
+            let settlements = self.settlements.iter().all(|settlement| {
+                !clashes(
+                    point,
+                    class,
+                    (settlement.x, settlement.y),
+                    FeatureClass::Settlement,
+                )
+            });
+            settlements
+                && self.pois.iter().enumerate().all(|(index, poi)| {
+                    let other = poi.kind.class();
+                    other > class
+                        || ignored.contains(&index)
+                        || !clashes(point, class, (poi.x, poi.y), other)
+                })
+        };
+        if clear(at) {
+            return Some(at);
+        }
+        let (cx, cy) = self.nearest_cell(at.0, at.1);
+        let width = self.width as usize;
+        let origin = cy * width + cx;
+        let reach = class.radius() * NUDGE_REACH;
+        let rx = (reach / REGION_SIZE * self.width as f32).ceil() as usize;
+        let ry = (reach / REGION_SIZE * self.height as f32).ceil() as usize;
+        let mut candidates: Vec<(f32, usize)> = Vec::new();
+        for y in cy.saturating_sub(ry)..=(cy + ry).min(self.height as usize - 1) {
+            for x in cx.saturating_sub(rx)..=(cx + rx).min(width - 1) {
+                let index = y * width + x;
+                let point = self.cell_to_world(index);
+                let offset = distance(at, point);
+                if offset <= reach
+                    && self.biome[index] == self.biome[origin]
+                    && self.is_river(index) == self.is_river(origin)
+                {
+                    candidates.push((offset, index));
+                }
+            }
+        }
+        candidates.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+        candidates
+            .into_iter()
+            .map(|(_, index)| self.cell_to_world(index))
+            .find(|&point| clear(point))
+    }
+}
+
+fn clashes(a: (f32, f32), a_class: FeatureClass, b: (f32, f32), b_class: FeatureClass) -> bool {
+    distance(a, b) < a_class.radius().max(b_class.radius())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FeatureClass;
+    use crate::generate_map;
+    use crate::geometry::distance;
+    use crate::poi::{Poi, PoiKind};
+
+    fn poi(kind: PoiKind, (x, y): (f32, f32)) -> Poi {
+        Poi {
+            id: 0,
+            kind,
+            x,
+            y,
+            era: 0,
+            reason: None,
+            coverage: None,
+        }
+    }
+
+    #[test]
+    fn higher_priorities_push_lower_ones_aside() {
+        let mut map = generate_map(96, 96, 4, 0.42, 1.0, 40.0, 2, 1.0);
+        map.pois.clear();
+        let town = (map.settlements[0].x, map.settlements[0].y);
+        if let Some(id) = map.register_poi(poi(PoiKind::HotSpring, town)) {
+            let spring = &map.pois[id as usize];
+            assert!(distance(town, (spring.x, spring.y)) >= FeatureClass::Settlement.radius());
+        }
+
+        // A lighthouse landing on an oasis moves it or drops it.
+        map.pois.clear();
+        let (cx, cy) = map.nearest_cell(town.0 + 200.0, town.1);
+        let site = map.cell_to_world(cy * 96 + cx);
+        let oasis = map.register_poi(poi(PoiKind::Oasis, site));
+        let tower = map.register_poi(poi(PoiKind::Lighthouse, site));
+        if let (Some(_), Some(tower)) = (oasis, tower) {
+            let tower = &map.pois[tower as usize];
+            assert!(tower.kind == PoiKind::Lighthouse);
+            for other in map.pois.iter().filter(|other| other.kind == PoiKind::Oasis) {
+                assert!(
+                    distance((tower.x, tower.y), (other.x, other.y)) >= FeatureClass::Poi.radius()
+                );
+            }
+        }
+        for (id, poi) in map.pois.iter().enumerate() {
+            assert_eq!(poi.id, id as u32);
+        }
+    }
+}
@@ -0,0 +1,284 @@
+#[cfg(feature = "wasm")]
+use js_sys::{Array, Uint8Array};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::biome::BIOMES;
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::json::Json;
+use crate::style::{depth_shade, FALLBACK_COLOR, HYPSOMETRIC_STOPS, RIVER_STROKE, ROAD_STROKE};
+use crate::MapResult;
+
+/// Style overrides shared by `render_rgba` and `style_manifest`.
+pub(crate) struct RenderOptions {
+    pub palette: Vec<[u8; 3]>,
+    pub hypsometric: bool,
+    pub hypsometric_strength: f32,
+    pub hillshade: bool,
+    pub hillshade_strength: f32,
+    pub water_depth: bool,
+    pub rivers: bool,
+    pub roads: bool,
+    pub river_color: [u8; 3],
+    pub road_color: [u8; 3],
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        let mut palette = vec![FALLBACK_COLOR; 256];
+        for biome in BIOMES {
+            palette[biome.code() as usize] = biome.default_color();
+        }
+        Self {
+            palette,
+            hypsometric: true,
+            hypsometric_strength: 0.35,
+            hillshade: true,
+            hillshade_strength: 0.6,
+            water_depth: true,
+            rivers: true,
+            roads: true,
+            river_color: RIVER_STROKE.color,
+            road_color: ROAD_STROKE.color,
+        }
+    }
+}
+
+impl RenderOptions {
+    #[cfg(feature = "wasm")]
+    pub(crate) fn from_js(options: &JsValue) -> Result<Self, JsValue> {
+        let mut parsed = Self::default();
+        if let Some(palette) = js::get_array(options, "palette") {
+            for entry in palette.iter() {
+                parsed.apply_palette_entry(&entry)?;
+            }
+        }
+        parsed.hypsometric = js::get_bool(options, "hypsometric", parsed.hypsometric);
+        parsed.hypsometric_strength =
+            js::get_f32(options, "hypsometric_strength", parsed.hypsometric_strength)
+                .clamp(0.0, 1.0);
+        parsed.hillshade = js::get_bool(options, "hillshade", parsed.hillshade);
+        parsed.hillshade_strength =
+            js::get_f32(options, "hillshade_strength", parsed.hillshade_strength).clamp(0.0, 1.0);
+        parsed.water_depth = js::get_bool(options, "water_depth", parsed.water_depth);
+        parsed.rivers = js::get_bool(options, "rivers", parsed.rivers);
+        parsed.roads = js::get_bool(options, "roads", parsed.roads);
+        if let Some(color) = js::get(options, "river_color") {
+            parsed.river_color = parse_color(&color, "river_color")?;
+        }
+        if let Some(color) = js::get(options, "road_color") {
+            parsed.road_color = parse_color(&color, "road_color")?;
+        }
+        Ok(parsed)
+    }
+
+    /// Every option, spelled out so `from_js` reads it back unchanged.
+    pub(crate) fn record(&self) -> Json {
+        let palette = BIOMES
+            .iter()
+            .map(|biome| {
+                let [r, g, b] = self.palette[biome.code() as usize];
+                Json::from(vec![biome.code() as u32, r as u32, g as u32, b as u32])
+            })
+            .collect::<Vec<_>>();
+        let color = |[r, g, b]: [u8; 3]| vec![r as u32, g as u32, b as u32];
+        Json::object()
+            .with("palette", palette)
+            .with("hypsometric", self.hypsometric)
+            .with("hypsometric_strength", self.hypsometric_strength)
+            .with("hillshade", self.hillshade)
+            .with("hillshade_strength", self.hillshade_strength)
+            .with("water_depth", self.water_depth)
+            .with("rivers", self.rivers)
+            .with("roads", self.roads)
+            .with("river_color", color(self.river_color))
+            .with("road_color", color(self.road_color))
+    }
+
+    #[cfg(feature = "wasm")]
+    fn apply_palette_entry(&mut self, entry: &JsValue) -> Result<(), JsValue> {
+        let invalid = || JsValue::from_str("palette entries must be [biome_code, r, g, b]");
+        if !Array::is_array(entry) {
+            return Err(invalid());
+        }
+        let entry = Array::from(entry);
+        if entry.length() != 4 {
+            return Err(invalid());
+        }
+        let mut values = [0u8; 4];
+        for (slot, value) in values.iter_mut().zip(entry.iter()) {
+            let number = value.as_f64().ok_or_else(invalid)?;
+            if !(0.0..=255.0).contains(&number) {
+                return Err(invalid());
+            }
+            *slot = number as u8;
+        }
+        self.palette[values[0] as usize] = [values[1], values[2], values[3]];
+        Ok(())
+    }
+}
+
+#[cfg(feature = "wasm")]
+fn parse_color(value: &JsValue, key: &str) -> Result<[u8; 3], JsValue> {
+    let invalid = || JsValue::from_str(&format!("{key} must be [r, g, b]"));
+    if !Array::is_array(value) {
+        return Err(invalid());
+    }
+    let entry = Array::from(value);
+    if entry.length() != 3 {
+        return Err(invalid());
+    }
+    let mut color = [0u8; 3];
+    for (slot, value) in color.iter_mut().zip(entry.iter()) {
+        let number = value.as_f64().ok_or_else(invalid)?;
+        if !(0.0..=255.0).contains(&number) {
+            return Err(invalid());
+        }
+        *slot = number as u8;
+    }
+    Ok(color)
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Composites the biome, relief, water, and road layers into a
+    /// `width * height * 4` RGBA buffer suitable for `ImageData`. Options:
+    /// `palette` (`[biome_code, r, g, b]` entries), `hypsometric`,
+    /// `hypsometric_strength`, `hillshade`, `hillshade_strength`,
+    /// `water_depth`, `rivers`, `roads`, `river_color`, and `road_color`
+    /// (`[r, g, b]`); `style_manifest` describes the result.
+    pub fn render_rgba(&self, options: JsValue) -> Result<Uint8Array, JsValue> {
+        let options = RenderOptions::from_js(&options)?;
+        Ok(Uint8Array::from(render_rgba(self, &options).as_slice()))
+    }
+}
+
+pub(crate) fn render_rgba(map: &MapResult, options: &RenderOptions) -> Vec<u8> {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let sea_level = map.sea_level;
+    let land_range = (1.0 - sea_level).max(f32::EPSILON);
+    let mut colors = vec![[0.0f32; 3]; width * height];
+
+    for y in 0..height {
+        for x in 0..width {
+            let index = y * width + x;
+            let elevation = map.heightmap[index];
+            let base = options.palette[map.biome[index] as usize];
+            let mut color = [base[0] as f32, base[1] as f32, base[2] as f32];
+
+            if elevation <= sea_level {
+                if options.water_depth {
+                    scale(&mut color, depth_shade(elevation, sea_level));
+                }
+            } else {
+                if options.hypsometric {
+                    let tint = hypsometric_color((elevation - sea_level) / land_range);
+                    mix(&mut color, tint, options.hypsometric_strength);
+                }
+                if options.hillshade {
+                    let shade = hillshade(&map.heightmap, width, height, x, y);
+                    scale(&mut color, 1.0 + (shade - 1.0) * options.hillshade_strength);
+                }
+                if options.rivers && map.is_river(index) {
+                    mix(
+                        &mut color,
+                        rgb(options.river_color),
+                        map.water[index].min(1.0),
+                    );
+                }
+            }
+
+            colors[index] = color;
+        }
+    }
+
+    if options.roads {
+        for (color, on_road) in colors.iter_mut().zip(map.road_mask()) {
+            if on_road {
+                *color = rgb(options.road_color);
+            }
+        }
+    }
+
+    let mut rgba = vec![255u8; width * height * 4];
+    for (pixel, color) in rgba.chunks_exact_mut(4).zip(colors.iter()) {
+        pixel[0] = color[0].clamp(0.0, 255.0) as u8;
+        pixel[1] = color[1].clamp(0.0, 255.0) as u8;
+        pixel[2] = color[2].clamp(0.0, 255.0) as u8;
+    }
+    rgba
+}
+
+fn hypsometric_color(t: f32) -> [f32; 3] {
+    let t = t.clamp(0.0, 1.0);
+    for pair in HYPSOMETRIC_STOPS.windows(2) {
+        let (start, from) = (pair[0].0, rgb(pair[0].1));
+        let (end, to) = (pair[1].0, rgb(pair[1].1));
+        if t <= end {
+            let f = (t - start) / (end - start);
+            return [
+                from[0] + (to[0] - from[0]) * f,
+                from[1] + (to[1] - from[1]) * f,
+                from[2] + (to[2] - from[2]) * f,
+            ];
+        }
+    }
+    rgb(HYPSOMETRIC_STOPS[HYPSOMETRIC_STOPS.len() - 1].1)
+}
+
+fn rgb([r, g, b]: [u8; 3]) -> [f32; 3] {
+    [r as f32, g as f32, b as f32]
+}
+
+/// Lambertian shading with the light in the north-west at 45° altitude,
+/// normalized so flat ground returns 1.0.
+pub(crate) fn hillshade(heightmap: &[f32], width: usize, height: usize, x: usize, y: usize) -> f32 {
+    let (dzdx, dzdy) = crate::gradient(heightmap, width, height, x, y);
+    let normal_length = (dzdx * dzdx + dzdy * dzdy + 1.0).sqrt();
+    let light = std::f32::consts::FRAC_1_SQRT_2;
+    let lit = (dzdx * 0.5 + dzdy * 0.5 + light) / normal_length;
+    (lit.max(0.0) / light).clamp(0.3, 1.4)
+}
+
+/// Bresenham walk between two cells, inclusive of both endpoints.
+pub(crate) fn raster_line(from: (usize, usize), to: (usize, usize)) -> Vec<(usize, usize)> {
+    let (mut x, mut y) = (from.0 as i64, from.1 as i64);
+    let (x1, y1) = (to.0 as i64, to.1 as i64);
+    let dx = (x1 - x).abs();
+    let dy = -(y1 - y).abs();
+    let sx = if x < x1 { 1 } else { -1 };
+    let sy = if y < y1 { 1 } else { -1 };
+    let mut err = dx + dy;
+    let mut cells = Vec::new();
+    loop {
+        cells.push((x as usize, y as usize));
+        if x == x1 && y == y1 {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+    cells
+}
+
+fn scale(color: &mut [f32; 3], factor: f32) {
+    for channel in color.iter_mut() {
+        *channel *= factor;
+    }
+}
+
+fn mix(color: &mut [f32; 3], other: [f32; 3], t: f32) {
+    for (channel, target) in color.iter_mut().zip(other) {
+        *channel += (target - *channel) * t;
+    }
+}
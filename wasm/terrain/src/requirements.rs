@@ -0,0 +1,376 @@
+#[cfg(feature = "wasm")]
+use js_sys::Object;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::hydrology::extract_rivers;
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::json::Json;
+use crate::{generate, mask, GenerationSettings, MapResult};
+
+/// Maps that must exist before a world is accepted. Zero disables a check.
+#[derive(Clone, Default)]
+pub(crate) struct Requirements {
+    /// Share of cells above sea level, 0..1.
+    pub min_land_fraction: f32,
+    pub min_settlements: u32,
+    /// Length in world units the longest river must reach.
+    pub min_river_length: f32,
+}
+
+/// Parameters that may be relaxed between attempts, each moved by its step
+/// once per attempt after the first.
+#[derive(Clone, Default)]
+pub(crate) struct Relaxation {
+    pub sea_level: f32,
+    pub elevation_amplitude: f32,
+    pub warp_strength: f32,
+    pub moisture_scale: f32,
+}
+
+impl Relaxation {
+    fn is_empty(&self) -> bool {
+        [
+            self.sea_level,
+            self.elevation_amplitude,
+            self.warp_strength,
+            self.moisture_scale,
+        ]
+        .iter()
+        .all(|&step| step == 0.0)
+    }
+
+    /// Settings of attempt `attempt`, counting from 0. With nothing to relax
+    /// the seed advances instead.
+    fn settings(&self, base: &GenerationSettings, attempt: u32) -> GenerationSettings {
+        if self.is_empty() {
+            return GenerationSettings {
+                seed: base.seed.wrapping_add(attempt),
+                ..base.clone()
+            };
+        }
+        let k = attempt as f32;
+        GenerationSettings {
+            sea_level: (base.sea_level + self.sea_level * k).clamp(0.0, 1.0),
+            elevation_amplitude: (base.elevation_amplitude + self.elevation_amplitude * k).max(0.0),
+            warp_strength: (base.warp_strength + self.warp_strength * k).max(0.0),
+            moisture_scale: (base.moisture_scale + self.moisture_scale * k).max(0.0),
+            ..base.clone()
+        }
+    }
+}
+
+pub(crate) struct SearchOptions {
+    pub requirements: Requirements,
+    pub relax: Relaxation,
+    pub max_attempts: u32,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            requirements: Requirements::default(),
+            relax: Relaxation::default(),
+            max_attempts: 8,
+        }
+    }
+}
+
+impl SearchOptions {
+    #[cfg(feature = "wasm")]
+    fn from_js(options: &JsValue) -> Self {
+        let defaults = Self::default();
+        let requirements = js::get(options, "requirements").unwrap_or(JsValue::UNDEFINED);
+        let relax = js::get(options, "relax").unwrap_or(JsValue::UNDEFINED);
+        Self {
+            requirements: Requirements {
+                min_land_fraction: js::get_f32(&requirements, "min_land_fraction", 0.0)
+                    .clamp(0.0, 1.0),
+                min_settlements: js::get_u32(&requirements, "min_settlements", 0),
+                min_river_length: js::get_f32(&requirements, "min_river_length", 0.0),
+            },
+            relax: Relaxation {
+                sea_level: js::get_f32(&relax, "sea_level", 0.0),
+                elevation_amplitude: js::get_f32(&relax, "elevation_amplitude", 0.0),
+                warp_strength: js::get_f32(&relax, "warp_strength", 0.0),
+                moisture_scale: js::get_f32(&relax, "moisture_scale", 0.0),
+            },
+            max_attempts: js::get_u32(options, "max_attempts", defaults.max_attempts).max(1),
+        }
+    }
+}
+
+/// What one attempt produced, measured against the requirements.
+pub(crate) struct Attempt {
+    pub seed: u32,
+    pub sea_level: f32,
+    pub elevation_amplitude: f32,
+    pub warp_strength: f32,
+    pub moisture_scale: f32,
+    pub land_fraction: f32,
+    pub settlements: u32,
+    pub longest_river: f32,
+    pub unmet: Vec<&'static str>,
+    /// Mean of each requirement's met share, capped at 1, for ranking
+    /// failed attempts.
+    pub score: f32,
+}
+
+impl Attempt {
+    fn measure(map: &MapResult, requirements: &Requirements) -> Self {
+        let land = map
+            .heightmap
+            .iter()
+            .filter(|&&height| height > map.sea_level)
+            .count() as f32
+            / map.heightmap.len() as f32;
+        let longest_river = extract_rivers(map)
+            .iter()
+            .map(|river| river.length(map))
+            .fold(0.0f32, f32::max);
+        let settlements = map.settlements.len() as u32;
+        let checks = [
+            ("min_land_fraction", land, requirements.min_land_fraction),
+            (
+                "min_settlements",
+                settlements as f32,
+                requirements.min_settlements as f32,
+            ),
+            (
+                "min_river_length",
+                longest_river,
+                requirements.min_river_length,
+            ),
+        ];
+        let unmet = checks
+            .iter()
+            .filter(|&&(_, value, required)| value < required)
+            .map(|&(key, _, _)| key)
+            .collect();
+        let score = checks
+            .iter()
+            .map(|&(_, value, required)| {
+                if required > 0.0 {
+                    (value / required).min(1.0)
+                } else {
+                    1.0
+                }
+            })
+            .sum::<f32>()
+            / checks.len() as f32;
+        Self {
+            seed: map.settings.seed,
+            sea_level: map.settings.sea_level,
+            elevation_amplitude: map.settings.elevation_amplitude,
+            warp_strength: map.settings.warp_strength,
+            moisture_scale: map.settings.moisture_scale,
+            land_fraction: land,
+            settlements,
+            longest_river,
+            unmet,
+            score,
+        }
+    }
+
+    fn record(&self) -> Json {
+        Json::object()
+            .with("seed", self.seed)
+            .with("sea_level", self.sea_level)
+            .with("elevation_amplitude", self.elevation_amplitude)
+            .with("warp_strength", self.warp_strength)
+            .with("moisture_scale", self.moisture_scale)
+            .with("land_fraction", self.land_fraction)
+            .with("settlements", self.settlements)
+            .with("longest_river", self.longest_river)
+            .with(
+                "unmet",
+                self.unmet
+                    .iter()
+                    .map(|&key| Json::from(key))
+                    .collect::<Vec<_>>(),
+            )
+    }
+}
+
+pub(crate) struct Search {
+    pub map: MapResult,
+    /// Index into `attempts` of the returned map.
+    pub chosen: usize,
+    pub attempts: Vec<Attempt>,
+}
+
+impl Search {
+    pub(crate) fn satisfied(&self) -> bool {
+        self.attempts[self.chosen].unmet.is_empty()
+    }
+
+    /// Requirements that failed on at least one attempt, in check order.
+    pub(crate) fn binding(&self) -> Vec<&'static str> {
+        let mut binding: Vec<&'static str> = Vec::new();
+        for key in self.attempts.iter().flat_map(|attempt| &attempt.unmet) {
+            if !binding.contains(key) {
+                binding.push(key);
+            }
+        }
+        binding
+    }
+
+    pub(crate) fn report(&self) -> Json {
+        let keys =
+            |keys: &[&'static str]| keys.iter().map(|&key| Json::from(key)).collect::<Vec<_>>();
+        Json::object()
+            .with("satisfied", self.satisfied())
+            .with("chosen", self.chosen)
+            .with(
+                "attempts",
+                self.attempts
+                    .iter()
+                    .map(Attempt::record)
+                    .collect::<Vec<_>>(),
+            )
+            .with("binding", keys(&self.binding()))
+            .with("unmet", keys(&self.attempts[self.chosen].unmet))
+    }
+}
+
+/// Generates until a map meets `options.requirements`, keeping the best
+/// candidate when none does. Attempts depend only on `base` and `options`.
+pub(crate) fn generate_satisfying(
+    width: u32,
+    height: u32,
+    base: &GenerationSettings,
+    mask: Option<&mask::LandMask>,
+    options: &SearchOptions,
+) -> Search {
+    let mut best: Option<(MapResult, usize)> = None;
+    let mut attempts: Vec<Attempt> = Vec::new();
+    for attempt in 0..options.max_attempts.max(1) {
+        let settings = options.relax.settings(base, attempt);
+        let map = generate(width, height, &settings, mask);
+        let measured = Attempt::measure(&map, &options.requirements);
+        let met = measured.unmet.is_empty();
+        let better = best.as_ref().is_none_or(|&(_, index)| {
+            let current = &attempts[index];
+            (measured.unmet.len(), -measured.score) < (current.unmet.len(), -current.score)
+        });
+        attempts.push(measured);
+        if better {
+            best = Some((map, attempts.len() - 1));
+        }
+        if met {
+            break;
+        }
+    }
+    let (map, chosen) = best.expect("at least one attempt runs");
+    Search {
+        map,
+        chosen,
+        attempts,
+    }
+}
+
+/// `generate_map_with_options`, retried until the map meets
+/// `options.requirements`: `min_land_fraction` (0..1), `min_settlements`,
+/// and `min_river_length` (world units), each off at 0. Without
+/// `options.relax` each attempt advances the seed by one; with it the seed
+/// stays and every attempt moves the listed parameters (`sea_level`,
+/// `elevation_amplitude`, `warp_strength`, `moisture_scale`) by their step
+/// once more. Stops at the first satisfying map or after `max_attempts`
+/// (default 8), then returns the best candidate. Returns `{ map, report }`
+/// with `report` as `{ satisfied, chosen, attempts, binding, unmet }`:
+/// `attempts` lists each try's settings and measurements, `binding` the
+/// requirements that failed at least once, and `unmet` those the returned
+/// map misses. The same inputs always give the same map.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn generate_map_satisfying(
+    width: u32,
+    height: u32,
+    seed: u32,
+    sea_level: f32,
+    elevation_amplitude: f32,
+    warp_strength: f32,
+    erosion_iterations: u32,
+    moisture_scale: f32,
+    options: JsValue,
+) -> Result<Object, JsValue> {
+    let defaults = GenerationSettings::new(
+        seed,
+        sea_level,
+        elevation_amplitude,
+        warp_strength,
+        erosion_iterations,
+        moisture_scale,
+    );
+    let settings = GenerationSettings::with_options(defaults, &options)?;
+    let mask = mask::LandMask::from_options(&options, width, height)?;
+    let search = generate_satisfying(
+        width,
+        height,
+        &settings,
+        mask.as_ref(),
+        &SearchOptions::from_js(&options),
+    );
+    let report = search.report().to_js();
+    let result = Object::new();
+    js::set(&result, "map", &JsValue::from(search.map));
+    js::set(&result, "report", &report);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate_satisfying, Relaxation, Requirements, SearchOptions};
+    use crate::GenerationSettings;
+
+    #[test]
+    fn retries_deterministically_and_keeps_the_best_candidate() {
+        let base = GenerationSettings::default();
+        let options = SearchOptions {
+            requirements: Requirements {
+                min_settlements: 1,
+                ..Requirements::default()
+            },
+            ..SearchOptions::default()
+        };
+        let easy = generate_satisfying(48, 48, &base, None, &options);
+        assert!(easy.satisfied());
+        assert_eq!(easy.attempts.len(), 1);
+
+        let impossible = SearchOptions {
+            requirements: Requirements {
+                min_land_fraction: 1.0,
+                min_settlements: 10_000,
+                ..Requirements::default()
+            },
+            max_attempts: 3,
+            ..SearchOptions::default()
+        };
+        let search = generate_satisfying(48, 48, &base, None, &impossible);
+        assert!(!search.satisfied());
+        let seeds: Vec<u32> = search.attempts.iter().map(|a| a.seed).collect();
+        assert_eq!(seeds, [0, 1, 2]);
+        assert!(search.binding().contains(&"min_settlements"));
+        let again = generate_satisfying(48, 48, &base, None, &impossible);
+        assert_eq!(again.chosen, search.chosen);
+        assert_eq!(again.map.heightmap, search.map.heightmap);
+
+        let relaxed = SearchOptions {
+            requirements: Requirements {
+                min_land_fraction: 0.99,
+                ..Requirements::default()
+            },
+            relax: Relaxation {
+                sea_level: -0.1,
+                ..Relaxation::default()
+            },
+            max_attempts: 6,
+        };
+        let search = generate_satisfying(48, 48, &base, None, &relaxed);
+        assert!(search.satisfied());
+        assert!(search.attempts.iter().all(|attempt| attempt.seed == 0));
+        assert!(search.map.settings.sea_level < base.sea_level);
+    }
+}
@@ -0,0 +1,236 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::economy::{territories, NO_TERRITORY};
+#[cfg(feature = "wasm")]
+use crate::json::Json;
+use crate::MapResult;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub(crate) enum RoadCondition {
+    /// Maintained primary route.
+    Paved,
+    Dirt,
+    /// Barely kept open; most of the road's speed is gone.
+    Overgrown,
+}
+
+impl RoadCondition {
+    pub(crate) fn key(self) -> &'static str {
+        match self {
+            RoadCondition::Paved => "paved",
+            RoadCondition::Dirt => "dirt",
+            RoadCondition::Overgrown => "overgrown",
+        }
+    }
+
+    fn downgraded(self, steps: u32) -> Self {
+        match (self as u32 + steps).min(2) {
+            0 => RoadCondition::Paved,
+            1 => RoadCondition::Dirt,
+            _ => RoadCondition::Overgrown,
+        }
+    }
+
+    /// Share of a profile's road discount this condition keeps.
+    pub(crate) fn speed_share(self) -> f32 {
+        match self {
+            RoadCondition::Paved => 1.0,
+            RoadCondition::Dirt => 0.6,
+            RoadCondition::Overgrown => 0.25,
+        }
+    }
+}
+
+/// Condition of each `road_graph` edge, by position. Roads between two
+/// settlements of at least `road_paved_size` start paved, the rest dirt.
+/// On maps with earlier eras, each `road_era_decay` eras of age then cost a
+/// road one condition; on single-era maps, roads straying more than
+/// `road_remote_distance` world units from every settlement along their
+/// line lose one instead. With `road_decay`, roads running mostly outside
+/// every territory lose one more.
+pub(crate) fn road_conditions(map: &MapResult) -> Vec<RoadCondition> {
+    let settings = &map.settings;
+    let width = map.width as usize;
+    let labels = settings.road_decay.then(|| territories(map));
+    let index = map.settlement_index();
+    let current = map.history.current_era();
+    map.road_graph
+        .iter()
+        .enumerate()
+        .map(|(position, &(a, b))| {
+            let (Some(start), Some(end)) = (map.settlement(a), map.settlement(b)) else {
+                return RoadCondition::Overgrown;
+            };
+            let primary =
+                start.size >= settings.road_paved_size && end.size >= settings.road_paved_size;
+            let mut condition = if primary {
+                RoadCondition::Paved
+            } else {
+                RoadCondition::Dirt
+            };
+            let cells = map.road_line(position).unwrap_or_default();
+            if map.history.eras > 1 {
+                let age = current.saturating_sub(map.history.road_era((a, b)));
+                let steps = age.checked_div(settings.road_era_decay).unwrap_or(0);
+                condition = condition.downgraded(steps);
+            } else {
+                let remote = cells.iter().any(|&(x, y)| {
+                    let (wx, wy) = map.cell_to_world(y * width + x);
+                    index
+                        .nearest(&map.settlements, wx, wy)
+                        .is_none_or(|(_, distance)| distance > settings.road_remote_distance)
+                });
+                if remote {
+                    condition = condition.downgraded(1);
+                }
+            }
+            if let Some(labels) = &labels {
+                let outside = cells
+                    .iter()
+                    .filter(|&&(x, y)| labels[y * width + x] == NO_TERRITORY)
+                    .count();
+                if outside * 2 > cells.len() {
+                    condition = condition.downgraded(1);
+                }
+            }
+            condition
+        })
+        .collect()
+}
+
+impl MapResult {
+    /// Best condition of any road over each cell, rasterized like
+    /// `road_mask`.
+    pub(crate) fn road_condition_mask(&self) -> Vec<Option<RoadCondition>> {
+        let width = self.width as usize;
+        let mut mask = vec![None; self.heightmap.len()];
+        for (position, condition) in road_conditions(self).into_iter().enumerate() {
+            for (x, y) in self.road_line(position).unwrap_or_default() {
+                let cell: &mut Option<RoadCondition> = &mut mask[y * width + x];
+                *cell = Some(cell.map_or(condition, |best| best.min(condition)));
+            }
+        }
+        mask
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Road segments as `{ a, b, era, condition, coastal, points }` over
+    /// settlement ids, with `condition` `"paved"`, `"dirt"`, or
+    /// `"overgrown"` for line styles. `coastal` roads follow the shore (see
+    /// `coastal_roads`), and `points` holds the interleaved world `x, y` of
+    /// each cell they pass; other roads run straight and list just their two
+    /// settlements.
+    /// Roads between settlements of at least `road_paved_size` (default 3)
+    /// are paved, others dirt. With several eras, every `road_era_decay`
+    /// (default 1) eras of age drop a road one condition; with one era,
+    /// roads passing more than `road_remote_distance` (default 192) world
+    /// units from every settlement drop one. `road_decay` (default false)
+    /// drops roads running mostly outside every territory one more. Movement
+    /// profiles keep all of their road discount on paved roads, 60% on dirt,
+    /// and 25% on overgrown roads.
+    pub fn roads(&self) -> JsValue {
+        let records = self
+            .road_graph
+            .iter()
+            .zip(road_conditions(self))
+            .zip(self.shore_routes())
+            .map(|((&(a, b), condition), route)| {
+                let points: Vec<Json> = match route {
+                    Some(route) => route
+                        .iter()
+                        .flat_map(|&cell| {
+                            let (x, y) = self.cell_to_world(cell);
+                            [Json::from(x), Json::from(y)]
+                        })
+                        .collect(),
+                    None => [self.settlement(a), self.settlement(b)]
+                        .into_iter()
+                        .flatten()
+                        .flat_map(|settlement| [Json::from(settlement.x), Json::from(settlement.y)])
+                        .collect(),
+                };
+                Json::object()
+                    .with("a", a)
+                    .with("b", b)
+                    .with("era", self.history.road_era((a, b)))
+                    .with("condition", condition.key())
+                    .with("coastal", route.is_some())
+                    .with("points", points)
+            })
+            .collect::<Vec<_>>();
+        Json::from(records).to_js()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::road_conditions;
+    use crate::movement::{build_cost_field, Profile};
+    use crate::{generate, GenerationSettings};
+
+    #[test]
+    fn conditions_follow_size_and_age_and_slow_travel() {
+        let settings = GenerationSettings {
+            seed: 7,
+            eras: 3,
+            ..GenerationSettings::default()
+        };
+        let map = generate(96, 96, &settings, None);
+        let conditions = road_conditions(&map);
+        assert_eq!(conditions, road_conditions(&map));
+        let current = map.history.current_era();
+        for (&(a, b), &condition) in map.road_graph.iter().zip(&conditions) {
+            let age = current - map.history.road_era((a, b));
+            let primary = [a, b]
+                .iter()
+                .all(|&id| map.settlement(id).unwrap().size >= settings.road_paved_size);
+            assert_eq!(condition as u32, (age + !primary as u32).min(2));
+        }
+
+        // Each cell keeps its condition's share of the cart road discount.
+        let cart = Profile::preset("cart").unwrap();
+        let roadless = build_cost_field(
+            &map,
+            &Profile {
+                roads: 1.0,
+                ..cart.clone()
+            },
+        );
+        let costs = build_cost_field(&map, &cart);
+        let mask = map.road_condition_mask();
+        let mut seen = Vec::new();
+        for (index, condition) in mask.iter().enumerate() {
+            let Some(condition) = condition else {
+                assert_eq!(costs[index], roadless[index]);
+                continue;
+            };
+            if map.is_river(index) || map.is_water_body(index) {
+                continue;
+            }
+            let factor = 1.0 + (cart.roads - 1.0) * condition.speed_share();
+            assert!((costs[index] - roadless[index] * factor).abs() < 1e-4);
+            seen.push(*condition);
+        }
+        seen.sort_unstable();
+        seen.dedup();
+        assert!(seen.len() >= 2, "{seen:?}");
+
+        // A single era falls back to distance from settlements.
+        let young = generate(96, 96, &GenerationSettings::default(), None);
+        let remote = GenerationSettings {
+            road_remote_distance: 0.0,
+            ..GenerationSettings::default()
+        };
+        let stranded = generate(96, 96, &remote, None);
+        for (fresh, far) in road_conditions(&young)
+            .iter()
+            .zip(road_conditions(&stranded))
+        {
+            assert_eq!(far, fresh.downgraded(1));
+        }
+    }
+}
@@ -0,0 +1,237 @@
+#[cfg(feature = "wasm")]
+use js_sys::Float32Array;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::MapResult;
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Terrain ruggedness: standard deviation of elevation about the local
+    /// best-fit plane over the `(2 * radius + 1)²` window around each cell,
+    /// clipped at the map edges and normalized so the most rugged cell is 1.0.
+    /// Unlike slope, an evenly tilted plain scores zero.
+    pub fn roughness(&self, radius: u32) -> Float32Array {
+        let roughness = roughness(
+            &self.heightmap,
+            self.width as usize,
+            self.height as usize,
+            radius as usize,
+        );
+        Float32Array::from(roughness.as_slice())
+    }
+}
+
+/// Windowed standard deviation about the best-fit plane, from summed-area
+/// tables of `z`, `z²`, `x·z`, and `y·z`, so the cost is independent of
+/// `radius`. Removing the plane is what keeps a steady incline smooth.
+pub(crate) fn roughness(values: &[f32], width: usize, height: usize, radius: usize) -> Vec<f32> {
+    // Tables carry an extra leading row and column of zeros.
+    let stride = width + 1;
+    let mut tables = [(); 4].map(|_| vec![0.0f64; stride * (height + 1)]);
+    for y in 0..height {
+        let mut row = [0.0f64; 4];
+        for x in 0..width {
+            let z = values[y * width + x] as f64;
+            let terms = [z, z * z, x as f64 * z, y as f64 * z];
+            for (table, (row, term)) in tables.iter_mut().zip(row.iter_mut().zip(terms)) {
+                *row += term;
+                table[(y + 1) * stride + x + 1] = table[y * stride + x + 1] + *row;
+            }
+        }
+    }
+    let window = |table: &[f64], (x0, y0, x1, y1): (usize, usize, usize, usize)| {
+        table[y1 * stride + x1] - table[y0 * stride + x1] - table[y1 * stride + x0]
+            + table[y0 * stride + x0]
+    };
+    // Mean and variance of the integers `lo..hi`.
+    let span = |lo: usize, hi: usize| {
+        let n = (hi - lo) as f64;
+        let mean = (lo + hi - 1) as f64 * 0.5;
+        (mean, (n * n - 1.0) / 12.0)
+    };
+
+    let mut deviation = vec![0.0f32; values.len()];
+    for y in 0..height {
+        let (y0, y1) = (y.saturating_sub(radius), (y + radius + 1).min(height));
+        let (mean_y, var_y) = span(y0, y1);
+        for x in 0..width {
+            let (x0, x1) = (x.saturating_sub(radius), (x + radius + 1).min(width));
+            let (mean_x, var_x) = span(x0, x1);
+            let rect = (x0, y0, x1, y1);
+            let count = ((x1 - x0) * (y1 - y0)) as f64;
+            let mean = window(&tables[0], rect) / count;
+            let mut variance = window(&tables[1], rect) / count - mean * mean;
+            // The x and y of a full rectangle are uncorrelated, so each
+            // axis's share of the plane fit comes off independently.
+            if var_x > 0.0 {
+                let cov_x = window(&tables[2], rect) / count - mean_x * mean;
+                variance -= cov_x * cov_x / var_x;
+            }
+            if var_y > 0.0 {
+                let cov_y = window(&tables[3], rect) / count - mean_y * mean;
+                variance -= cov_y * cov_y / var_y;
+            }
+            deviation[y * width + x] = variance.max(0.0).sqrt() as f32;
+        }
+    }
+    let peak = deviation.iter().copied().fold(0.0f32, f32::max);
+    if peak > 0.0 {
+        for value in &mut deviation {
+            *value /= peak;
+        }
+    }
+    deviation
+}
+
+/// Mean over the `(2 * radius + 1)²` window around each cell, clipped at the
+/// map edges, from one summed-area table.
+pub(crate) fn local_mean(values: &[f32], width: usize, height: usize, radius: usize) -> Vec<f32> {
+    let stride = width + 1;
+    let mut table = vec![0.0f64; stride * (height + 1)];
+    for y in 0..height {
+        let mut row = 0.0f64;
+        for x in 0..width {
+            row += values[y * width + x] as f64;
+            table[(y + 1) * stride + x + 1] = table[y * stride + x + 1] + row;
+        }
+    }
+    let mut mean = vec![0.0f32; values.len()];
+    for y in 0..height {
+        let (y0, y1) = (y.saturating_sub(radius), (y + radius + 1).min(height));
+        for x in 0..width {
+            let (x0, x1) = (x.saturating_sub(radius), (x + radius + 1).min(width));
+            let sum = table[y1 * stride + x1] - table[y0 * stride + x1] - table[y1 * stride + x0]
+                + table[y0 * stride + x0];
+            mean[y * width + x] = (sum / ((x1 - x0) * (y1 - y0)) as f64) as f32;
+        }
+    }
+    mean
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{local_mean, roughness};
+
+    const WIDTH: usize = 13;
+    const HEIGHT: usize = 9;
+
+    /// Rough but deterministic heights from an integer hash.
+    fn bumpy() -> Vec<f32> {
+        (0..WIDTH * HEIGHT)
+            .map(|index| {
+                let hash = (index as u32 ^ 0x9e37).wrapping_mul(0x2c1b_3c6d) >> 16;
+                (hash % 1000) as f32 / 1000.0 + (index % WIDTH) as f32 * 0.05
+            })
+            .collect()
+    }
+
+    /// Cells of the clipped window around `(x, y)` as `(x, y, z)`.
+    fn window(values: &[f32], x: usize, y: usize, radius: usize) -> Vec<(f64, f64, f64)> {
+        let mut cells = Vec::new();
+        for wy in y.saturating_sub(radius)..(y + radius + 1).min(HEIGHT) {
+            for wx in x.saturating_sub(radius)..(x + radius + 1).min(WIDTH) {
+                cells.push((wx as f64, wy as f64, values[wy * WIDTH + wx] as f64));
+            }
+        }
+        cells
+    }
+
+    /// Residual standard deviation of a least-squares plane, by solving the
+    /// normal equations directly.
+    fn plane_residual(cells: &[(f64, f64, f64)]) -> f64 {
+        let mut matrix = [[0.0f64; 4]; 3];
+        for &(x, y, z) in cells {
+            let basis = [1.0, x, y];
+            for (row, &a) in matrix.iter_mut().zip(&basis) {
+                for (entry, &b) in row.iter_mut().zip(&basis) {
+                    *entry += a * b;
+                }
+                row[3] += a * z;
+            }
+        }
+        // Gauss-Jordan with partial pivoting; a single row or column makes
+        // the system singular, so degenerate pivots are dropped.
+        let mut solution = [0.0f64; 3];
+        for column in 0..3 {
+            let pivot = (column..3)
+                .max_by(|&a, &b| matrix[a][column].abs().total_cmp(&matrix[b][column].abs()))
+                .unwrap();
+            matrix.swap(column, pivot);
+            if matrix[column][column].abs() < 1e-9 {
+                continue;
+            }
+            let lead = matrix[column];
+            for (row, entries) in matrix.iter_mut().enumerate() {
+                if row != column {
+                    let factor = entries[column] / lead[column];
+                    for (entry, &value) in entries.iter_mut().zip(&lead) {
+                        *entry -= factor * value;
+                    }
+                }
+            }
+        }
+        for (column, value) in solution.iter_mut().enumerate() {
+            let lead = matrix[column][column];
+            if lead.abs() >= 1e-9 {
+                *value = matrix[column][3] / lead;
+            }
+        }
+        let squares: f64 = cells
+            .iter()
+            .map(|&(x, y, z)| (z - solution[0] - solution[1] * x - solution[2] * y).powi(2))
+            .sum();
+        (squares / cells.len() as f64).sqrt()
+    }
+
+    #[test]
+    fn local_mean_matches_a_brute_force_window() {
+        let values = bumpy();
+        for radius in [0, 1, 3, 20] {
+            let mean = local_mean(&values, WIDTH, HEIGHT, radius);
+            for (index, &got) in mean.iter().enumerate() {
+                let cells = window(&values, index % WIDTH, index / WIDTH, radius);
+                let expected = cells.iter().map(|cell| cell.2).sum::<f64>() / cells.len() as f64;
+                assert!(
+                    (got as f64 - expected).abs() < 1e-5,
+                    "radius {radius} cell {index}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn roughness_matches_a_brute_force_plane_fit() {
+        let values = bumpy();
+        for radius in [1, 2, 5, 20] {
+            let expected: Vec<f64> = (0..values.len())
+                .map(|index| plane_residual(&window(&values, index % WIDTH, index / WIDTH, radius)))
+                .collect();
+            let peak = expected.iter().copied().fold(0.0, f64::max);
+            let rough = roughness(&values, WIDTH, HEIGHT, radius);
+            for (index, (&got, &want)) in rough.iter().zip(&expected).enumerate() {
+                assert!(
+                    (got as f64 - want / peak).abs() < 1e-4,
+                    "radius {radius} cell {index}: {got} vs {}",
+                    want / peak
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn adding_a_tilt_leaves_roughness_unchanged() {
+        let values = bumpy();
+        let tilted: Vec<f32> = values
+            .iter()
+            .enumerate()
+            .map(|(index, &z)| z + (index % WIDTH) as f32 * 0.03 - (index / WIDTH) as f32 * 0.02)
+            .collect();
+        let flat = roughness(&values, WIDTH, HEIGHT, 2);
+        let rough = roughness(&tilted, WIDTH, HEIGHT, 2);
+        for (index, (&a, &b)) in flat.iter().zip(&rough).enumerate() {
+            assert!((a - b).abs() < 1e-4, "cell {index}: {a} vs {b}");
+        }
+    }
+}
@@ -0,0 +1,221 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::biome::Biome;
+use crate::geometry::distance;
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::poi::{Poi, PoiKind};
+use crate::{cell_water, MapResult, SiteLayers, SITE_THRESHOLD};
+
+/// Why a settlement was given up, read off the change between the climate it
+/// was founded in and the present one.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum RuinReason {
+    /// The site now lies under the sea or a lake.
+    Flooded,
+    /// The land dried out below what a settlement needs.
+    Desertified,
+    /// The site is still habitable; people simply left.
+    Abandoned,
+}
+
+impl RuinReason {
+    pub(crate) fn key(self) -> &'static str {
+        match self {
+            RuinReason::Flooded => "flooded",
+            RuinReason::Desertified => "desertified",
+            RuinReason::Abandoned => "abandoned",
+        }
+    }
+}
+
+pub(crate) struct RuinOptions {
+    /// Sea level of the past climate relative to today's; negative exposes
+    /// coasts that have since drowned.
+    pub sea_level_shift: f32,
+    /// Factor on today's moisture giving the past moisture.
+    pub moisture_factor: f32,
+    /// World distance kept from settlements and other ruins.
+    pub spacing: f32,
+    pub max_count: u32,
+}
+
+impl Default for RuinOptions {
+    fn default() -> Self {
+        Self {
+            sea_level_shift: -0.04,
+            moisture_factor: 1.3,
+            spacing: 160.0,
+            max_count: 6,
+        }
+    }
+}
+
+impl RuinOptions {
+    #[cfg(feature = "wasm")]
+    fn from_js(options: &JsValue) -> Self {
+        let defaults = Self::default();
+        Self {
+            sea_level_shift: js::get_f32(options, "sea_level_shift", defaults.sea_level_shift),
+            moisture_factor: js::get_f32(options, "moisture_factor", defaults.moisture_factor)
+                .max(0.0),
+            spacing: js::get_f32(options, "spacing", defaults.spacing).max(0.0),
+            max_count: js::get_u32(options, "max_count", defaults.max_count),
+        }
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Adds ruin POIs where settlements would have stood in a past climate:
+    /// today's sea level moved by `sea_level_shift` (default -0.04) and
+    /// moisture scaled by `moisture_factor` (default 1.3). The best past
+    /// sites at least `spacing` (default 160 world units) from settlements
+    /// and ruins are kept, up to `max_count` (default 6). Each ruin's
+    /// `reason` is `"flooded"`, `"desertified"`, or `"abandoned"`, from how
+    /// its cell changed since. Returns the number placed.
+    pub fn place_historical_ruins(&mut self, options: JsValue) -> u32 {
+        self.add_historical_ruins(&RuinOptions::from_js(&options))
+    }
+}
+
+impl MapResult {
+    pub(crate) fn add_historical_ruins(&mut self, options: &RuinOptions) -> u32 {
+        historical_sites(self, options)
+            .into_iter()
+            .filter_map(|(index, reason)| {
+                let (x, y) = self.cell_to_world(index);
+                self.register_poi(Poi {
+                    id: 0,
+                    kind: PoiKind::Ruin,
+                    x,
+                    y,
+                    era: 0,
+                    reason: Some(reason),
+                    coverage: None,
+                })
+            })
+            .count() as u32
+    }
+}
+
+/// Cells scored as settlement sites under the past climate, best first,
+/// each with the reason it no longer holds one.
+pub(crate) fn historical_sites(map: &MapResult, options: &RuinOptions) -> Vec<(usize, RuinReason)> {
+    let sea_level = (map.sea_level + options.sea_level_shift).clamp(0.0, 1.0);
+    let max_flow = map.flow.iter().fold(0.0f32, |acc, &v| acc.max(v));
+    let water: Vec<f32> = map
+        .heightmap
+        .iter()
+        .zip(&map.flow)
+        .map(|(&height, &flow)| cell_water(height, flow, max_flow, sea_level))
+        .collect();
+    let moisture: Vec<f32> = map
+        .moisture
+        .iter()
+        .map(|&moisture| (moisture * options.moisture_factor).min(1.0))
+        .collect();
+    let width = map.width as usize;
+    let past = SiteLayers {
+        heightmap: &map.heightmap,
+        water: &water,
+        moisture: &moisture,
+        width,
+        height: map.height as usize,
+        sea_level,
+    };
+    let present = SiteLayers {
+        heightmap: &map.heightmap,
+        water: &map.water,
+        moisture: &map.moisture,
+        width,
+        height: map.height as usize,
+        sea_level: map.sea_level,
+    };
+
+    let mut taken: Vec<(f32, f32)> = map
+        .settlements
+        .iter()
+        .map(|settlement| (settlement.x, settlement.y))
+        .chain(
+            map.pois
+                .iter()
+                .filter(|poi| poi.kind == PoiKind::Ruin)
+                .map(|poi| (poi.x, poi.y)),
+        )
+        .collect();
+    let mut sites = Vec::new();
+    for (index, _) in past.candidates(map.settings.seed, 0.0) {
+        if sites.len() >= options.max_count as usize {
+            break;
+        }
+        let (x, y) = map.cell_to_world(index);
+        if taken
+            .iter()
+            .any(|&taken| distance(taken, (x, y)) < options.spacing)
+        {
+            continue;
+        }
+        let reason = match present.score(index % width, index / width) {
+            None if map.is_water_body(index) || map.heightmap[index] <= map.sea_level => {
+                RuinReason::Flooded
+            }
+            Some(score) if score > SITE_THRESHOLD => RuinReason::Abandoned,
+            _ if map.biome[index] == Biome::Desert.code()
+                || map.moisture[index] < moisture[index] =>
+            {
+                RuinReason::Desertified
+            }
+            _ => RuinReason::Abandoned,
+        };
+        taken.push((x, y));
+        sites.push((index, reason));
+    }
+    sites
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{historical_sites, RuinOptions, RuinReason};
+    use crate::generate_map;
+
+    #[test]
+    fn ruins_keep_clear_of_towns_and_explain_themselves() {
+        let mut map = generate_map(96, 96, 8, 0.42, 1.0, 40.0, 2, 1.0);
+        let drowned = RuinOptions {
+            sea_level_shift: -0.1,
+            moisture_factor: 1.0,
+            max_count: 40,
+            ..RuinOptions::default()
+        };
+        let sites = historical_sites(&map, &drowned);
+        assert!(sites
+            .iter()
+            .any(|&(_, reason)| reason == RuinReason::Flooded));
+        for &(index, reason) in &sites {
+            let (x, y) = map.cell_to_world(index);
+            assert!(map
+                .settlements
+                .iter()
+                .all(|s| (s.x - x).hypot(s.y - y) >= drowned.spacing));
+            if reason == RuinReason::Flooded {
+                assert!(map.is_water_body(index) || map.heightmap[index] <= map.sea_level);
+            }
+        }
+
+        let before = map.pois.len();
+        let placed = map.add_historical_ruins(&RuinOptions::default());
+        assert_eq!(map.pois.len(), before + placed as usize);
+        assert!(map.pois[before..].iter().all(|poi| poi.reason.is_some()));
+        // Ruins already placed keep later calls away.
+        let again = historical_sites(&map, &RuinOptions::default());
+        for &(index, _) in &again {
+            let (x, y) = map.cell_to_world(index);
+            assert!(map.pois[before..]
+                .iter()
+                .all(|poi| (poi.x - x).hypot(poi.y - y) >= 160.0));
+        }
+    }
+}
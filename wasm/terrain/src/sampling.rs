@@ -0,0 +1,306 @@
+#[cfg(feature = "wasm")]
+use js_sys::{Float32Array, Object};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::distance::chamfer_distance;
+use crate::geometry::distance;
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::{slope_map, MapResult, SimpleRng, REGION_SIZE};
+
+/// Random draws per requested point before falling back to an exhaustive pass.
+const ATTEMPTS_PER_POINT: u32 = 40;
+/// Most spacing buckets along each side of the region.
+const MAX_SPACING_BUCKETS: usize = 256;
+
+pub(crate) struct PositionConstraints {
+    pub biomes: Option<[bool; 256]>,
+    pub elevation: (f32, f32),
+    pub max_slope: f32,
+    pub min_settlement_distance: f32,
+    pub min_road_distance: f32,
+    pub min_water_distance: f32,
+    pub min_spacing: f32,
+    pub allow_water: bool,
+}
+
+impl Default for PositionConstraints {
+    fn default() -> Self {
+        Self {
+            biomes: None,
+            elevation: (0.0, 1.0),
+            max_slope: 1.0,
+            min_settlement_distance: 0.0,
+            min_road_distance: 0.0,
+            min_water_distance: 0.0,
+            min_spacing: 0.0,
+            allow_water: false,
+        }
+    }
+}
+
+impl PositionConstraints {
+    #[cfg(feature = "wasm")]
+    fn from_js(options: &JsValue) -> Result<Self, JsValue> {
+        let defaults = Self::default();
+        let biomes = match js::get_array(options, "biomes") {
+            Some(codes) => {
+                let mut allowed = [false; 256];
+                for code in codes.iter() {
+                    let code = code
+                        .as_f64()
+                        .filter(|code| (0.0..256.0).contains(code))
+                        .ok_or_else(|| JsValue::from_str("biome codes must be 0..=255"))?;
+                    allowed[code as usize] = true;
+                }
+                Some(allowed)
+            }
+            None => None,
+        };
+        Ok(Self {
+            biomes,
+            elevation: (
+                js::get_f32(options, "elevation_min", defaults.elevation.0),
+                js::get_f32(options, "elevation_max", defaults.elevation.1),
+            ),
+            max_slope: js::get_f32(options, "max_slope", defaults.max_slope),
+            min_settlement_distance: js::get_f32(options, "min_settlement_distance", 0.0),
+            min_road_distance: js::get_f32(options, "min_road_distance", 0.0),
+            min_water_distance: js::get_f32(options, "min_water_distance", 0.0),
+            min_spacing: js::get_f32(options, "min_spacing", 0.0),
+            allow_water: js::get_bool(options, "allow_water", false),
+        })
+    }
+}
+
+pub(crate) struct SampledPositions {
+    pub points: Vec<f32>,
+    /// Whether the exhaustive fallback pass was needed.
+    pub exhaustive: bool,
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Draws up to `count` random world positions satisfying `constraints`
+    /// (`biomes`, `elevation_min`/`elevation_max`, `max_slope`,
+    /// `min_settlement_distance`, `min_road_distance`, `min_water_distance`,
+    /// `min_spacing`, `allow_water`). Returns `{ points, requested, found,
+    /// exhaustive }`, where `points` holds interleaved world `x, y` and `found`
+    /// is below `requested` when the map lacks qualifying area.
+    pub fn sample_positions(
+        &self,
+        count: u32,
+        constraints: JsValue,
+        seed: u32,
+    ) -> Result<Object, JsValue> {
+        let constraints = PositionConstraints::from_js(&constraints)?;
+        let sampled = sample_positions(self, count, &constraints, seed);
+        let result = Object::new();
+        js::set(
+            &result,
+            "points",
+            &Float32Array::from(sampled.points.as_slice()).into(),
+        );
+        js::set(&result, "requested", &JsValue::from(count));
+        js::set(
+            &result,
+            "found",
+            &JsValue::from((sampled.points.len() / 2) as u32),
+        );
+        js::set(&result, "exhaustive", &JsValue::from(sampled.exhaustive));
+        Ok(result)
+    }
+}
+
+/// Accepted points bucketed by at least `min_spacing`, so a spacing check
+/// reads only the 3x3 buckets around the candidate.
+struct SpacingGrid {
+    spacing: f32,
+    bucket: f32,
+    columns: usize,
+    buckets: Vec<Vec<(f32, f32)>>,
+}
+
+impl SpacingGrid {
+    fn new(spacing: f32) -> Self {
+        let bucket = spacing.max(REGION_SIZE / MAX_SPACING_BUCKETS as f32);
+        let columns = (REGION_SIZE / bucket).ceil().max(1.0) as usize;
+        SpacingGrid {
+            spacing,
+            bucket,
+            columns,
+            buckets: vec![Vec::new(); columns * columns],
+        }
+    }
+
+    fn bucket_of(&self, x: f32, y: f32) -> (usize, usize) {
+        let clamp = |value: f32| ((value / self.bucket) as usize).min(self.columns - 1);
+        (clamp(x), clamp(y))
+    }
+
+    /// Whether `(x, y)` keeps `spacing` from every inserted point.
+    fn clear(&self, x: f32, y: f32) -> bool {
+        let (bx, by) = self.bucket_of(x, y);
+        let rows = by.saturating_sub(1)..=(by + 1).min(self.columns - 1);
+        rows.flat_map(|row| {
+            (bx.saturating_sub(1)..=(bx + 1).min(self.columns - 1))
+                .map(move |column| row * self.columns + column)
+        })
+        .all(|bucket| {
+            self.buckets[bucket]
+                .iter()
+                .all(|&point| distance(point, (x, y)) >= self.spacing)
+        })
+    }
+
+    fn insert(&mut self, x: f32, y: f32) {
+        let (bx, by) = self.bucket_of(x, y);
+        self.buckets[by * self.columns + bx].push((x, y));
+    }
+}
+
+pub(crate) fn sample_positions(
+    map: &MapResult,
+    count: u32,
+    constraints: &PositionConstraints,
+    seed: u32,
+) -> SampledPositions {
+    let qualifies = qualifying_cells(map, constraints);
+    let qualifying = qualifies.iter().filter(|&&qualifies| qualifies).count();
+    let cell_w = REGION_SIZE / map.width as f32;
+    let cell_h = REGION_SIZE / map.height as f32;
+    let mut rng = SimpleRng::new(seed);
+    let mut points: Vec<f32> = Vec::with_capacity((count as usize).min(qualifying) * 2);
+    let mut spacing =
+        (constraints.min_spacing > 0.0).then(|| SpacingGrid::new(constraints.min_spacing));
+
+    let mut accept = |points: &mut Vec<f32>, x: f32, y: f32| {
+        if constraints.min_settlement_distance > 0.0
+            && map.settlements.iter().any(|settlement| {
+                distance((settlement.x, settlement.y), (x, y)) < constraints.min_settlement_distance
+            })
+        {
+            return;
+        }
+        if let Some(grid) = &mut spacing {
+            if !grid.clear(x, y) {
+                return;
+            }
+            grid.insert(x, y);
+        }
+        points.extend_from_slice(&[x, y]);
+    };
+    // Points jitter within half a cell of the cell's sample position.
+    let place = |rng: &mut SimpleRng, index: usize| {
+        let (x, y) = map.cell_to_world(index);
+        let x = (x + (rng.next_f32() - 0.5) * cell_w).clamp(0.0, REGION_SIZE);
+        let y = (y + (rng.next_f32() - 0.5) * cell_h).clamp(0.0, REGION_SIZE);
+        (x, y)
+    };
+
+    let attempts = count.saturating_mul(ATTEMPTS_PER_POINT);
+    for _ in 0..attempts {
+        if points.len() / 2 >= count as usize {
+            break;
+        }
+        let index = (rng.next_u32() as usize) % qualifies.len();
+        if !qualifies[index] {
+            continue;
+        }
+        let (x, y) = place(&mut rng, index);
+        accept(&mut points, x, y);
+    }
+
+    let exhaustive = points.len() / 2 < count as usize;
+    if exhaustive {
+        let mut candidates: Vec<usize> = (0..qualifies.len()).filter(|&i| qualifies[i]).collect();
+        for i in (1..candidates.len()).rev() {
+            let j = (rng.next_u32() as usize) % (i + 1);
+            candidates.swap(i, j);
+        }
+        for index in candidates {
+            if points.len() / 2 >= count as usize {
+                break;
+            }
+            let (x, y) = map.cell_to_world(index);
+            accept(&mut points, x, y);
+        }
+    }
+
+    SampledPositions { points, exhaustive }
+}
+
+/// Cell-level constraints; distance-to-settlement and spacing are checked per point.
+fn qualifying_cells(map: &MapResult, constraints: &PositionConstraints) -> Vec<bool> {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let slopes = (constraints.max_slope < 1.0).then(|| slope_map(&map.heightmap, width, height));
+    let road_distance = (constraints.min_road_distance > 0.0).then(|| map.road_distance());
+    let water_distance = (constraints.min_water_distance > 0.0).then(|| {
+        let wet: Vec<bool> = map.water.iter().map(|&water| water > 0.0).collect();
+        chamfer_distance(&wet, width, height)
+    });
+
+    (0..map.heightmap.len())
+        .map(|index| {
+            if !constraints.allow_water && map.water[index] > 0.0 {
+                return false;
+            }
+            if let Some(allowed) = &constraints.biomes {
+                if !allowed[map.biome[index] as usize] {
+                    return false;
+                }
+            }
+            let elevation = map.heightmap[index];
+            if elevation < constraints.elevation.0 || elevation > constraints.elevation.1 {
+                return false;
+            }
+            if slopes
+                .as_ref()
+                .is_some_and(|slopes| slopes[index] > constraints.max_slope)
+            {
+                return false;
+            }
+            if road_distance
+                .as_ref()
+                .is_some_and(|d| d[index] < constraints.min_road_distance)
+            {
+                return false;
+            }
+            !water_distance
+                .as_ref()
+                .is_some_and(|d| d[index] < constraints.min_water_distance)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sample_positions, PositionConstraints};
+    use crate::generate_map;
+    use crate::geometry::distance;
+
+    #[test]
+    fn spaced_samples_keep_their_distance() {
+        let map = generate_map(64, 64, 11, 0.42, 1.0, 40.0, 2, 1.0);
+        let constraints = PositionConstraints {
+            min_spacing: 90.0,
+            ..PositionConstraints::default()
+        };
+        let sampled = sample_positions(&map, 10_000, &constraints, 3);
+        assert!(sampled.exhaustive);
+        let points: Vec<(f32, f32)> = sampled
+            .points
+            .chunks_exact(2)
+            .map(|p| (p[0], p[1]))
+            .collect();
+        assert!(points.len() > 20);
+        for (position, &a) in points.iter().enumerate() {
+            for &b in &points[position + 1..] {
+                assert!(distance(a, b) >= constraints.min_spacing);
+            }
+        }
+    }
+}
@@ -0,0 +1,415 @@
+#[cfg(feature = "wasm")]
+use js_sys::Array;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::geometry::distance;
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::json::Json;
+use crate::{settlement_site, MapResult, SiteLayers, REGION_SIZE};
+
+/// Snaps longer than this many world units are reported as warnings.
+pub(crate) const SNAP_WARNING_DISTANCE: f32 = 32.0;
+
+/// A settlement placed by the caller before any procedural ones.
+#[derive(Clone)]
+pub struct ForcedSettlement {
+    /// World coordinates, `0..REGION_SIZE` on both axes.
+    pub x: f32,
+    pub y: f32,
+    pub size: f32,
+    /// Overrides the culture's generated name.
+    pub name: Option<String>,
+}
+
+/// Ground procedural settlements stay out of, in world coordinates.
+#[derive(Clone)]
+pub enum ExclusionZone {
+    Circle {
+        x: f32,
+        y: f32,
+        radius: f32,
+    },
+    /// Vertices in order; the edge back to the first is implied.
+    Polygon(Vec<(f32, f32)>),
+}
+
+impl ForcedSettlement {
+    #[cfg(feature = "wasm")]
+    fn from_js(value: &JsValue) -> Result<Self, String> {
+        let coordinate = |key: &str| {
+            js::get(value, key)
+                .and_then(|value| value.as_f64())
+                .map(|value| value as f32)
+                .ok_or_else(|| format!("forced settlements need a numeric {key}"))
+        };
+        Ok(Self {
+            x: coordinate("x")?,
+            y: coordinate("y")?,
+            size: js::get_f32(value, "size", 3.0).max(0.0),
+            name: js::get_string(value, "name"),
+        })
+    }
+
+    pub(crate) fn to_json(&self) -> Json {
+        Json::object()
+            .with("x", self.x)
+            .with("y", self.y)
+            .with("size", self.size)
+            .with("name", self.name.clone().map_or(Json::Null, Json::from))
+    }
+}
+
+impl ExclusionZone {
+    /// Reads `{ x, y, radius }` circles or `{ points }` polygons, with
+    /// `points` interleaved `x, y` world coordinates.
+    #[cfg(feature = "wasm")]
+    fn from_js(value: &JsValue) -> Result<Self, String> {
+        if let Some(points) = js::get_array(value, "points") {
+            let coordinates: Vec<f32> = points
+                .iter()
+                .map(|value| value.as_f64().map(|value| value as f32))
+                .collect::<Option<_>>()
+                .ok_or("exclusion polygon points must be numbers")?;
+            if coordinates.len() < 6 || !coordinates.len().is_multiple_of(2) {
+                return Err("exclusion polygons need at least 3 x, y pairs".to_string());
+            }
+            return Ok(ExclusionZone::Polygon(
+                coordinates
+                    .chunks_exact(2)
+                    .map(|pair| (pair[0], pair[1]))
+                    .collect(),
+            ));
+        }
+        match (
+            js::get(value, "x").and_then(|value| value.as_f64()),
+            js::get(value, "y").and_then(|value| value.as_f64()),
+            js::get(value, "radius").and_then(|value| value.as_f64()),
+        ) {
+            (Some(x), Some(y), Some(radius)) => Ok(ExclusionZone::Circle {
+                x: x as f32,
+                y: y as f32,
+                radius: radius as f32,
+            }),
+            _ => Err("exclusion zones need { x, y, radius } or { points }".to_string()),
+        }
+    }
+
+    pub(crate) fn to_json(&self) -> Json {
+        match self {
+            ExclusionZone::Circle { x, y, radius } => Json::object()
+                .with("x", *x)
+                .with("y", *y)
+                .with("radius", *radius),
+            ExclusionZone::Polygon(points) => Json::object().with(
+                "points",
+                points
+                    .iter()
+                    .flat_map(|&(x, y)| [Json::from(x), Json::from(y)])
+                    .collect::<Vec<_>>(),
+            ),
+        }
+    }
+
+    /// Whether a world position lies inside, by the even-odd rule for
+    /// polygons.
+    pub(crate) fn contains(&self, x: f32, y: f32) -> bool {
+        match self {
+            ExclusionZone::Circle {
+                x: center_x,
+                y: center_y,
+                radius,
+            } => distance((x, y), (*center_x, *center_y)) <= *radius,
+            ExclusionZone::Polygon(points) => {
+                let mut inside = false;
+                let mut previous = points[points.len() - 1];
+                for &point in points {
+                    if (point.1 > y) != (previous.1 > y) {
+                        let crossing = point.0
+                            + (y - point.1) / (previous.1 - point.1) * (previous.0 - point.0);
+                        if x < crossing {
+                            inside = !inside;
+                        }
+                    }
+                    previous = point;
+                }
+                inside
+            }
+        }
+    }
+}
+
+/// Forced settlements and exclusion zones, each `None` when not given.
+#[cfg(feature = "wasm")]
+type Scenario = (Option<Vec<ForcedSettlement>>, Option<Vec<ExclusionZone>>);
+
+/// Reads `options.forced_settlements` and `options.exclusion_zones`.
+#[cfg(feature = "wasm")]
+pub(crate) fn from_options(options: &JsValue) -> Result<Scenario, JsValue> {
+    fn parse<T>(
+        list: Option<Array>,
+        read: impl Fn(&JsValue) -> Result<T, String>,
+    ) -> Result<Option<Vec<T>>, JsValue> {
+        list.map(|list| list.iter().map(|value| read(&value)).collect())
+            .transpose()
+            .map_err(|message| JsValue::from_str(&message))
+    }
+    Ok((
+        parse(
+            js::get_array(options, "forced_settlements"),
+            ForcedSettlement::from_js,
+        )?,
+        parse(
+            js::get_array(options, "exclusion_zones"),
+            ExclusionZone::from_js,
+        )?,
+    ))
+}
+
+/// Where one forced settlement ended up.
+#[derive(Clone)]
+pub(crate) struct Snap {
+    /// Position in `forced_settlements`.
+    pub index: usize,
+    pub from: (f32, f32),
+    /// `None` when no settlement site lies within `snap_radius`.
+    pub to: Option<(f32, f32)>,
+}
+
+impl Snap {
+    pub(crate) fn distance(&self) -> Option<f32> {
+        self.to.map(|to| distance(self.from, to))
+    }
+
+    pub(crate) fn to_json(&self) -> Json {
+        let distance = self.distance();
+        Json::object()
+            .with("index", self.index)
+            .with("x", self.from.0)
+            .with("y", self.from.1)
+            .with("snapped_x", self.to.map_or(Json::Null, |(x, _)| x.into()))
+            .with("snapped_y", self.to.map_or(Json::Null, |(_, y)| y.into()))
+            .with("distance", distance.map_or(Json::Null, Json::from))
+            .with(
+                "far",
+                distance.is_some_and(|distance| distance > SNAP_WARNING_DISTANCE),
+            )
+    }
+}
+
+/// The requested position when its cell can hold a settlement, else the
+/// nearest cell that can within `radius` world units, ties to the lower
+/// index.
+pub(crate) fn snap_site(layers: &SiteLayers, x: f32, y: f32, radius: f32) -> Option<(f32, f32)> {
+    let (width, height) = (layers.width, layers.height);
+    let to_cell = |world: f32, cells: usize| {
+        (world / REGION_SIZE * cells as f32)
+            .round()
+            .clamp(0.0, (cells - 1) as f32) as usize
+    };
+    let fits = |cell_x: usize, cell_y: usize| {
+        settlement_site(
+            layers.heightmap,
+            layers.water,
+            width,
+            height,
+            cell_x,
+            cell_y,
+            layers.sea_level,
+        )
+        .is_some()
+    };
+    let (center_x, center_y) = (to_cell(x, width), to_cell(y, height));
+    if (0.0..=REGION_SIZE).contains(&x)
+        && (0.0..=REGION_SIZE).contains(&y)
+        && fits(center_x, center_y)
+    {
+        return Some((x, y));
+    }
+    let reach = (radius.max(0.0) / REGION_SIZE * width.max(height) as f32).ceil() as usize + 1;
+    let mut best: Option<(f32, (f32, f32))> = None;
+    for cell_y in center_y.saturating_sub(reach)..=(center_y + reach).min(height - 1) {
+        for cell_x in center_x.saturating_sub(reach)..=(center_x + reach).min(width - 1) {
+            let world = (
+                cell_x as f32 / width as f32 * REGION_SIZE,
+                cell_y as f32 / height as f32 * REGION_SIZE,
+            );
+            let distance = distance(world, (x, y));
+            if distance > radius || best.is_some_and(|(closest, _)| distance >= closest) {
+                continue;
+            }
+            if fits(cell_x, cell_y) {
+                best = Some((distance, world));
+            }
+        }
+    }
+    best.map(|(_, world)| world)
+}
+
+/// `map`, or the error listing the forced settlements that found no site.
+#[cfg(feature = "wasm")]
+pub(crate) fn check_placements(map: MapResult) -> Result<MapResult, JsValue> {
+    let failed: Vec<Json> = map
+        .placements
+        .iter()
+        .filter(|snap| snap.to.is_none())
+        .map(|snap| {
+            Json::object()
+                .with("index", snap.index)
+                .with("x", snap.from.0)
+                .with("y", snap.from.1)
+        })
+        .collect();
+    if failed.is_empty() {
+        return Ok(map);
+    }
+    Err(Json::object()
+        .with(
+            "message",
+            format!(
+                "{} forced settlements have no valid site within snap_radius",
+                failed.len()
+            ),
+        )
+        .with("failed", failed)
+        .to_js())
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Where each of `options.forced_settlements` was placed, as `{ index,
+    /// x, y, snapped_x, snapped_y, distance, far }`: the requested position,
+    /// where it landed, and how far it moved in world units. `far` flags
+    /// moves of more than 32 world units, worth a warning to the designer.
+    pub fn forced_placements(&self) -> JsValue {
+        Json::from(
+            self.placements
+                .iter()
+                .map(Snap::to_json)
+                .collect::<Vec<_>>(),
+        )
+        .to_js()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExclusionZone, ForcedSettlement, SNAP_WARNING_DISTANCE};
+    use crate::{generate, settlement_site, GenerationSettings, REGION_SIZE};
+
+    #[test]
+    fn exclusion_shapes_contain_their_interior() {
+        let square =
+            ExclusionZone::Polygon(vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]);
+        assert!(square.contains(5.0, 5.0));
+        assert!(!square.contains(15.0, 5.0));
+        assert!(!square.contains(5.0, -1.0));
+        let notch = ExclusionZone::Polygon(vec![
+            (0.0, 0.0),
+            (10.0, 0.0),
+            (5.0, 5.0),
+            (10.0, 10.0),
+            (0.0, 10.0),
+        ]);
+        assert!(!notch.contains(9.0, 5.0));
+        assert!(notch.contains(2.0, 5.0));
+        let circle = ExclusionZone::Circle {
+            x: 100.0,
+            y: 100.0,
+            radius: 20.0,
+        };
+        assert!(circle.contains(110.0, 110.0));
+        assert!(!circle.contains(120.0, 120.0));
+    }
+
+    #[test]
+    fn forced_settlements_lead_and_exclusions_hold() {
+        let plain = generate(96, 96, &GenerationSettings::default(), None);
+        let first = plain.settlements[0].clone();
+        let zone = ExclusionZone::Circle {
+            x: first.x,
+            y: first.y,
+            radius: 200.0,
+        };
+        // A valid site exactly, a sea position needing a snap, and one
+        // with nothing in reach.
+        let cell = REGION_SIZE / 96.0;
+        let fits = |index: usize| {
+            settlement_site(
+                &plain.heightmap,
+                &plain.water,
+                96,
+                96,
+                index % 96,
+                index / 96,
+                plain.sea_level,
+            )
+            .is_some()
+        };
+        let ocean = (96 * 2..plain.biome.len() - 96 * 2)
+            .find(|&index| {
+                plain.is_water_body(index) && [1, 2].iter().any(|&step| fits(index + step))
+            })
+            .unwrap();
+        let forced = vec![
+            ForcedSettlement {
+                x: first.x,
+                y: first.y,
+                size: 4.0,
+                name: Some("Harrowgate".to_string()),
+            },
+            ForcedSettlement {
+                x: plain.cell_to_world(ocean).0,
+                y: plain.cell_to_world(ocean).1,
+                size: 2.0,
+                name: None,
+            },
+            ForcedSettlement {
+                x: -500.0,
+                y: -500.0,
+                size: 2.0,
+                name: None,
+            },
+        ];
+        let settings = GenerationSettings {
+            forced_settlements: forced,
+            exclusion_zones: vec![zone.clone()],
+            snap_radius: 64.0,
+            ..GenerationSettings::default()
+        };
+        let map = generate(96, 96, &settings, None);
+
+        // Forced ones come first, exactly or snapped, and the impossible
+        // one is reported instead of placed.
+        assert_eq!(
+            (map.settlements[0].x, map.settlements[0].y),
+            (first.x, first.y)
+        );
+        assert_eq!(map.settlements[0].name.as_deref(), Some("Harrowgate"));
+        let snapped = map.placements[1].to.unwrap();
+        assert_eq!((map.settlements[1].x, map.settlements[1].y), snapped);
+        let (x, y) = map.nearest_cell(snapped.0, snapped.1);
+        assert!(fits(y * 96 + x));
+        assert!(map.placements[1].distance().unwrap() <= 64.0);
+        assert!(map.placements[2].to.is_none());
+        assert!(SNAP_WARNING_DISTANCE > cell);
+
+        // Procedural settlements stay out of the zone and away from the
+        // forced ones, and roads reach every settlement.
+        for settlement in &map.settlements[2..] {
+            assert!(!zone.contains(settlement.x, settlement.y));
+            for forced in &map.settlements[..2] {
+                assert!((settlement.x - forced.x).hypot(settlement.y - forced.y) >= 100.0);
+            }
+        }
+        assert_eq!(map.road_graph.len(), map.settlements.len() - 1);
+        for settlement in &map.settlements {
+            assert!(map
+                .road_graph
+                .iter()
+                .any(|&(a, b)| a == settlement.id || b == settlement.id));
+        }
+    }
+}
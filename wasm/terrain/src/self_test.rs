@@ -0,0 +1,384 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::biome::Biome;
+use crate::geometry::distance;
+use crate::hydrology::{aridity, ocean_spill_levels};
+#[cfg(feature = "wasm")]
+use crate::json::Json;
+#[cfg(feature = "wasm")]
+use crate::metadata::settings_record;
+use crate::registry::FeatureClass;
+use crate::{generate, GenerationSettings, MapResult, SimpleRng, REGION_SIZE};
+
+/// Smallest and largest side of a self-test map, in cells.
+const MIN_SIDE: u32 = 12;
+const MAX_SIDE: u32 = 48;
+/// Relative slack on flow conservation, for f32 sums over many cells.
+const FLOW_TOLERANCE: f32 = 1e-3;
+
+/// One broken invariant on one self-test map.
+pub(crate) struct Violation {
+    /// Position of the map in the run, from 0.
+    pub case: u32,
+    pub invariant: &'static str,
+    /// The first offending cell or item, in words.
+    pub detail: String,
+    /// How many cells or items break the invariant.
+    pub count: usize,
+    pub width: u32,
+    pub height: u32,
+    pub settings: GenerationSettings,
+}
+
+/// Settings drawn across the valid ranges: the `generate_map` arguments
+/// span the presets and beyond, and the main option switches flip at
+/// random.
+fn random_case(rng: &mut SimpleRng) -> (u32, u32, GenerationSettings) {
+    let mut between = |low: f32, high: f32| low + rng.next_f32() * (high - low);
+    let width = between(MIN_SIDE as f32, MAX_SIDE as f32 + 1.0) as u32;
+    let height = between(MIN_SIDE as f32, MAX_SIDE as f32 + 1.0) as u32;
+    let mut settings = GenerationSettings::new(
+        between(0.0, u32::MAX as f32) as u32,
+        between(0.1, 0.8),
+        between(0.2, 2.0),
+        between(0.0, 120.0),
+        between(0.0, 6.99) as u32,
+        between(0.0, 3.0),
+    );
+    let mut flip = || between(0.0, 1.0) < 0.5;
+    settings.lake_outflows = flip();
+    settings.faults = flip();
+    settings.ocean_currents = flip();
+    settings.coastal_roads = flip();
+    settings.deterministic = flip();
+    settings.eras = between(1.0, 3.99) as u32;
+    settings.incision_iterations = between(0.0, 2.99) as u32;
+    (width, height, settings)
+}
+
+/// Counts the items of `items` failing `holds`, describing the first.
+fn tally<T>(
+    items: impl Iterator<Item = T>,
+    holds: impl Fn(&T) -> bool,
+    describe: impl Fn(&T) -> String,
+) -> Option<(String, usize)> {
+    let mut first = None;
+    let mut count = 0;
+    for item in items.filter(|item| !holds(item)) {
+        first.get_or_insert_with(|| describe(&item));
+        count += 1;
+    }
+    first.map(|detail| (detail, count))
+}
+
+/// Every invariant `map` breaks, as `(invariant, first offender, count)`.
+pub(crate) fn check_invariants(map: &MapResult) -> Vec<(&'static str, String, usize)> {
+    let cells = map.heightmap.len();
+    let width = map.width as usize;
+    let at = |index: usize| format!("cell ({}, {})", index % width, index / width);
+    let mut broken = Vec::new();
+    let mut report = |invariant: &'static str, found: Option<(String, usize)>| {
+        if let Some((detail, count)) = found {
+            broken.push((invariant, detail, count));
+        }
+    };
+
+    let layers = [
+        ("heightmap", &map.heightmap, 0.0, 1.0),
+        ("moisture", &map.moisture, 0.0, 1.0),
+        ("temperature", &map.temperature, 0.0, 1.0),
+        ("water", &map.water, 0.0, 1.0),
+        ("flow", &map.flow, 1.0, cells as f32),
+    ];
+    for (name, layer, low, high) in layers {
+        report(
+            "finite",
+            tally(
+                layer.iter().enumerate(),
+                |(_, value)| value.is_finite(),
+                |&(index, value)| format!("{name} is {value} at {}", at(index)),
+            ),
+        );
+        report(
+            "range",
+            tally(
+                layer.iter().enumerate(),
+                |&(_, &value)| !value.is_finite() || (low..=high).contains(&value),
+                |&(index, value)| format!("{name} is {value} at {}", at(index)),
+            ),
+        );
+    }
+
+    report(
+        "biome",
+        tally(
+            map.biome.iter().enumerate(),
+            |&(_, &code)| Biome::from_code(code).is_some(),
+            |&(index, code)| format!("unknown biome code {code} at {}", at(index)),
+        ),
+    );
+
+    // Standing water sits at or below the sea, or below the level that
+    // fills its depression up to the point where it spills to the sea.
+    // Rivers whose runoff tops 0.6 classify as lake wherever they run; their
+    // water stays below the 1.0 of open water, and they must run downhill
+    // or off the map.
+    let spill = ocean_spill_levels(map);
+    let receivers = map.receivers();
+    let held = |index: usize| {
+        let height = map.heightmap[index];
+        if height <= map.sea_level || height < spill[index] {
+            return true;
+        }
+        (0.6..1.0).contains(&map.water[index])
+            && receivers[index].is_none_or(|next| map.heightmap[next] < height)
+    };
+    report(
+        "standing_water",
+        tally(
+            (0..cells).filter(|&index| map.is_water_body(index)),
+            |&index| held(index),
+            |&index| {
+                format!(
+                    "water at height {} with nothing to hold it at {}",
+                    map.heightmap[index],
+                    at(index)
+                )
+            },
+        ),
+    );
+
+    report(
+        "settlement_on_land",
+        tally(
+            map.settlements.iter(),
+            |settlement| {
+                let inside = (0.0..=REGION_SIZE).contains(&settlement.x)
+                    && (0.0..=REGION_SIZE).contains(&settlement.y);
+                let (x, y) = map.nearest_cell(settlement.x, settlement.y);
+                inside && !map.is_water_body(y * width + x)
+            },
+            |settlement| {
+                format!(
+                    "settlement {} at ({}, {}) is off land",
+                    settlement.id, settlement.x, settlement.y
+                )
+            },
+        ),
+    );
+
+    report(
+        "road_endpoints",
+        tally(
+            map.road_graph.iter(),
+            |&&(a, b)| a != b && map.settlement(a).is_some() && map.settlement(b).is_some(),
+            |(a, b)| format!("road {a}-{b} does not join two settlements"),
+        ),
+    );
+
+    let features = map.point_features();
+    report(
+        "feature_spacing",
+        tally(
+            features.iter().enumerate().flat_map(|(position, a)| {
+                features[position + 1..]
+                    .iter()
+                    .filter(move |b| a.class != FeatureClass::Settlement || b.class != a.class)
+                    .map(move |b| (a, b))
+            }),
+            |(a, b)| distance((a.x, a.y), (b.x, b.y)) >= a.class.radius().max(b.class.radius()),
+            |(a, b)| format!("{} {} crowds {} {}", a.kind, a.id, b.kind, b.id),
+        ),
+    );
+
+    // Every land cell's unit of runoff leaves the land exactly once: into
+    // the sea, or at a pit with nowhere lower to go. Lakes may evaporate
+    // some of it on the way, but no more than their evaporation allows. A
+    // lake's inflow is the flow of each of its cells, so only its first
+    // cell counts.
+    let ocean = |index: usize| map.biome[index] == Biome::Ocean.code();
+    let mut repeated = vec![false; cells];
+    let mut evaporated = 0.0;
+    if map.settings.lake_outflows {
+        let aridity = aridity(
+            &map.temperature,
+            &map.base_moisture,
+            map.settings.moisture_scale,
+        );
+        for basin in map.lake_routing().basins.iter().filter(|basin| basin.lake) {
+            for &index in &basin.cells[1..] {
+                repeated[index] = true;
+            }
+            evaporated += basin.evaporation(&aridity).min(basin.inflow);
+        }
+    }
+    let land = (0..cells).filter(|&index| !ocean(index)).count() as f32;
+    let leaving: f32 = (0..cells)
+        .filter(|&index| !ocean(index) && !repeated[index] && receivers[index].is_none_or(ocean))
+        .map(|index| map.flow[index])
+        .sum();
+    let slack = land * FLOW_TOLERANCE;
+    if leaving > land + slack || leaving < land - evaporated - slack {
+        report(
+            "flow_conservation",
+            Some((
+                format!("{leaving} units of flow leave {land} land cells"),
+                1,
+            )),
+        );
+    }
+    broken
+}
+
+/// Generates `iterations` maps from settings drawn with `seed` and checks
+/// each against `check_invariants`. A panic counts as a `"panic"`
+/// violation, where the platform can unwind.
+pub(crate) fn run_self_test(iterations: u32, seed: u32) -> Vec<Violation> {
+    let mut rng = SimpleRng::new(seed.wrapping_mul(2_654_435_761));
+    let mut violations = Vec::new();
+    for case in 0..iterations {
+        let (width, height, settings) = random_case(&mut rng);
+        let violation = |invariant, detail, count| Violation {
+            case,
+            invariant,
+            detail,
+            count,
+            width,
+            height,
+            settings: settings.clone(),
+        };
+        match catch_unwind(AssertUnwindSafe(|| {
+            check_invariants(&generate(width, height, &settings, None))
+        })) {
+            Ok(broken) => violations.extend(
+                broken
+                    .into_iter()
+                    .map(|(invariant, detail, count)| violation(invariant, detail, count)),
+            ),
+            Err(payload) => {
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .map(|message| message.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "panicked".to_string());
+                violations.push(violation("panic", message, 1));
+            }
+        }
+    }
+    violations
+}
+
+#[cfg(feature = "wasm")]
+impl Violation {
+    fn to_json(&self) -> Json {
+        let mut parameters = vec![("width", self.width.into()), ("height", self.height.into())];
+        parameters.extend(settings_record(&self.settings));
+        Json::object()
+            .with("case", self.case)
+            .with("invariant", self.invariant)
+            .with("detail", self.detail.as_str())
+            .with("count", self.count)
+            .with("parameters", Json::from_record(parameters))
+    }
+}
+
+/// Generates `iterations` small maps (12 to 48 cells a side) with settings
+/// drawn from `seed` across the valid ranges and checks the invariants the
+/// pipeline promises. Returns `{ iterations, seed, passed, violations }`,
+/// each violation `{ case, invariant, detail, count, parameters }` with
+/// `parameters` holding `width`, `height`, and every setting as named in
+/// `generate_map_with_options`, enough to reproduce the map in a bug report.
+/// Invariants: `"finite"` and `"range"` (heightmap, moisture, temperature,
+/// and water in 0..1, flow from 1 to the cell count), `"biome"` (known
+/// codes), `"standing_water"` (open water at or below sea level or inside
+/// a depression; rivers wide enough to classify as lake must run downhill),
+/// `"settlement_on_land"`, `"road_endpoints"` (roads join two existing
+/// settlements), `"feature_spacing"` (point features other than two
+/// settlements keep the larger class radius apart), and `"flow_conservation"`
+/// (runoff leaving the land matches the land cell count, less at most what
+/// lakes evaporate). Panics abort the call in wasm, so a self-test that
+/// throws is itself worth reporting with its arguments.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn self_test(iterations: u32, seed: u32) -> JsValue {
+    let violations = run_self_test(iterations, seed);
+    Json::object()
+        .with("iterations", iterations)
+        .with("seed", seed)
+        .with("passed", violations.is_empty())
+        .with(
+            "violations",
+            violations
+                .iter()
+                .map(Violation::to_json)
+                .collect::<Vec<_>>(),
+        )
+        .to_js()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_invariants, run_self_test};
+    use crate::biome::Biome;
+    use crate::{generate, generate_map, GenerationSettings};
+
+    #[test]
+    fn random_maps_keep_their_invariants() {
+        let violations = run_self_test(100, 7);
+        let summary: Vec<String> = violations
+            .iter()
+            .map(|violation| {
+                format!(
+                    "case {} {}: {} ({} total)",
+                    violation.case, violation.invariant, violation.detail, violation.count
+                )
+            })
+            .collect();
+        assert!(summary.is_empty(), "{summary:#?}");
+    }
+
+    #[test]
+    fn broken_maps_are_caught() {
+        let mut map = generate_map(32, 32, 5, 0.42, 1.0, 40.0, 2, 1.0);
+        let count = |map: &_, invariant: &str| {
+            check_invariants(map)
+                .into_iter()
+                .find(|&(broken, _, _)| broken == invariant)
+                .map_or(0, |(_, _, count)| count)
+        };
+        assert!(check_invariants(&map).is_empty());
+
+        map.moisture[3] = f32::NAN;
+        map.temperature[4] = 1.5;
+        map.biome[5] = 200;
+        let dry = (0..map.biome.len())
+            .find(|&index| map.heightmap[index] > map.sea_level + 0.1 && map.water[index] == 0.0)
+            .unwrap();
+        map.biome[dry] = Biome::Lake.code();
+        map.road_graph.push((0, 999));
+        for invariant in [
+            "finite",
+            "range",
+            "biome",
+            "standing_water",
+            "road_endpoints",
+        ] {
+            assert_eq!(count(&map, invariant), 1, "{invariant}");
+        }
+
+        // Flow lost beyond what the lakes evaporate.
+        let settings = GenerationSettings {
+            lake_outflows: true,
+            ..GenerationSettings::new(5, 0.42, 1.0, 40.0, 2, 1.0)
+        };
+        let mut map = generate(32, 32, &settings, None);
+        assert!(check_invariants(&map).is_empty());
+        map.flow
+            .iter_mut()
+            .for_each(|flow| *flow = (*flow * 0.5).max(1.0));
+        assert_eq!(count(&map, "flow_conservation"), 1);
+    }
+}
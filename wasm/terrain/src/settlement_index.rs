@@ -0,0 +1,243 @@
+#[cfg(feature = "wasm")]
+use js_sys::Array;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::geometry::distance;
+use crate::json::Json;
+use crate::{MapResult, Settlement, REGION_SIZE};
+
+/// Bucket edge in world units, close to the minimum settlement spacing.
+const BUCKET_SIZE: f32 = 128.0;
+
+/// Uniform grid of settlement positions (indices into `MapResult::settlements`).
+pub(crate) struct SettlementIndex {
+    columns: usize,
+    rows: usize,
+    buckets: Vec<Vec<usize>>,
+}
+
+impl SettlementIndex {
+    pub(crate) fn build(settlements: &[Settlement]) -> Self {
+        let columns = (REGION_SIZE / BUCKET_SIZE).ceil() as usize;
+        let rows = columns;
+        let mut index = Self {
+            columns,
+            rows,
+            buckets: vec![Vec::new(); columns * rows],
+        };
+        for (position, settlement) in settlements.iter().enumerate() {
+            let (bx, by) = index.bucket_of(settlement.x, settlement.y);
+            index.buckets[by * columns + bx].push(position);
+        }
+        index
+    }
+
+    fn bucket_of(&self, world_x: f32, world_y: f32) -> (usize, usize) {
+        let clamp = |value: f32, count: usize| {
+            ((value / BUCKET_SIZE).floor().max(0.0) as usize).min(count - 1)
+        };
+        (clamp(world_x, self.columns), clamp(world_y, self.rows))
+    }
+
+    /// Closest settlement and its distance, searching outward ring by ring.
+    pub(crate) fn nearest(
+        &self,
+        settlements: &[Settlement],
+        world_x: f32,
+        world_y: f32,
+    ) -> Option<(usize, f32)> {
+        let (bx, by) = self.bucket_of(world_x, world_y);
+        let max_ring = self.columns.max(self.rows);
+        let mut best: Option<(usize, f32)> = None;
+        for ring in 0..=max_ring {
+            for (cx, cy) in ring_buckets(bx, by, ring, self.columns, self.rows) {
+                for &position in &self.buckets[cy * self.columns + cx] {
+                    let distance = distance_to(&settlements[position], world_x, world_y);
+                    let better = match best {
+                        Some((current, best_distance)) => {
+                            distance < best_distance
+                                || (distance == best_distance && position < current)
+                        }
+                        None => true,
+                    };
+                    if better {
+                        best = Some((position, distance));
+                    }
+                }
+            }
+            // Buckets beyond the next ring are at least `ring` buckets away.
+            if let Some((_, distance)) = best {
+                if distance <= ring as f32 * BUCKET_SIZE {
+                    break;
+                }
+            }
+        }
+        best
+    }
+
+    /// Settlements within `radius`, sorted by distance then position.
+    pub(crate) fn within(
+        &self,
+        settlements: &[Settlement],
+        world_x: f32,
+        world_y: f32,
+        radius: f32,
+    ) -> Vec<(usize, f32)> {
+        let (x0, y0) = self.bucket_of(world_x - radius, world_y - radius);
+        let (x1, y1) = self.bucket_of(world_x + radius, world_y + radius);
+        let mut found = Vec::new();
+        for cy in y0..=y1 {
+            for cx in x0..=x1 {
+                for &position in &self.buckets[cy * self.columns + cx] {
+                    let distance = distance_to(&settlements[position], world_x, world_y);
+                    if distance <= radius {
+                        found.push((position, distance));
+                    }
+                }
+            }
+        }
+        found.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+        found
+    }
+}
+
+fn distance_to(settlement: &Settlement, world_x: f32, world_y: f32) -> f32 {
+    distance((settlement.x, settlement.y), (world_x, world_y))
+}
+
+/// Buckets at Chebyshev distance `ring` from `(bx, by)`, clipped to the grid.
+fn ring_buckets(
+    bx: usize,
+    by: usize,
+    ring: usize,
+    columns: usize,
+    rows: usize,
+) -> impl Iterator<Item = (usize, usize)> {
+    let ring = ring as i64;
+    let (bx, by) = (bx as i64, by as i64);
+    (-ring..=ring)
+        .flat_map(move |dy| (-ring..=ring).map(move |dx| (dx, dy)))
+        .filter(move |(dx, dy)| dx.abs() == ring || dy.abs() == ring)
+        .map(move |(dx, dy)| (bx + dx, by + dy))
+        .filter(move |(x, y)| *x >= 0 && *y >= 0 && *x < columns as i64 && *y < rows as i64)
+        .map(|(x, y)| (x as usize, y as usize))
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Closest settlement to a world position with an added `distance` field,
+    /// or `null` when the map has no settlements.
+    pub fn nearest_settlement(&self, world_x: f32, world_y: f32) -> JsValue {
+        let index = self.settlement_index();
+        match index.nearest(&self.settlements, world_x, world_y) {
+            Some((position, distance)) => {
+                settlement_with_distance(&self.settlements[position], distance)
+            }
+            None => JsValue::NULL,
+        }
+    }
+
+    /// Settlements within `radius` world units, nearest first, each with a
+    /// `distance` field.
+    pub fn settlements_within(&self, world_x: f32, world_y: f32, radius: f32) -> Array {
+        let index = self.settlement_index();
+        index
+            .within(&self.settlements, world_x, world_y, radius.max(0.0))
+            .into_iter()
+            .map(|(position, distance)| {
+                settlement_with_distance(&self.settlements[position], distance)
+            })
+            .collect()
+    }
+}
+
+impl MapResult {
+    /// Lazily built settlement grid; dropped by `invalidate_settlements`.
+    pub(crate) fn settlement_index(&self) -> &SettlementIndex {
+        self.cache
+            .settlement_index
+            .get_or_init(|| SettlementIndex::build(&self.settlements))
+    }
+}
+
+#[cfg(feature = "wasm")]
+fn settlement_with_distance(settlement: &Settlement, distance: f32) -> JsValue {
+    let mut record = settlement.record();
+    record.push(("distance", distance.into()));
+    Json::from_record(record).to_js()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{distance_to, SettlementIndex};
+    use crate::{generate_map, Settlement, SimpleRng, REGION_SIZE};
+
+    fn settlement(id: u32, x: f32, y: f32) -> Settlement {
+        Settlement {
+            id,
+            x,
+            y,
+            size: 1.0,
+            issue: None,
+            era: 0,
+            name: None,
+        }
+    }
+
+    #[test]
+    fn nearest_matches_a_brute_force_scan() {
+        let mut rng = SimpleRng::new(17);
+        // A tight cluster in one corner and a few far-flung towns, so rings
+        // run long before finding anything.
+        let mut settlements: Vec<Settlement> = (0..12)
+            .map(|id| {
+                let x = 1800.0 + rng.next_f32() * 200.0;
+                let y = 1800.0 + rng.next_f32() * 200.0;
+                settlement(id, x, y)
+            })
+            .collect();
+        settlements.push(settlement(12, 40.0, 1900.0));
+        settlements.push(settlement(13, 1024.0, 1024.0));
+        let index = SettlementIndex::build(&settlements);
+
+        for _ in 0..500 {
+            let x = rng.next_f32() * (REGION_SIZE + 400.0) - 200.0;
+            let y = rng.next_f32() * (REGION_SIZE + 400.0) - 200.0;
+            let expected = (0..settlements.len())
+                .map(|position| (position, distance_to(&settlements[position], x, y)))
+                .min_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+            assert_eq!(index.nearest(&settlements, x, y), expected, "({x}, {y})");
+
+            let within = index.within(&settlements, x, y, 300.0);
+            let mut scan: Vec<(usize, f32)> = (0..settlements.len())
+                .map(|position| (position, distance_to(&settlements[position], x, y)))
+                .filter(|&(_, distance)| distance <= 300.0)
+                .collect();
+            scan.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+            assert_eq!(within, scan);
+        }
+        assert_eq!(SettlementIndex::build(&[]).nearest(&[], 5.0, 5.0), None);
+    }
+
+    #[test]
+    fn the_index_is_rebuilt_when_settlements_change() {
+        let mut map = generate_map(48, 48, 4, 0.42, 1.0, 40.0, 2, 1.0);
+        let (x, y) = (3.0, 2045.0);
+        let (before, _) = map
+            .settlement_index()
+            .nearest(&map.settlements, x, y)
+            .unwrap();
+        let id = map.settlements.len() as u32;
+        map.settlements.push(settlement(id, x, y));
+        map.invalidate_settlements();
+        let (after, distance) = map
+            .settlement_index()
+            .nearest(&map.settlements, x, y)
+            .unwrap();
+        assert_ne!(after, before);
+        assert_eq!(map.settlements[after].id, id);
+        assert_eq!(distance, 0.0);
+    }
+}
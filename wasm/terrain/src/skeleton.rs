@@ -0,0 +1,282 @@
+use std::collections::HashSet;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::geometry::polyline_length;
+use crate::hydrology::trace_channels;
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::json::Json;
+use crate::{MapResult, DIRECTIONS};
+
+/// Cells to either side sampled for the cross-ridge profile.
+const RIDGE_REACH: i32 = 2;
+/// Axes checked for a cross-ridge profile maximum.
+const RIDGE_AXES: [(i32, i32); 4] = [(1, 0), (0, 1), (1, 1), (1, -1)];
+
+pub(crate) struct SkeletonOptions {
+    /// Minimum height of a ridge cell above the mean of its two cross-ridge
+    /// neighbors.
+    pub ridge_prominence: f32,
+    /// Runoff (as in the water layer, before the river cutoff) above which a
+    /// cell belongs to the valley network.
+    pub valley_runoff: f32,
+    /// Polylines shorter than this many world units are dropped.
+    pub min_length: f32,
+}
+
+impl Default for SkeletonOptions {
+    fn default() -> Self {
+        Self {
+            ridge_prominence: 0.0005,
+            valley_runoff: 0.15,
+            min_length: 96.0,
+        }
+    }
+}
+
+impl SkeletonOptions {
+    #[cfg(feature = "wasm")]
+    fn from_js(options: &JsValue) -> Self {
+        let defaults = Self::default();
+        Self {
+            ridge_prominence: js::get_f32(options, "ridge_prominence", defaults.ridge_prominence),
+            valley_runoff: js::get_f32(options, "valley_runoff", defaults.valley_runoff),
+            min_length: js::get_f32(options, "min_length", defaults.min_length),
+        }
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Structural skeleton as `{ ridges, valleys }`, each an array of
+    /// polylines holding interleaved world `x, y` coordinates. Options:
+    /// `ridge_prominence`, `valley_runoff`, `min_length` (world units).
+    pub fn skeleton(&self, options: JsValue) -> JsValue {
+        let options = SkeletonOptions::from_js(&options);
+        let polylines = |lines: Vec<Vec<usize>>| {
+            lines
+                .into_iter()
+                .map(|cells| {
+                    cells
+                        .into_iter()
+                        .flat_map(|index| {
+                            let (x, y) = self.cell_to_world(index);
+                            [Json::from(x), Json::from(y)]
+                        })
+                        .collect::<Vec<_>>()
+                        .into()
+                })
+                .collect::<Vec<Json>>()
+        };
+        Json::object()
+            .with("ridges", polylines(ridge_lines(self, &options)))
+            .with("valleys", polylines(valley_lines(self, &options)))
+            .to_js()
+    }
+}
+
+/// Ridge polylines: cross-ridge profile maxima (non-maximum suppression over
+/// four axes), thinned to single-cell width and linked into chains between
+/// endpoints and junctions.
+pub(crate) fn ridge_lines(map: &MapResult, options: &SkeletonOptions) -> Vec<Vec<usize>> {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let smoothed = box_blur(&map.heightmap, width, height);
+    let mut mask = vec![false; smoothed.len()];
+    let reach = RIDGE_REACH as usize;
+    for y in reach..height.saturating_sub(reach) {
+        for x in reach..width.saturating_sub(reach) {
+            let index = y * width + x;
+            if map.heightmap[index] <= map.sea_level {
+                continue;
+            }
+            let center = smoothed[index];
+            mask[index] = RIDGE_AXES.iter().any(|&(dx, dy)| {
+                let (dx, dy) = (dx * RIDGE_REACH, dy * RIDGE_REACH);
+                let ahead = smoothed[(y as i32 + dy) as usize * width + (x as i32 + dx) as usize];
+                let behind = smoothed[(y as i32 - dy) as usize * width + (x as i32 - dx) as usize];
+                center > ahead
+                    && center > behind
+                    && center - (ahead + behind) * 0.5 >= options.ridge_prominence
+            });
+        }
+    }
+    thin(&mut mask, width, height);
+    link_chains(&mask, width, height)
+        .into_iter()
+        .filter(|chain| cells_length(map, chain) >= options.min_length)
+        .collect()
+}
+
+/// Valley polylines: the channel network traced like rivers, but down to a
+/// lower runoff cutoff so dry valleys join the rivers they feed.
+pub(crate) fn valley_lines(map: &MapResult, options: &SkeletonOptions) -> Vec<Vec<usize>> {
+    let max_flow = map.flow.iter().fold(0.0f32, |acc, &v| acc.max(v));
+    trace_channels(map, |index| {
+        !map.is_water_body(index)
+            && (map.flow[index] / (max_flow + 1.0)).powf(0.4) >= options.valley_runoff
+    })
+    .into_iter()
+    .map(|valley| valley.cells)
+    .filter(|cells| cells_length(map, cells) >= options.min_length)
+    .collect()
+}
+
+fn cells_length(map: &MapResult, cells: &[usize]) -> f32 {
+    let points: Vec<_> = cells
+        .iter()
+        .map(|&index| map.cell_to_world(index))
+        .collect();
+    polyline_length(&points, false)
+}
+
+fn box_blur(values: &[f32], width: usize, height: usize) -> Vec<f32> {
+    let mut blurred = vec![0.0f32; values.len()];
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = values[y * width + x];
+            let mut count = 1.0f32;
+            for (dx, dy) in DIRECTIONS {
+                let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+                if nx >= 0 && ny >= 0 && nx < width as i32 && ny < height as i32 {
+                    sum += values[ny as usize * width + nx as usize];
+                    count += 1.0;
+                }
+            }
+            blurred[y * width + x] = sum / count;
+        }
+    }
+    blurred
+}
+
+/// Zhang-Suen thinning, in place, down to an 8-connected one-cell skeleton.
+fn thin(mask: &mut [bool], width: usize, height: usize) {
+    // Clockwise from north: P2..P9 in the usual notation.
+    const RING: [(i32, i32); 8] = [
+        (0, -1),
+        (1, -1),
+        (1, 0),
+        (1, 1),
+        (0, 1),
+        (-1, 1),
+        (-1, 0),
+        (-1, -1),
+    ];
+    let at = |mask: &[bool], x: usize, y: usize, (dx, dy): (i32, i32)| {
+        let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+        nx >= 0
+            && ny >= 0
+            && nx < width as i32
+            && ny < height as i32
+            && mask[ny as usize * width + nx as usize]
+    };
+    let mut removals = Vec::new();
+    loop {
+        let mut changed = false;
+        for pass in 0..2 {
+            removals.clear();
+            for y in 0..height {
+                for x in 0..width {
+                    if !mask[y * width + x] {
+                        continue;
+                    }
+                    let p = RING.map(|offset| at(mask, x, y, offset));
+                    let neighbors = p.iter().filter(|&&set| set).count();
+                    let transitions = (0..8).filter(|&i| !p[i] && p[(i + 1) % 8]).count();
+                    let (a, b) = if pass == 0 {
+                        (p[0] && p[2] && p[4], p[2] && p[4] && p[6])
+                    } else {
+                        (p[0] && p[2] && p[6], p[0] && p[4] && p[6])
+                    };
+                    if (2..=6).contains(&neighbors) && transitions == 1 && !a && !b {
+                        removals.push(y * width + x);
+                    }
+                }
+            }
+            for &index in &removals {
+                mask[index] = false;
+            }
+            changed |= !removals.is_empty();
+        }
+        if !changed {
+            break;
+        }
+    }
+}
+
+/// Splits an 8-connected skeleton into chains that run between cells whose
+/// neighbor count is not two, then picks up the remaining closed loops.
+fn link_chains(mask: &[bool], width: usize, height: usize) -> Vec<Vec<usize>> {
+    let set = |x: i32, y: i32| {
+        x >= 0
+            && y >= 0
+            && x < width as i32
+            && y < height as i32
+            && mask[y as usize * width + x as usize]
+    };
+    // Mixed adjacency: a diagonal step only counts when no orthogonal cell
+    // already bridges it, so staircases do not form triangles.
+    let neighbors = move |index: usize| {
+        let (x, y) = ((index % width) as i32, (index / width) as i32);
+        DIRECTIONS.iter().filter_map(move |&(dx, dy)| {
+            let (nx, ny) = (x + dx, y + dy);
+            let bridged = dx != 0 && dy != 0 && (set(x + dx, y) || set(x, y + dy));
+            (set(nx, ny) && !bridged).then(|| ny as usize * width + nx as usize)
+        })
+    };
+    let degree: Vec<usize> = (0..mask.len())
+        .map(|index| {
+            if mask[index] {
+                neighbors(index).count()
+            } else {
+                0
+            }
+        })
+        .collect();
+    let mut walked: HashSet<(usize, usize)> = HashSet::new();
+    let edge = |a: usize, b: usize| (a.min(b), a.max(b));
+    let mut chains = Vec::new();
+
+    let walk = |start: usize, first: usize, walked: &mut HashSet<(usize, usize)>| {
+        let mut chain = vec![start];
+        let (mut previous, mut current) = (start, first);
+        walked.insert(edge(previous, current));
+        loop {
+            chain.push(current);
+            if degree[current] != 2 || current == start {
+                break;
+            }
+            let Some(next) = neighbors(current)
+                .find(|&next| next != previous && !walked.contains(&edge(current, next)))
+            else {
+                break;
+            };
+            walked.insert(edge(current, next));
+            previous = current;
+            current = next;
+        }
+        chain
+    };
+
+    for start in 0..mask.len() {
+        if mask[start] && degree[start] != 2 {
+            for first in neighbors(start).collect::<Vec<_>>() {
+                if !walked.contains(&edge(start, first)) {
+                    chains.push(walk(start, first, &mut walked));
+                }
+            }
+        }
+    }
+    for start in 0..mask.len() {
+        if mask[start] && degree[start] == 2 {
+            if let Some(first) = neighbors(start).find(|&next| !walked.contains(&edge(start, next)))
+            {
+                chains.push(walk(start, first, &mut walked));
+            }
+        }
+    }
+    chains
+}
@@ -0,0 +1,420 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::biome::Biome;
+use crate::distance::chamfer_distance;
+use crate::features::components;
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::json::Json;
+use crate::movement::Profile;
+use crate::pathfinding::relax_distances;
+use crate::{slope_map, MapResult, DIRECTIONS, REGION_SIZE};
+
+/// Temperature the climate score centers on, and how far either side it
+/// falls to zero.
+const IDEAL_TEMPERATURE: f32 = 0.55;
+const TEMPERATURE_TOLERANCE: f32 = 0.35;
+/// Steepest normalized slope that still counts as buildable.
+const BUILDABLE_SLOPE: f32 = 0.08;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Landmass {
+    Any,
+    /// Every spawn on its own landmass.
+    Separate,
+    /// Every spawn on the first spawn's landmass.
+    Same,
+}
+
+pub(crate) struct SpawnOptions {
+    /// Weights of the climate, water, and flat-ground scores in `quality`.
+    pub climate_weight: f32,
+    pub water_weight: f32,
+    pub flat_weight: f32,
+    /// Fresh water must lie within this many world units.
+    pub water_radius: f32,
+    /// Radius in world units of the area checked for buildable ground.
+    pub flat_radius: f32,
+    /// Share of that area that must be buildable.
+    pub min_flat_fraction: f32,
+    /// Candidates scoring lower are ignored.
+    pub min_quality: f32,
+    /// Travel cost below which a new spawn is too close to the others.
+    pub min_distance: f32,
+    pub landmass: Landmass,
+    pub profile: Profile,
+}
+
+impl Default for SpawnOptions {
+    fn default() -> Self {
+        Self {
+            climate_weight: 1.0,
+            water_weight: 1.0,
+            flat_weight: 1.0,
+            water_radius: 128.0,
+            flat_radius: 48.0,
+            min_flat_fraction: 0.5,
+            min_quality: 0.0,
+            min_distance: 0.0,
+            landmass: Landmass::Any,
+            profile: Profile::preset("foot").expect("foot is a preset"),
+        }
+    }
+}
+
+impl SpawnOptions {
+    #[cfg(feature = "wasm")]
+    fn from_js(options: &JsValue) -> Result<Self, JsValue> {
+        let defaults = Self::default();
+        let landmass = match js::get_string(options, "landmass").as_deref() {
+            None | Some("any") => Landmass::Any,
+            Some("separate") => Landmass::Separate,
+            Some("same") => Landmass::Same,
+            Some(other) => {
+                return Err(JsValue::from_str(&format!(
+                    "landmass must be \"any\", \"separate\", or \"same\", got {other}"
+                )))
+            }
+        };
+        let profile = match js::get(options, "profile") {
+            Some(profile) => Profile::from_js(&profile)?,
+            None => defaults.profile,
+        };
+        Ok(Self {
+            climate_weight: js::get_f32(options, "climate_weight", defaults.climate_weight)
+                .max(0.0),
+            water_weight: js::get_f32(options, "water_weight", defaults.water_weight).max(0.0),
+            flat_weight: js::get_f32(options, "flat_weight", defaults.flat_weight).max(0.0),
+            water_radius: js::get_f32(options, "water_radius", defaults.water_radius),
+            flat_radius: js::get_f32(options, "flat_radius", defaults.flat_radius),
+            min_flat_fraction: js::get_f32(
+                options,
+                "min_flat_fraction",
+                defaults.min_flat_fraction,
+            )
+            .clamp(0.0, 1.0),
+            min_quality: js::get_f32(options, "min_quality", defaults.min_quality),
+            min_distance: js::get_f32(options, "min_distance", defaults.min_distance),
+            landmass,
+            profile,
+        })
+    }
+}
+
+/// Habitability of one cell; each score is 0..1.
+#[derive(Clone, Copy)]
+pub(crate) struct SpawnScores {
+    pub climate: f32,
+    pub water: f32,
+    pub flat: f32,
+    pub quality: f32,
+}
+
+pub(crate) struct Spawn {
+    pub index: usize,
+    pub scores: SpawnScores,
+    /// Travel cost to the closest other spawn; infinite when none can be
+    /// reached.
+    pub separation: f32,
+}
+
+pub(crate) struct SpawnSelection {
+    pub spawns: Vec<Spawn>,
+    /// Why fewer spawns than requested were found.
+    pub shortfall: Option<String>,
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Up to `count` starting locations spread as far apart as travel allows.
+    /// Returns `{ spawns, quality_spread, min_separation, reason }` with
+    /// spawns `{ x, y, quality, climate, water, flat, separation }`;
+    /// `separation` is the travel cost to the nearest other spawn (`null`
+    /// when unreachable), `quality_spread` the gap between the best and worst
+    /// spawn, and `reason` explains a shortfall or is `null`. Options:
+    /// `climate_weight`, `water_weight`, `flat_weight`, `water_radius`,
+    /// `flat_radius`, `min_flat_fraction`, `min_quality`, `min_distance`,
+    /// `landmass` (`"any"`, `"separate"`, or `"same"`), and `profile`, a
+    /// movement profile as accepted by `movement_cost` (default `"foot"`).
+    pub fn select_spawns(&self, count: u32, options: JsValue) -> Result<JsValue, JsValue> {
+        let selection = select_spawns(self, count, &SpawnOptions::from_js(&options)?);
+        let records = selection
+            .spawns
+            .iter()
+            .map(|spawn| Json::from_record(spawn.record(self)))
+            .collect::<Vec<_>>();
+        let qualities = selection.spawns.iter().map(|spawn| spawn.scores.quality);
+        let spread = qualities.clone().fold(f32::NEG_INFINITY, f32::max)
+            - qualities.fold(f32::INFINITY, f32::min);
+        let min_separation = selection
+            .spawns
+            .iter()
+            .map(|spawn| spawn.separation)
+            .fold(f32::INFINITY, f32::min);
+        let result = Json::object()
+            .with("spawns", Json::from(records))
+            .with(
+                "quality_spread",
+                if spread.is_finite() { spread } else { 0.0 },
+            )
+            .with("min_separation", min_separation)
+            .with("reason", selection.shortfall.map_or(Json::Null, Json::from));
+        Ok(result.to_js())
+    }
+}
+
+/// Greedy maximin: the best-scoring valid cell first, then repeatedly the
+/// valid cell with the greatest travel cost to every spawn so far, found
+/// with an incrementally relaxed multi-source Dijkstra field.
+pub(crate) fn select_spawns(map: &MapResult, count: u32, options: &SpawnOptions) -> SpawnSelection {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let scores = habitability(map, options);
+    let land: Vec<bool> = (0..map.biome.len())
+        .map(|index| !map.is_water_body(index))
+        .collect();
+    let mut landmass = vec![usize::MAX; land.len()];
+    for (label, group) in components(&land, width, height).into_iter().enumerate() {
+        for index in group {
+            landmass[index] = label;
+        }
+    }
+    let costs = map.movement_costs(&options.profile);
+    let mut candidates: Vec<usize> = (0..scores.len())
+        .filter(|&index| scores[index].is_some_and(|s| s.quality >= options.min_quality))
+        .filter(|&index| costs[index].is_finite())
+        .collect();
+    let mut selection = SpawnSelection {
+        spawns: Vec::new(),
+        shortfall: None,
+    };
+    if count == 0 {
+        return selection;
+    }
+    if candidates.is_empty() {
+        selection.shortfall = Some("no cell meets the habitability criteria".to_string());
+        return selection;
+    }
+
+    let quality = |index: usize| scores[index].map_or(0.0, |s| s.quality);
+    let mut distance = vec![f32::INFINITY; land.len()];
+    let mut owner = vec![u32::MAX; land.len()];
+    let mut chosen: Vec<usize> = Vec::new();
+    while chosen.len() < count as usize {
+        let pick = candidates.iter().copied().max_by(|&a, &b| {
+            distance[a]
+                .total_cmp(&distance[b])
+                .then(quality(a).total_cmp(&quality(b)))
+                .then(b.cmp(&a))
+        });
+        let Some(pick) = pick else {
+            selection.shortfall = Some(match options.landmass {
+                Landmass::Separate => {
+                    format!("only {} landmasses have a valid spawn", chosen.len())
+                }
+                _ => format!("only {} valid spawns exist", chosen.len()),
+            });
+            break;
+        };
+        if distance[pick] < options.min_distance {
+            selection.shortfall = Some(format!(
+                "only {} spawns fit at least {} apart",
+                chosen.len(),
+                options.min_distance
+            ));
+            break;
+        }
+        match options.landmass {
+            Landmass::Any => {}
+            Landmass::Separate => candidates.retain(|&index| landmass[index] != landmass[pick]),
+            Landmass::Same => candidates.retain(|&index| landmass[index] == landmass[pick]),
+        }
+        candidates.retain(|&index| index != pick);
+        relax_distances(
+            &costs,
+            width,
+            height,
+            &[pick],
+            chosen.len() as u32,
+            &mut distance,
+            &mut owner,
+        );
+        chosen.push(pick);
+    }
+
+    let separations = separations(&costs, width, height, &distance, &owner, chosen.len());
+    selection.spawns = chosen
+        .into_iter()
+        .zip(separations)
+        .map(|(index, separation)| Spawn {
+            index,
+            scores: scores[index].expect("spawns are scored"),
+            separation,
+        })
+        .collect();
+    selection
+}
+
+/// Travel cost from each spawn to its nearest neighbour. The cheapest path
+/// between two spawns crosses the border of their catchments exactly once,
+/// so scanning each border edge of the nearest-spawn field is enough.
+fn separations(
+    costs: &[f32],
+    width: usize,
+    height: usize,
+    distance: &[f32],
+    owner: &[u32],
+    count: usize,
+) -> Vec<f32> {
+    let cell_w = REGION_SIZE / width as f32;
+    let cell_h = REGION_SIZE / height as f32;
+    let mut separation = vec![f32::INFINITY; count];
+    for index in 0..owner.len() {
+        if owner[index] == u32::MAX {
+            continue;
+        }
+        let (x, y) = ((index % width) as i32, (index / width) as i32);
+        for (dx, dy) in DIRECTIONS {
+            let (nx, ny) = (x + dx, y + dy);
+            if nx < 0 || ny < 0 || nx >= width as i32 || ny >= height as i32 {
+                continue;
+            }
+            let next = ny as usize * width + nx as usize;
+            if owner[next] == u32::MAX || owner[next] == owner[index] {
+                continue;
+            }
+            let step = ((dx as f32 * cell_w).powi(2) + (dy as f32 * cell_h).powi(2)).sqrt();
+            let total =
+                distance[index] + (costs[index] + costs[next]) * 0.5 * step + distance[next];
+            for label in [owner[index], owner[next]] {
+                let slot = &mut separation[label as usize];
+                *slot = slot.min(total);
+            }
+        }
+    }
+    separation
+}
+
+/// Scores every land cell, or `None` where it fails a hard criterion: too
+/// hot or cold, no fresh water within `water_radius`, or too little
+/// buildable ground within `flat_radius`.
+pub(crate) fn habitability(map: &MapResult, options: &SpawnOptions) -> Vec<Option<SpawnScores>> {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let slopes = slope_map(&map.heightmap, width, height);
+    let fresh: Vec<bool> = (0..map.biome.len())
+        .map(|index| map.is_river(index) || map.biome[index] == Biome::Lake.code())
+        .collect();
+    let water_distance = chamfer_distance(&fresh, width, height);
+
+    // Buildable share of the square around each cell, from a summed-area table.
+    let stride = width + 1;
+    let mut table = vec![0u32; stride * (height + 1)];
+    for y in 0..height {
+        for x in 0..width {
+            let index = y * width + x;
+            let buildable = (!map.is_water_body(index) && slopes[index] <= BUILDABLE_SLOPE) as u32;
+            table[(y + 1) * stride + x + 1] =
+                buildable + table[y * stride + x + 1] + table[(y + 1) * stride + x]
+                    - table[y * stride + x];
+        }
+    }
+    let reach_x = (options.flat_radius / (REGION_SIZE / width as f32)).round() as usize;
+    let reach_y = (options.flat_radius / (REGION_SIZE / height as f32)).round() as usize;
+    let total_weight = options.climate_weight + options.water_weight + options.flat_weight;
+
+    (0..map.heightmap.len())
+        .map(|index| {
+            if map.is_water_body(index) || map.is_river(index) {
+                return None;
+            }
+            let climate =
+                1.0 - (map.temperature[index] - IDEAL_TEMPERATURE).abs() / TEMPERATURE_TOLERANCE;
+            if climate <= 0.0 {
+                return None;
+            }
+            if water_distance[index] > options.water_radius {
+                return None;
+            }
+            let water = 1.0 - water_distance[index] / options.water_radius.max(f32::EPSILON);
+            let (x, y) = (index % width, index / width);
+            let (x0, y0) = (x.saturating_sub(reach_x), y.saturating_sub(reach_y));
+            let (x1, y1) = ((x + reach_x).min(width - 1), (y + reach_y).min(height - 1));
+            let buildable = table[(y1 + 1) * stride + x1 + 1] + table[y0 * stride + x0]
+                - table[y0 * stride + x1 + 1]
+                - table[(y1 + 1) * stride + x0];
+            let area = ((x1 - x0 + 1) * (y1 - y0 + 1)) as f32;
+            let fraction = buildable as f32 / area;
+            if fraction < options.min_flat_fraction {
+                return None;
+            }
+            let flat = fraction;
+            let quality = if total_weight > 0.0 {
+                (climate * options.climate_weight
+                    + water * options.water_weight
+                    + flat * options.flat_weight)
+                    / total_weight
+            } else {
+                1.0
+            };
+            Some(SpawnScores {
+                climate,
+                water,
+                flat,
+                quality,
+            })
+        })
+        .collect()
+}
+
+impl Spawn {
+    fn record(&self, map: &MapResult) -> Vec<(&'static str, Json)> {
+        let (x, y) = map.cell_to_world(self.index);
+        vec![
+            ("x", x.into()),
+            ("y", y.into()),
+            ("quality", self.scores.quality.into()),
+            ("climate", self.scores.climate.into()),
+            ("water", self.scores.water.into()),
+            ("flat", self.scores.flat.into()),
+            ("separation", self.separation.into()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{select_spawns, Landmass, SpawnOptions};
+    use crate::generate_map;
+
+    #[test]
+    fn spawns_are_valid_spread_and_capped() {
+        let map = generate_map(96, 96, 3, 0.42, 1.0, 40.0, 2, 1.0);
+        let selection = select_spawns(&map, 4, &SpawnOptions::default());
+        assert_eq!(selection.spawns.len(), 4, "{:?}", selection.shortfall);
+        for spawn in &selection.spawns {
+            assert!(!map.is_water_body(spawn.index));
+            assert!(spawn.separation > 0.0);
+        }
+
+        let greedy = select_spawns(&map, 10_000, &SpawnOptions::default());
+        assert!(greedy.spawns.len() < 10_000);
+        assert!(greedy.shortfall.is_some());
+    }
+
+    #[test]
+    fn same_landmass_spawns_reach_each_other() {
+        let map = generate_map(96, 96, 3, 0.42, 1.0, 40.0, 2, 1.0);
+        let options = SpawnOptions {
+            landmass: Landmass::Same,
+            ..SpawnOptions::default()
+        };
+        let selection = select_spawns(&map, 3, &options);
+        assert!(!selection.spawns.is_empty());
+        for spawn in &selection.spawns {
+            assert!(spawn.separation.is_finite() || selection.spawns.len() == 1);
+        }
+    }
+}
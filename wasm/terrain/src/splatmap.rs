@@ -0,0 +1,231 @@
+#[cfg(feature = "wasm")]
+use js_sys::{Array, Uint8Array};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::geometry::smoothstep;
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::{slope_map, MapResult};
+
+const MAX_CHANNELS: usize = 64;
+
+/// One weighted condition contributing to a material channel. A cell matches
+/// when its biome is listed (or no list is given) and its slope and elevation
+/// fall inside the ranges, with a soft ramp of `falloff` at each range edge.
+struct SplatTerm {
+    biomes: Option<[bool; 256]>,
+    slope: (f32, f32),
+    elevation: (f32, f32),
+    weight: f32,
+}
+
+pub(crate) struct SplatRules {
+    channels: Vec<Vec<SplatTerm>>,
+    falloff: f32,
+    smoothing_passes: u32,
+}
+
+impl SplatTerm {
+    #[cfg(feature = "wasm")]
+    fn from_js(value: &JsValue) -> Result<Self, JsValue> {
+        let biomes = match js::get_array(value, "biomes") {
+            Some(codes) => {
+                let mut allowed = [false; 256];
+                for code in codes.iter() {
+                    let code = code
+                        .as_f64()
+                        .filter(|code| (0.0..256.0).contains(code))
+                        .ok_or_else(|| JsValue::from_str("biome codes must be 0..=255"))?;
+                    allowed[code as usize] = true;
+                }
+                Some(allowed)
+            }
+            None => None,
+        };
+        Ok(Self {
+            biomes,
+            slope: (
+                js::get_f32(value, "slope_min", 0.0),
+                js::get_f32(value, "slope_max", 1.0),
+            ),
+            elevation: (
+                js::get_f32(value, "elevation_min", 0.0),
+                js::get_f32(value, "elevation_max", 1.0),
+            ),
+            weight: js::get_f32(value, "weight", 1.0).max(0.0),
+        })
+    }
+
+    fn evaluate(&self, biome: u8, slope: f32, elevation: f32, falloff: f32) -> f32 {
+        if let Some(allowed) = &self.biomes {
+            if !allowed[biome as usize] {
+                return 0.0;
+            }
+        }
+        self.weight
+            * range_weight(slope, self.slope, falloff)
+            * range_weight(elevation, self.elevation, falloff)
+    }
+}
+
+impl SplatRules {
+    /// Accepts either an array of channels or `{ channels, falloff, smoothing_passes }`.
+    /// Each channel is a term object or an array of term objects whose weights add.
+    #[cfg(feature = "wasm")]
+    fn from_js(rules: &JsValue) -> Result<Self, JsValue> {
+        let (channels, falloff, smoothing_passes) = if Array::is_array(rules) {
+            (Array::from(rules), 0.05, 1)
+        } else {
+            let channels = js::get_array(rules, "channels")
+                .ok_or_else(|| JsValue::from_str("splatmap rules need a channels array"))?;
+            (
+                channels,
+                js::get_f32(rules, "falloff", 0.05),
+                js::get_u32(rules, "smoothing_passes", 1),
+            )
+        };
+
+        if channels.length() == 0 || channels.length() as usize > MAX_CHANNELS {
+            return Err(JsValue::from_str(&format!(
+                "splatmap rules need between 1 and {MAX_CHANNELS} channels"
+            )));
+        }
+
+        let mut parsed = Vec::with_capacity(channels.length() as usize);
+        for channel in channels.iter() {
+            let terms = if Array::is_array(&channel) {
+                Array::from(&channel)
+                    .iter()
+                    .map(|term| SplatTerm::from_js(&term))
+                    .collect::<Result<Vec<_>, _>>()?
+            } else {
+                vec![SplatTerm::from_js(&channel)?]
+            };
+            parsed.push(terms);
+        }
+
+        Ok(Self {
+            channels: parsed,
+            falloff: falloff.max(0.0),
+            smoothing_passes,
+        })
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Builds RGBA splatmaps, one channel per material rule, with weights
+    /// normalized so every cell's channels sum to 255. Returns
+    /// `ceil(channels / 4)` buffers of `width * height * 4` bytes.
+    pub fn export_splatmaps(&self, rules: JsValue) -> Result<Array, JsValue> {
+        let rules = SplatRules::from_js(&rules)?;
+        let output = Array::new();
+        for map in export_splatmaps(self, &rules) {
+            output.push(&Uint8Array::from(map.as_slice()).into());
+        }
+        Ok(output)
+    }
+}
+
+pub(crate) fn export_splatmaps(map: &MapResult, rules: &SplatRules) -> Vec<Vec<u8>> {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let cells = width * height;
+    let slopes = slope_map(&map.heightmap, width, height);
+
+    let mut weights: Vec<Vec<f32>> = rules
+        .channels
+        .iter()
+        .map(|terms| {
+            (0..cells)
+                .map(|i| {
+                    terms
+                        .iter()
+                        .map(|term| {
+                            term.evaluate(map.biome[i], slopes[i], map.heightmap[i], rules.falloff)
+                        })
+                        .sum()
+                })
+                .collect()
+        })
+        .collect();
+
+    for channel in weights.iter_mut() {
+        for _ in 0..rules.smoothing_passes {
+            *channel = tent_filter(channel, width, height);
+        }
+    }
+
+    let channel_count = weights.len();
+    let mut maps = vec![vec![0u8; cells * 4]; channel_count.div_ceil(4)];
+    let mut quantized = vec![0u8; channel_count];
+    for i in 0..cells {
+        let total: f32 = weights.iter().map(|channel| channel[i]).sum();
+        quantize(&weights, i, total, &mut quantized);
+        for (channel, value) in quantized.iter().enumerate() {
+            maps[channel / 4][i * 4 + channel % 4] = *value;
+        }
+    }
+    maps
+}
+
+/// Converts one cell's weights to bytes summing to exactly 255, handing the
+/// rounding remainder to the strongest channel. Cells no rule matches fall
+/// back entirely to channel 0.
+fn quantize(weights: &[Vec<f32>], cell: usize, total: f32, out: &mut [u8]) {
+    if total <= f32::EPSILON {
+        out.fill(0);
+        out[0] = 255;
+        return;
+    }
+    let mut assigned = 0u32;
+    let mut strongest = 0;
+    for (channel, slot) in out.iter_mut().enumerate() {
+        let value = weights[channel][cell] / total;
+        *slot = (value * 255.0).floor() as u8;
+        assigned += *slot as u32;
+        if weights[channel][cell] > weights[strongest][cell] {
+            strongest = channel;
+        }
+    }
+    out[strongest] += (255 - assigned) as u8;
+}
+
+fn range_weight(value: f32, (min, max): (f32, f32), falloff: f32) -> f32 {
+    if falloff <= 0.0 {
+        return if value >= min && value <= max {
+            1.0
+        } else {
+            0.0
+        };
+    }
+    let rise = smoothstep((value - (min - falloff)) / falloff);
+    let fall = smoothstep(((max + falloff) - value) / falloff);
+    rise.min(fall)
+}
+
+/// Separable 1-2-1 (bilinear tent) filter with clamped borders.
+fn tent_filter(values: &[f32], width: usize, height: usize) -> Vec<f32> {
+    let mut horizontal = vec![0.0f32; values.len()];
+    for y in 0..height {
+        let row = y * width;
+        for x in 0..width {
+            let left = values[row + x.saturating_sub(1)];
+            let right = values[row + (x + 1).min(width - 1)];
+            horizontal[row + x] = (left + 2.0 * values[row + x] + right) * 0.25;
+        }
+    }
+    let mut result = vec![0.0f32; values.len()];
+    for y in 0..height {
+        let up = y.saturating_sub(1) * width;
+        let down = (y + 1).min(height - 1) * width;
+        for x in 0..width {
+            result[y * width + x] =
+                (horizontal[up + x] + 2.0 * horizontal[y * width + x] + horizontal[down + x])
+                    * 0.25;
+        }
+    }
+    result
+}
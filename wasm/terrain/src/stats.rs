@@ -0,0 +1,129 @@
+#[cfg(feature = "wasm")]
+use js_sys::Uint32Array;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::json::Json;
+use crate::population::{population_density, total_population, PopulationOptions};
+use crate::MapResult;
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Summary statistics: elevation `min`, `max`, `mean`, and `median`,
+    /// `land_fraction` (cells above sea level), `water_fraction` (cells with
+    /// any water, rivers included), mean temperature and moisture over land,
+    /// and `population` integrated from the default density layer.
+    pub fn statistics(&self) -> JsValue {
+        Json::from_record(statistics(self)).to_js()
+    }
+
+    /// Counts of `layer` values in `bins` equal-width bins. Normalized layers
+    /// span 0..1; `flow` spans its own min..max.
+    pub fn histogram(&self, layer: &str, bins: u32) -> Result<Uint32Array, JsValue> {
+        let values = self
+            .float_layer(layer)
+            .ok_or_else(|| JsValue::from_str(&format!("unknown layer: {layer}")))?;
+        if bins == 0 {
+            return Err(JsValue::from_str("bins must be positive"));
+        }
+        let range = layer_range(layer, values);
+        Ok(Uint32Array::from(
+            histogram(values, bins as usize, range).as_slice(),
+        ))
+    }
+
+    /// Value below which `p` percent of the layer's cells fall, interpolating
+    /// between ranks. `percentile("heightmap", 70)` is the sea level that
+    /// leaves 30% of the map as land.
+    pub fn percentile(&self, layer: &str, p: f32) -> Result<f32, JsValue> {
+        let values = self
+            .float_layer(layer)
+            .ok_or_else(|| JsValue::from_str(&format!("unknown layer: {layer}")))?;
+        if !(0.0..=100.0).contains(&p) {
+            return Err(JsValue::from_str("p must be within 0..=100"));
+        }
+        Ok(percentile(values, p))
+    }
+}
+
+pub(crate) fn statistics(map: &MapResult) -> Vec<(&'static str, Json)> {
+    let cells = map.heightmap.len() as f64;
+    let (min, max) = min_max(&map.heightmap);
+    let mean = map.heightmap.iter().map(|&v| v as f64).sum::<f64>() / cells;
+    let mut land = 0usize;
+    let mut wet = 0usize;
+    let mut land_temperature = 0.0f64;
+    let mut land_moisture = 0.0f64;
+    for index in 0..map.heightmap.len() {
+        if map.water[index] > 0.0 {
+            wet += 1;
+        }
+        if map.heightmap[index] > map.sea_level {
+            land += 1;
+            land_temperature += map.temperature[index] as f64;
+            land_moisture += map.moisture[index] as f64;
+        }
+    }
+    let density = population_density(map, &PopulationOptions::default());
+    let land_mean = |total: f64| if land > 0 { total / land as f64 } else { 0.0 };
+    vec![
+        ("min", min.into()),
+        ("max", max.into()),
+        ("mean", (mean as f32).into()),
+        ("median", percentile(&map.heightmap, 50.0).into()),
+        ("land_fraction", ((land as f64 / cells) as f32).into()),
+        ("water_fraction", ((wet as f64 / cells) as f32).into()),
+        (
+            "mean_land_temperature",
+            (land_mean(land_temperature) as f32).into(),
+        ),
+        (
+            "mean_land_moisture",
+            (land_mean(land_moisture) as f32).into(),
+        ),
+        ("population", total_population(map, &density).into()),
+    ]
+}
+
+fn layer_range(layer: &str, values: &[f32]) -> (f32, f32) {
+    match layer {
+        "flow" => min_max(values),
+        _ => (0.0, 1.0),
+    }
+}
+
+pub(crate) fn min_max(values: &[f32]) -> (f32, f32) {
+    values
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &v| {
+            (lo.min(v), hi.max(v))
+        })
+}
+
+pub(crate) fn histogram(values: &[f32], bins: usize, (min, max): (f32, f32)) -> Vec<u32> {
+    let mut counts = vec![0u32; bins];
+    let span = (max - min).max(f32::EPSILON);
+    for &value in values {
+        let bin = (((value - min) / span) * bins as f32).floor();
+        counts[(bin.max(0.0) as usize).min(bins - 1)] += 1;
+    }
+    counts
+}
+
+/// Linear-interpolated percentile using an O(n) selection on a copy.
+pub(crate) fn percentile(values: &[f32], p: f32) -> f32 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mut scratch = values.to_vec();
+    let rank = (p / 100.0).clamp(0.0, 1.0) * (scratch.len() - 1) as f32;
+    let lower = rank.floor() as usize;
+    let (_, &mut low, above) = scratch.select_nth_unstable_by(lower, f32::total_cmp);
+    let fraction = rank - lower as f32;
+    if fraction <= 0.0 || above.is_empty() {
+        return low;
+    }
+    let high = above.iter().copied().fold(f32::INFINITY, f32::min);
+    low + (high - low) * fraction
+}
@@ -0,0 +1,310 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::changes::ChangeLog;
+use crate::editing::{recompute, CellRect};
+use crate::exploration::Exploration;
+use crate::faults::Fault;
+use crate::geometry::{distance, smoothstep};
+use crate::history::{History, Trail};
+use crate::poi::{Coverage, Poi};
+use crate::{MapCache, MapResult, Settlement, REGION_SIZE};
+
+#[derive(Clone, Copy)]
+pub(crate) enum BlendCurve {
+    Linear,
+    Smoothstep,
+    Cosine,
+}
+
+impl BlendCurve {
+    fn weight(self, t: f32) -> f32 {
+        match self {
+            BlendCurve::Linear => t,
+            BlendCurve::Smoothstep => smoothstep(t),
+            BlendCurve::Cosine => 0.5 - 0.5 * (t * std::f32::consts::PI).cos(),
+        }
+    }
+}
+
+/// Joins `left` and `right` along their shared vertical edge. The last
+/// `overlap_cells` columns of `left` cross-fade into the first columns of
+/// `right` using a `"linear"`, `"smoothstep"`, or `"cosine"` curve. Heights
+/// and base moisture are blended; flow, water, moisture, and biomes are
+/// rederived over the joined grid, and temperature follows from the blended
+/// elevation so it crosses the seam just as smoothly. Right-hand settlement ids are
+/// shifted past the left ones, and the two road networks are joined by their
+/// shortest cross-seam link. The result spans the usual world region, so
+/// world x coordinates are rescaled to the wider grid.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn stitch_maps(
+    left: &MapResult,
+    right: &MapResult,
+    overlap_cells: u32,
+    blend_curve: &str,
+) -> Result<MapResult, JsValue> {
+    let curve = match blend_curve {
+        "linear" => BlendCurve::Linear,
+        "smoothstep" => BlendCurve::Smoothstep,
+        "cosine" => BlendCurve::Cosine,
+        other => return Err(JsValue::from_str(&format!("unknown blend curve: {other}"))),
+    };
+    stitch(left, right, overlap_cells, curve).map_err(|message| JsValue::from_str(&message))
+}
+
+pub(crate) fn stitch(
+    left: &MapResult,
+    right: &MapResult,
+    overlap_cells: u32,
+    curve: BlendCurve,
+) -> Result<MapResult, String> {
+    if left.height != right.height {
+        return Err(format!(
+            "shared edge mismatch: left is {} cells tall, right is {}",
+            left.height, right.height
+        ));
+    }
+    if overlap_cells > left.width.min(right.width) {
+        return Err("overlap is wider than one of the maps".to_string());
+    }
+
+    let width = left.width + right.width - overlap_cells;
+    let height = left.height;
+    let offset = (left.width - overlap_cells) as usize;
+    let blend = |left_layer: &[f32], right_layer: &[f32]| {
+        let mut joined = vec![0.0f32; (width * height) as usize];
+        for y in 0..height as usize {
+            for x in 0..width as usize {
+                let from_left =
+                    (x < left.width as usize).then(|| left_layer[y * left.width as usize + x]);
+                let from_right =
+                    (x >= offset).then(|| right_layer[y * right.width as usize + x - offset]);
+                joined[y * width as usize + x] = match (from_left, from_right) {
+                    (Some(a), Some(b)) => {
+                        let t = (x - offset) as f32 + 0.5;
+                        let weight = curve.weight(t / overlap_cells as f32);
+                        a + (b - a) * weight
+                    }
+                    (Some(value), None) | (None, Some(value)) => value,
+                    (None, None) => unreachable!(),
+                };
+            }
+        }
+        joined
+    };
+
+    let heightmap = blend(&left.heightmap, &right.heightmap);
+    let base_moisture = blend(&left.base_moisture, &right.base_moisture);
+    let mut exploration = Exploration::new((width * height) as usize);
+    for (source, column_offset) in [(left, 0), (right, offset)] {
+        let source_width = source.width as usize;
+        for (index, &value) in source.exploration.mask.iter().enumerate() {
+            let joined =
+                (index / source_width) * width as usize + index % source_width + column_offset;
+            exploration.mask[joined] = exploration.mask[joined].max(value);
+        }
+    }
+    exploration.edge = left.exploration.edge;
+    let size = heightmap.len();
+
+    let id_offset = left
+        .settlements
+        .iter()
+        .map(|settlement| settlement.id + 1)
+        .max()
+        .unwrap_or(0);
+    let rescale_x = |x: f32, source: &MapResult, column_offset: usize| {
+        let column = x / REGION_SIZE * source.width as f32 + column_offset as f32;
+        column / width as f32 * REGION_SIZE
+    };
+    let rescale =
+        |settlement: &Settlement, source: &MapResult, column_offset: usize, id: u32| Settlement {
+            id,
+            x: rescale_x(settlement.x, source, column_offset),
+            ..settlement.clone()
+        };
+    let left_settlements: Vec<Settlement> = left
+        .settlements
+        .iter()
+        .map(|settlement| rescale(settlement, left, 0, settlement.id))
+        .collect();
+    let right_settlements: Vec<Settlement> = right
+        .settlements
+        .iter()
+        .map(|settlement| rescale(settlement, right, offset, settlement.id + id_offset))
+        .collect();
+
+    let mut road_graph = left.road_graph.clone();
+    road_graph.extend(
+        right
+            .road_graph
+            .iter()
+            .map(|(a, b)| (a + id_offset, b + id_offset)),
+    );
+    let seam = left_settlements
+        .iter()
+        .flat_map(|a| right_settlements.iter().map(move |b| (a, b)))
+        .min_by(|(a0, b0), (a1, b1)| {
+            let d0 = distance((a0.x, a0.y), (b0.x, b0.y));
+            let d1 = distance((a1.x, a1.y), (b1.x, b1.y));
+            d0.total_cmp(&d1)
+        });
+    if let Some((a, b)) = seam {
+        road_graph.push((a.id, b.id));
+    }
+
+    let mut history = History {
+        eras: left.history.eras.max(right.history.eras),
+        ..History::default()
+    };
+    let mut pois = Vec::new();
+    for (source, column_offset, id_offset) in [(left, 0, 0), (right, offset, id_offset)] {
+        history
+            .trails
+            .extend(source.history.trails.iter().map(|trail| Trail {
+                from: (rescale_x(trail.from.0, source, column_offset), trail.from.1),
+                to: (rescale_x(trail.to.0, source, column_offset), trail.to.1),
+                era: trail.era,
+            }));
+        history.road_eras.extend(
+            source
+                .history
+                .road_eras
+                .iter()
+                .map(|(&(a, b), &era)| ((a + id_offset, b + id_offset), era)),
+        );
+        let poi_offset = pois.len() as u32;
+        pois.extend(source.pois.iter().map(|poi| Poi {
+            id: poi_offset + poi.id,
+            x: rescale_x(poi.x, source, column_offset),
+            coverage: poi.coverage.as_ref().map(|coverage| {
+                Coverage {
+                    // Cells keep their size in rows but narrow in x.
+                    area: coverage.area * source.width as f32 / width as f32,
+                    targets: coverage
+                        .targets
+                        .iter()
+                        .map(|&(x, y)| (rescale_x(x, source, column_offset), y))
+                        .collect(),
+                }
+            }),
+            ..poi.clone()
+        }));
+    }
+
+    let faults: Vec<Fault> = [(left, 0), (right, offset)]
+        .into_iter()
+        .flat_map(|(source, column_offset)| {
+            source.faults.iter().map(move |fault| Fault {
+                points: fault
+                    .points
+                    .iter()
+                    .map(|&(x, y)| (rescale_x(x, source, column_offset), y))
+                    .collect(),
+                ..fault.clone()
+            })
+        })
+        .collect();
+
+    let mut settlements = left_settlements;
+    settlements.extend(right_settlements);
+
+    let mut map = MapResult {
+        width,
+        height,
+        heightmap,
+        flow: vec![1.0; size],
+        moisture: base_moisture.clone(),
+        temperature: vec![0.0; size],
+        biome: vec![0; size],
+        water: vec![0.0; size],
+        road_graph,
+        settlements,
+        sea_level: (left.sea_level + right.sea_level) * 0.5,
+        base_moisture,
+        settings: left.settings.clone(),
+        dirty: None,
+        exploration,
+        pois,
+        history,
+        ice: left.ice.clone(),
+        faults,
+        ambience: left.ambience.clone(),
+        changes: ChangeLog::full(width, height),
+        pyramid: left.pyramid.clone(),
+        // Owner ids of the two halves would collide; start from territories.
+        ownership: None,
+        placements: Vec::new(),
+        cache: MapCache::default(),
+    };
+    let full = CellRect {
+        x0: 0,
+        y0: 0,
+        x1: width as usize - 1,
+        y1: height as usize - 1,
+    };
+    recompute(&mut map, full);
+    Ok(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{stitch, BlendCurve};
+    use crate::geometry::distance;
+    use crate::{generate_map, REGION_SIZE};
+
+    #[test]
+    fn maps_of_different_heights_do_not_stitch() {
+        let left = generate_map(32, 32, 1, 0.42, 1.0, 40.0, 0, 1.0);
+        let right = generate_map(32, 24, 2, 0.42, 1.0, 40.0, 0, 1.0);
+        let error = stitch(&left, &right, 4, BlendCurve::Linear).err().unwrap();
+        assert!(error.contains("shared edge mismatch"), "{error}");
+        let error = stitch(&left, &left, 40, BlendCurve::Linear).err().unwrap();
+        assert!(error.contains("overlap"), "{error}");
+    }
+
+    #[test]
+    fn right_settlements_are_renumbered_and_linked_across_the_seam() {
+        let left = generate_map(48, 48, 1, 0.42, 1.0, 40.0, 2, 1.0);
+        let right = generate_map(48, 48, 2, 0.42, 1.0, 40.0, 2, 1.0);
+        assert!(!left.settlements.is_empty() && !right.settlements.is_empty());
+        let joined = stitch(&left, &right, 8, BlendCurve::Smoothstep).unwrap();
+        assert_eq!((joined.width, joined.height), (88, 48));
+
+        let shift = left.settlements.iter().map(|s| s.id + 1).max().unwrap();
+        let ids: Vec<u32> = joined.settlements.iter().map(|s| s.id).collect();
+        let expected: Vec<u32> = left
+            .settlements
+            .iter()
+            .map(|s| s.id)
+            .chain(right.settlements.iter().map(|s| s.id + shift))
+            .collect();
+        assert_eq!(ids, expected);
+        let (lefts, rights) = joined.settlements.split_at(left.settlements.len());
+        assert!(lefts.iter().all(|s| s.x < 48.0 / 88.0 * REGION_SIZE));
+        assert!(rights.iter().all(|s| s.x >= 40.0 / 88.0 * REGION_SIZE));
+
+        // Both networks survive renumbered, plus one link across the seam.
+        let mut roads = left.road_graph.clone();
+        roads.extend(
+            right
+                .road_graph
+                .iter()
+                .map(|&(a, b)| (a + shift, b + shift)),
+        );
+        let (link, carried) = joined.road_graph.split_last().unwrap();
+        assert_eq!(carried, roads.as_slice());
+        let (a, b) = (
+            joined.settlement(link.0).unwrap(),
+            joined.settlement(link.1).unwrap(),
+        );
+        assert!(a.id < shift && b.id >= shift);
+        let seam = distance((a.x, a.y), (b.x, b.y));
+        for a in lefts {
+            for b in rights {
+                assert!(distance((a.x, a.y), (b.x, b.y)) >= seam);
+            }
+        }
+    }
+}
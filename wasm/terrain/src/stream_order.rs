@@ -0,0 +1,233 @@
+use std::collections::VecDeque;
+
+#[cfg(feature = "wasm")]
+use js_sys::Uint8Array;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::hydrology::{extract_lakes, extract_rivers, River};
+use crate::json::Json;
+use crate::regime::river_regimes;
+use crate::MapResult;
+
+/// Strahler order of every node of a drainage forest, given the node each
+/// one drains into. Sources are order 1. Where streams meet, the node takes
+/// the highest inflowing order, plus one when two or more inflows share it,
+/// so any number of streams can meet at one node. `pass_through` nodes
+/// (lakes) take the highest inflowing order without ever adding one, and
+/// are 0 when nothing flows in. Nodes in or below a cycle are left at 1.
+pub(crate) fn strahler_orders(downstream: &[Option<usize>], pass_through: &[bool]) -> Vec<u8> {
+    let size = downstream.len();
+    let mut pending = vec![0u32; size];
+    for &next in downstream.iter().flatten() {
+        pending[next] += 1;
+    }
+    // Highest inflowing order and how many inflows reach it.
+    let mut highest = vec![(0u8, 0u32); size];
+    let mut orders = vec![1u8; size];
+    let mut queue: VecDeque<usize> = (0..size).filter(|&node| pending[node] == 0).collect();
+    while let Some(node) = queue.pop_front() {
+        let (top, count) = highest[node];
+        orders[node] = if pass_through[node] {
+            top
+        } else if count >= 2 {
+            top.saturating_add(1)
+        } else {
+            top.max(1)
+        };
+        let Some(next) = downstream[node] else {
+            continue;
+        };
+        let order = orders[node];
+        let entry = &mut highest[next];
+        if order > entry.0 {
+            *entry = (order, 1);
+        } else if order == entry.0 && order > 0 {
+            entry.1 += 1;
+        }
+        pending[next] -= 1;
+        if pending[next] == 0 {
+            queue.push_back(next);
+        }
+    }
+    orders
+}
+
+pub(crate) struct StreamOrders {
+    /// Order of each river cell, 0 off the river network.
+    pub cells: Vec<u8>,
+    /// Order of each segment of each river's polyline, in `extract_rivers`
+    /// order: segment `i` joins points `i` and `i + 1`.
+    pub rivers: Vec<Vec<u8>>,
+}
+
+/// Strahler orders over the river cells, each draining to its receiver.
+/// Rivers flowing into a lake feed it as one node, and the lake's overflow
+/// river continues at the highest order flowing in.
+pub(crate) fn stream_orders(map: &MapResult, rivers: &[River]) -> StreamOrders {
+    let size = map.heightmap.len();
+    let receivers = map.receivers();
+    let lakes = extract_lakes(map);
+    let mut lake_of = vec![None; size];
+    for lake in &lakes {
+        for &cell in &lake.cells {
+            lake_of[cell] = Some(lake.id as usize);
+        }
+    }
+    // One node per cell, then one per lake.
+    let mut downstream: Vec<Option<usize>> = (0..size)
+        .map(|cell| {
+            let next = receivers[cell].filter(|_| map.is_river(cell))?;
+            if map.is_river(next) {
+                Some(next)
+            } else {
+                lake_of[next].map(|lake| size + lake)
+            }
+        })
+        .collect();
+    downstream.resize(size + lakes.len(), None);
+    for river in rivers.iter().filter(|river| river.from_lake) {
+        if let Some(lake) = lake_of[river.cells[0]] {
+            downstream[size + lake] = Some(river.cells[1]);
+        }
+    }
+    let mut pass_through = vec![false; size];
+    pass_through.resize(size + lakes.len(), true);
+
+    let mut cells = strahler_orders(&downstream, &pass_through);
+    cells.truncate(size);
+    for (cell, order) in cells.iter_mut().enumerate() {
+        if !map.is_river(cell) {
+            *order = 0;
+        }
+    }
+    let rivers = rivers
+        .iter()
+        .map(|river| {
+            river
+                .cells
+                .windows(2)
+                .map(|pair| {
+                    // The lake cell an overflow river starts from takes the
+                    // order of the river it spills into.
+                    let upstream = if map.is_river(pair[0]) {
+                        pair[0]
+                    } else {
+                        pair[1]
+                    };
+                    cells[upstream]
+                })
+                .collect()
+        })
+        .collect();
+    StreamOrders { cells, rivers }
+}
+
+/// `River::record` plus the highest `order` along the river, the
+/// per-segment `orders`, and the seasonal regime.
+pub(crate) fn river_records(map: &MapResult) -> Vec<Vec<(&'static str, Json)>> {
+    let rivers = extract_rivers(map);
+    let orders = stream_orders(map, &rivers);
+    rivers
+        .iter()
+        .zip(orders.rivers)
+        .zip(river_regimes(map, &rivers))
+        .map(|((river, segments), regime)| {
+            let mut record = river.record(map);
+            let order = segments.iter().copied().max().unwrap_or(1);
+            record.push(("order", (order as u32).into()));
+            record.push((
+                "orders",
+                segments
+                    .into_iter()
+                    .map(|order| order as u32)
+                    .collect::<Vec<_>>()
+                    .into(),
+            ));
+            record.extend(regime.record());
+            record
+        })
+        .collect()
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Strahler order of each river cell, 0 elsewhere. Headwater streams are
+    /// order 1; where two or more streams of the highest inflowing order
+    /// meet, the order rises by one, and otherwise it carries on. Lakes pass
+    /// the highest order flowing in on to their overflow river. `rivers()`
+    /// carries the same orders per polyline segment.
+    pub fn stream_orders(&self) -> Uint8Array {
+        Uint8Array::from(stream_orders(self, &extract_rivers(self)).cells.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{strahler_orders, stream_orders};
+    use crate::generate_map;
+    use crate::hydrology::extract_rivers;
+
+    #[test]
+    fn strahler_rules_hold_on_small_networks() {
+        // Two sources meet, then a single source joins the order-2 stem.
+        let downstream = [Some(2), Some(2), Some(4), Some(4), Some(5), None];
+        assert_eq!(
+            strahler_orders(&downstream, &[false; 6]),
+            vec![1, 1, 2, 1, 2, 2]
+        );
+        // Three sources at one node rise by one, not two.
+        let downstream = [Some(3), Some(3), Some(3), None];
+        assert_eq!(strahler_orders(&downstream, &[false; 4]), vec![1, 1, 1, 2]);
+        // Two order-2 streams make order 3.
+        let downstream = [Some(2), Some(2), Some(6), Some(5), Some(5), Some(6), None];
+        assert_eq!(
+            strahler_orders(&downstream, &[false; 7]),
+            vec![1, 1, 2, 1, 1, 2, 3]
+        );
+        // A lake (node 4) fed by order-2 and order-1 rivers passes on 2, and
+        // two order-2 inflows do not raise it either.
+        let downstream = [Some(2), Some(2), Some(4), Some(4), Some(5), None];
+        let lake = [false, false, false, false, true, false];
+        assert_eq!(strahler_orders(&downstream, &lake), vec![1, 1, 2, 1, 2, 2]);
+        let downstream = [
+            Some(2),
+            Some(2),
+            Some(6),
+            Some(5),
+            Some(5),
+            Some(6),
+            Some(7),
+            None,
+        ];
+        let lake = [false, false, false, false, false, false, true, false];
+        assert_eq!(strahler_orders(&downstream, &lake)[6..], [2, 2]);
+        // A lake with nothing flowing in starts its overflow at order 1.
+        assert_eq!(
+            strahler_orders(&[Some(1), None], &[true, false]),
+            vec![0, 1]
+        );
+    }
+
+    #[test]
+    fn map_orders_rise_downstream() {
+        let map = generate_map(96, 96, 4, 0.42, 1.0, 40.0, 2, 1.0);
+        let rivers = extract_rivers(&map);
+        let orders = stream_orders(&map, &rivers);
+        let receivers = map.receivers();
+        for (cell, &order) in orders.cells.iter().enumerate() {
+            assert_eq!(order > 0, map.is_river(cell));
+            if let Some(next) = receivers[cell].filter(|&next| map.is_river(next)) {
+                if order > 0 {
+                    assert!(orders.cells[next] >= order);
+                }
+            }
+        }
+        assert!(orders.cells.iter().any(|&order| order >= 2));
+        for (river, segments) in rivers.iter().zip(&orders.rivers) {
+            assert_eq!(segments.len(), river.cells.len().saturating_sub(1));
+            assert!(segments.windows(2).all(|pair| pair[0] <= pair[1]));
+        }
+    }
+}
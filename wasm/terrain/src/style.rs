@@ -0,0 +1,256 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::biome::BIOMES;
+use crate::json::Json;
+use crate::poi::{PoiKind, POI_KINDS};
+use crate::render::RenderOptions;
+
+/// Color of codes with no biome.
+pub(crate) const FALLBACK_COLOR: [u8; 3] = [128, 128, 128];
+/// Share by which water at the bottom of the map's range is darkened.
+pub(crate) const DEPTH_DARKENING: f32 = 0.6;
+
+/// Land tint stops over elevation above sea level, normalized to 0..1.
+pub(crate) const HYPSOMETRIC_STOPS: [(f32, [u8; 3]); 6] = [
+    (0.0, [192, 168, 128]),
+    (0.1, [146, 169, 92]),
+    (0.35, [110, 139, 80]),
+    (0.6, [160, 120, 96]),
+    (0.8, [200, 200, 200]),
+    (1.0, [240, 240, 240]),
+];
+
+/// How a line feature is drawn over the biome colors.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) struct Stroke {
+    pub color: [u8; 3],
+    /// In cells.
+    pub width: f32,
+    /// Whether the color is mixed in by the cell's `water` rather than
+    /// painted solid.
+    pub by_water: bool,
+}
+
+impl Stroke {
+    /// With `tint` in place of the default color.
+    fn record(&self, tint: [u8; 3], enabled: bool) -> Json {
+        Json::object()
+            .with("color", color(tint))
+            .with("width", self.width)
+            .with("blend", if self.by_water { "water" } else { "solid" })
+            .with("enabled", enabled)
+    }
+}
+
+pub(crate) const RIVER_STROKE: Stroke = Stroke {
+    color: [28, 88, 160],
+    width: 1.0,
+    by_water: true,
+};
+
+pub(crate) const ROAD_STROKE: Stroke = Stroke {
+    color: [92, 64, 40],
+    width: 1.0,
+    by_water: false,
+};
+
+/// A float layer and the values it holds. `max` is `INFINITY` for
+/// unbounded layers.
+pub(crate) struct LayerRange {
+    pub name: &'static str,
+    pub min: f32,
+    pub max: f32,
+    pub description: &'static str,
+}
+
+/// Every float layer, in the order the layer getters are listed.
+pub(crate) const FLOAT_LAYERS: [LayerRange; 5] = [
+    LayerRange {
+        name: "heightmap",
+        min: 0.0,
+        max: 1.0,
+        description: "elevation; sea_level splits land from sea",
+    },
+    LayerRange {
+        name: "flow",
+        min: 1.0,
+        max: f32::INFINITY,
+        description: "cells draining through the cell, itself included",
+    },
+    LayerRange {
+        name: "moisture",
+        min: 0.0,
+        max: 1.0,
+        description: "rainfall, dry to wet",
+    },
+    LayerRange {
+        name: "temperature",
+        min: 0.0,
+        max: 1.0,
+        description: "cold to hot",
+    },
+    LayerRange {
+        name: "water",
+        min: 0.0,
+        max: 1.0,
+        description: "river runoff; 1 on open water",
+    },
+];
+
+/// Multiplier `render_rgba` applies to water at `elevation`.
+pub(crate) fn depth_shade(elevation: f32, sea_level: f32) -> f32 {
+    let depth = ((sea_level - elevation) / sea_level.max(f32::EPSILON)).min(1.0);
+    1.0 - depth * DEPTH_DARKENING
+}
+
+/// Suggested legend glyph for a POI kind.
+pub(crate) fn poi_symbol(kind: PoiKind) -> &'static str {
+    match kind {
+        PoiKind::Ruin => "\u{26EC}",
+        PoiKind::Oasis => "\u{2663}",
+        PoiKind::HotSpring => "\u{2668}",
+        PoiKind::Geyser => "\u{26F2}",
+        PoiKind::Fumarole => "\u{2601}",
+        PoiKind::Watchtower => "\u{265C}",
+        PoiKind::Lighthouse => "\u{26EF}",
+    }
+}
+
+fn color([r, g, b]: [u8; 3]) -> Json {
+    vec![r as u32, g as u32, b as u32].into()
+}
+
+/// Everything the renderers draw with under `options`, for legends and
+/// settings panels; see `style_manifest`.
+pub(crate) fn manifest(options: &RenderOptions) -> Json {
+    let biomes = BIOMES
+        .iter()
+        .map(|biome| {
+            Json::object()
+                .with("code", biome.code() as u32)
+                .with("key", biome.key())
+                .with("display_name", biome.display_name())
+                .with("color", color(options.palette[biome.code() as usize]))
+                .with("default_color", color(biome.default_color()))
+        })
+        .collect::<Vec<_>>();
+    let stops = HYPSOMETRIC_STOPS
+        .iter()
+        .map(|&(at, tint)| Json::object().with("at", at).with("color", color(tint)))
+        .collect::<Vec<_>>();
+    let pois = POI_KINDS
+        .iter()
+        .map(|&kind| {
+            Json::object()
+                .with("type", kind.key())
+                .with("class", kind.class().key())
+                .with("symbol", poi_symbol(kind))
+                .with("radius", kind.class().radius())
+        })
+        .collect::<Vec<_>>();
+    let layers = FLOAT_LAYERS
+        .iter()
+        .map(|layer| {
+            Json::object()
+                .with("name", layer.name)
+                .with("min", layer.min)
+                .with("max", layer.max)
+                .with("description", layer.description)
+        })
+        .collect::<Vec<_>>();
+    Json::object()
+        .with("version", 1u32)
+        .with("biomes", biomes)
+        .with("fallback_color", color(FALLBACK_COLOR))
+        .with(
+            "water",
+            Json::object()
+                .with("depth_darkening", DEPTH_DARKENING)
+                .with("enabled", options.water_depth),
+        )
+        .with(
+            "river",
+            RIVER_STROKE.record(options.river_color, options.rivers),
+        )
+        .with(
+            "road",
+            ROAD_STROKE.record(options.road_color, options.roads),
+        )
+        .with(
+            "relief",
+            Json::object()
+                .with("hypsometric", options.hypsometric)
+                .with("hypsometric_strength", options.hypsometric_strength)
+                .with("hypsometric_stops", stops)
+                .with("hillshade", options.hillshade)
+                .with("hillshade_strength", options.hillshade_strength),
+        )
+        .with("pois", pois)
+        .with("layers", layers)
+        .with("render_options", options.record())
+}
+
+/// The biome table with colors and names, the water, river, and road
+/// styles, hypsometric stops, POI types with suggested glyphs, and the
+/// value range of every float layer, as drawn by `render_rgba` with the same
+/// `options`: `{ version, biomes: [{ code, key, display_name, color,
+/// default_color }], fallback_color, water: { depth_darkening, enabled },
+/// river, road: { color, width, blend, enabled }, relief: { hypsometric,
+/// hypsometric_strength, hypsometric_stops: [{ at, color }], hillshade,
+/// hillshade_strength }, pois: [{ type, class, symbol, radius }], layers:
+/// [{ name, min, max, description }], render_options }`. Colors are `[r, g,
+/// b]`; stroke widths are in cells; `blend` is `"water"` when the stroke
+/// mixes in by the cell's `water` and `"solid"` otherwise; `max` is `null`
+/// for unbounded layers. `render_options` spells out every option, overrides
+/// included, and renders the same when passed back to `render_rgba`.
+/// Throws on malformed options, as `render_rgba` does.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn style_manifest(options: JsValue) -> Result<JsValue, JsValue> {
+    Ok(manifest(&RenderOptions::from_js(&options)?).to_js())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{manifest, FLOAT_LAYERS};
+    use crate::generate_map;
+    use crate::render::{render_rgba, RenderOptions};
+
+    #[test]
+    fn manifest_colors_are_the_rendered_colors() {
+        let map = generate_map(64, 64, 2, 0.42, 1.0, 40.0, 0, 1.0);
+        let options = RenderOptions {
+            hypsometric: false,
+            hillshade: false,
+            water_depth: false,
+            rivers: false,
+            road_color: [1, 2, 3],
+            ..RenderOptions::default()
+        };
+        let rgba = render_rgba(&map, &options);
+        let roads = map.road_mask();
+        for (index, pixel) in rgba.chunks_exact(4).enumerate() {
+            let expected = if roads[index] {
+                options.road_color
+            } else {
+                options.palette[map.biome[index] as usize]
+            };
+            assert_eq!(pixel[..3], expected);
+        }
+
+        let text = manifest(&options).to_string();
+        assert!(
+            text.contains(r#""road":{"color":[1,2,3],"width":1,"blend":"solid""#),
+            "{text}"
+        );
+        assert!(text.contains(r#""name":"flow","min":1,"max":null"#));
+        assert!(text.contains(r#""road_color":[1,2,3]"#));
+        for layer in FLOAT_LAYERS {
+            let values = map.float_layer(layer.name).expect("listed layers exist");
+            assert!(values
+                .iter()
+                .all(|value| (layer.min..=layer.max).contains(value)));
+        }
+    }
+}
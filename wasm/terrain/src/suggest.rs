@@ -0,0 +1,291 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::biome::Biome;
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::json::Json;
+use crate::stats::percentile;
+use crate::thumbnails::{preview_layers, ThumbnailScratch};
+use crate::GenerationSettings;
+
+/// Statistics a suggestion aims for; `None` leaves one free. Fractions are
+/// shares of land except `land_fraction`, a share of the whole map.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct Targets {
+    pub land_fraction: Option<f32>,
+    /// Alpine cells.
+    pub mountain_fraction: Option<f32>,
+    /// Boreal, temperate, and tropical forest cells.
+    pub forest_fraction: Option<f32>,
+    /// Land cells carrying a river.
+    pub river_density: Option<f32>,
+}
+
+pub(crate) struct SuggestOptions {
+    pub targets: Targets,
+    pub max_trials: u32,
+    /// Side of the square preview each trial generates.
+    pub size: u32,
+}
+
+impl Default for SuggestOptions {
+    fn default() -> Self {
+        Self {
+            targets: Targets::default(),
+            max_trials: 24,
+            size: 64,
+        }
+    }
+}
+
+impl SuggestOptions {
+    #[cfg(feature = "wasm")]
+    fn from_js(options: &JsValue) -> Self {
+        let defaults = Self::default();
+        let target = |key: &str| {
+            js::get(options, key)
+                .and_then(|value| value.as_f64())
+                .map(|value| (value as f32).clamp(0.0, 1.0))
+        };
+        Self {
+            targets: Targets {
+                land_fraction: target("land_fraction"),
+                mountain_fraction: target("mountain_fraction"),
+                forest_fraction: target("forest_fraction"),
+                river_density: target("river_density"),
+            },
+            max_trials: js::get_u32(options, "max_trials", defaults.max_trials).max(1),
+            size: js::get_u32(options, "size", defaults.size).clamp(16, 256),
+        }
+    }
+}
+
+/// What one preview measured.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct Achieved {
+    pub land_fraction: f32,
+    pub mountain_fraction: f32,
+    pub forest_fraction: f32,
+    pub river_density: f32,
+}
+
+impl Achieved {
+    fn measure(scratch: &ThumbnailScratch, sea_level: f32) -> Self {
+        let (mut land, mut mountain, mut forest, mut river) = (0, 0, 0, 0);
+        for (index, &height) in scratch.heightmap.iter().enumerate() {
+            if height <= sea_level {
+                continue;
+            }
+            land += 1;
+            match Biome::from_code(scratch.biome[index]) {
+                Some(Biome::Alpine) => mountain += 1,
+                Some(Biome::BorealForest | Biome::TemperateForest | Biome::TropicalForest) => {
+                    forest += 1
+                }
+                Some(Biome::Lake) => continue,
+                _ => {}
+            }
+            if scratch.water[index] > 0.0 {
+                river += 1;
+            }
+        }
+        let share = |count: usize| count as f32 / land.max(1) as f32;
+        Self {
+            land_fraction: land as f32 / scratch.heightmap.len() as f32,
+            mountain_fraction: share(mountain),
+            forest_fraction: share(forest),
+            river_density: share(river),
+        }
+    }
+
+    /// Squared distance to the targets that are set.
+    fn error(&self, targets: &Targets) -> f32 {
+        [
+            (targets.land_fraction, self.land_fraction),
+            (targets.mountain_fraction, self.mountain_fraction),
+            (targets.forest_fraction, self.forest_fraction),
+            (targets.river_density, self.river_density),
+        ]
+        .iter()
+        .filter_map(|&(target, value)| target.map(|target| (value - target).powi(2)))
+        .sum()
+    }
+
+    fn record(&self) -> Json {
+        Json::object()
+            .with("land_fraction", self.land_fraction)
+            .with("mountain_fraction", self.mountain_fraction)
+            .with("forest_fraction", self.forest_fraction)
+            .with("river_density", self.river_density)
+    }
+}
+
+/// Parameters the search moves, with their step and bounds.
+const AXES: [(f32, f32, f32); 3] = [
+    // elevation_amplitude
+    (0.4, 0.2, 3.0),
+    // moisture_scale
+    (0.4, 0.1, 3.0),
+    // erosion_iterations
+    (4.0, 0.0, 16.0),
+];
+
+pub(crate) struct Suggestion {
+    pub settings: GenerationSettings,
+    pub achieved: Achieved,
+    pub error: f32,
+    pub trials: u32,
+}
+
+/// Pattern search over amplitude, moisture scale, and erosion iterations on
+/// `options.size`² previews, starting from `base`. Each trial first reads
+/// the sea level that gives the target land fraction off the elevation
+/// percentiles, then measures the rest. Steps halve once no move improves,
+/// and the search stops after `options.max_trials` previews.
+pub(crate) fn suggest(base: &GenerationSettings, options: &SuggestOptions) -> Suggestion {
+    let mut scratch = ThumbnailScratch::new((options.size * options.size) as usize);
+    let mut trial = |point: [f32; 3]| {
+        let mut settings = GenerationSettings {
+            elevation_amplitude: point[0],
+            moisture_scale: point[1],
+            erosion_iterations: point[2].round() as u32,
+            ..base.clone()
+        };
+        if let Some(land) = options.targets.land_fraction {
+            // Elevation ignores the sea level, so one pass finds it.
+            preview_layers(options.size, &settings, false, &mut scratch);
+            settings.sea_level = percentile(&scratch.heightmap, (1.0 - land) * 100.0);
+        }
+        preview_layers(options.size, &settings, true, &mut scratch);
+        let achieved = Achieved::measure(&scratch, settings.sea_level);
+        let error = achieved.error(&options.targets);
+        (settings, achieved, error)
+    };
+
+    let mut point = [
+        base.elevation_amplitude,
+        base.moisture_scale,
+        base.erosion_iterations as f32,
+    ];
+    let mut best = trial(point);
+    let mut trials = 1;
+    let mut steps = AXES.map(|(step, _, _)| step);
+    let max_trials = options.max_trials.max(1);
+    'search: while trials < max_trials && best.2 > 0.0 {
+        let mut improved = false;
+        for axis in 0..AXES.len() {
+            for sign in [1.0, -1.0] {
+                if trials >= max_trials {
+                    break 'search;
+                }
+                let (_, min, max) = AXES[axis];
+                let mut candidate = point;
+                candidate[axis] = (point[axis] + sign * steps[axis]).clamp(min, max);
+                if candidate[axis] == point[axis] {
+                    continue;
+                }
+                let result = trial(candidate);
+                trials += 1;
+                if result.2 < best.2 {
+                    best = result;
+                    point = candidate;
+                    improved = true;
+                    break;
+                }
+            }
+        }
+        if !improved {
+            steps = steps.map(|step| step * 0.5);
+            // Erosion is counted in whole iterations.
+            if steps[2] < 0.5 && steps[0] < 0.01 {
+                break;
+            }
+        }
+    }
+    let (settings, achieved, error) = best;
+    Suggestion {
+        settings,
+        achieved,
+        error,
+        trials,
+    }
+}
+
+/// Searches generation parameters for a world matching `targets`:
+/// `land_fraction` (share of the map), `mountain_fraction` (alpine share
+/// of land), `forest_fraction` (forest share of land), and `river_density`
+/// (share of land carrying rivers), each 0..1 and optional. `sea_level` is
+/// solved from elevation percentiles for the land target, then
+/// `elevation_amplitude`, `moisture_scale`, and `erosion_iterations` are
+/// tuned on `size`² previews (default 64) for at most `max_trials` (default
+/// 24) trials. Other options, `seed` included, are read as in
+/// `generate_map_with_options` and kept. The same targets and seed always
+/// give the same answer. Returns `{ options, achieved, error, trials }`
+/// with `options` ready to pass to `generate_map_with_options`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn suggest_options(targets: JsValue) -> Result<JsValue, JsValue> {
+    let defaults = GenerationSettings::new(
+        js::get_u32(&targets, "seed", 0),
+        js::get_f32(&targets, "sea_level", 0.42),
+        js::get_f32(&targets, "elevation_amplitude", 1.0),
+        js::get_f32(&targets, "warp_strength", 40.0),
+        js::get_u32(&targets, "erosion_iterations", 2),
+        js::get_f32(&targets, "moisture_scale", 1.0),
+    );
+    let base = GenerationSettings::with_options(defaults, &targets)?;
+    let suggestion = suggest(&base, &SuggestOptions::from_js(&targets));
+    let settings = &suggestion.settings;
+    let options = Json::object()
+        .with("seed", settings.seed)
+        .with("sea_level", settings.sea_level)
+        .with("elevation_amplitude", settings.elevation_amplitude)
+        .with("warp_strength", settings.warp_strength)
+        .with("erosion_iterations", settings.erosion_iterations)
+        .with("moisture_scale", settings.moisture_scale);
+    Ok(Json::object()
+        .with("options", options)
+        .with("achieved", suggestion.achieved.record())
+        .with("error", suggestion.error)
+        .with("trials", suggestion.trials)
+        .to_js())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{suggest, SuggestOptions, Targets};
+    use crate::GenerationSettings;
+
+    #[test]
+    fn search_hits_land_and_forest_targets_deterministically() {
+        let options = SuggestOptions {
+            targets: Targets {
+                land_fraction: Some(0.4),
+                forest_fraction: Some(0.5),
+                ..Targets::default()
+            },
+            max_trials: 12,
+            size: 48,
+        };
+        let base = GenerationSettings::default();
+        let suggestion = suggest(&base, &options);
+        assert!(suggestion.trials <= 12);
+        assert!((suggestion.achieved.land_fraction - 0.4).abs() < 0.03);
+        let start = suggest(
+            &base,
+            &SuggestOptions {
+                max_trials: 1,
+                ..options
+            },
+        );
+        assert!(suggestion.error <= start.error);
+
+        let again = suggest(&base, &options);
+        assert_eq!(again.settings.sea_level, suggestion.settings.sea_level);
+        assert_eq!(
+            again.settings.moisture_scale,
+            suggestion.settings.moisture_scale
+        );
+    }
+}
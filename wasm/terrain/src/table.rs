@@ -0,0 +1,196 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::agriculture::settlement_agriculture;
+use crate::claims::Ownership;
+use crate::hydrology::extract_lakes;
+use crate::json::Json;
+use crate::poi::Poi;
+use crate::population::PopulationOptions;
+use crate::stream_order::river_records;
+use crate::{MapResult, Settlement, REGION_SIZE};
+
+type Record = Vec<(&'static str, Json)>;
+
+/// Settlements within this many cells of a river count as on it.
+const ON_RIVER_CELLS: f32 = 1.5;
+
+const SETTLEMENT_COLUMNS: &[&str] = &[
+    "id",
+    "x",
+    "y",
+    "size",
+    "issue",
+    "era",
+    "name",
+    "industry",
+    "port",
+    "trade_balance",
+    "population",
+    "on_river",
+    "territory_area",
+];
+const POI_COLUMNS: &[&str] = &["id", "kind", "x", "y", "era", "reason", "coverage"];
+const RIVER_COLUMNS: &[&str] = &[
+    "id",
+    "source_x",
+    "source_y",
+    "mouth_x",
+    "mouth_y",
+    "length",
+    "max_flow",
+    "outlet",
+    "joins",
+    "from_lake",
+    "order",
+    "regime",
+    "snowmelt",
+    "spring_flow",
+    "autumn_flow",
+];
+const LAKE_COLUMNS: &[&str] = &[
+    "id",
+    "x",
+    "y",
+    "area_cells",
+    "area",
+    "outlet_x",
+    "outlet_y",
+    "inflow",
+    "salt",
+];
+
+impl Settlement {
+    pub(crate) fn record(&self) -> Record {
+        vec![
+            ("id", self.id.into()),
+            ("x", self.x.into()),
+            ("y", self.y.into()),
+            ("size", self.size.into()),
+            (
+                "issue",
+                self.issue.map_or(Json::Null, |issue| issue.key().into()),
+            ),
+            ("era", self.era.into()),
+            ("name", self.name.clone().map_or(Json::Null, Json::from)),
+        ]
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Exports `"settlements"`, `"pois"`, `"rivers"`, or `"lakes"` as `"csv"`
+    /// or `"json"`. JSON rows use the same fields as the matching getters;
+    /// CSV omits array-valued fields such as river `points`. Settlement rows
+    /// add economy, trade, nominal `population`, `on_river`, and the world
+    /// `territory_area` the settlement owns in the claims layer.
+    pub fn export_table(&self, kind: &str, format: &str) -> Result<String, JsValue> {
+        export_table(self, kind, format).map_err(|message| JsValue::from_str(&message))
+    }
+}
+
+pub(crate) fn export_table(map: &MapResult, kind: &str, format: &str) -> Result<String, String> {
+    let (columns, records): (&[&str], Vec<Record>) = match kind {
+        "settlements" => {
+            let trade = map.trade_network();
+            let people = PopulationOptions::default();
+            let river_distance = map.river_distance();
+            let on_river = ON_RIVER_CELLS * REGION_SIZE / map.width.min(map.height) as f32;
+            let primed;
+            let ownership = match &map.ownership {
+                Some(ownership) => ownership,
+                None => {
+                    primed = Ownership::from_territories(map);
+                    &primed
+                }
+            };
+            let records = map
+                .settlements
+                .iter()
+                .zip(map.economies())
+                .zip(settlement_agriculture(map))
+                .enumerate()
+                .map(|(position, ((settlement, economy), calendar))| {
+                    let (x, y) = map.nearest_cell(settlement.x, settlement.y);
+                    let center = y * map.width as usize + x;
+                    // Territories are owned by `position + 1`; see claims.
+                    let owner = (position + 1).min(u16::MAX as usize) as u16;
+                    let mut record = settlement.record();
+                    record.push(("economy", economy.to_json()));
+                    record.push((
+                        "industry",
+                        economy.dominant().map_or(Json::Null, Json::from),
+                    ));
+                    record.push(("port", trade.ports[position].into()));
+                    record.push(("trade_balance", trade.balance(position).into()));
+                    record.push(("agriculture", calendar.to_json()));
+                    record.push(("population", people.people(settlement).into()));
+                    record.push(("on_river", (river_distance[center] <= on_river).into()));
+                    record.push((
+                        "territory_area",
+                        (ownership.area(owner) as f32 * map.cell_area()).into(),
+                    ));
+                    record
+                })
+                .collect();
+            (SETTLEMENT_COLUMNS, records)
+        }
+        "pois" => (POI_COLUMNS, map.pois.iter().map(Poi::record).collect()),
+        "rivers" => (RIVER_COLUMNS, river_records(map)),
+        "lakes" => (
+            LAKE_COLUMNS,
+            extract_lakes(map)
+                .iter()
+                .map(|lake| lake.record(map))
+                .collect(),
+        ),
+        other => return Err(format!("unknown table kind: {other}")),
+    };
+
+    match format {
+        "csv" => Ok(to_csv(columns, &records)),
+        "json" => Ok(Json::from(
+            records
+                .into_iter()
+                .map(Json::from_record)
+                .collect::<Vec<_>>(),
+        )
+        .to_string()),
+        other => Err(format!("unknown table format: {other}")),
+    }
+}
+
+fn to_csv(columns: &[&str], records: &[Record]) -> String {
+    let mut csv = columns.join(",");
+    csv.push('\n');
+    for record in records {
+        let row = columns
+            .iter()
+            .map(|column| {
+                record
+                    .iter()
+                    .find(|(key, _)| key == column)
+                    .map(|(_, value)| csv_field(value))
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+fn csv_field(value: &Json) -> String {
+    match value {
+        Json::Null => String::new(),
+        Json::String(text) => {
+            if text.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", text.replace('"', "\"\""))
+            } else {
+                text.clone()
+            }
+        }
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,299 @@
+#[cfg(feature = "wasm")]
+use js_sys::Float32Array;
+use noise::{NoiseFn, OpenSimplex};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "wasm")]
+use crate::MapResult;
+use crate::{GenerationSettings, DIRECTIONS, REGION_SIZE};
+
+/// Drop to a lower neighbour beyond which legacy erosion moves material.
+pub(crate) const LEGACY_STEP: f32 = 0.03;
+
+/// How thermal erosion decides which slopes shed material.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ErosionMode {
+    /// Fixed 0.03 height step per cell, halved toward each lower neighbour.
+    /// Slopes steepen as the resolution grows; kept so older seeds still
+    /// reproduce.
+    Legacy,
+    /// Slopes steeper than `talus_angle` in world units shed `thermal_transfer`
+    /// of their excess downhill, so every resolution settles to the same
+    /// angle.
+    Talus,
+}
+
+impl ErosionMode {
+    pub(crate) fn key(self) -> &'static str {
+        match self {
+            ErosionMode::Legacy => "legacy",
+            ErosionMode::Talus => "talus",
+        }
+    }
+}
+
+/// Per-cell resistance to thermal erosion, 0 soft to `settings.hardness`,
+/// from a noise channel of `hardness_scale` world units. `None` when
+/// `hardness` is 0.
+pub(crate) fn hardness_field(
+    width: u32,
+    height: u32,
+    settings: &GenerationSettings,
+) -> Option<Vec<f32>> {
+    let hardness = hardness_sampler(width, height, settings)?;
+    Some((0..(width * height) as usize).map(hardness).collect())
+}
+
+/// `hardness_field` one cell at a time, for callers keeping it in another
+/// form.
+pub(crate) fn hardness_sampler(
+    width: u32,
+    height: u32,
+    settings: &GenerationSettings,
+) -> Option<impl Fn(usize) -> f32> {
+    if settings.hardness <= 0.0 {
+        return None;
+    }
+    let noise = OpenSimplex::new(settings.seed.wrapping_add(307));
+    let scale = settings.hardness_scale.max(1.0) as f64;
+    let hardness = settings.hardness;
+    Some(move |index: usize| {
+        let x = (index % width as usize) as f64 / width as f64 * REGION_SIZE as f64;
+        let y = (index / width as usize) as f64 / height as f64 * REGION_SIZE as f64;
+        let sample = noise.get([x / scale, y / scale]) as f32;
+        ((sample * 0.5 + 0.5) * hardness).clamp(0.0, 1.0)
+    })
+}
+
+pub(crate) fn apply_thermal_erosion(
+    heightmap: &mut [f32],
+    width: u32,
+    height: u32,
+    iterations: u32,
+    settings: &GenerationSettings,
+) {
+    if iterations == 0 {
+        return;
+    }
+    match settings.erosion_mode {
+        ErosionMode::Legacy => legacy_erosion(heightmap, width, height, iterations),
+        ErosionMode::Talus => {
+            let hardness = hardness_field(width, height, settings);
+            talus_erosion(
+                heightmap,
+                width,
+                height,
+                iterations,
+                settings,
+                hardness.as_deref(),
+            );
+        }
+    }
+}
+
+fn talus_erosion(
+    heightmap: &mut [f32],
+    width: u32,
+    height: u32,
+    iterations: u32,
+    settings: &GenerationSettings,
+    hardness: Option<&[f32]>,
+) {
+    let width_i = width as usize;
+    let height_i = height as usize;
+    let limits = talus_limits(width, height, settings);
+    let transfer = settings.thermal_transfer.clamp(0.0, 1.0);
+
+    let mut excess = [0.0f32; 8];
+    for _ in 0..iterations {
+        let mut scratch = heightmap.to_vec();
+        for y in 1..(height_i - 1) {
+            for x in 1..(width_i - 1) {
+                let index = y * width_i + x;
+                let center = heightmap[index];
+                let (mut total, mut steepest) = (0.0f32, 0.0f32);
+                for (slot, (dx, dy)) in DIRECTIONS.into_iter().enumerate() {
+                    let neighbor = (y as i32 + dy) as usize * width_i + (x as i32 + dx) as usize;
+                    excess[slot] = (center - heightmap[neighbor] - limits[slot]).max(0.0);
+                    total += excess[slot];
+                    steepest = steepest.max(excess[slot]);
+                }
+                if total <= 0.0 {
+                    continue;
+                }
+                // Half the steepest excess would level that pair; moving no
+                // more keeps the cell from dropping below its neighbours.
+                let softness = hardness.map_or(1.0, |hardness| 1.0 - hardness[index]);
+                let moved = steepest * 0.5 * transfer * softness;
+                scratch[index] -= moved;
+                for (slot, (dx, dy)) in DIRECTIONS.into_iter().enumerate() {
+                    if excess[slot] > 0.0 {
+                        let neighbor =
+                            (y as i32 + dy) as usize * width_i + (x as i32 + dx) as usize;
+                        scratch[neighbor] += moved * excess[slot] / total;
+                    }
+                }
+            }
+        }
+        heightmap.copy_from_slice(&scratch);
+    }
+}
+
+/// Height step, in normalized units, that reaches the talus angle over the
+/// distance to each neighbour in `DIRECTIONS`.
+pub(crate) fn talus_limits(width: u32, height: u32, settings: &GenerationSettings) -> [f32; 8] {
+    let cell_w = REGION_SIZE / width as f32;
+    let cell_h = REGION_SIZE / height as f32;
+    let rise = settings.talus_angle.to_radians().tan() / settings.vertical_scale.max(f32::EPSILON);
+    DIRECTIONS.map(|(dx, dy)| rise * (dx as f32 * cell_w).hypot(dy as f32 * cell_h))
+}
+
+fn legacy_erosion(heightmap: &mut [f32], width: u32, height: u32, iterations: u32) {
+    let width_i = width as usize;
+    let height_i = height as usize;
+    for _ in 0..iterations {
+        let mut scratch = heightmap.to_vec();
+        for y in 1..(height_i - 1) {
+            for x in 1..(width_i - 1) {
+                let index = y * width_i + x;
+                let center = heightmap[index];
+                let mut total = center;
+                let mut count = 1.0f32;
+                for (dx, dy) in DIRECTIONS {
+                    let nx = (x as i32 + dx) as usize;
+                    let ny = (y as i32 + dy) as usize;
+                    let n_index = ny * width_i + nx;
+                    let neighbor = heightmap[n_index];
+                    if (center - neighbor) > LEGACY_STEP {
+                        total += neighbor + (center - neighbor) * 0.5;
+                        count += 1.0;
+                    }
+                }
+                scratch[index] = total / count;
+            }
+        }
+        heightmap.copy_from_slice(&scratch);
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Per-cell resistance to thermal erosion the map was generated with,
+    /// 0..`hardness`; all zeros when `hardness` is 0. For debugging crags.
+    pub fn hardness(&self) -> Float32Array {
+        let field = hardness_field(self.width, self.height, &self.settings)
+            .unwrap_or_else(|| vec![0.0; self.heightmap.len()]);
+        Float32Array::from(field.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_thermal_erosion, hardness_field, ErosionMode};
+    use crate::native::sample_terrain;
+    use crate::{GenerationSettings, REGION_SIZE};
+
+    /// Share of cells in each 5° band of world slope along x.
+    fn slope_histogram(heightmap: &[f32], size: usize, settings: &GenerationSettings) -> Vec<f32> {
+        let cell = REGION_SIZE / size as f32;
+        let mut bins = [0.0f32; 18];
+        let mut count = 0.0;
+        for y in 1..size - 1 {
+            for x in 1..size - 2 {
+                let rise = (heightmap[y * size + x] - heightmap[y * size + x + 1]).abs();
+                let degrees = (rise * settings.vertical_scale / cell).atan().to_degrees();
+                bins[((degrees / 5.0) as usize).min(17)] += 1.0;
+                count += 1.0;
+            }
+        }
+        bins.iter().map(|bin| bin / count).collect()
+    }
+
+    /// A cone as steep as 60° in world units, sampled at `size`².
+    fn cone(size: usize, settings: &GenerationSettings) -> Vec<f32> {
+        let rise = 60f32.to_radians().tan() / settings.vertical_scale;
+        (0..size * size)
+            .map(|index| {
+                let x = (index % size) as f32 / size as f32 * REGION_SIZE;
+                let y = (index / size) as f32 / size as f32 * REGION_SIZE;
+                let distance = (x - 1024.0).hypot(y - 1024.0);
+                (0.3 - distance * rise).max(0.1)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn talus_slopes_match_across_resolutions() {
+        // Tall relief, so the cone spans many cells at either resolution.
+        let settings = GenerationSettings {
+            vertical_scale: 2000.0,
+            ..GenerationSettings::default()
+        };
+        let eroded = |size: usize, iterations: u32| {
+            let mut heightmap = cone(size, &settings);
+            let cells = size as u32;
+            apply_thermal_erosion(&mut heightmap, cells, cells, iterations, &settings);
+            slope_histogram(&heightmap, size, &settings)
+        };
+        let coarse = eroded(64, 150);
+        let fine = eroded(128, 600);
+        let distance: f32 = coarse.iter().zip(&fine).map(|(a, b)| (a - b).abs()).sum();
+        assert!(distance < 0.2, "{coarse:?}\n{fine:?}");
+        // Nothing is left much steeper than the talus angle.
+        let steep = |bins: &[f32]| {
+            bins[(settings.talus_angle as usize + 10) / 5..]
+                .iter()
+                .sum::<f32>()
+        };
+        assert!(
+            steep(&coarse) < 0.02 && steep(&fine) < 0.02,
+            "{coarse:?}\n{fine:?}"
+        );
+
+        let legacy = GenerationSettings {
+            erosion_mode: ErosionMode::Legacy,
+            ..settings.clone()
+        };
+        let legacy_histogram = |size: usize| {
+            let mut heightmap = cone(size, &settings);
+            let cells = size as u32;
+            apply_thermal_erosion(&mut heightmap, cells, cells, 150, &legacy);
+            slope_histogram(&heightmap, size, &settings)
+        };
+        let legacy_distance: f32 = legacy_histogram(64)
+            .iter()
+            .zip(&legacy_histogram(128))
+            .map(|(a, b)| (a - b).abs())
+            .sum();
+        assert!(legacy_distance > distance);
+
+        let hard = GenerationSettings {
+            hardness: 0.8,
+            ..settings
+        };
+        let field = hardness_field(32, 32, &hard).unwrap();
+        assert!(field.iter().all(|&h| (0.0..=0.8).contains(&h)));
+        assert!(hardness_field(32, 32, &GenerationSettings::default()).is_none());
+    }
+
+    #[test]
+    fn default_settings_erode_generated_terrain() {
+        for (width, height) in [(128, 96), (256, 256)] {
+            let settings = |erosion_iterations| GenerationSettings {
+                seed: 3,
+                erosion_iterations,
+                ..GenerationSettings::default()
+            };
+            let before = sample_terrain(width, height, &settings(0)).heightmap;
+            let after = sample_terrain(width, height, &settings(20)).heightmap;
+            let changed = before.iter().zip(&after).filter(|(a, b)| a != b).count();
+            assert!(
+                changed * 20 > before.len(),
+                "{changed} of {} cells eroded at {width}x{height}",
+                before.len()
+            );
+        }
+    }
+}
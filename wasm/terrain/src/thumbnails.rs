@@ -0,0 +1,303 @@
+#[cfg(feature = "wasm")]
+use js_sys::{Array, Int32Array, Uint8Array};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::biome::Biome;
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::overview::{level, overview_layers, OverviewScratch, OVERVIEW_MAX_SIZE};
+use crate::render::{render_rgba, RenderOptions};
+use crate::style::{depth_shade, FALLBACK_COLOR};
+use crate::thermal::apply_thermal_erosion;
+use crate::{
+    apply_shores, build_flow_map, cell_temperature, classify_cell, enhanced_moisture, generate,
+    FieldSampler, GenerationSettings,
+};
+
+/// Warp and moisture vary slowly, so previews sample them every this many
+/// pixels and interpolate; elevation keeps every octave at full resolution.
+const LATTICE_STEP: usize = 4;
+
+/// Reusable buffers for the preview pipeline, sized for one thumbnail.
+pub(crate) struct ThumbnailScratch {
+    pub heightmap: Vec<f32>,
+    pub moisture: Vec<f32>,
+    pub temperature: Vec<f32>,
+    pub water: Vec<f32>,
+    pub biome: Vec<u8>,
+    pub rgba: Vec<u8>,
+}
+
+impl ThumbnailScratch {
+    pub(crate) fn new(size: usize) -> Self {
+        Self {
+            heightmap: vec![0.0; size],
+            moisture: vec![0.0; size],
+            temperature: vec![0.0; size],
+            water: vec![0.0; size],
+            biome: vec![0; size],
+            rgba: vec![0; size * 4],
+        }
+    }
+}
+
+/// Buffers for whichever pipeline a batch of thumbnails runs.
+enum Scratch {
+    Float(ThumbnailScratch),
+    /// Byte layers plus the image; see `overview`.
+    Overview(OverviewScratch, Vec<u8>),
+}
+
+/// Which optional stages a thumbnail runs.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct ThumbnailStages {
+    /// Trace flow for rivers and lakes instead of thresholding at sea level.
+    pub flow: bool,
+    /// Run the full pipeline, settlements and roads included, and render it
+    /// with `render_rgba`.
+    pub full: bool,
+    /// Run the stripped pipeline on byte layers; see `overview`.
+    pub overview: bool,
+}
+
+/// Square `size × size` RGBA previews, one `Uint8Array` per seed, colored
+/// with the built-in biome palette. Only elevation, a sea-level water cut,
+/// and biome classification run unless asked: `options.erosion_iterations`
+/// (default 0), `options.flow` to trace rivers and lakes, or `options.full`
+/// for the complete pipeline with roads. `options.overview` runs the
+/// stripped pipeline on one byte per cell and layer instead of floats, for
+/// many small previews at once; it needs `size` at most 256, cannot be
+/// combined with `full`, and comes out close to but not exactly like the
+/// float previews. Terrain options are read as in
+/// `generate_map_with_options`, with `sea_level` (0.42),
+/// `elevation_amplitude` (1), `warp_strength` (40), and `moisture_scale` (1).
+/// `options.cancel` may hold an `Int32Array`, typically over a
+/// `SharedArrayBuffer`; once its first element is nonzero the batch stops
+/// and the thumbnails finished so far are returned.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn generate_thumbnails(seeds: &[u32], size: u32, options: JsValue) -> Result<Array, JsValue> {
+    if size == 0 {
+        return Err(JsValue::from_str("size must be positive"));
+    }
+    let defaults = GenerationSettings::new(
+        0,
+        js::get_f32(&options, "sea_level", 0.42),
+        js::get_f32(&options, "elevation_amplitude", 1.0),
+        js::get_f32(&options, "warp_strength", 40.0),
+        js::get_u32(&options, "erosion_iterations", 0),
+        js::get_f32(&options, "moisture_scale", 1.0),
+    );
+    let settings = GenerationSettings::with_options(defaults, &options)?;
+    let stages = ThumbnailStages {
+        flow: js::get_bool(&options, "flow", false),
+        full: js::get_bool(&options, "full", false),
+        overview: js::get_bool(&options, "overview", false),
+    };
+    if stages.overview && stages.full {
+        return Err(JsValue::from_str(
+            "overview previews cannot run the full pipeline",
+        ));
+    }
+    if stages.overview && size > OVERVIEW_MAX_SIZE {
+        return Err(JsValue::from_str(&format!(
+            "overview previews are at most {OVERVIEW_MAX_SIZE} pixels square"
+        )));
+    }
+    let cancel = js::get(&options, "cancel").map(Int32Array::from);
+
+    let thumbnails = Array::new();
+    let cells = (size * size) as usize;
+    let mut scratch = if stages.overview {
+        Scratch::Overview(OverviewScratch::new(cells), vec![0; cells * 4])
+    } else {
+        Scratch::Float(ThumbnailScratch::new(cells))
+    };
+    for &seed in seeds {
+        if cancel.as_ref().is_some_and(|flag| flag.get_index(0) != 0) {
+            break;
+        }
+        let settings = GenerationSettings {
+            seed,
+            ..settings.clone()
+        };
+        let rgba = thumbnail(size, &settings, stages, &mut scratch);
+        thumbnails.push(&Uint8Array::from(rgba).into());
+    }
+    Ok(thumbnails)
+}
+
+/// Renders one thumbnail into the scratch image and returns it.
+fn thumbnail<'a>(
+    size: u32,
+    settings: &GenerationSettings,
+    stages: ThumbnailStages,
+    scratch: &'a mut Scratch,
+) -> &'a [u8] {
+    let scratch = match scratch {
+        Scratch::Float(scratch) => scratch,
+        Scratch::Overview(layers, rgba) => {
+            overview_layers(size, settings, stages.flow, layers);
+            paint(
+                rgba,
+                &layers.biome,
+                |index| level(layers.heightmap[index]),
+                settings.sea_level,
+            );
+            return rgba;
+        }
+    };
+    if stages.full {
+        let map = generate(size, size, settings, None);
+        scratch.rgba = render_rgba(&map, &RenderOptions::default());
+        return &scratch.rgba;
+    }
+
+    preview_layers(size, settings, stages.flow, scratch);
+    let ThumbnailScratch {
+        heightmap,
+        biome,
+        rgba,
+        ..
+    } = scratch;
+    paint(rgba, biome, |index| heightmap[index], settings.sea_level);
+    rgba
+}
+
+/// Colors `rgba` with the built-in biome palette.
+fn paint(rgba: &mut [u8], biome: &[u8], heightmap: impl Fn(usize) -> f32, sea_level: f32) {
+    for (index, pixel) in rgba.chunks_exact_mut(4).enumerate() {
+        let [r, g, b] = Biome::from_code(biome[index]).map_or(FALLBACK_COLOR, Biome::default_color);
+        let elevation = heightmap(index);
+        let shade = if elevation <= sea_level {
+            depth_shade(elevation, sea_level)
+        } else {
+            1.0
+        };
+        pixel[0] = (r as f32 * shade) as u8;
+        pixel[1] = (g as f32 * shade) as u8;
+        pixel[2] = (b as f32 * shade) as u8;
+        pixel[3] = 255;
+    }
+}
+
+/// Fills the layers of `scratch`, all but `rgba`, through the stripped
+/// preview pipeline: elevation, erosion, a sea-level water cut or traced
+/// `flow`, biomes, and shores.
+pub(crate) fn preview_layers(
+    size: u32,
+    settings: &GenerationSettings,
+    flow: bool,
+    scratch: &mut ThumbnailScratch,
+) {
+    let sea_level = settings.sea_level;
+    let ThumbnailScratch {
+        heightmap,
+        moisture,
+        temperature,
+        water,
+        biome,
+        ..
+    } = scratch;
+    sample_preview_fields(size as usize, settings, |index, elevation, moist, heat| {
+        heightmap[index] = elevation;
+        moisture[index] = moist;
+        temperature[index] = heat;
+    });
+    apply_thermal_erosion(heightmap, size, size, settings.erosion_iterations, settings);
+
+    let (flow, max_flow) = if flow {
+        let (flow, traced) = build_flow_map(heightmap, size, size, sea_level);
+        water.copy_from_slice(&traced);
+        let max_flow = flow.iter().copied().fold(0.0f32, f32::max);
+        (Some(flow), max_flow)
+    } else {
+        for (water, &elevation) in water.iter_mut().zip(heightmap.iter()) {
+            *water = if elevation <= sea_level { 1.0 } else { 0.0 };
+        }
+        (None, 0.0)
+    };
+
+    for index in 0..heightmap.len() {
+        let cell_flow = flow.as_ref().map_or(0.0, |flow| flow[index]);
+        let moist = enhanced_moisture(moisture[index], water[index], cell_flow, max_flow, settings);
+        biome[index] = classify_cell(
+            heightmap[index],
+            water[index],
+            temperature[index],
+            moist,
+            sea_level,
+            &settings.biome_table,
+        )
+        .code();
+    }
+    let side = size as usize;
+    apply_shores(
+        heightmap,
+        biome,
+        side,
+        side,
+        sea_level,
+        settings,
+        0..side * side,
+    );
+}
+
+/// `sample_fields` for a square preview with no land mask, taking warp and
+/// moisture from a coarse lattice. Hands each cell's elevation, moisture,
+/// and temperature to `store` rather than keeping layers of its own.
+pub(crate) fn sample_preview_fields(
+    side: usize,
+    settings: &GenerationSettings,
+    mut store: impl FnMut(usize, f32, f32, f32),
+) {
+    let sampler = FieldSampler::new(settings);
+    let to_noise = |pixel: f32| pixel / side as f32 * 2.0 - 1.0;
+    let lattice_side = side.div_ceil(LATTICE_STEP) + 1;
+    let lattice: Vec<[f32; 3]> = (0..lattice_side * lattice_side)
+        .map(|index| {
+            let nx = to_noise(((index % lattice_side) * LATTICE_STEP) as f32);
+            let ny = to_noise(((index / lattice_side) * LATTICE_STEP) as f32);
+            let (warped_x, warped_y) = sampler.warped(nx, ny);
+            [
+                warped_x - nx,
+                warped_y - ny,
+                sampler.moisture(warped_x, warped_y),
+            ]
+        })
+        .collect();
+
+    for y in 0..side {
+        let (ly, ty) = (
+            y / LATTICE_STEP,
+            (y % LATTICE_STEP) as f32 / LATTICE_STEP as f32,
+        );
+        for x in 0..side {
+            let (lx, tx) = (
+                x / LATTICE_STEP,
+                (x % LATTICE_STEP) as f32 / LATTICE_STEP as f32,
+            );
+            let corner = |dx: usize, dy: usize| lattice[(ly + dy) * lattice_side + lx + dx];
+            let (a, b, c, d) = (corner(0, 0), corner(1, 0), corner(0, 1), corner(1, 1));
+            let lerp = |channel: usize| {
+                let top = a[channel] + (b[channel] - a[channel]) * tx;
+                let bottom = c[channel] + (d[channel] - c[channel]) * tx;
+                top + (bottom - top) * ty
+            };
+            let (nx, ny) = (to_noise(x as f32), to_noise(y as f32));
+            let index = y * side + x;
+            let elevation = sampler.elevation(
+                nx + lerp(0),
+                ny + lerp(1),
+                FieldSampler::continentality(nx, ny),
+            );
+            store(
+                index,
+                elevation,
+                lerp(2),
+                cell_temperature(y as f32 / side as f32, elevation, settings.sea_level),
+            );
+        }
+    }
+}
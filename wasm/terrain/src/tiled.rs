@@ -0,0 +1,439 @@
+#[cfg(feature = "wasm")]
+use js_sys::Array;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::json::Json;
+use crate::{MapResult, REGION_SIZE};
+
+/// Neighbor offsets and their bit in an auto-tiling mask, clockwise from north.
+const MASK_NEIGHBORS: [(i32, i32, u8); 8] = [
+    (0, -1, 1),
+    (1, -1, 2),
+    (1, 0, 4),
+    (1, 1, 8),
+    (0, 1, 16),
+    (-1, 1, 32),
+    (-1, 0, 64),
+    (-1, -1, 128),
+];
+
+/// Number of distinct masks once corners without both adjacent edges are dropped.
+const BLOB_VARIANTS: usize = 47;
+const CARDINAL_VARIANTS: usize = 16;
+
+/// Tile ids chosen for an auto-tiled layer.
+///
+/// `Cardinal` tables are indexed by the N=1, E=2, S=4, W=8 edge mask. `Blob`
+/// tables are indexed by the position of the reduced 8-neighbor mask in the
+/// ascending list of the 47 canonical masks.
+enum AutoTiles {
+    Empty,
+    Single(u32),
+    Cardinal(Vec<u32>),
+    Blob(Vec<u32>),
+}
+
+impl AutoTiles {
+    #[cfg(feature = "wasm")]
+    fn from_js(options: &JsValue, key: &str) -> Result<Self, JsValue> {
+        let Some(value) = js::get(options, key) else {
+            return Ok(AutoTiles::Empty);
+        };
+        if let Some(id) = value.as_f64() {
+            return Ok(AutoTiles::Single(id as u32));
+        }
+        if !Array::is_array(&value) {
+            return Err(JsValue::from_str(&format!(
+                "{key} must be a tile id or an array of 16 or 47 tile ids"
+            )));
+        }
+        let ids: Vec<u32> = Array::from(&value)
+            .iter()
+            .map(|id| id.as_f64().unwrap_or(0.0) as u32)
+            .collect();
+        match ids.len() {
+            CARDINAL_VARIANTS => Ok(AutoTiles::Cardinal(ids)),
+            BLOB_VARIANTS => Ok(AutoTiles::Blob(ids)),
+            count => Err(JsValue::from_str(&format!(
+                "{key} must contain 16 or 47 tile ids, got {count}"
+            ))),
+        }
+    }
+
+    fn pick(&self, mask: u8, blob_table: &[u8; 256]) -> u32 {
+        match self {
+            AutoTiles::Empty => 0,
+            AutoTiles::Single(id) => *id,
+            AutoTiles::Cardinal(ids) => {
+                let mut edges = 0usize;
+                if mask & 1 != 0 {
+                    edges |= 1;
+                }
+                if mask & 4 != 0 {
+                    edges |= 2;
+                }
+                if mask & 16 != 0 {
+                    edges |= 4;
+                }
+                if mask & 64 != 0 {
+                    edges |= 8;
+                }
+                ids[edges]
+            }
+            AutoTiles::Blob(ids) => ids[blob_table[mask as usize] as usize],
+        }
+    }
+}
+
+struct TiledOptions {
+    biome_tiles: Vec<u32>,
+    coast_tiles: AutoTiles,
+    river_tiles: AutoTiles,
+    road_tiles: AutoTiles,
+    tilesets: Vec<(u32, String)>,
+    tile_width: u32,
+    tile_height: u32,
+    chunk_size: u32,
+    max_layer_size: u32,
+}
+
+impl TiledOptions {
+    #[cfg(feature = "wasm")]
+    fn from_js(options: &JsValue) -> Result<Self, JsValue> {
+        // Without a table, biome code n maps to the n-th tile of the first tileset.
+        let mut biome_tiles: Vec<u32> = (1..=256).collect();
+        if let Some(table) = js::get_array(options, "biome_tiles") {
+            for entry in table.iter() {
+                let pair = Array::from(&entry);
+                let code = pair.get(0).as_f64();
+                let id = pair.get(1).as_f64();
+                match (code, id) {
+                    (Some(code), Some(id)) if (0.0..256.0).contains(&code) => {
+                        biome_tiles[code as usize] = id as u32;
+                    }
+                    _ => {
+                        return Err(JsValue::from_str(
+                            "biome_tiles entries must be [biome_code, tile_id]",
+                        ))
+                    }
+                }
+            }
+        }
+
+        let mut tilesets = Vec::new();
+        if let Some(list) = js::get_array(options, "tilesets") {
+            for entry in list.iter() {
+                let firstgid = js::get_u32(&entry, "firstgid", 1);
+                let source = js::get_string(&entry, "source")
+                    .ok_or_else(|| JsValue::from_str("tilesets entries need a source"))?;
+                tilesets.push((firstgid, source));
+            }
+        }
+        if tilesets.is_empty() {
+            tilesets.push((1, "terrain.tsj".to_string()));
+        }
+
+        Ok(Self {
+            biome_tiles,
+            coast_tiles: AutoTiles::from_js(options, "coast_tiles")?,
+            river_tiles: AutoTiles::from_js(options, "river_tiles")?,
+            road_tiles: AutoTiles::from_js(options, "road_tiles")?,
+            tilesets,
+            tile_width: js::get_u32(options, "tile_width", 16).max(1),
+            tile_height: js::get_u32(options, "tile_height", 16).max(1),
+            chunk_size: js::get_u32(options, "chunk_size", 64).max(1),
+            max_layer_size: js::get_u32(options, "max_layer_size", 1024).max(1),
+        })
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Exports the map as a Tiled JSON map with ground, water, and road tile
+    /// layers plus settlement and point-of-interest object layers; settlement
+    /// objects carry their name, and POI objects their kind as the class.
+    /// Maps wider or taller than `max_layer_size` are written as an infinite
+    /// map with chunked layers.
+    pub fn export_tiled(&self, options: JsValue) -> Result<String, JsValue> {
+        let options = TiledOptions::from_js(&options)?;
+        Ok(export_tiled(self, &options).to_string())
+    }
+}
+
+fn export_tiled(map: &MapResult, options: &TiledOptions) -> Json {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let blob_table = blob_table();
+    let roads = map.road_mask();
+
+    let is_water = |index: usize| map.is_water_body(index);
+    let is_river = |index: usize| map.is_river(index);
+
+    let mut ground = vec![0u32; width * height];
+    let mut water = vec![0u32; width * height];
+    let mut road = vec![0u32; width * height];
+    for y in 0..height {
+        for x in 0..width {
+            let index = y * width + x;
+            ground[index] = options.biome_tiles[map.biome[index] as usize];
+
+            if is_river(index) {
+                let mask = neighbor_mask(width, height, x, y, |i| is_river(i) || is_water(i));
+                water[index] = options.river_tiles.pick(mask, &blob_table);
+            } else if !is_water(index) {
+                let mask = neighbor_mask(width, height, x, y, is_water);
+                if mask != 0 {
+                    water[index] = options.coast_tiles.pick(mask, &blob_table);
+                }
+            }
+
+            if roads[index] {
+                let mask = neighbor_mask(width, height, x, y, |i| roads[i]);
+                road[index] = options.road_tiles.pick(mask, &blob_table);
+            }
+        }
+    }
+
+    let chunked = map.width > options.max_layer_size || map.height > options.max_layer_size;
+    let layers = vec![
+        tile_layer(1, "ground", &ground, map, options, chunked),
+        tile_layer(2, "water", &water, map, options, chunked),
+        tile_layer(3, "roads", &road, map, options, chunked),
+        settlement_layer(4, map, options),
+        poi_layer(5, map, options),
+    ];
+
+    let tilesets = options
+        .tilesets
+        .iter()
+        .map(|(firstgid, source)| {
+            Json::object()
+                .with("firstgid", *firstgid)
+                .with("source", source.as_str())
+        })
+        .collect::<Vec<_>>();
+
+    Json::object()
+        .with("type", "map")
+        .with("version", "1.10")
+        .with("orientation", "orthogonal")
+        .with("renderorder", "right-down")
+        .with("infinite", chunked)
+        .with("width", map.width)
+        .with("height", map.height)
+        .with("tilewidth", options.tile_width)
+        .with("tileheight", options.tile_height)
+        .with("nextlayerid", 6u32)
+        .with("nextobjectid", map.settlements.len() + map.pois.len() + 1)
+        .with("layers", layers)
+        .with("tilesets", tilesets)
+}
+
+fn tile_layer(
+    id: u32,
+    name: &str,
+    data: &[u32],
+    map: &MapResult,
+    options: &TiledOptions,
+    chunked: bool,
+) -> Json {
+    let layer = Json::object()
+        .with("id", id)
+        .with("name", name)
+        .with("type", "tilelayer")
+        .with("x", 0u32)
+        .with("y", 0u32)
+        .with("width", map.width)
+        .with("height", map.height)
+        .with("opacity", 1u32)
+        .with("visible", true);
+
+    if !chunked {
+        return layer.with("data", data.to_vec());
+    }
+
+    let width = map.width as usize;
+    let chunk = options.chunk_size as usize;
+    let mut chunks = Vec::new();
+    for chunk_y in (0..map.height as usize).step_by(chunk) {
+        for chunk_x in (0..width).step_by(chunk) {
+            let chunk_width = chunk.min(width - chunk_x);
+            let chunk_height = chunk.min(map.height as usize - chunk_y);
+            let mut values = Vec::with_capacity(chunk_width * chunk_height);
+            for y in chunk_y..chunk_y + chunk_height {
+                let row = y * width + chunk_x;
+                values.extend_from_slice(&data[row..row + chunk_width]);
+            }
+            chunks.push(
+                Json::object()
+                    .with("x", chunk_x)
+                    .with("y", chunk_y)
+                    .with("width", chunk_width)
+                    .with("height", chunk_height)
+                    .with("data", values),
+            );
+        }
+    }
+    layer
+        .with("startx", 0u32)
+        .with("starty", 0u32)
+        .with("chunks", chunks)
+}
+
+fn settlement_layer(id: u32, map: &MapResult, options: &TiledOptions) -> Json {
+    let objects = map
+        .settlements
+        .iter()
+        .enumerate()
+        .map(|(i, settlement)| {
+            let properties = vec![
+                Json::object()
+                    .with("name", "settlement_id")
+                    .with("type", "int")
+                    .with("value", settlement.id),
+                Json::object()
+                    .with("name", "size")
+                    .with("type", "float")
+                    .with("value", settlement.size),
+            ];
+            point_object(
+                i + 1,
+                settlement.name.as_deref().unwrap_or(""),
+                "settlement",
+                (settlement.x, settlement.y),
+                properties,
+                map,
+                options,
+            )
+        })
+        .collect::<Vec<_>>();
+    object_group(id, "settlements", objects)
+}
+
+/// Points of interest, numbered on from the settlement objects.
+fn poi_layer(id: u32, map: &MapResult, options: &TiledOptions) -> Json {
+    let first = map.settlements.len() + 1;
+    let objects = map
+        .pois
+        .iter()
+        .enumerate()
+        .map(|(i, poi)| {
+            let mut properties = vec![
+                Json::object()
+                    .with("name", "poi_id")
+                    .with("type", "int")
+                    .with("value", poi.id),
+                Json::object()
+                    .with("name", "era")
+                    .with("type", "int")
+                    .with("value", poi.era),
+            ];
+            if let Some(reason) = poi.reason {
+                properties.push(
+                    Json::object()
+                        .with("name", "reason")
+                        .with("type", "string")
+                        .with("value", reason.key()),
+                );
+            }
+            point_object(
+                first + i,
+                "",
+                poi.kind.key(),
+                (poi.x, poi.y),
+                properties,
+                map,
+                options,
+            )
+        })
+        .collect::<Vec<_>>();
+    object_group(id, "pois", objects)
+}
+
+/// A Tiled point object at a world position.
+fn point_object(
+    id: usize,
+    name: &str,
+    class: &str,
+    (x, y): (f32, f32),
+    properties: Vec<Json>,
+    map: &MapResult,
+    options: &TiledOptions,
+) -> Json {
+    let pixels_per_world_x = map.width as f32 * options.tile_width as f32 / REGION_SIZE;
+    let pixels_per_world_y = map.height as f32 * options.tile_height as f32 / REGION_SIZE;
+    Json::object()
+        .with("id", id)
+        .with("name", name)
+        .with("class", class)
+        .with("point", true)
+        .with("x", x * pixels_per_world_x)
+        .with("y", y * pixels_per_world_y)
+        .with("width", 0u32)
+        .with("height", 0u32)
+        .with("rotation", 0u32)
+        .with("visible", true)
+        .with("properties", properties)
+}
+
+fn object_group(id: u32, name: &str, objects: Vec<Json>) -> Json {
+    Json::object()
+        .with("id", id)
+        .with("name", name)
+        .with("type", "objectgroup")
+        .with("draworder", "topdown")
+        .with("x", 0u32)
+        .with("y", 0u32)
+        .with("opacity", 1u32)
+        .with("visible", true)
+        .with("objects", objects)
+}
+
+fn neighbor_mask(
+    width: usize,
+    height: usize,
+    x: usize,
+    y: usize,
+    matches: impl Fn(usize) -> bool,
+) -> u8 {
+    let mut mask = 0u8;
+    for (dx, dy, bit) in MASK_NEIGHBORS {
+        let nx = x as i32 + dx;
+        let ny = y as i32 + dy;
+        if nx < 0 || ny < 0 || nx >= width as i32 || ny >= height as i32 {
+            continue;
+        }
+        if matches(ny as usize * width + nx as usize) {
+            mask |= bit;
+        }
+    }
+    mask
+}
+
+/// Drops corner bits whose two adjacent edges are not both set.
+fn reduce_mask(mask: u8) -> u8 {
+    let mut reduced = mask & (1 | 4 | 16 | 64);
+    for (corner, a, b) in [(2, 1, 4), (8, 4, 16), (32, 16, 64), (128, 64, 1)] {
+        if mask & corner != 0 && mask & a != 0 && mask & b != 0 {
+            reduced |= corner;
+        }
+    }
+    reduced
+}
+
+/// Maps every raw 8-neighbor mask to its index among the 47 canonical masks.
+fn blob_table() -> [u8; 256] {
+    let mut canonical: Vec<u8> = (0..=255u8).map(reduce_mask).collect();
+    canonical.sort_unstable();
+    canonical.dedup();
+    let mut table = [0u8; 256];
+    for (mask, slot) in table.iter_mut().enumerate() {
+        let reduced = reduce_mask(mask as u8);
+        *slot = canonical.binary_search(&reduced).unwrap_or(0) as u8;
+    }
+    table
+}
@@ -0,0 +1,370 @@
+use std::collections::BinaryHeap;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::biome::Biome;
+use crate::economy::Economy;
+use crate::geometry::distance;
+use crate::json::Json;
+use crate::movement::Profile;
+use crate::pathfinding::{relax_distances, Frontier};
+use crate::render::raster_line;
+use crate::MapResult;
+
+/// Cost per world unit of shipping by sea, relative to by road.
+const SEA_RATE: f32 = 0.4;
+/// Fixed cost of loading and unloading a ship, in road world units.
+const PORT_FEE: f32 = 96.0;
+/// Exponent on route cost in the gravity model.
+const DISTANCE_DECAY: f32 = 2.0;
+/// Goods that settlements produce and consume.
+const GOODS: usize = 4;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Link {
+    Road,
+    Sea,
+}
+
+impl Link {
+    fn key(self) -> &'static str {
+        match self {
+            Link::Road => "road",
+            Link::Sea => "sea",
+        }
+    }
+}
+
+/// One leg of the trade network between two settlements, by position in
+/// `settlements`.
+#[derive(Clone, Debug)]
+pub(crate) struct TradeEdge {
+    pub a: usize,
+    pub b: usize,
+    pub link: Link,
+    pub cost: f32,
+    /// Goods carried in either direction.
+    pub volume: f32,
+}
+
+pub(crate) struct TradeNetwork {
+    pub edges: Vec<TradeEdge>,
+    pub ports: Vec<bool>,
+    pub exports: Vec<f32>,
+    pub imports: Vec<f32>,
+}
+
+impl TradeNetwork {
+    pub(crate) fn balance(&self, position: usize) -> f32 {
+        self.exports[position] - self.imports[position]
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Legs of the trade network as `{ a, b, kind, cost, volume, from_x,
+    /// from_y, to_x, to_y }`, with settlement ids `a` and `b`. `kind` is
+    /// `"road"` for roads that stay off the sea and `"sea"` for shipping
+    /// lanes between ports, settlements within 48 world units of open ocean
+    /// (see `harbors()`). Road legs run between the settlements and sea legs
+    /// between their docks, from `a`'s end to `b`'s. Each settlement's surplus of
+    /// farming, fishing, mining, and timber goes to the settlements short of
+    /// them in proportion to `size_a * size_b / cost^2`, along the cheapest
+    /// route; `volume` sums every shipment crossing the leg.
+    pub fn trade_routes(&self) -> JsValue {
+        let network = self.trade_network();
+        let docks = self.docks();
+        let end = |position: usize, link: Link| match (link, docks[position]) {
+            (Link::Sea, Some(dock)) => self.cell_to_world(dock),
+            _ => {
+                let settlement = &self.settlements[position];
+                (settlement.x, settlement.y)
+            }
+        };
+        let records = network
+            .edges
+            .iter()
+            .map(|edge| {
+                let (from_x, from_y) = end(edge.a, edge.link);
+                let (to_x, to_y) = end(edge.b, edge.link);
+                Json::object()
+                    .with("a", self.settlements[edge.a].id)
+                    .with("b", self.settlements[edge.b].id)
+                    .with("kind", edge.link.key())
+                    .with("cost", edge.cost)
+                    .with("volume", edge.volume)
+                    .with("from_x", from_x)
+                    .with("from_y", from_y)
+                    .with("to_x", to_x)
+                    .with("to_y", to_y)
+            })
+            .collect::<Vec<_>>();
+        Json::from(records).to_js()
+    }
+}
+
+impl MapResult {
+    /// Cached until the terrain, settlements, or roads change.
+    pub(crate) fn trade_network(&self) -> &TradeNetwork {
+        self.cache.trade.get_or_init(|| trade_network(self))
+    }
+}
+
+pub(crate) fn trade_network(map: &MapResult) -> TradeNetwork {
+    let width = map.width as usize;
+    let count = map.settlements.len();
+    let ocean = |index: usize| map.biome[index] == Biome::Ocean.code();
+    let cell_of = |position: usize| {
+        let settlement = &map.settlements[position];
+        map.nearest_cell(settlement.x, settlement.y)
+    };
+    let mut edges = Vec::new();
+
+    for &(a, b) in &map.road_graph {
+        let position = |id: u32| map.settlements.iter().position(|s| s.id == id);
+        let (Some(a), Some(b)) = (position(a), position(b)) else {
+            continue;
+        };
+        let wet = raster_line(cell_of(a), cell_of(b))
+            .into_iter()
+            .any(|(x, y)| ocean(y * width + x));
+        if wet {
+            continue;
+        }
+        let (sa, sb) = (&map.settlements[a], &map.settlements[b]);
+        edges.push(TradeEdge {
+            a,
+            b,
+            link: Link::Road,
+            cost: distance((sa.x, sa.y), (sb.x, sb.y)),
+            volume: 0.0,
+        });
+    }
+
+    // Each port loads at its dock and sails with boat costs.
+    let harbors = map.docks();
+    let boat = map.movement_costs(&Profile::preset("boat").expect("boat is a preset"));
+    for a in 0..count {
+        let Some(from) = harbors[a] else {
+            continue;
+        };
+        let mut distance = vec![f32::INFINITY; boat.len()];
+        let mut owner = vec![0u32; boat.len()];
+        relax_distances(
+            &boat,
+            width,
+            map.height as usize,
+            &[from],
+            0,
+            &mut distance,
+            &mut owner,
+        );
+        for (b, &to) in harbors.iter().enumerate().skip(a + 1) {
+            let Some(to) = to else {
+                continue;
+            };
+            if distance[to].is_finite() {
+                edges.push(TradeEdge {
+                    a,
+                    b,
+                    link: Link::Sea,
+                    cost: distance[to] * SEA_RATE + PORT_FEE,
+                    volume: 0.0,
+                });
+            }
+        }
+    }
+
+    let supply: Vec<[f32; GOODS]> = surpluses(
+        &map.settlements
+            .iter()
+            .map(|settlement| settlement.size)
+            .collect::<Vec<_>>(),
+        map.economies(),
+    );
+    let (exports, imports) = assign_flows(count, &mut edges, &supply);
+    TradeNetwork {
+        edges,
+        ports: harbors.iter().map(Option::is_some).collect(),
+        exports,
+        imports,
+    }
+}
+
+/// Size-weighted excess of each good's share over the mean share: positive
+/// for exporters, negative for importers. Trade itself is a service, not a
+/// good.
+fn surpluses(sizes: &[f32], economies: &[Economy]) -> Vec<[f32; GOODS]> {
+    let goods = |economy: &Economy| {
+        [
+            economy.farming,
+            economy.fishing,
+            economy.mining,
+            economy.timber,
+        ]
+    };
+    let mut mean = [0.0f32; GOODS];
+    for economy in economies {
+        for (total, share) in mean.iter_mut().zip(goods(economy)) {
+            *total += share / economies.len() as f32;
+        }
+    }
+    sizes
+        .iter()
+        .zip(economies)
+        .map(|(&size, economy)| {
+            let shares = goods(economy);
+            std::array::from_fn(|good| size * (shares[good] - mean[good]))
+        })
+        .collect()
+}
+
+/// Splits each exporter's surplus among importers by the gravity model and
+/// adds every shipment to the legs of its cheapest route. Returns total
+/// exports and imports per settlement.
+pub(crate) fn assign_flows(
+    count: usize,
+    edges: &mut [TradeEdge],
+    supply: &[[f32; GOODS]],
+) -> (Vec<f32>, Vec<f32>) {
+    let mut adjacency: Vec<Vec<usize>> = vec![Vec::new(); count];
+    for (slot, edge) in edges.iter().enumerate() {
+        adjacency[edge.a].push(slot);
+        adjacency[edge.b].push(slot);
+    }
+    let mut exports = vec![0.0f32; count];
+    let mut imports = vec![0.0f32; count];
+    for from in 0..count {
+        if supply[from].iter().all(|&surplus| surplus <= 0.0) {
+            continue;
+        }
+        let (cost, via) = cheapest_routes(count, edges, &adjacency, from);
+        for (good, &surplus) in supply[from].iter().enumerate() {
+            if surplus <= 0.0 {
+                continue;
+            }
+            let pulls: Vec<(usize, f32)> = (0..count)
+                .filter(|&to| to != from && supply[to][good] < 0.0 && cost[to].is_finite())
+                .map(|to| {
+                    let cost = cost[to].max(1.0);
+                    (to, -supply[to][good] / cost.powf(DISTANCE_DECAY))
+                })
+                .collect();
+            let total: f32 = pulls.iter().map(|&(_, pull)| pull).sum();
+            if total <= 0.0 {
+                continue;
+            }
+            for (to, pull) in pulls {
+                let shipment = surplus * pull / total;
+                exports[from] += shipment;
+                imports[to] += shipment;
+                let mut at = to;
+                while let Some(slot) = via[at] {
+                    let edge = &mut edges[slot];
+                    edge.volume += shipment;
+                    at = if edge.a == at { edge.b } else { edge.a };
+                }
+            }
+        }
+    }
+    (exports, imports)
+}
+
+/// Dijkstra over the settlement graph: route cost to each settlement and the
+/// edge it is reached by.
+fn cheapest_routes(
+    count: usize,
+    edges: &[TradeEdge],
+    adjacency: &[Vec<usize>],
+    from: usize,
+) -> (Vec<f32>, Vec<Option<usize>>) {
+    let mut cost = vec![f32::INFINITY; count];
+    let mut via: Vec<Option<usize>> = vec![None; count];
+    let mut heap = BinaryHeap::new();
+    cost[from] = 0.0;
+    heap.push(Frontier {
+        priority: 0.0,
+        index: from,
+    });
+    while let Some(Frontier { priority, index }) = heap.pop() {
+        if priority > cost[index] {
+            continue;
+        }
+        for &slot in &adjacency[index] {
+            let edge = &edges[slot];
+            let next = if edge.a == index { edge.b } else { edge.a };
+            let candidate = priority + edge.cost;
+            if candidate < cost[next] {
+                cost[next] = candidate;
+                via[next] = Some(slot);
+                heap.push(Frontier {
+                    priority: candidate,
+                    index: next,
+                });
+            }
+        }
+    }
+    (cost, via)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{assign_flows, trade_network, Link, TradeEdge};
+    use crate::generate_map;
+
+    fn leg(a: usize, b: usize, link: Link, cost: f32) -> TradeEdge {
+        TradeEdge {
+            a,
+            b,
+            link,
+            cost,
+            volume: 0.0,
+        }
+    }
+
+    #[test]
+    fn landlocked_trade_goes_through_ports() {
+        // 0 is an inland mine, 1 and 2 are ports on two islands, 3 is an
+        // inland town short of ore, and 4 is a nearby town with no needs.
+        let mut edges = vec![
+            leg(0, 1, Link::Road, 100.0),
+            leg(1, 2, Link::Sea, 300.0),
+            leg(2, 3, Link::Road, 100.0),
+            leg(0, 4, Link::Road, 50.0),
+        ];
+        let mut supply = [[0.0f32; 4]; 5];
+        supply[0][2] = 2.0;
+        supply[3][2] = -2.0;
+        let (exports, imports) = assign_flows(5, &mut edges, &supply);
+        assert_eq!(exports[0], 2.0);
+        assert_eq!(imports[3], 2.0);
+        assert_eq!(imports[1] + imports[2] + imports[4], 0.0);
+        for (slot, expected) in [(0, 2.0), (1, 2.0), (2, 2.0), (3, 0.0)] {
+            assert_eq!(edges[slot].volume, expected);
+        }
+    }
+
+    #[test]
+    fn sea_lanes_join_ports_and_trade_balances() {
+        let map = generate_map(128, 128, 9, 0.42, 1.0, 40.0, 2, 1.0);
+        let network = trade_network(&map);
+        let again = trade_network(&map);
+        assert_eq!(network.exports, again.exports);
+        assert!(network
+            .edges
+            .iter()
+            .zip(&again.edges)
+            .all(|(a, b)| (a.a, a.b, a.volume) == (b.a, b.b, b.volume)));
+        for edge in &network.edges {
+            if edge.link == Link::Sea {
+                assert!(network.ports[edge.a] && network.ports[edge.b]);
+            }
+        }
+        let exported: f32 = network.exports.iter().sum();
+        let imported: f32 = network.imports.iter().sum();
+        assert!(exported > 0.0);
+        assert!((exported - imported).abs() < 1e-3 * exported);
+    }
+}
@@ -0,0 +1,262 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::changes::ChangeLog;
+use crate::editing::CellRect;
+use crate::{MapResult, REGION_SIZE};
+
+/// Grid symmetries. Rotations are clockwise as drawn, with y pointing down.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Transform {
+    FlipHorizontal,
+    FlipVertical,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+}
+
+impl Transform {
+    fn swaps_axes(self) -> bool {
+        matches!(self, Transform::Rotate90 | Transform::Rotate270)
+    }
+
+    /// Where point `(x, y)` of a `width` by `height` grid lands, in cell
+    /// units. Works on fractional positions so vector data shares the matrix.
+    fn forward(self, x: f64, y: f64, width: f64, height: f64) -> (f64, f64) {
+        match self {
+            Transform::FlipHorizontal => (width - 1.0 - x, y),
+            Transform::FlipVertical => (x, height - 1.0 - y),
+            Transform::Rotate90 => (height - 1.0 - y, x),
+            Transform::Rotate180 => (width - 1.0 - x, height - 1.0 - y),
+            Transform::Rotate270 => (y, width - 1.0 - x),
+        }
+    }
+
+    /// Source cell of destination cell `(x, y)` in the transformed grid of
+    /// size `width` by `height`.
+    fn source(self, x: usize, y: usize, width: usize, height: usize) -> (usize, usize) {
+        match self {
+            Transform::FlipHorizontal => (width - 1 - x, y),
+            Transform::FlipVertical => (x, height - 1 - y),
+            Transform::Rotate90 => (y, width - 1 - x),
+            Transform::Rotate180 => (width - 1 - x, height - 1 - y),
+            Transform::Rotate270 => (height - 1 - y, x),
+        }
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Applies `"flip_horizontal"`, `"flip_vertical"`, `"rotate_90"`,
+    /// `"rotate_180"`, or `"rotate_270"` to every layer and to settlement,
+    /// POI, fault, and forced placement positions. Quarter turns require a
+    /// square map. Settlement ids, and so the road graph, are unchanged.
+    pub fn transform(&mut self, op: &str) -> Result<(), JsValue> {
+        let transform = match op {
+            "flip_horizontal" => Transform::FlipHorizontal,
+            "flip_vertical" => Transform::FlipVertical,
+            "rotate_90" => Transform::Rotate90,
+            "rotate_180" => Transform::Rotate180,
+            "rotate_270" => Transform::Rotate270,
+            other => return Err(JsValue::from_str(&format!("unknown transform: {other}"))),
+        };
+        transform_map(self, transform).map_err(|message| JsValue::from_str(&message))
+    }
+}
+
+pub(crate) fn transform_map(map: &mut MapResult, transform: Transform) -> Result<(), String> {
+    if transform.swaps_axes() && map.width != map.height {
+        return Err("quarter-turn rotations need a square map".to_string());
+    }
+    let width = map.width as usize;
+    let height = map.height as usize;
+
+    let sources: Vec<usize> = (0..width * height)
+        .map(|index| {
+            let (x, y) = transform.source(index % width, index / width, width, height);
+            y * width + x
+        })
+        .collect();
+    fn permute<T: Copy>(values: &mut Vec<T>, sources: &[usize]) {
+        *values = sources.iter().map(|&source| values[source]).collect();
+    }
+    permute(&mut map.heightmap, &sources);
+    permute(&mut map.flow, &sources);
+    permute(&mut map.moisture, &sources);
+    permute(&mut map.temperature, &sources);
+    permute(&mut map.biome, &sources);
+    permute(&mut map.water, &sources);
+    permute(&mut map.base_moisture, &sources);
+    permute(&mut map.exploration.mask, &sources);
+    if let Some(ownership) = &mut map.ownership {
+        ownership.remap(&sources);
+    }
+
+    let (w, h) = (map.width as f64, map.height as f64);
+    let region = REGION_SIZE as f64;
+    let forward_world = |x: f32, y: f32| {
+        let (x, y) = transform.forward(x as f64 / region * w, y as f64 / region * h, w, h);
+        (
+            (x / w * region).clamp(0.0, region) as f32,
+            (y / h * region).clamp(0.0, region) as f32,
+        )
+    };
+    for settlement in &mut map.settlements {
+        (settlement.x, settlement.y) = forward_world(settlement.x, settlement.y);
+    }
+    for poi in &mut map.pois {
+        (poi.x, poi.y) = forward_world(poi.x, poi.y);
+        if let Some(coverage) = &mut poi.coverage {
+            for target in &mut coverage.targets {
+                *target = forward_world(target.0, target.1);
+            }
+        }
+    }
+    for snap in &mut map.placements {
+        snap.from = forward_world(snap.from.0, snap.from.1);
+        snap.to = snap.to.map(|(x, y)| forward_world(x, y));
+    }
+    for fault in &mut map.faults {
+        for point in &mut fault.points {
+            *point = forward_world(point.0, point.1);
+        }
+    }
+    for trail in &mut map.history.trails {
+        trail.from = forward_world(trail.from.0, trail.from.1);
+        trail.to = forward_world(trail.to.0, trail.to.1);
+    }
+
+    if let Some(dirty) = map.dirty {
+        let (ax, ay) = transform.forward(dirty.x0 as f64, dirty.y0 as f64, w, h);
+        let (bx, by) = transform.forward(dirty.x1 as f64, dirty.y1 as f64, w, h);
+        map.dirty = Some(CellRect {
+            x0: ax.min(bx) as usize,
+            y0: ay.min(by) as usize,
+            x1: ax.max(bx) as usize,
+            y1: ay.max(by) as usize,
+        });
+    }
+
+    map.changes = ChangeLog::full(map.width, map.height);
+    map.invalidate_settlements();
+    map.invalidate_terrain();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{transform_map, Transform};
+    use crate::scenario::Snap;
+    use crate::{generate, generate_map, GenerationSettings, MapResult, REGION_SIZE};
+
+    fn layers(map: &MapResult) -> Vec<Vec<u8>> {
+        let bytes = |values: &[f32]| values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        vec![
+            bytes(&map.heightmap),
+            bytes(&map.flow),
+            bytes(&map.moisture),
+            bytes(&map.temperature),
+            map.biome.clone(),
+            bytes(&map.water),
+            bytes(&map.base_moisture),
+        ]
+    }
+
+    fn assert_round_trip(map: &mut MapResult, steps: &[Transform]) {
+        let layers_before = layers(map);
+        let settlements_before = map.settlements.clone();
+        let faults_before: Vec<Vec<(f32, f32)>> = map
+            .faults
+            .iter()
+            .map(|fault| fault.points.clone())
+            .collect();
+        let roads_before = map.road_graph.clone();
+        for &step in steps {
+            transform_map(map, step).unwrap();
+        }
+        assert!(layers(map) == layers_before);
+        assert_eq!(map.road_graph, roads_before);
+        for (after, before) in map.settlements.iter().zip(&settlements_before) {
+            assert_eq!(after.id, before.id);
+            assert!((after.x - before.x).abs() < 1e-3);
+            assert!((after.y - before.y).abs() < 1e-3);
+        }
+        // World positions past the last cell's origin clamp onto it, so
+        // only fault points up to there come back exactly.
+        let last = (
+            REGION_SIZE * (map.width - 1) as f32 / map.width as f32,
+            REGION_SIZE * (map.height - 1) as f32 / map.height as f32,
+        );
+        for (after, before) in map.faults.iter().zip(&faults_before) {
+            for (a, b) in after.points.iter().zip(before) {
+                if b.0 <= last.0 && b.1 <= last.1 {
+                    assert!((a.0 - b.0).abs() < 1e-2 && (a.1 - b.1).abs() < 1e-2);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn transforms_round_trip() {
+        let faulted = GenerationSettings {
+            faults: true,
+            ..GenerationSettings::new(4, 0.42, 1.0, 40.0, 2, 1.0)
+        };
+        let mut map = generate(48, 32, &faulted, None);
+        assert!(!map.faults.is_empty());
+        let before = map.faults[0].points.clone();
+        transform_map(&mut map, Transform::FlipHorizontal).unwrap();
+        // Flipped about the centre of the outermost cells.
+        for (a, b) in map.faults[0].points.iter().zip(&before) {
+            let mirrored = (REGION_SIZE * 47.0 / 48.0 - b.0).max(0.0);
+            assert!((a.0 - mirrored).abs() < 1e-2 && (a.1 - b.1).abs() < 1e-3);
+        }
+        transform_map(&mut map, Transform::FlipHorizontal).unwrap();
+        assert_round_trip(&mut map, &[Transform::FlipHorizontal; 2]);
+        assert_round_trip(&mut map, &[Transform::FlipVertical; 2]);
+        assert_round_trip(&mut map, &[Transform::Rotate180; 2]);
+        assert!(transform_map(&mut map, Transform::Rotate90).is_err());
+
+        let mut square = generate_map(40, 40, 9, 0.42, 1.0, 40.0, 2, 1.0);
+        assert_round_trip(&mut square, &[Transform::Rotate90; 4]);
+        assert_round_trip(&mut square, &[Transform::Rotate90, Transform::Rotate270]);
+        assert_round_trip(
+            &mut square,
+            &[
+                Transform::FlipHorizontal,
+                Transform::FlipVertical,
+                Transform::Rotate180,
+            ],
+        );
+    }
+
+    #[test]
+    fn settlements_follow_their_cells() {
+        let mut map = generate_map(40, 40, 9, 0.42, 1.0, 40.0, 2, 1.0);
+        let cells: Vec<u8> = map
+            .settlements
+            .iter()
+            .map(|settlement| {
+                let (x, y) = map.nearest_cell(settlement.x, settlement.y);
+                map.biome[y * 40 + x]
+            })
+            .collect();
+        let first = (map.settlements[0].x, map.settlements[0].y);
+        map.placements = vec![Snap {
+            index: 0,
+            from: (first.0 + 16.0, first.1),
+            to: Some(first),
+        }];
+        transform_map(&mut map, Transform::Rotate90).unwrap();
+        for (settlement, biome) in map.settlements.iter().zip(cells) {
+            let (x, y) = map.nearest_cell(settlement.x, settlement.y);
+            assert_eq!(map.biome[y * 40 + x], biome);
+        }
+        // Forced placements turn with the settlements they placed.
+        let snap = &map.placements[0];
+        assert_eq!(snap.to, Some((map.settlements[0].x, map.settlements[0].y)));
+        assert!((snap.from.0 - snap.to.unwrap().0).abs() < 1e-3);
+        assert!((snap.from.1 - snap.to.unwrap().1 - 16.0).abs() < 1e-3);
+    }
+}
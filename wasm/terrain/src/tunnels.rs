@@ -0,0 +1,479 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::ferries::{narrowest_crossing, reachable, Crossing};
+use crate::geometry::{distance, polyline_length, segment_distance};
+#[cfg(feature = "wasm")]
+use crate::js;
+#[cfg(feature = "wasm")]
+use crate::json::Json;
+use crate::movement::{build_cost_field, Profile};
+use crate::pathfinding::{astar, snap_to_passable};
+use crate::render::raster_line;
+use crate::{MapResult, REGION_SIZE};
+
+/// Cells an endpoint may move to reach passable ground.
+const SNAP_RADIUS: u32 = 4;
+/// A* expansions per road.
+const MAX_NODES: u32 = 250_000;
+
+pub(crate) struct RouteOptions {
+    /// Roads between two settlements at least this size are highways.
+    pub min_size: f32,
+    /// Highways whose surface route costs more than this multiple of their
+    /// straight length may tunnel.
+    pub detour_factor: f32,
+    /// Cells at or above this elevation may be tunneled through.
+    pub elevation: f32,
+    /// Cost multiplier of tunneled cells, in place of their surface cost.
+    pub tunnel_cost: f32,
+    /// Longest tunnel allowed, portal to portal, in world units.
+    pub max_length: f32,
+    /// Longest water crossing bridged, in world units.
+    pub bridge_length: f32,
+    /// Longest water crossing served by ferry, in world units.
+    pub ferry_length: f32,
+    /// Cost per world unit on a bridge.
+    pub bridge_cost: f32,
+    /// Cost per world unit on a ferry.
+    pub ferry_cost: f32,
+    /// Flat cost of boarding a ferry, in the units of a world unit of travel.
+    pub ferry_wait: f32,
+    /// Distance from the straight line between settlements within which
+    /// shores are searched for a crossing, in world units.
+    pub corridor: f32,
+}
+
+impl Default for RouteOptions {
+    fn default() -> Self {
+        Self {
+            min_size: 3.0,
+            detour_factor: 1.6,
+            elevation: 0.7,
+            tunnel_cost: 6.0,
+            max_length: 256.0,
+            bridge_length: 64.0,
+            ferry_length: 384.0,
+            bridge_cost: 2.0,
+            ferry_cost: 3.0,
+            ferry_wait: 96.0,
+            corridor: 256.0,
+        }
+    }
+}
+
+impl RouteOptions {
+    #[cfg(feature = "wasm")]
+    fn from_js(options: &JsValue) -> Self {
+        let defaults = Self::default();
+        Self {
+            min_size: js::get_f32(options, "min_size", defaults.min_size),
+            detour_factor: js::get_f32(options, "detour_factor", defaults.detour_factor),
+            elevation: js::get_f32(options, "elevation", defaults.elevation),
+            tunnel_cost: js::get_f32(options, "tunnel_cost", defaults.tunnel_cost).max(0.0),
+            max_length: js::get_f32(options, "max_length", defaults.max_length),
+            bridge_length: js::get_f32(options, "bridge_length", defaults.bridge_length),
+            ferry_length: js::get_f32(options, "ferry_length", defaults.ferry_length),
+            bridge_cost: js::get_f32(options, "bridge_cost", defaults.bridge_cost).max(0.0),
+            ferry_cost: js::get_f32(options, "ferry_cost", defaults.ferry_cost).max(0.0),
+            ferry_wait: js::get_f32(options, "ferry_wait", defaults.ferry_wait).max(0.0),
+            corridor: js::get_f32(options, "corridor", defaults.corridor),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum SegmentKind {
+    Tunnel,
+    Bridge,
+    Ferry,
+}
+
+impl SegmentKind {
+    #[cfg(feature = "wasm")]
+    fn key(self) -> &'static str {
+        match self {
+            SegmentKind::Tunnel => "tunnel",
+            SegmentKind::Bridge => "bridge",
+            SegmentKind::Ferry => "ferry",
+        }
+    }
+}
+
+/// Inclusive `cells` index range of a tunnel, portal to portal, or of a
+/// water crossing, shore to shore.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct Segment {
+    pub start: usize,
+    pub end: usize,
+    pub kind: SegmentKind,
+}
+
+/// A road traced over the terrain, cell by cell.
+pub(crate) struct RoadRoute {
+    pub road: (u32, u32),
+    pub highway: bool,
+    pub cells: Vec<usize>,
+    pub segments: Vec<Segment>,
+    /// Travel cost with cart costs, crossings priced by `RouteOptions`.
+    pub cost: f32,
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Every road traced over the terrain with cart costs, as `{ a, b,
+    /// highway, cost, points, segments }` with `points` as interleaved world
+    /// `x, y` per cell. Highways join two settlements of at least `min_size`
+    /// (default 3); when their surface route costs more than `detour_factor`
+    /// (default 1.6) times their straight length, they may tunnel through
+    /// cells at or above `elevation` (default 0.7) at `tunnel_cost` (default
+    /// 6) per world unit, if each tunnel stays within `max_length` (default
+    /// 256 world units) and both portals are on dry land. Any road that has
+    /// no overland route, or one over the detour limit, may instead cross
+    /// open water between the closest opposing shores within `corridor`
+    /// (default 256) world units of its straight line: by bridge up to
+    /// `bridge_length` (default 64) at `bridge_cost` (default 2) per world
+    /// unit, or by ferry up to `ferry_length` (default 384) at `ferry_cost`
+    /// (default 3) per world unit plus `ferry_wait` (default 96). `segments`
+    /// lists tunnels and crossings as `{ start, end, type }` with inclusive
+    /// point indices and `type` `"tunnel"`, `"bridge"`, or `"ferry"`.
+    /// Roads with no route are left out.
+    pub fn road_routes(&self, options: JsValue) -> JsValue {
+        let options = RouteOptions::from_js(&options);
+        let records = road_routes(self, &options)
+            .iter()
+            .map(|route| {
+                let points = route
+                    .cells
+                    .iter()
+                    .flat_map(|&index| {
+                        let (x, y) = self.cell_to_world(index);
+                        [Json::from(x), Json::from(y)]
+                    })
+                    .collect::<Vec<_>>();
+                let segments = route
+                    .segments
+                    .iter()
+                    .map(|segment| {
+                        Json::object()
+                            .with("start", segment.start)
+                            .with("end", segment.end)
+                            .with("type", segment.kind.key())
+                    })
+                    .collect::<Vec<_>>();
+                Json::object()
+                    .with("a", route.road.0)
+                    .with("b", route.road.1)
+                    .with("highway", route.highway)
+                    .with("cost", route.cost)
+                    .with("points", points)
+                    .with("segments", segments)
+            })
+            .collect::<Vec<_>>();
+        Json::from(records).to_js()
+    }
+}
+
+pub(crate) fn road_routes(map: &MapResult, options: &RouteOptions) -> Vec<RoadRoute> {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    // Existing roads are straight placeholders, so they earn no discount.
+    let profile = Profile {
+        roads: 1.0,
+        ..Profile::preset("cart").expect("cart is a preset")
+    };
+    let surface = build_cost_field(map, &profile);
+    let tunneling: Vec<f32> = surface
+        .iter()
+        .enumerate()
+        .map(|(index, &cost)| {
+            if map.heightmap[index] >= options.elevation && !map.is_water_body(index) {
+                options.tunnel_cost
+            } else {
+                cost
+            }
+        })
+        .collect();
+
+    let mut routes = Vec::new();
+    for &(a, b) in &map.road_graph {
+        let (Some(start), Some(end)) = (map.settlement(a), map.settlement(b)) else {
+            continue;
+        };
+        let highway = start.size >= options.min_size && end.size >= options.min_size;
+        let straight = distance((start.x, start.y), (end.x, end.y));
+        let snap = |costs: &[f32], x: f32, y: f32| {
+            let (x, y) = map.nearest_cell(x, y);
+            snap_to_passable(costs, width, height, x, y, SNAP_RADIUS)
+        };
+        let trace = |costs: &[f32]| {
+            let from = snap(costs, start.x, start.y)?;
+            let to = snap(costs, end.x, end.y)?;
+            astar(costs, width, height, from, to, MAX_NODES)
+        };
+
+        let mut route = trace(&surface).map(|cells| {
+            let cost = path_cost(&surface, width, height, &cells);
+            (cells, Vec::new(), cost)
+        });
+        let limit = straight * options.detour_factor;
+        let overland_cost = route.as_ref().map_or(f32::INFINITY, |route| route.2);
+        if overland_cost > limit {
+            let from = snap(&surface, start.x, start.y);
+            let to = snap(&surface, end.x, end.y);
+            if let Some(crossed) = from
+                .zip(to)
+                .and_then(|(from, to)| crossing_route(map, &surface, from, to, options))
+                .filter(|crossed| crossed.2 < overland_cost)
+            {
+                route = Some(crossed);
+            }
+        }
+        let best_cost = route.as_ref().map_or(f32::INFINITY, |route| route.2);
+        if highway && best_cost > limit {
+            let tunneled = trace(&tunneling).and_then(|cells| {
+                let tunnels = tunnel_runs(map, &cells, options)?;
+                let cost = path_cost(&tunneling, width, height, &cells);
+                (cost < best_cost).then_some((cells, tunnels, cost))
+            });
+            if tunneled.is_some() {
+                route = tunneled;
+            }
+        }
+        if let Some((cells, segments, cost)) = route {
+            routes.push(RoadRoute {
+                road: (a, b),
+                highway,
+                cells,
+                segments,
+                cost,
+            });
+        }
+    }
+    routes
+}
+
+/// Land route from `from` to `to` over one water crossing: the narrowest
+/// bridge or ferry between the shores reachable from each end, near the
+/// straight line between them. `None` when both ends share a landmass or no
+/// crossing is short enough.
+fn crossing_route(
+    map: &MapResult,
+    surface: &[f32],
+    from: usize,
+    to: usize,
+    options: &RouteOptions,
+) -> Option<(Vec<usize>, Vec<Segment>, f32)> {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let near = reachable(surface, width, height, from);
+    if near[to] {
+        return None;
+    }
+    let far = reachable(surface, width, height, to);
+    let (ax, ay) = map.cell_to_world(from);
+    let (bx, by) = map.cell_to_world(to);
+    let corridor = |index: usize| {
+        let (x, y) = map.cell_to_world(index);
+        segment_distance((x, y), (ax, ay), (bx, by)) <= options.corridor
+    };
+    let longest = options.bridge_length.max(options.ferry_length);
+    let Crossing {
+        from: shore_a,
+        to: shore_b,
+        length,
+    } = narrowest_crossing(map, &near, &far, corridor, longest)?;
+    let (kind, crossing_cost) = if length <= options.bridge_length {
+        (SegmentKind::Bridge, length * options.bridge_cost)
+    } else {
+        (
+            SegmentKind::Ferry,
+            length * options.ferry_cost + options.ferry_wait,
+        )
+    };
+
+    let first = astar(surface, width, height, from, shore_a, MAX_NODES)?;
+    let last = astar(surface, width, height, shore_b, to, MAX_NODES)?;
+    let water = raster_line(
+        (shore_a % width, shore_a / width),
+        (shore_b % width, shore_b / width),
+    );
+    let start = first.len() - 1;
+    let mut cells = first;
+    cells.extend(
+        water[1..water.len() - 1]
+            .iter()
+            .map(|&(x, y)| y * width + x),
+    );
+    let end = cells.len();
+    let cost = path_cost(surface, width, height, &cells[..=start])
+        + crossing_cost
+        + path_cost(surface, width, height, &last);
+    cells.extend(last);
+    Some((cells, vec![Segment { start, end, kind }], cost))
+}
+
+/// Runs of tunnelable cells along `cells`, widened by one cell to their
+/// portals, or `None` when a run is too long or a portal is underwater.
+fn tunnel_runs(map: &MapResult, cells: &[usize], options: &RouteOptions) -> Option<Vec<Segment>> {
+    let underground =
+        |index: usize| map.heightmap[index] >= options.elevation && !map.is_water_body(index);
+    let mut runs = Vec::new();
+    let mut position = 0;
+    while position < cells.len() {
+        if !underground(cells[position]) {
+            position += 1;
+            continue;
+        }
+        let first = position;
+        while position < cells.len() && underground(cells[position]) {
+            position += 1;
+        }
+        let (start, end) = (first.saturating_sub(1), position.min(cells.len() - 1));
+        if map.is_water_body(cells[start]) || map.is_water_body(cells[end]) {
+            return None;
+        }
+        let points: Vec<_> = cells[start..=end]
+            .iter()
+            .map(|&index| map.cell_to_world(index))
+            .collect();
+        let length = polyline_length(&points, false);
+        if length > options.max_length {
+            return None;
+        }
+        runs.push(Segment {
+            start,
+            end,
+            kind: SegmentKind::Tunnel,
+        });
+    }
+    Some(runs)
+}
+
+/// Cost of a cell path with `astar`'s step costs.
+fn path_cost(costs: &[f32], width: usize, height: usize, cells: &[usize]) -> f32 {
+    let cell_w = REGION_SIZE / width as f32;
+    let cell_h = REGION_SIZE / height as f32;
+    cells
+        .windows(2)
+        .map(|pair| {
+            let dx = (pair[1] % width) as f32 - (pair[0] % width) as f32;
+            let dy = (pair[1] / width) as f32 - (pair[0] / width) as f32;
+            let step = (dx * cell_w).hypot(dy * cell_h);
+            (costs[pair[0]] + costs[pair[1]]) * 0.5 * step
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{road_routes, RouteOptions, SegmentKind};
+    use crate::biome::Biome;
+    use crate::{generate_map, Settlement, REGION_SIZE};
+
+    /// Flat grassland with one road across the middle, from `west` to
+    /// `east`; `column` picks each column's terrain.
+    fn terrain(
+        west: f32,
+        east: f32,
+        column: impl Fn(usize, usize) -> (f32, Biome),
+    ) -> crate::MapResult {
+        let mut map = generate_map(64, 32, 1, 0.42, 1.0, 40.0, 0, 1.0);
+        for index in 0..map.heightmap.len() {
+            let (height, biome) = column(index % 64, index / 64);
+            map.heightmap[index] = height;
+            map.water[index] = if height < 0.42 { 1.0 } else { 0.0 };
+            map.biome[index] = biome.code();
+        }
+        let settlement = |id, x| Settlement {
+            id,
+            x,
+            y: REGION_SIZE / 2.0,
+            size: 5.0,
+            issue: None,
+            era: 0,
+            name: None,
+        };
+        map.settlements = vec![settlement(0, west), settlement(1, east)];
+        map.road_graph = vec![(0, 1)];
+        map.invalidate_terrain();
+        map.invalidate_settlements();
+        map
+    }
+
+    /// An alpine ridge five cells wide, top to bottom.
+    fn ridge() -> crate::MapResult {
+        terrain(256.0, 1792.0, |x, _| {
+            if (30..35).contains(&x) {
+                (0.95, Biome::Alpine)
+            } else {
+                (0.5, Biome::TemperateGrassland)
+            }
+        })
+    }
+
+    /// A strait top to bottom, `width` cells wide.
+    fn strait(width: usize) -> crate::MapResult {
+        terrain(512.0, 1536.0, move |x, _| {
+            if (32..32 + width).contains(&x) {
+                (0.2, Biome::Ocean)
+            } else {
+                (0.5, Biome::TemperateGrassland)
+            }
+        })
+    }
+
+    #[test]
+    fn highways_tunnel_through_ridges_within_length() {
+        let map = ridge();
+        let options = RouteOptions::default();
+        let routes = road_routes(&map, &options);
+        assert_eq!(routes.len(), 1);
+        let route = &routes[0];
+        assert!(route.highway);
+        assert_eq!(route.segments.len(), 1);
+        let tunnel = route.segments[0];
+        assert_eq!(tunnel.kind, SegmentKind::Tunnel);
+        for &index in &route.cells[tunnel.start + 1..tunnel.end] {
+            assert!(map.heightmap[index] >= options.elevation);
+        }
+        for portal in [route.cells[tunnel.start], route.cells[tunnel.end]] {
+            assert!(map.heightmap[portal] < options.elevation);
+        }
+        assert_eq!(road_routes(&map, &options)[0].cells, route.cells);
+
+        let short = RouteOptions {
+            max_length: 64.0,
+            ..RouteOptions::default()
+        };
+        assert!(road_routes(&map, &short).is_empty());
+        let minor = RouteOptions {
+            min_size: 6.0,
+            ..RouteOptions::default()
+        };
+        assert!(road_routes(&map, &minor).is_empty());
+    }
+
+    #[test]
+    fn water_gaps_take_bridges_then_ferries_up_to_their_limits() {
+        let options = RouteOptions::default();
+        let crossing = |width| {
+            let map = strait(width);
+            let routes = road_routes(&map, &options);
+            let route = routes.first()?;
+            let segment = route.segments[0];
+            for &index in &route.cells[segment.start + 1..segment.end] {
+                assert!(map.is_water_body(index));
+            }
+            for shore in [route.cells[segment.start], route.cells[segment.end]] {
+                assert!(!map.is_water_body(shore));
+            }
+            Some(segment.kind)
+        };
+        // Cells are 32 world units, so shores `width + 1` cells apart.
+        assert_eq!(crossing(1), Some(SegmentKind::Bridge));
+        assert_eq!(crossing(4), Some(SegmentKind::Ferry));
+        assert_eq!(crossing(16), None);
+    }
+}
@@ -0,0 +1,173 @@
+#[cfg(feature = "wasm")]
+use js_sys::{Array, Uint8Array};
+use noise::{NoiseFn, OpenSimplex};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::MapResult;
+
+/// Seed offsets of the two channels, apart from every other noise field.
+const CHANNEL_SEEDS: [u32; 2] = [1009, 1013];
+
+/// Two independent noise channels sampled in world space. Nothing here
+/// depends on map resolution, so every grid over the same world positions
+/// sees the same values.
+pub(crate) struct Variation {
+    channels: [OpenSimplex; 2],
+    frequency: f64,
+}
+
+impl Variation {
+    /// `frequency` is in cycles per world unit.
+    pub(crate) fn new(seed: u32, frequency: f32) -> Self {
+        Self {
+            channels: CHANNEL_SEEDS.map(|offset| OpenSimplex::new(seed.wrapping_add(offset))),
+            frequency: frequency.max(0.0) as f64,
+        }
+    }
+
+    /// Both channels at a world position, with 128 as neutral.
+    pub(crate) fn at(&self, world_x: f32, world_y: f32) -> [u8; 2] {
+        let point = [
+            world_x as f64 * self.frequency,
+            world_y as f64 * self.frequency,
+        ];
+        self.channels.each_ref().map(|noise| {
+            ((noise.get(point) * 0.5 + 0.5) * 255.0)
+                .round()
+                .clamp(0.0, 255.0) as u8
+        })
+    }
+
+    /// Both channels over a `width * height` grid whose cell `(x, y)` sits
+    /// at `origin + (x, y) * cell_size` in world units.
+    pub(crate) fn grid(
+        &self,
+        origin: (f32, f32),
+        cell_size: f32,
+        width: usize,
+        height: usize,
+    ) -> [Vec<u8>; 2] {
+        let mut channels = [
+            Vec::with_capacity(width * height),
+            Vec::with_capacity(width * height),
+        ];
+        for y in 0..height {
+            for x in 0..width {
+                let values = self.at(
+                    origin.0 + x as f32 * cell_size,
+                    origin.1 + y as f32 * cell_size,
+                );
+                for (channel, value) in channels.iter_mut().zip(values) {
+                    channel.push(value);
+                }
+            }
+        }
+        channels
+    }
+}
+
+impl MapResult {
+    /// Both channels at every cell's world position.
+    pub(crate) fn micro_variation_layers(&self, frequency: f32) -> [Vec<u8>; 2] {
+        let variation = Variation::new(self.settings.seed, frequency);
+        let mut channels = [
+            Vec::with_capacity(self.heightmap.len()),
+            Vec::with_capacity(self.heightmap.len()),
+        ];
+        for index in 0..self.heightmap.len() {
+            let (x, y) = self.cell_to_world(index);
+            for (channel, value) in channels.iter_mut().zip(variation.at(x, y)) {
+                channel.push(value);
+            }
+        }
+        channels
+    }
+}
+
+#[cfg(feature = "wasm")]
+fn to_js(channels: [Vec<u8>; 2]) -> Array {
+    let output = Array::new();
+    for channel in channels {
+        output.push(&Uint8Array::from(channel.as_slice()).into());
+    }
+    output
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Two per-cell variation channels for breaking up flat biome fills,
+    /// e.g. to nudge hue and brightness or pick texture variants. Each is a
+    /// `Uint8Array` with 128 as neutral, from noise seeded by the map seed
+    /// at `frequency` cycles per world unit (0.125 repeats every 8 world
+    /// units, two cells on a 512-cell map). The noise is sampled at world coordinates,
+    /// so any resolution or tile showing the same world point shows the
+    /// same values.
+    pub fn micro_variation(&self, frequency: f32) -> Array {
+        to_js(self.micro_variation_layers(frequency))
+    }
+
+    /// `micro_variation` over a `width * height` window of any resolution:
+    /// cell `(x, y)` samples world position `origin + (x, y) * cell_size`.
+    /// Windows and maps agree wherever their sample positions coincide, so
+    /// tiles and detail levels line up without shimmer.
+    pub fn micro_variation_window(
+        &self,
+        origin_x: f32,
+        origin_y: f32,
+        cell_size: f32,
+        width: u32,
+        height: u32,
+        frequency: f32,
+    ) -> Array {
+        let variation = Variation::new(self.settings.seed, frequency);
+        to_js(variation.grid(
+            (origin_x, origin_y),
+            cell_size,
+            width as usize,
+            height as usize,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Variation;
+    use crate::{generate_map, REGION_SIZE};
+
+    #[test]
+    fn variation_matches_across_resolutions_and_tiles() {
+        let coarse = generate_map(64, 64, 5, 0.42, 1.0, 40.0, 2, 1.0);
+        let fine = generate_map(128, 128, 5, 0.42, 1.0, 40.0, 2, 1.0);
+        let [a, b] = coarse.micro_variation_layers(0.125);
+        let [fine_a, fine_b] = fine.micro_variation_layers(0.125);
+        // Every coarse cell corner is a fine cell corner too.
+        for y in 0..64 {
+            for x in 0..64 {
+                let (c, f) = (y * 64 + x, y * 2 * 128 + x * 2);
+                assert_eq!((a[c], b[c]), (fine_a[f], fine_b[f]));
+            }
+        }
+        assert_ne!(a, b);
+        let mean = a.iter().map(|&value| value as f32).sum::<f32>() / a.len() as f32;
+        assert!((mean - 128.0).abs() < 16.0, "{mean}");
+        assert!(a.iter().any(|&value| value != a[0]));
+
+        // A 16-cell tile at four times the fine resolution, starting at fine
+        // cell (32, 48).
+        let variation = Variation::new(5, 0.125);
+        let cell = REGION_SIZE / 128.0;
+        let [tile, _] = variation.grid((32.0 * cell, 48.0 * cell), cell / 4.0, 16, 16);
+        for y in (0..16).step_by(4) {
+            for x in (0..16).step_by(4) {
+                assert_eq!(tile[y * 16 + x], fine_a[(48 + y / 4) * 128 + 32 + x / 4]);
+            }
+        }
+        // A different seed changes the pattern.
+        assert_ne!(
+            Variation::new(6, 0.125).grid((0.0, 0.0), cell, 64, 1)[0],
+            fine_a[..64]
+        );
+    }
+}
@@ -0,0 +1,332 @@
+#[cfg(feature = "wasm")]
+use js_sys::Uint8Array;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::pyramid::Reduction;
+use crate::render::raster_line;
+use crate::{MapResult, REGION_SIZE};
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Whether a target `target_height` above the surface at `(x1, y1)` is
+    /// visible from an eye `observer_height` above `(x0, y0)`. Coordinates are
+    /// world units; heights are normalized elevation units. Water cells use the
+    /// sea-level surface. With `enable_pyramid`, long rays clearing the coarse
+    /// maxima skip the cell-by-cell walk.
+    pub fn line_of_sight(
+        &self,
+        x0: f32,
+        y0: f32,
+        x1: f32,
+        y1: f32,
+        observer_height: f32,
+        target_height: f32,
+    ) -> bool {
+        line_of_sight(self, (x0, y0), (x1, y1), observer_height, target_height)
+    }
+
+    /// Mask of cells visible from an eye `observer_height` above `(x, y)`
+    /// within `max_radius` world units: 1 visible, 0 hidden or out of range.
+    pub fn viewshed(&self, x: f32, y: f32, observer_height: f32, max_radius: f32) -> Uint8Array {
+        let (cx, cy) = self.nearest_cell(x, y);
+        Uint8Array::from(viewshed(self, cx, cy, observer_height, max_radius).as_slice())
+    }
+}
+
+impl MapResult {
+    fn surface(&self, index: usize) -> f32 {
+        self.heightmap[index].max(self.sea_level)
+    }
+
+    fn surface_at(&self, world_x: f32, world_y: f32) -> f32 {
+        self.bilinear(&self.heightmap, world_x, world_y)
+            .max(self.sea_level)
+    }
+}
+
+pub(crate) fn line_of_sight(
+    map: &MapResult,
+    from: (f32, f32),
+    to: (f32, f32),
+    observer_height: f32,
+    target_height: f32,
+) -> bool {
+    let eye = map.surface_at(from.0, from.1) + observer_height;
+    let target = map.surface_at(to.0, to.1) + target_height;
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let length = (dx * dx + dy * dy).sqrt();
+    if clears_coarse_maxima(map, from, to, eye.min(target)) {
+        return true;
+    }
+
+    // Step half a cell at a time so no cell along the ray is skipped.
+    let cell = REGION_SIZE / map.width.max(map.height) as f32;
+    let steps = (length / (cell * 0.5)).ceil() as usize;
+    for step in 1..steps {
+        let t = step as f32 / steps as f32;
+        let ray = eye + (target - eye) * t;
+        if map.surface_at(from.0 + dx * t, from.1 + dy * t) > ray {
+            return false;
+        }
+    }
+    true
+}
+
+/// Whether every max-pyramid cell near the ray lies below `floor`, the
+/// ray's lowest point, so no finer sample can block it. Uses the coarsest
+/// level that still spans the ray with several cells; false when no
+/// pyramid is enabled or the ray is too short to gain from one.
+fn clears_coarse_maxima(map: &MapResult, from: (f32, f32), to: (f32, f32), floor: f32) -> bool {
+    let Some(levels) = map.pyramid_levels("heightmap", Reduction::Max) else {
+        return false;
+    };
+    if map.sea_level >= floor {
+        return false;
+    }
+    let cell = |(x, y): (f32, f32)| {
+        let (cx, cy) = map.world_to_cell(x, y);
+        (
+            cx.clamp(0.0, (map.width - 1) as f32) as usize,
+            cy.clamp(0.0, (map.height - 1) as f32) as usize,
+        )
+    };
+    let (a, b) = (cell(from), cell(to));
+    let span = a.0.abs_diff(b.0).max(a.1.abs_diff(b.1));
+    let level = ((span / 8).max(1).ilog2() as usize).min(levels.len());
+    if level == 0 {
+        return false;
+    }
+    let coarse = &levels[level - 1];
+    let shrink = |(x, y): (usize, usize)| (x >> level, y >> level);
+    // Bilinear samples read the next cell too, and the sampled segment may
+    // stray a cell from the raster line, so check two cells around it.
+    raster_line(shrink(a), shrink(b)).into_iter().all(|(x, y)| {
+        let xs = x.saturating_sub(2)..=(x + 2).min(coarse.width - 1);
+        xs.flat_map(|cx| {
+            (y.saturating_sub(2)..=(y + 2).min(coarse.height - 1))
+                .map(move |cy| cy * coarse.width + cx)
+        })
+        .all(|index| coarse.values[index] < floor)
+    })
+}
+
+/// Cells visible from an observer, over the bounding box of its radius.
+pub(crate) struct Viewshed {
+    /// Map cell of the window's top-left corner.
+    pub x0: usize,
+    pub y0: usize,
+    pub width: usize,
+    pub height: usize,
+    /// Row-major over the window: 1 visible, 0 hidden or out of range.
+    pub visible: Vec<u8>,
+}
+
+impl Viewshed {
+    /// Whether map cell `(x, y)` is visible; false outside the window.
+    pub(crate) fn sees(&self, x: usize, y: usize) -> bool {
+        (self.x0..self.x0 + self.width).contains(&x)
+            && (self.y0..self.y0 + self.height).contains(&y)
+            && self.visible[(y - self.y0) * self.width + x - self.x0] == 1
+    }
+}
+
+/// Full-map mask of `local_viewshed`: 1 visible, 0 hidden or out of range.
+pub(crate) fn viewshed(
+    map: &MapResult,
+    cx: usize,
+    cy: usize,
+    observer_height: f32,
+    max_radius: f32,
+) -> Vec<u8> {
+    let width = map.width as usize;
+    let local = local_viewshed(map, cx, cy, observer_height, max_radius);
+    let mut visible = vec![0u8; width * map.height as usize];
+    for (row, cells) in local.visible.chunks(local.width).enumerate() {
+        let start = (local.y0 + row) * width + local.x0;
+        visible[start..start + local.width].copy_from_slice(cells);
+    }
+    visible
+}
+
+/// XDraw viewshed: cells are visited ring by ring outward from the observer,
+/// and each cell's horizon (steepest elevation angle between it and the eye)
+/// is interpolated from the two cells of the previous ring its sight line
+/// crosses. Linear in the number of cells within the radius, and only the
+/// window around the radius is allocated.
+pub(crate) fn local_viewshed(
+    map: &MapResult,
+    cx: usize,
+    cy: usize,
+    observer_height: f32,
+    max_radius: f32,
+) -> Viewshed {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let cell_w = REGION_SIZE / map.width as f32;
+    let cell_h = REGION_SIZE / map.height as f32;
+    let max_ring = (max_radius / cell_w.min(cell_h)).ceil() as i64;
+    let max_ring = max_ring.min(width.max(height) as i64);
+    let (ox, oy) = (cx as i64, cy as i64);
+    let x0 = (ox - max_ring).max(0);
+    let y0 = (oy - max_ring).max(0);
+    let window_w = ((ox + max_ring).min(width as i64 - 1) - x0 + 1) as usize;
+    let window_h = ((oy + max_ring).min(height as i64 - 1) - y0 + 1) as usize;
+    // Ring cells inside the grid all fall in the window, so the window test
+    // doubles as the grid test.
+    let local = |x: i64, y: i64| {
+        let (lx, ly) = (x - x0, y - y0);
+        (lx >= 0 && ly >= 0 && lx < window_w as i64 && ly < window_h as i64)
+            .then(|| ly as usize * window_w + lx as usize)
+    };
+
+    let mut visible = vec![0u8; window_w * window_h];
+    let mut horizon = vec![f32::NEG_INFINITY; window_w * window_h];
+    let eye = map.surface(cy * width + cx) + observer_height;
+    visible[local(ox, oy).unwrap()] = 1;
+
+    for ring in 1..=max_ring {
+        for dy in -ring..=ring {
+            for dx in -ring..=ring {
+                if dx.abs() != ring && dy.abs() != ring {
+                    continue;
+                }
+                let (x, y) = (ox + dx, oy + dy);
+                let Some(slot) = local(x, y) else {
+                    continue;
+                };
+                let index = y as usize * width + x as usize;
+                let world_dx = dx as f32 * cell_w;
+                let world_dy = dy as f32 * cell_h;
+                let distance = (world_dx * world_dx + world_dy * world_dy).sqrt();
+                let angle = (map.surface(index) - eye) / distance;
+
+                let previous = if ring == 1 {
+                    f32::NEG_INFINITY
+                } else {
+                    let t = (ring - 1) as f32 / ring as f32;
+                    let (px, py) = (dx as f32 * t, dy as f32 * t);
+                    let sample = |sx: i64, sy: i64| {
+                        local(ox + sx, oy + sy).map_or(f32::NEG_INFINITY, |slot| horizon[slot])
+                    };
+                    // The crossing lies on a ring edge; interpolate along that edge.
+                    if dx.abs() == ring {
+                        let (y_lo, f) = (py.floor(), py - py.floor());
+                        let a = sample(px.round() as i64, y_lo as i64);
+                        let b = sample(px.round() as i64, y_lo as i64 + 1);
+                        lerp_horizon(a, b, f)
+                    } else {
+                        let (x_lo, f) = (px.floor(), px - px.floor());
+                        let a = sample(x_lo as i64, py.round() as i64);
+                        let b = sample(x_lo as i64 + 1, py.round() as i64);
+                        lerp_horizon(a, b, f)
+                    }
+                };
+
+                if angle >= previous && distance <= max_radius {
+                    visible[slot] = 1;
+                }
+                horizon[slot] = angle.max(previous);
+            }
+        }
+    }
+    Viewshed {
+        x0: x0 as usize,
+        y0: y0 as usize,
+        width: window_w,
+        height: window_h,
+        visible,
+    }
+}
+
+fn lerp_horizon(a: f32, b: f32, t: f32) -> f32 {
+    if t <= f32::EPSILON {
+        return a;
+    }
+    if a == f32::NEG_INFINITY || b == f32::NEG_INFINITY {
+        return a.max(b);
+    }
+    a + (b - a) * t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clears_coarse_maxima, line_of_sight, local_viewshed, viewshed};
+    use crate::geometry::distance;
+    use crate::pyramid::PyramidOptions;
+    use crate::{generate_map, MapResult};
+
+    const SIZE: usize = 64;
+    const RIDGE: usize = 32;
+
+    /// Flat ground above the sea with a high wall down column `RIDGE`.
+    fn ridge() -> MapResult {
+        let mut map = generate_map(SIZE as u32, SIZE as u32, 3, 0.42, 1.0, 40.0, 0, 1.0);
+        for index in 0..SIZE * SIZE {
+            map.heightmap[index] = if index % SIZE == RIDGE { 0.9 } else { 0.5 };
+        }
+        map.invalidate_terrain();
+        map
+    }
+
+    #[test]
+    fn viewshed_agrees_with_line_of_sight_across_a_ridge() {
+        let map = ridge();
+        let (ox, oy) = (16, 30);
+        let radius = 1200.0;
+        let visible = viewshed(&map, ox, oy, 0.01, radius);
+        let eye = map.cell_to_world(oy * SIZE + ox);
+        let (mut seen, mut hidden) = (0, 0);
+        for (index, &shown) in visible.iter().enumerate() {
+            let target = map.cell_to_world(index);
+            let x = index % SIZE;
+            // Bilinear sampling smears the wall into its neighbours.
+            if distance(eye, target) > radius || x.abs_diff(RIDGE) == 1 {
+                continue;
+            }
+            let sight = line_of_sight(&map, eye, target, 0.01, 0.0);
+            assert_eq!(shown == 1, sight, "cell ({x}, {})", index / SIZE);
+            assert_eq!(sight, x <= RIDGE);
+            if sight {
+                seen += 1;
+            } else {
+                hidden += 1;
+            }
+        }
+        assert!(seen > 0 && hidden > 0);
+
+        let local = local_viewshed(&map, ox, oy, 0.01, radius);
+        for (index, &shown) in visible.iter().enumerate() {
+            assert_eq!(local.sees(index % SIZE, index / SIZE), shown == 1);
+        }
+    }
+
+    #[test]
+    fn coarse_maxima_do_not_change_line_of_sight() {
+        let mut map = generate_map(SIZE as u32, SIZE as u32, 8, 0.42, 1.0, 40.0, 2, 1.0);
+        let cells: Vec<usize> = (0..SIZE * SIZE).step_by(37).collect();
+        let rays = |map: &MapResult| {
+            let (mut sights, mut cleared) = (Vec::new(), 0);
+            for &from in &cells {
+                for &to in cells.iter().step_by(5) {
+                    for (observer, target) in [(0.01, 0.0), (0.2, 0.05), (0.6, 0.6)] {
+                        let (a, b) = (map.cell_to_world(from), map.cell_to_world(to));
+                        sights.push(line_of_sight(map, a, b, observer, target));
+                        let floor = (map.surface_at(a.0, a.1) + observer)
+                            .min(map.surface_at(b.0, b.1) + target);
+                        cleared += clears_coarse_maxima(map, a, b, floor) as usize;
+                    }
+                }
+            }
+            (sights, cleared)
+        };
+        let (plain, none) = rays(&map);
+        assert_eq!(none, 0);
+        map.pyramid = Some(PyramidOptions::default());
+        let (pyramid, cleared) = rays(&map);
+        assert_eq!(pyramid, plain);
+        // The coarse test must actually settle some of those rays.
+        assert!(cleared > 0);
+    }
+}
@@ -0,0 +1,185 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::geometry::{heading, point_at, polyline_length};
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::json::Json;
+use crate::{MapResult, REGION_SIZE};
+
+/// Where waypoints go along each road.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum WaypointMode {
+    /// Every `spacing` world units.
+    Interval,
+    /// Only where the road crosses another road or a river.
+    Junctions,
+}
+
+/// A marker on a road pointing toward one of its end settlements.
+pub(crate) struct Waypoint {
+    pub x: f32,
+    pub y: f32,
+    pub road_index: usize,
+    pub toward: u32,
+    /// Distance along the road to `toward`, in world units.
+    pub distance: f32,
+    /// Compass heading of the road toward `toward`, in degrees clockwise
+    /// from north (up the map).
+    pub bearing: f32,
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Signposts along every road: two `{ x, y, road_index,
+    /// toward_settlement_id, distance, bearing }` records per position, one
+    /// for each end, with `distance` measured along the road.
+    /// `options.mode` is `"interval"` (default, every `options.spacing` world
+    /// units, default 128) or `"junctions"` (road and river crossings only).
+    pub fn waypoints(&self, options: JsValue) -> Result<JsValue, JsValue> {
+        let mode = match js::get_string(&options, "mode").as_deref() {
+            None | Some("interval") => WaypointMode::Interval,
+            Some("junctions") => WaypointMode::Junctions,
+            Some(other) => {
+                return Err(JsValue::from_str(&format!(
+                    "unknown waypoint mode: {other}"
+                )))
+            }
+        };
+        let spacing = js::get_f32(&options, "spacing", 128.0);
+        if mode == WaypointMode::Interval && (spacing.is_nan() || spacing <= 0.0) {
+            return Err(JsValue::from_str("spacing must be positive"));
+        }
+        let records = waypoints(self, mode, spacing)
+            .iter()
+            .map(|waypoint| Json::from_record(waypoint.record()))
+            .collect::<Vec<_>>();
+        Ok(Json::from(records).to_js())
+    }
+}
+
+impl MapResult {
+    /// World-space polyline of a road edge, from its first settlement to its
+    /// second, or `None` when either settlement is gone.
+    pub(crate) fn road_polyline(&self, (a, b): (u32, u32)) -> Option<Vec<(f32, f32)>> {
+        let start = self.settlement(a)?;
+        let end = self.settlement(b)?;
+        Some(vec![(start.x, start.y), (end.x, end.y)])
+    }
+}
+
+pub(crate) fn waypoints(map: &MapResult, mode: WaypointMode, spacing: f32) -> Vec<Waypoint> {
+    let roads: Vec<Option<Vec<(f32, f32)>>> = map
+        .road_graph
+        .iter()
+        .map(|&road| map.road_polyline(road))
+        .collect();
+    let mut waypoints = Vec::new();
+    for (road_index, polyline) in roads.iter().enumerate() {
+        let Some(polyline) = polyline else {
+            continue;
+        };
+        let (a, b) = map.road_graph[road_index];
+        let length = polyline_length(polyline, false);
+        let stations = match mode {
+            WaypointMode::Interval => (1..)
+                .map(|step| step as f32 * spacing)
+                .take_while(|&station| station < length)
+                .collect(),
+            WaypointMode::Junctions => {
+                let mut stations = river_crossings(map, polyline);
+                for (other_index, other) in roads.iter().enumerate() {
+                    let Some(other) = other else {
+                        continue;
+                    };
+                    let (c, d) = map.road_graph[other_index];
+                    if other_index != road_index && ![c, d].iter().any(|id| *id == a || *id == b) {
+                        stations.extend(crossings(polyline, other));
+                    }
+                }
+                stations.sort_by(f32::total_cmp);
+                stations
+            }
+        };
+        for station in stations {
+            let ((x, y), (dx, dy)) = point_at(polyline, station, false);
+            waypoints.push(Waypoint {
+                x,
+                y,
+                road_index,
+                toward: a,
+                distance: station,
+                bearing: heading(-dx, -dy),
+            });
+            waypoints.push(Waypoint {
+                x,
+                y,
+                road_index,
+                toward: b,
+                distance: length - station,
+                bearing: heading(dx, dy),
+            });
+        }
+    }
+    waypoints
+}
+
+/// Arc lengths along `polyline` where it crosses `other`.
+fn crossings(polyline: &[(f32, f32)], other: &[(f32, f32)]) -> Vec<f32> {
+    let mut stations = Vec::new();
+    let mut travelled = 0.0f32;
+    for p in polyline.windows(2) {
+        let r = (p[1].0 - p[0].0, p[1].1 - p[0].1);
+        let length = r.0.hypot(r.1);
+        for q in other.windows(2) {
+            let s = (q[1].0 - q[0].0, q[1].1 - q[0].1);
+            let denominator = r.0 * s.1 - r.1 * s.0;
+            if denominator.abs() < f32::EPSILON {
+                continue;
+            }
+            let (ox, oy) = (q[0].0 - p[0].0, q[0].1 - p[0].1);
+            let t = (ox * s.1 - oy * s.0) / denominator;
+            let u = (ox * r.1 - oy * r.0) / denominator;
+            if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u) {
+                stations.push(travelled + t * length);
+            }
+        }
+        travelled += length;
+    }
+    stations
+}
+
+/// Arc lengths where `polyline` steps from dry ground onto a river, sampled
+/// at half-cell intervals.
+fn river_crossings(map: &MapResult, polyline: &[(f32, f32)]) -> Vec<f32> {
+    let step = REGION_SIZE / map.width.max(map.height) as f32 * 0.5;
+    let length = polyline_length(polyline, false);
+    let mut stations = Vec::new();
+    let mut on_river = true;
+    let mut station = 0.0f32;
+    while station <= length {
+        let ((x, y), _) = point_at(polyline, station, false);
+        let (cx, cy) = map.nearest_cell(x, y);
+        let river = map.is_river(cy * map.width as usize + cx);
+        if river && !on_river {
+            stations.push(station);
+        }
+        on_river = river;
+        station += step;
+    }
+    stations
+}
+
+impl Waypoint {
+    fn record(&self) -> Vec<(&'static str, Json)> {
+        vec![
+            ("x", self.x.into()),
+            ("y", self.y.into()),
+            ("road_index", self.road_index.into()),
+            ("toward_settlement_id", self.toward.into()),
+            ("distance", self.distance.into()),
+            ("bearing", self.bearing.into()),
+        ]
+    }
+}
@@ -0,0 +1,170 @@
+use std::f32::consts::TAU;
+
+#[cfg(feature = "wasm")]
+use js_sys::{Float32Array, Object};
+use noise::{NoiseFn, OpenSimplex};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::geometry::distance;
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::json::Json;
+use crate::{gradient, MapResult, REGION_SIZE};
+
+pub(crate) const DAYS_PER_YEAR: f32 = 365.0;
+/// Day of the year with the strongest northern summer.
+pub(crate) const MIDSUMMER: f32 = 172.0;
+/// Candidate storm tracks; each is active only while its intensity clears
+/// `STORM_THRESHOLD`.
+const STORM_TRACKS: u32 = 12;
+const STORM_THRESHOLD: f32 = 0.35;
+/// Days for a storm to cross roughly half the map.
+const STORM_DRIFT_DAYS: f64 = 9.0;
+/// Days over which the background cloud pattern changes completely.
+const CLOUD_DAYS: f64 = 3.0;
+
+/// A storm center in world units; `dx`, `dy` is its motion per day.
+pub(crate) struct WeatherSystem {
+    pub track: u32,
+    pub x: f32,
+    pub y: f32,
+    pub radius: f32,
+    pub intensity: f32,
+    pub dx: f32,
+    pub dy: f32,
+}
+
+pub(crate) struct Weather {
+    pub precipitation: Vec<f32>,
+    pub cloud_cover: Vec<f32>,
+    pub systems: Vec<WeatherSystem>,
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Plausible weather for one day: `{ precipitation, cloud_cover, systems }`
+    /// with per-cell 0..1 layers and storm centers `{ track, x, y, radius,
+    /// intensity, dx, dy }`. `seed` numbers the year, so day 364 of one seed
+    /// flows into day 0 of the next and storms move coherently between days.
+    pub fn weather(&self, day_of_year: f32, seed: u32) -> Object {
+        let weather = weather(self, day_of_year, seed);
+        let systems = weather
+            .systems
+            .iter()
+            .map(|system| Json::from_record(system.record()))
+            .collect::<Vec<_>>();
+        let result = Object::new();
+        js::set(
+            &result,
+            "precipitation",
+            &Float32Array::from(weather.precipitation.as_slice()).into(),
+        );
+        js::set(
+            &result,
+            "cloud_cover",
+            &Float32Array::from(weather.cloud_cover.as_slice()).into(),
+        );
+        js::set(&result, "systems", &Json::from(systems).to_js());
+        result
+    }
+}
+
+pub(crate) fn weather(map: &MapResult, day_of_year: f32, seed: u32) -> Weather {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let day = seed as f64 * DAYS_PER_YEAR as f64 + day_of_year.rem_euclid(DAYS_PER_YEAR) as f64;
+    // Summer heat feeds convection; winter dampens it.
+    let season = ((day_of_year - MIDSUMMER) / DAYS_PER_YEAR * TAU).cos();
+    let noise = OpenSimplex::new(map.settings.seed.wrapping_add(389));
+
+    let mut instability = vec![0.0f32; map.heightmap.len()];
+    for y in 0..height {
+        for x in 0..width {
+            let (gx, gy) = gradient(&map.temperature, width, height, x, y);
+            instability[y * width + x] = gx.hypot(gy);
+        }
+    }
+    let peak = instability.iter().copied().fold(f32::EPSILON, f32::max);
+    let suitability: Vec<f32> = (0..instability.len())
+        .map(|index| map.moisture[index] * (0.5 + 0.5 * instability[index] / peak))
+        .collect();
+
+    let position = |track: u32, day: f64| {
+        let lane = track as f64 * 17.3;
+        let t = day / STORM_DRIFT_DAYS;
+        let axis = |offset: f64| {
+            let value = noise.get([lane, offset, t]) as f32;
+            (value * 0.75 + 0.5).clamp(0.0, 1.0) * REGION_SIZE
+        };
+        (axis(0.0), axis(41.7))
+    };
+    let mut systems = Vec::new();
+    for track in 0..STORM_TRACKS {
+        let (x, y) = position(track, day);
+        let (cx, cy) = map.nearest_cell(x, y);
+        let strength = noise.get([track as f64 * 17.3, 83.1, day / STORM_DRIFT_DAYS]) as f32;
+        let intensity = ((strength * 0.9 + 0.5).clamp(0.0, 1.0)
+            * (0.6 + 0.4 * suitability[cy * width + cx])
+            + 0.1 * season)
+            .clamp(0.0, 1.0);
+        if intensity < STORM_THRESHOLD {
+            continue;
+        }
+        let (ahead_x, ahead_y) = position(track, day + 0.5);
+        let (behind_x, behind_y) = position(track, day - 0.5);
+        systems.push(WeatherSystem {
+            track,
+            x,
+            y,
+            radius: REGION_SIZE * (0.04 + 0.08 * intensity),
+            intensity,
+            dx: ahead_x - behind_x,
+            dy: ahead_y - behind_y,
+        });
+    }
+
+    let mut cloud_cover = vec![0.0f32; map.heightmap.len()];
+    let mut precipitation = vec![0.0f32; map.heightmap.len()];
+    for index in 0..map.heightmap.len() {
+        let (x, y) = map.cell_to_world(index);
+        let background = noise.get([
+            x as f64 / REGION_SIZE as f64 * 4.0,
+            y as f64 / REGION_SIZE as f64 * 4.0,
+            day / CLOUD_DAYS,
+        ]) as f32;
+        let storm: f32 = systems
+            .iter()
+            .map(|system| {
+                let distance = distance((x, y), (system.x, system.y)) / system.radius;
+                system.intensity * (-distance * distance).exp()
+            })
+            .sum();
+        let cloud = (0.1 + map.moisture[index] * 0.6 + background * 0.4 + storm).clamp(0.0, 1.0);
+        cloud_cover[index] = cloud;
+        precipitation[index] = (((cloud - 0.5) / 0.5).max(0.0) * (0.5 + 0.5 * suitability[index])
+            + storm * 0.5)
+            .clamp(0.0, 1.0);
+    }
+
+    Weather {
+        precipitation,
+        cloud_cover,
+        systems,
+    }
+}
+
+impl WeatherSystem {
+    fn record(&self) -> Vec<(&'static str, Json)> {
+        vec![
+            ("track", self.track.into()),
+            ("x", self.x.into()),
+            ("y", self.y.into()),
+            ("radius", self.radius.into()),
+            ("intensity", self.intensity.into()),
+            ("dx", self.dx.into()),
+            ("dy", self.dy.into()),
+        ]
+    }
+}
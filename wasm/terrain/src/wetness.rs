@@ -0,0 +1,127 @@
+#[cfg(feature = "wasm")]
+use js_sys::Float32Array;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::biome::Biome;
+use crate::hand::height_above_drainage;
+#[cfg(feature = "wasm")]
+use crate::js;
+use crate::roughness::local_mean;
+use crate::{slope_map, MapResult};
+
+pub(crate) struct WetnessOptions {
+    /// Window radius, in cells, of the neighborhood mean for concavity.
+    pub radius: u32,
+    /// Slope at and above which ground drains instead of pooling.
+    pub max_slope: f32,
+    /// Moisture at and below which ground stays dry.
+    pub min_moisture: f32,
+    /// Depth below the neighborhood mean at which concavity saturates.
+    pub depth: f32,
+    /// Height above drainage at which ground no longer counts as lowland;
+    /// 0 ignores drainage.
+    pub max_hand: f32,
+}
+
+impl Default for WetnessOptions {
+    fn default() -> Self {
+        Self {
+            radius: 2,
+            max_slope: 0.06,
+            min_moisture: 0.3,
+            depth: 0.001,
+            max_hand: 0.08,
+        }
+    }
+}
+
+impl WetnessOptions {
+    #[cfg(feature = "wasm")]
+    fn from_js(options: &JsValue) -> Self {
+        let defaults = Self::default();
+        Self {
+            radius: js::get_u32(options, "radius", defaults.radius),
+            max_slope: js::get_f32(options, "max_slope", defaults.max_slope),
+            min_moisture: js::get_f32(options, "min_moisture", defaults.min_moisture)
+                .clamp(0.0, 1.0),
+            depth: js::get_f32(options, "depth", defaults.depth),
+            max_hand: js::get_f32(options, "max_hand", defaults.max_hand).max(0.0),
+        }
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Where rain would pool on the ground, 0..1 per cell: flat, moist cells
+    /// lying below the mean of their neighborhood and low above the drainage
+    /// they flow to. Zero on lakes, oceans, and rivers, in deserts, and
+    /// wherever the slope reaches `max_slope`. Options: `radius` (cells,
+    /// default 2), `max_slope` (default 0.06), `min_moisture` (default 0.3),
+    /// `depth` (elevation below the neighborhood mean where concavity
+    /// saturates, default 0.001), and `max_hand` (height above drainage
+    /// where ground stops counting as floodplain, see
+    /// `height_above_drainage()`, default 0.08; 0 ignores it). Shares
+    /// the 0..1 scale of `weather().precipitation`, so the product gives
+    /// puddles for a given day.
+    pub fn wetness(&self, options: JsValue) -> Float32Array {
+        let wetness = wetness(self, &WetnessOptions::from_js(&options));
+        Float32Array::from(wetness.as_slice())
+    }
+}
+
+pub(crate) fn wetness(map: &MapResult, options: &WetnessOptions) -> Vec<f32> {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let slopes = slope_map(&map.heightmap, width, height);
+    let mean = local_mean(&map.heightmap, width, height, options.radius as usize);
+    let hand = (options.max_hand > 0.0).then(|| height_above_drainage(map));
+    (0..map.heightmap.len())
+        .map(|index| {
+            if map.is_water_body(index)
+                || map.is_river(index)
+                || map.biome[index] == Biome::Desert.code()
+                || slopes[index] >= options.max_slope
+            {
+                return 0.0;
+            }
+            let flatness = 1.0 - slopes[index] / options.max_slope;
+            let dampness = ((map.moisture[index] - options.min_moisture)
+                / (1.0 - options.min_moisture).max(f32::EPSILON))
+            .clamp(0.0, 1.0);
+            let concavity = ((mean[index] - map.heightmap[index])
+                / options.depth.max(f32::EPSILON))
+            .clamp(0.0, 1.0);
+            let lowland = hand.as_ref().map_or(1.0, |hand| {
+                (1.0 - hand[index] / options.max_hand).clamp(0.0, 1.0)
+            });
+            flatness * dampness * concavity * lowland
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{wetness, WetnessOptions};
+    use crate::biome::Biome;
+    use crate::{generate_map, slope_map};
+
+    #[test]
+    fn puddles_need_flat_damp_hollows() {
+        let map = generate_map(128, 128, 6, 0.42, 1.0, 40.0, 2, 1.2);
+        let options = WetnessOptions::default();
+        let wet = wetness(&map, &options);
+        let slopes = slope_map(&map.heightmap, 128, 128);
+        assert!(wet.iter().any(|&value| value > 0.0));
+        for (index, &value) in wet.iter().enumerate() {
+            assert!((0.0..=1.0).contains(&value));
+            if slopes[index] >= options.max_slope
+                || map.biome[index] == Biome::Desert.code()
+                || map.water[index] > 0.0
+            {
+                assert_eq!(value, 0.0);
+            }
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use std::f32::consts::PI;
+
+#[cfg(feature = "wasm")]
+use js_sys::Float32Array;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::MapResult;
+
+/// Longest side of the grid the flow is solved on; the result is
+/// bilinearly upsampled, which also keeps it smooth.
+const SOLVER_SIZE: usize = 96;
+/// Gauss-Seidel sweeps and over-relaxation for the pressure solve.
+const SOLVER_ITERATIONS: u32 = 300;
+const OVER_RELAXATION: f32 = 1.8;
+/// Flow resistance of low-lying land relative to open sea; coastlines
+/// then funnel wind through straits.
+const LAND_DRAG: f32 = 0.15;
+/// Share of the relief between sea level and the highest peak where terrain
+/// starts, and finishes, blocking the wind.
+const BARRIER_LOW: f32 = 0.3;
+const BARRIER_HIGH: f32 = 0.8;
+/// Permeability left on the highest ground, so ridges slow the wind rather
+/// than cutting it off.
+const MIN_PERMEABILITY: f32 = 0.15;
+/// Fraction of the band wind blowing along the meridian.
+const MERIDIONAL_SHARE: f32 = 0.4;
+/// Cap on the speed-up through gaps, in units of the band wind.
+pub(crate) const MAX_WIND_SPEED: f32 = 2.0;
+
+/// Per-cell wind velocity. Speeds are relative to the strongest undisturbed
+/// band wind, 1.0, and funnelled flow is capped at `MAX_WIND_SPEED`. `v`
+/// grows downward, matching cell rows.
+pub(crate) struct WindField {
+    pub u: Vec<f32>,
+    pub v: Vec<f32>,
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl MapResult {
+    /// Prevailing wind as interleaved `u, v` per cell, with `v` positive
+    /// toward higher rows. A speed of 1.0 is the strongest band wind over
+    /// open water; flow squeezed through straits and passes reaches at most
+    /// 2.0, and flow over high ground drops well below 1.
+    pub fn wind(&self) -> Float32Array {
+        let field = self.wind_field();
+        let interleaved: Vec<f32> = field
+            .u
+            .iter()
+            .zip(&field.v)
+            .flat_map(|(&u, &v)| [u, v])
+            .collect();
+        Float32Array::from(interleaved.as_slice())
+    }
+
+    /// Bilinearly interpolated wind `[u, v]` at world coordinates, on the
+    /// same scale as `wind()`.
+    pub fn wind_at(&self, world_x: f32, world_y: f32) -> Float32Array {
+        let (u, v) = self.wind_at_world(world_x, world_y);
+        Float32Array::from([u, v].as_slice())
+    }
+}
+
+impl MapResult {
+    /// Lazily solved wind; dropped by `invalidate_terrain`.
+    pub(crate) fn wind_field(&self) -> &WindField {
+        self.cache.wind.get_or_init(|| wind_field(self))
+    }
+
+    pub(crate) fn wind_at_world(&self, world_x: f32, world_y: f32) -> (f32, f32) {
+        let field = self.wind_field();
+        (
+            self.bilinear(&field.u, world_x, world_y),
+            self.bilinear(&field.v, world_x, world_y),
+        )
+    }
+}
+
+/// Band wind at a row, with the equator at `y_fraction == 0.5` and north at
+/// the top: trade winds blow from the east toward the equator, westerlies
+/// from the west toward the poles, and polar easterlies toward the equator
+/// again. Each band peaks mid-band and calms at its edges.
+pub(crate) fn band_wind(y_fraction: f32) -> (f32, f32) {
+    let latitude = ((y_fraction - 0.5).abs() * PI).min(PI * 0.5);
+    let band = (6.0 * latitude).sin();
+    let poleward = -band * MERIDIONAL_SHARE;
+    let toward_pole = if y_fraction < 0.5 { -1.0 } else { 1.0 };
+    (-band, poleward * toward_pole)
+}
+
+/// Band winds steered around high ground by a potential-flow correction on
+/// a coarse grid: the velocity `k (w0 + ∇φ)` is made divergence-free, where
+/// `k` is the terrain's permeability, so air flows around barriers and
+/// speeds up where they pinch it. Border cells hold `φ = 0`, letting air
+/// enter and leave freely.
+pub(crate) fn wind_field(map: &MapResult) -> WindField {
+    let width = map.width as usize;
+    let height = map.height as usize;
+    let step = width.max(height).div_ceil(SOLVER_SIZE).max(1);
+    let cw = width.div_ceil(step);
+    let ch = height.div_ceil(step);
+
+    let peak = map.heightmap.iter().copied().fold(map.sea_level, f32::max);
+    let relief = (peak - map.sea_level).max(f32::EPSILON);
+    let mut drag = vec![0.0f32; cw * ch];
+    let mut counts = vec![0u32; cw * ch];
+    for (index, &elevation) in map.heightmap.iter().enumerate() {
+        let coarse = (index / width / step) * cw + (index % width) / step;
+        counts[coarse] += 1;
+        if map.is_water_body(index) {
+            continue;
+        }
+        let fraction = (elevation - map.sea_level) / relief;
+        let t = ((fraction - BARRIER_LOW) / (BARRIER_HIGH - BARRIER_LOW)).clamp(0.0, 1.0);
+        drag[coarse] += LAND_DRAG + (1.0 - LAND_DRAG) * t * t * (3.0 - 2.0 * t);
+    }
+    let permeability: Vec<f32> = drag
+        .iter()
+        .zip(&counts)
+        .map(|(&drag, &count)| (1.0 - drag / count.max(1) as f32).max(MIN_PERMEABILITY))
+        .collect();
+    let base: Vec<(f32, f32)> = (0..ch)
+        .map(|y| band_wind(((y * step) as f32 + step as f32 * 0.5) / height as f32))
+        .collect();
+
+    let potential = solve_potential(&permeability, &base, cw, ch);
+    let mut coarse_u = vec![0.0f32; cw * ch];
+    let mut coarse_v = vec![0.0f32; cw * ch];
+    for (y, &(base_u, base_v)) in base.iter().enumerate() {
+        for x in 0..cw {
+            let index = y * cw + x;
+            let slope = |a: usize, b: usize, span: f32| (potential[b] - potential[a]) / span;
+            let (x0, x1) = (x.saturating_sub(1), (x + 1).min(cw - 1));
+            let (y0, y1) = (y.saturating_sub(1), (y + 1).min(ch - 1));
+            let gx = slope(y * cw + x0, y * cw + x1, (x1 - x0).max(1) as f32);
+            let gy = slope(y0 * cw + x, y1 * cw + x, (y1 - y0).max(1) as f32);
+            let k = permeability[index];
+            let (mut u, mut v) = (k * (base_u + gx), k * (base_v + gy));
+            let speed = u.hypot(v);
+            if speed > MAX_WIND_SPEED {
+                u *= MAX_WIND_SPEED / speed;
+                v *= MAX_WIND_SPEED / speed;
+            }
+            coarse_u[index] = u;
+            coarse_v[index] = v;
+        }
+    }
+
+    WindField {
+        u: upsample(&coarse_u, cw, ch, step, width, height),
+        v: upsample(&coarse_v, cw, ch, step, width, height),
+    }
+}
+
+/// Red-black SOR for `∇·(k (w0 + ∇φ)) = 0` with face permeabilities
+/// averaged from both cells. The fixed sweep order and count make the
+/// result deterministic.
+fn solve_potential(permeability: &[f32], base: &[(f32, f32)], cw: usize, ch: usize) -> Vec<f32> {
+    let mut potential = vec![0.0f32; cw * ch];
+    if cw < 3 || ch < 3 {
+        return potential;
+    }
+    let face = |a: usize, b: usize| (permeability[a] + permeability[b]) * 0.5;
+    for _ in 0..SOLVER_ITERATIONS {
+        for parity in 0..2 {
+            for y in 1..ch - 1 {
+                for x in 1..cw - 1 {
+                    if (x + y) % 2 != parity {
+                        continue;
+                    }
+                    let index = y * cw + x;
+                    let (east, west) = (index + 1, index - 1);
+                    let (south, north) = (index + cw, index - cw);
+                    let (ke, kw) = (face(index, east), face(index, west));
+                    let (ks, kn) = (face(index, south), face(index, north));
+                    // The band wind varies only with the row, so east-west
+                    // faces share a value and north-south faces average rows.
+                    let u = base[y].0;
+                    let v_south = (base[y].1 + base[y + 1].1) * 0.5;
+                    let v_north = (base[y].1 + base[y - 1].1) * 0.5;
+                    let source = (ke - kw) * u + ks * v_south - kn * v_north;
+                    let neighbors = ke * potential[east]
+                        + kw * potential[west]
+                        + ks * potential[south]
+                        + kn * potential[north];
+                    let target = (neighbors + source) / (ke + kw + ks + kn);
+                    potential[index] += OVER_RELAXATION * (target - potential[index]);
+                }
+            }
+        }
+    }
+    potential
+}
+
+/// Bilinear upsampling from coarse cell centers to the full grid.
+fn upsample(
+    coarse: &[f32],
+    cw: usize,
+    ch: usize,
+    step: usize,
+    width: usize,
+    height: usize,
+) -> Vec<f32> {
+    let mut values = vec![0.0f32; width * height];
+    for y in 0..height {
+        let cy = ((y as f32 + 0.5) / step as f32 - 0.5).clamp(0.0, (ch - 1) as f32);
+        let y0 = cy.floor() as usize;
+        let y1 = (y0 + 1).min(ch - 1);
+        let ty = cy - y0 as f32;
+        for x in 0..width {
+            let cx = ((x as f32 + 0.5) / step as f32 - 0.5).clamp(0.0, (cw - 1) as f32);
+            let x0 = cx.floor() as usize;
+            let x1 = (x0 + 1).min(cw - 1);
+            let tx = cx - x0 as f32;
+            let top = coarse[y0 * cw + x0] * (1.0 - tx) + coarse[y0 * cw + x1] * tx;
+            let bottom = coarse[y1 * cw + x0] * (1.0 - tx) + coarse[y1 * cw + x1] * tx;
+            values[y * width + x] = top * (1.0 - ty) + bottom * ty;
+        }
+    }
+    values
+}